@@ -229,6 +229,8 @@ Restart=always
 # And a number of security settings to lock down the program somewhat.
 NoNewPrivileges=true
 ProtectSystem=strict
+# HTTPS relays requests over a Unix socket in a temporary directory.
+PrivateTmp=true
 ProtectClock=true
 ProtectKernelTunables=true
 ProtectKernelModules=true
@@ -50,6 +50,24 @@ fn ${t.name}() {
     fs.writeFileSync(`${root}/src/cli/args_tests/gen/${name}.rs`, source)
 }
 
+const parentArgs = tls => `Ok(Args::Parent(ParentArgs {
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: ${tls},
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
+        }))`
+
 const portParams = toParams(["-p", "--port"])
 const keyDirParams = toParams(["-k", "--key-dir"])
 const certificateParams = toParams(["-C", "--certificate"])
@@ -230,20 +248,12 @@ generate("port_and_key_dir", [
     ...joinPortKeyDir.map(([pn, pv, kn, kv]) => ({
         name: `${pn}_port_then_${kn}_normal_key_dir_returns_success`,
         test: `"${pv}123", "${kv}some/dir"`,
-        expect: `Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
-        }))`,
+        expect: parentArgs("None"),
     })),
     ...joinPortKeyDir.map(([pn, pv, kn, kv]) => ({
         name: `${kn}_normal_key_dir_then_${pn}_port_returns_success`,
         test: `"${kv}some/dir", "${pv}123"`,
-        expect: `Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
-        }))`,
+        expect: parentArgs("None"),
     })),
 ])
 
@@ -285,26 +295,24 @@ generate("certificate_and_private_key", [
     ...joinCertificatePrivateKey.map(([cn, cv, pn, pv]) => ({
         name: `${cn}_normal_certificate_then_${pn}_normal_private_key_returns_success`,
         test: `"-p", "123", "-k", "some/dir", "${cv}some/cert.pem", "${pv}some/key.pem"`,
-        expect: `Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
+        expect: parentArgs(`Some(TLSOptions {
                 certificate: std::path::PathBuf::from("some/cert.pem"),
                 private_key: std::path::PathBuf::from("some/key.pem"),
-            }),
-        }))`,
+                client_auth: None,
+                ocsp_response: None,
+                policy: crate::state::TlsPolicy::default(),
+            })`),
     })),
     ...joinCertificatePrivateKey.map(([cn, cv, pn, pv]) => ({
         name: `${pn}_normal_private_key_then_${cn}_normal_certificate_returns_success`,
         test: `"-p", "123", "-k", "some/dir", "${pv}some/key.pem", "${cv}some/cert.pem"`,
-        expect: `Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
+        expect: parentArgs(`Some(TLSOptions {
                 certificate: std::path::PathBuf::from("some/cert.pem"),
                 private_key: std::path::PathBuf::from("some/key.pem"),
-            }),
-        }))`,
+                client_auth: None,
+                ocsp_response: None,
+                policy: crate::state::TlsPolicy::default(),
+            })`),
     })),
 ])
 
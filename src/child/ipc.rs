@@ -1,16 +1,76 @@
 use crate::prelude::*;
 
+use super::request::response_ok_metrics;
+use super::request::HealthCheck;
 use super::request::RequestShared;
 use super::request::ResponseContext;
 use super::request::ResponseHead;
 use super::request::ServerState;
-use super::request::RESPONSE_OK_METRICS;
+use super::request::RESPONSE_HEALTHY;
+use super::request::RESPONSE_NOT_READY;
 use super::request::RESPONSE_SERVER_ERROR;
 use super::request::RESPONSE_UNAVAILABLE;
 use super::PENDING_REQUEST_CAPACITY;
 use crate::ffi::ImmutableWrite;
 use crate::ffi::Pollable;
 use crate::state::ipc::parent::ResponseItem;
+use crate::state::ipc::parent::TlsCertificatePair;
+use crate::state::ipc::HealthStatus;
+use crate::state::ipc::MetricsFormat;
+
+// What each pending request is waiting on from the parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PendingRequest {
+    Metrics(MetricsFormat),
+    Health(HealthCheck),
+    // Each gets its own response, tagged with `id`, as the parent renders it for just that scope.
+    ScopedMetrics {
+        id: u32,
+        format: MetricsFormat,
+        scope: Box<[u8]>,
+    },
+}
+
+struct PendingEntry<C> {
+    request: PendingRequest,
+    // When the request itself was received, and when it started waiting on the parent.
+    received: Instant,
+    queued: Instant,
+    res: C,
+}
+
+impl PendingRequest {
+    // Whether both are answered by the same response from the parent.
+    fn shares_response(&self, other: &PendingRequest) -> bool {
+        match (self, other) {
+            (PendingRequest::Metrics(a), PendingRequest::Metrics(b)) => a == b,
+            (PendingRequest::Health(_), PendingRequest::Health(_)) => true,
+            (
+                PendingRequest::ScopedMetrics {
+                    format: a,
+                    scope: a_scope,
+                    ..
+                },
+                PendingRequest::ScopedMetrics {
+                    format: b,
+                    scope: b_scope,
+                    ..
+                },
+            ) => a == b && a_scope == b_scope,
+            _ => false,
+        }
+    }
+
+    fn request_bytes(&self) -> Vec<u8> {
+        match self {
+            PendingRequest::Metrics(format) => vec![ipc::child::request_metrics_byte(*format)],
+            PendingRequest::Health(_) => vec![ipc::child::REQUEST_HEALTH],
+            PendingRequest::ScopedMetrics { id, format, scope } => {
+                ipc::child::scoped_metrics_request_bytes(*id, *format, scope)
+            }
+        }
+    }
+}
 
 fn read_request(
     state: &ServerState<impl ResponseContext>,
@@ -37,8 +97,75 @@ fn resume_queued_requests(
         take(&mut *guard)
     };
 
-    for res in pending {
-        res.respond(head, body);
+    for entry in pending {
+        state.respond(entry.res, head, body);
+    }
+}
+
+// Removes the requests waiting on the given IPC response, leaving the rest queued.
+fn take_queued_requests<C: ResponseContext>(
+    state: &ServerState<C>,
+    waits_on: impl Fn(&PendingRequest) -> bool,
+) -> heapless::Vec<PendingEntry<C>, PENDING_REQUEST_CAPACITY> {
+    let mut matching = heapless::Vec::new();
+
+    let mut guard = state
+        .ipc_requester
+        .pending_requests
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    for entry in take(&mut *guard) {
+        // Neither can overflow, as both are at most as long as the original.
+        if waits_on(&entry.request) {
+            matching.push(entry).ok().unwrap();
+        } else {
+            guard.push(entry).ok().unwrap();
+        }
+    }
+
+    matching
+}
+
+// Queues a report to send to the parent along with the next message. It's dropped if the buffer's
+// full, as it's only for metrics.
+pub fn queue_report(state: &ServerState<impl ResponseContext>, bytes: &[u8]) {
+    let mut guard = state.ipc_requester.reports.lock();
+
+    // Always leave room for the tracking byte sent after them.
+    if guard.capacity().wrapping_sub(guard.len()) > bytes.len() {
+        guard.extend_from_slice(bytes).ok().unwrap();
+    }
+}
+
+fn queue_observation(state: &ServerState<impl ResponseContext>, op: u8, duration: Duration) {
+    queue_report(state, &ipc::child::observation_bytes(op, duration));
+}
+
+// Same as `resume_queued_requests`, but only for the metrics requests waiting on the given
+// response. Their timings are also queued up to be sent to the parent.
+fn resume_queued_metrics_requests(
+    state: &ServerState<impl ResponseContext>,
+    waits_on: impl Fn(&PendingRequest) -> bool,
+    head: &'static ResponseHead,
+    body: &[u8],
+) {
+    let arrived = Instant::now();
+
+    for entry in take_queued_requests(state, waits_on) {
+        state.respond(entry.res, head, body);
+        let responded = Instant::now();
+
+        queue_observation(
+            state,
+            ipc::child::OBSERVE_SCRAPE_DURATION,
+            responded.saturating_duration_since(entry.received),
+        );
+        queue_observation(
+            state,
+            ipc::child::OBSERVE_IPC_DURATION,
+            arrived.saturating_duration_since(entry.queued),
+        );
     }
 }
 
@@ -57,18 +184,132 @@ fn handle_key_set_response(
     }
 }
 
+fn handle_tls_certificate_response(
+    state: &ServerState<impl ResponseContext>,
+    response: ResponseItem<TlsCertificatePair>,
+) {
+    match response {
+        ResponseItem::None => {}
+        ResponseItem::AllocationFailed => {
+            log::error!(
+                "Child TLS certificate response allocation failed. Retaining current certificate."
+            );
+        }
+        ResponseItem::Some(pair) => {
+            match state.tls_certificate.replace(
+                &pair.certificate,
+                &pair.private_key,
+                pair.ocsp_response.as_deref(),
+            ) {
+                Ok(()) => log::info!("TLS certificate updated."),
+                Err(e) => log::error!(
+                    "Received TLS certificate is invalid, retaining current certificate: {}",
+                    normalize_errno(e, None)
+                ),
+            }
+        }
+    }
+}
+
 fn handle_metrics_response(
     state: &ServerState<impl ResponseContext>,
+    format: MetricsFormat,
     response: ResponseItem<Box<[u8]>>,
 ) {
+    let waits_on = |request: &PendingRequest| *request == PendingRequest::Metrics(format);
+
     match response {
         ResponseItem::None => {}
         ResponseItem::AllocationFailed => {
             log::error!("Child metrics response allocation failed.");
-            resume_queued_requests(state, &RESPONSE_SERVER_ERROR, &[]);
+            resume_queued_metrics_requests(state, waits_on, &RESPONSE_SERVER_ERROR, &[]);
         }
         ResponseItem::Some(snapshot) => {
-            resume_queued_requests(state, &RESPONSE_OK_METRICS, &snapshot);
+            resume_queued_metrics_requests(state, waits_on, response_ok_metrics(format), &snapshot);
+        }
+    }
+}
+
+fn handle_scoped_metrics_response(
+    state: &ServerState<impl ResponseContext>,
+    id: u32,
+    response: ResponseItem<Box<[u8]>>,
+) {
+    // Only requests for the same format share an ID, so any of them has the right format.
+    let format = state
+        .ipc_requester
+        .pending_requests
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find_map(|entry| match &entry.request {
+            PendingRequest::ScopedMetrics {
+                id: entry_id,
+                format,
+                ..
+            } if *entry_id == id => Some(*format),
+            _ => None,
+        });
+
+    // Nothing's waiting on it anymore, like if they were already failed out.
+    let Some(format) = format else {
+        return;
+    };
+
+    let waits_on = |request: &PendingRequest| match request {
+        PendingRequest::ScopedMetrics { id: entry_id, .. } => *entry_id == id,
+        _ => false,
+    };
+
+    match response {
+        ResponseItem::None => {}
+        ResponseItem::AllocationFailed => {
+            log::error!("Child metrics response allocation failed.");
+            resume_queued_metrics_requests(state, waits_on, &RESPONSE_SERVER_ERROR, &[]);
+        }
+        ResponseItem::Some(snapshot) => {
+            resume_queued_metrics_requests(state, waits_on, response_ok_metrics(format), &snapshot);
+        }
+    }
+}
+
+fn handle_health_response(state: &ServerState<impl ResponseContext>, status: HealthStatus) {
+    let keys_loaded = state
+        .key_set
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .is_some_and(|key_set| !key_set.is_empty());
+
+    let ready = keys_loaded && status.journal_active && status.child_stable;
+
+    let waiting = take_queued_requests(state, |request| {
+        matches!(request, PendingRequest::Health(_))
+    });
+
+    for PendingEntry { request, res, .. } in waiting {
+        match request {
+            // Getting a response at all means the parent's reachable.
+            PendingRequest::Health(HealthCheck::Live) => {
+                state.respond(res, &RESPONSE_HEALTHY, b"ok\n")
+            }
+            PendingRequest::Health(HealthCheck::Ready) if ready => {
+                state.respond(res, &RESPONSE_HEALTHY, b"ok\n")
+            }
+            PendingRequest::Health(HealthCheck::Ready) => {
+                let mut body = Vec::new();
+                if !status.journal_active {
+                    body.extend_from_slice(b"journal: stalled\n");
+                }
+                if !keys_loaded {
+                    body.extend_from_slice(b"keys: not loaded\n");
+                }
+                if !status.child_stable {
+                    body.extend_from_slice(b"server: restarting\n");
+                }
+                state.respond(res, &RESPONSE_NOT_READY, &body);
+            }
+            PendingRequest::Metrics(_) | PendingRequest::ScopedMetrics { .. } => unreachable!(),
         }
     }
 }
@@ -82,24 +323,49 @@ pub fn child_ipc(
     let mut read_buf = [0_u8; 65536];
 
     while let Some(buf) = try_read(&mut input, terminate_notify, &mut read_buf)? {
-        let response = read_request(state, buf);
+        let mut response = read_request(state, buf);
+
+        for format in MetricsFormat::ALL {
+            let metrics = replace(response.metrics_mut(format), ResponseItem::None);
+            handle_metrics_response(state, format, metrics);
+        }
+
+        for (id, metrics) in take(&mut response.scoped_metrics) {
+            handle_scoped_metrics_response(state, id, metrics);
+        }
 
         handle_key_set_response(state, response.key_set);
-        handle_metrics_response(state, response.metrics);
+        handle_tls_certificate_response(state, response.tls_certificate);
+
+        // Handle this after the key set, in case they both arrived together.
+        if let Some(status) = response.health {
+            handle_health_response(state, status);
+        }
     }
 
     resume_queued_requests(state, &RESPONSE_UNAVAILABLE, &[]);
     Ok(())
 }
 
+// Enough for a scrape duration, IPC duration, and response code for every pending request, plus
+// the tracking byte. It's also kept under `PIPE_BUF`, so it's always written in one go.
+const REPORT_BUFFER_CAPACITY: usize = 3841;
+
 pub struct IPCRequester<C> {
-    pending_requests: Mutex<heapless::Vec<C, PENDING_REQUEST_CAPACITY>>,
+    pending_requests: Mutex<heapless::Vec<PendingEntry<C>, PENDING_REQUEST_CAPACITY>>,
+    // Encoded observations and counts not yet sent to the parent.
+    reports: Uncontended<heapless::Vec<u8, REPORT_BUFFER_CAPACITY>>,
+    // The ID to give the next scoped metrics request. It only needs to be unique among the ones
+    // still pending, so it's fine for it to wrap.
+    next_scoped_id: AtomicU32,
 }
 
 impl<C: ResponseContext> IPCRequester<C> {
     pub const fn new() -> Self {
         Self {
             pending_requests: Mutex::new(heapless::Vec::new()),
+            reports: Uncontended::new(heapless::Vec::new()),
+            next_scoped_id: AtomicU32::new(0),
         }
     }
 
@@ -121,26 +387,109 @@ pub fn send_msg<C: ResponseContext + 'static>(
     try_send_msg(&shared.state.terminate_notify, shared.output.inner(), buf)
 }
 
-pub fn request_metrics<C: ResponseContext + 'static>(
+// Returns `true` if successfully sent. Any queued reports are flushed along with it, so the
+// parent has them by the time it renders the metrics for this request. This also keeps all writes
+// to the parent on the request threads.
+pub fn send_track_request<C: ResponseContext + 'static>(
+    shared: &RequestShared<C, impl ImmutableWrite>,
+) -> bool {
+    let mut buf = take(&mut *shared.state.ipc_requester.reports.lock());
+    // Room for this is always left when queueing reports.
+    buf.push(ipc::child::TRACK_REQUEST).ok().unwrap();
+    send_msg(shared, &buf)
+}
+
+// Returns `true` if there was nothing to send or it was successfully sent.
+pub fn flush_reports<C: ResponseContext + 'static>(
+    shared: &RequestShared<C, impl ImmutableWrite>,
+) -> bool {
+    let buf = take(&mut *shared.state.ipc_requester.reports.lock());
+    buf.is_empty() || send_msg(shared, &buf)
+}
+
+// Returns `false` if the parent couldn't be reached.
+fn request_from_parent<C: ResponseContext + 'static>(
     res: C,
+    mut request: PendingRequest,
+    received: Instant,
     shared: &RequestShared<C, impl ImmutableWrite>,
-) {
+) -> bool {
     let pending_requests = &shared.state.ipc_requester.pending_requests;
     let mut guard = pending_requests.lock().unwrap_or_else(|e| e.into_inner());
-    let is_first = guard.is_empty();
-    let result = guard.push(res);
+    // Requests needing the same response share it, so only ask for the first.
+    let existing = guard
+        .iter()
+        .find(|entry| entry.request.shares_response(&request));
+    let message = match existing {
+        None => Some(request.request_bytes()),
+        Some(entry) => {
+            if let (
+                PendingRequest::ScopedMetrics { id, .. },
+                PendingRequest::ScopedMetrics {
+                    id: existing_id, ..
+                },
+            ) = (&mut request, &entry.request)
+            {
+                *id = *existing_id;
+            }
+            None
+        }
+    };
+    let result = guard.push(PendingEntry {
+        request,
+        received,
+        queued: Instant::now(),
+        res,
+    });
 
     // Don't retain the lock longer than necessary.
     drop(guard);
 
     match result {
         Ok(()) => {
-            if is_first && !send_msg(shared, &[ipc::child::REQUEST_METRICS]) {
+            if message.is_some_and(|message| !send_msg(shared, &message)) {
                 resume_queued_requests(shared.state, &RESPONSE_UNAVAILABLE, &[]);
+                return false;
             }
         }
-        Err(res) => {
-            res.respond(&RESPONSE_UNAVAILABLE, &[]);
+        Err(entry) => {
+            shared.state.respond(entry.res, &RESPONSE_UNAVAILABLE, &[]);
         }
     }
+
+    true
+}
+
+// `scope` is the authorizing key's encoded scope, empty if it's unscoped.
+pub fn request_metrics<C: ResponseContext + 'static>(
+    res: C,
+    format: MetricsFormat,
+    scope: Box<[u8]>,
+    received: Instant,
+    shared: &RequestShared<C, impl ImmutableWrite>,
+) -> bool {
+    let request = if scope.is_empty() {
+        PendingRequest::Metrics(format)
+    } else {
+        PendingRequest::ScopedMetrics {
+            id: shared
+                .state
+                .ipc_requester
+                .next_scoped_id
+                .fetch_add(1, Ordering::Relaxed),
+            format,
+            scope,
+        }
+    };
+
+    request_from_parent(res, request, received, shared)
+}
+
+pub fn request_health<C: ResponseContext + 'static>(
+    res: C,
+    check: HealthCheck,
+    received: Instant,
+    shared: &RequestShared<C, impl ImmutableWrite>,
+) -> bool {
+    request_from_parent(res, PendingRequest::Health(check), received, shared)
 }
@@ -1,166 +1,537 @@
+use crate::prelude::*;
+
 use std::net::Ipv6Addr;
 
-// A simple once-per-second IP rate limiter. Concurrency is controlled externally.
+// A token bucket rate limiter, tracked as the time each bucket next fills back up to its burst
+// (the generic cell rate algorithm). Concurrency is controlled externally.
+
+// Since request rate is unlikely to be high, it just uses a vector. Full buckets are dropped when
+// reaped, as they're no different from ones that were never used.
 
-// Since request rate is unlikely to be high, it just uses a vector.
+// Prefixes failing authorization are tracked separately, and are capped so a client with a lot of
+// prefixes to spare can't grow it without bound. Once full, the least recent offender that isn't
+// locked out is dropped first.
+const MAX_OFFENDERS: usize = 4096;
+
+#[derive(Debug)]
+struct Offender {
+    prefix: IpPrefix,
+    // When the current window of failures started, and how many there have been since.
+    window_start: Duration,
+    failures: u32,
+    // How many times it's been locked out, for the backoff.
+    lockouts: u32,
+    locked_until: Duration,
+    last_failure: Duration,
+}
+
+#[derive(Debug, PartialEq)]
+enum Bucket {
+    Address(IpPrefix),
+    Key(KeyName),
+}
 
 #[derive(Debug)]
 pub struct Limiter {
-    second: u64,
-    throttle_set: Vec<Ipv6Addr>,
+    policy: RateLimitPolicy,
+    // Each bucket's theoretical arrival time, relative to when the server started.
+    buckets: Vec<(Bucket, Duration)>,
+    offenders: Vec<Offender>,
 }
 
 impl Limiter {
     pub const fn new() -> Limiter {
         Limiter {
-            second: 0,
-            throttle_set: Vec::new(),
+            policy: RateLimitPolicy::new(),
+            buckets: Vec::new(),
+            offenders: Vec::new(),
         }
     }
 
-    pub fn reap(&mut self, second: u64) {
-        if self.second < second {
-            self.second = second;
-            self.throttle_set = Vec::new();
-        }
+    pub fn set_policy(&mut self, policy: RateLimitPolicy) {
+        self.policy = policy;
+        self.buckets.clear();
+        self.offenders.clear();
     }
 
-    pub fn check_throttled(&mut self, second: u64, key: Ipv6Addr) -> bool {
-        self.reap(second);
+    pub fn reap(&mut self, now: Duration) {
+        self.buckets.retain(|(_, tat)| *tat > now);
+
+        let forget_after = self.policy.lockout.forget_after();
+        self.offenders.retain(|offender| {
+            offender.locked_until > now || now.saturating_sub(offender.last_failure) < forget_after
+        });
+    }
+
+    /// Whether the request's prefix is still locked out. This is checked before authorization,
+    /// so it doesn't get a chance to compare anything.
+    pub fn is_locked_out(&self, now: Duration, addr: Ipv6Addr) -> bool {
+        let prefix = self.policy.address_prefix(addr);
+        self.offenders
+            .iter()
+            .any(|offender| offender.prefix == prefix && offender.locked_until > now)
+    }
+
+    /// Counts a failed authorization against the request's prefix. If that locks it out, returns
+    /// the prefix and how long it's locked out for.
+    pub fn record_auth_failure(
+        &mut self,
+        now: Duration,
+        addr: Ipv6Addr,
+    ) -> Option<(IpPrefix, Duration)> {
+        let lockout = self.policy.lockout;
+        let prefix = self.policy.address_prefix(addr);
+
+        let index = match self.offenders.iter().position(|o| o.prefix == prefix) {
+            Some(index) => index,
+            None => {
+                if self.offenders.len() >= MAX_OFFENDERS {
+                    let evicted = self
+                        .offenders
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, o)| (o.locked_until > now, o.last_failure))
+                        .map(|(i, _)| i);
+                    if let Some(evicted) = evicted {
+                        self.offenders.swap_remove(evicted);
+                    }
+                }
+                self.offenders.push(Offender {
+                    prefix,
+                    window_start: now,
+                    failures: 0,
+                    lockouts: 0,
+                    locked_until: Duration::ZERO,
+                    last_failure: now,
+                });
+                self.offenders.len().wrapping_sub(1)
+            }
+        };
+
+        let offender = &mut self.offenders[index];
+        let window = Duration::from_secs(lockout.window.get().into());
+
+        if now.saturating_sub(offender.window_start) >= window {
+            offender.window_start = now;
+            offender.failures = 0;
+        }
+
+        offender.failures = offender.failures.saturating_add(1);
+        offender.last_failure = now;
+
+        if offender.failures < lockout.failures.get() {
+            return None;
+        }
 
+        let duration = lockout.lockout_duration(offender.lockouts);
+        offender.lockouts = offender.lockouts.saturating_add(1);
+        offender.locked_until = now.saturating_add(duration);
+        offender.window_start = now;
+        offender.failures = 0;
+        Some((prefix, duration))
+    }
+
+    // Returns the new arrival time if a request would conform to the limit.
+    fn conform(&self, bucket: &Bucket, limit: &RateLimit, now: Duration) -> Option<Duration> {
         // Start from the end, as more recent entries are the most likely to repeat. Also will help
         // hide the fact this isn't constant-time.
-        if self.throttle_set.iter().rev().any(|k| *k == key) {
-            true
+        let tat = self
+            .buckets
+            .iter()
+            .rev()
+            .find(|(b, _)| b == bucket)
+            .map_or(now, |(_, tat)| (*tat).max(now));
+
+        if tat.saturating_sub(now) > limit.tolerance() {
+            None
         } else {
-            self.throttle_set.push(key);
-            false
+            Some(tat.saturating_add(limit.interval()))
+        }
+    }
+
+    fn commit(&mut self, bucket: Bucket, tat: Duration) {
+        match self.buckets.iter_mut().find(|(b, _)| *b == bucket) {
+            Some((_, existing)) => *existing = tat,
+            None => self.buckets.push((bucket, tat)),
+        }
+    }
+
+    /// Takes a request from each bucket the request falls under, or returns the limit it
+    /// exceeded. Nothing's taken from any bucket if it's throttled. Unnamed keys are only limited
+    /// by address.
+    pub fn check_throttled(
+        &mut self,
+        now: Duration,
+        addr: Ipv6Addr,
+        key_name: &[u8],
+    ) -> Option<ThrottleReason> {
+        if self.policy.is_exempt(addr) {
+            return None;
         }
+
+        let address = Bucket::Address(self.policy.address_prefix(addr));
+        let Some(address_tat) = self.conform(&address, &self.policy.address, now) else {
+            return Some(ThrottleReason::Address);
+        };
+
+        let key = match (&self.policy.key, KeyName::from_slice(key_name)) {
+            (Some(limit), Ok(name)) if !name.is_empty() => {
+                let key = Bucket::Key(name);
+                match self.conform(&key, limit, now) {
+                    Some(key_tat) => Some((key, key_tat)),
+                    None => return Some(ThrottleReason::Key),
+                }
+            }
+            _ => None,
+        };
+
+        self.commit(address, address_tat);
+        if let Some((key, key_tat)) = key {
+            self.commit(key, key_tat);
+        }
+
+        None
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // Makes the tests a little easier to follow
-    #![allow(clippy::bool_assert_comparison)]
-
     use super::*;
+    use std::num::NonZeroU32;
 
     fn ip_addr(s: &str) -> Ipv6Addr {
         s.parse().unwrap()
     }
 
+    fn prefix(s: &str) -> IpPrefix {
+        IpPrefix::parse(s.as_bytes()).unwrap()
+    }
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    fn limit(count: u32, seconds: u32, burst: u32) -> RateLimit {
+        RateLimit {
+            count: NonZeroU32::new(count).unwrap(),
+            seconds: NonZeroU32::new(seconds).unwrap(),
+            burst: NonZeroU32::new(burst).unwrap(),
+        }
+    }
+
+    fn limiter_with(policy: RateLimitPolicy) -> Limiter {
+        let mut limiter = Limiter::new();
+        limiter.set_policy(policy);
+        limiter
+    }
+
+    const A: &str = "2001:0:0:1::1111";
+    const B: &str = "2001:0:0:2::2222";
+
     #[test]
-    fn fails_to_throttle_on_one_call_per_second_with_one_key() {
+    fn fails_to_throttle_on_one_call_per_second_with_one_address() {
         let mut limiter = Limiter::new();
 
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), false,);
+        assert_eq!(limiter.check_throttled(secs(1), ip_addr(A), b""), None);
+        assert_eq!(limiter.check_throttled(secs(2), ip_addr(A), b""), None);
+        assert_eq!(limiter.check_throttled(secs(3), ip_addr(A), b""), None);
     }
 
     #[test]
-    fn throttles_on_two_calls_per_second_with_one_key() {
+    fn throttles_on_two_calls_per_second_with_one_address() {
         let mut limiter = Limiter::new();
 
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), true);
+        limiter.check_throttled(secs(1), ip_addr(A), b"");
+        assert_eq!(
+            limiter.check_throttled(secs(1), ip_addr(A), b""),
+            Some(ThrottleReason::Address)
+        );
     }
 
     #[test]
-    fn throttles_on_three_calls_per_second_with_one_key() {
+    fn fails_to_throttle_on_one_call_per_second_with_two_addresses() {
         let mut limiter = Limiter::new();
 
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), true);
+        assert_eq!(limiter.check_throttled(secs(1), ip_addr(A), b""), None);
+        assert_eq!(limiter.check_throttled(secs(1), ip_addr(B), b""), None);
     }
 
     #[test]
-    fn fails_to_throttle_on_one_call_per_second_with_two_keys() {
+    fn throttles_on_two_calls_per_second_with_two_addresses() {
         let mut limiter = Limiter::new();
 
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), false,);
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::2222")), false,);
+        limiter.check_throttled(secs(1), ip_addr(A), b"");
+        limiter.check_throttled(secs(1), ip_addr(B), b"");
+        assert_eq!(
+            limiter.check_throttled(secs(1), ip_addr(A), b""),
+            Some(ThrottleReason::Address)
+        );
+        assert_eq!(
+            limiter.check_throttled(secs(1), ip_addr(B), b""),
+            Some(ThrottleReason::Address)
+        );
     }
 
     #[test]
-    fn throttles_on_two_calls_per_second_with_two_keys() {
+    fn throttles_addresses_in_same_ipv6_prefix_together() {
         let mut limiter = Limiter::new();
 
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        limiter.check_throttled(1, ip_addr("2001::2222"));
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), true);
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::2222")), true);
+        limiter.check_throttled(secs(1), ip_addr("2001:0:0:1::1111"), b"");
+        assert_eq!(
+            limiter.check_throttled(secs(1), ip_addr("2001:0:0:1::2222"), b""),
+            Some(ThrottleReason::Address)
+        );
     }
 
     #[test]
-    fn throttles_on_three_calls_per_second_with_two_keys() {
+    fn throttles_ipv4_addresses_separately_by_default() {
         let mut limiter = Limiter::new();
 
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        limiter.check_throttled(1, ip_addr("2001::2222"));
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        limiter.check_throttled(1, ip_addr("2001::2222"));
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), true);
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::2222")), true);
+        limiter.check_throttled(secs(1), ip_addr("::ffff:10.0.0.1"), b"");
+        assert_eq!(
+            limiter.check_throttled(secs(1), ip_addr("::ffff:10.0.0.2"), b""),
+            None
+        );
     }
 
     #[test]
-    fn after_reap_fails_to_throttle_on_one_call_per_second_with_one_key() {
-        let mut limiter = Limiter::new();
+    fn throttles_by_configured_prefix_lengths() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            ipv4_prefix_len: 24,
+            ipv6_prefix_len: 128,
+            ..RateLimitPolicy::new()
+        });
+
+        limiter.check_throttled(secs(1), ip_addr("::ffff:10.0.0.1"), b"");
+        assert_eq!(
+            limiter.check_throttled(secs(1), ip_addr("::ffff:10.0.0.2"), b""),
+            Some(ThrottleReason::Address)
+        );
+        limiter.check_throttled(secs(1), ip_addr("2001:0:0:1::1111"), b"");
+        assert_eq!(
+            limiter.check_throttled(secs(1), ip_addr("2001:0:0:1::2222"), b""),
+            None
+        );
+    }
 
-        limiter.reap(1);
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), false,);
+    #[test]
+    fn allows_bursts_then_refills_at_rate() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            address: limit(1, 10, 3),
+            ..RateLimitPolicy::new()
+        });
+
+        assert_eq!(limiter.check_throttled(secs(0), ip_addr(A), b""), None);
+        assert_eq!(limiter.check_throttled(secs(0), ip_addr(A), b""), None);
+        assert_eq!(limiter.check_throttled(secs(0), ip_addr(A), b""), None);
+        assert_eq!(
+            limiter.check_throttled(secs(0), ip_addr(A), b""),
+            Some(ThrottleReason::Address)
+        );
+        assert_eq!(
+            limiter.check_throttled(secs(9), ip_addr(A), b""),
+            Some(ThrottleReason::Address)
+        );
+        assert_eq!(limiter.check_throttled(secs(10), ip_addr(A), b""), None);
+        assert_eq!(
+            limiter.check_throttled(secs(10), ip_addr(A), b""),
+            Some(ThrottleReason::Address)
+        );
     }
 
     #[test]
-    fn after_reap_throttles_on_two_calls_per_second_with_one_key() {
-        let mut limiter = Limiter::new();
-        limiter.reap(1);
+    fn throttles_by_key_across_addresses() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            key: Some(limit(1, 10, 1)),
+            ..RateLimitPolicy::new()
+        });
+
+        assert_eq!(
+            limiter.check_throttled(secs(1), ip_addr(A), b"grafana"),
+            None
+        );
+        assert_eq!(
+            limiter.check_throttled(secs(1), ip_addr(B), b"grafana"),
+            Some(ThrottleReason::Key)
+        );
+        assert_eq!(
+            limiter.check_throttled(secs(1), ip_addr(B), b"prometheus"),
+            None
+        );
+        // Unnamed keys aren't limited by key.
+        assert_eq!(limiter.check_throttled(secs(2), ip_addr(A), b""), None);
+        assert_eq!(limiter.check_throttled(secs(3), ip_addr(A), b""), None);
+    }
 
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), true);
+    #[test]
+    fn does_not_take_from_address_when_throttled_by_key() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            key: Some(limit(1, 10, 1)),
+            ..RateLimitPolicy::new()
+        });
+
+        limiter.check_throttled(secs(1), ip_addr(A), b"grafana");
+        assert_eq!(
+            limiter.check_throttled(secs(2), ip_addr(B), b"grafana"),
+            Some(ThrottleReason::Key)
+        );
+        assert_eq!(
+            limiter.check_throttled(secs(2), ip_addr(B), b"prometheus"),
+            None
+        );
     }
 
     #[test]
-    fn after_reap_throttles_on_three_calls_per_second_with_one_key() {
-        let mut limiter = Limiter::new();
-        limiter.reap(1);
+    fn never_throttles_exempt_addresses() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            key: Some(limit(1, 10, 1)),
+            exempt: RateLimitPolicy::parse_exempt(b"2001:0:0:1::/64").unwrap(),
+            ..RateLimitPolicy::new()
+        });
+
+        for _ in 0..3 {
+            assert_eq!(
+                limiter.check_throttled(secs(1), ip_addr(A), b"grafana"),
+                None
+            );
+        }
+        assert_eq!(
+            limiter.check_throttled(secs(1), ip_addr(B), b"grafana"),
+            None
+        );
+    }
 
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), true);
+    fn lockout(failures: u32, window: u32, duration: u32, max_duration: u32) -> AuthLockout {
+        AuthLockout {
+            failures: NonZeroU32::new(failures).unwrap(),
+            window: NonZeroU32::new(window).unwrap(),
+            duration: NonZeroU32::new(duration).unwrap(),
+            max_duration: NonZeroU32::new(max_duration).unwrap(),
+        }
     }
 
     #[test]
-    fn after_reap_fails_to_throttle_on_one_call_per_second_with_two_keys() {
-        let mut limiter = Limiter::new();
-        limiter.reap(1);
+    fn locks_out_after_failures_within_window() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            lockout: lockout(3, 10, 60, 600),
+            ..RateLimitPolicy::new()
+        });
+
+        assert_eq!(limiter.record_auth_failure(secs(0), ip_addr(A)), None);
+        assert_eq!(limiter.record_auth_failure(secs(5), ip_addr(A)), None);
+        assert!(!limiter.is_locked_out(secs(5), ip_addr(A)));
+        assert_eq!(
+            limiter.record_auth_failure(secs(9), ip_addr(A)),
+            Some((prefix("2001:0:0:1::/64"), secs(60)))
+        );
+        assert!(limiter.is_locked_out(secs(9), ip_addr(A)));
+        assert!(limiter.is_locked_out(secs(68), ip_addr("2001:0:0:1::2222")));
+        assert!(!limiter.is_locked_out(secs(9), ip_addr(B)));
+        assert!(!limiter.is_locked_out(secs(69), ip_addr(A)));
+    }
 
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), false,);
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::2222")), false,);
+    #[test]
+    fn does_not_lock_out_failures_spread_past_window() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            lockout: lockout(3, 10, 60, 600),
+            ..RateLimitPolicy::new()
+        });
+
+        for t in [0, 5, 10, 15, 20, 25] {
+            assert_eq!(limiter.record_auth_failure(secs(t), ip_addr(A)), None);
+        }
+        assert!(!limiter.is_locked_out(secs(25), ip_addr(A)));
     }
 
     #[test]
-    fn after_reap_throttles_on_two_calls_per_second_with_two_keys() {
-        let mut limiter = Limiter::new();
-        limiter.reap(1);
+    fn backs_off_exponentially_up_to_max() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            lockout: lockout(1, 10, 60, 200),
+            ..RateLimitPolicy::new()
+        });
+
+        assert_eq!(
+            limiter.record_auth_failure(secs(0), ip_addr(A)),
+            Some((prefix("2001:0:0:1::/64"), secs(60)))
+        );
+        assert_eq!(
+            limiter.record_auth_failure(secs(60), ip_addr(A)),
+            Some((prefix("2001:0:0:1::/64"), secs(120)))
+        );
+        assert_eq!(
+            limiter.record_auth_failure(secs(180), ip_addr(A)),
+            Some((prefix("2001:0:0:1::/64"), secs(200)))
+        );
+        assert!(limiter.is_locked_out(secs(379), ip_addr(A)));
+        assert!(!limiter.is_locked_out(secs(380), ip_addr(A)));
+    }
 
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        limiter.check_throttled(1, ip_addr("2001::2222"));
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), true);
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::2222")), true);
+    #[test]
+    fn forgets_past_lockouts_after_a_while() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            lockout: lockout(1, 10, 60, 200),
+            ..RateLimitPolicy::new()
+        });
+
+        limiter.record_auth_failure(secs(0), ip_addr(A));
+        limiter.reap(secs(199));
+        assert_eq!(limiter.offenders.len(), 1);
+        limiter.reap(secs(200));
+        assert_eq!(limiter.offenders.len(), 0);
+        assert_eq!(
+            limiter.record_auth_failure(secs(200), ip_addr(A)),
+            Some((prefix("2001:0:0:1::/64"), secs(60)))
+        );
     }
 
     #[test]
-    fn after_reap_throttles_on_three_calls_per_second_with_two_keys() {
-        let mut limiter = Limiter::new();
-        limiter.reap(1);
-
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        limiter.check_throttled(1, ip_addr("2001::2222"));
-        limiter.check_throttled(1, ip_addr("2001::1111"));
-        limiter.check_throttled(1, ip_addr("2001::2222"));
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::1111")), true);
-        assert_eq!(limiter.check_throttled(1, ip_addr("2001::2222")), true);
+    fn locks_out_exempt_addresses() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            exempt: RateLimitPolicy::parse_exempt(b"2001:0:0:1::/64").unwrap(),
+            lockout: lockout(1, 10, 60, 600),
+            ..RateLimitPolicy::new()
+        });
+
+        limiter.record_auth_failure(secs(0), ip_addr(A));
+        assert!(limiter.is_locked_out(secs(0), ip_addr(A)));
+    }
+
+    #[test]
+    fn caps_offenders_evicting_ones_not_locked_out_first() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            lockout: lockout(2, 10, 60, 600),
+            ..RateLimitPolicy::new()
+        });
+
+        limiter.record_auth_failure(secs(0), ip_addr(A));
+        limiter.record_auth_failure(secs(0), ip_addr(A));
+        for i in 0..MAX_OFFENDERS {
+            let addr = Ipv6Addr::from(u128::try_from(i).unwrap() << 64 | 0x3000 << 112);
+            limiter.record_auth_failure(secs(1), addr);
+        }
+
+        assert_eq!(limiter.offenders.len(), MAX_OFFENDERS);
+        assert!(limiter.is_locked_out(secs(1), ip_addr(A)));
+    }
+
+    #[test]
+    fn reaps_only_full_buckets() {
+        let mut limiter = limiter_with(RateLimitPolicy {
+            address: limit(1, 10, 2),
+            ..RateLimitPolicy::new()
+        });
+
+        limiter.check_throttled(secs(0), ip_addr(A), b"");
+        limiter.check_throttled(secs(0), ip_addr(A), b"");
+        limiter.check_throttled(secs(15), ip_addr(B), b"");
+        limiter.reap(secs(20));
+        assert_eq!(limiter.buckets.len(), 1);
+        assert_eq!(limiter.check_throttled(secs(20), ip_addr(B), b""), None);
+        assert_eq!(
+            limiter.check_throttled(secs(20), ip_addr(B), b""),
+            Some(ThrottleReason::Address)
+        );
+        limiter.reap(secs(40));
+        assert_eq!(limiter.buckets.len(), 0);
     }
 }
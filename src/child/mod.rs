@@ -5,6 +5,7 @@ mod request;
 mod request_tests;
 mod server;
 mod start;
+mod tls;
 
 pub use start::start_child;
 
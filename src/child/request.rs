@@ -1,9 +1,13 @@
 use crate::prelude::*;
 
+use super::ipc::queue_report;
+use super::ipc::request_health;
 use super::ipc::request_metrics;
 use super::ipc::IPCRequester;
 use super::limiter::Limiter;
+use super::tls::TlsCertificate;
 use crate::ffi::ImmutableWrite;
+use crate::state::ipc::MetricsFormat;
 use base64::engine::general_purpose::STANDARD_NO_PAD as ENGINE;
 use base64::engine::Engine as _;
 use std::net::Ipv6Addr;
@@ -13,12 +17,23 @@ pub enum Route {
     InvalidMethod,
     InvalidPath,
     MetricsGet,
+    HealthzGet,
+    ReadyzGet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthCheck {
+    // The server's up and the parent's responding.
+    Live,
+    // Same as above, but also that metrics are being collected and can be served.
+    Ready,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ResponseHeaderTemplate {
     Empty,
-    Metrics,
+    Metrics(MetricsFormat),
+    Health,
     BadAuthSyntax,
     MethodNotAllowed,
     Disconnect,
@@ -41,6 +56,9 @@ pub struct StaticRequestContext {
     pub received: Instant,
     pub peer_addr: Ipv6Addr,
     pub route: Route,
+    pub metrics_format: MetricsFormat,
+    // The verified client certificate's subject common name, if one was presented.
+    pub client_cn: Option<Box<[u8]>>,
 }
 
 impl RequestContext for StaticRequestContext {
@@ -56,6 +74,12 @@ impl RequestContext for StaticRequestContext {
     fn peer_addr(&self) -> Ipv6Addr {
         self.peer_addr
     }
+    fn metrics_format(&self) -> MetricsFormat {
+        self.metrics_format
+    }
+    fn client_cn(&self) -> Option<&[u8]> {
+        self.client_cn.as_deref()
+    }
 }
 
 pub trait RequestContext {
@@ -63,6 +87,8 @@ pub trait RequestContext {
     fn route(&self) -> Route;
     fn received(&self) -> Instant;
     fn peer_addr(&self) -> Ipv6Addr;
+    fn metrics_format(&self) -> MetricsFormat;
+    fn client_cn(&self) -> Option<&[u8]>;
 }
 
 pub trait ResponseContext {
@@ -71,6 +97,8 @@ pub trait ResponseContext {
 
 pub struct ServerState<C> {
     pub key_set: RwLock<Option<KeySet>>,
+    // Only used when serving HTTPS.
+    pub tls_certificate: TlsCertificate,
     pub ipc_requester: IPCRequester<C>,
     pub limiter: Uncontended<Limiter>,
     pub decoder: Uncontended<ipc::parent::Decoder>,
@@ -82,16 +110,51 @@ impl<C: ResponseContext> ServerState<C> {
         Self {
             ipc_requester: IPCRequester::new(),
             key_set: RwLock::new(None),
+            tls_certificate: TlsCertificate::new(),
             limiter: Uncontended::new(Limiter::new()),
             decoder: Uncontended::new(ipc::parent::Decoder::new()),
             terminate_notify: Notify::new(),
         }
     }
+
+    /// Sends the response, and queues its status code up to be counted by the parent.
+    pub fn respond(&self, res: C, head: &'static ResponseHead, body: &[u8]) {
+        res.respond(head, body);
+        queue_report(self, &ipc::child::response_bytes(head.status));
+    }
 }
 
-pub static RESPONSE_OK_METRICS: ResponseHead = ResponseHead {
+pub static RESPONSE_OK_OPENMETRICS: ResponseHead = ResponseHead {
+    status: 200,
+    header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::OpenMetrics),
+};
+
+pub static RESPONSE_OK_PROMETHEUS_TEXT: ResponseHead = ResponseHead {
     status: 200,
-    header_template: ResponseHeaderTemplate::Metrics,
+    header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::PrometheusText),
+};
+
+pub static RESPONSE_OK_PROTOBUF: ResponseHead = ResponseHead {
+    status: 200,
+    header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::Protobuf),
+};
+
+pub fn response_ok_metrics(format: MetricsFormat) -> &'static ResponseHead {
+    match format {
+        MetricsFormat::OpenMetrics => &RESPONSE_OK_OPENMETRICS,
+        MetricsFormat::PrometheusText => &RESPONSE_OK_PROMETHEUS_TEXT,
+        MetricsFormat::Protobuf => &RESPONSE_OK_PROTOBUF,
+    }
+}
+
+pub static RESPONSE_HEALTHY: ResponseHead = ResponseHead {
+    status: 200,
+    header_template: ResponseHeaderTemplate::Health,
+};
+
+pub static RESPONSE_NOT_READY: ResponseHead = ResponseHead {
+    status: 503,
+    header_template: ResponseHeaderTemplate::Health,
 };
 
 pub static RESPONSE_BAD_AUTH_SYNTAX: ResponseHead = ResponseHead {
@@ -129,82 +192,323 @@ pub static RESPONSE_UNAVAILABLE: ResponseHead = ResponseHead {
     header_template: ResponseHeaderTemplate::Disconnect,
 };
 
-// Very simplistic parsing. The username's hard-coded as it's just easier that way.
-fn handle_metrics_get<C: ResponseContext + 'static>(
-    req: impl RequestContext,
-    res: C,
-    shared: &RequestShared<C, impl ImmutableWrite>,
-) -> Option<C> {
-    let Some(auth_header) = req.authorization() else {
-        res.respond(&RESPONSE_BAD_AUTH_SYNTAX, &[]);
-        return None;
+fn parse_media_range(range: &[u8]) -> Option<(Option<MetricsFormat>, u16)> {
+    let mut params = range.split(|&b| b == b';').map(trim_ascii);
+    let media_type = params.next()?.to_ascii_lowercase();
+
+    let mut quality = 1000;
+    let mut proto = None;
+    let mut encoding = None;
+
+    for param in params {
+        let Some(index) = param.iter().position(|&b| b == b'=') else {
+            continue;
+        };
+
+        let name = trim_ascii(&param[..index]);
+        let value = trim_ascii(&param[index.wrapping_add(1)..]);
+
+        if name.eq_ignore_ascii_case(b"q") {
+            quality = parse_quality(value)?;
+        } else if name.eq_ignore_ascii_case(b"proto") {
+            proto = Some(value);
+        } else if name.eq_ignore_ascii_case(b"encoding") {
+            encoding = Some(value);
+        }
+    }
+
+    let format = match &*media_type {
+        b"application/openmetrics-text" | b"application/*" | b"*/*" => {
+            Some(MetricsFormat::OpenMetrics)
+        }
+        b"text/plain" | b"text/*" => Some(MetricsFormat::PrometheusText),
+        // Only the delimited `MetricFamily` stream is supported, not the text or compact text
+        // protobuf encodings.
+        b"application/vnd.google.protobuf"
+            if proto == Some(b"io.prometheus.client.MetricFamily")
+                && encoding == Some(b"delimited") =>
+        {
+            Some(MetricsFormat::Protobuf)
+        }
+        _ => None,
     };
 
-    let Some(rest) = auth_header.strip_prefix(b"Basic ") else {
-        res.respond(&RESPONSE_BAD_AUTH_SYNTAX, &[]);
+    Some((format, quality))
+}
+
+// Returns the quality value in thousandths, so it can be compared exactly.
+fn parse_quality(value: &[u8]) -> Option<u16> {
+    match value {
+        [b'1'] | [b'1', b'.'] => Some(1000),
+        [b'1', b'.', rest @ ..] if rest.len() <= 3 && rest.iter().all(|&b| b == b'0') => Some(1000),
+        [b'0'] | [b'0', b'.'] => Some(0),
+        [b'0', b'.', rest @ ..] if rest.len() <= 3 && rest.iter().all(u8::is_ascii_digit) => {
+            let mut result = 0_u16;
+            for i in 0..3 {
+                let digit = rest.get(i).map_or(0, |&b| b.wrapping_sub(b'0'));
+                result = result.wrapping_mul(10).wrapping_add(u16::from(digit));
+            }
+            Some(result)
+        }
+        _ => None,
+    }
+}
+
+/// Pick the metrics format to respond with based on the request's `Accept` header, preferring
+/// the highest quality value and then the first listed. Requests without one, or without any
+/// acceptable format, get OpenMetrics, as that's what was always returned before.
+pub fn negotiate_metrics_format(accept: Option<&[u8]>) -> MetricsFormat {
+    let mut best = None::<(MetricsFormat, u16)>;
+
+    for range in accept.unwrap_or_default().split(|&b| b == b',') {
+        if let Some((Some(format), quality)) = parse_media_range(range) {
+            if quality > 0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+                best = Some((format, quality));
+            }
+        }
+    }
+
+    match best {
+        Some((format, _)) => format,
+        None => MetricsFormat::OpenMetrics,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Identity,
+    Gzip,
+}
+
+impl ContentEncoding {
+    pub const fn as_header_value(self) -> &'static [u8] {
+        match self {
+            ContentEncoding::Identity => b"identity",
+            ContentEncoding::Gzip => b"gzip",
+        }
+    }
+}
+
+// Small bodies aren't worth the CPU time, and gzip's own framing could even make them larger.
+pub const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Pick the encoding to compress metrics responses with based on the request's `Accept-Encoding`
+/// header. Compression is only used when the client explicitly asks for it (or for `*`), and
+/// doesn't prefer uncompressed responses over it.
+pub fn negotiate_content_encoding(accept_encoding: Option<&[u8]>) -> ContentEncoding {
+    let mut gzip = None;
+    let mut identity = None;
+    let mut wildcard = None;
+
+    for coding in accept_encoding.unwrap_or_default().split(|&b| b == b',') {
+        let mut params = coding.split(|&b| b == b';').map(trim_ascii);
+        let name = params.next().unwrap_or_default();
+        let mut quality = Some(1000);
+
+        for param in params {
+            if let [b'q' | b'Q', b'=', value @ ..] = param {
+                quality = parse_quality(trim_ascii(value));
+            }
+        }
+
+        let Some(quality) = quality else {
+            continue;
+        };
+
+        if name.eq_ignore_ascii_case(b"gzip") || name.eq_ignore_ascii_case(b"x-gzip") {
+            gzip = Some(quality);
+        } else if name.eq_ignore_ascii_case(b"identity") {
+            identity = Some(quality);
+        } else if name == b"*" {
+            wildcard = Some(quality);
+        }
+    }
+
+    let gzip = gzip.or(wildcard).unwrap_or(0);
+    let identity = identity.or(wildcard).unwrap_or(1000);
+
+    if gzip > 0 && gzip >= identity {
+        ContentEncoding::Gzip
+    } else {
+        ContentEncoding::Identity
+    }
+}
+
+/// Compress a metrics response body with the negotiated encoding. Returns `None` if the body
+/// should just be sent uncompressed instead.
+pub fn compress_metrics_body(encoding: ContentEncoding, body: &[u8]) -> Option<Vec<u8>> {
+    if body.len() < COMPRESSION_THRESHOLD {
         return None;
+    }
+
+    match encoding {
+        ContentEncoding::Identity => None,
+        ContentEncoding::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body).ok()?;
+            encoder.finish().ok()
+        }
+    }
+}
+
+// Rejects the request, queueing up the reason for the parent to count.
+fn reject_auth<C: ResponseContext + 'static, T>(
+    res: C,
+    reason: AuthFailureReason,
+    shared: &RequestShared<C, impl ImmutableWrite>,
+    received: Duration,
+    peer_addr: Ipv6Addr,
+) -> Option<T> {
+    // Only count attempts that actually guessed a key. Missing and malformed headers can't get
+    // anywhere near one.
+    if let AuthFailureReason::WrongUser | AuthFailureReason::UnknownKey = reason {
+        let mut limiter = shared.state.limiter.lock();
+        let locked_out = limiter.record_auth_failure(received, peer_addr);
+        drop(limiter);
+
+        if let Some((prefix, duration)) = locked_out {
+            log::warn!(
+                "Locking out {prefix} for {} seconds after repeated authorization failures.",
+                duration.as_secs()
+            );
+        }
+    }
+
+    let head = match reason {
+        AuthFailureReason::MissingHeader | AuthFailureReason::BadSyntax => {
+            &RESPONSE_BAD_AUTH_SYNTAX
+        }
+        AuthFailureReason::WrongUser | AuthFailureReason::UnknownKey => &RESPONSE_FORBIDDEN,
     };
 
-    let rest = trim_auth_token(rest);
+    queue_report(shared.state, &[ipc::child::auth_failure_byte(reason)]);
+    shared.state.respond(res, head, &[]);
+    None
+}
 
-    // 0 = empty
-    // 1 = invalid Base64
-    if rest.len() <= 1 {
-        res.respond(&RESPONSE_BAD_AUTH_SYNTAX, &[]);
-        return None;
+// Very simplistic parsing. On success, returns the matched key's name and encoded scope.
+fn authenticate(
+    req: &impl RequestContext,
+    key_set: Option<&KeySet>,
+) -> Result<(KeyName, Box<[u8]>), AuthFailureReason> {
+    fn matched(key: &Key, scope: &[u8]) -> (KeyName, Box<[u8]>) {
+        (KeyName::from_slice(key.name()).ok().unwrap(), scope.into())
     }
 
-    let Ok(decoded) = ENGINE.decode(rest) else {
-        res.respond(&RESPONSE_BAD_AUTH_SYNTAX, &[]);
-        return None;
+    // A verified client certificate mapped to a key stands in for the header entirely. Ones that
+    // aren't mapped to anything still need the header like any other request.
+    if let (Some(client_cn), Some(key_set)) = (req.client_cn(), key_set) {
+        if let Some((key, scope)) = key_set.check_client_cn(client_cn) {
+            return Ok(matched(key, scope));
+        }
+    }
+
+    let Some(auth_header) = req.authorization() else {
+        return Err(AuthFailureReason::MissingHeader);
     };
 
-    let password = match decoded.as_slice() {
-        [b'm', b'e', b't', b'r', b'i', b'c', b's', b':', password @ ..] if !password.is_empty() => {
-            password
+    // Bearer tokens are just the key itself, and are checked against every key regardless of its
+    // username.
+    let decoded;
+    let (user, password) = if let Some(rest) = strip_auth_scheme(auth_header, b"Basic") {
+        let rest = trim_auth_token(rest);
+
+        // 0 = empty
+        // 1 = invalid Base64
+        if rest.len() <= 1 {
+            return Err(AuthFailureReason::BadSyntax);
         }
-        _ => {
-            if decoded.contains(&b':') {
-                res.respond(&RESPONSE_FORBIDDEN, &[]);
-                return None;
-            } else {
-                res.respond(&RESPONSE_BAD_AUTH_SYNTAX, &[]);
-                return None;
-            }
+
+        let Ok(result) = ENGINE.decode(rest) else {
+            return Err(AuthFailureReason::BadSyntax);
+        };
+        decoded = result;
+
+        let Some(split) = decoded.iter().position(|&b| b == b':') else {
+            return Err(AuthFailureReason::BadSyntax);
+        };
+
+        (Some(&decoded[..split]), &decoded[split.wrapping_add(1)..])
+    } else if let Some(rest) = strip_auth_scheme(auth_header, b"Bearer") {
+        let token = trim_ascii(rest);
+        if token.is_empty() {
+            return Err(AuthFailureReason::BadSyntax);
         }
+        (None, token)
+    } else {
+        return Err(AuthFailureReason::BadSyntax);
+    };
+
+    let Some(key_set) = key_set else {
+        return Err(AuthFailureReason::UnknownKey);
+    };
+
+    let check = match user {
+        Some(user) => key_set.check(user, password),
+        None => key_set.check_token(password),
     };
 
+    match check {
+        KeyCheck::Matched(key, scope) => Ok(matched(key, scope)),
+        KeyCheck::WrongUser => Err(AuthFailureReason::WrongUser),
+        KeyCheck::UnknownKey => Err(AuthFailureReason::UnknownKey),
+    }
+}
+
+// On success, also returns the matched key's encoded scope.
+fn handle_metrics_get<C: ResponseContext + 'static>(
+    req: impl RequestContext,
+    res: C,
+    shared: &RequestShared<C, impl ImmutableWrite>,
+) -> Option<(C, Box<[u8]>)> {
+    let received = req.received().saturating_duration_since(shared.initialized);
+
+    // Locked out clients don't get to have their keys compared at all.
+    if shared
+        .state
+        .limiter
+        .lock()
+        .is_locked_out(received, req.peer_addr())
+    {
+        queue_report(
+            shared.state,
+            &[ipc::child::throttle_byte(ThrottleReason::Lockout)],
+        );
+        shared.state.respond(res, &RESPONSE_THROTTLED, &[]);
+        return None;
+    }
+
     let guard = shared
         .state
         .key_set
         .read()
         .unwrap_or_else(|e| e.into_inner());
 
-    let Some(key_set) = &*guard else {
-        // No need to retain the lock while responding.
-        drop(guard);
-        res.respond(&RESPONSE_FORBIDDEN, &[]);
-        return None;
+    let result = authenticate(&req, guard.as_ref());
+
+    // No need to retain the lock while responding.
+    drop(guard);
+
+    let (name, scope) = match result {
+        Ok(matched) => matched,
+        Err(reason) => return reject_auth(res, reason, shared, received, req.peer_addr()),
     };
 
-    if !key_set.check_key(password) {
-        // No need to retain the lock while responding.
-        drop(guard);
-        res.respond(&RESPONSE_FORBIDDEN, &[]);
-        return None;
+    // Unnamed keys are only counted in the overall total.
+    if !name.is_empty() {
+        queue_report(shared.state, &ipc::child::key_request_bytes(&name));
     }
 
-    drop(decoded);
-
-    let diff = req.received().saturating_duration_since(shared.initialized);
     let mut limiter = shared.state.limiter.lock();
 
-    if limiter.check_throttled(diff.as_secs(), req.peer_addr()) {
+    if let Some(reason) = limiter.check_throttled(received, req.peer_addr(), &name) {
         drop(limiter);
-        res.respond(&RESPONSE_THROTTLED, &[]);
+        queue_report(shared.state, &[ipc::child::throttle_byte(reason)]);
+        shared.state.respond(res, &RESPONSE_THROTTLED, &[]);
         None
     } else {
-        Some(res)
+        Some((res, scope))
     }
 }
 
@@ -213,18 +517,66 @@ pub fn handle_request<C: ResponseContext + 'static>(
     res: C,
     shared: &RequestShared<C, impl ImmutableWrite>,
 ) {
-    if !super::ipc::send_msg(shared, &[ipc::child::TRACK_REQUEST]) {
-        res.respond(&RESPONSE_UNAVAILABLE, &[]);
-        return;
+    // Send whatever this request itself led to right away, rather than holding it until the next
+    // tracked request. Otherwise, a burst of failed attempts wouldn't show until something else
+    // came in after it. No point if the parent's already gone, though.
+    if route_request(req, res, shared) {
+        super::ipc::flush_reports(shared);
     }
+}
+
+// Returns `false` if the parent couldn't be reached.
+fn route_request<C: ResponseContext + 'static>(
+    req: impl RequestContext,
+    res: C,
+    shared: &RequestShared<C, impl ImmutableWrite>,
+) -> bool {
+    // Captured up front, as the request itself is consumed by the authorization check.
+    let received = req.received();
 
+    // Health checks skip authorization and rate limiting, so load balancers and orchestrators can
+    // probe as often as they like. They're also left out of the request count, so they don't drown
+    // out the scrapes in it.
     match req.route() {
-        Route::InvalidMethod => res.respond(&RESPONSE_METHOD_NOT_ALLOWED, &[]),
-        Route::InvalidPath => res.respond(&RESPONSE_NOT_FOUND, &[]),
-        Route::MetricsGet => {
-            if let Some(res) = handle_metrics_get(req, res, shared) {
-                request_metrics(res, shared);
+        Route::HealthzGet => request_health(res, HealthCheck::Live, received, shared),
+        Route::ReadyzGet => request_health(res, HealthCheck::Ready, received, shared),
+        Route::InvalidMethod => match track_request(res, shared) {
+            Some(res) => {
+                shared.state.respond(res, &RESPONSE_METHOD_NOT_ALLOWED, &[]);
+                true
             }
-        }
+            None => false,
+        },
+        Route::InvalidPath => match track_request(res, shared) {
+            Some(res) => {
+                shared.state.respond(res, &RESPONSE_NOT_FOUND, &[]);
+                true
+            }
+            None => false,
+        },
+        Route::MetricsGet => match track_request(res, shared) {
+            Some(res) => {
+                let format = req.metrics_format();
+                match handle_metrics_get(req, res, shared) {
+                    Some((res, scope)) => request_metrics(res, format, scope, received, shared),
+                    None => true,
+                }
+            }
+            None => false,
+        },
+    }
+}
+
+// Counts the request with the parent. If it couldn't be reached, this responds with a 503 itself
+// and returns `None`.
+fn track_request<C: ResponseContext + 'static>(
+    res: C,
+    shared: &RequestShared<C, impl ImmutableWrite>,
+) -> Option<C> {
+    if super::ipc::send_track_request(shared) {
+        Some(res)
+    } else {
+        shared.state.respond(res, &RESPONSE_UNAVAILABLE, &[]);
+        None
     }
 }
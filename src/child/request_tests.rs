@@ -4,12 +4,30 @@ use super::request::RequestContext;
 use super::request::RequestShared;
 use super::request::ResponseContext;
 use crate::child::ipc::child_ipc;
+use crate::child::request::compress_metrics_body;
 use crate::child::request::handle_request;
+use crate::child::request::negotiate_content_encoding;
+use crate::child::request::negotiate_metrics_format;
+use crate::child::request::ContentEncoding;
 use crate::child::request::ResponseHead;
 use crate::child::request::ResponseHeaderTemplate;
 use crate::child::request::Route;
 use crate::child::request::ServerState;
+use crate::child::request::COMPRESSION_THRESHOLD;
+use crate::child::request::RESPONSE_BAD_AUTH_SYNTAX;
+use crate::child::request::RESPONSE_FORBIDDEN;
+use crate::child::request::RESPONSE_HEALTHY;
+use crate::child::request::RESPONSE_METHOD_NOT_ALLOWED;
+use crate::child::request::RESPONSE_NOT_FOUND;
+use crate::child::request::RESPONSE_NOT_READY;
+use crate::child::request::RESPONSE_OK_OPENMETRICS;
+use crate::child::request::RESPONSE_OK_PROMETHEUS_TEXT;
+use crate::child::request::RESPONSE_OK_PROTOBUF;
+use crate::child::request::RESPONSE_SERVER_ERROR;
+use crate::child::request::RESPONSE_THROTTLED;
+use crate::child::request::RESPONSE_UNAVAILABLE;
 use crate::ffi::Pollable;
+use crate::state::ipc::MetricsFormat;
 use crate::state::ipc::VERSION_BYTES;
 
 //  #     #
@@ -30,6 +48,7 @@ fn test_not_found(
     let state = Arc::new(SyntheticRequestState::new(route, None));
 
     target.enqueue_write(Ok(1));
+    target.enqueue_write(Ok(5));
 
     let context = SyntheticRequestContext(state.clone());
     handle_request(context.clone(), context, &shared);
@@ -38,7 +57,10 @@ fn test_not_found(
 
     assert_eq!(response.head, head);
 
-    target.assert_data_written(&[ipc::child::TRACK_REQUEST]);
+    target.assert_data_written(&concat_reports(&[
+        &[ipc::child::TRACK_REQUEST],
+        &ipc::child::response_bytes(head.status),
+    ]));
 }
 
 #[test]
@@ -84,6 +106,7 @@ fn handles_an_unauthorized_metrics_get_request() {
     let state = Arc::new(SyntheticRequestState::new(Route::MetricsGet, None));
 
     TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(6));
 
     let context = SyntheticRequestContext(state.clone());
     handle_request(context.clone(), context, &shared);
@@ -106,7 +129,11 @@ fn handles_an_unauthorized_metrics_get_request() {
         }
     );
 
-    TARGET.assert_data_written(&[ipc::child::TRACK_REQUEST]);
+    TARGET.assert_data_written(&concat_reports(&[
+        &[ipc::child::TRACK_REQUEST],
+        &[ipc::child::AUTH_MISSING_HEADER],
+        &ipc::child::response_bytes(401),
+    ]));
     TARGET.assert_no_calls_remaining();
     logger_guard.expect_logs(&[]);
 }
@@ -127,6 +154,7 @@ fn test_bad_auth_syntax(
     ));
 
     target.enqueue_write(Ok(1));
+    target.enqueue_write(Ok(6));
 
     let context = SyntheticRequestContext(request_state.clone());
     handle_request(context.clone(), context, &shared);
@@ -153,7 +181,11 @@ fn test_bad_auth_syntax(
         }
     );
 
-    target.assert_data_written(&[ipc::child::TRACK_REQUEST]);
+    target.assert_data_written(&concat_reports(&[
+        &[ipc::child::TRACK_REQUEST],
+        &[ipc::child::AUTH_BAD_SYNTAX],
+        &ipc::child::response_bytes(401),
+    ]));
     target.assert_no_calls_remaining();
     logger_guard.expect_logs(&[]);
 }
@@ -200,6 +232,20 @@ fn handles_invalid_base64_authorization_in_metrics_get_request() {
     test_bad_auth_syntax(&TARGET, &STATE, b"Basic ???");
 }
 
+#[test]
+fn handles_empty_bearer_authorization_in_metrics_get_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    test_bad_auth_syntax(&TARGET, &STATE, b"Bearer    ");
+}
+
+#[test]
+fn handles_bearer_no_space_authorization_in_metrics_get_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    test_bad_auth_syntax(&TARGET, &STATE, b"Bearer0123456789abcdef");
+}
+
 #[test]
 fn handles_wrong_username_missing_password_in_metrics_get_request() {
     static TARGET: WriteSpy = WriteSpy::new("TARGET");
@@ -219,10 +265,12 @@ fn handles_right_username_missing_password_in_metrics_get_request() {
 fn test_bad_auth_credentials(
     target: &'static WriteSpy,
     state: &'static ServerState<SyntheticRequestContext>,
+    key_set: KeySet,
     authorization: &'static [u8],
+    reason_byte: u8,
 ) {
     let logger_guard = setup_capture_logger();
-    let shared = make_shared(state, target, &[b"0123456789abcdef"]);
+    let shared = make_shared_with_key_set(state, target, Some(key_set));
     let terminate_notify = Arc::new(Notify::new());
     let _terminate_guard = terminate_notify.create_guard();
 
@@ -232,6 +280,7 @@ fn test_bad_auth_credentials(
     ));
 
     target.enqueue_write(Ok(1));
+    target.enqueue_write(Ok(6));
 
     let context = SyntheticRequestContext(request_state.clone());
     handle_request(context.clone(), context, &shared);
@@ -258,7 +307,11 @@ fn test_bad_auth_credentials(
         }
     );
 
-    target.assert_data_written(&[ipc::child::TRACK_REQUEST]);
+    target.assert_data_written(&concat_reports(&[
+        &[ipc::child::TRACK_REQUEST],
+        &[reason_byte],
+        &ipc::child::response_bytes(403),
+    ]));
     target.assert_no_calls_remaining();
     logger_guard.expect_logs(&[]);
 }
@@ -268,7 +321,13 @@ fn handles_bad_username_in_metrics_get_request() {
     static TARGET: WriteSpy = WriteSpy::new("TARGET");
     static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
     // Decoded: `bad:0123456789abcdef`
-    test_bad_auth_credentials(&TARGET, &STATE, b"Basic YmFkOjAxMjM0NTY3ODlhYmNkZWY=");
+    test_bad_auth_credentials(
+        &TARGET,
+        &STATE,
+        KeySet::build(&[b"0123456789abcdef"]),
+        b"Basic YmFkOjAxMjM0NTY3ODlhYmNkZWY=",
+        ipc::child::AUTH_WRONG_USER,
+    );
 }
 
 #[test]
@@ -276,7 +335,13 @@ fn handles_right_username_empty_password_in_metrics_get_request() {
     static TARGET: WriteSpy = WriteSpy::new("TARGET");
     static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
     // Decoded: `metrics:`
-    test_bad_auth_credentials(&TARGET, &STATE, b"Basic bWV0cmljczo=");
+    test_bad_auth_credentials(
+        &TARGET,
+        &STATE,
+        KeySet::build(&[b"0123456789abcdef"]),
+        b"Basic bWV0cmljczo=",
+        ipc::child::AUTH_UNKNOWN_KEY,
+    );
 }
 
 #[test]
@@ -284,7 +349,224 @@ fn handles_bad_password_in_metrics_get_request() {
     static TARGET: WriteSpy = WriteSpy::new("TARGET");
     static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
     // Decoded: `metrics:000044448888cccc`
-    test_bad_auth_credentials(&TARGET, &STATE, b"Basic bWV0cmljczowMDAwNDQ0NDg4ODhjY2Nj");
+    test_bad_auth_credentials(
+        &TARGET,
+        &STATE,
+        KeySet::build(&[b"0123456789abcdef"]),
+        b"Basic bWV0cmljczowMDAwNDQ0NDg4ODhjY2Nj",
+        ipc::child::AUTH_UNKNOWN_KEY,
+    );
+}
+
+#[test]
+fn handles_bad_bearer_token_in_metrics_get_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    test_bad_auth_credentials(
+        &TARGET,
+        &STATE,
+        KeySet::build(&[b"0123456789abcdef"]),
+        b"Bearer 000044448888cccc",
+        ipc::child::AUTH_UNKNOWN_KEY,
+    );
+}
+
+#[test]
+fn handles_default_username_for_custom_username_key_in_metrics_get_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    // Decoded: `metrics:0123456789abcdef`
+    test_bad_auth_credentials(
+        &TARGET,
+        &STATE,
+        KeySet::build_named(&[(b"0123456789abcdef", b"grafana-agent", b"grafana")]),
+        b"Basic bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm",
+        ipc::child::AUTH_WRONG_USER,
+    );
+}
+
+#[test]
+fn handles_bad_password_for_custom_username_in_metrics_get_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    // Decoded: `grafana:000044448888cccc`
+    test_bad_auth_credentials(
+        &TARGET,
+        &STATE,
+        KeySet::build_named(&[(b"0123456789abcdef", b"grafana-agent", b"grafana")]),
+        b"Basic Z3JhZmFuYTowMDAwNDQ0NDg4ODhjY2Nj",
+        ipc::child::AUTH_UNKNOWN_KEY,
+    );
+}
+
+#[test]
+fn handles_throttled_metrics_get_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    let terminate_notify = Arc::new(Notify::new());
+    let _terminate_guard = terminate_notify.create_guard();
+
+    // Pretend the same peer already made a request this second.
+    assert_eq!(
+        STATE
+            .limiter
+            .lock()
+            .check_throttled(Duration::ZERO, std::net::Ipv6Addr::LOCALHOST, b""),
+        None
+    );
+
+    TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(6));
+
+    // Decoded: `metrics:0123456789abcdef`
+    let state = Arc::new(SyntheticRequestState::new(
+        Route::MetricsGet,
+        Some(b"Basic bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm"),
+    ));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert!(
+        !STATE.ipc_requester.has_requests_pending(),
+        "Expected request not to be queued.",
+    );
+
+    let response = state.response.lock().take().expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: &ResponseHead {
+                status: 429,
+                header_template: ResponseHeaderTemplate::Empty,
+            },
+            body: Vec::new(),
+        }
+    );
+
+    TARGET.assert_data_written(&concat_reports(&[
+        &[ipc::child::TRACK_REQUEST],
+        &[ipc::child::THROTTLED_BY_ADDRESS],
+        &ipc::child::response_bytes(429),
+    ]));
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
+}
+
+#[test]
+fn handles_locked_out_metrics_get_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    let terminate_notify = Arc::new(Notify::new());
+    let _terminate_guard = terminate_notify.create_guard();
+
+    // Pretend the same peer just failed authorization enough times to get locked out.
+    let failures = RateLimitPolicy::new().lockout.failures.get();
+    for _ in 1..failures {
+        assert_eq!(
+            STATE
+                .limiter
+                .lock()
+                .record_auth_failure(Duration::ZERO, std::net::Ipv6Addr::LOCALHOST),
+            None
+        );
+    }
+    assert!(STATE
+        .limiter
+        .lock()
+        .record_auth_failure(Duration::ZERO, std::net::Ipv6Addr::LOCALHOST)
+        .is_some());
+
+    TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(6));
+
+    // Decoded: `metrics:0123456789abcdef`. Even the right key is rejected without being checked.
+    let state = Arc::new(SyntheticRequestState::new(
+        Route::MetricsGet,
+        Some(b"Basic bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm"),
+    ));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert!(
+        !STATE.ipc_requester.has_requests_pending(),
+        "Expected request not to be queued.",
+    );
+
+    let response = state.response.lock().take().expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: &RESPONSE_THROTTLED,
+            body: Vec::new(),
+        }
+    );
+
+    TARGET.assert_data_written(&concat_reports(&[
+        &[ipc::child::TRACK_REQUEST],
+        &[ipc::child::THROTTLED_BY_LOCKOUT],
+        &ipc::child::response_bytes(429),
+    ]));
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
+}
+
+#[test]
+fn locks_out_after_repeated_unknown_keys() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    let terminate_notify = Arc::new(Notify::new());
+    let _terminate_guard = terminate_notify.create_guard();
+
+    let mut policy = RateLimitPolicy::new();
+    policy.lockout.failures = std::num::NonZeroU32::new(2).unwrap();
+    STATE.limiter.lock().set_policy(policy);
+
+    for _ in 0..2 {
+        TARGET.enqueue_write(Ok(1));
+        TARGET.enqueue_write(Ok(6));
+
+        // Decoded: `metrics:fedcba9876543210`
+        let state = Arc::new(SyntheticRequestState::new(
+            Route::MetricsGet,
+            Some(b"Basic bWV0cmljczpmZWRjYmE5ODc2NTQzMjEw"),
+        ));
+
+        let context = SyntheticRequestContext(state.clone());
+        handle_request(context.clone(), context, &shared);
+
+        let response = state.response.lock().take().expect("No response received");
+        assert_eq!(response.head, &RESPONSE_FORBIDDEN);
+    }
+
+    assert!(STATE.limiter.lock().is_locked_out(
+        Instant::now().saturating_duration_since(shared.initialized),
+        std::net::Ipv6Addr::LOCALHOST
+    ));
+
+    TARGET.assert_data_written(&concat_reports(&[
+        &[ipc::child::TRACK_REQUEST],
+        &[ipc::child::AUTH_UNKNOWN_KEY],
+        &ipc::child::response_bytes(403),
+        &[ipc::child::TRACK_REQUEST],
+        &[ipc::child::AUTH_UNKNOWN_KEY],
+        &ipc::child::response_bytes(403),
+    ]));
+    TARGET.assert_no_calls_remaining();
+    logger_guard
+        .expect_logs(&["Locking out ::/64 for 60 seconds after repeated authorization failures."]);
 }
 
 #[test]
@@ -332,7 +614,7 @@ fn handles_an_authorized_metrics_get_request() {
         SyntheticResponse {
             head: &ResponseHead {
                 status: 200,
-                header_template: ResponseHeaderTemplate::Metrics,
+                header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::OpenMetrics),
             },
             body: b"0123456789abcdef".to_vec()
         }
@@ -344,7 +626,7 @@ fn handles_an_authorized_metrics_get_request() {
 }
 
 #[test]
-fn trims_whitespace_in_metrics_get_request() {
+fn handles_an_authorized_bearer_metrics_get_request() {
     #[rustfmt::skip]
     static IPC_RECV: &[u8] = &[
         VERSION_BYTES[0], VERSION_BYTES[1], VERSION_BYTES[2], VERSION_BYTES[3],
@@ -358,17 +640,25 @@ fn trims_whitespace_in_metrics_get_request() {
     static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
 
     let logger_guard = setup_capture_logger();
-    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    // Bearer tokens aren't tied to the key's username.
+    let shared = make_shared_with_key_set(
+        &STATE,
+        &TARGET,
+        Some(KeySet::build_named(&[(
+            b"0123456789abcdef",
+            b"",
+            b"grafana",
+        )])),
+    );
     let terminate_notify = Arc::new(Notify::new());
     let _terminate_guard = terminate_notify.create_guard();
 
     TARGET.enqueue_write(Ok(1));
     TARGET.enqueue_write(Ok(1));
 
-    // Decoded: `metrics:0123456789abcdef`
     let state = Arc::new(SyntheticRequestState::new(
         Route::MetricsGet,
-        Some(b"Basic    bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm    "),
+        Some(b"Bearer 0123456789ABCDEF "),
     ));
 
     let context = SyntheticRequestContext(state.clone());
@@ -388,7 +678,7 @@ fn trims_whitespace_in_metrics_get_request() {
         SyntheticResponse {
             head: &ResponseHead {
                 status: 200,
-                header_template: ResponseHeaderTemplate::Metrics,
+                header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::OpenMetrics),
             },
             body: b"0123456789abcdef".to_vec()
         }
@@ -399,68 +689,131 @@ fn trims_whitespace_in_metrics_get_request() {
     logger_guard.expect_logs(&[]);
 }
 
-#[test]
-fn handles_metrics_get_request_disconnects_early() {
-    static TARGET: WriteSpy = WriteSpy::new("TARGET");
-    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+fn test_authorized_scheme(
+    target: &'static WriteSpy,
+    state: &'static ServerState<SyntheticRequestContext>,
+    authorization: &'static [u8],
+) {
+    #[rustfmt::skip]
+    static IPC_RECV: &[u8] = &[
+        VERSION_BYTES[0], VERSION_BYTES[1], VERSION_BYTES[2], VERSION_BYTES[3],
+        0x00, // Operation ID
+        0x10, 0x00, 0x00, 0x00, // Data length (16)
+        b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', // Data
+        b'8', b'9', b'a', b'b', b'c', b'd', b'e', b'f',
+    ];
 
     let logger_guard = setup_capture_logger();
-    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    let shared = make_shared(state, target, &[b"0123456789abcdef"]);
     let terminate_notify = Arc::new(Notify::new());
-    terminate_notify.notify();
+    let _terminate_guard = terminate_notify.create_guard();
 
-    TARGET.enqueue_write(Err(libc::EPIPE));
+    target.enqueue_write(Ok(1));
+    target.enqueue_write(Ok(1));
 
-    // Decoded: `metrics:0123456789abcdef`
-    let state = Arc::new(SyntheticRequestState::new(
+    let request_state = Arc::new(SyntheticRequestState::new(
         Route::MetricsGet,
-        Some(b"Basic bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm"),
+        Some(authorization),
     ));
 
-    let context = SyntheticRequestContext(state.clone());
+    let context = SyntheticRequestContext(request_state.clone());
     handle_request(context.clone(), context, &shared);
 
     assert!(
-        !STATE.ipc_requester.has_requests_pending(),
-        "Expected request not to be queued.",
+        state.ipc_requester.has_requests_pending(),
+        "Expected request to be queued.",
     );
 
-    let response = state.response.lock().take().expect("No response received");
+    assert_result_eq(resume_request(state, &terminate_notify, IPC_RECV), Ok(()));
+
+    let response = request_state
+        .response
+        .lock()
+        .take()
+        .expect("No response received");
 
     assert_eq!(
         response,
         SyntheticResponse {
             head: &ResponseHead {
-                status: 503,
-                header_template: ResponseHeaderTemplate::Disconnect,
+                status: 200,
+                header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::OpenMetrics),
             },
-            body: Vec::new(),
+            body: b"0123456789abcdef".to_vec()
         }
     );
 
-    TARGET.assert_data_written(&[]);
-    TARGET.assert_no_calls_remaining();
-    logger_guard.expect_logs(&["EPIPE: Broken pipe"]);
+    target.assert_data_written(&[ipc::child::TRACK_REQUEST, ipc::child::REQUEST_METRICS]);
+    target.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
 }
 
 #[test]
-fn handles_metrics_get_request_disconnects_late() {
+fn handles_a_lowercase_basic_scheme_in_metrics_get_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    // Decoded: `metrics:0123456789abcdef`
+    test_authorized_scheme(&TARGET, &STATE, b"basic bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm");
+}
+
+#[test]
+fn handles_a_mixed_case_basic_scheme_in_metrics_get_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    // Decoded: `metrics:0123456789abcdef`
+    test_authorized_scheme(&TARGET, &STATE, b"bAsIC bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm");
+}
+
+#[test]
+fn handles_a_lowercase_bearer_scheme_in_metrics_get_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    test_authorized_scheme(&TARGET, &STATE, b"bearer 0123456789abcdef");
+}
+
+#[test]
+fn handles_a_mixed_case_bearer_scheme_in_metrics_get_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    test_authorized_scheme(&TARGET, &STATE, b"BEARer 0123456789abcdef");
+}
+
+#[test]
+fn handles_an_authorized_client_certificate_metrics_get_request() {
+    #[rustfmt::skip]
+    static IPC_RECV: &[u8] = &[
+        VERSION_BYTES[0], VERSION_BYTES[1], VERSION_BYTES[2], VERSION_BYTES[3],
+        0x00, // Operation ID
+        0x10, 0x00, 0x00, 0x00, // Data length (16)
+        b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', // Data
+        b'8', b'9', b'a', b'b', b'c', b'd', b'e', b'f',
+    ];
+
     static TARGET: WriteSpy = WriteSpy::new("TARGET");
     static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
 
     let logger_guard = setup_capture_logger();
-    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    let shared = make_shared_with_key_set(
+        &STATE,
+        &TARGET,
+        Some(KeySet::build_client_cns(&[
+            (Some(b"aaaaaaaaaaaaaaaa"), b"", b"prometheus"),
+            (None, b"Grafana Agent", b"grafana-agent"),
+        ])),
+    );
     let terminate_notify = Arc::new(Notify::new());
-    let terminate_guard = terminate_notify.create_guard();
-
-    // Decoded: `metrics:0123456789abcdef`
-    let state = Arc::new(SyntheticRequestState::new(
-        Route::MetricsGet,
-        Some(b"Basic bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm"),
-    ));
+    let _terminate_guard = terminate_notify.create_guard();
 
     TARGET.enqueue_write(Ok(1));
     TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(15));
+
+    // The certificate's enough on its own, without any header.
+    let state = Arc::new(SyntheticRequestState::with_client_cn(
+        Route::MetricsGet,
+        None,
+        b"Grafana Agent",
+    ));
 
     let context = SyntheticRequestContext(state.clone());
     handle_request(context.clone(), context, &shared);
@@ -470,11 +823,7 @@ fn handles_metrics_get_request_disconnects_late() {
         "Expected request to be queued.",
     );
 
-    drop(terminate_guard);
-    assert_result_eq(
-        resume_request(&STATE, &terminate_notify, Disconnected),
-        Ok(()),
-    );
+    assert_result_eq(resume_request(&STATE, &terminate_notify, IPC_RECV), Ok(()));
 
     let response = state.response.lock().take().expect("No response received");
 
@@ -482,38 +831,1004 @@ fn handles_metrics_get_request_disconnects_late() {
         response,
         SyntheticResponse {
             head: &ResponseHead {
-                status: 503,
-                header_template: ResponseHeaderTemplate::Disconnect,
+                status: 200,
+                header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::OpenMetrics),
             },
-            body: Vec::new()
+            body: b"0123456789abcdef".to_vec()
         }
     );
 
-    TARGET.assert_data_written(&[ipc::child::TRACK_REQUEST, ipc::child::REQUEST_METRICS]);
+    TARGET.assert_data_written(&concat_reports(&[
+        &[ipc::child::TRACK_REQUEST],
+        &[ipc::child::REQUEST_METRICS],
+        &ipc::child::key_request_bytes(b"grafana-agent"),
+    ]));
     TARGET.assert_no_calls_remaining();
     logger_guard.expect_logs(&[]);
 }
 
-//  #     #
-//  #     # ###### #      #####  ###### #####   ####
-//  #     # #      #      #    # #      #    # #
-//  ####### #####  #      #    # #####  #    #  ####
-//  #     # #      #      #####  #      #####       #
+#[test]
+fn falls_back_to_authorization_for_unmapped_client_certificate() {
+    #[rustfmt::skip]
+    static IPC_RECV: &[u8] = &[
+        VERSION_BYTES[0], VERSION_BYTES[1], VERSION_BYTES[2], VERSION_BYTES[3],
+        0x00, // Operation ID
+        0x10, 0x00, 0x00, 0x00, // Data length (16)
+        b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', // Data
+        b'8', b'9', b'a', b'b', b'c', b'd', b'e', b'f',
+    ];
+
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared_with_key_set(
+        &STATE,
+        &TARGET,
+        Some(KeySet::build_client_cns(&[
+            (Some(b"0123456789abcdef"), b"", b""),
+            (None, b"Grafana Agent", b"grafana-agent"),
+        ])),
+    );
+    let terminate_notify = Arc::new(Notify::new());
+    let _terminate_guard = terminate_notify.create_guard();
+
+    TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(1));
+
+    let state = Arc::new(SyntheticRequestState::with_client_cn(
+        Route::MetricsGet,
+        Some(b"Bearer 0123456789abcdef"),
+        b"Someone Else",
+    ));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert!(
+        STATE.ipc_requester.has_requests_pending(),
+        "Expected request to be queued.",
+    );
+
+    assert_result_eq(resume_request(&STATE, &terminate_notify, IPC_RECV), Ok(()));
+
+    let response = state.response.lock().take().expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: &ResponseHead {
+                status: 200,
+                header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::OpenMetrics),
+            },
+            body: b"0123456789abcdef".to_vec()
+        }
+    );
+
+    TARGET.assert_data_written(&[ipc::child::TRACK_REQUEST, ipc::child::REQUEST_METRICS]);
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
+}
+
+#[test]
+fn rejects_unmapped_client_certificate_without_authorization() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared_with_key_set(
+        &STATE,
+        &TARGET,
+        Some(KeySet::build_client_cns(&[(None, b"Grafana Agent", b"")])),
+    );
+    let terminate_notify = Arc::new(Notify::new());
+    let _terminate_guard = terminate_notify.create_guard();
+
+    let state = Arc::new(SyntheticRequestState::with_client_cn(
+        Route::MetricsGet,
+        None,
+        b"grafana agent",
+    ));
+
+    TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(6));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert!(
+        !STATE.ipc_requester.has_requests_pending(),
+        "Expected request not to be queued.",
+    );
+
+    let response = state.response.lock().take().expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: &ResponseHead {
+                status: 401,
+                header_template: ResponseHeaderTemplate::BadAuthSyntax,
+            },
+            body: Vec::new(),
+        }
+    );
+
+    TARGET.assert_data_written(&concat_reports(&[
+        &[ipc::child::TRACK_REQUEST],
+        &[ipc::child::AUTH_MISSING_HEADER],
+        &ipc::child::response_bytes(401),
+    ]));
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
+}
+
+#[test]
+fn handles_an_authorized_metrics_get_request_for_named_key() {
+    #[rustfmt::skip]
+    static IPC_RECV: &[u8] = &[
+        VERSION_BYTES[0], VERSION_BYTES[1], VERSION_BYTES[2], VERSION_BYTES[3],
+        0x00, // Operation ID
+        0x10, 0x00, 0x00, 0x00, // Data length (16)
+        b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', // Data
+        b'8', b'9', b'a', b'b', b'c', b'd', b'e', b'f',
+    ];
+
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared_with_key_set(
+        &STATE,
+        &TARGET,
+        Some(KeySet::build_named(&[
+            (b"aaaaaaaaaaaaaaaa", b"prometheus", b"metrics"),
+            (b"0123456789abcdef", b"grafana-agent", b"grafana"),
+        ])),
+    );
+    let terminate_notify = Arc::new(Notify::new());
+    let _terminate_guard = terminate_notify.create_guard();
+
+    TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(15));
+
+    // Decoded: `grafana:0123456789abcdef`
+    let state = Arc::new(SyntheticRequestState::new(
+        Route::MetricsGet,
+        Some(b"Basic Z3JhZmFuYTowMTIzNDU2Nzg5YWJjZGVm"),
+    ));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert!(
+        STATE.ipc_requester.has_requests_pending(),
+        "Expected request to be queued.",
+    );
+
+    assert_result_eq(resume_request(&STATE, &terminate_notify, IPC_RECV), Ok(()));
+
+    let response = state.response.lock().take().expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: &ResponseHead {
+                status: 200,
+                header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::OpenMetrics),
+            },
+            body: b"0123456789abcdef".to_vec()
+        }
+    );
+
+    TARGET.assert_data_written(&concat_reports(&[
+        &[ipc::child::TRACK_REQUEST],
+        &[ipc::child::REQUEST_METRICS],
+        &ipc::child::key_request_bytes(b"grafana-agent"),
+    ]));
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
+}
+
+#[test]
+fn handles_an_authorized_metrics_get_request_for_scoped_key() {
+    #[rustfmt::skip]
+    static IPC_RECV: &[u8] = &[
+        VERSION_BYTES[0], VERSION_BYTES[1], VERSION_BYTES[2], VERSION_BYTES[3],
+        0x00, // Operation ID
+        0x03, 0x00, 0x00, 0x00, // Data length (3)
+        b'a', b'l', b'l', // Data
+        0x05, // Operation ID
+        0x00, 0x00, 0x00, 0x00, // ID (0)
+        0x00, // Operation ID
+        0x06, 0x00, 0x00, 0x00, // Data length (6)
+        b's', b'c', b'o', b'p', b'e', b'd', // Data
+    ];
+
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared_with_key_set(
+        &STATE,
+        &TARGET,
+        Some(KeySet::build_scoped(&[(
+            b"0123456789abcdef",
+            b"services=team-a-*",
+        )])),
+    );
+    let terminate_notify = Arc::new(Notify::new());
+    let _terminate_guard = terminate_notify.create_guard();
+
+    TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(27));
+
+    // Decoded: `metrics:0123456789abcdef`
+    let state = Arc::new(SyntheticRequestState::new(
+        Route::MetricsGet,
+        Some(b"Basic bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm"),
+    ));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert!(
+        STATE.ipc_requester.has_requests_pending(),
+        "Expected request to be queued.",
+    );
+
+    // The unscoped response before it shouldn't be mistaken for it.
+    assert_result_eq(resume_request(&STATE, &terminate_notify, IPC_RECV), Ok(()));
+
+    let response = state.response.lock().take().expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: &ResponseHead {
+                status: 200,
+                header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::OpenMetrics),
+            },
+            body: b"scoped".to_vec()
+        }
+    );
+
+    TARGET.assert_data_written(&concat_reports(&[
+        &[ipc::child::TRACK_REQUEST],
+        &ipc::child::scoped_metrics_request_bytes(
+            0,
+            MetricsFormat::OpenMetrics,
+            b"services=team-a-*",
+        ),
+    ]));
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
+}
+
+#[test]
+fn sends_metrics_timings_with_next_tracked_request() {
+    #[rustfmt::skip]
+    static IPC_RECV: &[u8] = &[
+        VERSION_BYTES[0], VERSION_BYTES[1], VERSION_BYTES[2], VERSION_BYTES[3],
+        0x00, // Operation ID
+        0x10, 0x00, 0x00, 0x00, // Data length (16)
+        b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', // Data
+        b'8', b'9', b'a', b'b', b'c', b'd', b'e', b'f',
+    ];
+
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    let terminate_notify = Arc::new(Notify::new());
+    let _terminate_guard = terminate_notify.create_guard();
+
+    TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(1));
+
+    // Decoded: `metrics:0123456789abcdef`
+    let mut state = SyntheticRequestState::new(
+        Route::MetricsGet,
+        Some(b"Basic bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm"),
+    );
+    // Pretend it's been waiting a while, so the scrape duration is clearly distinct.
+    state.received -= Duration::from_secs(2);
+    let state = Arc::new(state);
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+    assert_result_eq(resume_request(&STATE, &terminate_notify, IPC_RECV), Ok(()));
+    assert!(state.response.lock().is_some(), "No response received");
+
+    TARGET.assert_data_written(&[ipc::child::TRACK_REQUEST, ipc::child::REQUEST_METRICS]);
+    TARGET.reset_data_written();
+
+    // The timings and response code are only sent with the next tracked request. That request's
+    // own response code is then sent right after it.
+    let state = Arc::new(SyntheticRequestState::new(Route::InvalidPath, None));
+    TARGET.enqueue_write(Ok(16));
+    TARGET.enqueue_write(Ok(5));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    let written = TARGET.get_data_written();
+    assert_eq!(written.len(), 21);
+    assert_eq!(written[0..5], ipc::child::response_bytes(200));
+    assert_eq!(written[5], ipc::child::OBSERVE_SCRAPE_DURATION);
+    assert_eq!(written[10], ipc::child::OBSERVE_IPC_DURATION);
+    assert_eq!(written[15], ipc::child::TRACK_REQUEST);
+    assert_eq!(written[16..21], ipc::child::response_bytes(404));
+
+    let scrape_micros = u32::from_le_bytes(written[6..10].try_into().unwrap());
+    let ipc_micros = u32::from_le_bytes(written[11..15].try_into().unwrap());
+    assert!(scrape_micros >= 2_000_000, "{scrape_micros} < 2000000");
+    assert!(
+        ipc_micros < scrape_micros,
+        "{ipc_micros} >= {scrape_micros}"
+    );
+
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
+}
+
+#[test]
+fn trims_whitespace_in_metrics_get_request() {
+    #[rustfmt::skip]
+    static IPC_RECV: &[u8] = &[
+        VERSION_BYTES[0], VERSION_BYTES[1], VERSION_BYTES[2], VERSION_BYTES[3],
+        0x00, // Operation ID
+        0x10, 0x00, 0x00, 0x00, // Data length (16)
+        b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', // Data
+        b'8', b'9', b'a', b'b', b'c', b'd', b'e', b'f',
+    ];
+
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    let terminate_notify = Arc::new(Notify::new());
+    let _terminate_guard = terminate_notify.create_guard();
+
+    TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(1));
+
+    // Decoded: `metrics:0123456789abcdef`
+    let state = Arc::new(SyntheticRequestState::new(
+        Route::MetricsGet,
+        Some(b"Basic    bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm    "),
+    ));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert!(
+        STATE.ipc_requester.has_requests_pending(),
+        "Expected request to be queued.",
+    );
+
+    assert_result_eq(resume_request(&STATE, &terminate_notify, IPC_RECV), Ok(()));
+
+    let response = state.response.lock().take().expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: &ResponseHead {
+                status: 200,
+                header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::OpenMetrics),
+            },
+            body: b"0123456789abcdef".to_vec()
+        }
+    );
+
+    TARGET.assert_data_written(&[ipc::child::TRACK_REQUEST, ipc::child::REQUEST_METRICS]);
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
+}
+
+#[test]
+fn handles_prometheus_text_metrics_get_request() {
+    #[rustfmt::skip]
+    static IPC_RECV: &[u8] = &[
+        VERSION_BYTES[0], VERSION_BYTES[1], VERSION_BYTES[2], VERSION_BYTES[3],
+        0x00, // Operation ID
+        0x04, 0x00, 0x00, 0x00, // Data length (4)
+        b'o', b'p', b'e', b'n', // Data
+        0x02, // Operation ID
+        0x04, 0x00, 0x00, 0x00, // Data length (4)
+        b't', b'e', b'x', b't', // Data
+    ];
+
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    let terminate_notify = Arc::new(Notify::new());
+    let _terminate_guard = terminate_notify.create_guard();
+
+    TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(1));
+
+    // Decoded: `metrics:0123456789abcdef`
+    let state = Arc::new(SyntheticRequestState::with_format(
+        Route::MetricsGet,
+        Some(b"Basic bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm"),
+        MetricsFormat::PrometheusText,
+    ));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert_result_eq(resume_request(&STATE, &terminate_notify, IPC_RECV), Ok(()));
+
+    let response = state.response.lock().take().expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: &ResponseHead {
+                status: 200,
+                header_template: ResponseHeaderTemplate::Metrics(MetricsFormat::PrometheusText),
+            },
+            body: b"text".to_vec()
+        }
+    );
+
+    TARGET.assert_data_written(&[ipc::child::TRACK_REQUEST, ipc::child::REQUEST_TEXT_METRICS]);
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
+}
+
+#[test]
+fn handles_metrics_get_request_disconnects_early() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    let terminate_notify = Arc::new(Notify::new());
+    terminate_notify.notify();
+
+    TARGET.enqueue_write(Err(libc::EPIPE));
+
+    // Decoded: `metrics:0123456789abcdef`
+    let state = Arc::new(SyntheticRequestState::new(
+        Route::MetricsGet,
+        Some(b"Basic bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm"),
+    ));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert!(
+        !STATE.ipc_requester.has_requests_pending(),
+        "Expected request not to be queued.",
+    );
+
+    let response = state.response.lock().take().expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: &ResponseHead {
+                status: 503,
+                header_template: ResponseHeaderTemplate::Disconnect,
+            },
+            body: Vec::new(),
+        }
+    );
+
+    TARGET.assert_data_written(&[]);
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&["EPIPE: Broken pipe"]);
+}
+
+#[test]
+fn handles_metrics_get_request_disconnects_late() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    let terminate_notify = Arc::new(Notify::new());
+    let terminate_guard = terminate_notify.create_guard();
+
+    // Decoded: `metrics:0123456789abcdef`
+    let state = Arc::new(SyntheticRequestState::new(
+        Route::MetricsGet,
+        Some(b"Basic bWV0cmljczowMTIzNDU2Nzg5YWJjZGVm"),
+    ));
+
+    TARGET.enqueue_write(Ok(1));
+    TARGET.enqueue_write(Ok(1));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert!(
+        STATE.ipc_requester.has_requests_pending(),
+        "Expected request to be queued.",
+    );
+
+    drop(terminate_guard);
+    assert_result_eq(
+        resume_request(&STATE, &terminate_notify, Disconnected),
+        Ok(()),
+    );
+
+    let response = state.response.lock().take().expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: &ResponseHead {
+                status: 503,
+                header_template: ResponseHeaderTemplate::Disconnect,
+            },
+            body: Vec::new()
+        }
+    );
+
+    TARGET.assert_data_written(&[ipc::child::TRACK_REQUEST, ipc::child::REQUEST_METRICS]);
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
+}
+
+//  #     #
+//  #     # ######   ##   #      ##### #    #    ##### ######  ####  #####  ####
+//  #     # #       #  #  #        #   #    #      #   #      #        #   #
+//  ####### #####  #    # #        #   ######      #   #####   ####    #    ####
+//  #     # #      ###### #        #   #    #      #   #           #   #        #
+//  #     # #      #    # #        #   #    #      #   #      #    #   #   #    #
+//  #     # ###### #    # ######   #   #    #      #   ######  ####    #    ####
+
+fn test_health_request(
+    target: &'static WriteSpy,
+    state: &'static ServerState<SyntheticRequestContext>,
+    route: Route,
+    keys: &[&[u8]],
+    status: u8,
+    expected_head: &'static ResponseHead,
+    expected_body: &[u8],
+) {
+    let ipc_recv = [
+        VERSION_BYTES[0],
+        VERSION_BYTES[1],
+        VERSION_BYTES[2],
+        VERSION_BYTES[3],
+        0x04,
+        status,
+    ];
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared(state, target, keys);
+    let terminate_notify = Arc::new(Notify::new());
+    let _terminate_guard = terminate_notify.create_guard();
+
+    target.enqueue_write(Ok(1));
+
+    // No authorization needed.
+    let request_state = Arc::new(SyntheticRequestState::new(route, None));
+
+    let context = SyntheticRequestContext(request_state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert_result_eq(
+        resume_request(state, &terminate_notify, &ipc_recv[..]),
+        Ok(()),
+    );
+
+    let response = request_state
+        .response
+        .lock()
+        .take()
+        .expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: expected_head,
+            body: expected_body.to_vec(),
+        }
+    );
+
+    // Not tracked as a request, either.
+    target.assert_data_written(&[ipc::child::REQUEST_HEALTH]);
+    target.assert_no_calls_remaining();
+    logger_guard.expect_logs(&[]);
+}
+
+#[test]
+fn handles_healthz_request() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    test_health_request(
+        &TARGET,
+        &STATE,
+        Route::HealthzGet,
+        &[b"0123456789abcdef"],
+        0x03,
+        &RESPONSE_HEALTHY,
+        b"ok\n",
+    );
+}
+
+#[test]
+fn handles_healthz_request_when_not_ready() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    test_health_request(
+        &TARGET,
+        &STATE,
+        Route::HealthzGet,
+        &[],
+        0x00,
+        &RESPONSE_HEALTHY,
+        b"ok\n",
+    );
+}
+
+#[test]
+fn handles_readyz_request_when_ready() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    test_health_request(
+        &TARGET,
+        &STATE,
+        Route::ReadyzGet,
+        &[b"0123456789abcdef"],
+        0x03,
+        &RESPONSE_HEALTHY,
+        b"ok\n",
+    );
+}
+
+#[test]
+fn handles_readyz_request_when_journal_stalled() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    test_health_request(
+        &TARGET,
+        &STATE,
+        Route::ReadyzGet,
+        &[b"0123456789abcdef"],
+        0x02,
+        &RESPONSE_NOT_READY,
+        b"journal: stalled\n",
+    );
+}
+
+#[test]
+fn handles_readyz_request_when_child_restarting() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    test_health_request(
+        &TARGET,
+        &STATE,
+        Route::ReadyzGet,
+        &[b"0123456789abcdef"],
+        0x01,
+        &RESPONSE_NOT_READY,
+        b"server: restarting\n",
+    );
+}
+
+#[test]
+fn handles_readyz_request_without_keys() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+    test_health_request(
+        &TARGET,
+        &STATE,
+        Route::ReadyzGet,
+        &[],
+        0x00,
+        &RESPONSE_NOT_READY,
+        b"journal: stalled\nkeys: not loaded\nserver: restarting\n",
+    );
+}
+
+#[test]
+fn handles_health_request_disconnects_early() {
+    static TARGET: WriteSpy = WriteSpy::new("TARGET");
+    static STATE: ServerState<SyntheticRequestContext> = ServerState::new();
+
+    let logger_guard = setup_capture_logger();
+    let shared = make_shared(&STATE, &TARGET, &[b"0123456789abcdef"]);
+    let terminate_notify = Arc::new(Notify::new());
+    terminate_notify.notify();
+
+    TARGET.enqueue_write(Err(libc::EPIPE));
+
+    let state = Arc::new(SyntheticRequestState::new(Route::HealthzGet, None));
+
+    let context = SyntheticRequestContext(state.clone());
+    handle_request(context.clone(), context, &shared);
+
+    assert!(
+        !STATE.ipc_requester.has_requests_pending(),
+        "Expected request not to be queued.",
+    );
+
+    let response = state.response.lock().take().expect("No response received");
+
+    assert_eq!(
+        response,
+        SyntheticResponse {
+            head: &ResponseHead {
+                status: 503,
+                header_template: ResponseHeaderTemplate::Disconnect,
+            },
+            body: Vec::new(),
+        }
+    );
+
+    TARGET.assert_data_written(&[]);
+    TARGET.assert_no_calls_remaining();
+    logger_guard.expect_logs(&["EPIPE: Broken pipe"]);
+}
+
+//     #
+//    # #    ####   ####  ###### #####  #####    ##### ######  ####  #####  ####
+//   #   #  #    # #    # #      #    #   #        #   #      #        #   #
+//  #     # #      #      #####  #    #   #        #   #####   ####    #    ####
+//  ####### #      #      #      #####    #        #   #           #   #        #
+//  #     # #    # #    # #      #        #        #   #      #    #   #   #    #
+//  #     #  ####   ####  ###### #        #        #   ######  ####    #    ####
+
+#[test]
+fn negotiates_openmetrics_without_accept() {
+    assert_eq!(negotiate_metrics_format(None), MetricsFormat::OpenMetrics);
+}
+
+#[test]
+fn negotiates_openmetrics_for_wildcard() {
+    assert_eq!(
+        negotiate_metrics_format(Some(b"*/*")),
+        MetricsFormat::OpenMetrics
+    );
+}
+
+#[test]
+fn negotiates_openmetrics_for_unsupported_types() {
+    assert_eq!(
+        negotiate_metrics_format(Some(b"application/json, text/html")),
+        MetricsFormat::OpenMetrics
+    );
+}
+
+#[test]
+fn negotiates_prometheus_text() {
+    assert_eq!(
+        negotiate_metrics_format(Some(b"text/plain; version=0.0.4")),
+        MetricsFormat::PrometheusText
+    );
+}
+
+#[test]
+fn negotiates_protobuf() {
+    assert_eq!(
+        negotiate_metrics_format(Some(
+            b"application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;encoding=delimited"
+        )),
+        MetricsFormat::Protobuf
+    );
+}
+
+#[test]
+fn ignores_protobuf_without_delimited_encoding() {
+    assert_eq!(
+        negotiate_metrics_format(Some(
+            b"application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;encoding=text"
+        )),
+        MetricsFormat::OpenMetrics
+    );
+}
+
+#[test]
+fn negotiates_highest_quality() {
+    assert_eq!(
+        negotiate_metrics_format(Some(
+            b"application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;encoding=delimited;q=0.7,text/plain;version=0.0.4;q=0.3,*/*;q=0.1"
+        )),
+        MetricsFormat::Protobuf
+    );
+}
+
+#[test]
+fn negotiates_prometheus_scrape_accept_header() {
+    assert_eq!(
+        negotiate_metrics_format(Some(
+            b"application/openmetrics-text;version=1.0.0,application/openmetrics-text;version=0.0.1;q=0.75,text/plain;version=0.0.4;q=0.5,*/*;q=0.1"
+        )),
+        MetricsFormat::OpenMetrics
+    );
+}
+
+#[test]
+fn negotiates_first_listed_on_ties() {
+    assert_eq!(
+        negotiate_metrics_format(Some(b"text/plain, application/openmetrics-text")),
+        MetricsFormat::PrometheusText
+    );
+}
+
+#[test]
+fn skips_zero_quality() {
+    assert_eq!(
+        negotiate_metrics_format(Some(b"text/plain;q=0, */*;q=0.5")),
+        MetricsFormat::OpenMetrics
+    );
+}
+
+#[test]
+fn skips_invalid_quality() {
+    assert_eq!(
+        negotiate_metrics_format(Some(b"TEXT/PLAIN;q=2, text/*;Q=0.25")),
+        MetricsFormat::PrometheusText
+    );
+}
+
+//   #####
+//  #     #  ####  #    # #####  #####  ######  ####   ####  #  ####  #    #    ##### ######  ####  #####  ####
+//  #       #    # ##  ## #    # #    # #      #      #      # #    # ##   #      #   #      #        #   #
+//  #       #    # # ## # #    # #    # #####   ####   ####  # #    # # #  #      #   #####   ####    #    ####
+//  #       #    # #    # #####  #####  #           #      # # #    # #  # #      #   #           #   #        #
+//  #     # #    # #    # #      #   #  #      #    # #    # # #    # #   ##      #   #      #    #   #   #    #
+//   #####   ####  #    # #      #    # ######  ####   ####  #  ####  #    #      #   ######  ####    #    ####
+
+#[test]
+fn skips_compression_without_accept_encoding() {
+    assert_eq!(negotiate_content_encoding(None), ContentEncoding::Identity);
+}
+
+#[test]
+fn negotiates_gzip() {
+    assert_eq!(
+        negotiate_content_encoding(Some(b"gzip, deflate, br")),
+        ContentEncoding::Gzip
+    );
+}
+
+#[test]
+fn negotiates_x_gzip() {
+    assert_eq!(
+        negotiate_content_encoding(Some(b"X-GZIP")),
+        ContentEncoding::Gzip
+    );
+}
+
+#[test]
+fn negotiates_gzip_for_wildcard() {
+    assert_eq!(
+        negotiate_content_encoding(Some(b"*")),
+        ContentEncoding::Gzip
+    );
+}
+
+#[test]
+fn skips_compression_for_unsupported_encodings() {
+    assert_eq!(
+        negotiate_content_encoding(Some(b"deflate, br")),
+        ContentEncoding::Identity
+    );
+}
+
+#[test]
+fn skips_compression_for_zero_quality_gzip() {
+    assert_eq!(
+        negotiate_content_encoding(Some(b"gzip;q=0, *")),
+        ContentEncoding::Identity
+    );
+}
+
+#[test]
+fn skips_compression_when_identity_is_preferred() {
+    assert_eq!(
+        negotiate_content_encoding(Some(b"gzip;q=0.5, identity")),
+        ContentEncoding::Identity
+    );
+}
+
+#[test]
+fn skips_compression_for_invalid_quality() {
+    assert_eq!(
+        negotiate_content_encoding(Some(b"gzip;q=2")),
+        ContentEncoding::Identity
+    );
+}
+
+#[test]
+fn leaves_small_bodies_uncompressed() {
+    let body = vec![b'a'; COMPRESSION_THRESHOLD - 1];
+    assert_eq!(compress_metrics_body(ContentEncoding::Gzip, &body), None);
+}
+
+#[test]
+fn leaves_identity_bodies_uncompressed() {
+    let body = vec![b'a'; COMPRESSION_THRESHOLD];
+    assert_eq!(
+        compress_metrics_body(ContentEncoding::Identity, &body),
+        None
+    );
+}
+
+#[test]
+fn compresses_large_bodies_with_gzip() {
+    let body = b"journald_entries_ingested_total 123\n".repeat(100);
+    let compressed = compress_metrics_body(ContentEncoding::Gzip, &body).unwrap();
+    assert!(compressed.len() < body.len());
+
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(&*compressed)
+        .read_to_end(&mut decompressed)
+        .unwrap();
+    assert_eq!(decompressed, body);
+}
+
+//  #     #
+//  #     # ###### #      #####  ###### #####   ####
+//  #     # #      #      #    # #      #    # #
+//  ####### #####  #      #    # #####  #    #  ####
+//  #     # #      #      #####  #      #####       #
 //  #     # #      #      #      #      #   #  #    #
 //  #     # ###### ###### #      ###### #    #  ####
 
+#[test]
+fn counts_every_response_status() {
+    for head in [
+        &RESPONSE_OK_OPENMETRICS,
+        &RESPONSE_OK_PROMETHEUS_TEXT,
+        &RESPONSE_OK_PROTOBUF,
+        &RESPONSE_HEALTHY,
+        &RESPONSE_NOT_READY,
+        &RESPONSE_BAD_AUTH_SYNTAX,
+        &RESPONSE_FORBIDDEN,
+        &RESPONSE_THROTTLED,
+        &RESPONSE_METHOD_NOT_ALLOWED,
+        &RESPONSE_NOT_FOUND,
+        &RESPONSE_SERVER_ERROR,
+        &RESPONSE_UNAVAILABLE,
+    ] {
+        assert!(
+            http_response_code_index(head.status).is_some(),
+            "{} isn't counted",
+            head.status
+        );
+    }
+}
+
+fn concat_reports(reports: &[&[u8]]) -> Vec<u8> {
+    reports.concat()
+}
+
 fn make_shared(
     state: &'static ServerState<SyntheticRequestContext>,
     output: &'static WriteSpy,
     keys: &[&[u8]],
 ) -> RequestShared<SyntheticRequestContext, &'static WriteSpy> {
-    let mut guard = state.key_set.write().unwrap_or_else(|e| e.into_inner());
-    *guard = if keys.is_empty() {
+    let key_set = if keys.is_empty() {
         None
     } else {
         Some(KeySet::build(keys))
     };
 
+    make_shared_with_key_set(state, output, key_set)
+}
+
+fn make_shared_with_key_set(
+    state: &'static ServerState<SyntheticRequestContext>,
+    output: &'static WriteSpy,
+    key_set: Option<KeySet>,
+) -> RequestShared<SyntheticRequestContext, &'static WriteSpy> {
+    let mut guard = state.key_set.write().unwrap_or_else(|e| e.into_inner());
+    *guard = key_set;
+
     RequestShared {
         state,
         output,
@@ -530,19 +1845,42 @@ struct SyntheticResponse {
 struct SyntheticRequestState {
     route: Route,
     authorization: Option<&'static [u8]>,
+    metrics_format: MetricsFormat,
+    client_cn: Option<&'static [u8]>,
     received: Instant,
     response: Uncontended<Option<SyntheticResponse>>,
 }
 
 impl SyntheticRequestState {
     fn new(route: Route, authorization: Option<&'static [u8]>) -> Self {
+        Self::with_format(route, authorization, MetricsFormat::OpenMetrics)
+    }
+
+    fn with_format(
+        route: Route,
+        authorization: Option<&'static [u8]>,
+        metrics_format: MetricsFormat,
+    ) -> Self {
         Self {
             route,
             authorization,
+            metrics_format,
+            client_cn: None,
             response: Uncontended::new(None),
             received: Instant::now(),
         }
     }
+
+    fn with_client_cn(
+        route: Route,
+        authorization: Option<&'static [u8]>,
+        client_cn: &'static [u8],
+    ) -> Self {
+        Self {
+            client_cn: Some(client_cn),
+            ..Self::new(route, authorization)
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -564,6 +1902,14 @@ impl RequestContext for SyntheticRequestContext {
     fn peer_addr(&self) -> std::net::Ipv6Addr {
         std::net::Ipv6Addr::LOCALHOST
     }
+
+    fn metrics_format(&self) -> MetricsFormat {
+        self.0.metrics_format
+    }
+
+    fn client_cn(&self) -> Option<&[u8]> {
+        self.0.client_cn
+    }
 }
 
 impl ResponseContext for SyntheticRequestContext {
@@ -1,61 +1,118 @@
 use crate::prelude::*;
 
+use super::request::compress_metrics_body;
+use super::request::negotiate_content_encoding;
+use super::request::negotiate_metrics_format;
 use super::request::ResponseContext;
 use super::request::ResponseHead;
 use super::request::ResponseHeaderTemplate;
 use super::request::Route;
 use super::request::StaticRequestContext;
+use super::tls::TlsConnections;
+use super::tls::TLS_CONNECTION_HEADER;
 use std::net::SocketAddr;
 use tiny_http::Method;
 
+/// Returns `None` if TLS is enabled, but the request didn't come through it. Requests like that can
+/// only come from something connecting to the relay socket directly.
 pub fn build_request_context(
     received: Instant,
     request: &tiny_http::Request,
-) -> StaticRequestContext {
+    tls_connections: Option<&TlsConnections>,
+) -> Option<StaticRequestContext> {
     let mut authorization = None;
-    let peer_addr = match request.remote_addr() {
-        None => unreachable!(),
-        Some(SocketAddr::V4(v4)) => v4.ip().to_ipv6_mapped(),
-        Some(SocketAddr::V6(v6)) => *v6.ip(),
+    let mut accept = None;
+    let (peer_addr, client_cn) = match tls_connections {
+        Some(connections) => {
+            let token = find_header(request, TLS_CONNECTION_HEADER)?;
+            let peer = connections.lookup(token)?;
+            (peer.addr, peer.client_cn)
+        }
+        None => match request.remote_addr() {
+            None => unreachable!(),
+            Some(SocketAddr::V4(v4)) => (v4.ip().to_ipv6_mapped(), None),
+            Some(SocketAddr::V6(v6)) => (*v6.ip(), None),
+        },
     };
 
     for header in request.headers() {
         if header.field.equiv("authorization") {
             authorization = Some(header.value.as_bytes().into());
+        } else if header.field.equiv("accept") {
+            accept = Some(header.value.as_bytes());
         }
     }
 
-    let route = match (request.method(), request.url()) {
-        (Method::Get, "/metrics") => Route::MetricsGet,
-        (Method::Get | Method::Head, _) => Route::InvalidPath,
-        (_, _) => Route::InvalidMethod,
-    };
-
-    StaticRequestContext {
+    Some(StaticRequestContext {
         authorization,
         received,
         peer_addr,
-        route,
+        route: route_request(request.method(), request.url()),
+        metrics_format: negotiate_metrics_format(accept),
+        client_cn,
+    })
+}
+
+// Load balancers commonly probe with `HEAD`, so the health checks accept it too. tiny_http leaves
+// out the body for those on its own.
+fn route_request(method: &Method, url: &str) -> Route {
+    match (method, url) {
+        (Method::Get, "/metrics") => Route::MetricsGet,
+        (Method::Get | Method::Head, "/healthz") => Route::HealthzGet,
+        (Method::Get | Method::Head, "/readyz") => Route::ReadyzGet,
+        (Method::Get | Method::Head, _) => Route::InvalidPath,
+        (_, _) => Route::InvalidMethod,
     }
 }
 
+fn find_header<'a>(request: &'a tiny_http::Request, name: &'static str) -> Option<&'a [u8]> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_bytes())
+}
+
 pub fn respond(request: tiny_http::Request, head: &ResponseHead, body: &[u8]) {
     let status = tiny_http::StatusCode(head.status);
+    let mut compressed = None;
+
+    fn header(name: &[u8], value: &[u8]) -> tiny_http::Header {
+        tiny_http::Header::from_bytes(name, value).unwrap()
+    }
 
-    fn single_header(header: &[u8], value: &[u8]) -> Option<Vec<tiny_http::Header>> {
+    fn single_header(name: &[u8], value: &[u8]) -> Option<Vec<tiny_http::Header>> {
         let mut result = try_new_dynamic_vec(1)?;
-        result.push(tiny_http::Header::from_bytes(header, value).unwrap());
+        result.push(header(name, value));
         Some(result)
     }
 
     let headers = match head.header_template {
         ResponseHeaderTemplate::Empty => Some(Vec::new()),
-        ResponseHeaderTemplate::Metrics => {
-            single_header(b"content-type", b"application/openmetrics-text")
-        }
-        ResponseHeaderTemplate::BadAuthSyntax => {
-            single_header(b"www-authenticate", b"Basic realm=\"metrics\"")
+        ResponseHeaderTemplate::Metrics(format) => {
+            let encoding = negotiate_content_encoding(find_header(&request, "accept-encoding"));
+            compressed = compress_metrics_body(encoding, body);
+
+            try_new_dynamic_vec(3).map(|mut result| {
+                result.push(header(b"content-type", format.content_type()));
+                // Caches need to know the body varies with both negotiated headers, even when it
+                // ends up not being compressed.
+                result.push(header(b"vary", b"accept, accept-encoding"));
+                if compressed.is_some() {
+                    result.push(header(b"content-encoding", encoding.as_header_value()));
+                }
+                result
+            })
         }
+        ResponseHeaderTemplate::Health => try_new_dynamic_vec(2).map(|mut result| {
+            result.push(header(b"content-type", b"text/plain; charset=utf-8"));
+            result.push(header(b"cache-control", b"no-store"));
+            result
+        }),
+        ResponseHeaderTemplate::BadAuthSyntax => single_header(
+            b"www-authenticate",
+            b"Basic realm=\"metrics\", Bearer realm=\"metrics\"",
+        ),
         ResponseHeaderTemplate::MethodNotAllowed => single_header(b"allow", b"GET,HEAD"),
         ResponseHeaderTemplate::Disconnect => single_header(b"connection", b"close"),
     };
@@ -65,6 +122,7 @@ pub fn respond(request: tiny_http::Request, head: &ResponseHead, body: &[u8]) {
         None => std::panic::panic_any("Unable to allocate memory for headers!"),
     };
 
+    let body = compressed.as_deref().unwrap_or(body);
     let response = tiny_http::Response::new(status, headers, body, Some(body.len()), None);
 
     // `tiny_http` ignores client closing errors internally. No need to do it here. :-)
@@ -108,3 +166,90 @@ impl Drop for TinyHttpResponseContext {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::child::tls::TlsPeer;
+    use std::net::Ipv6Addr;
+    use std::os::unix::net::UnixListener;
+    use std::os::unix::net::UnixStream;
+
+    // Each connection is only sent the one request, as `tiny_http` won't read a connection's next
+    // request until the previous one's been responded to.
+    fn send_health_check(
+        server: &tiny_http::Server,
+        path: &std::path::Path,
+        token: &[u8],
+    ) -> tiny_http::Request {
+        let mut stream = UnixStream::connect(path).unwrap();
+        stream
+            .write_all(b"GET /healthz HTTP/1.1\r\nHost: localhost\r\n")
+            .unwrap();
+        if !token.is_empty() {
+            stream
+                .write_all(b"X-Journald-Exporter-Connection: ")
+                .unwrap();
+            stream.write_all(token).unwrap();
+            stream.write_all(b"\r\n").unwrap();
+        }
+        stream.write_all(b"Connection: close\r\n\r\n").unwrap();
+        server
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap()
+            .expect("No request received")
+    }
+
+    #[test]
+    fn refuses_requests_not_relayed_from_tls() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("relay.sock");
+        let server =
+            tiny_http::Server::from_listener(UnixListener::bind(&path).unwrap(), None).unwrap();
+        let connections = TlsConnections::new();
+        let peer = TlsPeer {
+            addr: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+            client_cn: Some(b"grafana"[..].into()),
+        };
+
+        let request = send_health_check(&server, &path, b"");
+        assert!(build_request_context(Instant::now(), &request, Some(&connections)).is_none());
+
+        // Like the proxy, the connection's token is registered before anything's sent with it.
+        let token = *b"0123456789ABCDEF0123456789ABCDEF";
+        let _guard = connections.register(token, peer.clone());
+
+        // Another connection being relayed doesn't let ones with other tokens through.
+        let request = send_health_check(&server, &path, b"");
+        assert!(build_request_context(Instant::now(), &request, Some(&connections)).is_none());
+        let request = send_health_check(&server, &path, b"0123456789ABCDEF0123456789ABCDE0");
+        assert!(build_request_context(Instant::now(), &request, Some(&connections)).is_none());
+
+        let request = send_health_check(&server, &path, &token);
+        let context = build_request_context(Instant::now(), &request, Some(&connections)).unwrap();
+        assert_eq!(context.peer_addr, peer.addr);
+        assert_eq!(context.client_cn, peer.client_cn);
+    }
+
+    #[test]
+    fn routes_health_checks_for_get_and_head() {
+        for method in [Method::Get, Method::Head] {
+            assert_eq!(route_request(&method, "/healthz"), Route::HealthzGet);
+            assert_eq!(route_request(&method, "/readyz"), Route::ReadyzGet);
+            assert_eq!(route_request(&method, "/other"), Route::InvalidPath);
+        }
+    }
+
+    #[test]
+    fn routes_metrics_for_get_only() {
+        assert_eq!(route_request(&Method::Get, "/metrics"), Route::MetricsGet);
+        assert_eq!(
+            route_request(&Method::Post, "/metrics"),
+            Route::InvalidMethod
+        );
+        assert_eq!(
+            route_request(&Method::Post, "/healthz"),
+            Route::InvalidMethod
+        );
+    }
+}
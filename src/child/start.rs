@@ -3,21 +3,23 @@ use crate::prelude::*;
 use super::ipc::*;
 use super::request::*;
 use super::server::TinyHttpResponseContext;
+use super::tls::*;
 use super::PENDING_REQUEST_CAPACITY;
 use crate::child::server::build_request_context;
-use crate::child::server::respond;
 use crate::ffi::set_non_blocking;
 use crate::ffi::ExitCode;
 use crate::ffi::ExitResult;
 use std::net::Ipv4Addr;
 use std::net::TcpListener;
 use std::num::NonZeroU16;
+use std::num::NonZeroU32;
 use std::os::unix::prelude::OsStrExt;
 use std::os::unix::prelude::OsStringExt;
 
 static SERVER_STATE: ServerState<TinyHttpResponseContext> = ServerState::new();
 static REQUEST_CHANNEL: Channel<(Instant, tiny_http::Request), PENDING_REQUEST_CAPACITY> =
     Channel::new();
+static TLS_CONNECTIONS: TlsConnections = TlsConnections::new();
 
 fn get_port() -> Option<NonZeroU16> {
     let result = std::env::var_os("PORT")?;
@@ -25,6 +27,181 @@ fn get_port() -> Option<NonZeroU16> {
     NonZeroU16::new(u16::try_from(port_num).ok()?)
 }
 
+fn get_tls_policy() -> io::Result<TlsPolicy> {
+    let mut policy = TlsPolicy::default();
+
+    if let Some(version) = std::env::var_os("TLS_MIN_VERSION") {
+        match TlsVersion::from_label(version.as_bytes()) {
+            Some(version) => policy.min_version = version,
+            None => return Err(error!("Minimum TLS version is invalid.")),
+        }
+    }
+
+    if let Some(version) = std::env::var_os("TLS_MAX_VERSION") {
+        match TlsVersion::from_label(version.as_bytes()) {
+            Some(version) => policy.max_version = version,
+            None => return Err(error!("Maximum TLS version is invalid.")),
+        }
+    }
+
+    if let Some(list) = std::env::var_os("TLS_CIPHER_SUITES") {
+        match TlsPolicy::parse_cipher_suites(list.as_bytes()) {
+            Some(cipher_suites) => policy.cipher_suites = cipher_suites,
+            None => return Err(error!("TLS cipher suites are invalid.")),
+        }
+    }
+
+    policy.session_tickets = std::env::var_os("TLS_SESSION_TICKETS").is_some();
+
+    Ok(policy)
+}
+
+fn get_rate_limit(rate: &str, burst: &str) -> io::Result<Option<RateLimit>> {
+    let Some(rate) = std::env::var_os(rate) else {
+        return Ok(None);
+    };
+
+    let Some((count, seconds)) = RateLimit::parse_rate(rate.as_bytes()) else {
+        return Err(error!("Rate limit is invalid."));
+    };
+
+    let burst = match std::env::var_os(burst) {
+        None => NonZeroU32::MIN,
+        Some(burst) => match parse_u32(burst.as_bytes()).and_then(NonZeroU32::new) {
+            Some(burst) => burst,
+            None => return Err(error!("Rate limit burst is invalid.")),
+        },
+    };
+
+    Ok(Some(RateLimit {
+        count,
+        seconds,
+        burst,
+    }))
+}
+
+fn get_prefix_len(name: &str, max: u8) -> io::Result<Option<u8>> {
+    match std::env::var_os(name) {
+        None => Ok(None),
+        Some(len) => match parse_u32(len.as_bytes()).and_then(|len| u8::try_from(len).ok()) {
+            Some(len) if (1..=max).contains(&len) => Ok(Some(len)),
+            _ => Err(error!("Rate limit prefix length is invalid.")),
+        },
+    }
+}
+
+fn get_nonzero(name: &str) -> io::Result<Option<NonZeroU32>> {
+    match std::env::var_os(name) {
+        None => Ok(None),
+        Some(value) => match parse_u32(value.as_bytes()).and_then(NonZeroU32::new) {
+            Some(value) => Ok(Some(value)),
+            None => Err(error!("Auth lockout setting is invalid.")),
+        },
+    }
+}
+
+fn get_rate_limit_policy() -> io::Result<RateLimitPolicy> {
+    let mut policy = RateLimitPolicy::new();
+
+    if let Some(limit) = get_rate_limit("RATE_LIMIT", "RATE_LIMIT_BURST")? {
+        policy.address = limit;
+    }
+
+    if let Some(len) = get_prefix_len("RATE_LIMIT_IPV4_PREFIX", 32)? {
+        policy.ipv4_prefix_len = len;
+    }
+
+    if let Some(len) = get_prefix_len("RATE_LIMIT_IPV6_PREFIX", 128)? {
+        policy.ipv6_prefix_len = len;
+    }
+
+    policy.key = get_rate_limit("KEY_RATE_LIMIT", "KEY_RATE_LIMIT_BURST")?;
+
+    if let Some(list) = std::env::var_os("RATE_LIMIT_EXEMPT") {
+        match RateLimitPolicy::parse_exempt(list.as_bytes()) {
+            Some(exempt) => policy.exempt = exempt,
+            None => return Err(error!("Rate limit exemptions are invalid.")),
+        }
+    }
+
+    let lockout = &mut policy.lockout;
+    if let Some(failures) = get_nonzero("AUTH_LOCKOUT_FAILURES")? {
+        lockout.failures = failures;
+    }
+    if let Some(window) = get_nonzero("AUTH_LOCKOUT_WINDOW")? {
+        lockout.window = window;
+    }
+    if let Some(duration) = get_nonzero("AUTH_LOCKOUT_DURATION")? {
+        lockout.duration = duration;
+    }
+    if let Some(max_duration) = get_nonzero("AUTH_LOCKOUT_MAX_DURATION")? {
+        lockout.max_duration = max_duration;
+    }
+
+    Ok(policy)
+}
+
+fn get_ocsp_response() -> io::Result<Option<Vec<u8>>> {
+    use base64::engine::general_purpose::STANDARD as ENGINE;
+    use base64::engine::Engine as _;
+
+    match std::env::var_os("TLS_OCSP_RESPONSE") {
+        None => Ok(None),
+        Some(encoded) => match ENGINE.decode(encoded.as_bytes()) {
+            Ok(ocsp_response) => Ok(Some(ocsp_response)),
+            Err(_) => Err(error!("OCSP response is not valid base64.")),
+        },
+    }
+}
+
+fn get_tls_config() -> io::Result<Option<Arc<rustls::ServerConfig>>> {
+    let client_auth = match (
+        std::env::var_os("TLS_CLIENT_CA"),
+        std::env::var_os("TLS_CLIENT_AUTH"),
+    ) {
+        (Some(ca), Some(mode)) => Some(TlsClientAuth {
+            ca: ca.into_vec(),
+            required: match mode.as_bytes() {
+                b"require" => true,
+                b"optional" => false,
+                _ => return Err(error!("Client auth mode is invalid.")),
+            },
+        }),
+        (None, None) => None,
+        (None, Some(_)) => return Err(error!("Received client auth mode but not client CA.")),
+        (Some(_), None) => return Err(error!("Received client CA but not client auth mode.")),
+    };
+
+    match (
+        std::env::var_os("TLS_CERTIFICATE"),
+        std::env::var_os("TLS_PRIVATE_KEY"),
+    ) {
+        (Some(certificate), Some(private_key)) => {
+            let ocsp_response = get_ocsp_response()?;
+            SERVER_STATE.tls_certificate.replace(
+                certificate.as_bytes(),
+                private_key.as_bytes(),
+                ocsp_response.as_deref(),
+            )?;
+            Ok(Some(build_server_config(
+                &SERVER_STATE.tls_certificate,
+                client_auth,
+                &get_tls_policy()?,
+            )?))
+        }
+        (None, None) if client_auth.is_some() => {
+            Err(error!("Received client CA but not certificate."))
+        }
+        (None, None) => Ok(None),
+        (None, Some(_)) => Err(error!("Received private key but not certificate.")),
+        (Some(_), None) => Err(error!("Received certificate but not private key.")),
+    }
+}
+
+fn report_tls_handshake(suite: TlsCipherSuite) {
+    queue_report(&SERVER_STATE, &ipc::child::tls_handshake_bytes(suite));
+}
+
 pub fn start_child() -> io::Result<ExitResult> {
     // Set the standard input and output to non-blocking mode so reads will correctly not block.
     set_non_blocking(libc::STDIN_FILENO);
@@ -44,18 +221,12 @@ pub fn start_child() -> io::Result<ExitResult> {
         None => return Err(error!("Port is invalid or missing.")),
     };
 
-    let tls = match (
-        std::env::var_os("TLS_CERTIFICATE"),
-        std::env::var_os("TLS_PRIVATE_KEY"),
-    ) {
-        (Some(certificate), Some(private_key)) => Some(tiny_http::SslConfig {
-            certificate: certificate.into_vec(),
-            private_key: private_key.into_vec(),
-        }),
-        (None, None) => None,
-        (None, Some(_)) => return Err(error!("Received private key but not certificate.")),
-        (Some(_), None) => return Err(error!("Received certificate but not private key.")),
-    };
+    let tls = get_tls_config()?;
+
+    SERVER_STATE
+        .limiter
+        .lock()
+        .set_policy(get_rate_limit_policy()?);
 
     let listener = match TcpListener::bind((Ipv4Addr::UNSPECIFIED, port.into())) {
         Ok(listener) => listener,
@@ -70,7 +241,20 @@ pub fn start_child() -> io::Result<ExitResult> {
 
     log::info!("Server listener bound at port {port}.");
 
-    let server = match tiny_http::Server::from_listener(listener, tls) {
+    // With TLS, the server only sees what the proxy relays to it.
+    // The relay directory has to outlive the server, so it's kept here until everything's joined.
+    let (listener, tls_proxy, _relay_dir) = match tls {
+        None => (tiny_http::Listener::from(listener), None, None),
+        Some(config) => {
+            let (proxy, relay_listener, relay_dir) =
+                TlsProxy::new(listener, config, report_tls_handshake)?;
+            (relay_listener.into(), Some(proxy), Some(relay_dir))
+        }
+    };
+
+    let tls_connections = tls_proxy.as_ref().map(|_| &TLS_CONNECTIONS);
+
+    let server = match tiny_http::Server::from_listener(listener, None) {
         Ok(server) => server,
         Err(e) => return Err(Error::new(ErrorKind::Other, e)),
     };
@@ -94,8 +278,9 @@ pub fn start_child() -> io::Result<ExitResult> {
     }
 
     // Spawn all the threads
-    let handle_request_handle = ThreadHandle::spawn(handle_request_task(shared));
+    let handle_request_handle = ThreadHandle::spawn(handle_request_task(shared, tls_connections));
     let server_recv_handle = ThreadHandle::spawn(server_recv_task(server));
+    let tls_proxy_handle = tls_proxy.map(|proxy| ThreadHandle::spawn(tls_proxy_task(proxy)));
 
     log::info!("Child IPC ready.");
 
@@ -105,9 +290,11 @@ pub fn start_child() -> io::Result<ExitResult> {
     // Wait for everything else to settle
     let handle_request_result = handle_request_handle.join();
     let server_recv_result = server_recv_handle.join();
+    let tls_proxy_result = tls_proxy_handle.map_or(Ok(()), |handle| handle.join());
     // And now wire up errors and return.
     handle_request_result?;
     server_recv_result?;
+    tls_proxy_result?;
     child_ipc_result?;
 
     Ok(ExitResult::Code(ExitCode(1)))
@@ -122,7 +309,8 @@ fn server_recv_task(server: tiny_http::Server) -> impl FnOnce() -> io::Result<()
         while !REQUEST_CHANNEL.has_closed() {
             if let Some(request) = server.recv_timeout(Duration::from_secs(1))? {
                 if let Err((_, request)) = REQUEST_CHANNEL.send((Instant::now(), request)) {
-                    respond(request, &RESPONSE_UNAVAILABLE, &[]);
+                    let response = TinyHttpResponseContext::new(request);
+                    SERVER_STATE.respond(response, &RESPONSE_UNAVAILABLE, &[]);
                 }
             }
         }
@@ -131,8 +319,23 @@ fn server_recv_task(server: tiny_http::Server) -> impl FnOnce() -> io::Result<()
     }
 }
 
+fn tls_proxy_task(proxy: TlsProxy) -> impl FnOnce() -> io::Result<()> + Send {
+    move || {
+        let _guard = REQUEST_CHANNEL.close_guard();
+
+        log::info!("TLS proxy accepting connections.");
+
+        while !REQUEST_CHANNEL.has_closed() {
+            proxy.accept_timeout(&TLS_CONNECTIONS, Duration::from_secs(1))?;
+        }
+
+        Ok(())
+    }
+}
+
 fn handle_request_task(
     shared: RequestShared<TinyHttpResponseContext, std::io::Stdout>,
+    tls_connections: Option<&'static TlsConnections>,
 ) -> impl FnOnce() -> io::Result<()> + Send {
     move || {
         let _guard = REQUEST_CHANNEL.close_guard();
@@ -144,15 +347,17 @@ fn handle_request_task(
             let mut duration = Instant::now().saturating_duration_since(shared.initialized);
             if duration.as_secs() >= target {
                 target = duration.as_secs().wrapping_add(1);
+                SERVER_STATE.limiter.lock().reap(duration);
                 duration = Duration::from_secs(1);
-                SERVER_STATE.limiter.lock().reap(target);
             }
 
             if let Some(requests) = REQUEST_CHANNEL.read_timeout(duration) {
                 for (received, request) in requests.into_iter() {
                     let response = TinyHttpResponseContext::new(request);
-                    let request = build_request_context(received, response.inner());
-                    handle_request(request, response, &shared);
+                    match build_request_context(received, response.inner(), tls_connections) {
+                        Some(request) => handle_request(request, response, &shared),
+                        None => SERVER_STATE.respond(response, &RESPONSE_FORBIDDEN, &[]),
+                    }
                 }
             }
         }
@@ -0,0 +1,983 @@
+//! TLS is terminated here rather than in `tiny_http`, as it can neither verify client certificates
+//! nor tell the request handler who presented one. Each TLS connection is instead decrypted and
+//! relayed to a plain HTTP `tiny_http` server over a Unix socket, in a directory only this user can
+//! get into, so there's no relay port anything else on this host could connect to.
+//!
+//! `tiny_http` doesn't report anything about where requests over a Unix socket came from, so each
+//! relayed request is tagged with a header carrying its connection's token, and that's what ties
+//! it back to the original peer and its verified certificate. Tokens are random and never sent to
+//! clients, and requests that already have that header are refused before anything's relayed, so
+//! clients can't claim another connection's peer. Requests without a registered token never get as
+//! far as authorization.
+
+use crate::prelude::*;
+
+use super::PENDING_REQUEST_CAPACITY;
+use crate::ffi::create_private_temp_dir;
+use crate::ffi::poll_pair;
+use crate::ffi::PollFlags;
+use crate::ffi::PollResult;
+use crate::ffi::Pollable;
+use ring::rand::SecureRandom;
+use rustls::sign::CertifiedKey;
+use std::net::Ipv6Addr;
+use std::net::Shutdown;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+// Each connection gets its own thread, so this is what keeps a flood of them from exhausting the
+// process. Scrapers don't need anywhere near this many at once.
+const MAX_TLS_CONNECTIONS: usize = PENDING_REQUEST_CAPACITY;
+// Long enough for any real client, short enough that stalled handshakes don't hold onto slots.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const RELAY_BUFFER_LEN: usize = 16384;
+// `tiny_http` doesn't limit request heads itself, and no scraper sends anything close to this.
+const MAX_REQUEST_HEAD_LEN: usize = 16384;
+
+/// The header relayed requests are tagged with. It's checked case-insensitively, like any other.
+pub const TLS_CONNECTION_HEADER: &str = "X-Journald-Exporter-Connection";
+
+// 128 random bits, in hex.
+pub type TlsConnectionToken = [u8; 32];
+
+pub struct TlsClientAuth {
+    // The PEM-encoded CA certificates client certificates are verified against.
+    pub ca: Vec<u8>,
+    // If not set, clients may still connect without a certificate, and then need to authorize
+    // with a key like usual.
+    pub required: bool,
+}
+
+/// The certificate and private key currently served, along with any OCSP response stapled to it.
+/// It's swapped out whenever the parent sends a new pair, so new connections pick it up without the
+/// listener having to be reopened.
+pub struct TlsCertificate {
+    current: RwLock<Option<Arc<CertifiedKey>>>,
+}
+
+impl TlsCertificate {
+    pub const fn new() -> Self {
+        Self {
+            current: RwLock::new(None),
+        }
+    }
+
+    /// Replaces the current pair if the new one is valid, and otherwise leaves it alone.
+    pub fn replace(
+        &self,
+        certificate: &[u8],
+        private_key: &[u8],
+        ocsp_response: Option<&[u8]>,
+    ) -> io::Result<()> {
+        let key = Arc::new(parse_certified_key(
+            certificate,
+            private_key,
+            ocsp_response,
+        )?);
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Some(key);
+        Ok(())
+    }
+
+    fn get(&self) -> Option<Arc<CertifiedKey>> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+struct CertificateResolver(&'static TlsCertificate);
+
+impl rustls::server::ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, _: rustls::server::ClientHello) -> Option<Arc<CertifiedKey>> {
+        self.0.get()
+    }
+}
+
+fn rustls_cipher_suite(suite: TlsCipherSuite) -> Option<rustls::SupportedCipherSuite> {
+    rustls::ALL_CIPHER_SUITES
+        .iter()
+        .copied()
+        .find(|s| s.suite().get_u16() == suite.code())
+}
+
+fn rustls_version(version: TlsVersion) -> &'static rustls::SupportedProtocolVersion {
+    match version {
+        TlsVersion::Tls12 => &rustls::version::TLS12,
+        TlsVersion::Tls13 => &rustls::version::TLS13,
+    }
+}
+
+pub fn build_server_config(
+    certificate: &'static TlsCertificate,
+    client_auth: Option<TlsClientAuth>,
+    policy: &TlsPolicy,
+) -> io::Result<Arc<rustls::ServerConfig>> {
+    let cipher_suites = Vec::from_iter(
+        policy
+            .cipher_suites
+            .iter()
+            .filter_map(|&suite| rustls_cipher_suite(suite)),
+    );
+    let versions = Vec::from_iter(policy.versions().map(rustls_version));
+
+    let builder = match rustls::ServerConfig::builder()
+        .with_cipher_suites(&cipher_suites)
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(&versions)
+    {
+        Ok(builder) => builder,
+        Err(e) => return Err(error!("Invalid TLS policy: {e}")),
+    };
+
+    let builder = match client_auth {
+        None => builder.with_no_client_auth(),
+        Some(client_auth) => {
+            let mut roots = rustls::RootCertStore::empty();
+            for certificate in parse_pem_certificates(&client_auth.ca, "client CA file")? {
+                if let Err(e) = roots.add(&certificate) {
+                    return Err(error!("Invalid client CA certificate: {e}"));
+                }
+            }
+
+            let verifier = if client_auth.required {
+                rustls::server::AllowAnyAuthenticatedClient::new(roots).boxed()
+            } else {
+                rustls::server::AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed()
+            };
+
+            builder.with_client_cert_verifier(verifier)
+        }
+    };
+
+    let resolver = Arc::new(CertificateResolver(certificate));
+    let mut config = builder.with_cert_resolver(resolver);
+
+    // Only HTTP/1.1 is served, and advertising it lets clients that check ALPN know that upfront.
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    if policy.session_tickets {
+        config.ticketer = match rustls::Ticketer::new() {
+            Ok(ticketer) => ticketer,
+            Err(e) => return Err(error!("Could not create TLS session ticketer: {e}")),
+        };
+    }
+
+    Ok(Arc::new(config))
+}
+
+// id-at-commonName, 2.5.4.3
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+/// Extracts the subject common name from a DER-encoded X.509 certificate. The certificate itself is
+/// parsed by `webpki`, just as `rustls` did when verifying it, so only the subject it hands back is
+/// searched here. Only the string types that are ASCII-compatible are accepted, as the name is
+/// compared byte for byte against key files.
+pub fn client_cn_from_der(der: &[u8]) -> Option<&[u8]> {
+    let mut subject = webpki::TrustAnchor::try_from_cert_der(der).ok()?.subject;
+
+    while !subject.is_empty() {
+        let (mut rdn, next) = split_der(subject, DER_SET)?;
+        subject = next;
+
+        while !rdn.is_empty() {
+            let (attribute, next) = split_der(rdn, DER_SEQUENCE)?;
+            rdn = next;
+
+            let (oid, value) = split_der(attribute, DER_OID)?;
+            if oid == OID_COMMON_NAME {
+                // UTF8String, PrintableString, T61String, and IA5String respectively.
+                return match split_any_der(value)? {
+                    (0x0C | 0x13 | 0x14 | 0x16, value, _) => Some(value),
+                    _ => None,
+                };
+            }
+        }
+    }
+
+    None
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsPeer {
+    pub addr: Ipv6Addr,
+    pub client_cn: Option<Box<[u8]>>,
+}
+
+/// The TLS connections currently being relayed, by the token their requests are tagged with.
+pub struct TlsConnections {
+    // There's never more than `MAX_TLS_CONNECTIONS` of these, so a list is plenty fast.
+    entries: Mutex<Vec<(TlsConnectionToken, TlsPeer)>>,
+}
+
+pub struct TlsConnectionGuard<'a> {
+    connections: &'a TlsConnections,
+    token: TlsConnectionToken,
+}
+
+impl Drop for TlsConnectionGuard<'_> {
+    fn drop(&mut self) {
+        let mut entries = self
+            .connections
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        entries.retain(|(token, _)| *token != self.token);
+    }
+}
+
+impl TlsConnections {
+    pub const fn new() -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Registers a relayed connection until the returned guard is dropped. This must be done
+    /// before anything is sent over it, so its requests can always be found.
+    pub fn register(&self, token: TlsConnectionToken, peer: TlsPeer) -> TlsConnectionGuard<'_> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        // Tokens are random, so they can't realistically collide, but be defensive about it.
+        entries.retain(|(t, _)| *t != token);
+        entries.push((token, peer));
+        TlsConnectionGuard {
+            connections: self,
+            token,
+        }
+    }
+
+    /// Returns `None` if no relayed connection has this token, in which case the request didn't
+    /// come through the proxy.
+    pub fn lookup(&self, token: &[u8]) -> Option<TlsPeer> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .iter()
+            .find(|(t, _)| t[..] == *token)
+            .map(|(_, peer)| peer.clone())
+    }
+}
+
+fn generate_token() -> io::Result<TlsConnectionToken> {
+    let mut bytes = [0; 16];
+    if ring::rand::SystemRandom::new().fill(&mut bytes).is_err() {
+        return Err(error!("Could not generate TLS connection token."));
+    }
+
+    let mut token = [0; 32];
+    for (pair, byte) in token.chunks_exact_mut(2).zip(bytes) {
+        pair.copy_from_slice(&to_hex_pair(byte));
+    }
+    Ok(token)
+}
+
+/// The private directory the relay socket is in. It's removed once this is dropped, which has to
+/// wait until the `tiny_http` server is gone, as that connects to the socket to shut down.
+pub struct RelaySocketDir {
+    path: PathBuf,
+}
+
+impl RelaySocketDir {
+    fn socket_path(&self) -> PathBuf {
+        self.path.join("relay.sock")
+    }
+}
+
+impl Drop for RelaySocketDir {
+    fn drop(&mut self) {
+        // The server usually removes its socket itself, so this is just a fallback.
+        drop(std::fs::remove_file(self.socket_path()));
+        drop(std::fs::remove_dir(&self.path));
+    }
+}
+
+pub struct TlsProxy {
+    listener: TcpListener,
+    relay_path: PathBuf,
+    config: Arc<rustls::ServerConfig>,
+    active: Arc<AtomicUsize>,
+    on_handshake: fn(TlsCipherSuite),
+}
+
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn to_ipv6(addr: SocketAddr) -> Ipv6Addr {
+    match addr {
+        SocketAddr::V4(v4) => v4.ip().to_ipv6_mapped(),
+        SocketAddr::V6(v6) => *v6.ip(),
+    }
+}
+
+impl TlsProxy {
+    /// Returns the proxy along with the Unix listener it relays to, for the HTTP server, and the
+    /// directory that listener's in.
+    /// `on_handshake` is called with the negotiated cipher suite after each completed handshake.
+    pub fn new(
+        listener: TcpListener,
+        config: Arc<rustls::ServerConfig>,
+        on_handshake: fn(TlsCipherSuite),
+    ) -> io::Result<(Self, UnixListener, RelaySocketDir)> {
+        let relay_dir = RelaySocketDir {
+            path: create_private_temp_dir("journald-exporter")?,
+        };
+        let relay_path = relay_dir.socket_path();
+        let relay_listener = UnixListener::bind(&relay_path)?;
+        listener.set_nonblocking(true)?;
+
+        let proxy = Self {
+            listener,
+            relay_path,
+            config,
+            active: Arc::new(AtomicUsize::new(0)),
+            on_handshake,
+        };
+
+        Ok((proxy, relay_listener, relay_dir))
+    }
+
+    /// Accepts a connection and starts relaying it in the background, or returns early if none
+    /// arrived within `timeout`.
+    pub fn accept_timeout(
+        &self,
+        connections: &'static TlsConnections,
+        timeout: Duration,
+    ) -> io::Result<()> {
+        match self.listener.poll(PollFlags::IN, Some(timeout)) {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::TimedOut => return Ok(()),
+            Err(e) => return Err(e),
+        }
+
+        let (client, peer_addr) = match self.listener.accept() {
+            Ok(accepted) => accepted,
+            // The connection was dropped before it could be accepted.
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+            Err(e) if e.raw_os_error() == Some(libc::ECONNABORTED) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        if self.active.fetch_add(1, Ordering::AcqRel) >= MAX_TLS_CONNECTIONS {
+            drop(ConnectionSlot(self.active.clone()));
+            return Ok(());
+        }
+
+        let slot = ConnectionSlot(self.active.clone());
+        let config = self.config.clone();
+        let relay_path = self.relay_path.clone();
+        let on_handshake = self.on_handshake;
+
+        // Errors here are just the client misbehaving or going away, and `tiny_http` ignores
+        // those all the same.
+        std::thread::spawn(move || {
+            let _slot = slot;
+            drop(relay_connection(
+                client,
+                peer_addr,
+                &relay_path,
+                config,
+                connections,
+                on_handshake,
+            ));
+        });
+
+        Ok(())
+    }
+}
+
+fn to_io_error(e: rustls::Error) -> Error {
+    Error::new(ErrorKind::InvalidData, e)
+}
+
+fn flush_tls(tls: &mut rustls::ServerConnection, client: &mut TcpStream) -> io::Result<()> {
+    while tls.wants_write() {
+        tls.write_tls(client)?;
+    }
+    Ok(())
+}
+
+fn relay_connection(
+    mut client: TcpStream,
+    peer_addr: SocketAddr,
+    relay_path: &Path,
+    config: Arc<rustls::ServerConfig>,
+    connections: &TlsConnections,
+    on_handshake: fn(TlsCipherSuite),
+) -> io::Result<()> {
+    client.set_nonblocking(false)?;
+    client.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    client.set_write_timeout(Some(IDLE_TIMEOUT))?;
+
+    let mut tls = rustls::ServerConnection::new(config).map_err(to_io_error)?;
+
+    while tls.is_handshaking() {
+        tls.complete_io(&mut client)?;
+    }
+
+    if let Some(suite) = tls
+        .negotiated_cipher_suite()
+        .and_then(|suite| TlsCipherSuite::from_code(suite.suite().get_u16()))
+    {
+        on_handshake(suite);
+    }
+
+    let client_cn = tls
+        .peer_certificates()
+        .and_then(|certificates| certificates.first())
+        .and_then(|certificate| client_cn_from_der(&certificate.0))
+        .map(Box::from);
+
+    let token = generate_token()?;
+    let _guard = connections.register(
+        token,
+        TlsPeer {
+            addr: to_ipv6(peer_addr),
+            client_cn,
+        },
+    );
+
+    let mut server = UnixStream::connect(relay_path)?;
+    let mut tagger = RequestTagger::new(&token);
+    relay(&mut tls, &mut client, &mut server, &mut tagger)
+}
+
+fn relay(
+    tls: &mut rustls::ServerConnection,
+    client: &mut TcpStream,
+    server: &mut UnixStream,
+    tagger: &mut RequestTagger,
+) -> io::Result<()> {
+    let mut buf = [0; RELAY_BUFFER_LEN];
+    let mut client_open = true;
+
+    loop {
+        flush_tls(tls, client)?;
+
+        // Once the client's done sending, stop polling it, so its hangup doesn't spin this.
+        let client_fd = if client_open { client.as_raw_fd() } else { -1 };
+        let (client_ready, server_ready) = match poll_pair(
+            (client_fd, PollFlags::IN),
+            (server.as_raw_fd(), PollFlags::IN),
+            Some(IDLE_TIMEOUT),
+        ) {
+            Ok(ready) => ready,
+            Err(e) if e.kind() == ErrorKind::TimedOut => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        if is_readable(client_ready) {
+            if tls.read_tls(client)? == 0 {
+                client_open = false;
+            } else if let Err(e) = tls.process_new_packets() {
+                // Let the client know why before hanging up.
+                drop(flush_tls(tls, client));
+                return Err(to_io_error(e));
+            }
+
+            loop {
+                match tls.reader().read(&mut buf) {
+                    Ok(0) => {
+                        client_open = false;
+                        break;
+                    }
+                    Ok(len) => tagger.relay(&buf[..len], server)?,
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                        client_open = false;
+                        break;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+
+            if !client_open {
+                // Let the server finish responding to whatever it was already sent.
+                drop(server.shutdown(Shutdown::Write));
+            }
+        }
+
+        if is_readable(server_ready) {
+            let len = server.read(&mut buf)?;
+            if len == 0 {
+                tls.send_close_notify();
+                return flush_tls(tls, client);
+            }
+
+            // This only buffers so much at a time, so it's flushed as it goes.
+            let mut data = &buf[..len];
+            while !data.is_empty() {
+                let written = tls.writer().write(data)?;
+                data = &data[written..];
+                flush_tls(tls, client)?;
+            }
+        }
+    }
+}
+
+/// Tags each request relayed to the server with its connection's token, right after the request
+/// line. Bodies are passed through as-is, and only ever with `Content-Length`, as nothing served
+/// here needs a body in the first place.
+struct RequestTagger {
+    // The line inserted after each request line, including the line break ending the request line.
+    tag_line: Vec<u8>,
+    pending: Vec<u8>,
+    body_remaining: usize,
+}
+
+fn refuse_request(reason: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, reason)
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+impl RequestTagger {
+    fn new(token: &TlsConnectionToken) -> Self {
+        let mut tag_line = Vec::new();
+        tag_line.extend_from_slice(b"\r\n");
+        tag_line.extend_from_slice(TLS_CONNECTION_HEADER.as_bytes());
+        tag_line.extend_from_slice(b": ");
+        tag_line.extend_from_slice(token);
+
+        Self {
+            tag_line,
+            pending: Vec::new(),
+            body_remaining: 0,
+        }
+    }
+
+    fn relay(&mut self, data: &[u8], server: &mut impl Write) -> io::Result<()> {
+        self.pending.extend_from_slice(data);
+
+        let mut start = 0;
+        let result = loop {
+            let rest = &self.pending[start..];
+
+            if rest.is_empty() {
+                break Ok(());
+            }
+
+            if self.body_remaining > 0 {
+                let len = rest.len().min(self.body_remaining);
+                if let Err(e) = server.write_all(&rest[..len]) {
+                    break Err(e);
+                }
+                self.body_remaining = self.body_remaining.wrapping_sub(len);
+                start = start.wrapping_add(len);
+                continue;
+            }
+
+            let Some(end) = find_subslice(rest, b"\r\n\r\n") else {
+                if rest.len() > MAX_REQUEST_HEAD_LEN {
+                    break Err(refuse_request("Request head too long."));
+                }
+                break Ok(());
+            };
+
+            let (head, _) = rest.split_at(end.wrapping_add(4));
+            match self.tag_request_head(head) {
+                Ok((tagged, body_len)) => {
+                    if let Err(e) = server.write_all(&tagged) {
+                        break Err(e);
+                    }
+                    self.body_remaining = body_len;
+                    start = start.wrapping_add(head.len());
+                }
+                Err(e) => break Err(e),
+            }
+        };
+
+        self.pending.drain(..start);
+        result
+    }
+
+    // Returns the tagged head along with the length of the body following it.
+    fn tag_request_head(&self, head: &[u8]) -> io::Result<(Vec<u8>, usize)> {
+        // Anywhere at all, so there's no question of whether `tiny_http` would read it as a header.
+        let header = TLS_CONNECTION_HEADER.as_bytes();
+        if head
+            .windows(header.len())
+            .any(|w| w.eq_ignore_ascii_case(header))
+        {
+            return Err(refuse_request("Request already tagged."));
+        }
+
+        let Some(line_end) = find_subslice(head, b"\r\n") else {
+            return Err(refuse_request("Missing request line."));
+        };
+
+        let mut body_len = None;
+
+        for line in head.split(|&b| b == b'\n') {
+            let Some(colon) = line.iter().position(|&b| b == b':') else {
+                continue;
+            };
+            let (name, value) = line.split_at(colon);
+            if name.eq_ignore_ascii_case(b"transfer-encoding") {
+                return Err(refuse_request("Unsupported transfer encoding."));
+            }
+            if name.eq_ignore_ascii_case(b"content-length") {
+                let value = std::str::from_utf8(trim_ascii(value.get(1..).unwrap_or_default()));
+                match (body_len, value.ok().and_then(|v| v.parse::<usize>().ok())) {
+                    (None, Some(len)) => body_len = Some(len),
+                    _ => return Err(refuse_request("Invalid content length.")),
+                }
+            }
+        }
+
+        let (request_line, rest) = head.split_at(line_end);
+        let mut tagged = Vec::with_capacity(head.len().saturating_add(self.tag_line.len()));
+        tagged.extend_from_slice(request_line);
+        tagged.extend_from_slice(&self.tag_line);
+        tagged.extend_from_slice(rest);
+
+        Ok((tagged, body_len.unwrap_or(0)))
+    }
+}
+
+fn is_readable(result: PollResult) -> bool {
+    result.has_in() || result.has_hup() || result.has_err()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn der(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut result = vec![tag];
+        if contents.len() < 0x80 {
+            result.push(u8::try_from(contents.len()).unwrap());
+        } else if contents.len() < 0x100 {
+            result.push(0x81);
+            result.push(u8::try_from(contents.len()).unwrap());
+        } else {
+            result.push(0x82);
+            result.extend_from_slice(&u16::try_from(contents.len()).unwrap().to_be_bytes());
+        }
+        result.extend_from_slice(contents);
+        result
+    }
+
+    fn name(attributes: &[(&[u8], u8, &[u8])]) -> Vec<u8> {
+        let mut rdns = Vec::new();
+        for &(oid, tag, value) in attributes {
+            let mut attribute = der(DER_OID, oid);
+            attribute.extend_from_slice(&der(tag, value));
+            rdns.extend_from_slice(&der(DER_SET, &der(DER_SEQUENCE, &attribute)));
+        }
+        der(DER_SEQUENCE, &rdns)
+    }
+
+    // The signature's never checked here, so it and the public key are just placeholders.
+    fn certificate(version: bool, subject: &[u8]) -> Vec<u8> {
+        let algorithm = der(DER_SEQUENCE, &der(DER_OID, &[0x2A, 0x86, 0x48]));
+        let mut tbs = Vec::new();
+        if version {
+            tbs.extend_from_slice(&der(DER_EXPLICIT_0, &der(0x02, &[0x02])));
+        }
+        tbs.extend_from_slice(&der(0x02, &[0x01, 0x23]));
+        tbs.extend_from_slice(&algorithm);
+        tbs.extend_from_slice(&name(&[(OID_COMMON_NAME, 0x0C, b"Issuing CA")]));
+        tbs.extend_from_slice(&der(DER_SEQUENCE, &[0x17, 0x00, 0x17, 0x00]));
+        tbs.extend_from_slice(subject);
+        tbs.extend_from_slice(&der(DER_SEQUENCE, &algorithm));
+
+        let mut certificate = der(DER_SEQUENCE, &tbs);
+        certificate.extend_from_slice(&algorithm);
+        certificate.extend_from_slice(&der(DER_BIT_STRING, &[0x00, 0x01]));
+        der(DER_SEQUENCE, &certificate)
+    }
+
+    const OID_ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0A];
+
+    #[test]
+    fn finds_subject_common_name_and_not_issuer() {
+        let cert = certificate(
+            true,
+            &name(&[
+                (OID_ORGANIZATION, 0x0C, b"Example"),
+                (OID_COMMON_NAME, 0x13, b"Grafana Agent"),
+            ]),
+        );
+        assert_eq!(client_cn_from_der(&cert), Some(&b"Grafana Agent"[..]));
+    }
+
+    #[test]
+    fn finds_common_name_in_real_certificate() {
+        let certificates = parse_pem_certificates(TEST_CERTIFICATE, "test certificate").unwrap();
+        assert_eq!(client_cn_from_der(&certificates[0].0), Some(&b"test"[..]));
+    }
+
+    #[test]
+    fn finds_common_name_in_v1_certificate() {
+        let cert = certificate(false, &name(&[(OID_COMMON_NAME, 0x0C, b"grafana")]));
+        assert_eq!(client_cn_from_der(&cert), Some(&b"grafana"[..]));
+    }
+
+    #[test]
+    fn finds_common_name_with_long_length() {
+        let cn = [b'a'; 200];
+        let cert = certificate(true, &name(&[(OID_COMMON_NAME, 0x0C, &cn)]));
+        assert_eq!(client_cn_from_der(&cert), Some(&cn[..]));
+    }
+
+    #[test]
+    fn finds_nothing_without_subject_common_name() {
+        let cert = certificate(true, &name(&[(OID_ORGANIZATION, 0x0C, b"Example")]));
+        assert_eq!(client_cn_from_der(&cert), None);
+        let cert = certificate(true, &der(DER_SEQUENCE, &[]));
+        assert_eq!(client_cn_from_der(&cert), None);
+    }
+
+    #[test]
+    fn rejects_non_ascii_compatible_common_name() {
+        // BMPString
+        let cert = certificate(true, &name(&[(OID_COMMON_NAME, 0x1E, b"\0a\0b")]));
+        assert_eq!(client_cn_from_der(&cert), None);
+    }
+
+    #[test]
+    fn rejects_truncated_certificate() {
+        let cert = certificate(true, &name(&[(OID_COMMON_NAME, 0x0C, b"grafana")]));
+        for len in 0..cert.len() {
+            assert_eq!(client_cn_from_der(&cert[..len]), None, "{len}");
+        }
+    }
+
+    #[test]
+    fn finds_common_name_in_multi_valued_rdn() {
+        let mut organization = der(DER_OID, OID_ORGANIZATION);
+        organization.extend_from_slice(&der(0x0C, b"Example"));
+        let mut common_name = der(DER_OID, OID_COMMON_NAME);
+        common_name.extend_from_slice(&der(0x0C, b"grafana"));
+        let mut rdn = der(DER_SEQUENCE, &organization);
+        rdn.extend_from_slice(&der(DER_SEQUENCE, &common_name));
+
+        let cert = certificate(true, &der(DER_SEQUENCE, &der(DER_SET, &rdn)));
+        assert_eq!(client_cn_from_der(&cert), Some(&b"grafana"[..]));
+    }
+
+    #[test]
+    fn rejects_nested_sets() {
+        let mut attribute = der(DER_OID, OID_COMMON_NAME);
+        attribute.extend_from_slice(&der(0x0C, b"grafana"));
+        let rdn = der(DER_SET, &der(DER_SEQUENCE, &attribute));
+
+        let cert = certificate(true, &der(DER_SEQUENCE, &der(DER_SET, &rdn)));
+        assert_eq!(client_cn_from_der(&cert), None);
+    }
+
+    #[test]
+    fn rejects_common_name_overrunning_its_attribute() {
+        // The value claims more bytes than its attribute has, but not more than the certificate.
+        let mut attribute = der(DER_OID, OID_COMMON_NAME);
+        attribute.extend_from_slice(&[0x0C, 0x10]);
+        attribute.extend_from_slice(b"grafana");
+        let mut subject = der(DER_SET, &der(DER_SEQUENCE, &attribute));
+        subject.extend_from_slice(&name(&[(OID_ORGANIZATION, 0x0C, b"Example")]));
+
+        let cert = certificate(true, &der(DER_SEQUENCE, &subject));
+        assert_eq!(client_cn_from_der(&cert), None);
+    }
+
+    #[test]
+    fn rejects_non_minimal_common_name_length() {
+        let mut attribute = der(DER_OID, OID_COMMON_NAME);
+        attribute.extend_from_slice(&[0x0C, 0x81, 0x07]);
+        attribute.extend_from_slice(b"grafana");
+        let subject = der(DER_SET, &der(DER_SEQUENCE, &attribute));
+
+        let cert = certificate(true, &der(DER_SEQUENCE, &subject));
+        assert_eq!(client_cn_from_der(&cert), None);
+    }
+
+    #[test]
+    fn rejects_truncated_long_form_lengths() {
+        let cn = [b'a'; 200];
+        let cert = certificate(true, &name(&[(OID_COMMON_NAME, 0x0C, &cn)]));
+        // Drop the common name's contents, leaving the long-form length to run past the end.
+        let cut = cert.len() - cn.len();
+        assert_eq!(client_cn_from_der(&cert[..cut]), None);
+        // And then its length bytes.
+        assert_eq!(client_cn_from_der(&cert[..cut - 1]), None);
+    }
+
+    #[test]
+    fn tracks_registered_connections_until_dropped() {
+        let connections = TlsConnections::new();
+        let peer = TlsPeer {
+            addr: Ipv6Addr::LOCALHOST,
+            client_cn: Some(b"grafana"[..].into()),
+        };
+
+        let token = generate_token().unwrap();
+        let other = generate_token().unwrap();
+        assert_ne!(token, other);
+
+        let guard = connections.register(token, peer.clone());
+        assert_eq!(connections.lookup(&token), Some(peer));
+        assert_eq!(connections.lookup(&other), None);
+        assert_eq!(connections.lookup(&token[..31]), None);
+        assert_eq!(connections.lookup(b""), None);
+        drop(guard);
+        assert_eq!(connections.lookup(&token), None);
+    }
+
+    const TEST_TOKEN: &TlsConnectionToken = b"0123456789ABCDEF0123456789ABCDEF";
+
+    fn tag(chunks: &[&[u8]]) -> (Vec<u8>, io::Result<()>) {
+        let mut tagger = RequestTagger::new(TEST_TOKEN);
+        let mut server = Vec::new();
+        for chunk in chunks {
+            if let Err(e) = tagger.relay(chunk, &mut server) {
+                return (server, Err(e));
+            }
+        }
+        (server, Ok(()))
+    }
+
+    #[test]
+    fn tags_each_pipelined_request() {
+        let (server, result) =
+            tag(&[b"GET /metrics HTTP/1.1\r\nHost: a\r\n\r\nHEAD /healthz HTTP/1.1\r\n\r\n"]);
+        result.unwrap();
+        assert_eq!(
+            BinaryToDebug(&server),
+            BinaryToDebug(
+                b"GET /metrics HTTP/1.1\r\n\
+                X-Journald-Exporter-Connection: 0123456789ABCDEF0123456789ABCDEF\r\n\
+                Host: a\r\n\r\n\
+                HEAD /healthz HTTP/1.1\r\n\
+                X-Journald-Exporter-Connection: 0123456789ABCDEF0123456789ABCDEF\r\n\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn tags_requests_split_across_reads() {
+        let (server, result) = tag(&[b"GET /met", b"rics HTTP/1.1\r", b"\n\r", b"\n"]);
+        result.unwrap();
+        assert_eq!(
+            BinaryToDebug(&server),
+            BinaryToDebug(
+                b"GET /metrics HTTP/1.1\r\n\
+                X-Journald-Exporter-Connection: 0123456789ABCDEF0123456789ABCDEF\r\n\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn relays_bodies_untagged() {
+        let (server, result) = tag(&[
+            b"POST /metrics HTTP/1.1\r\ncontent-LENGTH: 25\r\n\r\nGET /metrics HTTP/1.1\r\n",
+            b"\r\nGET /metrics HTTP/1.1\r\n\r\n",
+        ]);
+        result.unwrap();
+        assert_eq!(
+            BinaryToDebug(&server),
+            BinaryToDebug(
+                b"POST /metrics HTTP/1.1\r\n\
+                X-Journald-Exporter-Connection: 0123456789ABCDEF0123456789ABCDEF\r\n\
+                content-LENGTH: 25\r\n\r\n\
+                GET /metrics HTTP/1.1\r\n\r\n\
+                GET /metrics HTTP/1.1\r\n\
+                X-Journald-Exporter-Connection: 0123456789ABCDEF0123456789ABCDEF\r\n\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn refuses_requests_already_tagged() {
+        for request in [
+            &b"GET /metrics HTTP/1.1\r\nX-Journald-Exporter-Connection: 0123\r\n\r\n"[..],
+            b"GET /metrics HTTP/1.1\r\nx-journald-exporter-connection: 0123\r\n\r\n",
+            b"GET /metrics HTTP/1.1\r\nA: b\nX-Journald-Exporter-Connection: 0123\r\n\r\n",
+        ] {
+            let (server, result) = tag(&[request]);
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+            assert_eq!(server, b"");
+        }
+    }
+
+    #[test]
+    fn refuses_unsupported_bodies() {
+        for request in [
+            &b"POST /metrics HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n"[..],
+            b"POST /metrics HTTP/1.1\r\nContent-Length: 1\r\nContent-Length: 2\r\n\r\n",
+            b"POST /metrics HTTP/1.1\r\nContent-Length: -1\r\n\r\n",
+            b"POST /metrics HTTP/1.1\r\nContent-Length: 1, 1\r\n\r\n",
+        ] {
+            let (server, result) = tag(&[request]);
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+            assert_eq!(server, b"");
+        }
+    }
+
+    #[test]
+    fn refuses_overlong_request_heads() {
+        let (server, result) = tag(&[b"GET /", &[b'a'; MAX_REQUEST_HEAD_LEN]]);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(server, b"");
+    }
+
+    #[test]
+    fn relay_socket_is_private_and_removed_on_drop() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let listener = TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let config = build_server_config(
+            Box::leak(Box::new(TlsCertificate::new())),
+            None,
+            &TlsPolicy::default(),
+        )
+        .unwrap();
+        let (proxy, relay_listener, relay_dir) = TlsProxy::new(listener, config, |_| {}).unwrap();
+
+        let mode = std::fs::metadata(&relay_dir.path)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+        assert_eq!(
+            relay_listener.local_addr().unwrap().as_pathname(),
+            Some(&*proxy.relay_path)
+        );
+
+        let path = relay_dir.path.clone();
+        drop(relay_listener);
+        drop(relay_dir);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn retains_current_certificate_on_invalid_replacement() {
+        let certificate = TlsCertificate::new();
+        certificate
+            .replace(TEST_CERTIFICATE, TEST_PRIVATE_KEY, None)
+            .unwrap();
+        let current = certificate.get().unwrap();
+
+        let error = certificate
+            .replace(TEST_CERTIFICATE, OTHER_PRIVATE_KEY, None)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Private key does not match the certificate."
+        );
+        assert!(Arc::ptr_eq(&certificate.get().unwrap(), &current));
+    }
+}
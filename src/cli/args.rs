@@ -2,6 +2,7 @@ use crate::prelude::*;
 
 use std::ffi::OsString;
 use std::num::NonZeroU16;
+use std::num::NonZeroU32;
 use std::os::unix::prelude::OsStrExt;
 use std::path::PathBuf;
 
@@ -9,23 +10,103 @@ use std::path::PathBuf;
 pub struct TLSOptions {
     pub certificate: PathBuf,
     pub private_key: PathBuf,
+    pub client_auth: Option<ClientAuthOptions>,
+    pub ocsp_response: Option<PathBuf>,
+    pub policy: TlsPolicy,
 }
 
 #[derive(Debug, PartialEq)]
-pub struct ChildArgs {
-    pub port: NonZeroU16,
+pub struct ClientAuthOptions {
+    pub ca: PathBuf,
+    // Otherwise, clients without a certificate are still let through to API key authorization.
+    pub required: bool,
 }
 
 #[derive(Debug, PartialEq)]
-pub struct ParentArgs {
+pub struct ServerOptions {
     pub port: NonZeroU16,
     pub key_dir: PathBuf,
     pub tls: Option<TLSOptions>,
+    pub rate_limit: RateLimitPolicy,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PushAuthOptions {
+    Basic {
+        username: String,
+        password_file: PathBuf,
+    },
+    Bearer {
+        token_file: PathBuf,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PushOptions {
+    pub url: HttpUrl,
+    pub interval: Duration,
+    pub auth: Option<PushAuthOptions>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StatsdAddress {
+    // Kept as `HOST:PORT` so it's resolved when connecting rather than at startup.
+    Udp(String),
+    Unix(PathBuf),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StatsdOptions {
+    pub address: StatsdAddress,
+    pub interval: Duration,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TextfileOptions {
+    pub dir: PathBuf,
+    pub interval: Duration,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PushgatewayOptions {
+    pub push: PushOptions,
+    pub job: String,
+    // Defaults to the hostname, which is looked up at startup.
+    pub instance: Option<String>,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct StaticLabel {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ChildArgs {
+    pub port: NonZeroU16,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParentArgs {
+    pub server: Option<ServerOptions>,
+    pub remote_write: Option<PushOptions>,
+    pub otlp: Option<PushOptions>,
+    pub statsd: Option<StatsdOptions>,
+    pub textfile: Option<TextfileOptions>,
+    pub pushgateway: Option<PushgatewayOptions>,
+    pub top_series: Option<NonZeroU32>,
+    pub relabel_config: Option<PathBuf>,
+    pub labels: Vec<StaticLabel>,
+    pub field_stats: bool,
+}
+
+// It's only ever constructed once, at startup, so its size doesn't matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, PartialEq)]
 pub enum Args {
     Child,
+    Pusher,
+    HashKey,
     Parent(ParentArgs),
 }
 
@@ -41,6 +122,91 @@ pub enum ArgsError {
     EmptyCertificate,
     MissingPrivateKey,
     EmptyPrivateKey,
+    MissingClientCa,
+    EmptyClientCa,
+    MissingClientAuth,
+    InvalidClientAuth,
+    ClientAuthWithoutClientCa,
+    ClientCaWithoutTls,
+    MissingOcspResponse,
+    EmptyOcspResponse,
+    MissingTlsMinVersion,
+    InvalidTlsMinVersion,
+    MissingTlsMaxVersion,
+    InvalidTlsMaxVersion,
+    ConflictingTlsVersions,
+    MissingTlsCipherSuites,
+    InvalidTlsCipherSuites,
+    NoUsableTlsCipherSuites,
+    TlsOptionWithoutTls,
+    MissingRateLimit,
+    InvalidRateLimit,
+    MissingRateLimitBurst,
+    InvalidRateLimitBurst,
+    MissingRateLimitIpv4Prefix,
+    InvalidRateLimitIpv4Prefix,
+    MissingRateLimitIpv6Prefix,
+    InvalidRateLimitIpv6Prefix,
+    MissingKeyRateLimit,
+    InvalidKeyRateLimit,
+    MissingKeyRateLimitBurst,
+    InvalidKeyRateLimitBurst,
+    MissingRateLimitExempt,
+    InvalidRateLimitExempt,
+    MissingAuthLockoutFailures,
+    InvalidAuthLockoutFailures,
+    MissingAuthLockoutWindow,
+    InvalidAuthLockoutWindow,
+    MissingAuthLockoutDuration,
+    InvalidAuthLockoutDuration,
+    MissingAuthLockoutMaxDuration,
+    InvalidAuthLockoutMaxDuration,
+    ConflictingAuthLockoutDurations,
+    MissingTopSeries,
+    InvalidTopSeries,
+    MissingRelabelConfig,
+    EmptyRelabelConfig,
+    MissingLabel,
+    InvalidLabel,
+    ReservedLabel,
+    DuplicateLabel,
+    MissingRemoteWriteUrl,
+    InvalidRemoteWriteUrl,
+    MissingRemoteWriteInterval,
+    InvalidRemoteWriteInterval,
+    MissingRemoteWriteUsername,
+    InvalidRemoteWriteUsername,
+    MissingRemoteWritePasswordFile,
+    EmptyRemoteWritePasswordFile,
+    MissingRemoteWriteBearerTokenFile,
+    EmptyRemoteWriteBearerTokenFile,
+    ConflictingRemoteWriteAuth,
+    MissingOtlpUrl,
+    InvalidOtlpUrl,
+    MissingOtlpInterval,
+    InvalidOtlpInterval,
+    MissingOtlpBearerTokenFile,
+    EmptyOtlpBearerTokenFile,
+    MissingStatsdAddress,
+    InvalidStatsdAddress,
+    MissingStatsdInterval,
+    InvalidStatsdInterval,
+    MissingTextfileDir,
+    EmptyTextfileDir,
+    MissingTextfileInterval,
+    InvalidTextfileInterval,
+    MissingPushgatewayUrl,
+    InvalidPushgatewayUrl,
+    MissingPushgatewayInterval,
+    InvalidPushgatewayInterval,
+    MissingPushgatewayJob,
+    EmptyPushgatewayJob,
+    MissingPushgatewayInstance,
+    EmptyPushgatewayInstance,
+    MissingPushgatewayUsername,
+    InvalidPushgatewayUsername,
+    MissingPushgatewayPasswordFile,
+    EmptyPushgatewayPasswordFile,
     UnknownFlag(OsString),
 }
 
@@ -57,6 +223,189 @@ impl ArgsError {
             ArgsError::EmptyCertificate => Cow::Borrowed("Certificate file cannot be empty."),
             ArgsError::MissingPrivateKey => Cow::Borrowed("Private key file missing."),
             ArgsError::EmptyPrivateKey => Cow::Borrowed("Private key file cannot be empty."),
+            ArgsError::MissingClientCa => Cow::Borrowed("Client CA file missing."),
+            ArgsError::EmptyClientCa => Cow::Borrowed("Client CA file cannot be empty."),
+            ArgsError::MissingClientAuth => Cow::Borrowed("Client authentication mode missing."),
+            ArgsError::InvalidClientAuth => {
+                Cow::Borrowed("Client authentication mode must be `require` or `optional`.")
+            }
+            ArgsError::ClientAuthWithoutClientCa => {
+                Cow::Borrowed("Client authentication mode requires a client CA file.")
+            }
+            ArgsError::ClientCaWithoutTls => {
+                Cow::Borrowed("Client CA file requires a certificate and private key.")
+            }
+            ArgsError::MissingOcspResponse => Cow::Borrowed("OCSP response file missing."),
+            ArgsError::EmptyOcspResponse => Cow::Borrowed("OCSP response file cannot be empty."),
+            ArgsError::MissingTlsMinVersion => Cow::Borrowed("Minimum TLS version missing."),
+            ArgsError::InvalidTlsMinVersion => {
+                Cow::Borrowed("Minimum TLS version must be `1.2` or `1.3`.")
+            }
+            ArgsError::MissingTlsMaxVersion => Cow::Borrowed("Maximum TLS version missing."),
+            ArgsError::InvalidTlsMaxVersion => {
+                Cow::Borrowed("Maximum TLS version must be `1.2` or `1.3`.")
+            }
+            ArgsError::ConflictingTlsVersions => {
+                Cow::Borrowed("Minimum TLS version cannot be above the maximum TLS version.")
+            }
+            ArgsError::MissingTlsCipherSuites => Cow::Borrowed("TLS cipher suites missing."),
+            ArgsError::InvalidTlsCipherSuites => Cow::Borrowed(
+                "TLS cipher suites must be a comma-separated list of supported cipher suite names.",
+            ),
+            ArgsError::NoUsableTlsCipherSuites => {
+                Cow::Borrowed("None of the TLS cipher suites can be used with the TLS versions.")
+            }
+            ArgsError::TlsOptionWithoutTls => {
+                Cow::Borrowed("TLS options require a certificate and private key.")
+            }
+            ArgsError::MissingRateLimit => Cow::Borrowed("Rate limit missing."),
+            ArgsError::InvalidRateLimit => {
+                Cow::Borrowed("Rate limit must be of the form `COUNT/SECONDS`.")
+            }
+            ArgsError::MissingRateLimitBurst => Cow::Borrowed("Rate limit burst missing."),
+            ArgsError::InvalidRateLimitBurst => Cow::Borrowed("Rate limit burst is invalid."),
+            ArgsError::MissingRateLimitIpv4Prefix => {
+                Cow::Borrowed("Rate limit IPv4 prefix length missing.")
+            }
+            ArgsError::InvalidRateLimitIpv4Prefix => {
+                Cow::Borrowed("Rate limit IPv4 prefix length must be from 1 to 32.")
+            }
+            ArgsError::MissingRateLimitIpv6Prefix => {
+                Cow::Borrowed("Rate limit IPv6 prefix length missing.")
+            }
+            ArgsError::InvalidRateLimitIpv6Prefix => {
+                Cow::Borrowed("Rate limit IPv6 prefix length must be from 1 to 128.")
+            }
+            ArgsError::MissingKeyRateLimit => Cow::Borrowed("Key rate limit missing."),
+            ArgsError::InvalidKeyRateLimit => {
+                Cow::Borrowed("Key rate limit must be of the form `COUNT/SECONDS`.")
+            }
+            ArgsError::MissingKeyRateLimitBurst => Cow::Borrowed("Key rate limit burst missing."),
+            ArgsError::InvalidKeyRateLimitBurst => {
+                Cow::Borrowed("Key rate limit burst is invalid.")
+            }
+            ArgsError::MissingRateLimitExempt => Cow::Borrowed("Rate limit exemption missing."),
+            ArgsError::InvalidRateLimitExempt => {
+                Cow::Borrowed("Rate limit exemption must be an IP address or CIDR range.")
+            }
+            ArgsError::MissingAuthLockoutFailures => {
+                Cow::Borrowed("Auth lockout failure count missing.")
+            }
+            ArgsError::InvalidAuthLockoutFailures => {
+                Cow::Borrowed("Auth lockout failure count is invalid.")
+            }
+            ArgsError::MissingAuthLockoutWindow => Cow::Borrowed("Auth lockout window missing."),
+            ArgsError::InvalidAuthLockoutWindow => {
+                Cow::Borrowed("Auth lockout window is invalid.")
+            }
+            ArgsError::MissingAuthLockoutDuration => {
+                Cow::Borrowed("Auth lockout duration missing.")
+            }
+            ArgsError::InvalidAuthLockoutDuration => {
+                Cow::Borrowed("Auth lockout duration is invalid.")
+            }
+            ArgsError::MissingAuthLockoutMaxDuration => {
+                Cow::Borrowed("Auth lockout maximum duration missing.")
+            }
+            ArgsError::InvalidAuthLockoutMaxDuration => {
+                Cow::Borrowed("Auth lockout maximum duration is invalid.")
+            }
+            ArgsError::ConflictingAuthLockoutDurations => Cow::Borrowed(
+                "Auth lockout duration cannot be above the maximum auth lockout duration.",
+            ),
+            ArgsError::MissingTopSeries => Cow::Borrowed("Top series count missing."),
+            ArgsError::InvalidTopSeries => Cow::Borrowed("Top series count is invalid."),
+            ArgsError::MissingRelabelConfig => Cow::Borrowed("Relabel config file missing."),
+            ArgsError::EmptyRelabelConfig => Cow::Borrowed("Relabel config file cannot be empty."),
+            ArgsError::MissingLabel => Cow::Borrowed("Label missing."),
+            ArgsError::InvalidLabel => {
+                Cow::Borrowed("Label must be of the form `name=value` with a valid name.")
+            }
+            ArgsError::ReservedLabel => Cow::Borrowed("Label name is reserved."),
+            ArgsError::DuplicateLabel => Cow::Borrowed("Label name was already specified."),
+            ArgsError::MissingRemoteWriteUrl => Cow::Borrowed("Remote write URL missing."),
+            ArgsError::InvalidRemoteWriteUrl => Cow::Borrowed(
+                "Remote write URL must be an `http://` or `https://` URL without credentials.",
+            ),
+            ArgsError::MissingRemoteWriteInterval => {
+                Cow::Borrowed("Remote write interval missing.")
+            }
+            ArgsError::InvalidRemoteWriteInterval => {
+                Cow::Borrowed("Remote write interval is invalid.")
+            }
+            ArgsError::MissingRemoteWriteUsername => {
+                Cow::Borrowed("Remote write username missing.")
+            }
+            ArgsError::InvalidRemoteWriteUsername => {
+                Cow::Borrowed("Remote write username must be non-empty and cannot contain `:`.")
+            }
+            ArgsError::MissingRemoteWritePasswordFile => {
+                Cow::Borrowed("Remote write password file missing.")
+            }
+            ArgsError::EmptyRemoteWritePasswordFile => {
+                Cow::Borrowed("Remote write password file cannot be empty.")
+            }
+            ArgsError::MissingRemoteWriteBearerTokenFile => {
+                Cow::Borrowed("Remote write bearer token file missing.")
+            }
+            ArgsError::EmptyRemoteWriteBearerTokenFile => {
+                Cow::Borrowed("Remote write bearer token file cannot be empty.")
+            }
+            ArgsError::ConflictingRemoteWriteAuth => Cow::Borrowed(
+                "Remote write basic auth and bearer token auth cannot be used together.",
+            ),
+            ArgsError::MissingOtlpUrl => Cow::Borrowed("OTLP URL missing."),
+            ArgsError::InvalidOtlpUrl => Cow::Borrowed(
+                "OTLP URL must be an `http://` or `https://` URL without credentials.",
+            ),
+            ArgsError::MissingOtlpInterval => Cow::Borrowed("OTLP interval missing."),
+            ArgsError::InvalidOtlpInterval => Cow::Borrowed("OTLP interval is invalid."),
+            ArgsError::MissingOtlpBearerTokenFile => {
+                Cow::Borrowed("OTLP bearer token file missing.")
+            }
+            ArgsError::EmptyOtlpBearerTokenFile => {
+                Cow::Borrowed("OTLP bearer token file cannot be empty.")
+            }
+            ArgsError::MissingStatsdAddress => Cow::Borrowed("StatsD address missing."),
+            ArgsError::InvalidStatsdAddress => Cow::Borrowed(
+                "StatsD address must be `HOST:PORT` for UDP or `unix:///PATH` for a Unix socket.",
+            ),
+            ArgsError::MissingStatsdInterval => Cow::Borrowed("StatsD interval missing."),
+            ArgsError::InvalidStatsdInterval => Cow::Borrowed("StatsD interval is invalid."),
+            ArgsError::MissingTextfileDir => Cow::Borrowed("Textfile directory missing."),
+            ArgsError::EmptyTextfileDir => Cow::Borrowed("Textfile directory cannot be empty."),
+            ArgsError::MissingTextfileInterval => Cow::Borrowed("Textfile interval missing."),
+            ArgsError::InvalidTextfileInterval => Cow::Borrowed("Textfile interval is invalid."),
+            ArgsError::MissingPushgatewayUrl => Cow::Borrowed("Pushgateway URL missing."),
+            ArgsError::InvalidPushgatewayUrl => Cow::Borrowed(
+                "Pushgateway URL must be an `http://` or `https://` URL without credentials or a query.",
+            ),
+            ArgsError::MissingPushgatewayInterval => {
+                Cow::Borrowed("Pushgateway interval missing.")
+            }
+            ArgsError::InvalidPushgatewayInterval => {
+                Cow::Borrowed("Pushgateway interval is invalid.")
+            }
+            ArgsError::MissingPushgatewayJob => Cow::Borrowed("Pushgateway job missing."),
+            ArgsError::EmptyPushgatewayJob => Cow::Borrowed("Pushgateway job cannot be empty."),
+            ArgsError::MissingPushgatewayInstance => {
+                Cow::Borrowed("Pushgateway instance missing.")
+            }
+            ArgsError::EmptyPushgatewayInstance => {
+                Cow::Borrowed("Pushgateway instance cannot be empty.")
+            }
+            ArgsError::MissingPushgatewayUsername => {
+                Cow::Borrowed("Pushgateway username missing.")
+            }
+            ArgsError::InvalidPushgatewayUsername => {
+                Cow::Borrowed("Pushgateway username must be non-empty and cannot contain `:`.")
+            }
+            ArgsError::MissingPushgatewayPasswordFile => {
+                Cow::Borrowed("Pushgateway password file missing.")
+            }
+            ArgsError::EmptyPushgatewayPasswordFile => {
+                Cow::Borrowed("Pushgateway password file cannot be empty.")
+            }
             ArgsError::UnknownFlag(option) => {
                 let mut result = String::new();
                 result.push_str("Unknown flag or option: '");
@@ -75,6 +424,44 @@ pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Args, Args
         ExpectKeyDir,
         ExpectCertificate,
         ExpectPrivateKey,
+        ExpectClientCa,
+        ExpectClientAuth,
+        ExpectOcspResponse,
+        ExpectTlsMinVersion,
+        ExpectTlsMaxVersion,
+        ExpectTlsCipherSuites,
+        ExpectRateLimit,
+        ExpectRateLimitBurst,
+        ExpectRateLimitIpv4Prefix,
+        ExpectRateLimitIpv6Prefix,
+        ExpectKeyRateLimit,
+        ExpectKeyRateLimitBurst,
+        ExpectRateLimitExempt,
+        ExpectAuthLockoutFailures,
+        ExpectAuthLockoutWindow,
+        ExpectAuthLockoutDuration,
+        ExpectAuthLockoutMaxDuration,
+        ExpectTopSeries,
+        ExpectRelabelConfig,
+        ExpectLabel,
+        ExpectRemoteWriteUrl,
+        ExpectRemoteWriteInterval,
+        ExpectRemoteWriteUsername,
+        ExpectRemoteWritePasswordFile,
+        ExpectRemoteWriteBearerTokenFile,
+        ExpectOtlpUrl,
+        ExpectOtlpInterval,
+        ExpectOtlpBearerTokenFile,
+        ExpectStatsdAddress,
+        ExpectStatsdInterval,
+        ExpectTextfileDir,
+        ExpectTextfileInterval,
+        ExpectPushgatewayUrl,
+        ExpectPushgatewayInterval,
+        ExpectPushgatewayJob,
+        ExpectPushgatewayInstance,
+        ExpectPushgatewayUsername,
+        ExpectPushgatewayPasswordFile,
     }
 
     let mut state = ArgState::Initial;
@@ -83,6 +470,46 @@ pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Args, Args
     let mut key_dir = None::<PathBuf>;
     let mut certificate = None::<PathBuf>;
     let mut private_key = None::<PathBuf>;
+    let mut client_ca = None::<PathBuf>;
+    let mut client_auth_required = None::<bool>;
+    let mut ocsp_response = None::<PathBuf>;
+    let mut tls_min_version = None::<TlsVersion>;
+    let mut tls_max_version = None::<TlsVersion>;
+    let mut tls_cipher_suites = None::<Vec<TlsCipherSuite>>;
+    let mut tls_session_tickets = false;
+    let mut rate_limit = None::<(NonZeroU32, NonZeroU32)>;
+    let mut rate_limit_burst = None::<NonZeroU32>;
+    let mut rate_limit_ipv4_prefix = None::<u8>;
+    let mut rate_limit_ipv6_prefix = None::<u8>;
+    let mut key_rate_limit = None::<(NonZeroU32, NonZeroU32)>;
+    let mut key_rate_limit_burst = None::<NonZeroU32>;
+    let mut rate_limit_exempt = Vec::<IpPrefix>::new();
+    let mut auth_lockout_failures = None::<NonZeroU32>;
+    let mut auth_lockout_window = None::<NonZeroU32>;
+    let mut auth_lockout_duration = None::<NonZeroU32>;
+    let mut auth_lockout_max_duration = None::<NonZeroU32>;
+    let mut top_series = None::<NonZeroU32>;
+    let mut relabel_config = None::<PathBuf>;
+    let mut labels = Vec::<StaticLabel>::new();
+    let mut field_stats = false;
+    let mut remote_write_url = None::<HttpUrl>;
+    let mut remote_write_interval = None::<Duration>;
+    let mut remote_write_username = None::<String>;
+    let mut remote_write_password_file = None::<PathBuf>;
+    let mut remote_write_bearer_token_file = None::<PathBuf>;
+    let mut otlp_url = None::<HttpUrl>;
+    let mut otlp_interval = None::<Duration>;
+    let mut otlp_bearer_token_file = None::<PathBuf>;
+    let mut statsd_address = None::<StatsdAddress>;
+    let mut statsd_interval = None::<Duration>;
+    let mut textfile_dir = None::<PathBuf>;
+    let mut textfile_interval = None::<Duration>;
+    let mut pushgateway_url = None::<HttpUrl>;
+    let mut pushgateway_interval = None::<Duration>;
+    let mut pushgateway_job = None::<String>;
+    let mut pushgateway_instance = None::<String>;
+    let mut pushgateway_username = None::<String>;
+    let mut pushgateway_password_file = None::<PathBuf>;
 
     fn parse_port(arg: &[u8]) -> Result<NonZeroU16, ArgsError> {
         parse_u32(arg)
@@ -91,6 +518,146 @@ pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Args, Args
             .ok_or(ArgsError::InvalidPort)
     }
 
+    fn parse_top_series(arg: &[u8]) -> Result<NonZeroU32, ArgsError> {
+        parse_u32(arg)
+            .and_then(NonZeroU32::new)
+            .ok_or(ArgsError::InvalidTopSeries)
+    }
+
+    fn parse_client_auth(arg: &[u8]) -> Result<bool, ArgsError> {
+        match arg {
+            b"require" => Ok(true),
+            b"optional" => Ok(false),
+            _ => Err(ArgsError::InvalidClientAuth),
+        }
+    }
+
+    fn parse_tls_version(arg: &[u8], error: ArgsError) -> Result<TlsVersion, ArgsError> {
+        TlsVersion::from_label(arg).ok_or(error)
+    }
+
+    fn parse_tls_cipher_suites(arg: &[u8]) -> Result<Vec<TlsCipherSuite>, ArgsError> {
+        TlsPolicy::parse_cipher_suites(arg).ok_or(ArgsError::InvalidTlsCipherSuites)
+    }
+
+    fn parse_rate(arg: &[u8], error: ArgsError) -> Result<(NonZeroU32, NonZeroU32), ArgsError> {
+        RateLimit::parse_rate(arg).ok_or(error)
+    }
+
+    fn parse_nonzero(arg: &[u8], error: ArgsError) -> Result<NonZeroU32, ArgsError> {
+        parse_u32(arg).and_then(NonZeroU32::new).ok_or(error)
+    }
+
+    fn parse_prefix_len(arg: &[u8], max: u8, error: ArgsError) -> Result<u8, ArgsError> {
+        parse_u32(arg)
+            .and_then(|len| u8::try_from(len).ok())
+            .filter(|len| (1..=max).contains(len))
+            .ok_or(error)
+    }
+
+    fn parse_exempt(arg: &[u8]) -> Result<IpPrefix, ArgsError> {
+        IpPrefix::parse(arg).ok_or(ArgsError::InvalidRateLimitExempt)
+    }
+
+    fn parse_url(arg: &[u8], error: ArgsError) -> Result<HttpUrl, ArgsError> {
+        std::str::from_utf8(arg)
+            .ok()
+            .and_then(HttpUrl::parse)
+            .ok_or(error)
+    }
+
+    fn parse_interval(arg: &[u8], error: ArgsError) -> Result<Duration, ArgsError> {
+        parse_u32(arg)
+            .filter(|seconds| *seconds != 0)
+            .map(|seconds| Duration::from_secs(seconds.into()))
+            .ok_or(error)
+    }
+
+    fn parse_statsd_address(arg: &[u8]) -> Result<StatsdAddress, ArgsError> {
+        if let Some(path) = arg.strip_prefix(b"unix://") {
+            return match path {
+                [b'/', ..] => Ok(StatsdAddress::Unix(PathBuf::from(
+                    std::ffi::OsStr::from_bytes(path),
+                ))),
+                _ => Err(ArgsError::InvalidStatsdAddress),
+            };
+        }
+
+        let Some((host, port)) = std::str::from_utf8(arg)
+            .ok()
+            .and_then(|arg| arg.rsplit_once(':'))
+        else {
+            return Err(ArgsError::InvalidStatsdAddress);
+        };
+
+        if host.is_empty()
+            || host.contains(|c: char| c.is_whitespace() || c == '/')
+            || parse_port(port.as_bytes()).is_err()
+        {
+            return Err(ArgsError::InvalidStatsdAddress);
+        }
+
+        Ok(StatsdAddress::Udp(
+            String::from_utf8_lossy(arg).into_owned(),
+        ))
+    }
+
+    fn parse_pushgateway_url(arg: &[u8]) -> Result<HttpUrl, ArgsError> {
+        // The grouping key is appended to the path, so a query string would end up in the wrong
+        // place.
+        match parse_url(arg, ArgsError::InvalidPushgatewayUrl)? {
+            url if url.path.contains('?') => Err(ArgsError::InvalidPushgatewayUrl),
+            url => Ok(url),
+        }
+    }
+
+    fn parse_grouping_value(arg: &[u8], error: ArgsError) -> Result<String, ArgsError> {
+        match std::str::from_utf8(arg) {
+            Ok(value) if !value.is_empty() => Ok(value.into()),
+            _ => Err(error),
+        }
+    }
+
+    fn parse_username(arg: &[u8], error: ArgsError) -> Result<String, ArgsError> {
+        // Basic auth can't represent usernames with colons.
+        match std::str::from_utf8(arg) {
+            Ok(username) if !username.is_empty() && !username.contains(':') => Ok(username.into()),
+            _ => Err(error),
+        }
+    }
+
+    fn parse_label(arg: &[u8], labels: &[StaticLabel]) -> Result<StaticLabel, ArgsError> {
+        let Some((name, value)) = std::str::from_utf8(arg)
+            .ok()
+            .and_then(|arg| arg.split_once('='))
+        else {
+            return Err(ArgsError::InvalidLabel);
+        };
+
+        // Per the OpenMetrics spec: `[a-zA-Z_][a-zA-Z0-9_]*`.
+        match name.as_bytes() {
+            [b'a'..=b'z' | b'A'..=b'Z' | b'_', rest @ ..]
+                if rest
+                    .iter()
+                    .all(|b| matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_')) => {}
+            _ => return Err(ArgsError::InvalidLabel),
+        }
+
+        // Names starting with `__` are reserved for internal use.
+        if name.starts_with("__") || RESERVED_LABEL_NAMES.contains(&name) {
+            return Err(ArgsError::ReservedLabel);
+        }
+
+        if labels.iter().any(|label| label.name == name) {
+            return Err(ArgsError::DuplicateLabel);
+        }
+
+        Ok(StaticLabel {
+            name: name.into(),
+            value: value.into(),
+        })
+    }
+
     fn parse_path(arg: &[u8], error: ArgsError) -> Result<PathBuf, ArgsError> {
         if arg.is_empty() {
             Err(error)
@@ -109,7 +676,51 @@ pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Args, Args
                 b"-k" | b"--key-dir" => state = ArgState::ExpectKeyDir,
                 b"-C" | b"--certificate" => state = ArgState::ExpectCertificate,
                 b"-K" | b"--private-key" => state = ArgState::ExpectPrivateKey,
+                b"--client-ca" => state = ArgState::ExpectClientCa,
+                b"--client-auth" => state = ArgState::ExpectClientAuth,
+                b"--ocsp-response" => state = ArgState::ExpectOcspResponse,
+                b"--tls-min-version" => state = ArgState::ExpectTlsMinVersion,
+                b"--tls-max-version" => state = ArgState::ExpectTlsMaxVersion,
+                b"--tls-cipher-suites" => state = ArgState::ExpectTlsCipherSuites,
+                b"--tls-session-tickets" => tls_session_tickets = true,
+                b"--rate-limit" => state = ArgState::ExpectRateLimit,
+                b"--rate-limit-burst" => state = ArgState::ExpectRateLimitBurst,
+                b"--rate-limit-ipv4-prefix" => state = ArgState::ExpectRateLimitIpv4Prefix,
+                b"--rate-limit-ipv6-prefix" => state = ArgState::ExpectRateLimitIpv6Prefix,
+                b"--key-rate-limit" => state = ArgState::ExpectKeyRateLimit,
+                b"--key-rate-limit-burst" => state = ArgState::ExpectKeyRateLimitBurst,
+                b"--rate-limit-exempt" => state = ArgState::ExpectRateLimitExempt,
+                b"--auth-lockout-failures" => state = ArgState::ExpectAuthLockoutFailures,
+                b"--auth-lockout-window" => state = ArgState::ExpectAuthLockoutWindow,
+                b"--auth-lockout-duration" => state = ArgState::ExpectAuthLockoutDuration,
+                b"--auth-lockout-max-duration" => state = ArgState::ExpectAuthLockoutMaxDuration,
+                b"--top-series" => state = ArgState::ExpectTopSeries,
+                b"--relabel-config" => state = ArgState::ExpectRelabelConfig,
+                b"--label" => state = ArgState::ExpectLabel,
+                b"--field-stats" => field_stats = true,
+                b"--remote-write-url" => state = ArgState::ExpectRemoteWriteUrl,
+                b"--remote-write-interval" => state = ArgState::ExpectRemoteWriteInterval,
+                b"--remote-write-username" => state = ArgState::ExpectRemoteWriteUsername,
+                b"--remote-write-password-file" => state = ArgState::ExpectRemoteWritePasswordFile,
+                b"--remote-write-bearer-token-file" => {
+                    state = ArgState::ExpectRemoteWriteBearerTokenFile
+                }
+                b"--otlp-url" => state = ArgState::ExpectOtlpUrl,
+                b"--otlp-interval" => state = ArgState::ExpectOtlpInterval,
+                b"--otlp-bearer-token-file" => state = ArgState::ExpectOtlpBearerTokenFile,
+                b"--statsd-address" => state = ArgState::ExpectStatsdAddress,
+                b"--statsd-interval" => state = ArgState::ExpectStatsdInterval,
+                b"--textfile-dir" => state = ArgState::ExpectTextfileDir,
+                b"--textfile-interval" => state = ArgState::ExpectTextfileInterval,
+                b"--pushgateway-url" => state = ArgState::ExpectPushgatewayUrl,
+                b"--pushgateway-interval" => state = ArgState::ExpectPushgatewayInterval,
+                b"--pushgateway-job" => state = ArgState::ExpectPushgatewayJob,
+                b"--pushgateway-instance" => state = ArgState::ExpectPushgatewayInstance,
+                b"--pushgateway-username" => state = ArgState::ExpectPushgatewayUsername,
+                b"--pushgateway-password-file" => state = ArgState::ExpectPushgatewayPasswordFile,
                 b"--child-process" => return Ok(Args::Child),
+                b"--push-process" => return Ok(Args::Pusher),
+                b"--hash-key" => return Ok(Args::HashKey),
 
                 // Short option equals
                 [b'-', b'p', b'=', arg @ ..] => {
@@ -143,6 +754,224 @@ pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Args, Args
                 {
                     private_key = Some(parse_path(arg, ArgsError::EmptyPrivateKey)?);
                 }
+                // `--client-ca=`
+                [b'-', b'-', b'c', b'l', b'i', b'e', b'n', b't', b'-', b'c', b'a', b'=', arg @ ..] =>
+                {
+                    client_ca = Some(parse_path(arg, ArgsError::EmptyClientCa)?);
+                }
+                // `--client-auth=`
+                [b'-', b'-', b'c', b'l', b'i', b'e', b'n', b't', b'-', b'a', b'u', b't', b'h', b'=', arg @ ..] =>
+                {
+                    client_auth_required = Some(parse_client_auth(arg)?);
+                }
+                // `--ocsp-response=`
+                [b'-', b'-', b'o', b'c', b's', b'p', b'-', b'r', b'e', b's', b'p', b'o', b'n', b's', b'e', b'=', arg @ ..] =>
+                {
+                    ocsp_response = Some(parse_path(arg, ArgsError::EmptyOcspResponse)?);
+                }
+                // `--tls-min-version=`
+                [b'-', b'-', b't', b'l', b's', b'-', b'm', b'i', b'n', b'-', b'v', b'e', b'r', b's', b'i', b'o', b'n', b'=', arg @ ..] =>
+                {
+                    tls_min_version =
+                        Some(parse_tls_version(arg, ArgsError::InvalidTlsMinVersion)?);
+                }
+                // `--tls-max-version=`
+                [b'-', b'-', b't', b'l', b's', b'-', b'm', b'a', b'x', b'-', b'v', b'e', b'r', b's', b'i', b'o', b'n', b'=', arg @ ..] =>
+                {
+                    tls_max_version =
+                        Some(parse_tls_version(arg, ArgsError::InvalidTlsMaxVersion)?);
+                }
+                // `--tls-cipher-suites=`
+                [b'-', b'-', b't', b'l', b's', b'-', b'c', b'i', b'p', b'h', b'e', b'r', b'-', b's', b'u', b'i', b't', b'e', b's', b'=', arg @ ..] =>
+                {
+                    tls_cipher_suites = Some(parse_tls_cipher_suites(arg)?);
+                }
+                // `--rate-limit=`
+                [b'-', b'-', b'r', b'a', b't', b'e', b'-', b'l', b'i', b'm', b'i', b't', b'=', arg @ ..] =>
+                {
+                    rate_limit = Some(parse_rate(arg, ArgsError::InvalidRateLimit)?);
+                }
+                // `--rate-limit-burst=`
+                [b'-', b'-', b'r', b'a', b't', b'e', b'-', b'l', b'i', b'm', b'i', b't', b'-', b'b', b'u', b'r', b's', b't', b'=', arg @ ..] =>
+                {
+                    rate_limit_burst = Some(parse_nonzero(arg, ArgsError::InvalidRateLimitBurst)?);
+                }
+                // `--rate-limit-ipv4-prefix=`
+                [b'-', b'-', b'r', b'a', b't', b'e', b'-', b'l', b'i', b'm', b'i', b't', b'-', b'i', b'p', b'v', b'4', b'-', b'p', b'r', b'e', b'f', b'i', b'x', b'=', arg @ ..] =>
+                {
+                    rate_limit_ipv4_prefix = Some(parse_prefix_len(
+                        arg,
+                        32,
+                        ArgsError::InvalidRateLimitIpv4Prefix,
+                    )?);
+                }
+                // `--rate-limit-ipv6-prefix=`
+                [b'-', b'-', b'r', b'a', b't', b'e', b'-', b'l', b'i', b'm', b'i', b't', b'-', b'i', b'p', b'v', b'6', b'-', b'p', b'r', b'e', b'f', b'i', b'x', b'=', arg @ ..] =>
+                {
+                    rate_limit_ipv6_prefix = Some(parse_prefix_len(
+                        arg,
+                        128,
+                        ArgsError::InvalidRateLimitIpv6Prefix,
+                    )?);
+                }
+                // `--key-rate-limit=`
+                [b'-', b'-', b'k', b'e', b'y', b'-', b'r', b'a', b't', b'e', b'-', b'l', b'i', b'm', b'i', b't', b'=', arg @ ..] =>
+                {
+                    key_rate_limit = Some(parse_rate(arg, ArgsError::InvalidKeyRateLimit)?);
+                }
+                // `--key-rate-limit-burst=`
+                [b'-', b'-', b'k', b'e', b'y', b'-', b'r', b'a', b't', b'e', b'-', b'l', b'i', b'm', b'i', b't', b'-', b'b', b'u', b'r', b's', b't', b'=', arg @ ..] =>
+                {
+                    key_rate_limit_burst =
+                        Some(parse_nonzero(arg, ArgsError::InvalidKeyRateLimitBurst)?);
+                }
+                // `--rate-limit-exempt=`
+                [b'-', b'-', b'r', b'a', b't', b'e', b'-', b'l', b'i', b'm', b'i', b't', b'-', b'e', b'x', b'e', b'm', b'p', b't', b'=', arg @ ..] =>
+                {
+                    rate_limit_exempt.push(parse_exempt(arg)?);
+                }
+                // `--auth-lockout-failures=`
+                [b'-', b'-', b'a', b'u', b't', b'h', b'-', b'l', b'o', b'c', b'k', b'o', b'u', b't', b'-', b'f', b'a', b'i', b'l', b'u', b'r', b'e', b's', b'=', arg @ ..] =>
+                {
+                    auth_lockout_failures =
+                        Some(parse_nonzero(arg, ArgsError::InvalidAuthLockoutFailures)?);
+                }
+                // `--auth-lockout-window=`
+                [b'-', b'-', b'a', b'u', b't', b'h', b'-', b'l', b'o', b'c', b'k', b'o', b'u', b't', b'-', b'w', b'i', b'n', b'd', b'o', b'w', b'=', arg @ ..] =>
+                {
+                    auth_lockout_window =
+                        Some(parse_nonzero(arg, ArgsError::InvalidAuthLockoutWindow)?);
+                }
+                // `--auth-lockout-duration=`
+                [b'-', b'-', b'a', b'u', b't', b'h', b'-', b'l', b'o', b'c', b'k', b'o', b'u', b't', b'-', b'd', b'u', b'r', b'a', b't', b'i', b'o', b'n', b'=', arg @ ..] =>
+                {
+                    auth_lockout_duration =
+                        Some(parse_nonzero(arg, ArgsError::InvalidAuthLockoutDuration)?);
+                }
+                // `--auth-lockout-max-duration=`
+                [b'-', b'-', b'a', b'u', b't', b'h', b'-', b'l', b'o', b'c', b'k', b'o', b'u', b't', b'-', b'm', b'a', b'x', b'-', b'd', b'u', b'r', b'a', b't', b'i', b'o', b'n', b'=', arg @ ..] =>
+                {
+                    auth_lockout_max_duration = Some(parse_nonzero(
+                        arg,
+                        ArgsError::InvalidAuthLockoutMaxDuration,
+                    )?);
+                }
+                // `--top-series=`
+                [b'-', b'-', b't', b'o', b'p', b'-', b's', b'e', b'r', b'i', b'e', b's', b'=', arg @ ..] =>
+                {
+                    top_series = Some(parse_top_series(arg)?);
+                }
+                // `--relabel-config=`
+                [b'-', b'-', b'r', b'e', b'l', b'a', b'b', b'e', b'l', b'-', b'c', b'o', b'n', b'f', b'i', b'g', b'=', arg @ ..] =>
+                {
+                    relabel_config = Some(parse_path(arg, ArgsError::EmptyRelabelConfig)?);
+                }
+                // `--label=`
+                [b'-', b'-', b'l', b'a', b'b', b'e', b'l', b'=', arg @ ..] => {
+                    labels.push(parse_label(arg, &labels)?);
+                }
+                // `--remote-write-url=`
+                [b'-', b'-', b'r', b'e', b'm', b'o', b't', b'e', b'-', b'w', b'r', b'i', b't', b'e', b'-', b'u', b'r', b'l', b'=', arg @ ..] =>
+                {
+                    remote_write_url = Some(parse_url(arg, ArgsError::InvalidRemoteWriteUrl)?);
+                }
+                // `--remote-write-interval=`
+                [b'-', b'-', b'r', b'e', b'm', b'o', b't', b'e', b'-', b'w', b'r', b'i', b't', b'e', b'-', b'i', b'n', b't', b'e', b'r', b'v', b'a', b'l', b'=', arg @ ..] =>
+                {
+                    remote_write_interval =
+                        Some(parse_interval(arg, ArgsError::InvalidRemoteWriteInterval)?);
+                }
+                // `--remote-write-username=`
+                [b'-', b'-', b'r', b'e', b'm', b'o', b't', b'e', b'-', b'w', b'r', b'i', b't', b'e', b'-', b'u', b's', b'e', b'r', b'n', b'a', b'm', b'e', b'=', arg @ ..] =>
+                {
+                    remote_write_username =
+                        Some(parse_username(arg, ArgsError::InvalidRemoteWriteUsername)?);
+                }
+                // `--remote-write-password-file=`
+                [b'-', b'-', b'r', b'e', b'm', b'o', b't', b'e', b'-', b'w', b'r', b'i', b't', b'e', b'-', b'p', b'a', b's', b's', b'w', b'o', b'r', b'd', b'-', b'f', b'i', b'l', b'e', b'=', arg @ ..] =>
+                {
+                    remote_write_password_file =
+                        Some(parse_path(arg, ArgsError::EmptyRemoteWritePasswordFile)?);
+                }
+                // `--remote-write-bearer-token-file=`
+                [b'-', b'-', b'r', b'e', b'm', b'o', b't', b'e', b'-', b'w', b'r', b'i', b't', b'e', b'-', b'b', b'e', b'a', b'r', b'e', b'r', b'-', b't', b'o', b'k', b'e', b'n', b'-', b'f', b'i', b'l', b'e', b'=', arg @ ..] =>
+                {
+                    remote_write_bearer_token_file =
+                        Some(parse_path(arg, ArgsError::EmptyRemoteWriteBearerTokenFile)?);
+                }
+                // `--otlp-url=`
+                [b'-', b'-', b'o', b't', b'l', b'p', b'-', b'u', b'r', b'l', b'=', arg @ ..] => {
+                    otlp_url = Some(parse_url(arg, ArgsError::InvalidOtlpUrl)?);
+                }
+                // `--otlp-interval=`
+                [b'-', b'-', b'o', b't', b'l', b'p', b'-', b'i', b'n', b't', b'e', b'r', b'v', b'a', b'l', b'=', arg @ ..] =>
+                {
+                    otlp_interval = Some(parse_interval(arg, ArgsError::InvalidOtlpInterval)?);
+                }
+                // `--otlp-bearer-token-file=`
+                [b'-', b'-', b'o', b't', b'l', b'p', b'-', b'b', b'e', b'a', b'r', b'e', b'r', b'-', b't', b'o', b'k', b'e', b'n', b'-', b'f', b'i', b'l', b'e', b'=', arg @ ..] =>
+                {
+                    otlp_bearer_token_file =
+                        Some(parse_path(arg, ArgsError::EmptyOtlpBearerTokenFile)?);
+                }
+                // `--statsd-address=`
+                [b'-', b'-', b's', b't', b'a', b't', b's', b'd', b'-', b'a', b'd', b'd', b'r', b'e', b's', b's', b'=', arg @ ..] =>
+                {
+                    statsd_address = Some(parse_statsd_address(arg)?);
+                }
+                // `--statsd-interval=`
+                [b'-', b'-', b's', b't', b'a', b't', b's', b'd', b'-', b'i', b'n', b't', b'e', b'r', b'v', b'a', b'l', b'=', arg @ ..] =>
+                {
+                    statsd_interval = Some(parse_interval(arg, ArgsError::InvalidStatsdInterval)?);
+                }
+                // `--textfile-dir=`
+                [b'-', b'-', b't', b'e', b'x', b't', b'f', b'i', b'l', b'e', b'-', b'd', b'i', b'r', b'=', arg @ ..] =>
+                {
+                    textfile_dir = Some(parse_path(arg, ArgsError::EmptyTextfileDir)?);
+                }
+                // `--textfile-interval=`
+                [b'-', b'-', b't', b'e', b'x', b't', b'f', b'i', b'l', b'e', b'-', b'i', b'n', b't', b'e', b'r', b'v', b'a', b'l', b'=', arg @ ..] =>
+                {
+                    textfile_interval =
+                        Some(parse_interval(arg, ArgsError::InvalidTextfileInterval)?);
+                }
+                // `--pushgateway-url=`
+                [b'-', b'-', b'p', b'u', b's', b'h', b'g', b'a', b't', b'e', b'w', b'a', b'y', b'-', b'u', b'r', b'l', b'=', arg @ ..] =>
+                {
+                    pushgateway_url = Some(parse_pushgateway_url(arg)?);
+                }
+                // `--pushgateway-interval=`
+                [b'-', b'-', b'p', b'u', b's', b'h', b'g', b'a', b't', b'e', b'w', b'a', b'y', b'-', b'i', b'n', b't', b'e', b'r', b'v', b'a', b'l', b'=', arg @ ..] =>
+                {
+                    pushgateway_interval =
+                        Some(parse_interval(arg, ArgsError::InvalidPushgatewayInterval)?);
+                }
+                // `--pushgateway-job=`
+                [b'-', b'-', b'p', b'u', b's', b'h', b'g', b'a', b't', b'e', b'w', b'a', b'y', b'-', b'j', b'o', b'b', b'=', arg @ ..] =>
+                {
+                    pushgateway_job =
+                        Some(parse_grouping_value(arg, ArgsError::EmptyPushgatewayJob)?);
+                }
+                // `--pushgateway-instance=`
+                [b'-', b'-', b'p', b'u', b's', b'h', b'g', b'a', b't', b'e', b'w', b'a', b'y', b'-', b'i', b'n', b's', b't', b'a', b'n', b'c', b'e', b'=', arg @ ..] =>
+                {
+                    pushgateway_instance = Some(parse_grouping_value(
+                        arg,
+                        ArgsError::EmptyPushgatewayInstance,
+                    )?);
+                }
+                // `--pushgateway-username=`
+                [b'-', b'-', b'p', b'u', b's', b'h', b'g', b'a', b't', b'e', b'w', b'a', b'y', b'-', b'u', b's', b'e', b'r', b'n', b'a', b'm', b'e', b'=', arg @ ..] =>
+                {
+                    pushgateway_username =
+                        Some(parse_username(arg, ArgsError::InvalidPushgatewayUsername)?);
+                }
+                // `--pushgateway-password-file=`
+                [b'-', b'-', b'p', b'u', b's', b'h', b'g', b'a', b't', b'e', b'w', b'a', b'y', b'-', b'p', b'a', b's', b's', b'w', b'o', b'r', b'd', b'-', b'f', b'i', b'l', b'e', b'=', arg @ ..] =>
+                {
+                    pushgateway_password_file =
+                        Some(parse_path(arg, ArgsError::EmptyPushgatewayPasswordFile)?);
+                }
 
                 _ => return Err(ArgsError::UnknownFlag(arg)),
             },
@@ -162,32 +991,507 @@ pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Args, Args
                 state = ArgState::Initial;
                 private_key = Some(parse_path(arg.as_bytes(), ArgsError::EmptyPrivateKey)?);
             }
+            ArgState::ExpectClientCa => {
+                state = ArgState::Initial;
+                client_ca = Some(parse_path(arg.as_bytes(), ArgsError::EmptyClientCa)?);
+            }
+            ArgState::ExpectClientAuth => {
+                state = ArgState::Initial;
+                client_auth_required = Some(parse_client_auth(arg.as_bytes())?);
+            }
+            ArgState::ExpectOcspResponse => {
+                state = ArgState::Initial;
+                ocsp_response = Some(parse_path(arg.as_bytes(), ArgsError::EmptyOcspResponse)?);
+            }
+            ArgState::ExpectTlsMinVersion => {
+                state = ArgState::Initial;
+                tls_min_version = Some(parse_tls_version(
+                    arg.as_bytes(),
+                    ArgsError::InvalidTlsMinVersion,
+                )?);
+            }
+            ArgState::ExpectTlsMaxVersion => {
+                state = ArgState::Initial;
+                tls_max_version = Some(parse_tls_version(
+                    arg.as_bytes(),
+                    ArgsError::InvalidTlsMaxVersion,
+                )?);
+            }
+            ArgState::ExpectTlsCipherSuites => {
+                state = ArgState::Initial;
+                tls_cipher_suites = Some(parse_tls_cipher_suites(arg.as_bytes())?);
+            }
+            ArgState::ExpectRateLimit => {
+                state = ArgState::Initial;
+                rate_limit = Some(parse_rate(arg.as_bytes(), ArgsError::InvalidRateLimit)?);
+            }
+            ArgState::ExpectRateLimitBurst => {
+                state = ArgState::Initial;
+                rate_limit_burst = Some(parse_nonzero(
+                    arg.as_bytes(),
+                    ArgsError::InvalidRateLimitBurst,
+                )?);
+            }
+            ArgState::ExpectRateLimitIpv4Prefix => {
+                state = ArgState::Initial;
+                rate_limit_ipv4_prefix = Some(parse_prefix_len(
+                    arg.as_bytes(),
+                    32,
+                    ArgsError::InvalidRateLimitIpv4Prefix,
+                )?);
+            }
+            ArgState::ExpectRateLimitIpv6Prefix => {
+                state = ArgState::Initial;
+                rate_limit_ipv6_prefix = Some(parse_prefix_len(
+                    arg.as_bytes(),
+                    128,
+                    ArgsError::InvalidRateLimitIpv6Prefix,
+                )?);
+            }
+            ArgState::ExpectKeyRateLimit => {
+                state = ArgState::Initial;
+                key_rate_limit = Some(parse_rate(arg.as_bytes(), ArgsError::InvalidKeyRateLimit)?);
+            }
+            ArgState::ExpectKeyRateLimitBurst => {
+                state = ArgState::Initial;
+                key_rate_limit_burst = Some(parse_nonzero(
+                    arg.as_bytes(),
+                    ArgsError::InvalidKeyRateLimitBurst,
+                )?);
+            }
+            ArgState::ExpectRateLimitExempt => {
+                state = ArgState::Initial;
+                rate_limit_exempt.push(parse_exempt(arg.as_bytes())?);
+            }
+            ArgState::ExpectAuthLockoutFailures => {
+                state = ArgState::Initial;
+                auth_lockout_failures = Some(parse_nonzero(
+                    arg.as_bytes(),
+                    ArgsError::InvalidAuthLockoutFailures,
+                )?);
+            }
+            ArgState::ExpectAuthLockoutWindow => {
+                state = ArgState::Initial;
+                auth_lockout_window = Some(parse_nonzero(
+                    arg.as_bytes(),
+                    ArgsError::InvalidAuthLockoutWindow,
+                )?);
+            }
+            ArgState::ExpectAuthLockoutDuration => {
+                state = ArgState::Initial;
+                auth_lockout_duration = Some(parse_nonzero(
+                    arg.as_bytes(),
+                    ArgsError::InvalidAuthLockoutDuration,
+                )?);
+            }
+            ArgState::ExpectAuthLockoutMaxDuration => {
+                state = ArgState::Initial;
+                auth_lockout_max_duration = Some(parse_nonzero(
+                    arg.as_bytes(),
+                    ArgsError::InvalidAuthLockoutMaxDuration,
+                )?);
+            }
+            ArgState::ExpectTopSeries => {
+                state = ArgState::Initial;
+                top_series = Some(parse_top_series(arg.as_bytes())?);
+            }
+            ArgState::ExpectRelabelConfig => {
+                state = ArgState::Initial;
+                relabel_config = Some(parse_path(arg.as_bytes(), ArgsError::EmptyRelabelConfig)?);
+            }
+            ArgState::ExpectLabel => {
+                state = ArgState::Initial;
+                labels.push(parse_label(arg.as_bytes(), &labels)?);
+            }
+            ArgState::ExpectRemoteWriteUrl => {
+                state = ArgState::Initial;
+                remote_write_url =
+                    Some(parse_url(arg.as_bytes(), ArgsError::InvalidRemoteWriteUrl)?);
+            }
+            ArgState::ExpectRemoteWriteInterval => {
+                state = ArgState::Initial;
+                remote_write_interval = Some(parse_interval(
+                    arg.as_bytes(),
+                    ArgsError::InvalidRemoteWriteInterval,
+                )?);
+            }
+            ArgState::ExpectRemoteWriteUsername => {
+                state = ArgState::Initial;
+                remote_write_username = Some(parse_username(
+                    arg.as_bytes(),
+                    ArgsError::InvalidRemoteWriteUsername,
+                )?);
+            }
+            ArgState::ExpectRemoteWritePasswordFile => {
+                state = ArgState::Initial;
+                remote_write_password_file = Some(parse_path(
+                    arg.as_bytes(),
+                    ArgsError::EmptyRemoteWritePasswordFile,
+                )?);
+            }
+            ArgState::ExpectRemoteWriteBearerTokenFile => {
+                state = ArgState::Initial;
+                remote_write_bearer_token_file = Some(parse_path(
+                    arg.as_bytes(),
+                    ArgsError::EmptyRemoteWriteBearerTokenFile,
+                )?);
+            }
+            ArgState::ExpectOtlpUrl => {
+                state = ArgState::Initial;
+                otlp_url = Some(parse_url(arg.as_bytes(), ArgsError::InvalidOtlpUrl)?);
+            }
+            ArgState::ExpectOtlpInterval => {
+                state = ArgState::Initial;
+                otlp_interval = Some(parse_interval(
+                    arg.as_bytes(),
+                    ArgsError::InvalidOtlpInterval,
+                )?);
+            }
+            ArgState::ExpectOtlpBearerTokenFile => {
+                state = ArgState::Initial;
+                otlp_bearer_token_file = Some(parse_path(
+                    arg.as_bytes(),
+                    ArgsError::EmptyOtlpBearerTokenFile,
+                )?);
+            }
+            ArgState::ExpectStatsdAddress => {
+                state = ArgState::Initial;
+                statsd_address = Some(parse_statsd_address(arg.as_bytes())?);
+            }
+            ArgState::ExpectStatsdInterval => {
+                state = ArgState::Initial;
+                statsd_interval = Some(parse_interval(
+                    arg.as_bytes(),
+                    ArgsError::InvalidStatsdInterval,
+                )?);
+            }
+            ArgState::ExpectTextfileDir => {
+                state = ArgState::Initial;
+                textfile_dir = Some(parse_path(arg.as_bytes(), ArgsError::EmptyTextfileDir)?);
+            }
+            ArgState::ExpectTextfileInterval => {
+                state = ArgState::Initial;
+                textfile_interval = Some(parse_interval(
+                    arg.as_bytes(),
+                    ArgsError::InvalidTextfileInterval,
+                )?);
+            }
+            ArgState::ExpectPushgatewayUrl => {
+                state = ArgState::Initial;
+                pushgateway_url = Some(parse_pushgateway_url(arg.as_bytes())?);
+            }
+            ArgState::ExpectPushgatewayInterval => {
+                state = ArgState::Initial;
+                pushgateway_interval = Some(parse_interval(
+                    arg.as_bytes(),
+                    ArgsError::InvalidPushgatewayInterval,
+                )?);
+            }
+            ArgState::ExpectPushgatewayJob => {
+                state = ArgState::Initial;
+                pushgateway_job = Some(parse_grouping_value(
+                    arg.as_bytes(),
+                    ArgsError::EmptyPushgatewayJob,
+                )?);
+            }
+            ArgState::ExpectPushgatewayInstance => {
+                state = ArgState::Initial;
+                pushgateway_instance = Some(parse_grouping_value(
+                    arg.as_bytes(),
+                    ArgsError::EmptyPushgatewayInstance,
+                )?);
+            }
+            ArgState::ExpectPushgatewayUsername => {
+                state = ArgState::Initial;
+                pushgateway_username = Some(parse_username(
+                    arg.as_bytes(),
+                    ArgsError::InvalidPushgatewayUsername,
+                )?);
+            }
+            ArgState::ExpectPushgatewayPasswordFile => {
+                state = ArgState::Initial;
+                pushgateway_password_file = Some(parse_path(
+                    arg.as_bytes(),
+                    ArgsError::EmptyPushgatewayPasswordFile,
+                )?);
+            }
         }
     }
 
     match state {
         ArgState::Initial => {
+            // Client certificates are required unless said otherwise.
+            let client_auth = match (client_ca, client_auth_required) {
+                (None, None) => None,
+                (None, Some(_)) => return Err(ArgsError::ClientAuthWithoutClientCa),
+                (Some(ca), required) => Some(ClientAuthOptions {
+                    ca,
+                    required: required.unwrap_or(true),
+                }),
+            };
+
+            let has_tls_option = ocsp_response.is_some()
+                || tls_min_version.is_some()
+                || tls_max_version.is_some()
+                || tls_cipher_suites.is_some()
+                || tls_session_tickets;
+
+            let defaults = TlsPolicy::default();
+            let policy = TlsPolicy {
+                min_version: tls_min_version.unwrap_or(defaults.min_version),
+                max_version: tls_max_version.unwrap_or(defaults.max_version),
+                cipher_suites: tls_cipher_suites.unwrap_or(defaults.cipher_suites),
+                session_tickets: tls_session_tickets,
+            };
+
+            if policy.min_version > policy.max_version {
+                return Err(ArgsError::ConflictingTlsVersions);
+            }
+
+            if policy.usable_cipher_suites().next().is_none() {
+                return Err(ArgsError::NoUsableTlsCipherSuites);
+            }
+
             let tls = match (certificate, private_key) {
+                (None, None) if client_auth.is_some() => return Err(ArgsError::ClientCaWithoutTls),
+                (None, None) if has_tls_option => return Err(ArgsError::TlsOptionWithoutTls),
                 (None, None) => None,
                 (None, Some(_)) => return Err(ArgsError::MissingCertificate),
                 (Some(_), None) => return Err(ArgsError::MissingPrivateKey),
                 (Some(certificate), Some(private_key)) => Some(TLSOptions {
                     certificate,
                     private_key,
+                    client_auth,
+                    ocsp_response,
+                    policy,
                 }),
             };
 
-            match (port, key_dir) {
-                // Show help if no arguments are given.
-                (None, None) => Err(ArgsError::ShowHelp),
-                (None, Some(_)) => Err(ArgsError::MissingPort),
-                (Some(_), None) => Err(ArgsError::MissingKeyDir),
-                (Some(port), Some(key_dir)) => Ok(Args::Parent(ParentArgs { port, key_dir, tls })),
+            let has_rate_limit_option = rate_limit.is_some()
+                || rate_limit_burst.is_some()
+                || rate_limit_ipv4_prefix.is_some()
+                || rate_limit_ipv6_prefix.is_some()
+                || key_rate_limit.is_some()
+                || !rate_limit_exempt.is_empty()
+                || auth_lockout_failures.is_some()
+                || auth_lockout_window.is_some()
+                || auth_lockout_duration.is_some()
+                || auth_lockout_max_duration.is_some();
+
+            let defaults = RateLimitPolicy::new();
+            let (count, seconds) = match rate_limit {
+                Some(rate) => rate,
+                None => (defaults.address.count, defaults.address.seconds),
+            };
+
+            let key = match key_rate_limit {
+                None if key_rate_limit_burst.is_some() => {
+                    return Err(ArgsError::MissingKeyRateLimit)
+                }
+                None => None,
+                Some((count, seconds)) => Some(RateLimit {
+                    count,
+                    seconds,
+                    burst: key_rate_limit_burst.unwrap_or(NonZeroU32::MIN),
+                }),
+            };
+
+            let lockout = AuthLockout {
+                failures: auth_lockout_failures.unwrap_or(defaults.lockout.failures),
+                window: auth_lockout_window.unwrap_or(defaults.lockout.window),
+                duration: auth_lockout_duration.unwrap_or(defaults.lockout.duration),
+                max_duration: auth_lockout_max_duration.unwrap_or(defaults.lockout.max_duration),
+            };
+
+            if lockout.duration > lockout.max_duration {
+                return Err(ArgsError::ConflictingAuthLockoutDurations);
             }
+
+            let rate_limit = RateLimitPolicy {
+                address: RateLimit {
+                    count,
+                    seconds,
+                    burst: rate_limit_burst.unwrap_or(defaults.address.burst),
+                },
+                ipv4_prefix_len: rate_limit_ipv4_prefix.unwrap_or(defaults.ipv4_prefix_len),
+                ipv6_prefix_len: rate_limit_ipv6_prefix.unwrap_or(defaults.ipv6_prefix_len),
+                key,
+                exempt: rate_limit_exempt,
+                lockout,
+            };
+
+            let server = match (port, key_dir) {
+                // TLS and rate limits are only used by the server.
+                (None, None) if tls.is_some() || has_rate_limit_option => {
+                    return Err(ArgsError::MissingPort)
+                }
+                (None, None) => None,
+                (None, Some(_)) => return Err(ArgsError::MissingPort),
+                (Some(_), None) => return Err(ArgsError::MissingKeyDir),
+                (Some(port), Some(key_dir)) => Some(ServerOptions {
+                    port,
+                    key_dir,
+                    tls,
+                    rate_limit,
+                }),
+            };
+
+            let auth = match (
+                remote_write_username,
+                remote_write_password_file,
+                remote_write_bearer_token_file,
+            ) {
+                (None, None, None) => None,
+                (Some(_), None, None) => return Err(ArgsError::MissingRemoteWritePasswordFile),
+                (None, Some(_), None) => return Err(ArgsError::MissingRemoteWriteUsername),
+                (Some(username), Some(password_file), None) => Some(PushAuthOptions::Basic {
+                    username,
+                    password_file,
+                }),
+                (None, None, Some(token_file)) => Some(PushAuthOptions::Bearer { token_file }),
+                (_, _, Some(_)) => return Err(ArgsError::ConflictingRemoteWriteAuth),
+            };
+
+            let remote_write = match remote_write_url {
+                None if remote_write_interval.is_some() || auth.is_some() => {
+                    return Err(ArgsError::MissingRemoteWriteUrl)
+                }
+                None => None,
+                Some(url) => Some(PushOptions {
+                    url,
+                    interval: remote_write_interval.unwrap_or(Duration::from_secs(60)),
+                    auth,
+                }),
+            };
+
+            let otlp = match otlp_url {
+                None if otlp_interval.is_some() || otlp_bearer_token_file.is_some() => {
+                    return Err(ArgsError::MissingOtlpUrl)
+                }
+                None => None,
+                Some(url) => Some(PushOptions {
+                    url,
+                    interval: otlp_interval.unwrap_or(Duration::from_secs(60)),
+                    auth: otlp_bearer_token_file
+                        .map(|token_file| PushAuthOptions::Bearer { token_file }),
+                }),
+            };
+
+            let statsd = match statsd_address {
+                None if statsd_interval.is_some() => return Err(ArgsError::MissingStatsdAddress),
+                None => None,
+                Some(address) => Some(StatsdOptions {
+                    address,
+                    interval: statsd_interval.unwrap_or(Duration::from_secs(10)),
+                }),
+            };
+
+            let textfile = match textfile_dir {
+                None if textfile_interval.is_some() => return Err(ArgsError::MissingTextfileDir),
+                None => None,
+                Some(dir) => Some(TextfileOptions {
+                    dir,
+                    interval: textfile_interval.unwrap_or(Duration::from_secs(15)),
+                }),
+            };
+
+            let pushgateway_auth = match (pushgateway_username, pushgateway_password_file) {
+                (None, None) => None,
+                (Some(_), None) => return Err(ArgsError::MissingPushgatewayPasswordFile),
+                (None, Some(_)) => return Err(ArgsError::MissingPushgatewayUsername),
+                (Some(username), Some(password_file)) => Some(PushAuthOptions::Basic {
+                    username,
+                    password_file,
+                }),
+            };
+
+            let pushgateway = match pushgateway_url {
+                None if pushgateway_interval.is_some()
+                    || pushgateway_job.is_some()
+                    || pushgateway_instance.is_some()
+                    || pushgateway_auth.is_some() =>
+                {
+                    return Err(ArgsError::MissingPushgatewayUrl)
+                }
+                None => None,
+                Some(url) => Some(PushgatewayOptions {
+                    push: PushOptions {
+                        url,
+                        interval: pushgateway_interval.unwrap_or(Duration::from_secs(60)),
+                        auth: pushgateway_auth,
+                    },
+                    job: pushgateway_job.unwrap_or_else(|| "journald-exporter".into()),
+                    instance: pushgateway_instance,
+                }),
+            };
+
+            // Show help if there's nothing to export metrics to.
+            if server.is_none()
+                && remote_write.is_none()
+                && otlp.is_none()
+                && statsd.is_none()
+                && textfile.is_none()
+                && pushgateway.is_none()
+            {
+                return Err(ArgsError::ShowHelp);
+            }
+
+            Ok(Args::Parent(ParentArgs {
+                server,
+                remote_write,
+                otlp,
+                statsd,
+                textfile,
+                pushgateway,
+                top_series,
+                relabel_config,
+                labels,
+                field_stats,
+            }))
         }
         ArgState::ExpectParentPort => Err(ArgsError::MissingPort),
         ArgState::ExpectKeyDir => Err(ArgsError::MissingKeyDir),
         ArgState::ExpectCertificate => Err(ArgsError::MissingCertificate),
         ArgState::ExpectPrivateKey => Err(ArgsError::MissingPrivateKey),
+        ArgState::ExpectClientCa => Err(ArgsError::MissingClientCa),
+        ArgState::ExpectClientAuth => Err(ArgsError::MissingClientAuth),
+        ArgState::ExpectOcspResponse => Err(ArgsError::MissingOcspResponse),
+        ArgState::ExpectTlsMinVersion => Err(ArgsError::MissingTlsMinVersion),
+        ArgState::ExpectTlsMaxVersion => Err(ArgsError::MissingTlsMaxVersion),
+        ArgState::ExpectTlsCipherSuites => Err(ArgsError::MissingTlsCipherSuites),
+        ArgState::ExpectRateLimit => Err(ArgsError::MissingRateLimit),
+        ArgState::ExpectRateLimitBurst => Err(ArgsError::MissingRateLimitBurst),
+        ArgState::ExpectRateLimitIpv4Prefix => Err(ArgsError::MissingRateLimitIpv4Prefix),
+        ArgState::ExpectRateLimitIpv6Prefix => Err(ArgsError::MissingRateLimitIpv6Prefix),
+        ArgState::ExpectKeyRateLimit => Err(ArgsError::MissingKeyRateLimit),
+        ArgState::ExpectKeyRateLimitBurst => Err(ArgsError::MissingKeyRateLimitBurst),
+        ArgState::ExpectRateLimitExempt => Err(ArgsError::MissingRateLimitExempt),
+        ArgState::ExpectAuthLockoutFailures => Err(ArgsError::MissingAuthLockoutFailures),
+        ArgState::ExpectAuthLockoutWindow => Err(ArgsError::MissingAuthLockoutWindow),
+        ArgState::ExpectAuthLockoutDuration => Err(ArgsError::MissingAuthLockoutDuration),
+        ArgState::ExpectAuthLockoutMaxDuration => Err(ArgsError::MissingAuthLockoutMaxDuration),
+        ArgState::ExpectTopSeries => Err(ArgsError::MissingTopSeries),
+        ArgState::ExpectRelabelConfig => Err(ArgsError::MissingRelabelConfig),
+        ArgState::ExpectLabel => Err(ArgsError::MissingLabel),
+        ArgState::ExpectRemoteWriteUrl => Err(ArgsError::MissingRemoteWriteUrl),
+        ArgState::ExpectRemoteWriteInterval => Err(ArgsError::MissingRemoteWriteInterval),
+        ArgState::ExpectRemoteWriteUsername => Err(ArgsError::MissingRemoteWriteUsername),
+        ArgState::ExpectRemoteWritePasswordFile => Err(ArgsError::MissingRemoteWritePasswordFile),
+        ArgState::ExpectRemoteWriteBearerTokenFile => {
+            Err(ArgsError::MissingRemoteWriteBearerTokenFile)
+        }
+        ArgState::ExpectOtlpUrl => Err(ArgsError::MissingOtlpUrl),
+        ArgState::ExpectOtlpInterval => Err(ArgsError::MissingOtlpInterval),
+        ArgState::ExpectOtlpBearerTokenFile => Err(ArgsError::MissingOtlpBearerTokenFile),
+        ArgState::ExpectStatsdAddress => Err(ArgsError::MissingStatsdAddress),
+        ArgState::ExpectStatsdInterval => Err(ArgsError::MissingStatsdInterval),
+        ArgState::ExpectTextfileDir => Err(ArgsError::MissingTextfileDir),
+        ArgState::ExpectTextfileInterval => Err(ArgsError::MissingTextfileInterval),
+        ArgState::ExpectPushgatewayUrl => Err(ArgsError::MissingPushgatewayUrl),
+        ArgState::ExpectPushgatewayInterval => Err(ArgsError::MissingPushgatewayInterval),
+        ArgState::ExpectPushgatewayJob => Err(ArgsError::MissingPushgatewayJob),
+        ArgState::ExpectPushgatewayInstance => Err(ArgsError::MissingPushgatewayInstance),
+        ArgState::ExpectPushgatewayUsername => Err(ArgsError::MissingPushgatewayUsername),
+        ArgState::ExpectPushgatewayPasswordFile => Err(ArgsError::MissingPushgatewayPasswordFile),
     }
 }
@@ -1373,12 +1373,27 @@ fn short_normal_certificate_then_short_normal_private_key_returns_success() {
             "some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1397,12 +1412,27 @@ fn short_normal_certificate_then_short_eq_normal_private_key_returns_success() {
             "-K=some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1422,12 +1452,27 @@ fn short_normal_certificate_then_long_normal_private_key_returns_success() {
             "some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1446,12 +1491,27 @@ fn short_normal_certificate_then_long_eq_normal_private_key_returns_success() {
             "--private-key=some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1470,12 +1530,27 @@ fn short_eq_normal_certificate_then_short_normal_private_key_returns_success() {
             "some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1493,12 +1568,27 @@ fn short_eq_normal_certificate_then_short_eq_normal_private_key_returns_success(
             "-K=some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1517,12 +1607,27 @@ fn short_eq_normal_certificate_then_long_normal_private_key_returns_success() {
             "some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1540,12 +1645,27 @@ fn short_eq_normal_certificate_then_long_eq_normal_private_key_returns_success()
             "--private-key=some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1565,12 +1685,27 @@ fn long_normal_certificate_then_short_normal_private_key_returns_success() {
             "some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1589,12 +1724,27 @@ fn long_normal_certificate_then_short_eq_normal_private_key_returns_success() {
             "-K=some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1614,12 +1764,27 @@ fn long_normal_certificate_then_long_normal_private_key_returns_success() {
             "some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1638,12 +1803,27 @@ fn long_normal_certificate_then_long_eq_normal_private_key_returns_success() {
             "--private-key=some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1662,12 +1842,27 @@ fn long_eq_normal_certificate_then_short_normal_private_key_returns_success() {
             "some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1685,12 +1880,27 @@ fn long_eq_normal_certificate_then_short_eq_normal_private_key_returns_success()
             "-K=some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1709,12 +1919,27 @@ fn long_eq_normal_certificate_then_long_normal_private_key_returns_success() {
             "some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1732,12 +1957,27 @@ fn long_eq_normal_certificate_then_long_eq_normal_private_key_returns_success()
             "--private-key=some/key.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1757,12 +1997,27 @@ fn short_normal_private_key_then_short_normal_certificate_returns_success() {
             "some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1781,12 +2036,27 @@ fn short_eq_normal_private_key_then_short_normal_certificate_returns_success() {
             "some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1806,12 +2076,27 @@ fn long_normal_private_key_then_short_normal_certificate_returns_success() {
             "some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1830,12 +2115,27 @@ fn long_eq_normal_private_key_then_short_normal_certificate_returns_success() {
             "some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1854,12 +2154,27 @@ fn short_normal_private_key_then_short_eq_normal_certificate_returns_success() {
             "-C=some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1877,12 +2192,27 @@ fn short_eq_normal_private_key_then_short_eq_normal_certificate_returns_success(
             "-C=some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1901,12 +2231,27 @@ fn long_normal_private_key_then_short_eq_normal_certificate_returns_success() {
             "-C=some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1924,12 +2269,27 @@ fn long_eq_normal_private_key_then_short_eq_normal_certificate_returns_success()
             "-C=some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1949,12 +2309,27 @@ fn short_normal_private_key_then_long_normal_certificate_returns_success() {
             "some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1973,12 +2348,27 @@ fn short_eq_normal_private_key_then_long_normal_certificate_returns_success() {
             "some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1998,12 +2388,27 @@ fn long_normal_private_key_then_long_normal_certificate_returns_success() {
             "some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -2022,12 +2427,27 @@ fn long_eq_normal_private_key_then_long_normal_certificate_returns_success() {
             "some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -2046,12 +2466,27 @@ fn short_normal_private_key_then_long_eq_normal_certificate_returns_success() {
             "--certificate=some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -2069,12 +2504,27 @@ fn short_eq_normal_private_key_then_long_eq_normal_certificate_returns_success()
             "--certificate=some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -2093,12 +2543,27 @@ fn long_normal_private_key_then_long_eq_normal_certificate_returns_success() {
             "--certificate=some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -2116,12 +2581,27 @@ fn long_eq_normal_private_key_then_long_eq_normal_certificate_returns_success()
             "--certificate=some/cert.pem"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: Some(TLSOptions {
-                certificate: std::path::PathBuf::from("some/cert.pem"),
-                private_key: std::path::PathBuf::from("some/key.pem"),
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: Some(TLSOptions {
+                    certificate: std::path::PathBuf::from("some/cert.pem"),
+                    private_key: std::path::PathBuf::from("some/key.pem"),
+                    client_auth: None,
+                    ocsp_response: None,
+                    policy: crate::state::TlsPolicy::default(),
+                }),
+                rate_limit: crate::state::RateLimitPolicy::new(),
             }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -651,9 +651,21 @@ fn short_port_then_short_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-p", "123", "-k", "some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -663,9 +675,21 @@ fn short_port_then_short_eq_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-p", "123", "-k=some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -675,9 +699,21 @@ fn short_port_then_long_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-p", "123", "--key-dir", "some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -687,9 +723,21 @@ fn short_port_then_long_eq_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-p", "123", "--key-dir=some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -699,9 +747,21 @@ fn short_eq_port_then_short_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-p=123", "-k", "some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -711,9 +771,21 @@ fn short_eq_port_then_short_eq_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-p=123", "-k=some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -723,9 +795,21 @@ fn short_eq_port_then_long_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-p=123", "--key-dir", "some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -735,9 +819,21 @@ fn short_eq_port_then_long_eq_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-p=123", "--key-dir=some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -747,9 +843,21 @@ fn long_port_then_short_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--port", "123", "-k", "some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -759,9 +867,21 @@ fn long_port_then_short_eq_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--port", "123", "-k=some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -777,9 +897,21 @@ fn long_port_then_long_normal_key_dir_returns_success() {
             "some/dir"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -789,9 +921,21 @@ fn long_port_then_long_eq_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--port", "123", "--key-dir=some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -801,9 +945,21 @@ fn long_eq_port_then_short_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--port=123", "-k", "some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -813,9 +969,21 @@ fn long_eq_port_then_short_eq_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--port=123", "-k=some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -825,9 +993,21 @@ fn long_eq_port_then_long_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--port=123", "--key-dir", "some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -837,9 +1017,21 @@ fn long_eq_port_then_long_eq_normal_key_dir_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--port=123", "--key-dir=some/dir"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -849,9 +1041,21 @@ fn short_normal_key_dir_then_short_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-k", "some/dir", "-p", "123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -861,9 +1065,21 @@ fn short_eq_normal_key_dir_then_short_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-k=some/dir", "-p", "123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -873,9 +1089,21 @@ fn long_normal_key_dir_then_short_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--key-dir", "some/dir", "-p", "123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -885,9 +1113,21 @@ fn long_eq_normal_key_dir_then_short_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--key-dir=some/dir", "-p", "123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -897,9 +1137,21 @@ fn short_normal_key_dir_then_short_eq_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-k", "some/dir", "-p=123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -909,9 +1161,21 @@ fn short_eq_normal_key_dir_then_short_eq_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-k=some/dir", "-p=123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -921,9 +1185,21 @@ fn long_normal_key_dir_then_short_eq_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--key-dir", "some/dir", "-p=123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -933,9 +1209,21 @@ fn long_eq_normal_key_dir_then_short_eq_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--key-dir=some/dir", "-p=123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -945,9 +1233,21 @@ fn short_normal_key_dir_then_long_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-k", "some/dir", "--port", "123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -957,9 +1257,21 @@ fn short_eq_normal_key_dir_then_long_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-k=some/dir", "--port", "123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -975,9 +1287,21 @@ fn long_normal_key_dir_then_long_port_returns_success() {
             "123"
         ]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -987,9 +1311,21 @@ fn long_eq_normal_key_dir_then_long_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--key-dir=some/dir", "--port", "123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -999,9 +1335,21 @@ fn short_normal_key_dir_then_long_eq_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-k", "some/dir", "--port=123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1011,9 +1359,21 @@ fn short_eq_normal_key_dir_then_long_eq_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "-k=some/dir", "--port=123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1023,9 +1383,21 @@ fn long_normal_key_dir_then_long_eq_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--key-dir", "some/dir", "--port=123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
@@ -1035,9 +1407,21 @@ fn long_eq_normal_key_dir_then_long_eq_port_returns_success() {
     assert_eq!(
         parse_args(&["journald-exporter", "--key-dir=some/dir", "--port=123"]),
         Ok(Args::Parent(ParentArgs {
-            port: std::num::NonZeroU16::new(123).unwrap(),
-            key_dir: std::path::PathBuf::from("some/dir"),
-            tls: None,
+            server: Some(ServerOptions {
+                port: std::num::NonZeroU16::new(123).unwrap(),
+                key_dir: std::path::PathBuf::from("some/dir"),
+                tls: None,
+                rate_limit: crate::state::RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            top_series: None,
+            relabel_config: None,
+            labels: Vec::new(),
+            field_stats: false,
         })),
     );
 }
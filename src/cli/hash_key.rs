@@ -0,0 +1,30 @@
+use crate::prelude::*;
+
+use crate::ffi::ExitCode;
+use crate::ffi::ExitResult;
+
+/// Reads a key from standard input and prints the `key_hash` line to replace its `key` line with.
+/// The key's read from standard input so it doesn't end up in the shell history or process list.
+pub fn hash_key_from_stdin() -> io::Result<ExitResult> {
+    let mut key = Vec::new();
+    io::stdin().read_to_end(&mut key)?;
+
+    let result = match hash_hex_key(trim_ascii(&key)) {
+        Some(hash) => {
+            let mut line = b"key_hash=".to_vec();
+            line.extend_from_slice(&hash.format());
+            line.push(b'\n');
+            io::stdout().write_all(&line)?;
+            ExitCode(0)
+        }
+        None => {
+            let msg =
+                format!("Key must be an even number of hexadecimal digits, up to {MAX_KEY_LEN}.\n");
+            io::stderr().write_all(msg.as_bytes())?;
+            ExitCode(1)
+        }
+    };
+
+    key.fill(0);
+    Ok(ExitResult::Code(result))
+}
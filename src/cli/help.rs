@@ -16,6 +16,12 @@ pub static HELP_STRING: &str = concat!(
     r#"
 
 Usage: journald-exporter --port PORT --key-dir KEY_DIRECTORY
+       journald-exporter --remote-write-url URL
+       journald-exporter --otlp-url URL
+       journald-exporter --statsd-address ADDRESS
+       journald-exporter --textfile-dir DIRECTORY
+       journald-exporter --pushgateway-url URL
+       journald-exporter --hash-key < KEY_FILE
 
 Arguments:
 
@@ -33,27 +39,204 @@ Arguments:
     The PEM-encoded file with the private key to use for HTTPS. Must be used
     in conjunction with `-C`/`--certificate`.
 
+--client-ca CA_FILE
+    The PEM-encoded file with the CA certificates to verify client
+    certificates against. Requires `-C`/`--certificate` and
+    `-K`/`--private-key`. Certificates whose subject common name is mapped to
+    a key via its `client_cn` field are authorized as that key.
+
+--client-auth MODE
+    Whether clients must present a certificate, either `require` (the
+    default) or `optional`. With `optional`, clients without a certificate,
+    or with one not mapped to any key, authorize with a key like usual.
+    Requires `--client-ca`.
+
+--ocsp-response OCSP_FILE
+    A DER-encoded OCSP response to staple to the certificate. Requires
+    `-C`/`--certificate` and `-K`/`--private-key`, and is watched along with
+    them.
+
+--tls-min-version VERSION
+--tls-max-version VERSION
+    The minimum and maximum TLS versions to accept, either `1.2` or `1.3`.
+    Default to `1.2` and `1.3`, respectively.
+
+--tls-cipher-suites SUITES
+    A comma-separated list of the cipher suites to accept, by IANA name, like
+    `TLS_AES_256_GCM_SHA384,TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384`. By
+    default, all suites supported are accepted. At least one of them must be
+    usable with the TLS versions accepted.
+
+--tls-session-tickets
+    Issue stateless session tickets for resuming TLS sessions. Off by
+    default.
+
+--rate-limit COUNT/SECONDS
+--rate-limit-burst COUNT
+    How many metrics requests each client can make, refilling at COUNT every
+    SECONDS seconds and holding up to `--rate-limit-burst` at once. Default to
+    `1/1` and 1, respectively.
+
+--rate-limit-ipv4-prefix LENGTH
+--rate-limit-ipv6-prefix LENGTH
+    The prefix length addresses are grouped by for rate limiting, so every
+    address in the same range shares one limit. Default to 32 and 64,
+    respectively.
+
+--key-rate-limit COUNT/SECONDS
+--key-rate-limit-burst COUNT
+    Also limit each named API key to this rate across all addresses. Unnamed
+    keys are only limited by address. Off by default, and the burst defaults
+    to 1.
+
+--rate-limit-exempt ADDRESS_OR_RANGE
+    An address or CIDR range, like `10.0.0.0/8`, that's never rate limited.
+    Can be specified multiple times.
+
+--auth-lockout-failures COUNT
+--auth-lockout-window SECONDS
+    Lock out an address prefix after COUNT failed authorization attempts
+    within SECONDS seconds. Locked out clients get a 429 without their key
+    being checked. Default to 10 and 60, respectively.
+
+--auth-lockout-duration SECONDS
+--auth-lockout-max-duration SECONDS
+    How long the first lockout lasts, and the most any lockout can last, as
+    each one after the first doubles. Default to 60 and 3600, respectively.
+
+--top-series COUNT
+    Only emit the COUNT largest series for each of the per-message metrics,
+    summing the rest into a single `__other__` series per priority. By
+    default, all series are emitted.
+
+--relabel-config RELABEL_CONFIG_FILE
+    A file with rules to relabel messages with before they're counted. See
+    the README for the format. Rules can only rewrite the existing `service`,
+    `priority`, `uid`, and `gid` labels, not add new labels.
+
+--label NAME=VALUE
+    A label to add to every exported series, like `--label env=prod`. Can be
+    specified multiple times. Names must match `[a-zA-Z_][a-zA-Z0-9_]*` and
+    can't collide with the labels the exporter already emits.
+
+--field-stats
+    Also emit per-field breakdowns of the fields read, bytes read, and
+    unreadable and corrupted fields, with a `field` label set to the journal
+    field name. This also reads `_CMDLINE`, which isn't otherwise needed.
+
+--remote-write-url URL
+    Periodically push metrics to this Prometheus remote write endpoint, like
+    `https://prometheus.example.com/api/v1/write`. When given, `--port` and
+    `--key-dir` are optional, and the metrics server is only started if
+    they're given.
+
+--remote-write-interval SECONDS
+    How often to push metrics via remote write. Defaults to 60 seconds.
+
+--remote-write-username USERNAME
+--remote-write-password-file PASSWORD_FILE
+    The username and a file with the password to use for HTTP basic
+    authorization when pushing via remote write. Must be used together.
+
+--remote-write-bearer-token-file TOKEN_FILE
+    A file with a bearer token to use for authorization when pushing via
+    remote write. Cannot be used with basic authorization.
+
+--otlp-url URL
+    Periodically export metrics to this OTLP/HTTP metrics endpoint, like
+    `http://localhost:4318/v1/metrics`, as protobuf. As with
+    `--remote-write-url`, `--port` and `--key-dir` are optional when given.
+
+--otlp-interval SECONDS
+    How often to export metrics via OTLP. Defaults to 60 seconds.
+
+--otlp-bearer-token-file TOKEN_FILE
+    A file with a bearer token to use for authorization when exporting via
+    OTLP.
+
+--statsd-address ADDRESS
+    Periodically send the change in each counter as StatsD counters with
+    DogStatsD tags, either over UDP to `HOST:PORT` or to a Unix datagram
+    socket at `unix:///PATH`. As with `--remote-write-url`, `--port` and
+    `--key-dir` are optional when given.
+
+--statsd-interval SECONDS
+    How often to send metrics via StatsD. Defaults to 10 seconds.
+
+--textfile-dir DIRECTORY
+    Periodically write metrics in the Prometheus text format to
+    `DIRECTORY/journald.prom`, for node_exporter's textfile collector. As
+    with `--remote-write-url`, `--port` and `--key-dir` are optional when
+    given.
+
+--textfile-interval SECONDS
+    How often to write the textfile. Defaults to 15 seconds.
+
+--pushgateway-url URL
+    Periodically push metrics to this Prometheus Pushgateway, like
+    `http://pushgateway.example.com:9091`, replacing the whole group each
+    time. A final push is made on `SIGTERM`. As with `--remote-write-url`,
+    `--port` and `--key-dir` are optional when given.
+
+--pushgateway-interval SECONDS
+    How often to push metrics to the Pushgateway. Defaults to 60 seconds.
+
+--pushgateway-job JOB
+--pushgateway-instance INSTANCE
+    The `job` and `instance` labels to group pushed metrics under. Default to
+    `journald-exporter` and the hostname.
+
+--pushgateway-username USERNAME
+--pushgateway-password-file PASSWORD_FILE
+    The username and a file with the password to use for HTTP basic
+    authorization when pushing to the Pushgateway. Must be used together.
+
+--hash-key
+    Read an API key from standard input and print a `key_hash=` line to use
+    in its key file instead of the key itself, then exit.
+
 Notes:
 
   - When run as root, a `journald-exporter` user is expected to exist, and the
     web server is opened and run under that user. When run normally, any user
     will do, and the web server is run with that user's privileges.
 
-  - The server exposes a single `/metrics` endpoint that returns metrics.
-    Authorization uses the HTTP basic authorization protocol, with a user of
-    `metrics` and a password that's one of the accepted API keys. The endpoint
-    is rate-limited to one request per second per source IP, and it does not
-    attempt to inspect either of the Forwarded or X-Forwarded-For headers to
-    determine the "true" client IP.
+  - The server exposes a `/metrics` endpoint that returns metrics, along with
+    `/healthz` and `/readyz` health checks that need no authorization and
+    accept both GET and HEAD. Authorization for `/metrics` uses either the
+    HTTP basic authorization protocol, with the key's configured user (default
+    `metrics`) and a password that's one of the accepted API keys, or a bearer
+    token that's one of the accepted API keys. The endpoint is rate-limited to
+    one request per second per source IP (or /64 for IPv6) by default, and it
+    does not attempt to inspect either of the Forwarded or X-Forwarded-For
+    headers to determine the "true" client IP.
 
   - The key directory is watched, so new API keys can be added and removed
     without having to restart the server. It can also have multiple key files,
     in which all keys in them are accepted, allowing for zero downtime key
-    updates. This is only for the key directory - the HTTPS certificate and
-    private key files cannot be updated this way.
+    updates. The HTTPS certificate and private key files are watched too, and
+    new connections use the new pair once both files match each other. The
+    client CA file cannot be updated this way.
+
+  - Each key file holds one API key. It can be just the key itself, in hex,
+    or `field=value` lines, with `#` starting a comment line:
+
+        key=0123456789abcdef0123456789abcdef
+        name=grafana-agent
+        user=grafana
+
+    `key` is the key in hex, or `key_hash` a salted hash of it in its place.
+    One of the two is required unless `client_cn` maps a client certificate's
+    subject common name to the key instead. `name` labels its scrapes and
+    defaults to the file name, and `user` is the username it must be sent
+    with and defaults to `metrics`. `scope_services`, `scope_users`, and
+    `scope_families` limit what it can see to the comma-separated patterns
+    given. Keys are always sent in hex, as the basic authorization password
+    or as the bearer token.
 
-  - API keys are specified in hex, both within the key files and as the
-    "password" for authorization.
+  - To keep a key off the host, pipe it into `journald-exporter --hash-key`,
+    and write the `key_hash=...` line it prints to the key file in place of
+    the `key=...` line.
 
 License:
 
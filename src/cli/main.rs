@@ -4,12 +4,14 @@
 use crate::prelude::*;
 
 use super::args::Args;
+use super::hash_key::hash_key_from_stdin;
 use crate::child::start_child;
 use crate::cli::args::parse_args;
 use crate::ffi::normalize_errno;
 use crate::ffi::request_signal_when_parent_terminates;
 use crate::ffi::Signal;
 use crate::parent::start_parent;
+use crate::pusher::start_pusher;
 
 fn eprintln(msg: Cow<str>) {
     let mut msg = msg.into_owned().into_bytes();
@@ -42,6 +44,8 @@ pub fn main() {
 
     let result = match args {
         Args::Child => start_child(),
+        Args::Pusher => start_pusher(),
+        Args::HashKey => hash_key_from_stdin(),
         Args::Parent(args) => start_parent(args),
     };
 
@@ -1,5 +1,6 @@
 pub mod args;
 #[cfg(test)]
 mod args_tests;
+mod hash_key;
 mod help;
 pub mod main;
@@ -1,4 +1,7 @@
 mod checkpoint;
+mod http_client;
+mod process_stats;
+mod protobuf;
 mod sd_types;
 mod thread;
 mod tiny_channel;
@@ -8,6 +11,9 @@ mod user_group_tests;
 mod utils;
 
 pub use self::checkpoint::*;
+pub use self::http_client::*;
+pub use self::process_stats::*;
+pub use self::protobuf::*;
 pub use self::sd_types::*;
 pub use self::thread::*;
 pub use self::tiny_channel::*;
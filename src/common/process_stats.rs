@@ -0,0 +1,208 @@
+use crate::prelude::*;
+
+use crate::ffi::clock_ticks_per_second;
+use crate::ffi::page_size;
+
+/// Resource usage of a single process, as read from `/proc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessStats {
+    pub cpu_time: Duration,
+    // Relative to the Unix epoch.
+    pub start_time: Duration,
+    pub resident_memory_bytes: u64,
+    pub virtual_memory_bytes: u64,
+    pub open_fds: u64,
+    pub max_fds: u64,
+    pub threads: u64,
+}
+
+/// Either side is `None` if it couldn't be read, or in the child's case, if it's not running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessSnapshot {
+    pub parent: Option<ProcessStats>,
+    pub child: Option<ProcessStats>,
+}
+
+impl ProcessSnapshot {
+    pub const fn empty() -> ProcessSnapshot {
+        ProcessSnapshot {
+            parent: None,
+            child: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ProcStat {
+    cpu_ticks: u64,
+    threads: u64,
+    start_ticks: u64,
+    virtual_memory_bytes: u64,
+    resident_pages: u64,
+}
+
+// See `proc_pid_stat(5)` for the layout. The command name is wrapped in parentheses, but can
+// itself contain both spaces and parentheses, so fields are counted from the last `)`.
+fn parse_proc_stat(data: &str) -> Option<ProcStat> {
+    let (_, rest) = data.rsplit_once(')')?;
+    // Field 3 (the state) is the first one after the command name.
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let field = |n: usize| -> Option<u64> { fields.get(n.wrapping_sub(3))?.parse().ok() };
+
+    Some(ProcStat {
+        cpu_ticks: field(14)?.saturating_add(field(15)?),
+        threads: field(20)?,
+        start_ticks: field(22)?,
+        virtual_memory_bytes: field(23)?,
+        resident_pages: field(24)?,
+    })
+}
+
+fn parse_boot_time(data: &str) -> Option<u64> {
+    data.lines()
+        .find_map(|line| line.strip_prefix("btime "))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// Only the soft limit matters, as that's what `open` is actually bound by.
+fn parse_max_open_files(data: &str) -> Option<u64> {
+    let limit = data
+        .lines()
+        .find_map(|line| line.strip_prefix("Max open files"))?
+        .split_whitespace()
+        .next()?;
+
+    match limit {
+        "unlimited" => Some(u64::MAX),
+        limit => limit.parse().ok(),
+    }
+}
+
+fn ticks_to_duration(ticks: u64, ticks_per_second: u64) -> Duration {
+    let secs = ticks.checked_div(ticks_per_second).unwrap_or(0);
+    let nanos = ticks
+        .checked_rem(ticks_per_second)
+        .unwrap_or(0)
+        .saturating_mul(1_000_000_000)
+        .checked_div(ticks_per_second)
+        .unwrap_or(0);
+
+    Duration::new(secs, u32::try_from(nanos).unwrap_or(0))
+}
+
+fn invalid_data(path: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Could not parse {path}"))
+}
+
+/// Reads the stats of the given process, or of the current process if `pid` is `None`.
+pub fn read_process_stats(pid: Option<u32>) -> io::Result<ProcessStats> {
+    let dir = match pid {
+        Some(pid) => format!("/proc/{pid}"),
+        None => String::from("/proc/self"),
+    };
+
+    let stat_path = format!("{dir}/stat");
+    let stat = parse_proc_stat(&std::fs::read_to_string(&stat_path)?)
+        .ok_or_else(|| invalid_data(&stat_path))?;
+
+    let limits_path = format!("{dir}/limits");
+    let max_fds = parse_max_open_files(&std::fs::read_to_string(&limits_path)?)
+        .ok_or_else(|| invalid_data(&limits_path))?;
+
+    let boot_time = parse_boot_time(&std::fs::read_to_string("/proc/stat")?)
+        .ok_or_else(|| invalid_data("/proc/stat"))?;
+
+    let mut open_fds = 0_u64;
+    for entry in std::fs::read_dir(format!("{dir}/fd"))? {
+        entry?;
+        open_fds = open_fds.saturating_add(1);
+    }
+
+    let ticks_per_second = clock_ticks_per_second();
+
+    Ok(ProcessStats {
+        cpu_time: ticks_to_duration(stat.cpu_ticks, ticks_per_second),
+        start_time: Duration::from_secs(boot_time)
+            .saturating_add(ticks_to_duration(stat.start_ticks, ticks_per_second)),
+        resident_memory_bytes: stat.resident_pages.saturating_mul(page_size()),
+        virtual_memory_bytes: stat.virtual_memory_bytes,
+        open_fds,
+        max_fds,
+        threads: stat.threads,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAT: &str = "1234 (journald (x) exporter) S 1 1234 1234 0 -1 4194560 1630 0 0 0 \
+        250 175 0 0 20 0 3 0 4567 12345678 890 18446744073709551615 1 1 0 0 0 0 0 4096 17664 \
+        0 0 0 17 2 0 0 0 0 0 0 0 0 0 0 0 0 0\n";
+
+    #[test]
+    fn parses_proc_stat_with_odd_command_name() {
+        assert_eq!(
+            parse_proc_stat(STAT),
+            Some(ProcStat {
+                cpu_ticks: 425,
+                threads: 3,
+                start_ticks: 4567,
+                virtual_memory_bytes: 12345678,
+                resident_pages: 890,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_proc_stat() {
+        assert_eq!(parse_proc_stat("1234 (foo) S 1 1234 1234 0 -1\n"), None);
+        assert_eq!(parse_proc_stat(""), None);
+    }
+
+    #[test]
+    fn parses_boot_time() {
+        let data = "cpu  1 2 3 4\nintr 12345\nctxt 678\nbtime 1700000000\nprocesses 42\n";
+        assert_eq!(parse_boot_time(data), Some(1700000000));
+        assert_eq!(parse_boot_time("cpu  1 2 3 4\n"), None);
+    }
+
+    #[test]
+    fn parses_max_open_files_soft_limit() {
+        let data = "\
+Limit                     Soft Limit           Hard Limit           Units
+Max processes             63356                63356                processes
+Max open files            1024                 524288               files
+Max locked memory         8388608              8388608              bytes
+";
+        assert_eq!(parse_max_open_files(data), Some(1024));
+    }
+
+    #[test]
+    fn parses_unlimited_max_open_files() {
+        let data = "Max open files            unlimited            unlimited            files\n";
+        assert_eq!(parse_max_open_files(data), Some(u64::MAX));
+    }
+
+    #[test]
+    fn converts_ticks_to_duration() {
+        assert_eq!(ticks_to_duration(0, 100), Duration::ZERO);
+        assert_eq!(ticks_to_duration(425, 100), Duration::from_millis(4250));
+        assert_eq!(ticks_to_duration(1, 3), Duration::from_nanos(333_333_333));
+    }
+
+    // Skip in Miri due to the FFI calls and file system access.
+    #[cfg(not(miri))]
+    #[test]
+    fn reads_own_process_stats() {
+        let stats = read_process_stats(None).unwrap();
+        assert!(stats.resident_memory_bytes > 0);
+        assert!(stats.virtual_memory_bytes >= stats.resident_memory_bytes);
+        assert!(stats.open_fds > 0);
+        assert!(stats.max_fds >= stats.open_fds);
+        assert!(stats.threads > 0);
+        assert!(stats.start_time > Duration::from_secs(1_600_000_000));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::prelude::*;
+
+// Just enough of the protobuf wire format to encode the few push payloads this emits. Nested
+// messages are written into their own buffer first so their length is known up front.
+
+const WIRE_TYPE_VARINT: u64 = 0;
+const WIRE_TYPE_I64: u64 = 1;
+const WIRE_TYPE_LEN: u64 = 2;
+
+pub struct ProtobufWriter {
+    result: Vec<u8>,
+}
+
+impl ProtobufWriter {
+    pub fn new() -> Self {
+        Self { result: Vec::new() }
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.result
+    }
+
+    fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.result.push(truncate_u64_u8(value) | 0x80);
+            value >>= 7;
+        }
+        self.result.push(truncate_u64_u8(value));
+    }
+
+    fn write_tag(&mut self, field: u32, wire_type: u64) {
+        self.write_varint(u64::from(field) << 3 | wire_type);
+    }
+
+    pub fn write_uint64(&mut self, field: u32, value: u64) {
+        self.write_tag(field, WIRE_TYPE_VARINT);
+        self.write_varint(value);
+    }
+
+    pub fn write_int64(&mut self, field: u32, value: i64) {
+        self.write_uint64(field, reinterpret_i64_u64(value));
+    }
+
+    pub fn write_fixed64(&mut self, field: u32, value: u64) {
+        self.write_tag(field, WIRE_TYPE_I64);
+        self.result.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_sfixed64(&mut self, field: u32, value: i64) {
+        self.write_fixed64(field, reinterpret_i64_u64(value));
+    }
+
+    pub fn write_bool(&mut self, field: u32, value: bool) {
+        self.write_uint64(field, u64::from(value));
+    }
+
+    pub fn write_double(&mut self, field: u32, value: f64) {
+        self.write_fixed64(field, value.to_bits());
+    }
+
+    pub fn write_bytes(&mut self, field: u32, value: &[u8]) {
+        self.write_tag(field, WIRE_TYPE_LEN);
+        self.write_varint(zero_extend_usize_u64(value.len()));
+        self.result.extend_from_slice(value);
+    }
+
+    pub fn write_message(&mut self, field: u32, message: ProtobufWriter) {
+        self.write_bytes(field, &message.result);
+    }
+
+    // For streams of messages, each prefixed with just its length and no tag.
+    pub fn write_length_delimited(&mut self, message: ProtobufWriter) {
+        self.write_varint(zero_extend_usize_u64(message.result.len()));
+        self.result.extend_from_slice(&message.result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_small_varints_in_one_byte() {
+        let mut writer = ProtobufWriter::new();
+        writer.write_uint64(1, 0);
+        writer.write_uint64(2, 127);
+        assert_eq!(writer.finish(), [0x08, 0x00, 0x10, 0x7F]);
+    }
+
+    #[test]
+    fn writes_large_varints_in_groups_of_seven_bits() {
+        let mut writer = ProtobufWriter::new();
+        writer.write_uint64(1, 300);
+        writer.write_uint64(1, u64::MAX);
+        assert_eq!(
+            writer.finish(),
+            [
+                0x08, 0xAC, 0x02, //
+                0x08, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01,
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_negative_int64_as_ten_byte_varint() {
+        let mut writer = ProtobufWriter::new();
+        writer.write_int64(2, -1);
+        assert_eq!(
+            writer.finish(),
+            [0x10, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]
+        );
+    }
+
+    #[test]
+    fn writes_sfixed64_as_little_endian_twos_complement() {
+        let mut writer = ProtobufWriter::new();
+        writer.write_sfixed64(6, -2);
+        assert_eq!(
+            writer.finish(),
+            [0x31, 0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+    }
+
+    #[test]
+    fn writes_bools_as_varints() {
+        let mut writer = ProtobufWriter::new();
+        writer.write_bool(3, true);
+        writer.write_bool(3, false);
+        assert_eq!(writer.finish(), [0x18, 0x01, 0x18, 0x00]);
+    }
+
+    #[test]
+    fn writes_doubles_as_little_endian_fixed64() {
+        let mut writer = ProtobufWriter::new();
+        writer.write_double(1, 1.5);
+        assert_eq!(
+            writer.finish(),
+            [0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF8, 0x3F]
+        );
+    }
+
+    #[test]
+    fn writes_length_delimited_fields() {
+        let mut inner = ProtobufWriter::new();
+        inner.write_bytes(1, b"abc");
+
+        let mut writer = ProtobufWriter::new();
+        writer.write_message(3, inner);
+        writer.write_bytes(4, b"");
+
+        assert_eq!(
+            writer.finish(),
+            [0x1A, 0x05, 0x0A, 0x03, b'a', b'b', b'c', 0x22, 0x00]
+        );
+    }
+
+    #[test]
+    fn writes_length_delimited_messages_without_tags() {
+        let mut first = ProtobufWriter::new();
+        first.write_uint64(1, 2);
+
+        let mut writer = ProtobufWriter::new();
+        writer.write_length_delimited(first);
+        writer.write_length_delimited(ProtobufWriter::new());
+
+        assert_eq!(writer.finish(), [0x02, 0x08, 0x02, 0x00]);
+    }
+
+    #[test]
+    fn uses_multi_byte_tags_for_large_field_numbers() {
+        let mut writer = ProtobufWriter::new();
+        writer.write_uint64(16, 1);
+        assert_eq!(writer.finish(), [0x80, 0x01, 0x01]);
+    }
+}
@@ -232,6 +232,9 @@ impl ServiceRepr {
         let bytes = service.as_bytes();
         self.service_len = truncate_usize_u16(bytes.len());
         self.service_bytes[..bytes.len()].copy_from_slice(bytes);
+        // Clear out any remnants of a previous, longer service name, as equality and hashing
+        // cover the whole buffer.
+        self.service_bytes[bytes.len()..].fill(0);
     }
 }
 
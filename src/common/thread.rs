@@ -7,6 +7,7 @@ use crate::prelude::*;
 
 use std::thread::JoinHandle;
 
+#[derive(Debug)]
 pub struct ThreadHandle(Option<JoinHandle<io::Result<()>>>);
 
 impl ThreadHandle {
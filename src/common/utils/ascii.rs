@@ -117,6 +117,16 @@ pub fn trim_auth_token(mut data: &[u8]) -> &[u8] {
     }
 }
 
+// Strips an `Authorization` scheme and the space after it. Auth schemes are case-insensitive per
+// RFC 9110, so `basic` and `BASIC` need to be accepted just the same as `Basic`.
+pub fn strip_auth_scheme<'a>(header: &'a [u8], scheme: &[u8]) -> Option<&'a [u8]> {
+    let (name, rest) = header.split_at_checked(scheme.len())?;
+    match rest {
+        [b' ', rest @ ..] if name.eq_ignore_ascii_case(scheme) => Some(rest),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
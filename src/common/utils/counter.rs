@@ -26,4 +26,9 @@ impl Counter {
         // https://www.boost.org/doc/libs/1_55_0/doc/html/atomic/usage_examples.html
         self.current.fetch_add(n, Ordering::Relaxed).wrapping_add(n)
     }
+
+    // For tracking maximums (like timestamps) rather than counts.
+    pub fn record_max(&self, n: u64) {
+        self.current.fetch_max(n, Ordering::Relaxed);
+    }
 }
@@ -109,3 +109,8 @@ pub const fn truncate_usize_u16(value: usize) -> u16 {
 pub const fn truncate_usize_u32(value: usize) -> u32 {
     value as u32
 }
+
+// Values past 2^53 lose precision, as is expected for metric sample values.
+pub const fn round_u64_f64(value: u64) -> f64 {
+    value as f64
+}
@@ -29,6 +29,31 @@ impl Notify {
     }
 }
 
+// Don't sleep more than this between checks for termination.
+const TERMINATE_POLL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Sleep for the given duration, returning early with `false` if termination is requested.
+pub fn sleep_unless_terminated(terminate_notify: &Notify, duration: Duration) -> bool {
+    let deadline = Instant::now().checked_add(duration);
+
+    loop {
+        if terminate_notify.has_notified() {
+            return false;
+        }
+
+        let remaining = match deadline {
+            Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+            None => TERMINATE_POLL_TIMEOUT,
+        };
+
+        if remaining.is_zero() {
+            return true;
+        }
+
+        std::thread::sleep(remaining.min(TERMINATE_POLL_TIMEOUT));
+    }
+}
+
 pub struct NotifyGuard<'a>(&'a Notify);
 
 impl Drop for NotifyGuard<'_> {
@@ -64,4 +89,17 @@ mod tests {
         handle.join().unwrap();
         assert!(NOTIFY.has_notified());
     }
+
+    #[test]
+    fn sleep_returns_true_after_duration() {
+        let notify = Notify::new();
+        assert!(sleep_unless_terminated(&notify, Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn sleep_returns_false_if_terminated() {
+        let notify = Notify::new();
+        notify.notify();
+        assert!(!sleep_unless_terminated(&notify, Duration::from_secs(60)));
+    }
 }
@@ -0,0 +1,116 @@
+use crate::prelude::*;
+
+use super::syscall_utils::syscall_check_int;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::ffi::OsString;
+use std::os::unix::prelude::*;
+use std::path::Path;
+use std::path::PathBuf;
+
+// Directory handle for creating and replacing files by name without following symlinks. Lookups
+// are all relative to the directory itself, so swapping out a path component after it's opened
+// can't redirect anything written through it.
+#[derive(Debug)]
+pub struct DirFd {
+    fd: OwnedFd,
+}
+
+fn path_to_c_string(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|_| Error::from_raw_os_error(libc::EINVAL))
+}
+
+impl DirFd {
+    pub fn open(path: &Path) -> io::Result<DirFd> {
+        assert_not_miri();
+
+        let path = path_to_c_string(path)?;
+
+        // SAFETY: FFI call, called with a valid C string and returns a new file descriptor that
+        // nothing else owns.
+        unsafe {
+            let result = syscall_check_int(
+                "open",
+                libc::open(
+                    path.as_ptr(),
+                    libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+                ),
+            )?;
+            Ok(DirFd {
+                fd: OwnedFd::from_raw_fd(result),
+            })
+        }
+    }
+
+    /// Create a file in this directory for writing. This fails if anything, including a symlink
+    /// or hard link, already exists at that name.
+    pub fn create_new(&self, name: &CStr, mode: libc::mode_t) -> io::Result<std::fs::File> {
+        assert_not_miri();
+
+        // SAFETY: FFI call, called with a valid C string and an owned directory descriptor, and
+        // returns a new file descriptor that nothing else owns.
+        unsafe {
+            let result = syscall_check_int(
+                "openat",
+                libc::openat(
+                    self.fd.as_raw_fd(),
+                    name.as_ptr(),
+                    libc::O_WRONLY
+                        | libc::O_CREAT
+                        | libc::O_EXCL
+                        | libc::O_NOFOLLOW
+                        | libc::O_CLOEXEC,
+                    libc::c_uint::from(mode),
+                ),
+            )?;
+            Ok(std::fs::File::from_raw_fd(result))
+        }
+    }
+
+    /// Remove a file from this directory. Symlinks are removed themselves, not their targets.
+    pub fn unlink(&self, name: &CStr) -> io::Result<()> {
+        assert_not_miri();
+
+        // SAFETY: FFI call, called with a valid C string and an owned directory descriptor.
+        unsafe {
+            syscall_check_int(
+                "unlinkat",
+                libc::unlinkat(self.fd.as_raw_fd(), name.as_ptr(), 0),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Atomically replace `to` with `from`, both within this directory.
+    pub fn rename(&self, from: &CStr, to: &CStr) -> io::Result<()> {
+        assert_not_miri();
+
+        let fd = self.fd.as_raw_fd();
+        // SAFETY: FFI call, called with valid C strings and an owned directory descriptor.
+        unsafe {
+            syscall_check_int(
+                "renameat",
+                libc::renameat(fd, from.as_ptr(), fd, to.as_ptr()),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates a new directory in the system's temporary directory that only this user can access,
+/// named with the given prefix and a random suffix.
+pub fn create_private_temp_dir(prefix: &str) -> io::Result<PathBuf> {
+    assert_not_miri();
+
+    let template = std::env::temp_dir().join(format!("{prefix}.XXXXXX"));
+    let mut template = path_to_c_string(&template)?.into_bytes_with_nul();
+
+    // SAFETY: FFI call, called with a NUL-terminated buffer that `mkdtemp` only overwrites the
+    // trailing `X`s of, in place.
+    if unsafe { libc::mkdtemp(template.as_mut_ptr().cast()) }.is_null() {
+        return Err(Error::last_os_error());
+    }
+
+    template.pop();
+    Ok(PathBuf::from(OsString::from_vec(template)))
+}
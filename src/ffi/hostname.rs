@@ -0,0 +1,17 @@
+use super::syscall_utils::syscall_check_int;
+use crate::prelude::*;
+
+pub fn hostname() -> io::Result<String> {
+    assert_not_miri();
+
+    // Linux limits hostnames to 64 bytes (`HOST_NAME_MAX`), and this leaves room for the null.
+    let mut buf = [0_u8; 65];
+
+    // SAFETY: The buffer is valid for its whole length, and the result is checked.
+    syscall_check_int("gethostname", unsafe {
+        libc::gethostname(buf.as_mut_ptr().cast(), buf.len())
+    })?;
+
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    Ok(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
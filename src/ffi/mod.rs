@@ -1,6 +1,8 @@
+mod dir_fd;
 mod errno;
 mod exit_result;
 mod fd_utils;
+mod hostname;
 mod pidfd;
 mod pollable;
 mod pollable_flags;
@@ -8,14 +10,18 @@ mod sd_journal;
 mod signal;
 mod signal_action;
 mod syscall_utils;
+mod sysconf;
 mod uid_gid;
 
+pub use self::dir_fd::*;
 pub use self::errno::*;
 pub use self::exit_result::*;
 pub use self::fd_utils::*;
+pub use self::hostname::*;
 pub use self::pidfd::*;
 pub use self::pollable::*;
 pub use self::sd_journal::*;
 pub use self::signal::*;
 pub use self::signal_action::*;
+pub use self::sysconf::*;
 pub use self::uid_gid::*;
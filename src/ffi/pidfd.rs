@@ -31,6 +31,27 @@ impl PidFd {
         }
     }
 
+    /// Looks up the PID this refers to. It's `-1` in the kernel's records once the process has
+    /// been reaped, and that's reported here as an error.
+    pub fn pid(&self) -> io::Result<u32> {
+        let f = std::fs::File::open(format!("/proc/self/fdinfo/{}", self.fd.as_raw_fd()))?;
+
+        let mut lines = io::BufReader::new(f);
+        let mut line = String::new();
+
+        while lines.read_line(&mut line)? > 0 {
+            if let Some(suffix) = line.strip_prefix("Pid:") {
+                return suffix
+                    .trim()
+                    .parse()
+                    .map_err(|_| Error::from_raw_os_error(libc::ESRCH));
+            }
+            line.clear();
+        }
+
+        Err(Error::from_raw_os_error(libc::ENOSYS))
+    }
+
     pub fn terminate(&self) -> io::Result<()> {
         assert_not_miri();
 
@@ -161,29 +182,6 @@ impl IntoRawFd for PidFd {
 mod tests {
     use super::*;
 
-    // So I can actually read and check this external to the program.
-    fn get_pidfd_pid(pidfd: &PidFd) -> u32 {
-        use io::BufRead as _;
-
-        let f = std::fs::File::open(format!("/proc/self/fdinfo/{}", pidfd.as_raw_fd())).unwrap();
-
-        let mut lines = io::BufReader::new(f);
-        let mut line = String::new();
-
-        while lines.read_line(&mut line).unwrap() > 0 {
-            if let Some(suffix) = line.strip_prefix("Pid:") {
-                let suffix = suffix.trim();
-                match suffix.parse() {
-                    Ok(pidfd) => return pidfd,
-                    Err(_) => panic!("Invalid PID line: {}", suffix),
-                }
-            }
-            line.clear();
-        }
-
-        panic!("Could not find PID!");
-    }
-
     // Don't leak the process in case of spawn error.
     struct TestProcess(Option<std::process::Child>);
 
@@ -233,7 +231,7 @@ mod tests {
 
         let pidfd = PidFd::open_from_child(inner).unwrap();
 
-        assert_eq!(id, get_pidfd_pid(&pidfd));
+        assert_eq!(id, pidfd.pid().unwrap());
 
         // Close the input so it dies naturally.
         drop(inner.stdin.take());
@@ -253,7 +251,7 @@ mod tests {
 
         let pidfd = PidFd::open_from_child(inner).unwrap();
 
-        assert_eq!(id, get_pidfd_pid(&pidfd));
+        assert_eq!(id, pidfd.pid().unwrap());
 
         pidfd.terminate().unwrap();
 
@@ -58,6 +58,36 @@ fn do_poll_fd(fd: RawFd, flags: PollFlags, duration: Option<Duration>) -> io::Re
     }
 }
 
+/// Polls two file descriptors at once, for when either one could become ready first. Like with
+/// `poll(2)` itself, a negative descriptor is skipped, with its result left empty.
+pub fn poll_pair(
+    first: (RawFd, PollFlags),
+    second: (RawFd, PollFlags),
+    duration: Option<Duration>,
+) -> io::Result<(PollResult, PollResult)> {
+    assert_not_miri();
+
+    let timeout = duration.map_or(-1, |d| truncate_u128_i32(d.as_millis()));
+    let mut pollfds = [first, second].map(|(fd, flags)| libc::pollfd {
+        fd,
+        events: flags.raw_bits(),
+        revents: 0,
+    });
+
+    // SAFETY: FFI call, doesn't leave anything uninitalized when returning.
+    let result = syscall_check_int("poll", unsafe {
+        libc::poll(pollfds.as_mut_ptr(), 2, timeout)
+    })?;
+    if result == 0 {
+        Err(ErrorKind::TimedOut.into())
+    } else {
+        Ok((
+            PollResult::from_raw_bits(pollfds[0].revents),
+            PollResult::from_raw_bits(pollfds[1].revents),
+        ))
+    }
+}
+
 impl Pollable for std::os::fd::OwnedFd {
     fn poll(&self, flags: PollFlags, duration: Option<Duration>) -> io::Result<PollResult> {
         do_poll_fd(self.as_raw_fd(), flags, duration)
@@ -94,6 +124,12 @@ impl Pollable for io::Stderr {
     }
 }
 
+impl Pollable for std::net::TcpListener {
+    fn poll(&self, flags: PollFlags, duration: Option<Duration>) -> io::Result<PollResult> {
+        do_poll_fd(self.as_raw_fd(), flags, duration)
+    }
+}
+
 impl Pollable for std::fs::File {
     fn poll(&self, flags: PollFlags, duration: Option<Duration>) -> io::Result<PollResult> {
         do_poll_fd(self.as_raw_fd(), flags, duration)
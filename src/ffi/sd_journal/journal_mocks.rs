@@ -17,6 +17,7 @@ pub struct FakeJournalRef {
     pub next: CallSpy<(), io::Result<bool>>,
     pub cursor: CallSpy<(), io::Result<Cursor>>,
     pub get_data: CallSpyMap<FixedCString, (), io::Result<&'static [u8]>>,
+    pub get_realtime_usec: CallSpy<(), io::Result<u64>>,
 }
 
 impl FakeJournalRef {
@@ -29,6 +30,7 @@ impl FakeJournalRef {
             next: CallSpy::new("next"),
             cursor: CallSpy::new("cursor"),
             get_data: CallSpyMap::new("get_data"),
+            get_realtime_usec: CallSpy::new("get_realtime_usec"),
         }
     }
 
@@ -40,6 +42,7 @@ impl FakeJournalRef {
         self.next.assert_no_calls_remaining();
         self.cursor.assert_no_calls_remaining();
         self.get_data.assert_no_calls_remaining();
+        self.get_realtime_usec.assert_no_calls_remaining();
     }
 }
 
@@ -83,6 +86,10 @@ impl JournalRef for &'static FakeJournalRef {
     fn get_data<'a>(&'a mut self, field: &CStr) -> io::Result<&'a [u8]> {
         self.get_data.call(FixedCString::new(field.to_bytes()), ())
     }
+
+    fn get_realtime_usec(&mut self) -> io::Result<u64> {
+        self.get_realtime_usec.call(())
+    }
 }
 
 pub struct FakeSystemdProvider {
@@ -566,4 +573,28 @@ mod tests {
         );
         PROVIDER.assert_no_calls_remaining();
     }
+
+    #[test]
+    fn fake_systemd_provider_expected_get_realtime_usec_call_works() {
+        static PROVIDER: FakeSystemdProvider = FakeSystemdProvider::new(Id128(0));
+        PROVIDER.open.enqueue_io(Ok(()));
+        PROVIDER.journal.get_realtime_usec.enqueue_io(Ok(123));
+        assert_result_eq(
+            <&FakeJournalRef>::open(&PROVIDER)
+                .unwrap()
+                .get_realtime_usec(),
+            Ok(123),
+        );
+        PROVIDER.assert_no_calls_remaining();
+    }
+
+    #[test]
+    #[should_panic = "Unexpected calls remaining for `get_realtime_usec`: [Ok(123)]"]
+    fn fake_systemd_provider_extra_get_realtime_usec_call_is_asserted() {
+        static PROVIDER: FakeSystemdProvider = FakeSystemdProvider::new(Id128(0));
+        PROVIDER.open.enqueue_io(Ok(()));
+        PROVIDER.journal.get_realtime_usec.enqueue_io(Ok(123));
+        let _ = <&FakeJournalRef>::open(&PROVIDER).unwrap();
+        PROVIDER.assert_no_calls_remaining();
+    }
 }
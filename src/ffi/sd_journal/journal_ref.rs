@@ -125,6 +125,16 @@ impl JournalRef for NativeJournalRef {
             );
         }
     }
+
+    fn get_realtime_usec(&mut self) -> io::Result<u64> {
+        let mut usec = 0;
+        // SAFETY: FFI call only writes to the timestamp pointer, and it doesn't modify anything
+        // directly observable by safe Rust code.
+        sd_check("sd_journal_get_realtime_usec", unsafe {
+            sd_journal_get_realtime_usec(self.raw.as_ptr(), &mut usec)
+        })?;
+        Ok(usec)
+    }
 }
 
 #[cfg(test)]
@@ -27,6 +27,8 @@ where
     fn next(&mut self) -> io::Result<bool>;
     fn cursor(&mut self) -> io::Result<Cursor>;
     fn get_data<'a>(&'a mut self, field: &CStr) -> io::Result<&'a [u8]>;
+    /// Returns the current entry's realtime timestamp, in microseconds since the Unix epoch.
+    fn get_realtime_usec(&mut self) -> io::Result<u64>;
 }
 
 // This is distinct from Rust's `Instant`.
@@ -19,21 +19,16 @@ pub fn request_signal_when_parent_terminates(signal: Signal) {
     }
 }
 
-// This is only used in setup code. It's okay for it to panic.
-pub fn install_handler(
+fn set_handler(
     signals: &[Signal],
     extra_flags: libc::c_int,
     handler: extern "C" fn(signum: Signal),
-) {
+) -> libc::sigaction {
     #[allow(clippy::as_conversions)]
     let action = handler as usize;
 
     assert_not_miri();
 
-    if signals.is_empty() {
-        return;
-    }
-
     // I'd normally initialize this directly, but the layout of this is architecture-dependent
     // and it's more portable to just use libc. (It's *far* beyond me why this isn't the same
     // across all architectures, and just as much why `sigset_t` isn't publicly defined.)
@@ -62,8 +57,32 @@ pub fn install_handler(
         });
     }
 
+    sigaction
+}
+
+// This is only used in setup code. It's okay for it to panic.
+pub fn install_handler(
+    signals: &[Signal],
+    extra_flags: libc::c_int,
+    handler: extern "C" fn(signum: Signal),
+) {
+    if signals.is_empty() {
+        return;
+    }
+
+    let sigaction = set_handler(signals, extra_flags, handler);
+
     // SAFETY: it's only passed in valid addresses, and the result is asserted.
     syscall_assert_int("sigprocmask", unsafe {
         libc::sigprocmask(libc::SIG_BLOCK, &sigaction.sa_mask, std::ptr::null_mut())
     });
 }
+
+// Unlike `install_handler`, this leaves the signals unblocked so the handler actually runs, and
+// restores the default action once it has. The handler must be async-signal-safe. This is also
+// only used in setup code.
+pub fn install_oneshot_handler(signals: &[Signal], handler: extern "C" fn(signum: Signal)) {
+    if !signals.is_empty() {
+        set_handler(signals, libc::SA_RESETHAND, handler);
+    }
+}
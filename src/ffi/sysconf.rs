@@ -0,0 +1,19 @@
+use crate::prelude::*;
+
+fn sysconf_positive(name: libc::c_int) -> Option<u64> {
+    assert_not_miri();
+
+    // SAFETY: `sysconf` just reads a constant. It doesn't touch any Rust-observable memory.
+    let result = unsafe { libc::sysconf(name) };
+    u64::try_from(result).ok().filter(|&value| value > 0)
+}
+
+/// The unit of the CPU and start times in `/proc/<pid>/stat`. It's practically always 100.
+pub fn clock_ticks_per_second() -> u64 {
+    sysconf_positive(libc::_SC_CLK_TCK).unwrap_or(100)
+}
+
+/// The unit of the resident set size in `/proc/<pid>/stat`.
+pub fn page_size() -> u64 {
+    sysconf_positive(libc::_SC_PAGESIZE).unwrap_or(4096)
+}
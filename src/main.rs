@@ -37,6 +37,7 @@ mod common;
 mod ffi;
 mod parent;
 mod prelude;
+mod pusher;
 mod state;
 #[cfg(test)]
 mod test_utils;
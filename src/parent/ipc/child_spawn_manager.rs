@@ -41,10 +41,15 @@ impl<M: ParentIpcMethods> ChildSpawnManager<M> {
             _ => return Some(Err(error!("Child errored during termination."))),
         };
 
-        if self
-            .fail_counter
-            .check_fail(self.state.methods().next_instant())
-        {
+        // The child was terminated as part of shutting down, so don't respawn it.
+        if self.state.terminate_notify().has_notified() {
+            return Some(Ok(result));
+        }
+
+        let now = self.state.methods().next_instant();
+        self.state.health().child_failed(now);
+
+        if self.fail_counter.check_fail(now) {
             return Some(Ok(result));
         }
         log::error!("Child exited prematurely with {}", result);
@@ -55,10 +60,10 @@ impl<M: ParentIpcMethods> ChildSpawnManager<M> {
     }
 
     fn handle_prev_error(&mut self, error: Error) -> Option<io::Result<ExitResult>> {
-        if self
-            .fail_counter
-            .check_fail(self.state.methods().next_instant())
-        {
+        let now = self.state.methods().next_instant();
+        self.state.health().child_failed(now);
+
+        if self.fail_counter.check_fail(now) {
             return Some(Err(error));
         }
 
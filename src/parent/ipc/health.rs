@@ -0,0 +1,111 @@
+use crate::prelude::*;
+
+// The journal loop normally wakes up at least once a second, so this leaves plenty of slack for
+// large backlogs and slow disks.
+const JOURNAL_STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+// A crashing child is restarted within milliseconds, so it'd otherwise almost never be caught
+// mid-respawn. Instead, just wait until it's stayed up for a while.
+const CHILD_SETTLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct HealthTracker {
+    journal_heartbeat: Mutex<Option<Instant>>,
+    last_child_failure: Mutex<Option<Instant>>,
+}
+
+impl HealthTracker {
+    pub const fn new() -> Self {
+        Self {
+            journal_heartbeat: Mutex::new(None),
+            last_child_failure: Mutex::new(None),
+        }
+    }
+
+    pub fn journal_heartbeat(&self, now: Instant) {
+        *self
+            .journal_heartbeat
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(now);
+    }
+
+    pub fn child_failed(&self, now: Instant) {
+        *self
+            .last_child_failure
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(now);
+    }
+
+    pub fn status(&self, now: Instant) -> ipc::HealthStatus {
+        let journal_heartbeat = *self
+            .journal_heartbeat
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        let last_child_failure = *self
+            .last_child_failure
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        ipc::HealthStatus {
+            journal_active: journal_heartbeat
+                .is_some_and(|t| now.saturating_duration_since(t) <= JOURNAL_STALL_TIMEOUT),
+            child_stable: last_child_failure
+                .is_none_or(|t| now.saturating_duration_since(t) > CHILD_SETTLE_TIMEOUT),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn after(i: Instant, secs: u64) -> Instant {
+        i + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn journal_is_inactive_before_the_first_heartbeat() {
+        let tracker = HealthTracker::new();
+        assert!(!tracker.status(Instant::now()).journal_active);
+    }
+
+    #[test]
+    fn journal_is_active_shortly_after_a_heartbeat() {
+        let tracker = HealthTracker::new();
+        let base = Instant::now();
+        tracker.journal_heartbeat(base);
+        assert!(tracker.status(base).journal_active);
+        assert!(tracker.status(after(base, 30)).journal_active);
+    }
+
+    #[test]
+    fn journal_is_inactive_long_after_a_heartbeat() {
+        let tracker = HealthTracker::new();
+        let base = Instant::now();
+        tracker.journal_heartbeat(base);
+        assert!(!tracker.status(after(base, 31)).journal_active);
+    }
+
+    #[test]
+    fn child_is_stable_without_failures() {
+        let tracker = HealthTracker::new();
+        assert!(tracker.status(Instant::now()).child_stable);
+    }
+
+    #[test]
+    fn child_is_unstable_shortly_after_a_failure() {
+        let tracker = HealthTracker::new();
+        let base = Instant::now();
+        tracker.child_failed(base);
+        assert!(!tracker.status(base).child_stable);
+        assert!(!tracker.status(after(base, 30)).child_stable);
+    }
+
+    #[test]
+    fn child_is_stable_long_after_a_failure() {
+        let tracker = HealthTracker::new();
+        let base = Instant::now();
+        tracker.child_failed(base);
+        assert!(tracker.status(after(base, 31)).child_stable);
+    }
+}
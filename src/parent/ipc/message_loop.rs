@@ -3,6 +3,7 @@ use crate::prelude::*;
 use super::state::ParentIpcState;
 use super::types::*;
 use crate::parent::key_watcher::write_current_key_set;
+use crate::parent::tls_watcher::write_current_tls_certificate;
 
 #[must_use]
 fn read_request(
@@ -30,13 +31,48 @@ pub fn write_to_child_input(s: &'static ParentIpcState<impl ParentIpcMethods>, b
     result
 }
 
+// `scope` is only set for scoped metrics requests.
 fn try_handle_metrics_request(
     s: &'static ParentIpcState<impl ParentIpcMethods>,
+    format: ipc::MetricsFormat,
+    scope: Option<&KeyScope>,
 ) -> io::Result<Vec<u8>> {
     let table = s.methods().get_user_group_table()?;
     if let Some(snapshot) = s.state().snapshot() {
         let environment = &s.dynamic().prom_environment;
-        if let Some(result) = render_openapi_metrics(environment, &snapshot, &table) {
+        let process = s.methods().process_stats();
+        let scrape = s.state().scrape_stats_snapshot();
+        let start = s.methods().next_instant();
+
+        let rendered = match scope {
+            None => render_metrics(
+                environment,
+                &snapshot,
+                &process,
+                Some(&scrape),
+                &table,
+                format,
+            ),
+            Some(scope) => render_scoped_metrics(
+                environment,
+                snapshot,
+                &process,
+                Some(scrape),
+                &table,
+                format,
+                scope,
+            ),
+        };
+
+        if let Some(result) = rendered {
+            // Recorded after rendering, so each response only covers the requests before it.
+            let header_len = ipc::parent::metrics_response_header(format).len();
+            let size = result.len().saturating_sub(header_len);
+            let elapsed = s.methods().next_instant().saturating_duration_since(start);
+            s.state()
+                .observe_scrape_duration(ScrapeHistogram::RenderDuration, elapsed);
+            s.state()
+                .observe_scrape(ScrapeHistogram::RenderSize, zero_extend_usize_u64(size));
             return Ok(result);
         }
     };
@@ -45,22 +81,46 @@ fn try_handle_metrics_request(
 }
 
 #[must_use]
-fn handle_metrics_request(s: &'static ParentIpcState<impl ParentIpcMethods>) -> bool {
-    let result = try_handle_metrics_request(s).unwrap_or_else(|e| {
+fn handle_metrics_request(
+    s: &'static ParentIpcState<impl ParentIpcMethods>,
+    format: ipc::MetricsFormat,
+) -> bool {
+    let result = try_handle_metrics_request(s, format, None).unwrap_or_else(|e| {
         log::error!("{}", normalize_errno(e, None));
         Vec::new()
     });
     write_to_child_input(s, &result)
 }
 
+#[must_use]
+fn handle_scoped_metrics_request(
+    s: &'static ParentIpcState<impl ParentIpcMethods>,
+    request: &ipc::child::ScopedMetricsRequest,
+) -> bool {
+    let result = match try_handle_metrics_request(s, request.format, Some(&request.scope)) {
+        // The prefix is sent in the same write, so it can't be separated from its response.
+        Ok(mut result) => {
+            let prefix = ipc::parent::scoped_metrics_response_prefix(request.id);
+            result.splice(0..0, prefix);
+            result
+        }
+        Err(e) => {
+            log::error!("{}", normalize_errno(e, None));
+            Vec::new()
+        }
+    };
+    write_to_child_input(s, &result)
+}
+
 pub fn ipc_message_loop<M: ParentIpcMethods>(
     mut child_output: M::ChildOutput,
     s: &'static ParentIpcState<M>,
 ) -> io::Result<()> {
-    // 4 bytes is far more than enough to read client IPC messages efficiently, since they're
-    // all just one byte and they're always batched into one go. In practice, there's really
-    // only going to be 1-2 bytes to read total.
-    let mut read_buf = [0_u8; 4];
+    // 64 bytes is far more than enough to read client IPC messages efficiently. Requests are almost
+    // all just one byte (scoped metrics requests are the rare exception), and the child's observations and response codes are only 5 bytes each, and
+    // they're always batched into one go. In practice, there's really only going to be a few dozen bytes
+    // to read total.
+    let mut read_buf = [0_u8; 64];
 
     while let Some(buf) = try_read(&mut child_output, s.done_notify(), &mut read_buf)? {
         let request = read_request(s, buf);
@@ -69,13 +129,58 @@ pub fn ipc_message_loop<M: ParentIpcMethods>(
         s.state()
             .add_metrics_requests(request.tracked_metrics_requests());
 
+        for &(histogram, micros) in request.observations() {
+            s.state().observe_scrape(histogram, u64::from(micros));
+        }
+
+        for (code, count) in request.responses() {
+            s.state().add_http_responses(code, count);
+        }
+
+        for (reason, count) in request.auth_failures() {
+            s.state().add_auth_failures(reason, count);
+        }
+
+        for (reason, count) in request.throttled_requests() {
+            s.state().add_throttled_requests(reason, count);
+        }
+
+        for (suite, count) in request.tls_handshakes() {
+            s.state().add_tls_handshakes(suite, count);
+        }
+
+        for (name, count) in request.key_requests() {
+            s.state().add_key_requests(name, count);
+        }
+
         if request.keys_requested() && !write_current_key_set(s) {
             break;
         }
 
-        if request.metrics_requested() && !handle_metrics_request(s) {
+        // Keys are only requested when the child starts, which is also when it needs to catch up
+        // on any certificate reloaded while it was being spawned.
+        if request.keys_requested() && !write_current_tls_certificate(s) {
             break;
         }
+
+        if request.health_requested() {
+            let status = s.health().status(Instant::now());
+            if !write_to_child_input(s, &ipc::parent::health_response_bytes(status)) {
+                break;
+            }
+        }
+
+        for format in ipc::MetricsFormat::ALL {
+            if request.metrics_requested(format) && !handle_metrics_request(s, format) {
+                return Ok(());
+            }
+        }
+
+        for scoped in request.scoped_metrics() {
+            if !handle_scoped_metrics_request(s, scoped) {
+                return Ok(());
+            }
+        }
     }
 
     Ok(())
@@ -96,7 +96,7 @@ fn read_header_then_request_keys() {
 
     let key_dir = write_test_key();
 
-    static EXPECTED: &[u8] = b"\x01\x01\x100123456789abcdef";
+    static EXPECTED: &[u8] = b"\x01\x01\x900123456789abcdef\x04test\x07metrics\x00\x00\x00\x00\x00";
 
     static S: StaticState = StaticState::new();
     let _watcher_guard = S.state.terminate_notify().create_guard();
@@ -132,7 +132,7 @@ fn read_header_then_request_metrics() {
     let guard = setup_capture_logger();
 
     static EXPECTED_EXPOSITION: &[u8] =
-        b"\x00\x02\x05\x00\x00# TYPE journald_entries_ingested counter
+        b"\x00\x75\x1B\x00\x00# TYPE journald_entries_ingested counter
 journald_entries_ingested_created 123.456
 journald_entries_ingested_total 0
 # TYPE journald_fields_ingested counter
@@ -164,6 +164,119 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_duration_seconds_count 0
+journald_scrape_duration_seconds_sum 0.000
+journald_scrape_duration_seconds_created 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_ipc_duration_seconds_count 0
+journald_scrape_ipc_duration_seconds_sum 0.000
+journald_scrape_ipc_duration_seconds_created 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\"} 0
+journald_render_duration_seconds_bucket{le=\"0.01\"} 0
+journald_render_duration_seconds_bucket{le=\"0.025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.05\"} 0
+journald_render_duration_seconds_bucket{le=\"0.1\"} 0
+journald_render_duration_seconds_bucket{le=\"0.25\"} 0
+journald_render_duration_seconds_bucket{le=\"0.5\"} 0
+journald_render_duration_seconds_bucket{le=\"1\"} 0
+journald_render_duration_seconds_bucket{le=\"2.5\"} 0
+journald_render_duration_seconds_bucket{le=\"5\"} 0
+journald_render_duration_seconds_bucket{le=\"10\"} 0
+journald_render_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_render_duration_seconds_count 0
+journald_render_duration_seconds_sum 0.000
+journald_render_duration_seconds_created 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\"} 0
+journald_render_size_bytes_bucket{le=\"2048\"} 0
+journald_render_size_bytes_bucket{le=\"4096\"} 0
+journald_render_size_bytes_bucket{le=\"8192\"} 0
+journald_render_size_bytes_bucket{le=\"16384\"} 0
+journald_render_size_bytes_bucket{le=\"32768\"} 0
+journald_render_size_bytes_bucket{le=\"65536\"} 0
+journald_render_size_bytes_bucket{le=\"131072\"} 0
+journald_render_size_bytes_bucket{le=\"262144\"} 0
+journald_render_size_bytes_bucket{le=\"524288\"} 0
+journald_render_size_bytes_bucket{le=\"1048576\"} 0
+journald_render_size_bytes_bucket{le=\"2097152\"} 0
+journald_render_size_bytes_bucket{le=\"4194304\"} 0
+journald_render_size_bytes_bucket{le=\"+Inf\"} 0
+journald_render_size_bytes_count 0
+journald_render_size_bytes_sum 0
+journald_render_size_bytes_created 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\"} 123.456
+journald_http_responses_total{code=\"200\"} 0
+journald_http_responses_created{code=\"401\"} 123.456
+journald_http_responses_total{code=\"401\"} 0
+journald_http_responses_created{code=\"403\"} 123.456
+journald_http_responses_total{code=\"403\"} 0
+journald_http_responses_created{code=\"404\"} 123.456
+journald_http_responses_total{code=\"404\"} 0
+journald_http_responses_created{code=\"405\"} 123.456
+journald_http_responses_total{code=\"405\"} 0
+journald_http_responses_created{code=\"429\"} 123.456
+journald_http_responses_total{code=\"429\"} 0
+journald_http_responses_created{code=\"500\"} 123.456
+journald_http_responses_total{code=\"500\"} 0
+journald_http_responses_created{code=\"503\"} 123.456
+journald_http_responses_total{code=\"503\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\"} 0
+journald_auth_failures_created{reason=\"bad_syntax\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\"} 0
+journald_auth_failures_created{reason=\"wrong_user\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\"} 0
+journald_auth_failures_created{reason=\"unknown_key\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\"} 0
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\"} 123.456
+journald_throttled_requests_total{reason=\"address\"} 0
+journald_throttled_requests_created{reason=\"key\"} 123.456
+journald_throttled_requests_total{reason=\"key\"} 0
+journald_throttled_requests_created{reason=\"lockout\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\"} 0
 # EOF
 ";
 
@@ -182,6 +295,7 @@ journald_messages_ingested_bytes_total 0
     S.enqueue_child_output(Err(libc::EAGAIN));
     S.enqueue_child_output(Err(libc::EPIPE));
 
+    S.enqueue_render_timing();
     S.enqueue_child_input(Ok(EXPECTED_EXPOSITION.len()));
 
     assert_result_eq(
@@ -195,12 +309,523 @@ journald_messages_ingested_bytes_total 0
     guard.expect_logs(&[]);
 }
 
+#[test]
+fn read_header_then_request_scoped_metrics() {
+    let guard = setup_capture_logger();
+
+    static EXPECTED_EXPOSITION: &[u8] =
+        b"\x05\x07\x00\x00\x00\x00\x5D\x00\x00\x00# TYPE journald_faults counter
+journald_faults_created 123.456
+journald_faults_total 0
+# EOF
+";
+
+    static S: StaticState = StaticState::new();
+    let _watcher_guard = S.state.terminate_notify().create_guard();
+    S.init_test_state();
+
+    static REQUEST: &[u8] = b"\x0F\x07\x00\x00\x00\x18\x00\x00\x00families=journald_faults\x00";
+
+    S.enqueue_child_output(Ok(&ipc::VERSION_BYTES));
+    S.enqueue_child_output(Ok(REQUEST));
+    S.enqueue_child_output(Err(libc::EPIPE));
+
+    S.enqueue_render_timing();
+    S.enqueue_child_input(Ok(EXPECTED_EXPOSITION.len()));
+
+    assert_result_eq(
+        S.run_ipc_message_loop(),
+        Err(Error::from_raw_os_error(libc::EPIPE)),
+    );
+
+    S.assert_input_sent(EXPECTED_EXPOSITION);
+
+    S.assert_no_calls_remaining();
+    guard.expect_logs(&[]);
+}
+
+#[test]
+fn read_header_then_observations_then_request_metrics_twice() {
+    let guard = setup_capture_logger();
+
+    static EXPECTED_FIRST: &[u8] = b"\x00\x75\x1B\x00\x00# TYPE journald_entries_ingested counter
+journald_entries_ingested_created 123.456
+journald_entries_ingested_total 0
+# TYPE journald_fields_ingested counter
+journald_fields_ingested_created 123.456
+journald_fields_ingested_total 0
+# TYPE journald_data_ingested_bytes counter
+# UNIT journald_data_ingested_bytes bytes
+journald_data_ingested_bytes_created 123.456
+journald_data_ingested_bytes_total 0
+# TYPE journald_faults counter
+journald_faults_created 123.456
+journald_faults_total 0
+# TYPE journald_cursor_double_retries counter
+journald_cursor_double_retries_created 123.456
+journald_cursor_double_retries_total 0
+# TYPE journald_unreadable_fields counter
+journald_unreadable_fields_created 123.456
+journald_unreadable_fields_total 0
+# TYPE journald_corrupted_fields counter
+journald_corrupted_fields_created 123.456
+journald_corrupted_fields_total 0
+# TYPE journald_metrics_requests counter
+journald_metrics_requests_created 123.456
+journald_metrics_requests_total 0
+# TYPE journald_messages_ingested counter
+journald_messages_ingested_created 123.456
+journald_messages_ingested_total 0
+# TYPE journald_messages_ingested_bytes counter
+# UNIT journald_messages_ingested_bytes bytes
+journald_messages_ingested_bytes_created 123.456
+journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.1\"} 1
+journald_scrape_duration_seconds_bucket{le=\"0.25\"} 1
+journald_scrape_duration_seconds_bucket{le=\"0.5\"} 1
+journald_scrape_duration_seconds_bucket{le=\"1\"} 1
+journald_scrape_duration_seconds_bucket{le=\"2.5\"} 1
+journald_scrape_duration_seconds_bucket{le=\"5\"} 1
+journald_scrape_duration_seconds_bucket{le=\"10\"} 1
+journald_scrape_duration_seconds_bucket{le=\"+Inf\"} 1
+journald_scrape_duration_seconds_count 1
+journald_scrape_duration_seconds_sum 0.100
+journald_scrape_duration_seconds_created 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\"} 1
+journald_scrape_ipc_duration_seconds_count 1
+journald_scrape_ipc_duration_seconds_sum 0.010
+journald_scrape_ipc_duration_seconds_created 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\"} 0
+journald_render_duration_seconds_bucket{le=\"0.01\"} 0
+journald_render_duration_seconds_bucket{le=\"0.025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.05\"} 0
+journald_render_duration_seconds_bucket{le=\"0.1\"} 0
+journald_render_duration_seconds_bucket{le=\"0.25\"} 0
+journald_render_duration_seconds_bucket{le=\"0.5\"} 0
+journald_render_duration_seconds_bucket{le=\"1\"} 0
+journald_render_duration_seconds_bucket{le=\"2.5\"} 0
+journald_render_duration_seconds_bucket{le=\"5\"} 0
+journald_render_duration_seconds_bucket{le=\"10\"} 0
+journald_render_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_render_duration_seconds_count 0
+journald_render_duration_seconds_sum 0.000
+journald_render_duration_seconds_created 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\"} 0
+journald_render_size_bytes_bucket{le=\"2048\"} 0
+journald_render_size_bytes_bucket{le=\"4096\"} 0
+journald_render_size_bytes_bucket{le=\"8192\"} 0
+journald_render_size_bytes_bucket{le=\"16384\"} 0
+journald_render_size_bytes_bucket{le=\"32768\"} 0
+journald_render_size_bytes_bucket{le=\"65536\"} 0
+journald_render_size_bytes_bucket{le=\"131072\"} 0
+journald_render_size_bytes_bucket{le=\"262144\"} 0
+journald_render_size_bytes_bucket{le=\"524288\"} 0
+journald_render_size_bytes_bucket{le=\"1048576\"} 0
+journald_render_size_bytes_bucket{le=\"2097152\"} 0
+journald_render_size_bytes_bucket{le=\"4194304\"} 0
+journald_render_size_bytes_bucket{le=\"+Inf\"} 0
+journald_render_size_bytes_count 0
+journald_render_size_bytes_sum 0
+journald_render_size_bytes_created 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\"} 123.456
+journald_http_responses_total{code=\"200\"} 0
+journald_http_responses_created{code=\"401\"} 123.456
+journald_http_responses_total{code=\"401\"} 0
+journald_http_responses_created{code=\"403\"} 123.456
+journald_http_responses_total{code=\"403\"} 0
+journald_http_responses_created{code=\"404\"} 123.456
+journald_http_responses_total{code=\"404\"} 0
+journald_http_responses_created{code=\"405\"} 123.456
+journald_http_responses_total{code=\"405\"} 0
+journald_http_responses_created{code=\"429\"} 123.456
+journald_http_responses_total{code=\"429\"} 0
+journald_http_responses_created{code=\"500\"} 123.456
+journald_http_responses_total{code=\"500\"} 0
+journald_http_responses_created{code=\"503\"} 123.456
+journald_http_responses_total{code=\"503\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\"} 0
+journald_auth_failures_created{reason=\"bad_syntax\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\"} 0
+journald_auth_failures_created{reason=\"wrong_user\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\"} 0
+journald_auth_failures_created{reason=\"unknown_key\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\"} 0
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\"} 123.456
+journald_throttled_requests_total{reason=\"address\"} 0
+journald_throttled_requests_created{reason=\"key\"} 123.456
+journald_throttled_requests_total{reason=\"key\"} 0
+journald_throttled_requests_created{reason=\"lockout\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\"} 0
+# EOF
+";
+
+    static EXPECTED_SECOND: &[u8] = b"\x00\x78\x1B\x00\x00# TYPE journald_entries_ingested counter
+journald_entries_ingested_created 123.456
+journald_entries_ingested_total 0
+# TYPE journald_fields_ingested counter
+journald_fields_ingested_created 123.456
+journald_fields_ingested_total 0
+# TYPE journald_data_ingested_bytes counter
+# UNIT journald_data_ingested_bytes bytes
+journald_data_ingested_bytes_created 123.456
+journald_data_ingested_bytes_total 0
+# TYPE journald_faults counter
+journald_faults_created 123.456
+journald_faults_total 0
+# TYPE journald_cursor_double_retries counter
+journald_cursor_double_retries_created 123.456
+journald_cursor_double_retries_total 0
+# TYPE journald_unreadable_fields counter
+journald_unreadable_fields_created 123.456
+journald_unreadable_fields_total 0
+# TYPE journald_corrupted_fields counter
+journald_corrupted_fields_created 123.456
+journald_corrupted_fields_total 0
+# TYPE journald_metrics_requests counter
+journald_metrics_requests_created 123.456
+journald_metrics_requests_total 0
+# TYPE journald_messages_ingested counter
+journald_messages_ingested_created 123.456
+journald_messages_ingested_total 0
+# TYPE journald_messages_ingested_bytes counter
+# UNIT journald_messages_ingested_bytes bytes
+journald_messages_ingested_bytes_created 123.456
+journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.1\"} 1
+journald_scrape_duration_seconds_bucket{le=\"0.25\"} 1
+journald_scrape_duration_seconds_bucket{le=\"0.5\"} 1
+journald_scrape_duration_seconds_bucket{le=\"1\"} 1
+journald_scrape_duration_seconds_bucket{le=\"2.5\"} 1
+journald_scrape_duration_seconds_bucket{le=\"5\"} 1
+journald_scrape_duration_seconds_bucket{le=\"10\"} 1
+journald_scrape_duration_seconds_bucket{le=\"+Inf\"} 1
+journald_scrape_duration_seconds_count 1
+journald_scrape_duration_seconds_sum 0.100
+journald_scrape_duration_seconds_created 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\"} 1
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\"} 1
+journald_scrape_ipc_duration_seconds_count 1
+journald_scrape_ipc_duration_seconds_sum 0.010
+journald_scrape_ipc_duration_seconds_created 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\"} 1
+journald_render_duration_seconds_bucket{le=\"0.01\"} 1
+journald_render_duration_seconds_bucket{le=\"0.025\"} 1
+journald_render_duration_seconds_bucket{le=\"0.05\"} 1
+journald_render_duration_seconds_bucket{le=\"0.1\"} 1
+journald_render_duration_seconds_bucket{le=\"0.25\"} 1
+journald_render_duration_seconds_bucket{le=\"0.5\"} 1
+journald_render_duration_seconds_bucket{le=\"1\"} 1
+journald_render_duration_seconds_bucket{le=\"2.5\"} 1
+journald_render_duration_seconds_bucket{le=\"5\"} 1
+journald_render_duration_seconds_bucket{le=\"10\"} 1
+journald_render_duration_seconds_bucket{le=\"+Inf\"} 1
+journald_render_duration_seconds_count 1
+journald_render_duration_seconds_sum 0.003
+journald_render_duration_seconds_created 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\"} 0
+journald_render_size_bytes_bucket{le=\"2048\"} 0
+journald_render_size_bytes_bucket{le=\"4096\"} 0
+journald_render_size_bytes_bucket{le=\"8192\"} 1
+journald_render_size_bytes_bucket{le=\"16384\"} 1
+journald_render_size_bytes_bucket{le=\"32768\"} 1
+journald_render_size_bytes_bucket{le=\"65536\"} 1
+journald_render_size_bytes_bucket{le=\"131072\"} 1
+journald_render_size_bytes_bucket{le=\"262144\"} 1
+journald_render_size_bytes_bucket{le=\"524288\"} 1
+journald_render_size_bytes_bucket{le=\"1048576\"} 1
+journald_render_size_bytes_bucket{le=\"2097152\"} 1
+journald_render_size_bytes_bucket{le=\"4194304\"} 1
+journald_render_size_bytes_bucket{le=\"+Inf\"} 1
+journald_render_size_bytes_count 1
+journald_render_size_bytes_sum 7029
+journald_render_size_bytes_created 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\"} 123.456
+journald_http_responses_total{code=\"200\"} 0
+journald_http_responses_created{code=\"401\"} 123.456
+journald_http_responses_total{code=\"401\"} 0
+journald_http_responses_created{code=\"403\"} 123.456
+journald_http_responses_total{code=\"403\"} 0
+journald_http_responses_created{code=\"404\"} 123.456
+journald_http_responses_total{code=\"404\"} 0
+journald_http_responses_created{code=\"405\"} 123.456
+journald_http_responses_total{code=\"405\"} 0
+journald_http_responses_created{code=\"429\"} 123.456
+journald_http_responses_total{code=\"429\"} 0
+journald_http_responses_created{code=\"500\"} 123.456
+journald_http_responses_total{code=\"500\"} 0
+journald_http_responses_created{code=\"503\"} 123.456
+journald_http_responses_total{code=\"503\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\"} 0
+journald_auth_failures_created{reason=\"bad_syntax\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\"} 0
+journald_auth_failures_created{reason=\"wrong_user\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\"} 0
+journald_auth_failures_created{reason=\"unknown_key\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\"} 0
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\"} 123.456
+journald_throttled_requests_total{reason=\"address\"} 0
+journald_throttled_requests_created{reason=\"key\"} 123.456
+journald_throttled_requests_total{reason=\"key\"} 0
+journald_throttled_requests_created{reason=\"lockout\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\"} 0
+# EOF
+";
+
+    static S: StaticState = StaticState::new();
+    let _watcher_guard = S.state.terminate_notify().create_guard();
+    S.init_test_state();
+
+    S.enqueue_child_output(Ok(&ipc::VERSION_BYTES));
+    // Split mid-observation, to make sure it resumes correctly.
+    S.enqueue_child_output(Ok(&[ipc::child::OBSERVE_SCRAPE_DURATION, 0xA0, 0x86]));
+    #[rustfmt::skip]
+    S.enqueue_child_output(Ok(&[
+        0x01, 0x00,
+        ipc::child::OBSERVE_IPC_DURATION, 0x10, 0x27, 0x00, 0x00,
+        ipc::child::REQUEST_METRICS,
+    ]));
+    S.enqueue_child_output(Ok(&[ipc::child::REQUEST_METRICS]));
+    S.enqueue_child_output(Err(libc::EPIPE));
+
+    S.enqueue_render_timing();
+    S.enqueue_child_input(Ok(EXPECTED_FIRST.len()));
+    S.enqueue_render_timing();
+    S.enqueue_child_input(Ok(EXPECTED_SECOND.len()));
+
+    assert_result_eq(
+        S.run_ipc_message_loop(),
+        Err(Error::from_raw_os_error(libc::EPIPE)),
+    );
+
+    let mut expected_sent = Vec::new();
+    write_slices(&mut expected_sent, &[EXPECTED_FIRST, EXPECTED_SECOND]);
+    S.assert_input_sent(&expected_sent);
+
+    S.assert_no_calls_remaining();
+    guard.expect_logs(&[]);
+}
+
+#[test]
+fn read_header_then_responses_and_auth_failures() {
+    let guard = setup_capture_logger();
+
+    static S: StaticState = StaticState::new();
+    let _watcher_guard = S.state.terminate_notify().create_guard();
+    S.init_test_state();
+
+    S.enqueue_child_output(Ok(&ipc::VERSION_BYTES));
+    // Split mid-status, to make sure it resumes correctly.
+    #[rustfmt::skip]
+    S.enqueue_child_output(Ok(&[
+        ipc::child::TRACK_REQUEST,
+        ipc::child::AUTH_UNKNOWN_KEY,
+        ipc::child::RECORD_RESPONSE, 0x93,
+    ]));
+    #[rustfmt::skip]
+    S.enqueue_child_output(Ok(&[
+        0x01, 0x00, 0x00,
+        ipc::child::TRACK_REQUEST,
+        ipc::child::THROTTLED_BY_ADDRESS,
+        ipc::child::RECORD_RESPONSE, 0xAD, 0x01, 0x00, 0x00,
+        ipc::child::RECORD_RESPONSE, 0xC8, 0x00, 0x00, 0x00,
+    ]));
+    S.enqueue_child_output(Err(libc::EPIPE));
+
+    assert_result_eq(
+        S.run_ipc_message_loop(),
+        Err(Error::from_raw_os_error(libc::EPIPE)),
+    );
+
+    assert_eq!(
+        S.state.state().scrape_stats_snapshot(),
+        ScrapeStatsSnapshot::empty()
+            .with_responses(200, 1)
+            .with_responses(403, 1)
+            .with_responses(429, 1)
+            .with_auth_failures(AuthFailureReason::UnknownKey, 1)
+            .with_throttled_requests(ThrottleReason::Address, 1)
+    );
+
+    S.assert_no_calls_remaining();
+    guard.expect_logs(&[]);
+}
+
+#[test]
+fn read_header_then_key_requests() {
+    let guard = setup_capture_logger();
+
+    static S: StaticState = StaticState::new();
+    let _watcher_guard = S.state.terminate_notify().create_guard();
+    S.init_test_state();
+
+    S.enqueue_child_output(Ok(&ipc::VERSION_BYTES));
+    // Split mid-name, to make sure it resumes correctly.
+    #[rustfmt::skip]
+    S.enqueue_child_output(Ok(&[
+        ipc::child::TRACK_REQUEST,
+        ipc::child::KEY_REQUEST, 0x05, b'a', b'g',
+    ]));
+    #[rustfmt::skip]
+    S.enqueue_child_output(Ok(&[
+        b'e', b'n', b't',
+        ipc::child::TRACK_REQUEST,
+        ipc::child::KEY_REQUEST, 0x02, b'c', b'i',
+        ipc::child::TRACK_REQUEST,
+        ipc::child::KEY_REQUEST, 0x05, b'a', b'g', b'e', b'n', b't',
+    ]));
+    S.enqueue_child_output(Err(libc::EPIPE));
+
+    assert_result_eq(
+        S.run_ipc_message_loop(),
+        Err(Error::from_raw_os_error(libc::EPIPE)),
+    );
+
+    assert_eq!(
+        S.state.state().scrape_stats_snapshot(),
+        ScrapeStatsSnapshot::empty()
+            .with_key_requests(b"agent", 2)
+            .with_key_requests(b"ci", 1)
+    );
+
+    S.assert_no_calls_remaining();
+    guard.expect_logs(&[]);
+}
+
+#[test]
+fn read_header_then_request_health() {
+    let guard = setup_capture_logger();
+
+    // The journal loop isn't running, and the child hasn't failed.
+    static EXPECTED: &[u8] = &[0x04, 0x02];
+
+    static S: StaticState = StaticState::new();
+    let _watcher_guard = S.state.terminate_notify().create_guard();
+    S.init_test_state();
+
+    S.enqueue_child_output(Ok(&ipc::VERSION_BYTES));
+    S.enqueue_child_output(Ok(&[ipc::child::REQUEST_HEALTH]));
+    S.enqueue_child_output(Err(libc::EPIPE));
+
+    S.enqueue_child_input(Ok(EXPECTED.len()));
+
+    assert_result_eq(
+        S.run_ipc_message_loop(),
+        Err(Error::from_raw_os_error(libc::EPIPE)),
+    );
+
+    S.assert_input_sent(EXPECTED);
+
+    S.assert_no_calls_remaining();
+    guard.expect_logs(&[]);
+}
+
+#[test]
+fn read_header_then_request_health_after_journal_heartbeat() {
+    let guard = setup_capture_logger();
+
+    static EXPECTED: &[u8] = &[0x04, 0x03];
+
+    static S: StaticState = StaticState::new();
+    let _watcher_guard = S.state.terminate_notify().create_guard();
+    S.init_test_state();
+    S.state.health().journal_heartbeat(Instant::now());
+
+    S.enqueue_child_output(Ok(&ipc::VERSION_BYTES));
+    S.enqueue_child_output(Ok(&[ipc::child::REQUEST_HEALTH]));
+    S.enqueue_child_output(Err(libc::EPIPE));
+
+    S.enqueue_child_input(Ok(EXPECTED.len()));
+
+    assert_result_eq(
+        S.run_ipc_message_loop(),
+        Err(Error::from_raw_os_error(libc::EPIPE)),
+    );
+
+    S.assert_input_sent(EXPECTED);
+
+    S.assert_no_calls_remaining();
+    guard.expect_logs(&[]);
+}
+
 #[test]
 fn read_header_then_in_same_chunk_track_request_then_request_metrics() {
     let guard = setup_capture_logger();
 
     static EXPECTED_EXPOSITION: &[u8] =
-        b"\x00\x02\x05\x00\x00# TYPE journald_entries_ingested counter
+        b"\x00\x75\x1B\x00\x00# TYPE journald_entries_ingested counter
 journald_entries_ingested_created 123.456
 journald_entries_ingested_total 0
 # TYPE journald_fields_ingested counter
@@ -232,6 +857,119 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_duration_seconds_count 0
+journald_scrape_duration_seconds_sum 0.000
+journald_scrape_duration_seconds_created 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_ipc_duration_seconds_count 0
+journald_scrape_ipc_duration_seconds_sum 0.000
+journald_scrape_ipc_duration_seconds_created 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\"} 0
+journald_render_duration_seconds_bucket{le=\"0.01\"} 0
+journald_render_duration_seconds_bucket{le=\"0.025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.05\"} 0
+journald_render_duration_seconds_bucket{le=\"0.1\"} 0
+journald_render_duration_seconds_bucket{le=\"0.25\"} 0
+journald_render_duration_seconds_bucket{le=\"0.5\"} 0
+journald_render_duration_seconds_bucket{le=\"1\"} 0
+journald_render_duration_seconds_bucket{le=\"2.5\"} 0
+journald_render_duration_seconds_bucket{le=\"5\"} 0
+journald_render_duration_seconds_bucket{le=\"10\"} 0
+journald_render_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_render_duration_seconds_count 0
+journald_render_duration_seconds_sum 0.000
+journald_render_duration_seconds_created 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\"} 0
+journald_render_size_bytes_bucket{le=\"2048\"} 0
+journald_render_size_bytes_bucket{le=\"4096\"} 0
+journald_render_size_bytes_bucket{le=\"8192\"} 0
+journald_render_size_bytes_bucket{le=\"16384\"} 0
+journald_render_size_bytes_bucket{le=\"32768\"} 0
+journald_render_size_bytes_bucket{le=\"65536\"} 0
+journald_render_size_bytes_bucket{le=\"131072\"} 0
+journald_render_size_bytes_bucket{le=\"262144\"} 0
+journald_render_size_bytes_bucket{le=\"524288\"} 0
+journald_render_size_bytes_bucket{le=\"1048576\"} 0
+journald_render_size_bytes_bucket{le=\"2097152\"} 0
+journald_render_size_bytes_bucket{le=\"4194304\"} 0
+journald_render_size_bytes_bucket{le=\"+Inf\"} 0
+journald_render_size_bytes_count 0
+journald_render_size_bytes_sum 0
+journald_render_size_bytes_created 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\"} 123.456
+journald_http_responses_total{code=\"200\"} 0
+journald_http_responses_created{code=\"401\"} 123.456
+journald_http_responses_total{code=\"401\"} 0
+journald_http_responses_created{code=\"403\"} 123.456
+journald_http_responses_total{code=\"403\"} 0
+journald_http_responses_created{code=\"404\"} 123.456
+journald_http_responses_total{code=\"404\"} 0
+journald_http_responses_created{code=\"405\"} 123.456
+journald_http_responses_total{code=\"405\"} 0
+journald_http_responses_created{code=\"429\"} 123.456
+journald_http_responses_total{code=\"429\"} 0
+journald_http_responses_created{code=\"500\"} 123.456
+journald_http_responses_total{code=\"500\"} 0
+journald_http_responses_created{code=\"503\"} 123.456
+journald_http_responses_total{code=\"503\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\"} 0
+journald_auth_failures_created{reason=\"bad_syntax\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\"} 0
+journald_auth_failures_created{reason=\"wrong_user\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\"} 0
+journald_auth_failures_created{reason=\"unknown_key\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\"} 0
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\"} 123.456
+journald_throttled_requests_total{reason=\"address\"} 0
+journald_throttled_requests_created{reason=\"key\"} 123.456
+journald_throttled_requests_total{reason=\"key\"} 0
+journald_throttled_requests_created{reason=\"lockout\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\"} 0
 # EOF
 ";
 
@@ -253,6 +991,7 @@ journald_messages_ingested_bytes_total 0
     S.enqueue_child_output(Err(libc::EAGAIN));
     S.enqueue_child_output(Err(libc::EPIPE));
 
+    S.enqueue_render_timing();
     S.enqueue_child_input(Ok(EXPECTED_EXPOSITION.len()));
 
     assert_result_eq(
@@ -271,7 +1010,7 @@ fn read_header_then_track_request_then_request_metrics() {
     let guard = setup_capture_logger();
 
     static EXPECTED_EXPOSITION: &[u8] =
-        b"\x00\x02\x05\x00\x00# TYPE journald_entries_ingested counter
+        b"\x00\x75\x1B\x00\x00# TYPE journald_entries_ingested counter
 journald_entries_ingested_created 123.456
 journald_entries_ingested_total 0
 # TYPE journald_fields_ingested counter
@@ -303,6 +1042,119 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_duration_seconds_count 0
+journald_scrape_duration_seconds_sum 0.000
+journald_scrape_duration_seconds_created 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_ipc_duration_seconds_count 0
+journald_scrape_ipc_duration_seconds_sum 0.000
+journald_scrape_ipc_duration_seconds_created 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\"} 0
+journald_render_duration_seconds_bucket{le=\"0.01\"} 0
+journald_render_duration_seconds_bucket{le=\"0.025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.05\"} 0
+journald_render_duration_seconds_bucket{le=\"0.1\"} 0
+journald_render_duration_seconds_bucket{le=\"0.25\"} 0
+journald_render_duration_seconds_bucket{le=\"0.5\"} 0
+journald_render_duration_seconds_bucket{le=\"1\"} 0
+journald_render_duration_seconds_bucket{le=\"2.5\"} 0
+journald_render_duration_seconds_bucket{le=\"5\"} 0
+journald_render_duration_seconds_bucket{le=\"10\"} 0
+journald_render_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_render_duration_seconds_count 0
+journald_render_duration_seconds_sum 0.000
+journald_render_duration_seconds_created 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\"} 0
+journald_render_size_bytes_bucket{le=\"2048\"} 0
+journald_render_size_bytes_bucket{le=\"4096\"} 0
+journald_render_size_bytes_bucket{le=\"8192\"} 0
+journald_render_size_bytes_bucket{le=\"16384\"} 0
+journald_render_size_bytes_bucket{le=\"32768\"} 0
+journald_render_size_bytes_bucket{le=\"65536\"} 0
+journald_render_size_bytes_bucket{le=\"131072\"} 0
+journald_render_size_bytes_bucket{le=\"262144\"} 0
+journald_render_size_bytes_bucket{le=\"524288\"} 0
+journald_render_size_bytes_bucket{le=\"1048576\"} 0
+journald_render_size_bytes_bucket{le=\"2097152\"} 0
+journald_render_size_bytes_bucket{le=\"4194304\"} 0
+journald_render_size_bytes_bucket{le=\"+Inf\"} 0
+journald_render_size_bytes_count 0
+journald_render_size_bytes_sum 0
+journald_render_size_bytes_created 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\"} 123.456
+journald_http_responses_total{code=\"200\"} 0
+journald_http_responses_created{code=\"401\"} 123.456
+journald_http_responses_total{code=\"401\"} 0
+journald_http_responses_created{code=\"403\"} 123.456
+journald_http_responses_total{code=\"403\"} 0
+journald_http_responses_created{code=\"404\"} 123.456
+journald_http_responses_total{code=\"404\"} 0
+journald_http_responses_created{code=\"405\"} 123.456
+journald_http_responses_total{code=\"405\"} 0
+journald_http_responses_created{code=\"429\"} 123.456
+journald_http_responses_total{code=\"429\"} 0
+journald_http_responses_created{code=\"500\"} 123.456
+journald_http_responses_total{code=\"500\"} 0
+journald_http_responses_created{code=\"503\"} 123.456
+journald_http_responses_total{code=\"503\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\"} 0
+journald_auth_failures_created{reason=\"bad_syntax\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\"} 0
+journald_auth_failures_created{reason=\"wrong_user\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\"} 0
+journald_auth_failures_created{reason=\"unknown_key\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\"} 0
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\"} 123.456
+journald_throttled_requests_total{reason=\"address\"} 0
+journald_throttled_requests_created{reason=\"key\"} 123.456
+journald_throttled_requests_total{reason=\"key\"} 0
+journald_throttled_requests_created{reason=\"lockout\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\"} 0
 # EOF
 ";
 
@@ -322,6 +1174,7 @@ journald_messages_ingested_bytes_total 0
     S.enqueue_child_output(Err(libc::EAGAIN));
     S.enqueue_child_output(Err(libc::EPIPE));
 
+    S.enqueue_render_timing();
     S.enqueue_child_input(Ok(EXPECTED_EXPOSITION.len()));
 
     assert_result_eq(
@@ -340,7 +1193,7 @@ fn read_header_then_request_metrics_then_track_request() {
     let guard = setup_capture_logger();
 
     static EXPECTED_EXPOSITION: &[u8] =
-        b"\x00\x02\x05\x00\x00# TYPE journald_entries_ingested counter
+        b"\x00\x75\x1B\x00\x00# TYPE journald_entries_ingested counter
 journald_entries_ingested_created 123.456
 journald_entries_ingested_total 0
 # TYPE journald_fields_ingested counter
@@ -372,6 +1225,119 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_duration_seconds_count 0
+journald_scrape_duration_seconds_sum 0.000
+journald_scrape_duration_seconds_created 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_ipc_duration_seconds_count 0
+journald_scrape_ipc_duration_seconds_sum 0.000
+journald_scrape_ipc_duration_seconds_created 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\"} 0
+journald_render_duration_seconds_bucket{le=\"0.01\"} 0
+journald_render_duration_seconds_bucket{le=\"0.025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.05\"} 0
+journald_render_duration_seconds_bucket{le=\"0.1\"} 0
+journald_render_duration_seconds_bucket{le=\"0.25\"} 0
+journald_render_duration_seconds_bucket{le=\"0.5\"} 0
+journald_render_duration_seconds_bucket{le=\"1\"} 0
+journald_render_duration_seconds_bucket{le=\"2.5\"} 0
+journald_render_duration_seconds_bucket{le=\"5\"} 0
+journald_render_duration_seconds_bucket{le=\"10\"} 0
+journald_render_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_render_duration_seconds_count 0
+journald_render_duration_seconds_sum 0.000
+journald_render_duration_seconds_created 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\"} 0
+journald_render_size_bytes_bucket{le=\"2048\"} 0
+journald_render_size_bytes_bucket{le=\"4096\"} 0
+journald_render_size_bytes_bucket{le=\"8192\"} 0
+journald_render_size_bytes_bucket{le=\"16384\"} 0
+journald_render_size_bytes_bucket{le=\"32768\"} 0
+journald_render_size_bytes_bucket{le=\"65536\"} 0
+journald_render_size_bytes_bucket{le=\"131072\"} 0
+journald_render_size_bytes_bucket{le=\"262144\"} 0
+journald_render_size_bytes_bucket{le=\"524288\"} 0
+journald_render_size_bytes_bucket{le=\"1048576\"} 0
+journald_render_size_bytes_bucket{le=\"2097152\"} 0
+journald_render_size_bytes_bucket{le=\"4194304\"} 0
+journald_render_size_bytes_bucket{le=\"+Inf\"} 0
+journald_render_size_bytes_count 0
+journald_render_size_bytes_sum 0
+journald_render_size_bytes_created 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\"} 123.456
+journald_http_responses_total{code=\"200\"} 0
+journald_http_responses_created{code=\"401\"} 123.456
+journald_http_responses_total{code=\"401\"} 0
+journald_http_responses_created{code=\"403\"} 123.456
+journald_http_responses_total{code=\"403\"} 0
+journald_http_responses_created{code=\"404\"} 123.456
+journald_http_responses_total{code=\"404\"} 0
+journald_http_responses_created{code=\"405\"} 123.456
+journald_http_responses_total{code=\"405\"} 0
+journald_http_responses_created{code=\"429\"} 123.456
+journald_http_responses_total{code=\"429\"} 0
+journald_http_responses_created{code=\"500\"} 123.456
+journald_http_responses_total{code=\"500\"} 0
+journald_http_responses_created{code=\"503\"} 123.456
+journald_http_responses_total{code=\"503\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\"} 0
+journald_auth_failures_created{reason=\"bad_syntax\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\"} 0
+journald_auth_failures_created{reason=\"wrong_user\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\"} 0
+journald_auth_failures_created{reason=\"unknown_key\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\"} 0
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\"} 123.456
+journald_throttled_requests_total{reason=\"address\"} 0
+journald_throttled_requests_created{reason=\"key\"} 123.456
+journald_throttled_requests_total{reason=\"key\"} 0
+journald_throttled_requests_created{reason=\"lockout\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\"} 0
 # EOF
 ";
 
@@ -391,6 +1357,7 @@ journald_messages_ingested_bytes_total 0
     S.enqueue_child_output(Err(libc::EAGAIN));
     S.enqueue_child_output(Err(libc::EPIPE));
 
+    S.enqueue_render_timing();
     S.enqueue_child_input(Ok(EXPECTED_EXPOSITION.len()));
 
     assert_result_eq(
@@ -412,10 +1379,11 @@ fn read_header_then_in_same_chunk_request_keys_then_request_metrics() {
 
     let key_dir = write_test_key();
 
-    static EXPECTED_KEY_SET: &[u8] = b"\x01\x01\x100123456789abcdef";
+    static EXPECTED_KEY_SET: &[u8] =
+        b"\x01\x01\x900123456789abcdef\x04test\x07metrics\x00\x00\x00\x00\x00";
 
     static EXPECTED_EXPOSITION: &[u8] =
-        b"\x00\x02\x05\x00\x00# TYPE journald_entries_ingested counter
+        b"\x00\x75\x1B\x00\x00# TYPE journald_entries_ingested counter
 journald_entries_ingested_created 123.456
 journald_entries_ingested_total 0
 # TYPE journald_fields_ingested counter
@@ -447,6 +1415,119 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_duration_seconds_count 0
+journald_scrape_duration_seconds_sum 0.000
+journald_scrape_duration_seconds_created 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_ipc_duration_seconds_count 0
+journald_scrape_ipc_duration_seconds_sum 0.000
+journald_scrape_ipc_duration_seconds_created 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\"} 0
+journald_render_duration_seconds_bucket{le=\"0.01\"} 0
+journald_render_duration_seconds_bucket{le=\"0.025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.05\"} 0
+journald_render_duration_seconds_bucket{le=\"0.1\"} 0
+journald_render_duration_seconds_bucket{le=\"0.25\"} 0
+journald_render_duration_seconds_bucket{le=\"0.5\"} 0
+journald_render_duration_seconds_bucket{le=\"1\"} 0
+journald_render_duration_seconds_bucket{le=\"2.5\"} 0
+journald_render_duration_seconds_bucket{le=\"5\"} 0
+journald_render_duration_seconds_bucket{le=\"10\"} 0
+journald_render_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_render_duration_seconds_count 0
+journald_render_duration_seconds_sum 0.000
+journald_render_duration_seconds_created 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\"} 0
+journald_render_size_bytes_bucket{le=\"2048\"} 0
+journald_render_size_bytes_bucket{le=\"4096\"} 0
+journald_render_size_bytes_bucket{le=\"8192\"} 0
+journald_render_size_bytes_bucket{le=\"16384\"} 0
+journald_render_size_bytes_bucket{le=\"32768\"} 0
+journald_render_size_bytes_bucket{le=\"65536\"} 0
+journald_render_size_bytes_bucket{le=\"131072\"} 0
+journald_render_size_bytes_bucket{le=\"262144\"} 0
+journald_render_size_bytes_bucket{le=\"524288\"} 0
+journald_render_size_bytes_bucket{le=\"1048576\"} 0
+journald_render_size_bytes_bucket{le=\"2097152\"} 0
+journald_render_size_bytes_bucket{le=\"4194304\"} 0
+journald_render_size_bytes_bucket{le=\"+Inf\"} 0
+journald_render_size_bytes_count 0
+journald_render_size_bytes_sum 0
+journald_render_size_bytes_created 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\"} 123.456
+journald_http_responses_total{code=\"200\"} 0
+journald_http_responses_created{code=\"401\"} 123.456
+journald_http_responses_total{code=\"401\"} 0
+journald_http_responses_created{code=\"403\"} 123.456
+journald_http_responses_total{code=\"403\"} 0
+journald_http_responses_created{code=\"404\"} 123.456
+journald_http_responses_total{code=\"404\"} 0
+journald_http_responses_created{code=\"405\"} 123.456
+journald_http_responses_total{code=\"405\"} 0
+journald_http_responses_created{code=\"429\"} 123.456
+journald_http_responses_total{code=\"429\"} 0
+journald_http_responses_created{code=\"500\"} 123.456
+journald_http_responses_total{code=\"500\"} 0
+journald_http_responses_created{code=\"503\"} 123.456
+journald_http_responses_total{code=\"503\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\"} 0
+journald_auth_failures_created{reason=\"bad_syntax\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\"} 0
+journald_auth_failures_created{reason=\"wrong_user\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\"} 0
+journald_auth_failures_created{reason=\"unknown_key\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\"} 0
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\"} 123.456
+journald_throttled_requests_total{reason=\"address\"} 0
+journald_throttled_requests_created{reason=\"key\"} 123.456
+journald_throttled_requests_total{reason=\"key\"} 0
+journald_throttled_requests_created{reason=\"lockout\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\"} 0
 # EOF
 ";
 
@@ -469,6 +1550,7 @@ journald_messages_ingested_bytes_total 0
     S.enqueue_child_output(Err(libc::EPIPE));
 
     S.enqueue_child_input(Ok(EXPECTED_KEY_SET.len()));
+    S.enqueue_render_timing();
     S.enqueue_child_input(Ok(EXPECTED_EXPOSITION.len()));
 
     assert_result_eq(
@@ -490,10 +1572,11 @@ fn read_header_then_in_same_chunk_keys_then_track_then_metrics_then_track() {
 
     let key_dir = write_test_key();
 
-    static EXPECTED_KEY_SET: &[u8] = b"\x01\x01\x100123456789abcdef";
+    static EXPECTED_KEY_SET: &[u8] =
+        b"\x01\x01\x900123456789abcdef\x04test\x07metrics\x00\x00\x00\x00\x00";
 
     static EXPECTED_EXPOSITION: &[u8] =
-        b"\x00\x02\x05\x00\x00# TYPE journald_entries_ingested counter
+        b"\x00\x75\x1B\x00\x00# TYPE journald_entries_ingested counter
 journald_entries_ingested_created 123.456
 journald_entries_ingested_total 0
 # TYPE journald_fields_ingested counter
@@ -525,6 +1608,119 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_duration_seconds_count 0
+journald_scrape_duration_seconds_sum 0.000
+journald_scrape_duration_seconds_created 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_ipc_duration_seconds_count 0
+journald_scrape_ipc_duration_seconds_sum 0.000
+journald_scrape_ipc_duration_seconds_created 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\"} 0
+journald_render_duration_seconds_bucket{le=\"0.01\"} 0
+journald_render_duration_seconds_bucket{le=\"0.025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.05\"} 0
+journald_render_duration_seconds_bucket{le=\"0.1\"} 0
+journald_render_duration_seconds_bucket{le=\"0.25\"} 0
+journald_render_duration_seconds_bucket{le=\"0.5\"} 0
+journald_render_duration_seconds_bucket{le=\"1\"} 0
+journald_render_duration_seconds_bucket{le=\"2.5\"} 0
+journald_render_duration_seconds_bucket{le=\"5\"} 0
+journald_render_duration_seconds_bucket{le=\"10\"} 0
+journald_render_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_render_duration_seconds_count 0
+journald_render_duration_seconds_sum 0.000
+journald_render_duration_seconds_created 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\"} 0
+journald_render_size_bytes_bucket{le=\"2048\"} 0
+journald_render_size_bytes_bucket{le=\"4096\"} 0
+journald_render_size_bytes_bucket{le=\"8192\"} 0
+journald_render_size_bytes_bucket{le=\"16384\"} 0
+journald_render_size_bytes_bucket{le=\"32768\"} 0
+journald_render_size_bytes_bucket{le=\"65536\"} 0
+journald_render_size_bytes_bucket{le=\"131072\"} 0
+journald_render_size_bytes_bucket{le=\"262144\"} 0
+journald_render_size_bytes_bucket{le=\"524288\"} 0
+journald_render_size_bytes_bucket{le=\"1048576\"} 0
+journald_render_size_bytes_bucket{le=\"2097152\"} 0
+journald_render_size_bytes_bucket{le=\"4194304\"} 0
+journald_render_size_bytes_bucket{le=\"+Inf\"} 0
+journald_render_size_bytes_count 0
+journald_render_size_bytes_sum 0
+journald_render_size_bytes_created 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\"} 123.456
+journald_http_responses_total{code=\"200\"} 0
+journald_http_responses_created{code=\"401\"} 123.456
+journald_http_responses_total{code=\"401\"} 0
+journald_http_responses_created{code=\"403\"} 123.456
+journald_http_responses_total{code=\"403\"} 0
+journald_http_responses_created{code=\"404\"} 123.456
+journald_http_responses_total{code=\"404\"} 0
+journald_http_responses_created{code=\"405\"} 123.456
+journald_http_responses_total{code=\"405\"} 0
+journald_http_responses_created{code=\"429\"} 123.456
+journald_http_responses_total{code=\"429\"} 0
+journald_http_responses_created{code=\"500\"} 123.456
+journald_http_responses_total{code=\"500\"} 0
+journald_http_responses_created{code=\"503\"} 123.456
+journald_http_responses_total{code=\"503\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\"} 0
+journald_auth_failures_created{reason=\"bad_syntax\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\"} 0
+journald_auth_failures_created{reason=\"wrong_user\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\"} 0
+journald_auth_failures_created{reason=\"unknown_key\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\"} 0
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\"} 123.456
+journald_throttled_requests_total{reason=\"address\"} 0
+journald_throttled_requests_created{reason=\"key\"} 123.456
+journald_throttled_requests_total{reason=\"key\"} 0
+journald_throttled_requests_created{reason=\"lockout\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\"} 0
 # EOF
 ";
 
@@ -552,6 +1748,7 @@ journald_messages_ingested_bytes_total 0
     S.enqueue_child_output(Err(libc::EPIPE));
 
     S.enqueue_child_input(Ok(EXPECTED_KEY_SET.len()));
+    S.enqueue_render_timing();
     S.enqueue_child_input(Ok(EXPECTED_EXPOSITION.len()));
 
     assert_result_eq(
@@ -573,10 +1770,11 @@ fn read_header_then_in_same_chunk_request_metrics_then_request_keys() {
 
     let key_dir = write_test_key();
 
-    static EXPECTED_KEY_SET: &[u8] = b"\x01\x01\x100123456789abcdef";
+    static EXPECTED_KEY_SET: &[u8] =
+        b"\x01\x01\x900123456789abcdef\x04test\x07metrics\x00\x00\x00\x00\x00";
 
     static EXPECTED_EXPOSITION: &[u8] =
-        b"\x00\x02\x05\x00\x00# TYPE journald_entries_ingested counter
+        b"\x00\x75\x1B\x00\x00# TYPE journald_entries_ingested counter
 journald_entries_ingested_created 123.456
 journald_entries_ingested_total 0
 # TYPE journald_fields_ingested counter
@@ -608,6 +1806,119 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_duration_seconds_count 0
+journald_scrape_duration_seconds_sum 0.000
+journald_scrape_duration_seconds_created 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_ipc_duration_seconds_count 0
+journald_scrape_ipc_duration_seconds_sum 0.000
+journald_scrape_ipc_duration_seconds_created 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\"} 0
+journald_render_duration_seconds_bucket{le=\"0.01\"} 0
+journald_render_duration_seconds_bucket{le=\"0.025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.05\"} 0
+journald_render_duration_seconds_bucket{le=\"0.1\"} 0
+journald_render_duration_seconds_bucket{le=\"0.25\"} 0
+journald_render_duration_seconds_bucket{le=\"0.5\"} 0
+journald_render_duration_seconds_bucket{le=\"1\"} 0
+journald_render_duration_seconds_bucket{le=\"2.5\"} 0
+journald_render_duration_seconds_bucket{le=\"5\"} 0
+journald_render_duration_seconds_bucket{le=\"10\"} 0
+journald_render_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_render_duration_seconds_count 0
+journald_render_duration_seconds_sum 0.000
+journald_render_duration_seconds_created 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\"} 0
+journald_render_size_bytes_bucket{le=\"2048\"} 0
+journald_render_size_bytes_bucket{le=\"4096\"} 0
+journald_render_size_bytes_bucket{le=\"8192\"} 0
+journald_render_size_bytes_bucket{le=\"16384\"} 0
+journald_render_size_bytes_bucket{le=\"32768\"} 0
+journald_render_size_bytes_bucket{le=\"65536\"} 0
+journald_render_size_bytes_bucket{le=\"131072\"} 0
+journald_render_size_bytes_bucket{le=\"262144\"} 0
+journald_render_size_bytes_bucket{le=\"524288\"} 0
+journald_render_size_bytes_bucket{le=\"1048576\"} 0
+journald_render_size_bytes_bucket{le=\"2097152\"} 0
+journald_render_size_bytes_bucket{le=\"4194304\"} 0
+journald_render_size_bytes_bucket{le=\"+Inf\"} 0
+journald_render_size_bytes_count 0
+journald_render_size_bytes_sum 0
+journald_render_size_bytes_created 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\"} 123.456
+journald_http_responses_total{code=\"200\"} 0
+journald_http_responses_created{code=\"401\"} 123.456
+journald_http_responses_total{code=\"401\"} 0
+journald_http_responses_created{code=\"403\"} 123.456
+journald_http_responses_total{code=\"403\"} 0
+journald_http_responses_created{code=\"404\"} 123.456
+journald_http_responses_total{code=\"404\"} 0
+journald_http_responses_created{code=\"405\"} 123.456
+journald_http_responses_total{code=\"405\"} 0
+journald_http_responses_created{code=\"429\"} 123.456
+journald_http_responses_total{code=\"429\"} 0
+journald_http_responses_created{code=\"500\"} 123.456
+journald_http_responses_total{code=\"500\"} 0
+journald_http_responses_created{code=\"503\"} 123.456
+journald_http_responses_total{code=\"503\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\"} 0
+journald_auth_failures_created{reason=\"bad_syntax\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\"} 0
+journald_auth_failures_created{reason=\"wrong_user\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\"} 0
+journald_auth_failures_created{reason=\"unknown_key\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\"} 0
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\"} 123.456
+journald_throttled_requests_total{reason=\"address\"} 0
+journald_throttled_requests_created{reason=\"key\"} 123.456
+journald_throttled_requests_total{reason=\"key\"} 0
+journald_throttled_requests_created{reason=\"lockout\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\"} 0
 # EOF
 ";
 
@@ -630,6 +1941,7 @@ journald_messages_ingested_bytes_total 0
     S.enqueue_child_output(Err(libc::EPIPE));
 
     S.enqueue_child_input(Ok(EXPECTED_KEY_SET.len()));
+    S.enqueue_render_timing();
     S.enqueue_child_input(Ok(EXPECTED_EXPOSITION.len()));
 
     assert_result_eq(
@@ -651,10 +1963,11 @@ fn read_header_then_request_keys_then_request_metrics() {
 
     let key_dir = write_test_key();
 
-    static EXPECTED_KEY_SET: &[u8] = b"\x01\x01\x100123456789abcdef";
+    static EXPECTED_KEY_SET: &[u8] =
+        b"\x01\x01\x900123456789abcdef\x04test\x07metrics\x00\x00\x00\x00\x00";
 
     static EXPECTED_EXPOSITION: &[u8] =
-        b"\x00\x02\x05\x00\x00# TYPE journald_entries_ingested counter
+        b"\x00\x75\x1B\x00\x00# TYPE journald_entries_ingested counter
 journald_entries_ingested_created 123.456
 journald_entries_ingested_total 0
 # TYPE journald_fields_ingested counter
@@ -686,6 +1999,119 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_duration_seconds_count 0
+journald_scrape_duration_seconds_sum 0.000
+journald_scrape_duration_seconds_created 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_ipc_duration_seconds_count 0
+journald_scrape_ipc_duration_seconds_sum 0.000
+journald_scrape_ipc_duration_seconds_created 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\"} 0
+journald_render_duration_seconds_bucket{le=\"0.01\"} 0
+journald_render_duration_seconds_bucket{le=\"0.025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.05\"} 0
+journald_render_duration_seconds_bucket{le=\"0.1\"} 0
+journald_render_duration_seconds_bucket{le=\"0.25\"} 0
+journald_render_duration_seconds_bucket{le=\"0.5\"} 0
+journald_render_duration_seconds_bucket{le=\"1\"} 0
+journald_render_duration_seconds_bucket{le=\"2.5\"} 0
+journald_render_duration_seconds_bucket{le=\"5\"} 0
+journald_render_duration_seconds_bucket{le=\"10\"} 0
+journald_render_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_render_duration_seconds_count 0
+journald_render_duration_seconds_sum 0.000
+journald_render_duration_seconds_created 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\"} 0
+journald_render_size_bytes_bucket{le=\"2048\"} 0
+journald_render_size_bytes_bucket{le=\"4096\"} 0
+journald_render_size_bytes_bucket{le=\"8192\"} 0
+journald_render_size_bytes_bucket{le=\"16384\"} 0
+journald_render_size_bytes_bucket{le=\"32768\"} 0
+journald_render_size_bytes_bucket{le=\"65536\"} 0
+journald_render_size_bytes_bucket{le=\"131072\"} 0
+journald_render_size_bytes_bucket{le=\"262144\"} 0
+journald_render_size_bytes_bucket{le=\"524288\"} 0
+journald_render_size_bytes_bucket{le=\"1048576\"} 0
+journald_render_size_bytes_bucket{le=\"2097152\"} 0
+journald_render_size_bytes_bucket{le=\"4194304\"} 0
+journald_render_size_bytes_bucket{le=\"+Inf\"} 0
+journald_render_size_bytes_count 0
+journald_render_size_bytes_sum 0
+journald_render_size_bytes_created 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\"} 123.456
+journald_http_responses_total{code=\"200\"} 0
+journald_http_responses_created{code=\"401\"} 123.456
+journald_http_responses_total{code=\"401\"} 0
+journald_http_responses_created{code=\"403\"} 123.456
+journald_http_responses_total{code=\"403\"} 0
+journald_http_responses_created{code=\"404\"} 123.456
+journald_http_responses_total{code=\"404\"} 0
+journald_http_responses_created{code=\"405\"} 123.456
+journald_http_responses_total{code=\"405\"} 0
+journald_http_responses_created{code=\"429\"} 123.456
+journald_http_responses_total{code=\"429\"} 0
+journald_http_responses_created{code=\"500\"} 123.456
+journald_http_responses_total{code=\"500\"} 0
+journald_http_responses_created{code=\"503\"} 123.456
+journald_http_responses_total{code=\"503\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\"} 0
+journald_auth_failures_created{reason=\"bad_syntax\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\"} 0
+journald_auth_failures_created{reason=\"wrong_user\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\"} 0
+journald_auth_failures_created{reason=\"unknown_key\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\"} 0
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\"} 123.456
+journald_throttled_requests_total{reason=\"address\"} 0
+journald_throttled_requests_created{reason=\"key\"} 123.456
+journald_throttled_requests_total{reason=\"key\"} 0
+journald_throttled_requests_created{reason=\"lockout\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\"} 0
 # EOF
 ";
 
@@ -709,6 +2135,7 @@ journald_messages_ingested_bytes_total 0
     S.enqueue_child_output(Err(libc::EPIPE));
 
     S.enqueue_child_input(Ok(EXPECTED_KEY_SET.len()));
+    S.enqueue_render_timing();
     S.enqueue_child_input(Ok(EXPECTED_EXPOSITION.len()));
 
     assert_result_eq(
@@ -730,10 +2157,11 @@ fn read_header_then_request_metrics_then_request_keys() {
 
     let key_dir = write_test_key();
 
-    static EXPECTED_KEY_SET: &[u8] = b"\x01\x01\x100123456789abcdef";
+    static EXPECTED_KEY_SET: &[u8] =
+        b"\x01\x01\x900123456789abcdef\x04test\x07metrics\x00\x00\x00\x00\x00";
 
     static EXPECTED_EXPOSITION: &[u8] =
-        b"\x00\x02\x05\x00\x00# TYPE journald_entries_ingested counter
+        b"\x00\x75\x1B\x00\x00# TYPE journald_entries_ingested counter
 journald_entries_ingested_created 123.456
 journald_entries_ingested_total 0
 # TYPE journald_fields_ingested counter
@@ -765,6 +2193,119 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_duration_seconds_count 0
+journald_scrape_duration_seconds_sum 0.000
+journald_scrape_duration_seconds_created 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_scrape_ipc_duration_seconds_count 0
+journald_scrape_ipc_duration_seconds_sum 0.000
+journald_scrape_ipc_duration_seconds_created 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\"} 0
+journald_render_duration_seconds_bucket{le=\"0.01\"} 0
+journald_render_duration_seconds_bucket{le=\"0.025\"} 0
+journald_render_duration_seconds_bucket{le=\"0.05\"} 0
+journald_render_duration_seconds_bucket{le=\"0.1\"} 0
+journald_render_duration_seconds_bucket{le=\"0.25\"} 0
+journald_render_duration_seconds_bucket{le=\"0.5\"} 0
+journald_render_duration_seconds_bucket{le=\"1\"} 0
+journald_render_duration_seconds_bucket{le=\"2.5\"} 0
+journald_render_duration_seconds_bucket{le=\"5\"} 0
+journald_render_duration_seconds_bucket{le=\"10\"} 0
+journald_render_duration_seconds_bucket{le=\"+Inf\"} 0
+journald_render_duration_seconds_count 0
+journald_render_duration_seconds_sum 0.000
+journald_render_duration_seconds_created 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\"} 0
+journald_render_size_bytes_bucket{le=\"2048\"} 0
+journald_render_size_bytes_bucket{le=\"4096\"} 0
+journald_render_size_bytes_bucket{le=\"8192\"} 0
+journald_render_size_bytes_bucket{le=\"16384\"} 0
+journald_render_size_bytes_bucket{le=\"32768\"} 0
+journald_render_size_bytes_bucket{le=\"65536\"} 0
+journald_render_size_bytes_bucket{le=\"131072\"} 0
+journald_render_size_bytes_bucket{le=\"262144\"} 0
+journald_render_size_bytes_bucket{le=\"524288\"} 0
+journald_render_size_bytes_bucket{le=\"1048576\"} 0
+journald_render_size_bytes_bucket{le=\"2097152\"} 0
+journald_render_size_bytes_bucket{le=\"4194304\"} 0
+journald_render_size_bytes_bucket{le=\"+Inf\"} 0
+journald_render_size_bytes_count 0
+journald_render_size_bytes_sum 0
+journald_render_size_bytes_created 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\"} 123.456
+journald_http_responses_total{code=\"200\"} 0
+journald_http_responses_created{code=\"401\"} 123.456
+journald_http_responses_total{code=\"401\"} 0
+journald_http_responses_created{code=\"403\"} 123.456
+journald_http_responses_total{code=\"403\"} 0
+journald_http_responses_created{code=\"404\"} 123.456
+journald_http_responses_total{code=\"404\"} 0
+journald_http_responses_created{code=\"405\"} 123.456
+journald_http_responses_total{code=\"405\"} 0
+journald_http_responses_created{code=\"429\"} 123.456
+journald_http_responses_total{code=\"429\"} 0
+journald_http_responses_created{code=\"500\"} 123.456
+journald_http_responses_total{code=\"500\"} 0
+journald_http_responses_created{code=\"503\"} 123.456
+journald_http_responses_total{code=\"503\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\"} 0
+journald_auth_failures_created{reason=\"bad_syntax\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\"} 0
+journald_auth_failures_created{reason=\"wrong_user\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\"} 0
+journald_auth_failures_created{reason=\"unknown_key\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\"} 0
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\"} 123.456
+journald_throttled_requests_total{reason=\"address\"} 0
+journald_throttled_requests_created{reason=\"key\"} 123.456
+journald_throttled_requests_total{reason=\"key\"} 0
+journald_throttled_requests_created{reason=\"lockout\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\"} 0
 # EOF
 ";
 
@@ -787,6 +2328,7 @@ journald_messages_ingested_bytes_total 0
     S.enqueue_child_output(Err(libc::EAGAIN));
     S.enqueue_child_output(Err(libc::EPIPE));
 
+    S.enqueue_render_timing();
     S.enqueue_child_input(Ok(EXPECTED_EXPOSITION.len()));
     S.enqueue_child_input(Ok(EXPECTED_KEY_SET.len()));
 
@@ -816,6 +2358,7 @@ fn bails_on_immediately_disconnected_receiver_when_handling_request_metrics() {
             S.state.done_notify().notify();
         }),
     );
+    S.enqueue_render_timing();
 
     let _stdin_lease = S.connect_stdin();
 
@@ -1,9 +1,15 @@
 use crate::prelude::*;
 
 use super::*;
+use crate::parent::push::PushConfig;
+use crate::pusher::run_pusher;
+use crate::pusher::PushTargetConfig;
+use crate::pusher::PusherConfig;
+use crate::pusher::TEST_RETRY_POLICY;
 use crate::test_utils::CallSpy;
 use crate::test_utils::ReadSpy;
 use crate::test_utils::WriteSpy;
+use std::os::unix::net::UnixStream;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChildState {
@@ -87,6 +93,9 @@ pub struct FakeIpcChildHandle {
     pub child_input: WriteSpy,
     pub child_output: ReadSpy,
     spawn_result: Mutex<SpawnStatus>,
+    // The pusher runs for real, just on a thread, so tests can check what gets pushed.
+    pusher_config: OnceCell<PusherConfig>,
+    pusher: Mutex<Option<ThreadHandle>>,
 }
 
 impl FakeIpcChildHandle {
@@ -97,6 +106,8 @@ impl FakeIpcChildHandle {
             child_input: WriteSpy::new("child_state.stdio.input"),
             child_output: ReadSpy::new("child_state.stdio.output"),
             spawn_result: Mutex::new(SpawnStatus::None),
+            pusher_config: OnceCell::new(),
+            pusher: Mutex::new(None),
         }
     }
 
@@ -119,6 +130,7 @@ impl FakeIpcChildHandle {
 impl ParentIpcMethods for FakeIpcChildHandle {
     type ChildInput = &'static WriteSpy;
     type ChildOutput = &'static ReadSpy;
+    type PusherInput = UnixStream;
 
     fn next_instant(&'static self) -> Instant {
         self.next_instant.call(())
@@ -128,6 +140,10 @@ impl ParentIpcMethods for FakeIpcChildHandle {
         Ok(get_user_group_table())
     }
 
+    fn process_stats(&'static self) -> ProcessSnapshot {
+        ProcessSnapshot::empty()
+    }
+
     fn child_spawn(&self, _: &'static ParentIpcState<Self>) -> io::Result<(&WriteSpy, &ReadSpy)> {
         let spy_result = self.child_spawn.call(());
         let mut guard = self.lock_spawn_result();
@@ -167,6 +183,46 @@ impl ParentIpcMethods for FakeIpcChildHandle {
             SpawnStatus::Terminated(status) => status,
         }
     }
+
+    fn pusher_spawn(&'static self, s: &'static ParentIpcState<Self>) -> io::Result<UnixStream> {
+        let config = self.pusher_config.get_or_init(|| {
+            let target = |config: &Option<PushConfig>| {
+                config.as_ref().map(|config| PushTargetConfig {
+                    url: config.url.clone(),
+                    authorization: config.authorization.clone(),
+                    retry_policy: TEST_RETRY_POLICY,
+                })
+            };
+
+            PusherConfig {
+                remote_write: target(&s.dynamic().remote_write),
+                otlp: target(&s.dynamic().otlp),
+                pushgateway: target(&s.dynamic().pushgateway),
+            }
+        });
+
+        let (input, output) = UnixStream::pair()?;
+        let handle = ThreadHandle::spawn(move || run_pusher(output, config));
+
+        let mut guard = self.pusher.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_some() {
+            panic!("Pusher already spawned.");
+        }
+        *guard = Some(handle);
+
+        Ok(input)
+    }
+
+    fn pusher_wait(&'static self) -> io::Result<()> {
+        let handle = self
+            .pusher
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .expect("No pusher spawned.");
+
+        handle.join()
+    }
 }
 
 mod tests {
@@ -180,14 +236,23 @@ mod tests {
 
     fn init_mock_ipc_dynamic(s: &'static ParentIpcState<FakeIpcChildHandle>) {
         s.init_dynamic(ParentIpcDynamic {
-            port: std::num::NonZeroU16::new(1).unwrap(),
-            child_user_group: UserGroup {
-                uid: current_uid(),
-                gid: current_gid(),
-            },
             prom_environment: PromEnvironment::new(mock_system_time(123, 456)),
-            key_target: KeyWatcherTarget::new(std::path::PathBuf::new()),
-            tls_config: None,
+            server: Some(ParentServerDynamic {
+                port: std::num::NonZeroU16::new(1).unwrap(),
+                child_user_group: UserGroup {
+                    uid: current_uid(),
+                    gid: current_gid(),
+                },
+                key_target: KeyWatcherTarget::new(std::path::PathBuf::new()),
+                tls_config: None,
+                rate_limit: RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            pusher_user_group: None,
         });
     }
 
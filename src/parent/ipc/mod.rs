@@ -1,4 +1,5 @@
 mod child_spawn_manager;
+mod health;
 mod message_loop;
 #[cfg(test)]
 mod message_loop_tests;
@@ -11,6 +12,7 @@ pub(super) mod test_utils;
 mod types;
 
 pub use child_spawn_manager::*;
+pub use health::*;
 pub use message_loop::*;
 pub use native_ipc::*;
 pub use state::*;
@@ -36,6 +36,7 @@ struct UserGroupTableCacheEntry {
 
 pub struct NativeIpcMethods {
     child_state: Mutex<Option<PidFd>>,
+    pusher_state: Mutex<Option<std::process::Child>>,
     user_group_table_cache: Uncontended<Option<UserGroupTableCacheEntry>>,
 }
 
@@ -43,6 +44,7 @@ impl NativeIpcMethods {
     pub const fn new() -> NativeIpcMethods {
         NativeIpcMethods {
             child_state: Mutex::new(None),
+            pusher_state: Mutex::new(None),
             user_group_table_cache: Uncontended::new(None),
         }
     }
@@ -51,6 +53,7 @@ impl NativeIpcMethods {
 impl ParentIpcMethods for NativeIpcMethods {
     type ChildInput = std::process::ChildStdin;
     type ChildOutput = std::process::ChildStdout;
+    type PusherInput = std::process::ChildStdin;
 
     fn next_instant(&'static self) -> Instant {
         Instant::now()
@@ -117,6 +120,23 @@ impl ParentIpcMethods for NativeIpcMethods {
         Ok(table)
     }
 
+    fn process_stats(&'static self) -> ProcessSnapshot {
+        // Don't hold the lock while reading `/proc`, as the child spawn manager needs it.
+        let child_pid = self
+            .child_state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .and_then(|pidfd| pidfd.pid().ok());
+
+        // Failures are expected if the child's in the middle of restarting, so they're just left
+        // out rather than logged.
+        ProcessSnapshot {
+            parent: read_process_stats(None).ok(),
+            child: child_pid.and_then(|pid| read_process_stats(Some(pid)).ok()),
+        }
+    }
+
     fn child_spawn(
         &'static self,
         ipc_state: &'static ParentIpcState<Self>,
@@ -127,7 +147,7 @@ impl ParentIpcMethods for NativeIpcMethods {
         // fixing that is non-trivial and requires some temporary Unix sockets to coordinate.
         let mut command = std::process::Command::new("/proc/self/exe");
 
-        let ipc_dynamic = ipc_state.dynamic();
+        let ipc_dynamic = ipc_state.dynamic().server();
 
         let mut port_bytes = [0; MAX_USIZE_ASCII_BYTES];
         let port_bytes_start = write_u64(
@@ -147,9 +167,71 @@ impl ParentIpcMethods for NativeIpcMethods {
             std::ffi::OsStr::from_bytes(&port_bytes[port_bytes_start..]),
         );
 
+        let rate_limit = &ipc_dynamic.rate_limit;
+        command.env("RATE_LIMIT", rate_limit.address.rate());
+        command.env("RATE_LIMIT_BURST", rate_limit.address.burst.to_string());
+        command.env(
+            "RATE_LIMIT_IPV4_PREFIX",
+            rate_limit.ipv4_prefix_len.to_string(),
+        );
+        command.env(
+            "RATE_LIMIT_IPV6_PREFIX",
+            rate_limit.ipv6_prefix_len.to_string(),
+        );
+        if let Some(key) = &rate_limit.key {
+            command.env("KEY_RATE_LIMIT", key.rate());
+            command.env("KEY_RATE_LIMIT_BURST", key.burst.to_string());
+        }
+        if !rate_limit.exempt.is_empty() {
+            command.env("RATE_LIMIT_EXEMPT", rate_limit.exempt_list());
+        }
+        let lockout = &rate_limit.lockout;
+        command.env("AUTH_LOCKOUT_FAILURES", lockout.failures.to_string());
+        command.env("AUTH_LOCKOUT_WINDOW", lockout.window.to_string());
+        command.env("AUTH_LOCKOUT_DURATION", lockout.duration.to_string());
+        command.env(
+            "AUTH_LOCKOUT_MAX_DURATION",
+            lockout.max_duration.to_string(),
+        );
+
         if let Some(tls_options) = &ipc_dynamic.tls_config {
-            command.env("TLS_CERTIFICATE", &tls_options.certificate);
-            command.env("TLS_PRIVATE_KEY", &tls_options.private_key);
+            let pair = tls_options.target.current();
+            command.env(
+                "TLS_CERTIFICATE",
+                std::ffi::OsStr::from_bytes(&pair.certificate),
+            );
+            command.env(
+                "TLS_PRIVATE_KEY",
+                std::ffi::OsStr::from_bytes(&pair.private_key),
+            );
+            if let Some(ocsp_response) = &pair.ocsp_response {
+                use base64::engine::general_purpose::STANDARD as ENGINE;
+                use base64::engine::Engine as _;
+
+                // It's DER, and so almost certainly has a NUL somewhere in it.
+                command.env("TLS_OCSP_RESPONSE", ENGINE.encode(ocsp_response));
+            }
+            drop(pair);
+
+            let policy = &tls_options.policy;
+            command.env("TLS_MIN_VERSION", policy.min_version.as_label());
+            command.env("TLS_MAX_VERSION", policy.max_version.as_label());
+            command.env("TLS_CIPHER_SUITES", policy.cipher_suite_list());
+            if policy.session_tickets {
+                command.env("TLS_SESSION_TICKETS", "1");
+            }
+
+            if let Some(client_auth) = &tls_options.client_auth {
+                command.env("TLS_CLIENT_CA", &client_auth.ca);
+                command.env(
+                    "TLS_CLIENT_AUTH",
+                    if client_auth.required {
+                        "require"
+                    } else {
+                        "optional"
+                    },
+                );
+            }
         }
 
         let mut child = command.spawn()?;
@@ -210,4 +292,74 @@ impl ParentIpcMethods for NativeIpcMethods {
 
         status
     }
+
+    fn pusher_spawn(
+        &'static self,
+        ipc_state: &'static ParentIpcState<Self>,
+    ) -> io::Result<std::process::ChildStdin> {
+        let ipc_dynamic = ipc_state.dynamic();
+        let user_group = ipc_dynamic
+            .pusher_user_group
+            .as_ref()
+            .expect("Pusher user not resolved");
+
+        let mut command = std::process::Command::new("/proc/self/exe");
+
+        command.arg("--push-process");
+        command.stdin(Stdio::piped());
+        command.stdout(Stdio::null());
+        command.stderr(Stdio::inherit());
+        command.uid(user_group.uid);
+        command.gid(user_group.gid);
+
+        for (config, url_var, authorization_var) in [
+            (
+                &ipc_dynamic.remote_write,
+                "REMOTE_WRITE_URL",
+                "REMOTE_WRITE_AUTHORIZATION",
+            ),
+            (&ipc_dynamic.otlp, "OTLP_URL", "OTLP_AUTHORIZATION"),
+            (
+                &ipc_dynamic.pushgateway,
+                "PUSHGATEWAY_URL",
+                "PUSHGATEWAY_AUTHORIZATION",
+            ),
+        ] {
+            if let Some(config) = config {
+                command.env(url_var, config.url.to_string());
+                if let Some(authorization) = &config.authorization {
+                    command.env(authorization_var, &**authorization);
+                }
+            }
+        }
+
+        let mut child = command.spawn()?;
+        drop(command);
+
+        let stdin = child.stdin.take().unwrap();
+
+        {
+            let mut guard = self.pusher_state.lock().unwrap_or_else(|e| e.into_inner());
+            *guard = Some(child);
+        }
+
+        Ok(stdin)
+    }
+
+    fn pusher_wait(&'static self) -> io::Result<()> {
+        let mut child = self
+            .pusher_state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .expect("No pusher spawned.");
+
+        let status = child.wait()?;
+
+        if !status.success() {
+            log::error!("Pusher exited unexpectedly with {}.", status);
+        }
+
+        Ok(())
+    }
 }
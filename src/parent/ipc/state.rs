@@ -1,31 +1,64 @@
 use crate::prelude::*;
 
+use super::HealthTracker;
 use super::ParentIpcMethods;
 use crate::parent::key_watcher::KeyWatcherTarget;
+use crate::parent::push::PushConfig;
+use crate::parent::push::StatsdConfig;
+use crate::parent::push::TextfileConfig;
+use crate::parent::tls_watcher::TlsWatcherTarget;
 use std::ffi::OsStr;
 use std::num::NonZeroU16;
 
 pub struct TLSConfig {
-    pub certificate: Box<OsStr>,
-    pub private_key: Box<OsStr>,
+    pub target: TlsWatcherTarget,
+    pub client_auth: Option<TLSClientAuthConfig>,
+    pub policy: TlsPolicy,
 }
 
-pub struct ParentIpcDynamic {
+pub struct TLSClientAuthConfig {
+    pub ca: Box<OsStr>,
+    pub required: bool,
+}
+
+pub struct ParentServerDynamic {
     pub port: NonZeroU16,
     pub child_user_group: UserGroup,
-    pub prom_environment: PromEnvironment,
     pub key_target: KeyWatcherTarget,
     pub tls_config: Option<TLSConfig>,
+    pub rate_limit: RateLimitPolicy,
+}
+
+pub struct ParentIpcDynamic {
+    pub prom_environment: PromEnvironment,
+    // Not present when only pushing metrics.
+    pub server: Option<ParentServerDynamic>,
+    pub remote_write: Option<PushConfig>,
+    pub otlp: Option<PushConfig>,
+    pub statsd: Option<StatsdConfig>,
+    pub textfile: Option<TextfileConfig>,
+    // The URL already includes the grouping key.
+    pub pushgateway: Option<PushConfig>,
+    // Only resolved when there's something for the pusher to push.
+    pub pusher_user_group: Option<UserGroup>,
+}
+
+impl ParentIpcDynamic {
+    pub fn server(&self) -> &ParentServerDynamic {
+        self.server.as_ref().expect("Server not configured")
+    }
 }
 
 pub struct ParentIpcState<M: ParentIpcMethods> {
     dynamic: OnceCell<ParentIpcDynamic>,
     state: PromState,
+    health: HealthTracker,
     methods: M,
     terminate_notify: Notify,
     done_notify: Notify,
     decoder: Uncontended<ipc::child::Decoder>,
     child_input: Mutex<Option<M::ChildInput>>,
+    pusher_input: Mutex<Option<M::PusherInput>>,
 }
 
 impl<M: ParentIpcMethods> ParentIpcState<M> {
@@ -33,10 +66,12 @@ impl<M: ParentIpcMethods> ParentIpcState<M> {
         ParentIpcState {
             dynamic: OnceCell::new(),
             state: PromState::new(),
+            health: HealthTracker::new(),
             methods,
             terminate_notify: Notify::new(),
             done_notify: Notify::new(),
             child_input: Mutex::new(None),
+            pusher_input: Mutex::new(None),
             decoder: Uncontended::new(ipc::child::Decoder::new()),
         }
     }
@@ -64,6 +99,10 @@ impl<M: ParentIpcMethods> ParentIpcState<M> {
         &self.state
     }
 
+    pub fn health(&'static self) -> &'static HealthTracker {
+        &self.health
+    }
+
     pub fn decoder(&'static self) -> &'static Uncontended<ipc::child::Decoder> {
         &self.decoder
     }
@@ -75,4 +114,8 @@ impl<M: ParentIpcMethods> ParentIpcState<M> {
     pub fn child_input(&'static self) -> MutexGuard<'static, Option<M::ChildInput>> {
         self.child_input.lock().unwrap_or_else(|e| e.into_inner())
     }
+
+    pub fn pusher_input(&'static self) -> MutexGuard<'static, Option<M::PusherInput>> {
+        self.pusher_input.lock().unwrap_or_else(|e| e.into_inner())
+    }
 }
@@ -76,14 +76,23 @@ impl StaticState {
     pub fn init_test_state_with_key_dir(&'static self, key_dir: std::path::PathBuf) {
         init_logger();
         self.state.init_dynamic(ParentIpcDynamic {
-            port: std::num::NonZeroU16::new(1).unwrap(),
-            child_user_group: UserGroup {
-                uid: current_uid(),
-                gid: current_gid(),
-            },
             prom_environment: PromEnvironment::new(mock_system_time(123, 456)),
-            key_target: KeyWatcherTarget::new(key_dir),
-            tls_config: None,
+            server: Some(ParentServerDynamic {
+                port: std::num::NonZeroU16::new(1).unwrap(),
+                child_user_group: UserGroup {
+                    uid: current_uid(),
+                    gid: current_gid(),
+                },
+                key_target: KeyWatcherTarget::new(key_dir),
+                tls_config: None,
+                rate_limit: RateLimitPolicy::new(),
+            }),
+            remote_write: None,
+            otlp: None,
+            statsd: None,
+            textfile: None,
+            pushgateway: None,
+            pusher_user_group: None,
         });
     }
 
@@ -129,6 +138,13 @@ impl StaticState {
         self.state.methods().next_instant.enqueue(result);
     }
 
+    /// Rendering metrics is timed, so it reads the clock once before and once after.
+    pub fn enqueue_render_timing(&'static self) {
+        let start = Instant::now();
+        self.enqueue_next_instant(start);
+        self.enqueue_next_instant(start + Duration::from_millis(3));
+    }
+
     pub fn enqueue_child_spawn(
         &'static self,
         result: Result<(&'static ChildStateNotify, IpcExitStatus), libc::c_int>,
@@ -7,11 +7,14 @@ use crate::ffi::Pollable;
 pub trait ParentIpcMethods: Send + Sync + Sized {
     type ChildInput: Pollable + Write + Unpin + Send;
     type ChildOutput: Pollable + Read + Unpin + Send;
+    type PusherInput: Write + Send;
 
     fn next_instant(&'static self) -> Instant;
 
     fn get_user_group_table(&'static self) -> io::Result<Arc<UidGidTable>>;
 
+    fn process_stats(&'static self) -> ProcessSnapshot;
+
     fn child_spawn(
         &'static self,
         ipc_state: &'static ParentIpcState<Self>,
@@ -20,6 +23,13 @@ pub trait ParentIpcMethods: Send + Sync + Sized {
     fn child_terminate(&'static self) -> io::Result<()>;
 
     fn child_wait(&self) -> IpcExitStatus;
+
+    fn pusher_spawn(
+        &'static self,
+        ipc_state: &'static ParentIpcState<Self>,
+    ) -> io::Result<Self::PusherInput>;
+
+    fn pusher_wait(&'static self) -> io::Result<()>;
 }
 
 #[derive(Debug)]
@@ -6,15 +6,9 @@ use crate::ffi::Cursor;
 use crate::ffi::JournalRef;
 use crate::ffi::SystemdMonotonicUsec;
 use crate::ffi::SystemdProvider;
+use crate::parent::relabel::RelabelCache;
+use crate::parent::relabel::RelabelRule;
 use crate::parent::utils::WatchdogCounter;
-use const_str::cstr;
-use std::ffi::CStr;
-
-static MESSAGE: &CStr = cstr!("MESSAGE");
-static PRIORITY: &CStr = cstr!("PRIORITY");
-static UID: &CStr = cstr!("_UID");
-static GID: &CStr = cstr!("_GID");
-static SYSTEMD_UNIT: &CStr = cstr!("_SYSTEMD_UNIT");
 
 enum ServiceErrorType {
     Invalid,
@@ -49,11 +43,11 @@ impl<M: ParentIpcMethods> MessageReaderState<M> {
     fn get_data<'a>(
         &mut self,
         j: &'a mut impl JournalRef,
-        field: &'static CStr,
+        field: JournalField,
     ) -> io::Result<Option<&'a [u8]>> {
-        match j.get_data(field) {
+        match j.get_data(field.as_cstr()) {
             Ok(name) => {
-                self.state.state().add_field_ingested(name.len());
+                self.state.state().add_field_ingested(field, name.len());
                 Ok(Some(name))
             }
             Err(e) => match e.raw_os_error() {
@@ -61,17 +55,17 @@ impl<M: ParentIpcMethods> MessageReaderState<M> {
                 Some(libc::ENOENT) => Ok(None),
                 // Data field too large for architecture.
                 Some(libc::E2BIG) => {
-                    self.state.state().add_unreadable_field();
+                    self.state.state().add_unreadable_field_read(field);
                     Ok(None)
                 }
                 // Compressed entry too large.
                 Some(libc::ENOBUFS) => {
-                    self.state.state().add_unreadable_field();
+                    self.state.state().add_unreadable_field_read(field);
                     Ok(None)
                 }
                 // Entry is corrupted.
                 Some(libc::EBADMSG) => {
-                    self.state.state().add_corrupted_field();
+                    self.state.state().add_corrupted_field(field);
                     Ok(None)
                 }
                 // Other errors I'm not really able to tolerate.
@@ -83,7 +77,7 @@ impl<M: ParentIpcMethods> MessageReaderState<M> {
     fn try_read_id(
         &mut self,
         j: &mut impl JournalRef,
-        field: &'static CStr,
+        field: JournalField,
         unreadable_name: &mut Option<Box<[u8]>>,
         target: &mut Option<u32>,
     ) -> io::Result<bool> {
@@ -112,14 +106,22 @@ struct Malformed {
     gid: Option<Box<[u8]>>,
 }
 
-struct MessageReader<M: ParentIpcMethods + 'static> {
+struct MessageReader<'r, M: ParentIpcMethods + 'static> {
     inner: MessageReaderState<M>,
     malformed: Malformed,
     key: MessageKey,
+    relabel_rules: &'r [RelabelRule],
+    relabel_cache: &'r mut RelabelCache,
+    field_stats: bool,
 }
 
-impl<M: ParentIpcMethods> MessageReader<M> {
-    fn new(state: &'static ParentIpcState<M>) -> Self {
+impl<'r, M: ParentIpcMethods> MessageReader<'r, M> {
+    fn new(
+        state: &'static ParentIpcState<M>,
+        relabel_rules: &'r [RelabelRule],
+        relabel_cache: &'r mut RelabelCache,
+        field_stats: bool,
+    ) -> Self {
         Self {
             inner: MessageReaderState::new(state),
             malformed: Malformed {
@@ -129,12 +131,15 @@ impl<M: ParentIpcMethods> MessageReader<M> {
                 gid: None,
             },
             key: MessageKey::new(),
+            relabel_rules,
+            relabel_cache,
+            field_stats,
         }
     }
 
     // `true` means continue, `false` or error means abort.
     fn try_read_service(&mut self, j: &mut impl JournalRef) -> io::Result<bool> {
-        let result = self.inner.get_data(j, SYSTEMD_UNIT)?;
+        let result = self.inner.get_data(j, JournalField::SystemdUnit)?;
 
         if self.inner.state.terminate_notify().has_notified() {
             return Ok(false);
@@ -168,7 +173,7 @@ impl<M: ParentIpcMethods> MessageReader<M> {
     }
 
     fn try_read_priority(&mut self, j: &mut impl JournalRef) -> io::Result<bool> {
-        let result = self.inner.get_data(j, PRIORITY)?;
+        let result = self.inner.get_data(j, JournalField::Priority)?;
 
         if self.inner.state.terminate_notify().has_notified() {
             return Ok(false);
@@ -195,13 +200,21 @@ impl<M: ParentIpcMethods> MessageReader<M> {
     }
 
     fn try_read_uid(&mut self, j: &mut impl JournalRef) -> io::Result<bool> {
-        self.inner
-            .try_read_id(j, UID, &mut self.malformed.uid, &mut self.key.table_key.uid)
+        self.inner.try_read_id(
+            j,
+            JournalField::Uid,
+            &mut self.malformed.uid,
+            &mut self.key.table_key.uid,
+        )
     }
 
     fn try_read_gid(&mut self, j: &mut impl JournalRef) -> io::Result<bool> {
-        self.inner
-            .try_read_id(j, GID, &mut self.malformed.gid, &mut self.key.table_key.gid)
+        self.inner.try_read_id(
+            j,
+            JournalField::Gid,
+            &mut self.malformed.gid,
+            &mut self.key.table_key.gid,
+        )
     }
 
     fn try_read_msg(&mut self, j: &mut impl JournalRef) -> io::Result<()> {
@@ -211,15 +224,30 @@ impl<M: ParentIpcMethods> MessageReader<M> {
             && self.try_read_gid(j)?
         {
             // Fall back to a "message length" of 0 if missing.
-            let msg_len = self.inner.get_data(j, MESSAGE)?.map_or(0, |msg| msg.len());
+            let msg_len = self
+                .inner
+                .get_data(j, JournalField::Message)?
+                .map_or(0, |msg| msg.len());
+
+            // Only read for its field stats, so skip it when those aren't emitted.
+            if self.field_stats {
+                self.inner.get_data(j, JournalField::Cmdline)?;
+            }
+
+            // Use the entry's own timestamp rather than the current time, so replays and
+            // backfills are still tracked correctly.
+            let realtime_usec = j.get_realtime_usec()?;
 
             // No need to check. It'll get checked after this function returns anyways, and the
             // below step is fairly trivial.
 
-            self.inner
-                .state
-                .state()
-                .add_message_line_ingested(&self.key, msg_len);
+            if self.relabel_cache.apply(self.relabel_rules, &mut self.key) {
+                self.inner.state.state().add_message_line_ingested(
+                    &self.key,
+                    msg_len,
+                    realtime_usec,
+                );
+            }
         }
 
         Ok(())
@@ -310,6 +338,8 @@ const FORCE_REPORT_INTERVAL_ENTRIES: usize = 100_000;
 fn run_loop_inner<J: JournalRef>(
     s: &'static ParentIpcState<impl ParentIpcMethods>,
     provider: &'static J::Provider,
+    relabel_rules: &[RelabelRule],
+    field_stats: bool,
     resume_cursor: &mut Option<Cursor>,
 ) -> io::Result<()> {
     if s.terminate_notify().has_notified() {
@@ -368,6 +398,7 @@ fn run_loop_inner<J: JournalRef>(
             }
 
             let mut watchdog_counter = WatchdogCounter::<FORCE_REPORT_INTERVAL_ENTRIES>::new();
+            let mut relabel_cache = RelabelCache::new();
 
             while journal.next()? {
                 if s.terminate_notify().has_notified() {
@@ -383,7 +414,8 @@ fn run_loop_inner<J: JournalRef>(
                     return Ok(());
                 }
 
-                let mut reader = MessageReader::new(s);
+                let mut reader =
+                    MessageReader::new(s, relabel_rules, &mut relabel_cache, field_stats);
                 let read_msg_result = reader.try_read_msg(&mut journal);
 
                 if reader.inner.reported_error {
@@ -397,11 +429,13 @@ fn run_loop_inner<J: JournalRef>(
                 }
 
                 if watchdog_counter.hit() {
+                    s.health().journal_heartbeat(Instant::now());
                     provider.watchdog_notify()?;
                 }
             }
         };
 
+        s.health().journal_heartbeat(Instant::now());
         provider.watchdog_notify()?;
     }
 }
@@ -409,6 +443,8 @@ fn run_loop_inner<J: JournalRef>(
 pub fn run_journal_loop<J: JournalRef>(
     s: &'static ParentIpcState<impl ParentIpcMethods>,
     provider: &'static J::Provider,
+    relabel_rules: &[RelabelRule],
+    field_stats: bool,
 ) -> io::Result<()> {
     if s.terminate_notify().has_notified() {
         return Ok(());
@@ -426,7 +462,7 @@ pub fn run_journal_loop<J: JournalRef>(
             return Ok(());
         }
 
-        match run_loop_inner::<J>(s, provider, &mut resume_cursor) {
+        match run_loop_inner::<J>(s, provider, relabel_rules, field_stats, &mut resume_cursor) {
             Ok(()) => return Ok(()),
             Err(e) => match e.raw_os_error() {
                 Some(
@@ -2,6 +2,7 @@ use crate::prelude::*;
 
 use super::ipc::ParentIpcState;
 use super::journal::run_journal_loop;
+use super::relabel::parse_relabel_config;
 use crate::ffi::Cursor;
 use crate::ffi::FakeJournalRef;
 use crate::ffi::FakeSystemdProvider;
@@ -19,6 +20,7 @@ struct Entry {
     uid: Result<&'static [u8], i32>,
     gid: Result<&'static [u8], i32>,
     message: Result<&'static [u8], i32>,
+    realtime: Result<u64, i32>,
 }
 
 impl TestState {
@@ -30,7 +32,16 @@ impl TestState {
     }
 
     fn start(&'static self) -> io::Result<()> {
-        run_journal_loop::<&FakeJournalRef>(&self.state, &self.provider)
+        self.start_with_relabel_config(b"")
+    }
+
+    fn start_with_relabel_config(&'static self, config: &[u8]) -> io::Result<()> {
+        let rules = parse_relabel_config(config).unwrap();
+        run_journal_loop::<&FakeJournalRef>(&self.state, &self.provider, &rules, false)
+    }
+
+    fn start_with_field_stats(&'static self) -> io::Result<()> {
+        run_journal_loop::<&FakeJournalRef>(&self.state, &self.provider, &[], true)
     }
 
     fn snapshot(&'static self) -> PromSnapshot {
@@ -51,6 +62,10 @@ impl TestState {
         self.push_field(b"_UID", entry.uid);
         self.push_field(b"_GID", entry.gid);
         self.push_field(b"MESSAGE", entry.message);
+        self.provider
+            .journal
+            .get_realtime_usec
+            .enqueue_io(entry.realtime);
     }
 }
 
@@ -75,7 +90,8 @@ fn aborts_on_initial_watchdog_error() {
             unreadable_fields: 0,
             corrupted_fields: 0,
             metrics_requests: 0,
-            messages_ingested: ByteCountSnapshot::empty()
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
     T.provider.assert_no_calls_remaining();
@@ -101,7 +117,8 @@ fn aborts_on_initial_termination() {
             unreadable_fields: 0,
             corrupted_fields: 0,
             metrics_requests: 0,
-            messages_ingested: ByteCountSnapshot::empty()
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -128,7 +145,8 @@ fn aborts_on_early_fatal_open_failure() {
             unreadable_fields: 0,
             corrupted_fields: 0,
             metrics_requests: 0,
-            messages_ingested: ByteCountSnapshot::empty()
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -159,7 +177,8 @@ fn aborts_on_early_fatal_set_data_threshold() {
             unreadable_fields: 0,
             corrupted_fields: 0,
             metrics_requests: 0,
-            messages_ingested: ByteCountSnapshot::empty()
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -192,7 +211,8 @@ fn aborts_on_early_fatal_seek() {
             unreadable_fields: 0,
             corrupted_fields: 0,
             metrics_requests: 0,
-            messages_ingested: ByteCountSnapshot::empty()
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -228,7 +248,8 @@ fn retries_on_out_of_descriptors() {
             unreadable_fields: 0,
             corrupted_fields: 0,
             metrics_requests: 0,
-            messages_ingested: ByteCountSnapshot::empty()
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -276,7 +297,8 @@ fn retries_on_connection_error() {
             unreadable_fields: 0,
             corrupted_fields: 0,
             metrics_requests: 0,
-            messages_ingested: ByteCountSnapshot::empty()
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -311,7 +333,8 @@ fn aborts_on_io_error_during_wait() {
             unreadable_fields: 0,
             corrupted_fields: 0,
             metrics_requests: 0,
-            messages_ingested: ByteCountSnapshot::empty()
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -347,7 +370,8 @@ fn aborts_on_memory_error_during_next() {
             unreadable_fields: 0,
             corrupted_fields: 0,
             metrics_requests: 0,
-            messages_ingested: ByteCountSnapshot::empty()
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -384,7 +408,8 @@ fn aborts_on_missing_cursor_after_next() {
             unreadable_fields: 0,
             corrupted_fields: 0,
             metrics_requests: 0,
-            messages_ingested: ByteCountSnapshot::empty()
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -412,6 +437,7 @@ fn pushes_entry_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Ok(b"some text"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(false));
     T.provider.watchdog_notify.enqueue_io(Ok(()));
@@ -443,7 +469,186 @@ fn pushes_entry_then_aborts_on_wait_error() {
                 ),
                 lines: 1,
                 bytes: 9,
-            }])
+                last_seen: 1_700_000_000_123_456,
+            }]),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 18,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 1,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 3,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 3,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 9,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+            ]),
+        },
+    );
+    T.provider.assert_no_calls_remaining();
+}
+
+#[test]
+fn pushes_entry_with_cmdline_when_field_stats_enabled_then_aborts_on_wait_error() {
+    let logger_guard = setup_capture_logger();
+    static T: TestState = TestState::init();
+
+    T.provider.watchdog_notify.enqueue_io(Ok(()));
+    T.provider.open.enqueue_io(Ok(()));
+    T.provider.journal.set_data_threshold.enqueue_io(Ok(()));
+    T.provider.get_monotonic_time_usec.enqueue(123_000_000_000);
+    T.provider.journal.seek_monotonic_usec.enqueue_io(Ok(()));
+    T.provider.journal.wait.enqueue_io(Ok(true));
+    T.provider.journal.next.enqueue_io(Ok(true));
+    T.provider
+        .journal
+        .cursor
+        .enqueue_io(Ok(Cursor::new(b"test cursor")));
+    T.push_entry(Entry {
+        unit: Ok(b"my-service.service"),
+        priority: Ok(b"4"),
+        uid: Ok(b"123"),
+        gid: Ok(b"123"),
+        message: Ok(b"some text"),
+        realtime: Ok(1_700_000_000_123_456),
+    });
+    T.push_field(b"_CMDLINE", Ok(b"/usr/bin/my-service --verbose"));
+    T.provider.journal.next.enqueue_io(Ok(true));
+    T.provider
+        .journal
+        .cursor
+        .enqueue_io(Ok(Cursor::new(b"test cursor 2")));
+    T.push_entry(Entry {
+        unit: Ok(b"my-service.service"),
+        priority: Ok(b"4"),
+        uid: Ok(b"123"),
+        gid: Ok(b"123"),
+        message: Ok(b"more text"),
+        realtime: Ok(1_700_000_000_123_457),
+    });
+    T.push_field(b"_CMDLINE", Err(libc::E2BIG));
+    T.provider.journal.next.enqueue_io(Ok(false));
+    T.provider.watchdog_notify.enqueue_io(Ok(()));
+    T.provider.journal.wait.enqueue_io(Err(libc::EIO));
+
+    assert_result_eq(
+        T.start_with_field_stats(),
+        Err(Error::from_raw_os_error(libc::EIO)),
+    );
+    logger_guard.expect_logs(&[]);
+    assert_eq!(
+        T.snapshot(),
+        PromSnapshot {
+            entries_ingested: 2,
+            fields_ingested: 11,
+            data_ingested_bytes: 97,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 1,
+            corrupted_fields: 0,
+            metrics_requests: 0,
+            messages_ingested: ByteCountSnapshot::build([ByteCountSnapshotEntry {
+                key: MessageKey::build(
+                    Some(123),
+                    Some(123),
+                    Some(b"my-service.service"),
+                    Priority::Warning
+                ),
+                lines: 2,
+                bytes: 18,
+                last_seen: 1_700_000_000_123_457,
+            }]),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 36,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 2,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 6,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 6,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 18,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Cmdline,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 29,
+                        unreadable: 1,
+                        corrupted: 0
+                    }
+                ),
+            ]),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -471,6 +676,7 @@ fn pushes_entry_with_empty_message_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Ok(b""),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(false));
     T.provider.watchdog_notify.enqueue_io(Ok(()));
@@ -502,7 +708,55 @@ fn pushes_entry_with_empty_message_then_aborts_on_wait_error() {
                 ),
                 lines: 1,
                 bytes: 0,
-            }])
+                last_seen: 1_700_000_000_123_456,
+            }]),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 18,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 1,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 3,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 3,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 0,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+            ]),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -530,6 +784,7 @@ fn processes_unreadable_service_names_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -542,6 +797,7 @@ fn processes_unreadable_service_names_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -554,6 +810,7 @@ fn processes_unreadable_service_names_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -566,6 +823,7 @@ fn processes_unreadable_service_names_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(false));
     T.provider.watchdog_notify.enqueue_io(Ok(()));
@@ -592,7 +850,55 @@ fn processes_unreadable_service_names_then_aborts_on_wait_error() {
                 key: MessageKey::build(Some(123), Some(123), None, Priority::Warning),
                 lines: 4,
                 bytes: 28,
-            }])
+                last_seen: 1_700_000_000_123_456,
+            }]),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 0,
+                        ingested_bytes: 0,
+                        unreadable: 2,
+                        corrupted: 1
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 4,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 12,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 12,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 28,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+            ]),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -620,6 +926,7 @@ fn processes_unreadable_priorities_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -632,6 +939,7 @@ fn processes_unreadable_priorities_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -644,6 +952,7 @@ fn processes_unreadable_priorities_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -656,6 +965,7 @@ fn processes_unreadable_priorities_then_aborts_on_wait_error() {
         priority: Err(libc::EBADMSG),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(false));
     T.provider.watchdog_notify.enqueue_io(Ok(()));
@@ -687,7 +997,55 @@ fn processes_unreadable_priorities_then_aborts_on_wait_error() {
                 ),
                 lines: 4,
                 bytes: 28,
-            }])
+                last_seen: 1_700_000_000_123_456,
+            }]),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 72,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 0,
+                        ingested_bytes: 0,
+                        unreadable: 2,
+                        corrupted: 1
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 12,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 12,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 28,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+            ]),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -715,6 +1073,7 @@ fn processes_invalid_priority_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(false));
     T.provider.watchdog_notify.enqueue_io(Ok(()));
@@ -748,7 +1107,55 @@ fn processes_invalid_priority_then_aborts_on_wait_error() {
                 ),
                 lines: 1,
                 bytes: 7,
-            }],)
+                last_seen: 1_700_000_000_123_456,
+            }],),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 18,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 3,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 3,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 3,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 7,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+            ]),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -776,6 +1183,7 @@ fn processes_unreadable_uids_then_aborts_on_wait_error() {
         uid: Err(libc::ENOENT),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -788,6 +1196,7 @@ fn processes_unreadable_uids_then_aborts_on_wait_error() {
         uid: Err(libc::E2BIG),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -800,6 +1209,7 @@ fn processes_unreadable_uids_then_aborts_on_wait_error() {
         uid: Err(libc::ENOBUFS),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -812,6 +1222,7 @@ fn processes_unreadable_uids_then_aborts_on_wait_error() {
         uid: Err(libc::EBADMSG),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(false));
     T.provider.watchdog_notify.enqueue_io(Ok(()));
@@ -843,7 +1254,55 @@ fn processes_unreadable_uids_then_aborts_on_wait_error() {
                 ),
                 lines: 4,
                 bytes: 28,
-            }])
+                last_seen: 1_700_000_000_123_456,
+            }]),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 72,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 4,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 0,
+                        ingested_bytes: 0,
+                        unreadable: 2,
+                        corrupted: 1
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 12,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 28,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+            ]),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -871,6 +1330,7 @@ fn processes_invalid_uids_then_aborts_on_wait_error() {
         uid: Ok(b"wut"),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -883,6 +1343,7 @@ fn processes_invalid_uids_then_aborts_on_wait_error() {
         uid: Err(libc::E2BIG),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -895,6 +1356,7 @@ fn processes_invalid_uids_then_aborts_on_wait_error() {
         uid: Err(libc::ENOBUFS),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -907,6 +1369,7 @@ fn processes_invalid_uids_then_aborts_on_wait_error() {
         uid: Err(libc::EBADMSG),
         gid: Ok(b"123"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(false));
     T.provider.watchdog_notify.enqueue_io(Ok(()));
@@ -940,7 +1403,55 @@ fn processes_invalid_uids_then_aborts_on_wait_error() {
                 ),
                 lines: 4,
                 bytes: 28,
-            }])
+                last_seen: 1_700_000_000_123_456,
+            }]),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 72,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 4,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 3,
+                        unreadable: 2,
+                        corrupted: 1
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 12,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 28,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+            ]),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -968,6 +1479,7 @@ fn processes_unreadable_gids_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Err(libc::ENOENT),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -980,6 +1492,7 @@ fn processes_unreadable_gids_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Err(libc::E2BIG),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -992,6 +1505,7 @@ fn processes_unreadable_gids_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Err(libc::ENOBUFS),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -1004,6 +1518,7 @@ fn processes_unreadable_gids_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Err(libc::EBADMSG),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(false));
     T.provider.watchdog_notify.enqueue_io(Ok(()));
@@ -1035,7 +1550,55 @@ fn processes_unreadable_gids_then_aborts_on_wait_error() {
                 ),
                 lines: 4,
                 bytes: 28,
-            }])
+                last_seen: 1_700_000_000_123_456,
+            }]),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 72,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 4,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 12,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 0,
+                        ingested_bytes: 0,
+                        unreadable: 2,
+                        corrupted: 1
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 28,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+            ]),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -1063,6 +1626,7 @@ fn processes_invalid_gids_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"wut"),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -1075,6 +1639,7 @@ fn processes_invalid_gids_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Err(libc::E2BIG),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -1087,6 +1652,7 @@ fn processes_invalid_gids_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Err(libc::ENOBUFS),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -1099,6 +1665,7 @@ fn processes_invalid_gids_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Err(libc::EBADMSG),
         message: Ok(b"message"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(false));
     T.provider.watchdog_notify.enqueue_io(Ok(()));
@@ -1132,7 +1699,55 @@ fn processes_invalid_gids_then_aborts_on_wait_error() {
                 ),
                 lines: 4,
                 bytes: 28,
-            }])
+                last_seen: 1_700_000_000_123_456,
+            }]),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 72,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 4,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 12,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 3,
+                        unreadable: 2,
+                        corrupted: 1
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 28,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+            ]),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -1160,6 +1775,7 @@ fn processes_unreadable_messages_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Err(libc::ENOENT),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -1172,6 +1788,7 @@ fn processes_unreadable_messages_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Err(libc::E2BIG),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -1184,6 +1801,7 @@ fn processes_unreadable_messages_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Err(libc::ENOBUFS),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -1196,6 +1814,7 @@ fn processes_unreadable_messages_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"123"),
         message: Err(libc::EBADMSG),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(false));
     T.provider.watchdog_notify.enqueue_io(Ok(()));
@@ -1227,7 +1846,55 @@ fn processes_unreadable_messages_then_aborts_on_wait_error() {
                 ),
                 lines: 4,
                 bytes: 0,
-            }],)
+                last_seen: 1_700_000_000_123_456,
+            }],),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 72,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 4,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 12,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 4,
+                        ingested_bytes: 12,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 0,
+                        ingested_bytes: 0,
+                        unreadable: 2,
+                        corrupted: 1
+                    }
+                ),
+            ]),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -1255,6 +1922,7 @@ fn pushes_multiple_entries_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"456"),
         message: Ok(b"some text 1"),
+        realtime: Ok(1_700_000_000_223_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -1267,6 +1935,8 @@ fn pushes_multiple_entries_then_aborts_on_wait_error() {
         uid: Ok(b"123"),
         gid: Ok(b"456"),
         message: Ok(b"some text 2"),
+        // Entries aren't always in timestamp order.
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(true));
     T.provider
@@ -1279,6 +1949,7 @@ fn pushes_multiple_entries_then_aborts_on_wait_error() {
         uid: Ok(b"456"),
         gid: Ok(b"123"),
         message: Ok(b"some text 3"),
+        realtime: Ok(1_700_000_000_123_456),
     });
     T.provider.journal.next.enqueue_io(Ok(false));
     T.provider.watchdog_notify.enqueue_io(Ok(()));
@@ -1311,6 +1982,7 @@ fn pushes_multiple_entries_then_aborts_on_wait_error() {
                     ),
                     lines: 2,
                     bytes: 22,
+                    last_seen: 1_700_000_000_223_456,
                 },
                 ByteCountSnapshotEntry {
                     key: MessageKey::build(
@@ -1321,8 +1993,196 @@ fn pushes_multiple_entries_then_aborts_on_wait_error() {
                     ),
                     lines: 1,
                     bytes: 11,
+                    last_seen: 1_700_000_000_123_456,
                 },
-            ])
+            ]),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 3,
+                        ingested_bytes: 54,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 3,
+                        ingested_bytes: 3,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 3,
+                        ingested_bytes: 9,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 3,
+                        ingested_bytes: 9,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 3,
+                        ingested_bytes: 33,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+            ]),
+        },
+    );
+    T.provider.assert_no_calls_remaining();
+}
+
+#[test]
+fn relabels_entries_before_aggregation_then_aborts_on_wait_error() {
+    let logger_guard = setup_capture_logger();
+    static T: TestState = TestState::init();
+
+    T.provider.watchdog_notify.enqueue_io(Ok(()));
+    T.provider.open.enqueue_io(Ok(()));
+    T.provider.journal.set_data_threshold.enqueue_io(Ok(()));
+    T.provider.get_monotonic_time_usec.enqueue(123_000_000_000);
+    T.provider.journal.seek_monotonic_usec.enqueue_io(Ok(()));
+    T.provider.journal.wait.enqueue_io(Ok(true));
+    T.provider.journal.next.enqueue_io(Ok(true));
+    T.provider
+        .journal
+        .cursor
+        .enqueue_io(Ok(Cursor::new(b"test cursor")));
+    T.push_entry(Entry {
+        unit: Ok(b"worker@1.service"),
+        priority: Ok(b"4"),
+        uid: Ok(b"123"),
+        gid: Ok(b"456"),
+        message: Ok(b"some text 1"),
+        realtime: Ok(1_700_000_000_123_456),
+    });
+    T.provider.journal.next.enqueue_io(Ok(true));
+    T.provider
+        .journal
+        .cursor
+        .enqueue_io(Ok(Cursor::new(b"test cursor")));
+    T.push_entry(Entry {
+        unit: Ok(b"worker@2.service"),
+        priority: Ok(b"4"),
+        uid: Ok(b"123"),
+        gid: Ok(b"456"),
+        message: Ok(b"some text 2"),
+        realtime: Ok(1_700_000_000_123_456),
+    });
+    T.provider.journal.next.enqueue_io(Ok(true));
+    T.provider
+        .journal
+        .cursor
+        .enqueue_io(Ok(Cursor::new(b"test cursor")));
+    T.push_entry(Entry {
+        unit: Ok(b"chatty.service"),
+        priority: Ok(b"7"),
+        uid: Ok(b"456"),
+        gid: Ok(b"123"),
+        message: Ok(b"some text 3"),
+        realtime: Ok(1_700_000_000_123_456),
+    });
+    T.provider.journal.next.enqueue_io(Ok(false));
+    T.provider.watchdog_notify.enqueue_io(Ok(()));
+    T.provider.journal.wait.enqueue_io(Err(libc::EIO));
+
+    assert_result_eq(
+        T.start_with_relabel_config(
+            br"source_labels=service regex=worker@\d+\.service replacement=worker.service target_label=service
+action=drop source_labels=service,priority regex=chatty\.service;DEBUG",
+        ),
+        Err(Error::from_raw_os_error(libc::EIO)),
+    );
+    logger_guard.expect_logs(&[]);
+    T.provider
+        .journal
+        .seek_monotonic_usec
+        .assert_calls(&[(Id128(123), 122_940_000_000)]);
+    assert_eq!(
+        T.snapshot(),
+        PromSnapshot {
+            entries_ingested: 3,
+            fields_ingested: 15,
+            data_ingested_bytes: 100,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 0,
+            corrupted_fields: 0,
+            metrics_requests: 0,
+            messages_ingested: ByteCountSnapshot::build([ByteCountSnapshotEntry {
+                key: MessageKey::build(
+                    Some(123),
+                    Some(456),
+                    Some(b"worker.service"),
+                    Priority::Warning
+                ),
+                lines: 2,
+                bytes: 22,
+                last_seen: 1_700_000_000_123_456,
+            }]),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 3,
+                        ingested_bytes: 46,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 3,
+                        ingested_bytes: 3,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 3,
+                        ingested_bytes: 9,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 3,
+                        ingested_bytes: 9,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 3,
+                        ingested_bytes: 33,
+                        unreadable: 0,
+                        corrupted: 0
+                    }
+                ),
+            ]),
         },
     );
     T.provider.assert_no_calls_remaining();
@@ -78,7 +78,7 @@ impl KeyWatcherTarget {
 #[must_use]
 pub fn write_current_key_set(s: &'static ParentIpcState<impl ParentIpcMethods>) -> bool {
     log::info!("Retrieving key set.");
-    let key_target = &s.dynamic().key_target;
+    let key_target = &s.dynamic().server().key_target;
     match key_target.get_current_key_set() {
         // If it's terminating, it's pointless to report errors here. Also suppresses a bunch of
         // useless error logs in the tests.
@@ -219,7 +219,7 @@ fn process_path(
 
     let key_data = ZeroOnDrop(std::fs::read(entry.path())?);
 
-    match builder.push_hex(trim_ascii(&key_data.0)) {
+    match builder.push_file(entry.file_name().as_bytes(), &key_data.0) {
         KeyPushResult::Success => Ok(()),
         KeyPushResult::Invalid => Err(error!("File contents are not a valid key.")),
         KeyPushResult::TooManyKeys => {
@@ -314,17 +314,29 @@ impl notify::EventHandler for EventHandler {
 }
 
 pub fn run_watcher(s: &'static ParentIpcState<impl ParentIpcMethods>) -> io::Result<()> {
+    let key_dir = &s.dynamic().server().key_target.key_dir;
+    watch_dirs(s, &[key_dir], "Key watcher", || write_current_key_set(s))
+}
+
+/// Watches the given directories until termination is requested, calling `on_update` once each
+/// burst of changes in them settles. If `on_update` returns `false`, this stops early. `name` is
+/// what errors are logged under.
+pub fn watch_dirs(
+    s: &'static ParentIpcState<impl ParentIpcMethods>,
+    dirs: &[&Path],
+    name: &str,
+    mut on_update: impl FnMut() -> bool,
+) -> io::Result<()> {
     let checkpoint = Arc::new(Checkpoint::new(EventState::None));
 
     let mut notify_watcher =
         recommended_watcher(EventHandler(Arc::clone(&checkpoint))).map_err(to_io_error)?;
 
-    notify_watcher
-        .watch(
-            &s.dynamic().key_target.key_dir,
-            notify::RecursiveMode::NonRecursive,
-        )
-        .map_err(to_io_error)?;
+    for dir in dirs {
+        notify_watcher
+            .watch(dir, notify::RecursiveMode::NonRecursive)
+            .map_err(to_io_error)?;
+    }
 
     // To handle non-atomic writes correctly.
     const ATOMIC_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(100);
@@ -352,24 +364,21 @@ pub fn run_watcher(s: &'static ParentIpcState<impl ParentIpcMethods>) -> io::Res
 
         drop(guard);
 
-        fn report_errors(errors: Vec<notify::Error>) {
+        let report_errors = |errors: Vec<notify::Error>| {
             for e in errors {
-                log::error!(
-                    "Key watcher error: {}",
-                    normalize_errno(to_io_error(e), None)
-                );
+                log::error!("{name} error: {}", normalize_errno(to_io_error(e), None));
             }
-        }
+        };
 
         match state {
             EventState::None => {
-                if replace(&mut has_update, false) && !write_current_key_set(s) {
+                if replace(&mut has_update, false) && !on_update() {
                     break;
                 }
             }
             EventState::Error(errors) => {
                 report_errors(errors);
-                if replace(&mut has_update, false) && !write_current_key_set(s) {
+                if replace(&mut has_update, false) && !on_update() {
                     break;
                 }
             }
@@ -448,9 +457,21 @@ mod tests {
             &mut expected,
             &[
                 &[0x01, truncate_u32_u8(files.count_ones())],
-                select_file(files, FILE_TEST_KEY, b"\x100123456789abcdef"),
-                select_file(files, FILE_TEST_KEY_2, b"\x1076543210fedcba98"),
-                select_file(files, FILE_OTHER_KEY, b"\x10fedcba9876543210"),
+                select_file(
+                    files,
+                    FILE_TEST_KEY,
+                    b"\x900123456789abcdef\x04test\x07metrics\0\0\0\0\0",
+                ),
+                select_file(
+                    files,
+                    FILE_TEST_KEY_2,
+                    b"\x9076543210fedcba98\x04test\x07metrics\0\0\0\0\0",
+                ),
+                select_file(
+                    files,
+                    FILE_OTHER_KEY,
+                    b"\x90fedcba9876543210\x05other\x07metrics\0\0\0\0\0",
+                ),
             ],
         );
 
@@ -3,7 +3,10 @@ mod journal;
 #[cfg(test)]
 mod journal_tests;
 mod key_watcher;
+mod push;
+mod relabel;
 mod start;
+mod tls_watcher;
 mod utils;
 
 pub use start::start_parent;
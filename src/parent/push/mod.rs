@@ -0,0 +1,130 @@
+use crate::prelude::*;
+
+// Shared plumbing for the modes that push metrics somewhere instead of (or in addition to) serving
+// them. Each push mode runs as its own background task in the parent. The ones that push over HTTP
+// only build their bodies here, and hand them to the unprivileged pusher process to send.
+
+mod otlp;
+#[cfg(test)]
+mod otlp_tests;
+mod pushgateway;
+#[cfg(test)]
+mod pushgateway_tests;
+mod remote_write;
+#[cfg(test)]
+mod remote_write_tests;
+mod statsd;
+#[cfg(test)]
+mod statsd_tests;
+#[cfg(test)]
+mod test_utils;
+mod textfile;
+#[cfg(test)]
+mod textfile_tests;
+
+pub use otlp::*;
+pub use pushgateway::*;
+pub use remote_write::*;
+pub use statsd::*;
+pub use textfile::*;
+
+use crate::parent::ipc::ParentIpcMethods;
+use crate::parent::ipc::ParentIpcState;
+use crate::state::ipc::push::*;
+
+pub struct PushConfig {
+    pub url: HttpUrl,
+    pub interval: Duration,
+    // The full `Authorization` header value, if any.
+    pub authorization: Option<Box<str>>,
+}
+
+/// Render the metrics as a bare OpenMetrics body, for the push modes that write out the text format.
+/// The process metrics are left out, as they'd clash with the receiving end's own, and so are the
+/// scrape histograms, as nothing scrapes these.
+pub fn render_openmetrics_body(
+    s: &'static ParentIpcState<impl ParentIpcMethods>,
+) -> io::Result<Vec<u8>> {
+    let table = s.methods().get_user_group_table()?;
+
+    let Some(rendered) = s.state().snapshot().and_then(|snapshot| {
+        render_openapi_metrics(
+            &s.dynamic().prom_environment,
+            &snapshot,
+            &ProcessSnapshot::empty(),
+            None,
+            &table,
+        )
+    }) else {
+        return Err(Error::from_raw_os_error(libc::ENOMEM));
+    };
+
+    Ok(openmetrics_body(&rendered).into())
+}
+
+/// Hand a body off to the pusher process, starting it first if it isn't running. The parent never
+/// talks to push targets itself, so the pusher is what makes the requests and reads the responses.
+/// If the pusher can't be reached, the body's dropped, as the next push will include its data
+/// anyways, and a new pusher is started for that one.
+pub fn send_to_pusher(
+    s: &'static ParentIpcState<impl ParentIpcMethods>,
+    target: PushTarget,
+    body: &[u8],
+) {
+    let mut guard = s.pusher_input();
+
+    let input = match &mut *guard {
+        Some(input) => input,
+        None => match s.methods().pusher_spawn(s) {
+            Ok(input) => guard.insert(input),
+            Err(e) => {
+                log::error!(
+                    "{} push dropped, as the pusher could not be started: {}",
+                    target.name(),
+                    normalize_errno(e, None)
+                );
+                return;
+            }
+        },
+    };
+
+    if let Err(e) = write_push_request(input, target, body) {
+        *guard = None;
+        log::error!(
+            "{} push dropped, as the pusher could not be reached: {}",
+            target.name(),
+            normalize_errno(e, None)
+        );
+        if let Err(e) = s.methods().pusher_wait() {
+            log::error!("{}", normalize_errno(e, None));
+        }
+    }
+}
+
+/// Close the pusher's input and wait for it to finish whatever it still has pending. Only call
+/// this once all the push loops have returned.
+pub fn close_pusher(s: &'static ParentIpcState<impl ParentIpcMethods>) -> io::Result<()> {
+    match s.pusher_input().take() {
+        None => Ok(()),
+        Some(input) => {
+            drop(input);
+            s.methods().pusher_wait()
+        }
+    }
+}
+
+/// Push a body built by `build_body` every interval until termination is requested. Bodies that
+/// can't be built are logged and skipped.
+pub fn run_push_loop(
+    s: &'static ParentIpcState<impl ParentIpcMethods>,
+    config: &PushConfig,
+    target: PushTarget,
+    mut build_body: impl FnMut() -> io::Result<Vec<u8>>,
+) {
+    while sleep_unless_terminated(s.terminate_notify(), config.interval) {
+        match build_body() {
+            Ok(body) => send_to_pusher(s, target, &body),
+            Err(e) => log::error!("{}", normalize_errno(e, None)),
+        }
+    }
+}
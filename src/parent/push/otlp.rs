@@ -0,0 +1,39 @@
+use crate::prelude::*;
+
+use super::run_push_loop;
+use crate::parent::ipc::ParentIpcMethods;
+use crate::parent::ipc::ParentIpcState;
+use crate::state::ipc::push::PushTarget;
+use std::time::SystemTime;
+
+fn build_otlp_request(s: &'static ParentIpcState<impl ParentIpcMethods>) -> io::Result<Vec<u8>> {
+    let table = s.methods().get_user_group_table()?;
+    let time_unix_nanos = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => u64::try_from(d.as_nanos()).unwrap_or(u64::MAX),
+        Err(_) => 0,
+    };
+
+    s.state()
+        .snapshot()
+        .and_then(|snapshot| {
+            encode_otlp_metrics(
+                &s.dynamic().prom_environment,
+                &snapshot,
+                &table,
+                time_unix_nanos,
+            )
+        })
+        .ok_or_else(|| Error::from_raw_os_error(libc::ENOMEM))
+}
+
+pub fn run_otlp_loop(s: &'static ParentIpcState<impl ParentIpcMethods>) -> io::Result<()> {
+    let Some(config) = &s.dynamic().otlp else {
+        return Ok(());
+    };
+
+    log::info!("Exporting metrics to {} via OTLP.", config.url);
+
+    run_push_loop(s, config, PushTarget::Otlp, || build_otlp_request(s));
+
+    Ok(())
+}
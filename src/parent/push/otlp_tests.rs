@@ -0,0 +1,91 @@
+use super::test_utils::*;
+use super::*;
+use crate::parent::ipc::mocks::FakeIpcChildHandle;
+use crate::parent::ipc::ParentIpcDynamic;
+use crate::parent::ipc::ParentIpcState;
+
+fn init_state(
+    s: &'static ParentIpcState<FakeIpcChildHandle>,
+    url: HttpUrl,
+    authorization: Option<&str>,
+) {
+    init_logger();
+    s.init_dynamic(ParentIpcDynamic {
+        prom_environment: PromEnvironment::new(mock_system_time(123, 456)),
+        server: None,
+        remote_write: None,
+        otlp: Some(PushConfig {
+            url,
+            interval: Duration::from_millis(10),
+            authorization: authorization.map(Box::from),
+        }),
+        statsd: None,
+        textfile: None,
+        pushgateway: None,
+        pusher_user_group: None,
+    });
+}
+
+#[test]
+fn exports_protobuf_snapshot_and_retries_after_server_error() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let (server, url) = start_receiver("/v1/metrics");
+    init_state(&S, url, Some("Bearer some-token"));
+
+    S.state().add_entry_ingested();
+    S.state().add_entry_ingested();
+
+    let handle = ThreadHandle::spawn(|| run_otlp_loop(&S));
+
+    let first = receive(&server, 502, || {});
+    let second = receive(&server, 200, || {
+        S.terminate_notify().notify();
+    });
+
+    handle.join().unwrap();
+    close_and_receive_rest(&server, &S);
+
+    assert_eq!(first.body, second.body);
+    assert_eq!(second.url, "/v1/metrics");
+    assert_eq!(
+        second.header("Content-Type"),
+        Some("application/x-protobuf")
+    );
+    assert_eq!(second.header("Authorization"), Some("Bearer some-token"));
+
+    // The encoding itself is tested elsewhere, so just check the first metric is the expected
+    // cumulative sum, starting from when the environment was created.
+    let request = read_protobuf_fields(&second.body);
+    let resource_metrics = read_protobuf_fields(request[0].1.bytes());
+    let scope_metrics = read_protobuf_fields(resource_metrics[1].1.bytes());
+    let metric = read_protobuf_fields(scope_metrics[1].1.bytes());
+    assert_eq!(metric[0].1.str(), "journald_entries_ingested");
+    assert_eq!(metric[1].0, 7);
+
+    let sum = read_protobuf_fields(metric[1].1.bytes());
+    let point = read_protobuf_fields(sum[0].1.bytes());
+    assert_eq!(point[0], (2, ProtobufValue::Fixed64(123_456_000_000)));
+    assert_eq!(point[2], (6, ProtobufValue::Fixed64(2)));
+}
+
+#[test]
+fn does_not_retry_client_errors() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let (server, url) = start_receiver("/v1/metrics");
+    init_state(&S, url, None);
+
+    let handle = ThreadHandle::spawn(|| run_otlp_loop(&S));
+
+    let received = receive(&server, 400, || {
+        S.terminate_notify().notify();
+    });
+
+    handle.join().unwrap();
+
+    let rest = close_and_receive_rest(&server, &S);
+
+    assert_eq!(received.header("Authorization"), None);
+    assert!(rest.iter().all(|r| r.body != received.body));
+}
@@ -0,0 +1,72 @@
+use crate::prelude::*;
+
+use super::render_openmetrics_body;
+use super::send_to_pusher;
+use crate::parent::ipc::ParentIpcMethods;
+use crate::parent::ipc::ParentIpcState;
+use crate::state::ipc::push::PushTarget;
+
+fn is_plain_grouping_value(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'~' | b'-'))
+}
+
+fn push_grouping_label(path: &mut String, label: &str, value: &str) {
+    use base64::engine::general_purpose::URL_SAFE as ENGINE;
+    use base64::engine::Engine as _;
+
+    // Anything that isn't safe to put in a path segment as-is uses the Pushgateway's base64
+    // encoding, so slashes and such in instance names still work.
+    if is_plain_grouping_value(value) {
+        write!(path, "/{label}/{value}").unwrap();
+    } else {
+        write!(path, "/{label}@base64/{}", ENGINE.encode(value)).unwrap();
+    }
+}
+
+/// Build the URL metrics get pushed to, with the grouping key appended to the base URL's path.
+pub fn pushgateway_url(base: &HttpUrl, job: &str, instance: &str) -> HttpUrl {
+    let mut path = String::from(base.path.trim_end_matches('/'));
+    path.push_str("/metrics");
+    push_grouping_label(&mut path, "job", job);
+    push_grouping_label(&mut path, "instance", instance);
+
+    HttpUrl {
+        tls: base.tls,
+        host: base.host.clone(),
+        port: base.port,
+        path: path.into(),
+    }
+}
+
+fn build_pushgateway_body(
+    s: &'static ParentIpcState<impl ParentIpcMethods>,
+) -> io::Result<Vec<u8>> {
+    Ok(openmetrics_to_prometheus_text(&render_openmetrics_body(s)?))
+}
+
+/// Push the metrics to the Pushgateway every interval, replacing the whole group each time. Once
+/// termination is requested, one last push is made so the group reflects everything ingested
+/// before shutdown.
+pub fn run_pushgateway_loop(s: &'static ParentIpcState<impl ParentIpcMethods>) -> io::Result<()> {
+    let Some(config) = &s.dynamic().pushgateway else {
+        return Ok(());
+    };
+
+    log::info!("Pushing metrics to {} via the Pushgateway.", config.url);
+
+    loop {
+        let running = sleep_unless_terminated(s.terminate_notify(), config.interval);
+
+        match build_pushgateway_body(s) {
+            Ok(body) => send_to_pusher(s, PushTarget::Pushgateway, &body),
+            Err(e) => log::error!("{}", normalize_errno(e, None)),
+        }
+
+        if !running {
+            break Ok(());
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use super::test_utils::*;
+use super::*;
+use crate::parent::ipc::mocks::FakeIpcChildHandle;
+use crate::parent::ipc::ParentIpcDynamic;
+use crate::parent::ipc::ParentIpcState;
+
+fn init_state(
+    s: &'static ParentIpcState<FakeIpcChildHandle>,
+    url: HttpUrl,
+    interval: Duration,
+    authorization: Option<&str>,
+) {
+    init_logger();
+    s.init_dynamic(ParentIpcDynamic {
+        prom_environment: PromEnvironment::new(mock_system_time(123, 456)),
+        server: None,
+        remote_write: None,
+        otlp: None,
+        statsd: None,
+        textfile: None,
+        pushgateway: Some(PushConfig {
+            url,
+            interval,
+            authorization: authorization.map(Box::from),
+        }),
+        pusher_user_group: None,
+    });
+}
+
+#[test]
+fn builds_plain_grouping_url() {
+    let base = HttpUrl::parse("https://example.com:9091/prefix/").unwrap();
+    let url = pushgateway_url(&base, "journald-exporter", "host-1.example.com");
+
+    assert_eq!(
+        url.to_string(),
+        "https://example.com:9091/prefix/metrics/job/journald-exporter/instance/host-1.example.com"
+    );
+}
+
+#[test]
+fn builds_base64_grouping_url() {
+    let base = HttpUrl::parse("http://localhost:9091").unwrap();
+    let url = pushgateway_url(&base, "a/b", "c d");
+
+    assert_eq!(&*url.path, "/metrics/job@base64/YS9i/instance@base64/YyBk");
+}
+
+#[test]
+fn puts_prometheus_text_and_retries_after_server_error() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let (server, url) = start_receiver("/metrics/job/test/instance/host");
+    init_state(
+        &S,
+        url,
+        Duration::from_millis(10),
+        Some("Basic dXNlcjpwYXNz"),
+    );
+
+    S.state().add_entry_ingested();
+
+    let handle = ThreadHandle::spawn(|| run_pushgateway_loop(&S));
+
+    let first = receive(&server, 503, || {});
+    let second = receive(&server, 200, || {
+        S.terminate_notify().notify();
+    });
+    // The final push on termination.
+    receive(&server, 200, || {});
+
+    handle.join().unwrap();
+    close_and_receive_rest(&server, &S);
+
+    assert_eq!(first.body, second.body);
+    assert_eq!(second.method, "PUT");
+    assert_eq!(second.url, "/metrics/job/test/instance/host");
+    assert_eq!(
+        second.header("Content-Type"),
+        Some("text/plain; version=0.0.4")
+    );
+    assert_eq!(second.header("Authorization"), Some("Basic dXNlcjpwYXNz"));
+
+    let body = String::from_utf8(second.body).unwrap();
+    assert!(body.starts_with(
+        "# TYPE journald_entries_ingested_total counter\njournald_entries_ingested_total 1\n"
+    ));
+    assert!(!body.contains("# EOF"));
+}
+
+#[test]
+fn pushes_once_more_on_terminate() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let (server, url) = start_receiver("/metrics/job/test/instance/host");
+    init_state(&S, url, Duration::from_secs(60), None);
+
+    S.state().add_entry_ingested();
+
+    let handle = ThreadHandle::spawn(|| run_pushgateway_loop(&S));
+
+    S.state().add_entry_ingested();
+    S.terminate_notify().notify();
+
+    let received = receive(&server, 200, || {});
+
+    handle.join().unwrap();
+
+    assert!(close_and_receive_rest(&server, &S).is_empty());
+    assert_eq!(received.header("Authorization"), None);
+    assert!(String::from_utf8(received.body)
+        .unwrap()
+        .contains("\njournald_entries_ingested_total 2\n"));
+}
@@ -0,0 +1,47 @@
+use crate::prelude::*;
+
+use super::run_push_loop;
+use crate::parent::ipc::ParentIpcMethods;
+use crate::parent::ipc::ParentIpcState;
+use crate::state::ipc::push::PushTarget;
+use std::time::SystemTime;
+
+fn build_remote_write_request(
+    s: &'static ParentIpcState<impl ParentIpcMethods>,
+) -> io::Result<Vec<u8>> {
+    let table = s.methods().get_user_group_table()?;
+    let timestamp_millis = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => i64::try_from(d.as_millis()).unwrap_or(i64::MAX),
+        Err(_) => 0,
+    };
+
+    let Some(request) = s.state().snapshot().and_then(|snapshot| {
+        encode_remote_write(
+            &s.dynamic().prom_environment,
+            &snapshot,
+            &table,
+            timestamp_millis,
+        )
+    }) else {
+        return Err(Error::from_raw_os_error(libc::ENOMEM));
+    };
+
+    // Remote write uses the raw (block) format, not the framed format.
+    snap::raw::Encoder::new()
+        .compress_vec(&request)
+        .map_err(|e| error!("Could not compress remote write request: {e}"))
+}
+
+pub fn run_remote_write_loop(s: &'static ParentIpcState<impl ParentIpcMethods>) -> io::Result<()> {
+    let Some(config) = &s.dynamic().remote_write else {
+        return Ok(());
+    };
+
+    log::info!("Pushing metrics to {} via remote write.", config.url);
+
+    run_push_loop(s, config, PushTarget::RemoteWrite, || {
+        build_remote_write_request(s)
+    });
+
+    Ok(())
+}
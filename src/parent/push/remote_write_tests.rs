@@ -0,0 +1,119 @@
+use super::test_utils::*;
+use super::*;
+use crate::parent::ipc::mocks::FakeIpcChildHandle;
+use crate::parent::ipc::ParentIpcDynamic;
+use crate::parent::ipc::ParentIpcState;
+
+fn init_state(
+    s: &'static ParentIpcState<FakeIpcChildHandle>,
+    url: HttpUrl,
+    authorization: Option<&str>,
+) {
+    init_logger();
+    s.init_dynamic(ParentIpcDynamic {
+        prom_environment: PromEnvironment::new(mock_system_time(123, 456)),
+        server: None,
+        remote_write: Some(PushConfig {
+            url,
+            interval: Duration::from_millis(10),
+            authorization: authorization.map(Box::from),
+        }),
+        otlp: None,
+        statsd: None,
+        textfile: None,
+        pushgateway: None,
+        pusher_user_group: None,
+    });
+}
+
+#[test]
+fn pushes_snappy_compressed_snapshot_and_retries_after_server_error() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let (server, url) = start_receiver("/api/v1/write");
+    init_state(&S, url, Some("Basic dXNlcjpwYXNz"));
+
+    S.state().add_entry_ingested();
+    S.state().add_entry_ingested();
+    S.state().add_entry_ingested();
+
+    let handle = ThreadHandle::spawn(|| run_remote_write_loop(&S));
+
+    let first = receive(&server, 503, || {});
+    let second = receive(&server, 204, || {
+        S.terminate_notify().notify();
+    });
+
+    handle.join().unwrap();
+    close_and_receive_rest(&server, &S);
+
+    assert_eq!(first.body, second.body);
+    assert_eq!(second.url, "/api/v1/write");
+    assert_eq!(second.header("Content-Encoding"), Some("snappy"));
+    assert_eq!(
+        second.header("Content-Type"),
+        Some("application/x-protobuf")
+    );
+    assert_eq!(
+        second.header("X-Prometheus-Remote-Write-Version"),
+        Some("0.1.0")
+    );
+    assert_eq!(second.header("Authorization"), Some("Basic dXNlcjpwYXNz"));
+
+    let request = snap::raw::Decoder::new()
+        .decompress_vec(&second.body)
+        .unwrap();
+
+    // The encoding itself is tested elsewhere, so just check the expected series is present.
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    let mut sample_value = vec![0x09];
+    sample_value.extend_from_slice(&3.0_f64.to_le_bytes());
+
+    assert!(contains(&request, b"journald_entries_ingested_total"));
+    assert!(contains(&request, &sample_value));
+}
+
+#[test]
+fn does_not_retry_client_errors() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let (server, url) = start_receiver("/api/v1/write");
+    init_state(&S, url, Some("Bearer some-token"));
+
+    let handle = ThreadHandle::spawn(|| run_remote_write_loop(&S));
+
+    let received = receive(&server, 400, || {
+        S.terminate_notify().notify();
+    });
+
+    handle.join().unwrap();
+
+    let rest = close_and_receive_rest(&server, &S);
+
+    assert_eq!(received.header("Authorization"), Some("Bearer some-token"));
+    assert!(rest.iter().all(|r| r.body != received.body));
+}
+
+#[test]
+fn sends_no_authorization_by_default() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let (server, url) = start_receiver("/api/v1/write");
+    init_state(&S, url, None);
+
+    let handle = ThreadHandle::spawn(|| run_remote_write_loop(&S));
+
+    let received = receive(&server, 200, || {
+        S.terminate_notify().notify();
+    });
+
+    handle.join().unwrap();
+    close_and_receive_rest(&server, &S);
+
+    assert_eq!(received.header("Authorization"), None);
+}
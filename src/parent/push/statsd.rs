@@ -0,0 +1,108 @@
+use crate::prelude::*;
+
+use crate::parent::ipc::ParentIpcMethods;
+use crate::parent::ipc::ParentIpcState;
+use std::net::SocketAddr;
+use std::net::ToSocketAddrs;
+use std::net::UdpSocket;
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+
+pub enum StatsdTarget {
+    // `HOST:PORT`, resolved each time metrics are sent so DNS changes are picked up.
+    Udp(Box<str>),
+    Unix(PathBuf),
+}
+
+impl fmt::Display for StatsdTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatsdTarget::Udp(address) => write!(f, "udp://{address}"),
+            StatsdTarget::Unix(path) => write!(f, "unix://{}", path.display()),
+        }
+    }
+}
+
+pub struct StatsdConfig {
+    pub target: StatsdTarget,
+    pub interval: Duration,
+}
+
+fn send_udp_packets(address: &str, packets: &[Vec<u8>]) -> io::Result<()> {
+    let Some(remote) = address.to_socket_addrs()?.next() else {
+        return Err(error!("{address} did not resolve to any addresses."));
+    };
+
+    let local: SocketAddr = match remote {
+        SocketAddr::V4(_) => (std::net::Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (std::net::Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+
+    let socket = UdpSocket::bind(local)?;
+    socket.connect(remote)?;
+
+    for packet in packets {
+        socket.send(packet)?;
+    }
+
+    Ok(())
+}
+
+fn send_unix_packets(path: &std::path::Path, packets: &[Vec<u8>]) -> io::Result<()> {
+    let socket = UnixDatagram::unbound()?;
+
+    for packet in packets {
+        socket.send_to(packet, path)?;
+    }
+
+    Ok(())
+}
+
+/// Send the change in each counter every interval until termination is requested. Like any other
+/// StatsD client, this is fire-and-forget: packets that can't be sent are logged and dropped.
+pub fn run_statsd_loop(s: &'static ParentIpcState<impl ParentIpcMethods>) -> io::Result<()> {
+    let Some(config) = &s.dynamic().statsd else {
+        return Ok(());
+    };
+
+    log::info!("Sending metrics to {} via StatsD.", config.target);
+
+    let mut encoder = StatsdEncoder::new();
+
+    while sleep_unless_terminated(s.terminate_notify(), config.interval) {
+        let table = match s.methods().get_user_group_table() {
+            Ok(table) => table,
+            Err(e) => {
+                log::error!("{}", normalize_errno(e, None));
+                continue;
+            }
+        };
+
+        let Some(packets) = s
+            .state()
+            .snapshot()
+            .and_then(|snapshot| encoder.encode(&s.dynamic().prom_environment, &snapshot, &table))
+        else {
+            log::error!(
+                "{}",
+                normalize_errno(Error::from_raw_os_error(libc::ENOMEM), None)
+            );
+            continue;
+        };
+
+        if packets.is_empty() {
+            continue;
+        }
+
+        let result = match &config.target {
+            StatsdTarget::Udp(address) => send_udp_packets(address, &packets),
+            StatsdTarget::Unix(path) => send_unix_packets(path, &packets),
+        };
+
+        if let Err(e) = result {
+            log::warn!("StatsD send failed: {}", normalize_errno(e, None));
+        }
+    }
+
+    Ok(())
+}
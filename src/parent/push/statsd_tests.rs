@@ -0,0 +1,98 @@
+use super::*;
+use crate::parent::ipc::mocks::FakeIpcChildHandle;
+use crate::parent::ipc::ParentIpcDynamic;
+use crate::parent::ipc::ParentIpcState;
+use std::net::UdpSocket;
+use std::os::unix::net::UnixDatagram;
+
+fn init_state(s: &'static ParentIpcState<FakeIpcChildHandle>, target: StatsdTarget) {
+    init_logger();
+    s.init_dynamic(ParentIpcDynamic {
+        prom_environment: PromEnvironment::new(mock_system_time(123, 456)),
+        server: None,
+        remote_write: None,
+        otlp: None,
+        statsd: Some(StatsdConfig {
+            target,
+            interval: Duration::from_millis(10),
+        }),
+        textfile: None,
+        pushgateway: None,
+        pusher_user_group: None,
+    });
+}
+
+fn receive(recv: impl FnOnce(&mut [u8]) -> io::Result<usize>) -> String {
+    let mut buf = [0; 2048];
+    let len = recv(&mut buf).unwrap();
+    String::from_utf8(buf[..len].to_vec()).unwrap()
+}
+
+#[test]
+fn sends_counter_deltas_over_udp() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let address = receiver.local_addr().unwrap().to_string();
+    init_state(&S, StatsdTarget::Udp(address.into()));
+
+    S.state().add_entry_ingested();
+    S.state().add_entry_ingested();
+
+    let handle = ThreadHandle::spawn(|| run_statsd_loop(&S));
+
+    let first = receive(|buf| receiver.recv(buf));
+
+    S.state().add_entry_ingested();
+
+    let second = receive(|buf| receiver.recv(buf));
+
+    S.terminate_notify().notify();
+    handle.join().unwrap();
+
+    assert_eq!(first, "journald_entries_ingested:2|c");
+    assert_eq!(second, "journald_entries_ingested:1|c");
+}
+
+#[test]
+fn sends_counter_deltas_over_unix_socket() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("statsd.sock");
+    let receiver = UnixDatagram::bind(&path).unwrap();
+    receiver
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    init_state(&S, StatsdTarget::Unix(path));
+
+    S.state().add_entry_ingested();
+
+    let handle = ThreadHandle::spawn(|| run_statsd_loop(&S));
+
+    let received = receive(|buf| receiver.recv(buf));
+
+    S.terminate_notify().notify();
+    handle.join().unwrap();
+
+    assert_eq!(received, "journald_entries_ingested:1|c");
+}
+
+#[test]
+fn keeps_running_when_nothing_is_listening() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let dir = tempfile::tempdir().unwrap();
+    init_state(&S, StatsdTarget::Unix(dir.path().join("missing.sock")));
+
+    S.state().add_entry_ingested();
+
+    let handle = ThreadHandle::spawn(|| run_statsd_loop(&S));
+
+    std::thread::sleep(Duration::from_millis(50));
+    S.terminate_notify().notify();
+    handle.join().unwrap();
+}
@@ -0,0 +1,94 @@
+use crate::prelude::*;
+
+use super::close_pusher;
+use crate::parent::ipc::mocks::FakeIpcChildHandle;
+use crate::parent::ipc::ParentIpcState;
+
+// A stand-in for the push target, so requests can be inspected and responses controlled.
+pub struct ReceivedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl ReceivedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| &**v)
+    }
+}
+
+pub fn start_receiver(path: &str) -> (tiny_http::Server, HttpUrl) {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let port = server.server_addr().to_ip().unwrap().port();
+    let url = HttpUrl::parse(&format!("http://127.0.0.1:{port}{path}")).unwrap();
+    (server, url)
+}
+
+fn respond(
+    mut request: tiny_http::Request,
+    status: u16,
+    before_respond: impl FnOnce(),
+) -> ReceivedRequest {
+    let mut body = Vec::new();
+    request.as_reader().read_to_end(&mut body).unwrap();
+
+    let received = ReceivedRequest {
+        method: request.method().to_string(),
+        url: request.url().to_owned(),
+        headers: request
+            .headers()
+            .iter()
+            .map(|h| (h.field.to_string(), h.value.to_string()))
+            .collect(),
+        body,
+    };
+
+    before_respond();
+    request.respond(tiny_http::Response::empty(status)).unwrap();
+    received
+}
+
+pub fn receive(
+    server: &tiny_http::Server,
+    status: u16,
+    before_respond: impl FnOnce(),
+) -> ReceivedRequest {
+    let request = server
+        .recv_timeout(Duration::from_secs(5))
+        .unwrap()
+        .expect("No request received");
+
+    respond(request, status, before_respond)
+}
+
+/// Close the pusher, answering whatever it still had pending. The parent doesn't wait for pushes
+/// to be delivered, so there may be a few more after the ones a test inspected.
+pub fn close_and_receive_rest(
+    server: &tiny_http::Server,
+    s: &'static ParentIpcState<FakeIpcChildHandle>,
+) -> Vec<ReceivedRequest> {
+    let closed = Notify::new();
+    let mut received = Vec::new();
+
+    std::thread::scope(|scope| {
+        let handle = scope.spawn(|| {
+            let result = close_pusher(s);
+            closed.notify();
+            result
+        });
+
+        while !closed.has_notified() {
+            if let Some(request) = server.recv_timeout(Duration::from_millis(10)).unwrap() {
+                received.push(respond(request, 200, || {}));
+            }
+        }
+
+        handle.join().unwrap().unwrap();
+    });
+
+    received
+}
@@ -0,0 +1,80 @@
+use crate::prelude::*;
+
+use super::render_openmetrics_body;
+use crate::ffi::DirFd;
+use crate::parent::ipc::ParentIpcMethods;
+use crate::parent::ipc::ParentIpcState;
+use std::ffi::CString;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub const TEXTFILE_NAME: &str = "journald.prom";
+
+// The textfile collector only reads `*.prom` files, so it never sees this half-written. It's
+// suffixed with the PID so two exporters pointed at the same directory can't clobber each other's.
+pub fn textfile_temp_name() -> String {
+    format!("{TEXTFILE_NAME}.tmp.{}", std::process::id())
+}
+
+pub struct TextfileConfig {
+    pub dir: PathBuf,
+    pub interval: Duration,
+}
+
+fn build_textfile(s: &'static ParentIpcState<impl ParentIpcMethods>) -> io::Result<Vec<u8>> {
+    Ok(openmetrics_to_prometheus_text(&render_openmetrics_body(s)?))
+}
+
+fn write_textfile(dir: &Path, contents: &[u8]) -> io::Result<()> {
+    // This runs as root, so everything's done relative to the directory and nothing's followed
+    // through symlinks. Otherwise, anyone able to write to it could plant a link at the temp name
+    // and have this overwrite whatever it points to.
+    let dir = DirFd::open(dir)?;
+    let temp_name = CString::new(textfile_temp_name()).unwrap();
+
+    // Clear out anything left behind by a previous crash (or planted there) so the exclusive
+    // create below can succeed.
+    match dir.unlink(&temp_name) {
+        Err(e) if e.raw_os_error() != Some(libc::ENOENT) => return Err(e),
+        _ => {}
+    }
+
+    // The collector usually runs as its own user, so it needs to be able to read this.
+    let mut file = dir.create_new(&temp_name, 0o644)?;
+
+    if let Err(e) = file.write_all(contents) {
+        drop(file);
+        drop(dir.unlink(&temp_name));
+        return Err(e);
+    }
+
+    drop(file);
+
+    // Renames within a directory are atomic, so the collector only ever sees complete files.
+    dir.rename(&temp_name, &CString::new(TEXTFILE_NAME).unwrap())
+}
+
+/// Write the metrics to the textfile right away and then every interval until termination is
+/// requested. Failed writes are logged and retried on the next interval.
+pub fn run_textfile_loop(s: &'static ParentIpcState<impl ParentIpcMethods>) -> io::Result<()> {
+    let Some(config) = &s.dynamic().textfile else {
+        return Ok(());
+    };
+
+    log::info!(
+        "Writing metrics to {}.",
+        config.dir.join(TEXTFILE_NAME).display()
+    );
+
+    loop {
+        if let Err(e) =
+            build_textfile(s).and_then(|contents| write_textfile(&config.dir, &contents))
+        {
+            log::error!("Could not write textfile: {}", normalize_errno(e, None));
+        }
+
+        if !sleep_unless_terminated(s.terminate_notify(), config.interval) {
+            break Ok(());
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use super::*;
+use crate::parent::ipc::mocks::FakeIpcChildHandle;
+use crate::parent::ipc::ParentIpcDynamic;
+use crate::parent::ipc::ParentIpcState;
+use std::os::unix::fs::PermissionsExt;
+
+fn init_state(s: &'static ParentIpcState<FakeIpcChildHandle>, dir: &std::path::Path) {
+    init_logger();
+    s.init_dynamic(ParentIpcDynamic {
+        prom_environment: PromEnvironment::new(mock_system_time(123, 456)),
+        server: None,
+        remote_write: None,
+        otlp: None,
+        statsd: None,
+        textfile: Some(TextfileConfig {
+            dir: dir.to_owned(),
+            interval: Duration::from_millis(10),
+        }),
+        pushgateway: None,
+        pusher_user_group: None,
+    });
+}
+
+// Wait for the textfile to contain the given line, returning its full contents.
+fn wait_for_line(path: &std::path::Path, line: &str) -> String {
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    loop {
+        if let Ok(contents) = std::fs::read_to_string(path) {
+            if contents.lines().any(|l| l == line) {
+                return contents;
+            }
+        }
+
+        assert!(Instant::now() < deadline, "Timed out waiting for {line:?}");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn writes_prometheus_text_and_keeps_it_updated() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("journald.prom");
+    init_state(&S, dir.path());
+
+    S.state().add_entry_ingested();
+
+    let handle = ThreadHandle::spawn(|| run_textfile_loop(&S));
+
+    let first = wait_for_line(&path, "journald_entries_ingested_total 1");
+
+    S.state().add_entry_ingested();
+
+    wait_for_line(&path, "journald_entries_ingested_total 2");
+
+    S.terminate_notify().notify();
+    handle.join().unwrap();
+
+    assert!(first.starts_with(
+        "# TYPE journald_entries_ingested_total counter\njournald_entries_ingested_total 1\n"
+    ));
+    assert!(!first.contains("_created"));
+    assert!(!first.contains("# EOF"));
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o644);
+
+    let names: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(names, ["journald.prom"]);
+}
+
+#[test]
+fn keeps_running_when_directory_is_missing() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let dir = tempfile::tempdir().unwrap();
+    init_state(&S, &dir.path().join("missing"));
+
+    let handle = ThreadHandle::spawn(|| run_textfile_loop(&S));
+
+    std::thread::sleep(Duration::from_millis(50));
+    S.terminate_notify().notify();
+    handle.join().unwrap();
+}
+
+#[test]
+fn does_not_write_through_planted_temp_symlink() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let dir = tempfile::tempdir().unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    let target = outside.path().join("target");
+    std::fs::write(&target, "untouched").unwrap();
+    std::os::unix::fs::symlink(&target, dir.path().join(textfile_temp_name())).unwrap();
+
+    let path = dir.path().join("journald.prom");
+    init_state(&S, dir.path());
+
+    let handle = ThreadHandle::spawn(|| run_textfile_loop(&S));
+
+    wait_for_line(&path, "journald_entries_ingested_total 0");
+
+    S.terminate_notify().notify();
+    handle.join().unwrap();
+
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "untouched");
+    assert!(!std::fs::symlink_metadata(&path).unwrap().is_symlink());
+}
+
+#[test]
+fn does_not_follow_symlinked_directory() {
+    static S: ParentIpcState<FakeIpcChildHandle> = ParentIpcState::new(FakeIpcChildHandle::new());
+
+    let dir = tempfile::tempdir().unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    let link = dir.path().join("link");
+    std::os::unix::fs::symlink(outside.path(), &link).unwrap();
+    init_state(&S, &link);
+
+    let handle = ThreadHandle::spawn(|| run_textfile_loop(&S));
+
+    std::thread::sleep(Duration::from_millis(50));
+    S.terminate_notify().notify();
+    handle.join().unwrap();
+
+    assert_eq!(std::fs::read_dir(outside.path()).unwrap().count(), 0);
+}
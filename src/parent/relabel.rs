@@ -0,0 +1,921 @@
+use crate::prelude::*;
+
+use regex::Regex;
+use std::collections::HashMap;
+
+// Relabel rules are specified one per line, as whitespace-separated `field=value` pairs. Empty
+// lines and lines starting with `#` are ignored. It's modeled after Prometheus's own relabel
+// configs, just restricted to the labels that exist before aggregation:
+//
+// ```
+// # Merge all the worker instances into a single service.
+// action=replace source_labels=service regex=worker@.*\.service replacement=worker.service target_label=service
+// # Nobody cares about this service's debug logs.
+// action=drop source_labels=service,priority regex=chatty\.service;DEBUG
+// ```
+//
+// Values can't contain whitespace. Use `\s` or `\x20` in regexes if you need to match it.
+//
+// Only the existing labels can be written to. New ones would have to be carried through `MessageKey`
+// and every writer, so mapping services onto something like a `team` label isn't supported.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelabelLabel {
+    Service,
+    Priority,
+    Severity,
+    Uid,
+    Gid,
+}
+
+const LABEL_COUNT: usize = 5;
+
+impl RelabelLabel {
+    const ALL: [RelabelLabel; LABEL_COUNT] = [
+        RelabelLabel::Service,
+        RelabelLabel::Priority,
+        RelabelLabel::Severity,
+        RelabelLabel::Uid,
+        RelabelLabel::Gid,
+    ];
+
+    fn from_name(name: &str) -> Option<RelabelLabel> {
+        RelabelLabel::ALL
+            .into_iter()
+            .find(|label| label.as_name() == name)
+    }
+
+    fn as_name(self) -> &'static str {
+        match self {
+            RelabelLabel::Service => "service",
+            RelabelLabel::Priority => "priority",
+            RelabelLabel::Severity => "severity",
+            RelabelLabel::Uid => "uid",
+            RelabelLabel::Gid => "gid",
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            RelabelLabel::Service => 0,
+            RelabelLabel::Priority => 1,
+            RelabelLabel::Severity => 2,
+            RelabelLabel::Uid => 3,
+            RelabelLabel::Gid => 4,
+        }
+    }
+
+    // `severity` is derived from `priority`, so only the latter can be written to.
+    fn is_writable(self) -> bool {
+        !matches!(self, RelabelLabel::Severity)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelabelAction {
+    Replace,
+    Keep,
+    Drop,
+    LabelMap,
+}
+
+#[derive(Debug)]
+pub struct RelabelRule {
+    action: RelabelAction,
+    source_labels: Box<[RelabelLabel]>,
+    separator: Box<str>,
+    regex: Regex,
+    target_label: Option<RelabelLabel>,
+    replacement: Box<str>,
+}
+
+// Values are borrowed from the key where possible, so rules that don't rewrite anything don't
+// allocate. Only labels a rule actually wrote to get written back to the key.
+#[derive(Debug, Clone)]
+enum LabelValue<'a> {
+    Borrowed(&'a str),
+    Id(Option<u32>),
+    Owned(String),
+}
+
+impl LabelValue<'_> {
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            LabelValue::Borrowed(s) => Some(s),
+            LabelValue::Owned(s) => Some(s),
+            LabelValue::Id(None) => Some(""),
+            LabelValue::Id(Some(_)) => None,
+        }
+    }
+
+    fn push_to(&self, target: &mut String) {
+        match self.as_str() {
+            Some(s) => target.push_str(s),
+            None => {
+                if let LabelValue::Id(Some(id)) = self {
+                    write!(target, "{id}").unwrap();
+                }
+            }
+        }
+    }
+
+    fn into_owned(self) -> LabelValue<'static> {
+        match self {
+            LabelValue::Borrowed(s) => LabelValue::Owned(s.into()),
+            LabelValue::Id(id) => LabelValue::Id(id),
+            LabelValue::Owned(s) => LabelValue::Owned(s),
+        }
+    }
+}
+
+const SEVERITY_NAMES: [&str; 8] = ["0", "1", "2", "3", "4", "5", "6", "7"];
+
+struct LabelValues<'a> {
+    values: [LabelValue<'a>; LABEL_COUNT],
+    written: [bool; LABEL_COUNT],
+}
+
+type LabelChanges = [Option<LabelValue<'static>>; LABEL_COUNT];
+
+impl<'a> LabelValues<'a> {
+    fn read(key: &'a MessageKey) -> Self {
+        Self {
+            values: [
+                LabelValue::Borrowed(
+                    std::str::from_utf8(key.table_key.service_repr.as_bytes()).unwrap_or_default(),
+                ),
+                LabelValue::Borrowed(
+                    std::str::from_utf8(key.priority.as_name_bytes()).unwrap_or_default(),
+                ),
+                LabelValue::Borrowed(
+                    SEVERITY_NAMES[zero_extend_u8_usize(key.priority.as_severity_index())],
+                ),
+                LabelValue::Id(key.table_key.uid),
+                LabelValue::Id(key.table_key.gid),
+            ],
+            written: [false; LABEL_COUNT],
+        }
+    }
+
+    fn get(&self, label: RelabelLabel) -> &LabelValue<'a> {
+        &self.values[label.index()]
+    }
+
+    fn set(&mut self, label: RelabelLabel, value: LabelValue<'a>) {
+        self.values[label.index()] = value;
+        self.written[label.index()] = true;
+    }
+
+    fn into_changes(self) -> LabelChanges {
+        let mut changes = LabelChanges::default();
+        for ((change, value), written) in changes.iter_mut().zip(self.values).zip(self.written) {
+            if written {
+                *change = Some(value.into_owned());
+            }
+        }
+        changes
+    }
+}
+
+impl RelabelRule {
+    // Only joins into `scratch` when there's more than one label or the label isn't already a
+    // string.
+    fn join_source<'v>(&self, values: &'v LabelValues, scratch: &'v mut String) -> &'v str {
+        if let [label] = *self.source_labels {
+            if let Some(value) = values.get(label).as_str() {
+                return value;
+            }
+        }
+
+        scratch.clear();
+        for (i, label) in self.source_labels.iter().enumerate() {
+            if i != 0 {
+                scratch.push_str(&self.separator);
+            }
+            values.get(*label).push_to(scratch);
+        }
+        scratch
+    }
+
+    // Returns `false` if the message should be dropped.
+    fn apply(&self, values: &mut LabelValues, scratch: &mut String) -> bool {
+        match self.action {
+            RelabelAction::Replace => {
+                let source = self.join_source(values, scratch);
+                let result = self.regex.captures(source).map(|captures| {
+                    let mut result = String::new();
+                    captures.expand(&self.replacement, &mut result);
+                    result
+                });
+                if let (Some(result), Some(target)) = (result, self.target_label) {
+                    values.set(target, LabelValue::Owned(result));
+                }
+                true
+            }
+            RelabelAction::Keep => self.regex.is_match(self.join_source(values, scratch)),
+            RelabelAction::Drop => !self.regex.is_match(self.join_source(values, scratch)),
+            RelabelAction::LabelMap => {
+                // Apply them all at once, so one mapping can't feed into another.
+                let mut mapped = Vec::new();
+                for label in RelabelLabel::ALL {
+                    if let Some(captures) = self.regex.captures(label.as_name()) {
+                        scratch.clear();
+                        captures.expand(&self.replacement, scratch);
+                        match RelabelLabel::from_name(scratch) {
+                            Some(target) if target.is_writable() => {
+                                mapped.push((target, values.get(label).clone()));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                for (target, value) in mapped {
+                    values.set(target, value);
+                }
+                true
+            }
+        }
+    }
+}
+
+fn priority_from_name(name: &[u8]) -> Option<Priority> {
+    (0..8)
+        .filter_map(Priority::from_severity_index)
+        .find(|priority| priority.as_name_bytes() == name)
+}
+
+// Values that can't be represented are ignored, leaving the original value in place.
+fn write_label_values(key: &mut MessageKey, changes: LabelChanges) {
+    let [service, priority, _, uid, gid] = changes;
+
+    fn to_string(value: LabelValue) -> String {
+        match value {
+            LabelValue::Owned(s) => s,
+            value => {
+                let mut result = String::new();
+                value.push_to(&mut result);
+                result
+            }
+        }
+    }
+
+    if let Some(service) = service.map(to_string) {
+        if service.is_empty() {
+            key.table_key.service_repr = ServiceRepr::EMPTY;
+        } else if let Ok(service) = Service::from_slice(service.as_bytes()) {
+            key.set_service(service);
+        }
+    }
+
+    if let Some(priority) = priority.map(to_string) {
+        let priority = priority.as_bytes();
+        if let Some(priority) =
+            priority_from_name(priority).or_else(|| Priority::from_severity_value(priority).ok())
+        {
+            key.priority = priority;
+        }
+    }
+
+    fn write_id(target: &mut Option<u32>, value: Option<LabelValue>) {
+        match value {
+            None => {}
+            Some(LabelValue::Id(id)) => *target = id,
+            Some(value) => {
+                let value = to_string(value);
+                if value.is_empty() {
+                    *target = None;
+                } else if let Some(id) = parse_u32(value.as_bytes()) {
+                    *target = Some(id);
+                }
+            }
+        }
+    }
+
+    write_id(&mut key.table_key.uid, uid);
+    write_id(&mut key.table_key.gid, gid);
+}
+
+// Returns `false` if the message should be dropped.
+pub fn apply_relabel_rules(rules: &[RelabelRule], key: &mut MessageKey) -> bool {
+    if rules.is_empty() {
+        return true;
+    }
+
+    let changes = {
+        let mut values = LabelValues::read(key);
+        let mut scratch = String::new();
+
+        for rule in rules {
+            if !rule.apply(&mut values, &mut scratch) {
+                return false;
+            }
+        }
+
+        values.into_changes()
+    };
+
+    if changes.iter().any(Option::is_some) {
+        write_label_values(key, changes);
+    }
+
+    true
+}
+
+// Most messages come from the same few services, users, and groups, and the rules only ever look
+// at the key, so each distinct key's result is remembered rather than running every regex again
+// for every message. It's capped in case something floods the journal with distinct keys, and just
+// starts over once it fills up.
+const RELABEL_CACHE_CAPACITY: usize = 1024;
+
+pub struct RelabelCache {
+    // `None` if the key's messages are dropped.
+    results: HashMap<MessageKey, Option<MessageKey>>,
+}
+
+impl RelabelCache {
+    pub fn new() -> Self {
+        Self {
+            results: HashMap::new(),
+        }
+    }
+
+    // Like `apply_relabel_rules`, but only applies the rules the first time a key is seen. Keys
+    // are only copyable in tests.
+    #[allow(clippy::clone_on_copy)]
+    pub fn apply(&mut self, rules: &[RelabelRule], key: &mut MessageKey) -> bool {
+        if rules.is_empty() {
+            return true;
+        }
+
+        match self.results.get(key) {
+            Some(Some(relabeled)) => {
+                *key = relabeled.clone();
+                return true;
+            }
+            Some(None) => return false,
+            None => {}
+        }
+
+        let original = key.clone();
+        let keep = apply_relabel_rules(rules, key);
+
+        if self.results.len() >= RELABEL_CACHE_CAPACITY {
+            self.results.clear();
+        }
+
+        self.results.insert(original, keep.then(|| key.clone()));
+        keep
+    }
+}
+
+fn parse_relabel_rule(line: &str) -> Result<RelabelRule, String> {
+    let mut action = None::<&str>;
+    let mut source_labels = None::<&str>;
+    let mut separator = None::<&str>;
+    let mut regex = None::<&str>;
+    let mut target_label = None::<&str>;
+    let mut replacement = None::<&str>;
+
+    for field in line.split_ascii_whitespace() {
+        let Some((name, value)) = field.split_once('=') else {
+            return Err(format!("Expected `name=value`, found `{field}`."));
+        };
+
+        let slot = match name {
+            "action" => &mut action,
+            "source_labels" => &mut source_labels,
+            "separator" => &mut separator,
+            "regex" => &mut regex,
+            "target_label" => &mut target_label,
+            "replacement" => &mut replacement,
+            _ => return Err(format!("Unknown field `{name}`.")),
+        };
+
+        if slot.replace(value).is_some() {
+            return Err(format!("Duplicate field `{name}`."));
+        }
+    }
+
+    let action = match action.unwrap_or("replace") {
+        "replace" => RelabelAction::Replace,
+        "keep" => RelabelAction::Keep,
+        "drop" => RelabelAction::Drop,
+        "labelmap" => RelabelAction::LabelMap,
+        action => return Err(format!("Unknown action `{action}`.")),
+    };
+
+    let source_labels = match source_labels {
+        None => Box::default(),
+        Some(labels) => labels
+            .split(',')
+            .map(|name| {
+                RelabelLabel::from_name(name).ok_or_else(|| format!("Unknown label `{name}`."))
+            })
+            .collect::<Result<Box<[_]>, _>>()?,
+    };
+
+    let target_label = match target_label {
+        None => None,
+        Some(name) => match RelabelLabel::from_name(name) {
+            None => return Err(format!("Unknown label `{name}`.")),
+            Some(label) if !label.is_writable() => {
+                return Err(format!("Label `{name}` cannot be written to."))
+            }
+            Some(label) => Some(label),
+        },
+    };
+
+    match action {
+        RelabelAction::Replace => {
+            if target_label.is_none() {
+                return Err("`target_label` is required for `replace`.".into());
+            }
+        }
+        RelabelAction::Keep | RelabelAction::Drop => {
+            if source_labels.is_empty() {
+                return Err("`source_labels` is required for `keep` and `drop`.".into());
+            }
+            if target_label.is_some() || replacement.is_some() {
+                return Err(
+                    "`target_label` and `replacement` are not allowed for `keep` and `drop`."
+                        .into(),
+                );
+            }
+        }
+        RelabelAction::LabelMap => {
+            if !source_labels.is_empty() || target_label.is_some() {
+                return Err(
+                    "`source_labels` and `target_label` are not allowed for `labelmap`.".into(),
+                );
+            }
+        }
+    }
+
+    // Like Prometheus, regexes are fully anchored.
+    let regex = regex.unwrap_or("(.*)");
+    let regex = match Regex::new(&format!("^(?:{regex})$")) {
+        Ok(regex) => regex,
+        Err(e) => return Err(format!("Invalid regex: {e}")),
+    };
+
+    Ok(RelabelRule {
+        action,
+        source_labels,
+        separator: separator.unwrap_or(";").into(),
+        regex,
+        target_label,
+        replacement: replacement.unwrap_or("$1").into(),
+    })
+}
+
+pub fn parse_relabel_config(source: &[u8]) -> io::Result<Box<[RelabelRule]>> {
+    let Ok(source) = std::str::from_utf8(source) else {
+        return Err(error!(
+            ErrorKind::InvalidData,
+            "Relabel config is not valid UTF-8."
+        ));
+    };
+
+    let mut rules = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match parse_relabel_rule(line) {
+            Ok(rule) => rules.push(rule),
+            Err(message) => {
+                return Err(error!(
+                    ErrorKind::InvalidData,
+                    "Invalid relabel rule on line {}: {}",
+                    index.wrapping_add(1),
+                    message
+                ))
+            }
+        }
+    }
+
+    Ok(rules.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Box<[RelabelRule]> {
+        parse_relabel_config(source.as_bytes()).unwrap()
+    }
+
+    fn parse_err(source: &str) -> String {
+        parse_relabel_config(source.as_bytes())
+            .unwrap_err()
+            .to_string()
+    }
+
+    fn relabel(rules: &str, key: MessageKey) -> Option<MessageKey> {
+        let mut key = key;
+        if apply_relabel_rules(&parse(rules), &mut key) {
+            Some(key)
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn ignores_empty_lines_and_comments() {
+        assert_eq!(parse("\n  # comment\n\n").len(), 0);
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        assert_eq!(
+            parse_relabel_config(b"\xFF").unwrap_err().to_string(),
+            "Relabel config is not valid UTF-8."
+        );
+    }
+
+    #[test]
+    fn rejects_fields_without_values() {
+        assert_eq!(
+            parse_err("action"),
+            "Invalid relabel rule on line 1: Expected `name=value`, found `action`."
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert_eq!(
+            parse_err("# comment\nfoo=bar"),
+            "Invalid relabel rule on line 2: Unknown field `foo`."
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_fields() {
+        assert_eq!(
+            parse_err("target_label=service target_label=service"),
+            "Invalid relabel rule on line 1: Duplicate field `target_label`."
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_actions() {
+        assert_eq!(
+            parse_err("action=hashmod"),
+            "Invalid relabel rule on line 1: Unknown action `hashmod`."
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_labels() {
+        assert_eq!(
+            parse_err("source_labels=service,user target_label=service"),
+            "Invalid relabel rule on line 1: Unknown label `user`."
+        );
+    }
+
+    #[test]
+    fn rejects_writing_to_severity() {
+        assert_eq!(
+            parse_err("source_labels=priority target_label=severity"),
+            "Invalid relabel rule on line 1: Label `severity` cannot be written to."
+        );
+    }
+
+    #[test]
+    fn rejects_replace_without_target() {
+        assert_eq!(
+            parse_err("source_labels=service"),
+            "Invalid relabel rule on line 1: `target_label` is required for `replace`."
+        );
+    }
+
+    #[test]
+    fn rejects_drop_without_source() {
+        assert_eq!(
+            parse_err("action=drop regex=foo"),
+            "Invalid relabel rule on line 1: `source_labels` is required for `keep` and `drop`."
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_regexes() {
+        assert!(
+            parse_err("source_labels=service regex=( target_label=service")
+                .starts_with("Invalid relabel rule on line 1: Invalid regex: ")
+        );
+    }
+
+    #[test]
+    fn no_rules_leaves_key_untouched() {
+        let key = MessageKey::build(Some(123), Some(456), Some(b"foo"), Priority::Warning);
+        assert_eq!(relabel("", key), Some(key));
+    }
+
+    #[test]
+    fn non_matching_rules_leave_key_untouched() {
+        let key = MessageKey::build(Some(123), Some(456), Some(b"foo"), Priority::Warning);
+        let rules = parse(
+            "source_labels=service regex=bar replacement=baz target_label=service\n\
+             action=keep source_labels=service,priority regex=foo;WARNING\n\
+             action=drop source_labels=service regex=bar",
+        );
+
+        let mut values = LabelValues::read(&key);
+        let mut scratch = String::new();
+        for rule in rules.iter() {
+            assert!(rule.apply(&mut values, &mut scratch));
+        }
+
+        assert!(matches!(
+            values.get(RelabelLabel::Service),
+            LabelValue::Borrowed("foo")
+        ));
+        assert!(values.into_changes().iter().all(Option::is_none));
+
+        let mut relabeled = key;
+        assert!(apply_relabel_rules(&rules, &mut relabeled));
+        assert_eq!(relabeled, key);
+    }
+
+    #[test]
+    fn replace_maps_several_services_to_one() {
+        let rules = r"source_labels=service regex=(foo|bar)\.service replacement=team.service target_label=service";
+
+        assert_eq!(
+            relabel(
+                rules,
+                MessageKey::build(Some(1), Some(2), Some(b"foo.service"), Priority::Warning)
+            ),
+            Some(MessageKey::build(
+                Some(1),
+                Some(2),
+                Some(b"team.service"),
+                Priority::Warning
+            )),
+        );
+        assert_eq!(
+            relabel(
+                rules,
+                MessageKey::build(Some(1), Some(2), Some(b"bar.service"), Priority::Warning)
+            ),
+            Some(MessageKey::build(
+                Some(1),
+                Some(2),
+                Some(b"team.service"),
+                Priority::Warning
+            )),
+        );
+        assert_eq!(
+            relabel(
+                rules,
+                MessageKey::build(Some(1), Some(2), Some(b"baz.service"), Priority::Warning)
+            ),
+            Some(MessageKey::build(
+                Some(1),
+                Some(2),
+                Some(b"baz.service"),
+                Priority::Warning
+            )),
+        );
+    }
+
+    #[test]
+    fn replace_substitutes_captures() {
+        assert_eq!(
+            relabel(
+                r"source_labels=service regex=(\w+)@\d+\.service replacement=$1.service target_label=service",
+                MessageKey::build(None, None, Some(b"worker@12.service"), Priority::Notice)
+            ),
+            Some(MessageKey::build(
+                None,
+                None,
+                Some(b"worker.service"),
+                Priority::Notice
+            )),
+        );
+    }
+
+    #[test]
+    fn replace_remaps_priority_of_a_single_service() {
+        let rules = r"source_labels=service,priority regex=chatty\.service;(INFO|NOTICE) replacement=DEBUG target_label=priority";
+
+        assert_eq!(
+            relabel(
+                rules,
+                MessageKey::build(None, None, Some(b"chatty.service"), Priority::Notice)
+            ),
+            Some(MessageKey::build(
+                None,
+                None,
+                Some(b"chatty.service"),
+                Priority::Debug
+            )),
+        );
+        assert_eq!(
+            relabel(
+                rules,
+                MessageKey::build(None, None, Some(b"chatty.service"), Priority::Error)
+            ),
+            Some(MessageKey::build(
+                None,
+                None,
+                Some(b"chatty.service"),
+                Priority::Error
+            )),
+        );
+    }
+
+    #[test]
+    fn replace_accepts_severity_numbers_for_priority() {
+        assert_eq!(
+            relabel(
+                "source_labels=severity regex=[0-2] replacement=3 target_label=priority",
+                MessageKey::build(None, None, None, Priority::Alert)
+            ),
+            Some(MessageKey::build(None, None, None, Priority::Error)),
+        );
+    }
+
+    #[test]
+    fn replace_with_empty_result_removes_label() {
+        assert_eq!(
+            relabel(
+                "source_labels=uid regex=1000 replacement= target_label=uid",
+                MessageKey::build(Some(1000), Some(1000), None, Priority::Error)
+            ),
+            Some(MessageKey::build(None, Some(1000), None, Priority::Error)),
+        );
+    }
+
+    #[test]
+    fn replace_ignores_unrepresentable_values() {
+        assert_eq!(
+            relabel(
+                "source_labels=service replacement=not/valid target_label=service\n\
+                 source_labels=service replacement=nope target_label=priority\n\
+                 source_labels=service replacement=nope target_label=gid",
+                MessageKey::build(None, Some(5), Some(b"foo"), Priority::Error)
+            ),
+            Some(MessageKey::build(
+                None,
+                Some(5),
+                Some(b"foo"),
+                Priority::Error
+            )),
+        );
+    }
+
+    #[test]
+    fn keep_drops_non_matching_messages() {
+        let rules = r"action=keep source_labels=service regex=.*\.service";
+
+        assert_eq!(
+            relabel(
+                rules,
+                MessageKey::build(None, None, Some(b"foo.service"), Priority::Error)
+            ),
+            Some(MessageKey::build(
+                None,
+                None,
+                Some(b"foo.service"),
+                Priority::Error
+            )),
+        );
+        assert_eq!(
+            relabel(
+                rules,
+                MessageKey::build(None, None, Some(b"foo.scope"), Priority::Error)
+            ),
+            None,
+        );
+        assert_eq!(
+            relabel(rules, MessageKey::build(None, None, None, Priority::Error)),
+            None,
+        );
+    }
+
+    #[test]
+    fn drop_drops_matching_messages() {
+        let rules = r"action=drop source_labels=service,priority regex=chatty\.service;DEBUG";
+
+        assert_eq!(
+            relabel(
+                rules,
+                MessageKey::build(None, None, Some(b"chatty.service"), Priority::Debug)
+            ),
+            None,
+        );
+        assert_eq!(
+            relabel(
+                rules,
+                MessageKey::build(None, None, Some(b"chatty.service"), Priority::Informational)
+            ),
+            Some(MessageKey::build(
+                None,
+                None,
+                Some(b"chatty.service"),
+                Priority::Informational
+            )),
+        );
+    }
+
+    #[test]
+    fn drop_uses_custom_separator() {
+        assert_eq!(
+            relabel(
+                "action=drop source_labels=uid,gid separator=: regex=0:0",
+                MessageKey::build(Some(0), Some(0), None, Priority::Debug)
+            ),
+            None,
+        );
+    }
+
+    #[test]
+    fn regexes_are_anchored() {
+        assert_eq!(
+            relabel(
+                "action=drop source_labels=service regex=foo",
+                MessageKey::build(None, None, Some(b"foobar"), Priority::Debug)
+            ),
+            Some(MessageKey::build(
+                None,
+                None,
+                Some(b"foobar"),
+                Priority::Debug
+            )),
+        );
+    }
+
+    #[test]
+    fn labelmap_copies_matching_labels() {
+        assert_eq!(
+            relabel(
+                "action=labelmap regex=u(id) replacement=g$1",
+                MessageKey::build(Some(12), Some(34), None, Priority::Debug)
+            ),
+            Some(MessageKey::build(Some(12), Some(12), None, Priority::Debug)),
+        );
+    }
+
+    #[test]
+    fn rules_apply_in_order() {
+        assert_eq!(
+            relabel(
+                r"source_labels=service regex=foo replacement=bar target_label=service
+                  action=drop source_labels=service regex=bar",
+                MessageKey::build(None, None, Some(b"foo"), Priority::Debug)
+            ),
+            None,
+        );
+    }
+
+    #[test]
+    fn caches_results_per_key() {
+        let rules = parse(concat!(
+            "action=replace source_labels=service regex=worker@.*\\.service replacement=worker.service target_label=service\n",
+            "action=drop source_labels=priority regex=DEBUG\n",
+        ));
+        let mut cache = RelabelCache::new();
+
+        for _ in 0..2 {
+            let mut key = MessageKey::build(
+                Some(123),
+                Some(456),
+                Some(b"worker@1.service"),
+                Priority::Informational,
+            );
+            assert!(cache.apply(&rules, &mut key));
+            assert_eq!(
+                key,
+                MessageKey::build(
+                    Some(123),
+                    Some(456),
+                    Some(b"worker.service"),
+                    Priority::Informational
+                )
+            );
+
+            let mut key = MessageKey::build(None, None, Some(b"worker@1.service"), Priority::Debug);
+            assert!(!cache.apply(&rules, &mut key));
+        }
+
+        assert_eq!(cache.results.len(), 2);
+    }
+
+    #[test]
+    fn starts_cache_over_once_full() {
+        let rules = parse("action=drop source_labels=priority regex=DEBUG");
+        let mut cache = RelabelCache::new();
+
+        for uid in 0..=u32::try_from(RELABEL_CACHE_CAPACITY).unwrap() {
+            let mut key = MessageKey::build(Some(uid), None, None, Priority::Informational);
+            assert!(cache.apply(&rules, &mut key));
+        }
+
+        assert_eq!(cache.results.len(), 1);
+    }
+}
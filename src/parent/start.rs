@@ -3,10 +3,33 @@ use crate::prelude::*;
 use super::ipc::*;
 use super::journal::run_journal_loop;
 use super::key_watcher::run_watcher;
+use super::push::close_pusher;
+use super::push::pushgateway_url;
+use super::push::run_otlp_loop;
+use super::push::run_pushgateway_loop;
+use super::push::run_remote_write_loop;
+use super::push::run_statsd_loop;
+use super::push::run_textfile_loop;
+use super::push::PushConfig;
+use super::push::StatsdConfig;
+use super::push::StatsdTarget;
+use super::push::TextfileConfig;
 use crate::cli::args::ParentArgs;
+use crate::cli::args::PushAuthOptions;
+use crate::cli::args::PushOptions;
+use crate::cli::args::PushgatewayOptions;
+use crate::cli::args::ServerOptions;
+use crate::cli::args::StatsdAddress;
+use crate::cli::args::StatsdOptions;
 use crate::cli::args::TLSOptions;
+use crate::cli::args::TextfileOptions;
 use crate::ffi::*;
 use crate::parent::key_watcher::KeyWatcherTarget;
+use crate::parent::relabel::parse_relabel_config;
+use crate::parent::relabel::RelabelRule;
+use crate::parent::tls_watcher::run_tls_watcher;
+use crate::parent::tls_watcher::TlsWatcherTarget;
+use crate::state::ipc::parent::TlsCertificatePair;
 use const_str::cstr;
 use std::time::SystemTime;
 
@@ -20,30 +43,82 @@ struct WaitState {
 
 static NATIVE_JOURNALD_PROVIDER: OnceCell<NativeSystemdProvider> = OnceCell::new();
 
+static RELABEL_RULES: OnceCell<Box<[RelabelRule]>> = OnceCell::new();
+
 static IPC_STATE: ParentIpcState<NativeIpcMethods> = ParentIpcState::new(NativeIpcMethods::new());
 
+// Set when shutdown was requested via `SIGTERM`, as opposed to a task ending on its own.
+static TERMINATE_SIGNALED: Notify = Notify::new();
+
+extern "C" fn handle_terminate_signal(_: Signal) {
+    // Both of these are just atomic stores, so they're safe to do here.
+    TERMINATE_SIGNALED.notify();
+    IPC_STATE.terminate_notify().notify();
+}
+
 pub fn start_parent(args: ParentArgs) -> io::Result<ExitResult> {
     check_parent_uid_gid()?;
-    let child_user_group = get_child_uid_gid()?;
     let provider = NativeSystemdProvider::open_provider()?;
 
     NATIVE_JOURNALD_PROVIDER.get_or_init(|| provider);
 
+    let relabel_rules = load_relabel_rules(args.relabel_config)?;
+    RELABEL_RULES.get_or_init(|| relabel_rules);
+
     let _notify_guard = IPC_STATE.terminate_notify().create_guard();
     let _notify_guard = IPC_STATE.done_notify().create_guard();
 
+    let mut prom_environment = PromEnvironment::new(SystemTime::now());
+    prom_environment.top_series = args.top_series;
+    prom_environment.field_stats = args.field_stats;
+    for label in &args.labels {
+        prom_environment.add_static_label(&label.name, &label.value);
+    }
+
+    // The pusher drops to the same user as the child.
+    let pusher_user_group =
+        if args.remote_write.is_some() || args.otlp.is_some() || args.pushgateway.is_some() {
+            Some(get_child_uid_gid()?)
+        } else {
+            None
+        };
+
     IPC_STATE.init_dynamic(ParentIpcDynamic {
-        port: args.port,
-        child_user_group,
-        prom_environment: PromEnvironment::new(SystemTime::now()),
-        key_target: KeyWatcherTarget::new(args.key_dir),
-        tls_config: load_tls_config(args.tls)?,
+        prom_environment,
+        server: match args.server {
+            None => None,
+            Some(server) => Some(load_server_dynamic(server)?),
+        },
+        remote_write: match args.remote_write {
+            None => None,
+            Some(remote_write) => Some(load_push_config(remote_write)?),
+        },
+        otlp: match args.otlp {
+            None => None,
+            Some(otlp) => Some(load_push_config(otlp)?),
+        },
+        statsd: args.statsd.map(load_statsd_config),
+        textfile: match args.textfile {
+            None => None,
+            Some(textfile) => Some(load_textfile_config(textfile)?),
+        },
+        pushgateway: match args.pushgateway {
+            None => None,
+            Some(pushgateway) => Some(load_pushgateway_config(pushgateway)?),
+        },
+        pusher_user_group,
     });
 
+    // Only the Pushgateway needs a chance to push before exiting. Everything else is fine with the
+    // default action, and a second `SIGTERM` still gets that.
+    if IPC_STATE.dynamic().pushgateway.is_some() {
+        install_oneshot_handler(&[Signal::SIGTERM], handle_terminate_signal);
+    }
+
     resolve_parent_return()
 }
 
-fn load_tls_file(path: &std::path::Path) -> io::Result<Box<std::ffi::OsStr>> {
+fn load_config_file(path: &std::path::Path) -> io::Result<Box<std::ffi::OsStr>> {
     use std::os::unix::prelude::OsStringExt;
 
     match std::fs::read(path) {
@@ -60,12 +135,34 @@ fn load_tls_file(path: &std::path::Path) -> io::Result<Box<std::ffi::OsStr>> {
 }
 
 fn load_tls_config(tls: Option<TLSOptions>) -> io::Result<Option<TLSConfig>> {
+    use std::os::unix::prelude::OsStrExt;
+
     match tls {
         None => Ok(None),
         Some(tls) => {
+            let initial = TlsCertificatePair {
+                certificate: load_config_file(&tls.certificate)?.as_bytes().into(),
+                private_key: load_config_file(&tls.private_key)?.as_bytes().into(),
+                ocsp_response: match &tls.ocsp_response {
+                    None => None,
+                    Some(path) => Some(load_config_file(path)?.as_bytes().into()),
+                },
+            };
             let config = TLSConfig {
-                certificate: load_tls_file(&tls.certificate)?,
-                private_key: load_tls_file(&tls.private_key)?,
+                target: TlsWatcherTarget::new(
+                    tls.certificate,
+                    tls.private_key,
+                    tls.ocsp_response,
+                    initial,
+                )?,
+                policy: tls.policy,
+                client_auth: match tls.client_auth {
+                    None => None,
+                    Some(client_auth) => Some(TLSClientAuthConfig {
+                        ca: load_config_file(&client_auth.ca)?,
+                        required: client_auth.required,
+                    }),
+                },
             };
             log::info!("TLS config loaded.");
             Ok(Some(config))
@@ -73,6 +170,117 @@ fn load_tls_config(tls: Option<TLSOptions>) -> io::Result<Option<TLSConfig>> {
     }
 }
 
+fn load_server_dynamic(server: ServerOptions) -> io::Result<ParentServerDynamic> {
+    Ok(ParentServerDynamic {
+        port: server.port,
+        child_user_group: get_child_uid_gid()?,
+        key_target: KeyWatcherTarget::new(server.key_dir),
+        tls_config: load_tls_config(server.tls)?,
+        rate_limit: server.rate_limit,
+    })
+}
+
+fn load_secret_file(path: &std::path::Path) -> io::Result<Box<str>> {
+    let data = load_config_file(path)?;
+
+    // Allow the usual trailing newline, but nothing that could break out of the header.
+    match data.to_str().map(str::trim) {
+        Some(secret)
+            if !secret.is_empty() && secret.bytes().all(|b| b == b' ' || b.is_ascii_graphic()) =>
+        {
+            Ok(secret.into())
+        }
+        _ => Err(error!(
+            ErrorKind::InvalidData,
+            "{} must contain a non-empty printable ASCII secret.",
+            path.display()
+        )),
+    }
+}
+
+fn load_push_authorization(auth: Option<PushAuthOptions>) -> io::Result<Option<Box<str>>> {
+    use base64::engine::general_purpose::STANDARD as ENGINE;
+    use base64::engine::Engine as _;
+
+    match auth {
+        None => Ok(None),
+        Some(PushAuthOptions::Basic {
+            username,
+            password_file,
+        }) => {
+            let password = load_secret_file(&password_file)?;
+            let credentials = ENGINE.encode(format!("{username}:{password}"));
+            Ok(Some(format!("Basic {credentials}").into()))
+        }
+        Some(PushAuthOptions::Bearer { token_file }) => {
+            let token = load_secret_file(&token_file)?;
+            Ok(Some(format!("Bearer {token}").into()))
+        }
+    }
+}
+
+fn load_push_config(options: PushOptions) -> io::Result<PushConfig> {
+    Ok(PushConfig {
+        url: options.url,
+        interval: options.interval,
+        authorization: load_push_authorization(options.auth)?,
+    })
+}
+
+fn load_statsd_config(options: StatsdOptions) -> StatsdConfig {
+    StatsdConfig {
+        target: match options.address {
+            StatsdAddress::Udp(address) => StatsdTarget::Udp(address.into()),
+            StatsdAddress::Unix(path) => StatsdTarget::Unix(path),
+        },
+        interval: options.interval,
+    }
+}
+
+fn load_textfile_config(options: TextfileOptions) -> io::Result<TextfileConfig> {
+    // Catch typos up front, rather than logging an error every interval.
+    match std::fs::metadata(&options.dir) {
+        Ok(metadata) if metadata.is_dir() => Ok(TextfileConfig {
+            dir: options.dir,
+            interval: options.interval,
+        }),
+        Ok(_) => Err(error!(
+            ErrorKind::InvalidInput,
+            "{} is not a directory.",
+            options.dir.display()
+        )),
+        Err(e) => Err(error!(
+            "An error occurred while checking {}: {}.",
+            options.dir.display(),
+            normalize_errno(e, Some("stat"))
+        )),
+    }
+}
+
+fn load_pushgateway_config(options: PushgatewayOptions) -> io::Result<PushConfig> {
+    let instance = match options.instance {
+        Some(instance) => instance,
+        None => hostname()?,
+    };
+
+    let mut config = load_push_config(options.push)?;
+    config.url = pushgateway_url(&config.url, &options.job, &instance);
+    Ok(config)
+}
+
+fn load_relabel_rules(path: Option<std::path::PathBuf>) -> io::Result<Box<[RelabelRule]>> {
+    use std::os::unix::prelude::OsStrExt;
+
+    match path {
+        None => Ok(Box::new([])),
+        Some(path) => {
+            let rules = parse_relabel_config(load_config_file(&path)?.as_bytes())?;
+            log::info!("Relabel config loaded.");
+            Ok(rules)
+        }
+    }
+}
+
 fn check_parent_uid_gid() -> io::Result<()> {
     // Verify it's running as root and then get the UID and GID of the child.
 
@@ -140,7 +348,12 @@ fn resolve_parent_return() -> io::Result<ExitResult> {
     fn journal_task() -> io::Result<()> {
         let _task_guard = BackgroundTaskGuard;
         log::info!("Journal iteration started.");
-        run_journal_loop::<NativeJournalRef>(&IPC_STATE, NATIVE_JOURNALD_PROVIDER.get().unwrap())
+        run_journal_loop::<NativeJournalRef>(
+            &IPC_STATE,
+            NATIVE_JOURNALD_PROVIDER.get().unwrap(),
+            RELABEL_RULES.get().unwrap(),
+            IPC_STATE.dynamic().prom_environment.field_stats,
+        )
     }
 
     fn key_updater_task() -> io::Result<()> {
@@ -149,9 +362,85 @@ fn resolve_parent_return() -> io::Result<ExitResult> {
         run_watcher(&IPC_STATE)
     }
 
-    let parent_ipc_handle = ThreadHandle::spawn(parent_ipc_task);
+    fn tls_watcher_task() -> io::Result<()> {
+        let _task_guard = BackgroundTaskGuard;
+        log::info!("TLS watcher started.");
+        run_tls_watcher(&IPC_STATE)
+    }
+
+    fn remote_write_task() -> io::Result<()> {
+        let _task_guard = BackgroundTaskGuard;
+        log::info!("Remote write started.");
+        run_remote_write_loop(&IPC_STATE)
+    }
+
+    fn otlp_task() -> io::Result<()> {
+        let _task_guard = BackgroundTaskGuard;
+        log::info!("OTLP export started.");
+        run_otlp_loop(&IPC_STATE)
+    }
+
+    fn statsd_task() -> io::Result<()> {
+        let _task_guard = BackgroundTaskGuard;
+        log::info!("StatsD export started.");
+        run_statsd_loop(&IPC_STATE)
+    }
+
+    fn textfile_task() -> io::Result<()> {
+        let _task_guard = BackgroundTaskGuard;
+        log::info!("Textfile export started.");
+        run_textfile_loop(&IPC_STATE)
+    }
+
+    fn pushgateway_task() -> io::Result<()> {
+        let _task_guard = BackgroundTaskGuard;
+        log::info!("Pushgateway export started.");
+        run_pushgateway_loop(&IPC_STATE)
+    }
+
+    let dynamic = IPC_STATE.dynamic();
+
+    // The child and its keys are only needed when serving metrics.
+    let (parent_ipc_handle, key_updater_handle) = match dynamic.server {
+        None => (None, None),
+        Some(_) => (
+            Some(ThreadHandle::spawn(parent_ipc_task)),
+            Some(ThreadHandle::spawn(key_updater_task)),
+        ),
+    };
+
+    let tls_watcher_handle = dynamic
+        .server
+        .as_ref()
+        .and_then(|server| server.tls_config.as_ref())
+        .map(|_| ThreadHandle::spawn(tls_watcher_task));
+
     let journal_handle = ThreadHandle::spawn(journal_task);
-    let key_updater_handle = ThreadHandle::spawn(key_updater_task);
+
+    let remote_write_handle = dynamic
+        .remote_write
+        .as_ref()
+        .map(|_| ThreadHandle::spawn(remote_write_task));
+
+    let otlp_handle = dynamic
+        .otlp
+        .as_ref()
+        .map(|_| ThreadHandle::spawn(otlp_task));
+
+    let statsd_handle = dynamic
+        .statsd
+        .as_ref()
+        .map(|_| ThreadHandle::spawn(statsd_task));
+
+    let textfile_handle = dynamic
+        .textfile
+        .as_ref()
+        .map(|_| ThreadHandle::spawn(textfile_task));
+
+    let pushgateway_handle = dynamic
+        .pushgateway
+        .as_ref()
+        .map(|_| ThreadHandle::spawn(pushgateway_task));
 
     static READY_MSG: &std::ffi::CStr = cstr!("READY=1");
 
@@ -162,7 +451,14 @@ fn resolve_parent_return() -> io::Result<ExitResult> {
 
     let mut result = Ok(WAIT_CHECKPOINT.wait().exit_result);
 
-    if let Err(e) = parent_ipc_handle.join() {
+    // The other tasks already see the termination request, but the child has to be told.
+    if TERMINATE_SIGNALED.has_notified() && dynamic.server.is_some() {
+        if let Err(e) = IPC_STATE.methods().child_terminate() {
+            result = Err(e);
+        }
+    }
+
+    if let Some(Err(e)) = parent_ipc_handle.map(ThreadHandle::join) {
         result = Err(e);
     }
 
@@ -170,10 +466,44 @@ fn resolve_parent_return() -> io::Result<ExitResult> {
         result = Err(e);
     }
 
-    if let Err(e) = key_updater_handle.join() {
+    if let Some(Err(e)) = key_updater_handle.map(ThreadHandle::join) {
+        result = Err(e);
+    }
+
+    if let Some(Err(e)) = tls_watcher_handle.map(ThreadHandle::join) {
+        result = Err(e);
+    }
+
+    if let Some(Err(e)) = remote_write_handle.map(ThreadHandle::join) {
+        result = Err(e);
+    }
+
+    if let Some(Err(e)) = otlp_handle.map(ThreadHandle::join) {
+        result = Err(e);
+    }
+
+    if let Some(Err(e)) = statsd_handle.map(ThreadHandle::join) {
+        result = Err(e);
+    }
+
+    if let Some(Err(e)) = textfile_handle.map(ThreadHandle::join) {
+        result = Err(e);
+    }
+
+    if let Some(Err(e)) = pushgateway_handle.map(ThreadHandle::join) {
+        result = Err(e);
+    }
+
+    // Only now is everything that'll be pushed handed off, so the pusher can finish up.
+    if let Err(e) = close_pusher(&IPC_STATE) {
         result = Err(e);
     }
 
+    // A requested shutdown is a clean exit, even though the child was killed along the way.
+    if TERMINATE_SIGNALED.has_notified() && result.is_ok() {
+        result = Ok(ExitResult::Code(ExitCode(0)));
+    }
+
     result
 }
 
@@ -0,0 +1,286 @@
+use crate::prelude::*;
+
+use super::ipc::write_to_child_input;
+use super::ipc::ParentIpcMethods;
+use super::ipc::ParentIpcState;
+use super::key_watcher::watch_dirs;
+use crate::state::ipc::parent::TlsCertificatePair;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub struct TlsWatcherTarget {
+    certificate_path: PathBuf,
+    private_key_path: PathBuf,
+    ocsp_response_path: Option<PathBuf>,
+    // The last valid pair read. New children are spawned with this.
+    current: Mutex<TlsCertificatePair>,
+}
+
+fn read_file(path: &Path) -> io::Result<Box<[u8]>> {
+    match std::fs::read(path) {
+        Ok(data) => Ok(data.into()),
+        Err(e) => Err(error!(
+            "An error occurred while loading {}: {}.",
+            path.display(),
+            normalize_errno(e, Some("open"))
+        )),
+    }
+}
+
+// Files are watched through their directories, so they're still seen when replaced by a rename or
+// through a symlink swap, like ACME clients and Kubernetes secret mounts do.
+fn watched_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+impl TlsWatcherTarget {
+    /// Fails if the initial pair isn't valid, so it's caught before the child ever starts.
+    pub fn new(
+        certificate_path: PathBuf,
+        private_key_path: PathBuf,
+        ocsp_response_path: Option<PathBuf>,
+        initial: TlsCertificatePair,
+    ) -> io::Result<Self> {
+        check_pair(&initial)?;
+
+        Ok(Self {
+            certificate_path,
+            private_key_path,
+            ocsp_response_path,
+            current: Mutex::new(initial),
+        })
+    }
+
+    pub fn current(&self) -> MutexGuard<'_, TlsCertificatePair> {
+        self.current.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Returns `None` if the pair on disk is what's already being served.
+    fn read_changed(&self, current: &TlsCertificatePair) -> io::Result<Option<TlsCertificatePair>> {
+        let pair = read_pair(
+            &self.certificate_path,
+            &self.private_key_path,
+            self.ocsp_response_path.as_deref(),
+        )?;
+
+        if pair.certificate == current.certificate
+            && pair.private_key == current.private_key
+            && pair.ocsp_response == current.ocsp_response
+        {
+            return Ok(None);
+        }
+
+        check_pair(&pair)?;
+        Ok(Some(pair))
+    }
+}
+
+fn read_pair(
+    certificate_path: &Path,
+    private_key_path: &Path,
+    ocsp_response_path: Option<&Path>,
+) -> io::Result<TlsCertificatePair> {
+    Ok(TlsCertificatePair {
+        certificate: read_file(certificate_path)?,
+        private_key: read_file(private_key_path)?,
+        ocsp_response: match ocsp_response_path {
+            None => None,
+            Some(path) => Some(read_file(path)?),
+        },
+    })
+}
+
+fn check_pair(pair: &TlsCertificatePair) -> io::Result<()> {
+    parse_certified_key(
+        &pair.certificate,
+        &pair.private_key,
+        pair.ocsp_response.as_deref(),
+    )?;
+    Ok(())
+}
+
+fn tls_target(
+    s: &'static ParentIpcState<impl ParentIpcMethods>,
+) -> Option<&'static TlsWatcherTarget> {
+    s.dynamic()
+        .server()
+        .tls_config
+        .as_ref()
+        .map(|tls| &tls.target)
+}
+
+// The pair is kept locked until it's sent, so a reload can't race a send of the pair it replaced.
+fn write_tls_certificate(
+    s: &'static ParentIpcState<impl ParentIpcMethods>,
+    pair: &TlsCertificatePair,
+) -> bool {
+    let msg = ipc::parent::receive_tls_certificate_bytes(pair);
+    write_to_child_input(s, &msg)
+}
+
+/// The child is already spawned with the current pair, but it could've been reloaded in the
+/// meantime, so it's sent again once the child's ready for it.
+#[must_use]
+pub fn write_current_tls_certificate(s: &'static ParentIpcState<impl ParentIpcMethods>) -> bool {
+    match tls_target(s) {
+        None => true,
+        Some(target) => write_tls_certificate(s, &target.current()),
+    }
+}
+
+pub fn run_tls_watcher(s: &'static ParentIpcState<impl ParentIpcMethods>) -> io::Result<()> {
+    let Some(target) = tls_target(s) else {
+        return Ok(());
+    };
+
+    let mut dirs = vec![watched_dir(&target.certificate_path)];
+    let others = [
+        Some(&*target.private_key_path),
+        target.ocsp_response_path.as_deref(),
+    ];
+    for dir in others.into_iter().flatten().map(watched_dir) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+
+    watch_dirs(s, &dirs, "TLS watcher", || {
+        let mut current = target.current();
+        match target.read_changed(&current) {
+            Ok(None) => {}
+            Ok(Some(pair)) => {
+                *current = pair;
+                log::info!("TLS certificate reloaded.");
+                // If there's no child to send it to, the next one's just spawned with it.
+                write_tls_certificate(s, &current);
+            }
+            // Keep serving the current pair, as the files could just be mid-update.
+            Err(e) => log::error!("TLS watcher error: {}", normalize_errno(e, None)),
+        }
+        true
+    })
+}
+
+// Skip in Miri, as it can't touch the filesystem.
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::*;
+
+    fn write_pair(dir: &Path, certificate: &[u8], private_key: &[u8]) {
+        std::fs::write(dir.join("cert.pem"), certificate).unwrap();
+        std::fs::write(dir.join("key.pem"), private_key).unwrap();
+    }
+
+    fn load_target(dir: &Path) -> io::Result<TlsWatcherTarget> {
+        let certificate_path = dir.join("cert.pem");
+        let private_key_path = dir.join("key.pem");
+        let initial = read_pair(&certificate_path, &private_key_path, None)?;
+        TlsWatcherTarget::new(certificate_path, private_key_path, None, initial)
+    }
+
+    // A successful response, with its response bytes cut down to an empty sequence.
+    const OCSP_RESPONSE: &[u8] = b"\x30\x07\x0A\x01\x00\xA0\x02\x30\x00";
+
+    #[test]
+    fn rejects_mismatched_initial_pair() {
+        let dir = tempfile::tempdir().unwrap();
+        write_pair(dir.path(), TEST_CERTIFICATE, OTHER_PRIVATE_KEY);
+        let error = load_target(dir.path()).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Private key does not match the certificate."
+        );
+    }
+
+    #[test]
+    fn ignores_unchanged_pair() {
+        let dir = tempfile::tempdir().unwrap();
+        write_pair(dir.path(), TEST_CERTIFICATE, TEST_PRIVATE_KEY);
+        let target = load_target(dir.path()).unwrap();
+        assert!(target.read_changed(&target.current()).unwrap().is_none());
+    }
+
+    #[test]
+    fn reads_changed_pair() {
+        let dir = tempfile::tempdir().unwrap();
+        write_pair(dir.path(), TEST_CERTIFICATE, TEST_PRIVATE_KEY);
+        let target = load_target(dir.path()).unwrap();
+
+        // Same pair, but still different bytes.
+        let mut certificate = TEST_CERTIFICATE.to_vec();
+        certificate.push(b'\n');
+        write_pair(dir.path(), &certificate, TEST_PRIVATE_KEY);
+
+        let pair = target.read_changed(&target.current()).unwrap().unwrap();
+        assert_eq!(&*pair.certificate, &*certificate);
+        assert_eq!(&*pair.private_key, TEST_PRIVATE_KEY);
+    }
+
+    #[test]
+    fn rejects_changed_pair_that_does_not_match() {
+        let dir = tempfile::tempdir().unwrap();
+        write_pair(dir.path(), TEST_CERTIFICATE, TEST_PRIVATE_KEY);
+        let target = load_target(dir.path()).unwrap();
+
+        // Like if the certificate was replaced, but the private key hasn't been yet.
+        write_pair(dir.path(), TEST_CERTIFICATE, OTHER_PRIVATE_KEY);
+
+        let error = target.read_changed(&target.current()).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Private key does not match the certificate."
+        );
+    }
+
+    #[test]
+    fn reads_changed_ocsp_response() {
+        let dir = tempfile::tempdir().unwrap();
+        write_pair(dir.path(), TEST_CERTIFICATE, TEST_PRIVATE_KEY);
+        std::fs::write(dir.path().join("ocsp.der"), OCSP_RESPONSE).unwrap();
+        let certificate_path = dir.path().join("cert.pem");
+        let private_key_path = dir.path().join("key.pem");
+        let ocsp_response_path = dir.path().join("ocsp.der");
+        let initial = read_pair(
+            &certificate_path,
+            &private_key_path,
+            Some(&ocsp_response_path),
+        )
+        .unwrap();
+        let target = TlsWatcherTarget::new(
+            certificate_path,
+            private_key_path,
+            Some(ocsp_response_path),
+            initial,
+        )
+        .unwrap();
+        assert_eq!(
+            target.current().ocsp_response.as_deref(),
+            Some(OCSP_RESPONSE)
+        );
+
+        // `tryLater`
+        std::fs::write(dir.path().join("ocsp.der"), b"\x30\x03\x0A\x01\x03").unwrap();
+        let error = target.read_changed(&target.current()).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "OCSP response is not a successful response."
+        );
+
+        let ocsp_response = b"\x30\x08\x0A\x01\x00\xA0\x03\x30\x01\x00";
+        std::fs::write(dir.path().join("ocsp.der"), ocsp_response).unwrap();
+        let pair = target.read_changed(&target.current()).unwrap().unwrap();
+        assert_eq!(pair.ocsp_response.as_deref(), Some(&ocsp_response[..]));
+    }
+
+    #[test]
+    fn watches_parent_dir() {
+        assert_eq!(
+            watched_dir(Path::new("/etc/tls/cert.pem")),
+            Path::new("/etc/tls")
+        );
+        assert_eq!(watched_dir(Path::new("cert.pem")), Path::new("."));
+    }
+}
@@ -0,0 +1,7 @@
+mod sender;
+mod start;
+
+// Only the parent's fake IPC needs these, to run the pusher on a thread instead.
+#[cfg(test)]
+pub use sender::*;
+pub use start::start_pusher;
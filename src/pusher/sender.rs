@@ -0,0 +1,368 @@
+use crate::prelude::*;
+
+use crate::state::ipc::push::*;
+
+// How long a single push request may take, including connecting.
+pub const PUSH_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub const DEFAULT: RetryPolicy = RetryPolicy {
+        max_attempts: 5,
+        initial_backoff: Duration::from_secs(1),
+        max_backoff: Duration::from_secs(30),
+    };
+}
+
+#[cfg(test)]
+pub const TEST_RETRY_POLICY: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    initial_backoff: Duration::from_millis(1),
+    max_backoff: Duration::from_millis(1),
+};
+
+#[derive(Debug)]
+pub struct PushTargetConfig {
+    pub url: HttpUrl,
+    // The full `Authorization` header value, if any.
+    pub authorization: Option<Box<str>>,
+    pub retry_policy: RetryPolicy,
+}
+
+#[derive(Debug)]
+pub struct PusherConfig {
+    pub remote_write: Option<PushTargetConfig>,
+    pub otlp: Option<PushTargetConfig>,
+    // The URL already includes the grouping key.
+    pub pushgateway: Option<PushTargetConfig>,
+}
+
+impl PusherConfig {
+    fn target(&self, target: PushTarget) -> Option<&PushTargetConfig> {
+        match target {
+            PushTarget::RemoteWrite => self.remote_write.as_ref(),
+            PushTarget::Otlp => self.otlp.as_ref(),
+            PushTarget::Pushgateway => self.pushgateway.as_ref(),
+        }
+    }
+}
+
+fn is_retryable_status(status: u16) -> bool {
+    // Per the remote write spec, 5xx and 429 are retryable and all other errors are not. This
+    // applies just as well to other push targets.
+    status == 429 || (500..600).contains(&status)
+}
+
+/// Send a push request, retrying with exponential backoff on connection errors and retryable
+/// statuses. Returns `false` if termination was requested while backing off. Pushes that can't be
+/// delivered are logged and dropped, as the next push will include their data anyways.
+pub fn send_with_retries(
+    terminate_notify: &Notify,
+    policy: &RetryPolicy,
+    target: &str,
+    mut send: impl FnMut() -> io::Result<HttpResponse>,
+) -> bool {
+    let mut backoff = policy.initial_backoff;
+    let mut attempt = 1;
+
+    loop {
+        match send() {
+            Ok(response) if response.is_success() => return true,
+            Ok(response) => {
+                let mut message = String::new();
+                write!(
+                    message,
+                    "{} push failed with status {}: ",
+                    target, response.status
+                )
+                .unwrap();
+                binary_to_display(&mut message, &response.body);
+
+                if !is_retryable_status(response.status) {
+                    log::error!("{}", message);
+                    return true;
+                }
+
+                log::warn!("{}", message);
+            }
+            Err(e) => {
+                log::warn!("{} push failed: {}", target, normalize_errno(e, None));
+            }
+        }
+
+        if attempt >= policy.max_attempts {
+            log::error!("{} push dropped after {} attempts.", target, attempt);
+            return true;
+        }
+
+        if !sleep_unless_terminated(terminate_notify, backoff) {
+            return false;
+        }
+
+        attempt = attempt.wrapping_add(1);
+        backoff = backoff.saturating_mul(2).min(policy.max_backoff);
+    }
+}
+
+fn send_push(
+    target: PushTarget,
+    config: &PushTargetConfig,
+    body: &[u8],
+) -> io::Result<HttpResponse> {
+    let mut headers = match target {
+        PushTarget::RemoteWrite => vec![
+            ("Content-Encoding", "snappy"),
+            ("Content-Type", "application/x-protobuf"),
+            ("X-Prometheus-Remote-Write-Version", "0.1.0"),
+        ],
+        PushTarget::Otlp => vec![("Content-Type", "application/x-protobuf")],
+        PushTarget::Pushgateway => vec![("Content-Type", "text/plain; version=0.0.4")],
+    };
+
+    if let Some(authorization) = &config.authorization {
+        headers.push(("Authorization", authorization));
+    }
+
+    match target {
+        // The Pushgateway replaces the whole group on `PUT`, rather than merging into it.
+        PushTarget::Pushgateway => http_put(&config.url, &headers, body, PUSH_REQUEST_TIMEOUT),
+        PushTarget::RemoteWrite | PushTarget::Otlp => {
+            http_post(&config.url, &headers, body, PUSH_REQUEST_TIMEOUT)
+        }
+    }
+}
+
+struct PushSlot {
+    // Only the latest body matters, as each one includes everything before it.
+    body: Option<Vec<u8>>,
+    closed: bool,
+}
+
+fn run_sender(
+    slot: &Checkpoint<PushSlot>,
+    terminate_notify: &Notify,
+    target: PushTarget,
+    config: &PushTargetConfig,
+) {
+    loop {
+        let body = {
+            let mut guard = slot.lock();
+            loop {
+                if let Some(body) = guard.body.take() {
+                    break body;
+                }
+                if guard.closed {
+                    return;
+                }
+                guard = slot.resume_wait(guard);
+            }
+        };
+
+        // Once the parent's done, there's no next push to fall back on, so a body still pending
+        // gets exactly one attempt.
+        send_with_retries(
+            terminate_notify,
+            &config.retry_policy,
+            target.name(),
+            || send_push(target, config, &body),
+        );
+    }
+}
+
+/// Push every body the parent sends over `input` until it closes its end. Each target gets its own
+/// sender, so a slow or failing target doesn't hold up the others.
+pub fn run_pusher(mut input: impl Read, config: &PusherConfig) -> io::Result<()> {
+    let slots: [Checkpoint<PushSlot>; 3] = std::array::from_fn(|_| {
+        Checkpoint::new(PushSlot {
+            body: None,
+            closed: false,
+        })
+    });
+    let terminate_notify = Notify::new();
+
+    let slot_for = |target: PushTarget| &slots[usize::from(target.to_byte())];
+
+    std::thread::scope(|scope| {
+        for target in [
+            PushTarget::RemoteWrite,
+            PushTarget::Otlp,
+            PushTarget::Pushgateway,
+        ] {
+            if let Some(target_config) = config.target(target) {
+                let slot = slot_for(target);
+                let terminate_notify = &terminate_notify;
+                scope.spawn(move || run_sender(slot, terminate_notify, target, target_config));
+            }
+        }
+
+        let result = loop {
+            match read_push_request(&mut input) {
+                Ok(Some((target, body))) => {
+                    if config.target(target).is_none() {
+                        break Err(error!(
+                            "{} push received but not configured.",
+                            target.name()
+                        ));
+                    }
+
+                    slot_for(target).notify(|slot| slot.body = Some(body));
+                }
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+
+        // Cut any backoff short, but still let the senders drain what's pending.
+        terminate_notify.notify();
+        for slot in &slots {
+            slot.notify(|slot| slot.closed = true);
+        }
+
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16) -> io::Result<HttpResponse> {
+        Ok(HttpResponse {
+            status,
+            body: Box::new([]),
+        })
+    }
+
+    #[test]
+    fn sends_once_on_success() {
+        let notify = Notify::new();
+        let mut calls = 0;
+        assert!(send_with_retries(
+            &notify,
+            &TEST_RETRY_POLICY,
+            "Test",
+            || {
+                calls += 1;
+                response(204)
+            }
+        ));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn retries_retryable_statuses_and_errors() {
+        let notify = Notify::new();
+        let mut calls = 0;
+        assert!(send_with_retries(
+            &notify,
+            &TEST_RETRY_POLICY,
+            "Test",
+            || {
+                calls += 1;
+                match calls {
+                    1 => response(503),
+                    2 => Err(Error::from_raw_os_error(libc::ECONNREFUSED)),
+                    _ => response(200),
+                }
+            }
+        ));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn retries_too_many_requests() {
+        let notify = Notify::new();
+        let mut calls = 0;
+        assert!(send_with_retries(
+            &notify,
+            &TEST_RETRY_POLICY,
+            "Test",
+            || {
+                calls += 1;
+                response(if calls == 1 { 429 } else { 200 })
+            }
+        ));
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn does_not_retry_client_errors() {
+        let notify = Notify::new();
+        let mut calls = 0;
+        assert!(send_with_retries(
+            &notify,
+            &TEST_RETRY_POLICY,
+            "Test",
+            || {
+                calls += 1;
+                response(400)
+            }
+        ));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let notify = Notify::new();
+        let mut calls = 0;
+        assert!(send_with_retries(
+            &notify,
+            &TEST_RETRY_POLICY,
+            "Test",
+            || {
+                calls += 1;
+                response(500)
+            }
+        ));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn stops_retrying_on_terminate() {
+        let notify = Notify::new();
+        let mut calls = 0;
+        assert!(!send_with_retries(
+            &notify,
+            &TEST_RETRY_POLICY,
+            "Test",
+            || {
+                calls += 1;
+                notify.notify();
+                response(500)
+            }
+        ));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn rejects_requests_for_unconfigured_targets() {
+        let config = PusherConfig {
+            remote_write: None,
+            otlp: None,
+            pushgateway: None,
+        };
+
+        let mut input = Vec::new();
+        write_push_request(&mut input, PushTarget::Otlp, b"body").unwrap();
+
+        let e = run_pusher(&*input, &config).unwrap_err();
+        assert_eq!(e.to_string(), "OTLP push received but not configured.");
+    }
+
+    #[test]
+    fn returns_once_input_closes() {
+        let config = PusherConfig {
+            remote_write: None,
+            otlp: None,
+            pushgateway: None,
+        };
+
+        assert_result_eq(run_pusher(&[][..], &config), Ok(()));
+    }
+}
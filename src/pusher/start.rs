@@ -0,0 +1,52 @@
+use crate::prelude::*;
+
+use super::sender::*;
+use crate::ffi::install_oneshot_handler;
+use crate::ffi::ExitCode;
+use crate::ffi::ExitResult;
+use crate::ffi::Signal;
+
+fn get_target_config(
+    url_var: &str,
+    authorization_var: &str,
+) -> io::Result<Option<PushTargetConfig>> {
+    let Some(url) = std::env::var_os(url_var) else {
+        return Ok(None);
+    };
+
+    let Some(url) = url.to_str().and_then(HttpUrl::parse) else {
+        return Err(error!("Push URL is invalid."));
+    };
+
+    let authorization = match std::env::var_os(authorization_var) {
+        None => None,
+        Some(authorization) => match authorization.to_str() {
+            Some(authorization) => Some(authorization.into()),
+            None => return Err(error!("Push authorization is invalid.")),
+        },
+    };
+
+    Ok(Some(PushTargetConfig {
+        url,
+        authorization,
+        retry_policy: RetryPolicy::DEFAULT,
+    }))
+}
+
+extern "C" fn ignore_terminate_signal(_: Signal) {}
+
+pub fn start_pusher() -> io::Result<ExitResult> {
+    let config = PusherConfig {
+        remote_write: get_target_config("REMOTE_WRITE_URL", "REMOTE_WRITE_AUTHORIZATION")?,
+        otlp: get_target_config("OTLP_URL", "OTLP_AUTHORIZATION")?,
+        pushgateway: get_target_config("PUSHGATEWAY_URL", "PUSHGATEWAY_AUTHORIZATION")?,
+    };
+
+    // Shutdown is driven by the parent closing its end, so the last push it hands over still goes
+    // out when the whole service gets terminated at once. A second `SIGTERM` still kills it.
+    install_oneshot_handler(&[Signal::SIGTERM], ignore_terminate_signal);
+
+    run_pusher(io::stdin().lock(), &config)?;
+
+    Ok(ExitResult::Code(ExitCode(0)))
+}
@@ -9,6 +9,7 @@ pub struct ByteCountSnapshotEntry {
     pub key: MessageKey,
     pub lines: u64,
     pub bytes: u64,
+    pub last_seen: u64,
 }
 
 // `repr(C)` to ensure the order's well-defined.
@@ -17,9 +18,38 @@ pub struct ByteCountSnapshotEntry {
 pub struct ByteCountTableEntrySnapshot {
     pub lines: u64,
     pub bytes: u64,
+    // Realtime timestamp of the most recent entry, in microseconds since the Unix epoch, or 0 if
+    // not known.
+    pub last_seen: u64,
     pub key: ByteCountTableKey,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct TopSelection {
+    // One entry per snapshot entry, in `ByteCountSnapshot::each_while` order.
+    retained: Box<[bool]>,
+    // Per priority, the sum of all entries not retained, or `None` if all of them were retained.
+    remainder: [Option<u64>; 8],
+}
+
+impl TopSelection {
+    pub fn is_retained(&self, index: usize) -> bool {
+        self.retained[index]
+    }
+
+    pub fn each_remainder(&self, mut receiver: impl FnMut(Priority, u64) -> bool) -> bool {
+        for (i, sum) in self.remainder.iter().enumerate() {
+            if let Some(sum) = *sum {
+                let priority = Priority::from_severity_index(truncate_usize_u8(i)).unwrap();
+                if !receiver(priority, sum) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct ByteCountSnapshot {
     // The last entry of "priority" 8 is the fallback.
@@ -47,9 +77,78 @@ impl ByteCountSnapshot {
         self.priority_table.iter().all(|t| t.is_empty())
     }
 
-    pub fn each_while(
+    pub fn len(&self) -> usize {
+        self.priority_table.iter().map(|t| t.len()).sum()
+    }
+
+    // Selects the `limit` entries with the highest `value`, and sums the rest per priority so the
+    // total across everything is left unchanged. Ties are broken by `each_while` order, so the
+    // selection is deterministic. Returns `None` on allocation failure.
+    pub fn select_top(
         &self,
-        mut receiver: impl FnMut(Priority, &ByteCountTableEntrySnapshot) -> bool,
+        limit: usize,
+        value: impl Fn(&ByteCountTableEntrySnapshot) -> u64,
+    ) -> Option<TopSelection> {
+        let len = self.len();
+        let mut retained = try_new_dynamic_vec(len)?;
+        let mut remainder = [None; 8];
+
+        if len <= limit {
+            retained.resize(len, true);
+            return Some(TopSelection {
+                retained: retained.into(),
+                remainder,
+            });
+        }
+
+        let mut ranked = try_new_dynamic_vec::<(u64, usize)>(len)?;
+
+        self.each_while(|_, entry| {
+            ranked.push((value(entry), ranked.len()));
+            true
+        });
+
+        // Highest values first, and for equal values, earliest entries first. Only the partition
+        // point matters, so there's no need to fully sort it.
+        if limit > 0 {
+            ranked.select_nth_unstable_by(limit.wrapping_sub(1), |a, b| {
+                b.0.cmp(&a.0).then(a.1.cmp(&b.1))
+            });
+        }
+
+        retained.resize(len, false);
+        for &(_, index) in &ranked[..limit] {
+            retained[index] = true;
+        }
+
+        let mut index = 0_usize;
+        self.each_while(|priority, entry| {
+            if !retained[index] {
+                let target = &mut remainder[zero_extend_u8_usize(priority.as_severity_index())];
+                *target = Some(target.unwrap_or(0).wrapping_add(value(entry)));
+            }
+            index = index.wrapping_add(1);
+            true
+        });
+
+        Some(TopSelection {
+            retained: retained.into(),
+            remainder,
+        })
+    }
+
+    // Drops every entry `keep` returns `false` for.
+    pub fn retain(&mut self, mut keep: impl FnMut(&ByteCountTableEntrySnapshot) -> bool) {
+        for table in &mut self.priority_table {
+            let mut entries = take(table).into_vec();
+            entries.retain(|entry| keep(entry));
+            *table = entries.into_boxed_slice();
+        }
+    }
+
+    pub fn each_while<'a>(
+        &'a self,
+        mut receiver: impl FnMut(Priority, &'a ByteCountTableEntrySnapshot) -> bool,
     ) -> bool {
         for (i, table) in self.priority_table.iter().enumerate() {
             let priority = Priority::from_severity_index(truncate_usize_u8(i)).unwrap();
@@ -80,6 +179,7 @@ impl ByteCountSnapshot {
                 key: item.key.table_key,
                 lines: item.lines,
                 bytes: item.bytes,
+                last_seen: item.last_seen,
             };
             result[priority_index].push(entry);
         }
@@ -95,6 +195,7 @@ impl ByteCountSnapshot {
 struct ByteCountTableEntry {
     lines: Counter,
     bytes: Counter,
+    last_seen: Counter,
     key: ByteCountTableKey,
 }
 
@@ -172,9 +273,11 @@ impl ByteCountMap {
                 for (i, entry) in table_lock.iter().enumerate() {
                     let bytes = entry.bytes.current();
                     let lines = entry.lines.current();
+                    let last_seen = entry.last_seen.current();
                     let target = result_ptr.add(i);
                     std::ptr::write(std::ptr::addr_of_mut!((*target).bytes), bytes);
                     std::ptr::write(std::ptr::addr_of_mut!((*target).lines), lines);
+                    std::ptr::write(std::ptr::addr_of_mut!((*target).last_seen), last_seen);
                 }
 
                 let result = Box::from_raw(std::ptr::slice_from_raw_parts_mut(result_ptr, len));
@@ -194,7 +297,7 @@ impl ByteCountMap {
     }
 
     // Take a reference to avoid a copy.
-    pub fn push_line(&self, key: &MessageKey, msg_len: usize) -> bool {
+    pub fn push_line(&self, key: &MessageKey, msg_len: usize, realtime_usec: u64) -> bool {
         // Services are very rarely added. Try opening a read first and doing atomic updates, and
         // fall back to a write lock if the entry doesn't exist yet. Contention should already be
         // low as-is since only two threads could be accessing the map, and it's further reduced by
@@ -206,25 +309,33 @@ impl ByteCountMap {
             .read()
             .unwrap_or_else(|e| e.into_inner());
 
-        if find_and_increment(&read_lock, key, msg_len) {
+        if find_and_increment(&read_lock, key, msg_len, realtime_usec) {
             return true;
         }
 
         // Don't deadlock. Drop the lock before entering the fallback path.
         drop(read_lock);
 
-        return push_line_likely_new(&self.priority_table[priority_index], key, msg_len);
+        return push_line_likely_new(
+            &self.priority_table[priority_index],
+            key,
+            msg_len,
+            realtime_usec,
+        );
 
         fn find_and_increment(
             entries: &[ByteCountTableEntry],
             key: &MessageKey,
             msg_len: usize,
+            realtime_usec: u64,
         ) -> bool {
             match entries.iter().find(|entry| entry.key == key.table_key) {
                 None => false,
                 Some(entry) => {
                     entry.lines.increment();
                     entry.bytes.increment_by(zero_extend_usize_u64(msg_len));
+                    // Entries aren't necessarily read in timestamp order, so keep the latest.
+                    entry.last_seen.record_max(realtime_usec);
                     true
                 }
             }
@@ -247,12 +358,13 @@ impl ByteCountMap {
             priority_entry: &RwLock<Vec<ByteCountTableEntry>>,
             key: &MessageKey,
             msg_len: usize,
+            realtime_usec: u64,
         ) -> bool {
             // Entry doesn't exist. Time to acquire a write lock and update the hash map with a
             // possible new key.
             let mut write_lock = priority_entry.write().unwrap_or_else(|e| e.into_inner());
 
-            if !find_and_increment(&write_lock, key, msg_len) {
+            if !find_and_increment(&write_lock, key, msg_len, realtime_usec) {
                 // While this may reallocate a lot at first, it's unlikely to reallocate too much
                 // after that, since there's only so many system services. This is why it doesn't
                 // try to pre-allocate - it's just not needed.
@@ -265,6 +377,7 @@ impl ByteCountMap {
                 let entry = ByteCountTableEntry {
                     lines: Counter::new(1),
                     bytes: Counter::new(zero_extend_usize_u64(msg_len)),
+                    last_seen: Counter::new(realtime_usec),
                     key: table_key,
                 };
 
@@ -299,27 +412,30 @@ mod tests {
         init_logger();
         static S: PromState = PromState::new();
 
-        S.add_message_line_ingested(&map_key(b"one"), 123);
-        S.add_message_line_ingested(&map_key(b"one"), 456);
-        S.add_message_line_ingested(&map_key(b"two"), 789);
-        S.add_message_line_ingested(&map_key(b"three"), 555);
-        S.add_message_line_ingested(&map_key(b"three"), 444);
+        S.add_message_line_ingested(&map_key(b"one"), 123, 0);
+        S.add_message_line_ingested(&map_key(b"one"), 456, 0);
+        S.add_message_line_ingested(&map_key(b"two"), 789, 0);
+        S.add_message_line_ingested(&map_key(b"three"), 555, 0);
+        S.add_message_line_ingested(&map_key(b"three"), 444, 0);
 
         const EXPECTED_DATA: &[ByteCountSnapshotEntry] = &[
             ByteCountSnapshotEntry {
                 key: map_key(b"one"),
                 lines: 2,
                 bytes: 579,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: map_key(b"two"),
                 lines: 1,
                 bytes: 789,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: map_key(b"three"),
                 lines: 2,
                 bytes: 999,
+                last_seen: 0,
             },
         ];
 
@@ -335,6 +451,7 @@ mod tests {
                 corrupted_fields: 0,
                 metrics_requests: 0,
                 messages_ingested: ByteCountSnapshot::build(EXPECTED_DATA.iter().cloned()),
+                fields: FieldStatsSnapshot::empty(),
             }
         );
     }
@@ -362,11 +479,11 @@ mod tests {
         let mut test_modulo = 9_usize;
         for i in 1..=100 {
             for key in &KEYS {
-                state.add_message_line_ingested(key, 10);
+                state.add_message_line_ingested(key, 10, 0);
             }
             if i % 10 == test_modulo {
                 test_modulo = test_modulo.wrapping_sub(1);
-                state.add_message_line_ingested(&contends_list[i / 10], 1);
+                state.add_message_line_ingested(&contends_list[i / 10], 1, 0);
             }
         }
     }
@@ -406,42 +523,42 @@ mod tests {
 
         #[rustfmt::skip]
         const EXPECTED_DATA: &[ByteCountSnapshotEntry] = &[
-            ByteCountSnapshotEntry { key: map_key(b"test_1"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_2"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_3"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_4"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_5"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_6"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_7"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_8"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_9"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_10"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_11"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_12"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_13"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_14"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_15"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"test_16"), lines: 200, bytes: 2000 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_0_0"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_0_1"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_0_2"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_0_3"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_0_4"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_0_5"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_0_6"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_0_7"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_0_8"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_0_9"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_1_0"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_1_1"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_1_2"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_1_3"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_1_4"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_1_5"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_1_6"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_1_7"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_1_8"), lines: 1, bytes: 1 },
-            ByteCountSnapshotEntry { key: map_key(b"contend_1_9"), lines: 1, bytes: 1 },
+            ByteCountSnapshotEntry { key: map_key(b"test_1"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_2"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_3"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_4"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_5"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_6"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_7"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_8"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_9"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_10"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_11"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_12"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_13"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_14"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_15"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"test_16"), lines: 200, bytes: 2000, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_0_0"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_0_1"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_0_2"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_0_3"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_0_4"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_0_5"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_0_6"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_0_7"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_0_8"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_0_9"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_1_0"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_1_1"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_1_2"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_1_3"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_1_4"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_1_5"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_1_6"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_1_7"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_1_8"), lines: 1, bytes: 1, last_seen: 0 },
+            ByteCountSnapshotEntry { key: map_key(b"contend_1_9"), lines: 1, bytes: 1, last_seen: 0 },
         ];
 
         std::thread::scope(|s| {
@@ -461,6 +578,7 @@ mod tests {
                 corrupted_fields: 0,
                 metrics_requests: 0,
                 messages_ingested: ByteCountSnapshot::build(EXPECTED_DATA.iter().cloned()),
+                fields: FieldStatsSnapshot::empty(),
             }
         );
     }
@@ -503,28 +621,29 @@ mod tests {
                 key: map_key(b"placeholder"),
                 lines: 1,
                 bytes: 1,
+                last_seen: 0,
             }; KEYS.len() + 1000];
 
             // Don't format this bit. It'll just make this less readable.
             #[rustfmt::skip]
             #[allow(clippy::let_unit_value)]
             let _ = {
-                result[0] = ByteCountSnapshotEntry { key: map_key(b"test_1"), lines: 10_000, bytes: 100_000 };
-                result[1] = ByteCountSnapshotEntry { key: map_key(b"test_2"), lines: 10_000, bytes: 100_000 };
-                result[2] = ByteCountSnapshotEntry { key: map_key(b"test_3"), lines: 10_000, bytes: 100_000 };
-                result[3] = ByteCountSnapshotEntry { key: map_key(b"test_4"), lines: 10_000, bytes: 100_000 };
-                result[4] = ByteCountSnapshotEntry { key: map_key(b"test_5"), lines: 10_000, bytes: 100_000 };
-                result[5] = ByteCountSnapshotEntry { key: map_key(b"test_6"), lines: 10_000, bytes: 100_000 };
-                result[6] = ByteCountSnapshotEntry { key: map_key(b"test_7"), lines: 10_000, bytes: 100_000 };
-                result[7] = ByteCountSnapshotEntry { key: map_key(b"test_8"), lines: 10_000, bytes: 100_000 };
-                result[8] = ByteCountSnapshotEntry { key: map_key(b"test_9"), lines: 10_000, bytes: 100_000 };
-                result[9] = ByteCountSnapshotEntry { key: map_key(b"test_10"), lines: 10_000, bytes: 100_000 };
-                result[10] = ByteCountSnapshotEntry { key: map_key(b"test_11"), lines: 10_000, bytes: 100_000 };
-                result[11] = ByteCountSnapshotEntry { key: map_key(b"test_12"), lines: 10_000, bytes: 100_000 };
-                result[12] = ByteCountSnapshotEntry { key: map_key(b"test_13"), lines: 10_000, bytes: 100_000 };
-                result[13] = ByteCountSnapshotEntry { key: map_key(b"test_14"), lines: 10_000, bytes: 100_000 };
-                result[14] = ByteCountSnapshotEntry { key: map_key(b"test_15"), lines: 10_000, bytes: 100_000 };
-                result[15] = ByteCountSnapshotEntry { key: map_key(b"test_16"), lines: 10_000, bytes: 100_000 };
+                result[0] = ByteCountSnapshotEntry { key: map_key(b"test_1"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[1] = ByteCountSnapshotEntry { key: map_key(b"test_2"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[2] = ByteCountSnapshotEntry { key: map_key(b"test_3"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[3] = ByteCountSnapshotEntry { key: map_key(b"test_4"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[4] = ByteCountSnapshotEntry { key: map_key(b"test_5"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[5] = ByteCountSnapshotEntry { key: map_key(b"test_6"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[6] = ByteCountSnapshotEntry { key: map_key(b"test_7"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[7] = ByteCountSnapshotEntry { key: map_key(b"test_8"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[8] = ByteCountSnapshotEntry { key: map_key(b"test_9"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[9] = ByteCountSnapshotEntry { key: map_key(b"test_10"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[10] = ByteCountSnapshotEntry { key: map_key(b"test_11"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[11] = ByteCountSnapshotEntry { key: map_key(b"test_12"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[12] = ByteCountSnapshotEntry { key: map_key(b"test_13"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[13] = ByteCountSnapshotEntry { key: map_key(b"test_14"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[14] = ByteCountSnapshotEntry { key: map_key(b"test_15"), lines: 10_000, bytes: 100_000, last_seen: 0 };
+                result[15] = ByteCountSnapshotEntry { key: map_key(b"test_16"), lines: 10_000, bytes: 100_000, last_seen: 0 };
             };
 
             let mut target = 16;
@@ -537,6 +656,7 @@ mod tests {
                         key: entry[j],
                         lines: 1,
                         bytes: 1,
+                        last_seen: 0,
                     };
                     j += 1;
                     target += 1;
@@ -565,7 +685,125 @@ mod tests {
                 corrupted_fields: 0,
                 metrics_requests: 0,
                 messages_ingested: ByteCountSnapshot::build(EXPECTED_DATA.iter().cloned()),
+                fields: FieldStatsSnapshot::empty(),
             }
         );
     }
+
+    fn build_top_snapshot(entries: &[(MessageKey, u32)]) -> ByteCountSnapshot {
+        ByteCountSnapshot::build(entries.iter().map(|&(key, lines)| ByteCountSnapshotEntry {
+            key,
+            lines: u64::from(lines),
+            bytes: 0,
+            last_seen: 0,
+        }))
+    }
+
+    fn collect_selection(
+        snapshot: &ByteCountSnapshot,
+        selection: &TopSelection,
+    ) -> (Vec<u64>, Vec<u64>) {
+        let mut retained = Vec::new();
+        let mut dropped = Vec::new();
+        let mut index = 0;
+        snapshot.each_while(|_, entry| {
+            if selection.is_retained(index) {
+                retained.push(entry.lines);
+            } else {
+                dropped.push(entry.lines);
+            }
+            index += 1;
+            true
+        });
+        (retained, dropped)
+    }
+
+    #[test]
+    fn select_top_retains_everything_when_under_limit() {
+        let snapshot = build_top_snapshot(&[(map_key(b"one"), 1), (map_key(b"two"), 2)]);
+        let selection = snapshot.select_top(2, |e| e.lines).unwrap();
+        assert_eq!(
+            collect_selection(&snapshot, &selection),
+            (vec![1, 2], vec![])
+        );
+        assert!(selection.each_remainder(|_, _| panic!("Remainder present")));
+    }
+
+    #[test]
+    fn select_top_sums_rest_per_priority() {
+        let snapshot = build_top_snapshot(&[
+            (map_key(b"one"), 5),
+            (map_key(b"two"), 2),
+            (map_key(b"three"), 7),
+            (
+                MessageKey::build(None, None, Some(b"four"), Priority::Warning),
+                1,
+            ),
+        ]);
+        let selection = snapshot.select_top(2, |e| e.lines).unwrap();
+        let mut remainder = Vec::new();
+        selection.each_remainder(|priority, sum| {
+            remainder.push((priority, sum));
+            true
+        });
+        assert_eq!(
+            collect_selection(&snapshot, &selection),
+            (vec![5, 7], vec![1, 2])
+        );
+        assert_eq!(
+            remainder,
+            vec![(Priority::Warning, 1), (Priority::Informational, 2)]
+        );
+    }
+
+    #[test]
+    fn select_top_breaks_ties_by_order() {
+        let snapshot = build_top_snapshot(&[
+            (map_key(b"one"), 3),
+            (map_key(b"two"), 3),
+            (map_key(b"three"), 3),
+        ]);
+        let selection = snapshot.select_top(1, |e| e.lines).unwrap();
+        assert!(selection.is_retained(0));
+        assert!(!selection.is_retained(1));
+        assert!(!selection.is_retained(2));
+    }
+
+    #[quickcheck]
+    fn select_top_preserves_total(entries: Vec<(MessageKey, u32)>, limit: u8) -> bool {
+        let snapshot = build_top_snapshot(&entries);
+        let selection = snapshot
+            .select_top(zero_extend_u8_usize(limit), |e| e.lines)
+            .unwrap();
+        let (retained, _) = collect_selection(&snapshot, &selection);
+        let mut remainder_total = 0;
+        selection.each_remainder(|_, sum| {
+            remainder_total += sum;
+            true
+        });
+        let expected: u64 = entries.iter().map(|e| u64::from(e.1)).sum();
+        retained.iter().sum::<u64>() + remainder_total == expected
+    }
+
+    #[quickcheck]
+    fn select_top_retains_up_to_limit(entries: Vec<(MessageKey, u32)>, limit: u8) -> bool {
+        let limit = zero_extend_u8_usize(limit);
+        let snapshot = build_top_snapshot(&entries);
+        let selection = snapshot.select_top(limit, |e| e.lines).unwrap();
+        let (retained, _) = collect_selection(&snapshot, &selection);
+        retained.len() == limit.min(entries.len())
+    }
+
+    #[quickcheck]
+    fn select_top_retains_highest_values(entries: Vec<(MessageKey, u32)>, limit: u8) -> bool {
+        let snapshot = build_top_snapshot(&entries);
+        let selection = snapshot
+            .select_top(zero_extend_u8_usize(limit), |e| e.lines)
+            .unwrap();
+        let (retained, dropped) = collect_selection(&snapshot, &selection);
+        match (retained.iter().min(), dropped.iter().max()) {
+            (Some(min_retained), Some(max_dropped)) => min_retained >= max_dropped,
+            _ => true,
+        }
+    }
 }
@@ -0,0 +1,232 @@
+use crate::prelude::*;
+
+use const_str::cstr;
+use std::ffi::CStr;
+
+/// The journal fields read for each entry. Field stats are tracked per field, and the order here
+/// is also the order they're rendered in. `_CMDLINE` isn't needed for anything else, so it's only
+/// read when field stats are enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JournalField {
+    SystemdUnit,
+    Priority,
+    Uid,
+    Gid,
+    Message,
+    Cmdline,
+}
+
+const FIELD_COUNT: usize = 6;
+
+impl JournalField {
+    pub const ALL: [JournalField; FIELD_COUNT] = [
+        JournalField::SystemdUnit,
+        JournalField::Priority,
+        JournalField::Uid,
+        JournalField::Gid,
+        JournalField::Message,
+        JournalField::Cmdline,
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            JournalField::SystemdUnit => 0,
+            JournalField::Priority => 1,
+            JournalField::Uid => 2,
+            JournalField::Gid => 3,
+            JournalField::Message => 4,
+            JournalField::Cmdline => 5,
+        }
+    }
+
+    pub fn as_cstr(self) -> &'static CStr {
+        match self {
+            JournalField::SystemdUnit => cstr!("_SYSTEMD_UNIT"),
+            JournalField::Priority => cstr!("PRIORITY"),
+            JournalField::Uid => cstr!("_UID"),
+            JournalField::Gid => cstr!("_GID"),
+            JournalField::Message => cstr!("MESSAGE"),
+            JournalField::Cmdline => cstr!("_CMDLINE"),
+        }
+    }
+
+    pub fn as_name_bytes(self) -> &'static [u8] {
+        self.as_cstr().to_bytes()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FieldStatsEntry {
+    pub ingested: u64,
+    pub ingested_bytes: u64,
+    // Fields too large to read (`E2BIG` or `ENOBUFS`).
+    pub unreadable: u64,
+    // Fields detected as corrupted (`EBADMSG`).
+    pub corrupted: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct FieldStatsSnapshot {
+    entries: [FieldStatsEntry; FIELD_COUNT],
+}
+
+impl FieldStatsSnapshot {
+    #[cfg(test)]
+    pub fn empty() -> Self {
+        Self {
+            entries: [FieldStatsEntry::default(); FIELD_COUNT],
+        }
+    }
+
+    #[cfg(test)]
+    pub fn build(data: impl IntoIterator<Item = (JournalField, FieldStatsEntry)>) -> Self {
+        let mut result = Self::empty();
+        for (field, entry) in data {
+            result.entries[field.index()] = entry;
+        }
+        result
+    }
+
+    pub fn get(&self, field: JournalField) -> &FieldStatsEntry {
+        &self.entries[field.index()]
+    }
+}
+
+struct FieldCounters {
+    ingested: Counter,
+    ingested_bytes: Counter,
+    unreadable: Counter,
+    corrupted: Counter,
+}
+
+impl FieldCounters {
+    const fn new() -> Self {
+        Self {
+            ingested: Counter::new(0),
+            ingested_bytes: Counter::new(0),
+            unreadable: Counter::new(0),
+            corrupted: Counter::new(0),
+        }
+    }
+
+    fn snapshot(&self) -> FieldStatsEntry {
+        FieldStatsEntry {
+            ingested: self.ingested.current(),
+            ingested_bytes: self.ingested_bytes.current(),
+            unreadable: self.unreadable.current(),
+            corrupted: self.corrupted.current(),
+        }
+    }
+}
+
+pub struct FieldStats {
+    fields: [FieldCounters; FIELD_COUNT],
+}
+
+impl FieldStats {
+    pub const fn new() -> Self {
+        Self {
+            fields: [
+                FieldCounters::new(),
+                FieldCounters::new(),
+                FieldCounters::new(),
+                FieldCounters::new(),
+                FieldCounters::new(),
+                FieldCounters::new(),
+            ],
+        }
+    }
+
+    pub fn add_ingested(&self, field: JournalField, bytes: usize) {
+        let counters = &self.fields[field.index()];
+        counters.ingested.increment();
+        counters
+            .ingested_bytes
+            .increment_by(zero_extend_usize_u64(bytes));
+    }
+
+    #[cold]
+    pub fn add_unreadable(&self, field: JournalField) {
+        self.fields[field.index()].unreadable.increment();
+    }
+
+    #[cold]
+    pub fn add_corrupted(&self, field: JournalField) {
+        self.fields[field.index()].corrupted.increment();
+    }
+
+    pub fn snapshot(&self) -> FieldStatsSnapshot {
+        FieldStatsSnapshot {
+            entries: [
+                self.fields[0].snapshot(),
+                self.fields[1].snapshot(),
+                self.fields[2].snapshot(),
+                self.fields[3].snapshot(),
+                self.fields[4].snapshot(),
+                self.fields[5].snapshot(),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_indices_match_all_order() {
+        for (i, field) in JournalField::ALL.iter().enumerate() {
+            assert_eq!(field.index(), i);
+        }
+    }
+
+    #[test]
+    fn starts_empty() {
+        let stats = FieldStats::new();
+        assert_eq!(stats.snapshot(), FieldStatsSnapshot::empty());
+    }
+
+    #[test]
+    fn tracks_each_field_separately() {
+        let stats = FieldStats::new();
+        stats.add_ingested(JournalField::Message, 123);
+        stats.add_ingested(JournalField::Message, 456);
+        stats.add_ingested(JournalField::Uid, 4);
+        stats.add_unreadable(JournalField::Message);
+        stats.add_corrupted(JournalField::SystemdUnit);
+        stats.add_corrupted(JournalField::SystemdUnit);
+
+        assert_eq!(
+            stats.snapshot(),
+            FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 0,
+                        ingested_bytes: 0,
+                        unreadable: 0,
+                        corrupted: 2,
+                    },
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 4,
+                        unreadable: 0,
+                        corrupted: 0,
+                    },
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 579,
+                        unreadable: 1,
+                        corrupted: 0,
+                    },
+                ),
+            ])
+        );
+    }
+}
@@ -1,25 +1,166 @@
 use crate::prelude::*;
 
 use super::common::*;
+use super::MetricsFormat;
 
 pub const REQUEST_METRICS: u8 = 0x00;
 pub const REQUEST_KEY: u8 = 0x01;
 pub const TRACK_REQUEST: u8 = 0x02;
+pub const REQUEST_TEXT_METRICS: u8 = 0x03;
+pub const REQUEST_PROTOBUF_METRICS: u8 = 0x04;
+pub const REQUEST_HEALTH: u8 = 0x05;
+// Each followed by the observed duration in microseconds, as a little-endian `u32`.
+pub const OBSERVE_SCRAPE_DURATION: u8 = 0x06;
+pub const OBSERVE_IPC_DURATION: u8 = 0x07;
+// Followed by the response's status code, as a little-endian `u32`.
+pub const RECORD_RESPONSE: u8 = 0x08;
+pub const AUTH_MISSING_HEADER: u8 = 0x09;
+pub const AUTH_BAD_SYNTAX: u8 = 0x0A;
+pub const AUTH_WRONG_USER: u8 = 0x0B;
+pub const AUTH_UNKNOWN_KEY: u8 = 0x0C;
+pub const THROTTLED_BY_ADDRESS: u8 = 0x0D;
+// Followed by the matched key's name, prefixed with its length as a single byte.
+pub const KEY_REQUEST: u8 = 0x0E;
+// Followed by the request's ID and its key's encoded scope's length, both as little-endian `u32`s,
+// then the scope itself, and finally the metrics request byte for the format to render.
+pub const REQUEST_SCOPED_METRICS: u8 = 0x0F;
+// Followed by the negotiated cipher suite's IANA code point, as a little-endian `u32`.
+pub const TLS_HANDSHAKE: u8 = 0x10;
+pub const THROTTLED_BY_KEY: u8 = 0x11;
+pub const THROTTLED_BY_LOCKOUT: u8 = 0x12;
+
+// The parent reads at most 64 bytes at a time and takes the request after each read, so this is
+// more than enough to never drop any in practice.
+const MAX_OBSERVATIONS: usize = 16;
+// Same here, as each key request takes at least 3 bytes, and repeated names are merged.
+const MAX_KEY_REQUESTS: usize = 24;
+
+const MAX_KEY_REQUEST_BYTES: usize = MAX_KEY_NAME_LEN.wrapping_add(2);
 
 const STATE_METRICS_REQUESTED: u8 = 1 << 0;
 const STATE_KEYS_REQUESTED: u8 = 1 << 1;
 const STATE_VERSION_ADDED: u8 = 1 << 2;
+const STATE_TEXT_METRICS_REQUESTED: u8 = 1 << 3;
+const STATE_PROTOBUF_METRICS_REQUESTED: u8 = 1 << 4;
+const STATE_HEALTH_REQUESTED: u8 = 1 << 5;
+
+const STATE_ANY_METRICS_REQUESTED: u8 =
+    STATE_METRICS_REQUESTED | STATE_TEXT_METRICS_REQUESTED | STATE_PROTOBUF_METRICS_REQUESTED;
+
+pub const fn request_metrics_byte(format: MetricsFormat) -> u8 {
+    match format {
+        MetricsFormat::OpenMetrics => REQUEST_METRICS,
+        MetricsFormat::PrometheusText => REQUEST_TEXT_METRICS,
+        MetricsFormat::Protobuf => REQUEST_PROTOBUF_METRICS,
+    }
+}
+
+/// Encodes a duration observation for the parent to record, with `op` being one of the
+/// `OBSERVE_*` bytes.
+pub fn observation_bytes(op: u8, duration: Duration) -> [u8; 5] {
+    let micros = u32::try_from(duration.as_micros()).unwrap_or(u32::MAX);
+    let [a, b, c, d] = micros.to_le_bytes();
+    [op, a, b, c, d]
+}
+
+/// Encodes a sent response for the parent to count.
+pub fn response_bytes(status: u16) -> [u8; 5] {
+    let [a, b, c, d] = u32::from(status).to_le_bytes();
+    [RECORD_RESPONSE, a, b, c, d]
+}
+
+/// Encodes a request authorized by a named key, for the parent to count against that key.
+pub fn key_request_bytes(name: &[u8]) -> heapless::Vec<u8, MAX_KEY_REQUEST_BYTES> {
+    debug_assert!(is_valid_key_name(name));
+    let mut result = heapless::Vec::new();
+    result
+        .extend_from_slice(&[KEY_REQUEST, truncate_usize_u8(name.len())])
+        .unwrap();
+    result.extend_from_slice(name).unwrap();
+    result
+}
+
+/// Encodes a metrics request that's only allowed to see what `scope` allows. The parent responds
+/// to it separately from unscoped requests, tagging its response with the same `id`.
+pub fn scoped_metrics_request_bytes(id: u32, format: MetricsFormat, scope: &[u8]) -> Vec<u8> {
+    debug_assert!(scope.len() <= MAX_KEY_SCOPE_LEN);
+    let mut result = Vec::with_capacity(scope.len().wrapping_add(10));
+    result.push(REQUEST_SCOPED_METRICS);
+    result.extend_from_slice(&id.to_le_bytes());
+    result.extend_from_slice(&truncate_usize_u32(scope.len()).to_le_bytes());
+    result.extend_from_slice(scope);
+    result.push(request_metrics_byte(format));
+    result
+}
+
+/// Encodes a completed TLS handshake for the parent to count. The suite also implies the version.
+pub fn tls_handshake_bytes(suite: TlsCipherSuite) -> [u8; 5] {
+    let [a, b, c, d] = u32::from(suite.code()).to_le_bytes();
+    [TLS_HANDSHAKE, a, b, c, d]
+}
+
+pub const fn auth_failure_byte(reason: AuthFailureReason) -> u8 {
+    match reason {
+        AuthFailureReason::MissingHeader => AUTH_MISSING_HEADER,
+        AuthFailureReason::BadSyntax => AUTH_BAD_SYNTAX,
+        AuthFailureReason::WrongUser => AUTH_WRONG_USER,
+        AuthFailureReason::UnknownKey => AUTH_UNKNOWN_KEY,
+    }
+}
+
+pub const fn throttle_byte(reason: ThrottleReason) -> u8 {
+    match reason {
+        ThrottleReason::Address => THROTTLED_BY_ADDRESS,
+        ThrottleReason::Key => THROTTLED_BY_KEY,
+        ThrottleReason::Lockout => THROTTLED_BY_LOCKOUT,
+    }
+}
+
+const fn metrics_requested_flag(format: MetricsFormat) -> u8 {
+    match format {
+        MetricsFormat::OpenMetrics => STATE_METRICS_REQUESTED,
+        MetricsFormat::PrometheusText => STATE_TEXT_METRICS_REQUESTED,
+        MetricsFormat::Protobuf => STATE_PROTOBUF_METRICS_REQUESTED,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScopedMetricsRequest {
+    pub id: u32,
+    pub format: MetricsFormat,
+    pub scope: KeyScope,
+}
 
 pub struct DecoderRequest {
     flags: u8,
     tracked_metrics_requests: usize,
+    // In microseconds.
+    observations: heapless::Vec<(ScrapeHistogram, u32), MAX_OBSERVATIONS>,
+    // Indexed the same as `HTTP_RESPONSE_CODES`, `AuthFailureReason::ALL`, and
+    // `ThrottleReason::ALL` respectively.
+    responses: [usize; HTTP_RESPONSE_CODES.len()],
+    auth_failures: [usize; AuthFailureReason::ALL.len()],
+    throttled_requests: [usize; ThrottleReason::ALL.len()],
+    // Indexed the same as `TlsCipherSuite::ALL`.
+    tls_handshakes: [usize; TlsCipherSuite::ALL.len()],
+    key_requests: heapless::Vec<(KeyName, usize), MAX_KEY_REQUESTS>,
+    // Each is responded to separately, so these can't just be dropped on overflow.
+    scoped_metrics: Vec<ScopedMetricsRequest>,
 }
 
 impl PartialEq for DecoderRequest {
     fn eq(&self, other: &Self) -> bool {
-        self.metrics_requested() == other.metrics_requested()
+        (self.flags & STATE_ANY_METRICS_REQUESTED) == (other.flags & STATE_ANY_METRICS_REQUESTED)
             && self.keys_requested() == other.keys_requested()
+            && self.health_requested() == other.health_requested()
             && self.tracked_metrics_requests == other.tracked_metrics_requests
+            && self.observations == other.observations
+            && self.responses == other.responses
+            && self.auth_failures == other.auth_failures
+            && self.throttled_requests == other.throttled_requests
+            && self.tls_handshakes == other.tls_handshakes
+            && self.key_requests == other.key_requests
+            && self.scoped_metrics == other.scoped_metrics
     }
 }
 
@@ -28,43 +169,198 @@ impl DecoderRequest {
     #[cfg(test)]
     pub const NO_FLAGS: u8 = 0;
 
-    pub const METRICS_REQUESTED: u8 = STATE_METRICS_REQUESTED;
     pub const KEYS_REQUESTED: u8 = STATE_KEYS_REQUESTED;
+    pub const HEALTH_REQUESTED: u8 = STATE_HEALTH_REQUESTED;
+
+    // Outside tests, metrics requests are checked per format via `metrics_requested`.
+    #[cfg(test)]
+    pub const METRICS_REQUESTED: u8 = STATE_METRICS_REQUESTED;
+    #[cfg(test)]
+    pub const TEXT_METRICS_REQUESTED: u8 = STATE_TEXT_METRICS_REQUESTED;
+    #[cfg(test)]
+    pub const PROTOBUF_METRICS_REQUESTED: u8 = STATE_PROTOBUF_METRICS_REQUESTED;
 
+    #[cfg(test)]
     pub const fn new(flags: u8, tracked_metrics_requests: usize) -> Self {
         Self {
             flags,
             tracked_metrics_requests,
+            observations: heapless::Vec::new(),
+            responses: [0; HTTP_RESPONSE_CODES.len()],
+            auth_failures: [0; AuthFailureReason::ALL.len()],
+            throttled_requests: [0; ThrottleReason::ALL.len()],
+            tls_handshakes: [0; TlsCipherSuite::ALL.len()],
+            key_requests: heapless::Vec::new(),
+            scoped_metrics: Vec::new(),
         }
     }
 
-    pub const fn metrics_requested(&self) -> bool {
-        (self.flags & DecoderRequest::METRICS_REQUESTED) != 0
+    #[cfg(test)]
+    pub fn with_scoped_metrics(mut self, id: u32, format: MetricsFormat, scope: &[u8]) -> Self {
+        self.scoped_metrics.push(ScopedMetricsRequest {
+            id,
+            format,
+            scope: KeyScope::decode(scope).expect("invalid scope"),
+        });
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_key_requests(mut self, name: &[u8], count: usize) -> Self {
+        self.key_requests
+            .push((KeyName::from_slice(name).unwrap(), count))
+            .expect("too many key requests");
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_responses(mut self, code: u16, count: usize) -> Self {
+        self.responses[http_response_code_index(code).expect("unknown status code")] = count;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_auth_failures(mut self, reason: AuthFailureReason, count: usize) -> Self {
+        self.auth_failures[reason.index()] = count;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_throttled_requests(mut self, reason: ThrottleReason, count: usize) -> Self {
+        self.throttled_requests[reason.index()] = count;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_tls_handshakes(mut self, suite: TlsCipherSuite, count: usize) -> Self {
+        self.tls_handshakes[suite.index()] = count;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_observation(mut self, histogram: ScrapeHistogram, micros: u32) -> Self {
+        self.observations
+            .push((histogram, micros))
+            .expect("too many observations");
+        self
+    }
+
+    pub const fn metrics_requested(&self, format: MetricsFormat) -> bool {
+        (self.flags & metrics_requested_flag(format)) != 0
     }
 
     pub const fn keys_requested(&self) -> bool {
         (self.flags & DecoderRequest::KEYS_REQUESTED) != 0
     }
 
+    pub const fn health_requested(&self) -> bool {
+        (self.flags & DecoderRequest::HEALTH_REQUESTED) != 0
+    }
+
     pub const fn tracked_metrics_requests(&self) -> usize {
         self.tracked_metrics_requests
     }
+
+    pub fn observations(&self) -> &[(ScrapeHistogram, u32)] {
+        &self.observations
+    }
+
+    pub fn responses(&self) -> impl Iterator<Item = (u16, usize)> + '_ {
+        HTTP_RESPONSE_CODES.into_iter().zip(self.responses)
+    }
+
+    pub fn auth_failures(&self) -> impl Iterator<Item = (AuthFailureReason, usize)> + '_ {
+        AuthFailureReason::ALL.into_iter().zip(self.auth_failures)
+    }
+
+    pub fn throttled_requests(&self) -> impl Iterator<Item = (ThrottleReason, usize)> + '_ {
+        ThrottleReason::ALL.into_iter().zip(self.throttled_requests)
+    }
+
+    pub fn tls_handshakes(&self) -> impl Iterator<Item = (TlsCipherSuite, usize)> + '_ {
+        TlsCipherSuite::ALL.into_iter().zip(self.tls_handshakes)
+    }
+
+    pub fn key_requests(&self) -> impl Iterator<Item = (&[u8], usize)> + '_ {
+        self.key_requests
+            .iter()
+            .map(|(name, count)| (name.as_slice(), *count))
+    }
+
+    pub fn scoped_metrics(&self) -> &[ScopedMetricsRequest] {
+        &self.scoped_metrics
+    }
 }
 
 impl fmt::Debug for DecoderRequest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("DecoderRequest")
-            .field("metrics_requested", &self.metrics_requested())
+            .field(
+                "metrics_requested",
+                &MetricsFormat::ALL.map(|format| self.metrics_requested(format)),
+            )
             .field("keys_requested", &self.keys_requested())
+            .field("health_requested", &self.health_requested())
             .field("tracked_metrics_requests", &self.tracked_metrics_requests())
+            .field("observations", &self.observations())
+            .field("responses", &self.responses)
+            .field("auth_failures", &self.auth_failures)
+            .field("throttled_requests", &self.throttled_requests)
+            .field("tls_handshakes", &self.tls_handshakes)
+            .field(
+                "key_requests",
+                &Vec::from_iter(
+                    self.key_requests()
+                        .map(|(name, count)| (BinaryToDebug(name), count)),
+                ),
+            )
+            .field("scoped_metrics", &self.scoped_metrics)
             .finish()
     }
 }
 
+// What the 32-bit value currently being read belongs to.
+#[derive(Debug, Clone, Copy)]
+enum PendingValue {
+    Observation(ScrapeHistogram),
+    ResponseStatus,
+    ScopedMetricsId,
+    ScopedMetricsScopeLen { id: u32 },
+    TlsHandshake,
+}
+
+// Where the scoped metrics request currently being read is at, once its ID and scope length are
+// read.
+#[derive(Debug, Clone, Copy)]
+enum PendingScopedMetrics {
+    Scope { id: u32, remaining: usize },
+    Format { id: u32 },
+}
+
+// Where the key name currently being read is at.
+#[derive(Debug, Clone, Copy)]
+enum PendingKeyName {
+    Length,
+    Name { remaining: usize },
+}
+
 pub struct Decoder {
     state: u8,
     tracked_metrics_requests: Wrapping<usize>,
     version_phase: ReadPhase,
+    pending_value: Option<PendingValue>,
+    value_phase: ReadPhase,
+    observations: heapless::Vec<(ScrapeHistogram, u32), MAX_OBSERVATIONS>,
+    responses: [usize; HTTP_RESPONSE_CODES.len()],
+    auth_failures: [usize; AuthFailureReason::ALL.len()],
+    throttled_requests: [usize; ThrottleReason::ALL.len()],
+    tls_handshakes: [usize; TlsCipherSuite::ALL.len()],
+    pending_key_name: Option<PendingKeyName>,
+    key_name: KeyName,
+    key_requests: heapless::Vec<(KeyName, usize), MAX_KEY_REQUESTS>,
+    pending_scoped_metrics: Option<PendingScopedMetrics>,
+    scope: heapless::Vec<u8, MAX_KEY_SCOPE_LEN>,
+    scoped_metrics: Vec<ScopedMetricsRequest>,
 }
 
 impl Decoder {
@@ -73,15 +369,39 @@ impl Decoder {
             state: 0,
             tracked_metrics_requests: Wrapping(0),
             version_phase: ReadPhase::new(),
+            pending_value: None,
+            value_phase: ReadPhase::new(),
+            observations: heapless::Vec::new(),
+            responses: [0; HTTP_RESPONSE_CODES.len()],
+            auth_failures: [0; AuthFailureReason::ALL.len()],
+            throttled_requests: [0; ThrottleReason::ALL.len()],
+            tls_handshakes: [0; TlsCipherSuite::ALL.len()],
+            pending_key_name: None,
+            key_name: heapless::Vec::new(),
+            key_requests: heapless::Vec::new(),
+            pending_scoped_metrics: None,
+            scope: heapless::Vec::new(),
+            scoped_metrics: Vec::new(),
         }
     }
 
     pub fn take_request(&mut self) -> DecoderRequest {
         let state = self.state;
         let tracked_metrics_requests = self.tracked_metrics_requests.0;
-        self.state &= !(STATE_METRICS_REQUESTED | STATE_KEYS_REQUESTED);
+        self.state &=
+            !(STATE_ANY_METRICS_REQUESTED | STATE_KEYS_REQUESTED | STATE_HEALTH_REQUESTED);
         self.tracked_metrics_requests.0 = 0;
-        DecoderRequest::new(state, tracked_metrics_requests)
+        DecoderRequest {
+            flags: state,
+            tracked_metrics_requests,
+            observations: take(&mut self.observations),
+            responses: take(&mut self.responses),
+            auth_failures: take(&mut self.auth_failures),
+            throttled_requests: take(&mut self.throttled_requests),
+            tls_handshakes: take(&mut self.tls_handshakes),
+            key_requests: take(&mut self.key_requests),
+            scoped_metrics: take(&mut self.scoped_metrics),
+        }
     }
 
     pub fn read_bytes(&mut self, buf: &[u8]) {
@@ -95,13 +415,178 @@ impl Decoder {
             }
         }
 
-        for byte in iter.remaining() {
+        loop {
+            if let Some(pending) = self.pending_value {
+                let Some(value) = iter.phase_next_32(&mut self.value_phase) else {
+                    return;
+                };
+
+                self.pending_value = None;
+                match pending {
+                    PendingValue::Observation(histogram) => {
+                        // Observations are just metrics, so it's fine to drop them on overflow.
+                        if !self.observations.is_full() {
+                            self.observations.push((histogram, value)).ok().unwrap();
+                        }
+                    }
+                    PendingValue::ResponseStatus => {
+                        let index = u16::try_from(value).ok().and_then(http_response_code_index);
+                        if let Some(index) = index {
+                            self.responses[index] = self.responses[index].wrapping_add(1);
+                        }
+                    }
+                    PendingValue::ScopedMetricsId => {
+                        self.pending_value =
+                            Some(PendingValue::ScopedMetricsScopeLen { id: value });
+                        continue;
+                    }
+                    PendingValue::ScopedMetricsScopeLen { id } => {
+                        let len = zero_extend_u32_usize(value);
+                        if len > MAX_KEY_SCOPE_LEN {
+                            std::panic::panic_any("Key scope too long.");
+                        }
+                        self.scope.clear();
+                        self.pending_scoped_metrics = Some(match len {
+                            0 => PendingScopedMetrics::Format { id },
+                            remaining => PendingScopedMetrics::Scope { id, remaining },
+                        });
+                    }
+                    PendingValue::TlsHandshake => {
+                        let suite = u16::try_from(value)
+                            .ok()
+                            .and_then(TlsCipherSuite::from_code);
+                        if let Some(suite) = suite {
+                            let count = &mut self.tls_handshakes[suite.index()];
+                            *count = count.wrapping_add(1);
+                        }
+                    }
+                }
+            }
+
+            while let Some(pending) = self.pending_scoped_metrics {
+                let Some(byte) = iter.next() else {
+                    return;
+                };
+
+                self.pending_scoped_metrics = match pending {
+                    PendingScopedMetrics::Scope { id, remaining } => {
+                        // The length was checked above, so this can't overflow.
+                        self.scope.push(byte).unwrap();
+                        match remaining.wrapping_sub(1) {
+                            0 => Some(PendingScopedMetrics::Format { id }),
+                            remaining => Some(PendingScopedMetrics::Scope { id, remaining }),
+                        }
+                    }
+                    PendingScopedMetrics::Format { id } => {
+                        self.add_scoped_metrics(id, byte);
+                        None
+                    }
+                };
+            }
+
+            while let Some(pending) = self.pending_key_name {
+                let Some(byte) = iter.next() else {
+                    return;
+                };
+
+                self.pending_key_name = match pending {
+                    PendingKeyName::Length => {
+                        let len = zero_extend_u8_usize(byte);
+                        if len == 0 || len > MAX_KEY_NAME_LEN {
+                            std::panic::panic_any("Key name has an invalid length.");
+                        }
+                        self.key_name.clear();
+                        Some(PendingKeyName::Name { remaining: len })
+                    }
+                    PendingKeyName::Name { remaining } => {
+                        // The length was checked above, so this can't overflow.
+                        self.key_name.push(byte).unwrap();
+                        match remaining.wrapping_sub(1) {
+                            0 => {
+                                self.add_key_request();
+                                None
+                            }
+                            remaining => Some(PendingKeyName::Name { remaining }),
+                        }
+                    }
+                };
+            }
+
+            let Some(byte) = iter.next() else {
+                return;
+            };
+
             match byte {
                 0x00 => self.state |= STATE_METRICS_REQUESTED,
                 0x01 => self.state |= STATE_KEYS_REQUESTED,
                 0x02 => self.tracked_metrics_requests += 1,
-                _ => unknown_byte(*byte),
+                0x03 => self.state |= STATE_TEXT_METRICS_REQUESTED,
+                0x04 => self.state |= STATE_PROTOBUF_METRICS_REQUESTED,
+                0x05 => self.state |= STATE_HEALTH_REQUESTED,
+                0x06 => {
+                    self.pending_value =
+                        Some(PendingValue::Observation(ScrapeHistogram::ScrapeDuration))
+                }
+                0x07 => {
+                    self.pending_value =
+                        Some(PendingValue::Observation(ScrapeHistogram::IpcDuration))
+                }
+                0x08 => self.pending_value = Some(PendingValue::ResponseStatus),
+                0x09 => self.add_auth_failure(AuthFailureReason::MissingHeader),
+                0x0A => self.add_auth_failure(AuthFailureReason::BadSyntax),
+                0x0B => self.add_auth_failure(AuthFailureReason::WrongUser),
+                0x0C => self.add_auth_failure(AuthFailureReason::UnknownKey),
+                0x0D => self.add_throttled_request(ThrottleReason::Address),
+                0x0E => self.pending_key_name = Some(PendingKeyName::Length),
+                0x0F => self.pending_value = Some(PendingValue::ScopedMetricsId),
+                0x10 => self.pending_value = Some(PendingValue::TlsHandshake),
+                0x11 => self.add_throttled_request(ThrottleReason::Key),
+                0x12 => self.add_throttled_request(ThrottleReason::Lockout),
+                _ => unknown_byte(byte),
             }
         }
     }
+
+    fn add_key_request(&mut self) {
+        let existing = self
+            .key_requests
+            .iter_mut()
+            .find(|(name, _)| *name == self.key_name);
+
+        if let Some((_, count)) = existing {
+            *count = count.wrapping_add(1);
+        } else if !self.key_requests.is_full() {
+            // Like observations, these are just metrics, so it's fine to drop them on overflow.
+            self.key_requests
+                .push((self.key_name.clone(), 1))
+                .ok()
+                .unwrap();
+        }
+    }
+
+    fn add_scoped_metrics(&mut self, id: u32, request_byte: u8) {
+        let format = match request_byte {
+            REQUEST_METRICS => MetricsFormat::OpenMetrics,
+            REQUEST_TEXT_METRICS => MetricsFormat::PrometheusText,
+            REQUEST_PROTOBUF_METRICS => MetricsFormat::Protobuf,
+            byte => unknown_byte(byte),
+        };
+
+        let Some(scope) = KeyScope::decode(&self.scope) else {
+            std::panic::panic_any("Invalid key scope.");
+        };
+
+        self.scoped_metrics
+            .push(ScopedMetricsRequest { id, format, scope });
+    }
+
+    fn add_auth_failure(&mut self, reason: AuthFailureReason) {
+        let count = &mut self.auth_failures[reason.index()];
+        *count = count.wrapping_add(1);
+    }
+
+    fn add_throttled_request(&mut self, reason: ThrottleReason) {
+        let count = &mut self.throttled_requests[reason.index()];
+        *count = count.wrapping_add(1);
+    }
 }
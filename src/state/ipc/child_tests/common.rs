@@ -1,2 +1,3 @@
 pub use crate::state::ipc::child::*;
+pub use crate::state::ipc::MetricsFormat;
 pub use crate::state::ipc::VERSION_BYTES;
@@ -141,3 +141,298 @@ fn processes_single_track_request() {
         DecoderRequest::new(DecoderRequest::NO_FLAGS, 1)
     );
 }
+
+#[test]
+fn processes_observations_then_track_request() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x06,
+        // Duration
+        0xA0, 0x86, 0x01, 0x00,
+        // Operation ID
+        0x07,
+        // Duration
+        0x10, 0x27, 0x00, 0x00,
+        // Operation ID
+        0x02,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+
+    assert_eq!(
+        D.lock().take_request(),
+        DecoderRequest::new(DecoderRequest::NO_FLAGS, 1)
+            .with_observation(ScrapeHistogram::ScrapeDuration, 100_000)
+            .with_observation(ScrapeHistogram::IpcDuration, 10_000)
+    );
+    assert_eq!(
+        D.lock().take_request(),
+        DecoderRequest::new(DecoderRequest::NO_FLAGS, 0)
+    );
+}
+
+#[test]
+fn processes_responses_auth_failures_and_throttled_requests() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x02,
+        // Operation ID
+        0x09,
+        // Operation ID
+        0x08,
+        // Status (401)
+        0x91, 0x01, 0x00, 0x00,
+        // Operation ID
+        0x0B,
+        // Operation ID
+        0x08,
+        // Status (403)
+        0x93, 0x01, 0x00, 0x00,
+        // Operation ID
+        0x0C,
+        // Operation ID
+        0x08,
+        // Status (403)
+        0x93, 0x01, 0x00, 0x00,
+        // Operation ID
+        0x0D,
+        // Operation ID
+        0x08,
+        // Status (429)
+        0xAD, 0x01, 0x00, 0x00,
+        // Operation ID
+        0x11,
+        // Operation ID
+        0x08,
+        // Status (429)
+        0xAD, 0x01, 0x00, 0x00,
+        // Operation ID
+        0x12,
+        // Operation ID
+        0x08,
+        // Status (429)
+        0xAD, 0x01, 0x00, 0x00,
+        // Operation ID
+        0x08,
+        // Status (418, unknown so dropped)
+        0xA2, 0x01, 0x00, 0x00,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+
+    assert_eq!(
+        D.lock().take_request(),
+        DecoderRequest::new(DecoderRequest::NO_FLAGS, 1)
+            .with_responses(401, 1)
+            .with_responses(403, 2)
+            .with_responses(429, 3)
+            .with_auth_failures(AuthFailureReason::MissingHeader, 1)
+            .with_auth_failures(AuthFailureReason::WrongUser, 1)
+            .with_auth_failures(AuthFailureReason::UnknownKey, 1)
+            .with_throttled_requests(ThrottleReason::Address, 1)
+            .with_throttled_requests(ThrottleReason::Key, 1)
+            .with_throttled_requests(ThrottleReason::Lockout, 1)
+    );
+    assert_eq!(
+        D.lock().take_request(),
+        DecoderRequest::new(DecoderRequest::NO_FLAGS, 0)
+    );
+}
+
+#[test]
+fn processes_tls_handshakes() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x10,
+        // Cipher suite (TLS_AES_128_GCM_SHA256)
+        0x01, 0x13, 0x00, 0x00,
+        // Operation ID
+        0x10,
+        // Cipher suite (TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256)
+        0x2F, 0xC0, 0x00, 0x00,
+        // Operation ID
+        0x10,
+        // Cipher suite (TLS_AES_128_GCM_SHA256)
+        0x01, 0x13, 0x00, 0x00,
+        // Operation ID
+        0x10,
+        // Cipher suite (TLS_RSA_WITH_AES_128_GCM_SHA256, unknown so dropped)
+        0x9C, 0x00, 0x00, 0x00,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+
+    assert_eq!(
+        D.lock().take_request(),
+        DecoderRequest::new(DecoderRequest::NO_FLAGS, 0)
+            .with_tls_handshakes(TlsCipherSuite::Tls13Aes128GcmSha256, 2)
+            .with_tls_handshakes(TlsCipherSuite::EcdheRsaAes128GcmSha256, 1)
+    );
+    assert_eq!(
+        D.lock().take_request(),
+        DecoderRequest::new(DecoderRequest::NO_FLAGS, 0)
+    );
+}
+
+#[test]
+fn processes_key_requests() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x02,
+        // Operation ID
+        0x0E,
+        // Name (length: 5)
+        0x05,
+        b'a', b'g', b'e', b'n', b't',
+        // Operation ID
+        0x02,
+        // Operation ID
+        0x0E,
+        // Name (length: 2)
+        0x02,
+        b'c', b'i',
+        // Operation ID
+        0x0E,
+        // Name (length: 5)
+        0x05,
+        b'a', b'g', b'e', b'n', b't',
+        // Operation ID
+        0x00,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+    assert_eq!(
+        D.lock().take_request(),
+        DecoderRequest::new(DecoderRequest::METRICS_REQUESTED, 2)
+            .with_key_requests(b"agent", 2)
+            .with_key_requests(b"ci", 1)
+    );
+    assert_eq!(
+        D.lock().take_request(),
+        DecoderRequest::new(DecoderRequest::NO_FLAGS, 0)
+    );
+}
+
+#[test]
+#[should_panic = "Key name has an invalid length."]
+fn panics_on_empty_key_request_name() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x0E,
+        // Name (length: 0)
+        0x00,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+}
+
+#[test]
+fn processes_scoped_metrics_requests() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x0F,
+        // ID (1)
+        0x01, 0x00, 0x00, 0x00,
+        // Scope (length: 10)
+        0x0A, 0x00, 0x00, 0x00,
+        b's', b'e', b'r', b'v', b'i', b'c', b'e', b's', b'=', b'a',
+        // Format (OpenMetrics)
+        0x00,
+        // Operation ID
+        0x0F,
+        // ID (258)
+        0x02, 0x01, 0x00, 0x00,
+        // Scope (length: 8)
+        0x08, 0x00, 0x00, 0x00,
+        b'u', b's', b'e', b'r', b's', b'=', b'c', b'i',
+        // Format (protobuf)
+        0x04,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+    assert_eq!(
+        D.lock().take_request(),
+        DecoderRequest::new(DecoderRequest::NO_FLAGS, 0)
+            .with_scoped_metrics(1, MetricsFormat::OpenMetrics, b"services=a")
+            .with_scoped_metrics(258, MetricsFormat::Protobuf, b"users=ci")
+    );
+    assert_eq!(
+        D.lock().take_request(),
+        DecoderRequest::new(DecoderRequest::NO_FLAGS, 0)
+    );
+}
+
+#[test]
+#[should_panic = "Invalid key scope."]
+fn panics_on_invalid_scoped_metrics_scope() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x0F,
+        // ID (1)
+        0x01, 0x00, 0x00, 0x00,
+        // Scope (length: 6)
+        0x06, 0x00, 0x00, 0x00,
+        b'g', b'r', b'o', b'u', b'p', b's',
+        // Format (OpenMetrics)
+        0x00,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+}
+
+#[test]
+#[should_panic = "Unknown IPC byte '01'"]
+fn panics_on_invalid_scoped_metrics_format() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x0F,
+        // ID (1)
+        0x01, 0x00, 0x00, 0x00,
+        // Scope (length: 0)
+        0x00, 0x00, 0x00, 0x00,
+        // Format (invalid)
+        0x01,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+}
@@ -68,6 +68,73 @@ impl KeyAccumulator {
             }
         }
     }
+
+    // `value` is the salt followed by the digest.
+    pub fn push_hashed(&mut self, value: &[u8]) {
+        match self.remaining.checked_sub(1) {
+            None => panic!("Overflowed slice buffer!"),
+            Some(remaining) => self.remaining = remaining,
+        }
+
+        if let Some(data) = &mut self.data {
+            let (salt, digest) = value.split_at(KEY_SALT_LEN);
+            let hash = KeyHash {
+                salt: salt.try_into().unwrap(),
+                digest: digest.try_into().unwrap(),
+            };
+            match data.push_hashed(&hash) {
+                KeyPushResult::Success => {}
+                // The set was reserved up front with room for every key.
+                KeyPushResult::Invalid | KeyPushResult::TooManyKeys => unreachable!(),
+            }
+        }
+    }
+
+    pub fn push_keyless(&mut self) {
+        match self.remaining.checked_sub(1) {
+            None => panic!("Overflowed slice buffer!"),
+            Some(remaining) => self.remaining = remaining,
+        }
+
+        if let Some(data) = &mut self.data {
+            match data.push_keyless() {
+                KeyPushResult::Success => {}
+                // The set was reserved up front with room for every key.
+                KeyPushResult::Invalid | KeyPushResult::TooManyKeys => unreachable!(),
+            }
+        }
+    }
+
+    // These apply to the key last pushed via `push_raw`, `push_hashed`, or `push_keyless`.
+
+    pub fn set_name(&mut self, name: &[u8]) {
+        if let Some(data) = &mut self.data {
+            data.set_last_name(name);
+        }
+    }
+
+    pub fn set_user(&mut self, user: &[u8]) {
+        if let Some(data) = &mut self.data {
+            data.set_last_user(user);
+        }
+    }
+
+    pub fn set_scope(&mut self, scope: &[u8]) {
+        if let Some(data) = &mut self.data {
+            data.set_last_scope(scope);
+        }
+    }
+
+    pub fn set_client_cn(&mut self, client_cn: &[u8]) {
+        if let Some(data) = &mut self.data {
+            data.set_last_client_cn(client_cn);
+        }
+    }
+
+    // Drops the keys read so far, so the whole set is reported as failing to allocate.
+    pub fn set_allocation_failed(&mut self) {
+        self.data = None;
+    }
 }
 
 #[derive(Debug)]
@@ -145,10 +212,6 @@ impl<'a> ReadIter<'a> {
         ReadIter { buf }
     }
 
-    pub fn remaining(self) -> &'a [u8] {
-        self.buf
-    }
-
     pub fn next(&mut self) -> Option<u8> {
         let (&value, tail) = self.buf.split_first()?;
         self.buf = tail;
@@ -0,0 +1,32 @@
+// What the parent knows about the exporter's health, sent to the child for `/readyz`. The child
+// checks its own key set on top of this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthStatus {
+    // The journal loop has checked for new entries recently.
+    pub journal_active: bool,
+    // The child hasn't exited or failed to spawn recently.
+    pub child_stable: bool,
+}
+
+const JOURNAL_ACTIVE: u8 = 1 << 0;
+const CHILD_STABLE: u8 = 1 << 1;
+
+impl HealthStatus {
+    pub const fn to_byte(self) -> u8 {
+        let mut result = 0;
+        if self.journal_active {
+            result |= JOURNAL_ACTIVE;
+        }
+        if self.child_stable {
+            result |= CHILD_STABLE;
+        }
+        result
+    }
+
+    pub const fn from_byte(byte: u8) -> Self {
+        Self {
+            journal_active: (byte & JOURNAL_ACTIVE) != 0,
+            child_stable: (byte & CHILD_STABLE) != 0,
+        }
+    }
+}
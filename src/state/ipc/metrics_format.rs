@@ -0,0 +1,24 @@
+// The exposition formats metrics can be requested in. The child picks one per request based on
+// the `Accept` header, and the parent renders each one requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsFormat {
+    OpenMetrics,
+    PrometheusText,
+    Protobuf,
+}
+
+impl MetricsFormat {
+    pub const ALL: [MetricsFormat; 3] = [
+        MetricsFormat::OpenMetrics,
+        MetricsFormat::PrometheusText,
+        MetricsFormat::Protobuf,
+    ];
+
+    pub const fn content_type(self) -> &'static [u8] {
+        match self {
+            MetricsFormat::OpenMetrics => b"application/openmetrics-text; version=1.0.0; charset=utf-8",
+            MetricsFormat::PrometheusText => b"text/plain; version=0.0.4; charset=utf-8",
+            MetricsFormat::Protobuf => b"application/vnd.google.protobuf; proto=io.prometheus.client.MetricFamily; encoding=delimited",
+        }
+    }
+}
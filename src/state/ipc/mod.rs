@@ -1,13 +1,21 @@
 pub mod child;
 pub mod common;
+mod health_status;
+mod metrics_format;
 pub mod parent;
+pub mod push;
 
 #[cfg(test)]
 mod child_tests;
 #[cfg(test)]
 mod parent_tests;
 #[cfg(test)]
+mod push_tests;
+#[cfg(test)]
 mod read_phase_tests;
 
+pub use health_status::HealthStatus;
+pub use metrics_format::MetricsFormat;
+
 pub const VERSION: u32 = 0;
 pub static VERSION_BYTES: [u8; 4] = VERSION.to_le_bytes();
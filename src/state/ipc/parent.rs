@@ -5,8 +5,44 @@ Warning: this module interacts very closely with `crate::state` and its children
 use crate::prelude::*;
 
 use super::common::*;
+use super::HealthStatus;
+use super::MetricsFormat;
 
 pub const METRICS_RESPONSE_HEADER: &[u8] = &[0x00, 0, 0, 0, 0];
+pub const TEXT_METRICS_RESPONSE_HEADER: &[u8] = &[0x02, 0, 0, 0, 0];
+pub const PROTOBUF_METRICS_RESPONSE_HEADER: &[u8] = &[0x03, 0, 0, 0, 0];
+
+pub const fn metrics_response_header(format: MetricsFormat) -> &'static [u8] {
+    match format {
+        MetricsFormat::OpenMetrics => METRICS_RESPONSE_HEADER,
+        MetricsFormat::PrometheusText => TEXT_METRICS_RESPONSE_HEADER,
+        MetricsFormat::Protobuf => PROTOBUF_METRICS_RESPONSE_HEADER,
+    }
+}
+
+pub const fn health_response_bytes(status: HealthStatus) -> [u8; 2] {
+    [0x04, status.to_byte()]
+}
+
+/// Sent immediately before a metrics response rendered for a scoped request, with `id` being the
+/// ID the child sent with that request.
+pub const fn scoped_metrics_response_prefix(id: u32) -> [u8; 5] {
+    let [a, b, c, d] = id.to_le_bytes();
+    [0x05, a, b, c, d]
+}
+
+/// Sent whenever the HTTPS certificate, private key, or OCSP response is updated, with the first
+/// two still PEM-encoded. An empty OCSP response means there's none to staple.
+pub fn receive_tls_certificate_bytes(pair: &TlsCertificatePair) -> Box<[u8]> {
+    let ocsp_response = pair.ocsp_response.as_deref().unwrap_or_default();
+    let mut buf = Vec::new();
+    buf.push(0x06);
+    for part in [&*pair.certificate, &*pair.private_key, ocsp_response] {
+        buf.extend_from_slice(&truncate_usize_u32(part.len()).to_le_bytes());
+        buf.extend_from_slice(part);
+    }
+    buf.into()
+}
 
 pub fn finish_response_metrics(buf: &mut [u8]) {
     let len = buf.len().checked_sub(5).expect("buffer not initialized");
@@ -17,17 +53,61 @@ pub fn finish_response_metrics(buf: &mut [u8]) {
     buf[4] = d;
 }
 
+// Set in a key's length byte if it's followed by the key's name, username, scope, and client
+// certificate common name. Keys without any of those are sent without them, as they're by far the
+// common case.
+const KEY_HAS_IDENTITY: u8 = 0x80;
+// Sent as the key's length if it's hashed, with its salt and digest in place of the key. Keys are
+// never empty, so it's otherwise unused.
+const KEY_IS_HASHED: u8 = 0;
+// Sent as the key's length if there's no key at all, only a client certificate common name. It's
+// followed by nothing, as there's nothing to send. Keys are never this long, so it's otherwise
+// unused.
+const KEY_IS_KEYLESS: u8 = 0x7F;
+
 pub fn receive_key_set_bytes(key_set: KeySet) -> Box<[u8]> {
-    let key_set = key_set.insecure_view_keys();
-    debug_assert!(key_set.len() <= zero_extend_u8_usize(u8::MAX));
+    let keys = key_set.insecure_view_keys();
+    debug_assert!(keys.len() <= zero_extend_u8_usize(u8::MAX));
     let mut buf = Vec::new();
-    buf.extend_from_slice(&[0x01, truncate_usize_u8(key_set.len())]);
+    buf.extend_from_slice(&[0x01, truncate_usize_u8(keys.len())]);
 
-    for key in key_set.iter() {
+    let identities = key_set.scopes().iter().zip(key_set.client_cns().iter());
+    for (key, (scope, client_cn)) in keys.iter().zip(identities) {
         let key_value = key.insecure_get_value();
-        debug_assert!(key_value.len() <= zero_extend_u8_usize(u8::MAX));
-        buf.push(truncate_usize_u8(key_value.len()));
-        buf.extend_from_slice(key.insecure_get_value());
+        debug_assert!(key_value.len() <= MAX_KEY_LEN);
+        debug_assert!(scope.len() <= MAX_KEY_SCOPE_LEN);
+        debug_assert!(client_cn.len() <= MAX_KEY_CLIENT_CN_LEN);
+
+        let has_identity = !key.name().is_empty()
+            || key.user() != DEFAULT_KEY_USER
+            || !scope.is_empty()
+            || !client_cn.is_empty();
+
+        let identity_flag = if has_identity { KEY_HAS_IDENTITY } else { 0 };
+
+        match key.hash() {
+            Some(hash) => {
+                buf.push(KEY_IS_HASHED | identity_flag);
+                buf.extend_from_slice(&hash.salt);
+                buf.extend_from_slice(&hash.digest);
+            }
+            None if key_value.is_empty() => buf.push(KEY_IS_KEYLESS | identity_flag),
+            None => {
+                buf.push(truncate_usize_u8(key_value.len()) | identity_flag);
+                buf.extend_from_slice(key_value);
+            }
+        }
+
+        if has_identity {
+            buf.push(truncate_usize_u8(key.name().len()));
+            buf.extend_from_slice(key.name());
+            buf.push(truncate_usize_u8(key.user().len()));
+            buf.extend_from_slice(key.user());
+            buf.extend_from_slice(&truncate_usize_u32(scope.len()).to_le_bytes());
+            buf.extend_from_slice(scope);
+            buf.push(truncate_usize_u8(client_cn.len()));
+            buf.extend_from_slice(client_cn);
+        }
     }
 
     buf.into()
@@ -38,11 +118,28 @@ enum DecoderState {
     Locked,
     Version,
     Start,
-    ResponseMetrics,
-    ResponseMetricsExpectBody,
+    ResponseMetrics(MetricsFormat),
+    ResponseMetricsExpectBody(MetricsFormat),
+    ResponseHealth,
     ReceiveKeySet,
     ReceiveKeySetExpectEntry,
-    ReceiveKeySetExpectKey,
+    ReceiveKeySetExpectKey { has_identity: bool, hashed: bool },
+    ReceiveKeySetExpectNameLen,
+    ReceiveKeySetExpectName,
+    ReceiveKeySetExpectUserLen,
+    ReceiveKeySetExpectUser,
+    ReceiveKeySetExpectScopeLen,
+    ReceiveKeySetExpectScope,
+    ReceiveKeySetExpectClientCnLen,
+    ReceiveKeySetExpectClientCn,
+    ResponseScopedMetrics,
+    ResponseScopedMetricsExpectResponse,
+    ReceiveTlsCertificate,
+    ReceiveTlsCertificateExpectBody,
+    ReceiveTlsPrivateKey,
+    ReceiveTlsPrivateKeyExpectBody,
+    ReceiveTlsOcspResponse,
+    ReceiveTlsOcspResponseExpectBody,
 }
 
 #[must_use]
@@ -53,24 +150,95 @@ pub enum ResponseItem<T> {
     Some(T),
 }
 
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct TlsCertificatePair {
+    pub certificate: Box<[u8]>,
+    pub private_key: Box<[u8]>,
+    // DER-encoded, as it's stapled as-is.
+    pub ocsp_response: Option<Box<[u8]>>,
+}
+
+impl fmt::Debug for TlsCertificatePair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsCertificatePair")
+            .field("certificate", &BinaryToDebug(&self.certificate))
+            // Keep it out of any logs.
+            .field("private_key", &"<redacted>")
+            .field(
+                "ocsp_response",
+                &self.ocsp_response.as_deref().map(BinaryToDebug),
+            )
+            .finish()
+    }
+}
+
+impl Drop for TlsCertificatePair {
+    fn drop(&mut self) {
+        secure_clear_bytes(&mut self.private_key);
+    }
+}
+
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct DecoderResponse {
     pub key_set: ResponseItem<KeySet>,
     pub metrics: ResponseItem<Box<[u8]>>,
+    pub text_metrics: ResponseItem<Box<[u8]>>,
+    pub protobuf_metrics: ResponseItem<Box<[u8]>>,
+    pub health: Option<HealthStatus>,
+    // Responses to scoped metrics requests, by the IDs they were requested with. Their formats
+    // are left out, as the child already knows what it asked for.
+    pub scoped_metrics: Vec<(u32, ResponseItem<Box<[u8]>>)>,
+    pub tls_certificate: ResponseItem<TlsCertificatePair>,
+}
+
+impl DecoderResponse {
+    const EMPTY: DecoderResponse = DecoderResponse {
+        key_set: ResponseItem::None,
+        metrics: ResponseItem::None,
+        text_metrics: ResponseItem::None,
+        protobuf_metrics: ResponseItem::None,
+        health: None,
+        scoped_metrics: Vec::new(),
+        tls_certificate: ResponseItem::None,
+    };
+
+    pub fn metrics_mut(&mut self, format: MetricsFormat) -> &mut ResponseItem<Box<[u8]>> {
+        match format {
+            MetricsFormat::OpenMetrics => &mut self.metrics,
+            MetricsFormat::PrometheusText => &mut self.text_metrics,
+            MetricsFormat::Protobuf => &mut self.protobuf_metrics,
+        }
+    }
 }
 
 impl fmt::Debug for DecoderResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn metrics_to_debug(metrics: &ResponseItem<Box<[u8]>>) -> ResponseItem<BinaryToDebug<'_>> {
+            match metrics {
+                ResponseItem::None => ResponseItem::None,
+                ResponseItem::AllocationFailed => ResponseItem::AllocationFailed,
+                ResponseItem::Some(vec) => ResponseItem::Some(BinaryToDebug(vec)),
+            }
+        }
+
         f.debug_struct("DecoderResponse")
             .field("key_set", &self.key_set)
+            .field("metrics", &metrics_to_debug(&self.metrics))
+            .field("text_metrics", &metrics_to_debug(&self.text_metrics))
             .field(
-                "metrics",
-                &match &self.metrics {
-                    ResponseItem::None => ResponseItem::None,
-                    ResponseItem::AllocationFailed => ResponseItem::AllocationFailed,
-                    ResponseItem::Some(vec) => ResponseItem::Some(BinaryToDebug(vec)),
-                },
+                "protobuf_metrics",
+                &metrics_to_debug(&self.protobuf_metrics),
             )
+            .field("health", &self.health)
+            .field(
+                "scoped_metrics",
+                &Vec::from_iter(
+                    self.scoped_metrics
+                        .iter()
+                        .map(|(id, metrics)| (id, metrics_to_debug(metrics))),
+                ),
+            )
+            .field("tls_certificate", &self.tls_certificate)
             .finish()
     }
 }
@@ -81,6 +249,13 @@ pub struct Decoder {
     response: DecoderResponse,
     byte_acc: Option<ByteAccumulator>,
     key_acc: Option<KeyAccumulator>,
+    // Set if the metrics response being read is for a scoped request.
+    scoped_id: Option<u32>,
+    // The certificate already read, held onto while the private key after it is read. It's `None`
+    // if it failed to allocate.
+    tls_certificate: Option<Box<[u8]>>,
+    // Same, but for the certificate and private key both while the OCSP response is read.
+    tls_pair: Option<TlsCertificatePair>,
 }
 
 impl Decoder {
@@ -88,23 +263,17 @@ impl Decoder {
         Self {
             state: DecoderState::Version,
             read_phase: ReadPhase::new(),
-            response: DecoderResponse {
-                key_set: ResponseItem::None,
-                metrics: ResponseItem::None,
-            },
+            response: DecoderResponse::EMPTY,
             byte_acc: None,
             key_acc: None,
+            scoped_id: None,
+            tls_certificate: None,
+            tls_pair: None,
         }
     }
 
     pub fn take_response(&mut self) -> DecoderResponse {
-        replace(
-            &mut self.response,
-            DecoderResponse {
-                key_set: ResponseItem::None,
-                metrics: ResponseItem::None,
-            },
-        )
+        replace(&mut self.response, DecoderResponse::EMPTY)
     }
 
     pub fn read_bytes(&mut self, buf: &[u8]) {
@@ -123,38 +292,163 @@ impl Decoder {
 
                 DecoderState::Start => match iter.next() {
                     None => break DecoderState::Start,
-                    Some(0) => state = DecoderState::ResponseMetrics,
+                    Some(0) => state = DecoderState::ResponseMetrics(MetricsFormat::OpenMetrics),
                     Some(1) => state = DecoderState::ReceiveKeySet,
+                    Some(2) => state = DecoderState::ResponseMetrics(MetricsFormat::PrometheusText),
+                    Some(3) => state = DecoderState::ResponseMetrics(MetricsFormat::Protobuf),
+                    Some(4) => state = DecoderState::ResponseHealth,
+                    Some(5) => state = DecoderState::ResponseScopedMetrics,
+                    Some(6) => state = DecoderState::ReceiveTlsCertificate,
                     Some(byte) => unknown_byte(byte),
                 },
 
-                DecoderState::ResponseMetrics => match iter.phase_next_32(&mut self.read_phase) {
-                    None => break DecoderState::ResponseMetrics,
-                    Some(len) => {
-                        self.byte_acc = Some(ByteAccumulator::new(len));
-                        state = DecoderState::ResponseMetricsExpectBody;
+                DecoderState::ResponseMetrics(format) => {
+                    match iter.phase_next_32(&mut self.read_phase) {
+                        None => break DecoderState::ResponseMetrics(format),
+                        Some(len) => {
+                            self.byte_acc = Some(ByteAccumulator::new(len));
+                            state = DecoderState::ResponseMetricsExpectBody(format);
+                        }
                     }
-                },
+                }
 
-                DecoderState::ResponseMetricsExpectBody => {
+                DecoderState::ResponseMetricsExpectBody(format) => {
                     let byte_acc = self.byte_acc.as_mut().unwrap();
                     if byte_acc.has_remaining() {
-                        state = DecoderState::ResponseMetricsExpectBody;
+                        state = DecoderState::ResponseMetricsExpectBody(format);
                         if !byte_acc.push_from_iter(&mut iter) {
                             break state;
                         }
                     } else {
-                        self.response.metrics = match self.byte_acc.take() {
+                        let metrics = match self.byte_acc.take() {
                             None => ResponseItem::None,
                             Some(response) => match response.finish() {
                                 None => ResponseItem::AllocationFailed,
                                 Some(metrics_data) => ResponseItem::Some(metrics_data),
                             },
                         };
+                        match self.scoped_id.take() {
+                            None => *self.response.metrics_mut(format) = metrics,
+                            Some(id) => self.response.scoped_metrics.push((id, metrics)),
+                        }
                         state = DecoderState::Start;
                     }
                 }
 
+                DecoderState::ResponseScopedMetrics => {
+                    match iter.phase_next_32(&mut self.read_phase) {
+                        None => break DecoderState::ResponseScopedMetrics,
+                        Some(id) => {
+                            self.scoped_id = Some(id);
+                            state = DecoderState::ResponseScopedMetricsExpectResponse;
+                        }
+                    }
+                }
+
+                // The response itself always comes right after.
+                DecoderState::ResponseScopedMetricsExpectResponse => match iter.next() {
+                    None => break DecoderState::ResponseScopedMetricsExpectResponse,
+                    Some(0) => state = DecoderState::ResponseMetrics(MetricsFormat::OpenMetrics),
+                    Some(2) => state = DecoderState::ResponseMetrics(MetricsFormat::PrometheusText),
+                    Some(3) => state = DecoderState::ResponseMetrics(MetricsFormat::Protobuf),
+                    Some(byte) => unknown_byte(byte),
+                },
+
+                DecoderState::ReceiveTlsCertificate => {
+                    match iter.phase_next_32(&mut self.read_phase) {
+                        None => break DecoderState::ReceiveTlsCertificate,
+                        Some(len) => {
+                            self.byte_acc = Some(ByteAccumulator::new(len));
+                            state = DecoderState::ReceiveTlsCertificateExpectBody;
+                        }
+                    }
+                }
+
+                DecoderState::ReceiveTlsCertificateExpectBody => {
+                    let byte_acc = self.byte_acc.as_mut().unwrap();
+                    if byte_acc.has_remaining() {
+                        state = DecoderState::ReceiveTlsCertificateExpectBody;
+                        if !byte_acc.push_from_iter(&mut iter) {
+                            break state;
+                        }
+                    } else {
+                        self.tls_certificate = self.byte_acc.take().and_then(|c| c.finish());
+                        state = DecoderState::ReceiveTlsPrivateKey;
+                    }
+                }
+
+                DecoderState::ReceiveTlsPrivateKey => {
+                    match iter.phase_next_32(&mut self.read_phase) {
+                        None => break DecoderState::ReceiveTlsPrivateKey,
+                        Some(len) => {
+                            self.byte_acc = Some(ByteAccumulator::new(len));
+                            state = DecoderState::ReceiveTlsPrivateKeyExpectBody;
+                        }
+                    }
+                }
+
+                DecoderState::ReceiveTlsPrivateKeyExpectBody => {
+                    let byte_acc = self.byte_acc.as_mut().unwrap();
+                    if byte_acc.has_remaining() {
+                        state = DecoderState::ReceiveTlsPrivateKeyExpectBody;
+                        if !byte_acc.push_from_iter(&mut iter) {
+                            break state;
+                        }
+                    } else {
+                        let private_key = self.byte_acc.take().and_then(|k| k.finish());
+                        self.tls_pair = match (self.tls_certificate.take(), private_key) {
+                            (Some(certificate), Some(private_key)) => Some(TlsCertificatePair {
+                                certificate,
+                                private_key,
+                                ocsp_response: None,
+                            }),
+                            _ => None,
+                        };
+                        state = DecoderState::ReceiveTlsOcspResponse;
+                    }
+                }
+
+                DecoderState::ReceiveTlsOcspResponse => {
+                    match iter.phase_next_32(&mut self.read_phase) {
+                        None => break DecoderState::ReceiveTlsOcspResponse,
+                        Some(len) => {
+                            self.byte_acc = Some(ByteAccumulator::new(len));
+                            state = DecoderState::ReceiveTlsOcspResponseExpectBody;
+                        }
+                    }
+                }
+
+                DecoderState::ReceiveTlsOcspResponseExpectBody => {
+                    let byte_acc = self.byte_acc.as_mut().unwrap();
+                    if byte_acc.has_remaining() {
+                        state = DecoderState::ReceiveTlsOcspResponseExpectBody;
+                        if !byte_acc.push_from_iter(&mut iter) {
+                            break state;
+                        }
+                    } else {
+                        let ocsp_response = self.byte_acc.take().and_then(|r| r.finish());
+                        self.response.tls_certificate = match (self.tls_pair.take(), ocsp_response)
+                        {
+                            (Some(mut pair), Some(ocsp_response)) => {
+                                if !ocsp_response.is_empty() {
+                                    pair.ocsp_response = Some(ocsp_response);
+                                }
+                                ResponseItem::Some(pair)
+                            }
+                            _ => ResponseItem::AllocationFailed,
+                        };
+                        state = DecoderState::Start;
+                    }
+                }
+
+                DecoderState::ResponseHealth => match iter.next() {
+                    None => break DecoderState::ResponseHealth,
+                    Some(byte) => {
+                        self.response.health = Some(HealthStatus::from_byte(byte));
+                        state = DecoderState::Start;
+                    }
+                },
+
                 DecoderState::ReceiveKeySet => match iter.next() {
                     None => break DecoderState::ReceiveKeySet,
                     Some(len) => {
@@ -169,11 +463,29 @@ impl Decoder {
                         match iter.next() {
                             None => break DecoderState::ReceiveKeySetExpectEntry,
                             Some(len) => {
-                                if len > truncate_usize_u8(MAX_KEY_LEN) {
+                                let has_identity = (len & KEY_HAS_IDENTITY) != 0;
+                                let len = len & !KEY_HAS_IDENTITY;
+                                if len == KEY_IS_KEYLESS {
+                                    key_acc.push_keyless();
+                                    state = if has_identity {
+                                        DecoderState::ReceiveKeySetExpectNameLen
+                                    } else {
+                                        DecoderState::ReceiveKeySetExpectEntry
+                                    };
+                                } else if len > truncate_usize_u8(MAX_KEY_LEN) {
                                     std::panic::panic_any("Key entry too long.");
+                                } else {
+                                    let hashed = len == KEY_IS_HASHED;
+                                    self.byte_acc = Some(ByteAccumulator::new(if hashed {
+                                        truncate_usize_u32(KEY_HASH_LEN)
+                                    } else {
+                                        zero_extend_u8_u32(len)
+                                    }));
+                                    state = DecoderState::ReceiveKeySetExpectKey {
+                                        has_identity,
+                                        hashed,
+                                    };
                                 }
-                                self.byte_acc = Some(ByteAccumulator::new(zero_extend_u8_u32(len)));
-                                state = DecoderState::ReceiveKeySetExpectKey;
                             }
                         }
                     } else {
@@ -188,10 +500,134 @@ impl Decoder {
                     }
                 }
 
-                DecoderState::ReceiveKeySetExpectKey => {
+                DecoderState::ReceiveKeySetExpectKey {
+                    has_identity,
+                    hashed,
+                } => {
+                    let byte_acc = self.byte_acc.as_mut().unwrap();
+                    if byte_acc.has_remaining() {
+                        state = DecoderState::ReceiveKeySetExpectKey {
+                            has_identity,
+                            hashed,
+                        };
+                        if !byte_acc.push_from_iter(&mut iter) {
+                            break state;
+                        }
+                    } else {
+                        let key_acc = self.key_acc.as_mut().unwrap();
+                        if hashed {
+                            key_acc.push_hashed(byte_acc.initialized());
+                        } else {
+                            key_acc.push_raw(byte_acc.initialized());
+                        }
+                        state = if has_identity {
+                            DecoderState::ReceiveKeySetExpectNameLen
+                        } else {
+                            DecoderState::ReceiveKeySetExpectEntry
+                        };
+                    }
+                }
+
+                DecoderState::ReceiveKeySetExpectNameLen => match iter.next() {
+                    None => break DecoderState::ReceiveKeySetExpectNameLen,
+                    Some(len) => {
+                        if len > truncate_usize_u8(MAX_KEY_NAME_LEN) {
+                            std::panic::panic_any("Key name too long.");
+                        }
+                        self.byte_acc = Some(ByteAccumulator::new(zero_extend_u8_u32(len)));
+                        state = DecoderState::ReceiveKeySetExpectName;
+                    }
+                },
+
+                DecoderState::ReceiveKeySetExpectName => {
+                    let byte_acc = self.byte_acc.as_mut().unwrap();
+                    if byte_acc.has_remaining() {
+                        state = DecoderState::ReceiveKeySetExpectName;
+                        if !byte_acc.push_from_iter(&mut iter) {
+                            break state;
+                        }
+                    } else {
+                        self.key_acc
+                            .as_mut()
+                            .unwrap()
+                            .set_name(byte_acc.initialized());
+                        state = DecoderState::ReceiveKeySetExpectUserLen;
+                    }
+                }
+
+                DecoderState::ReceiveKeySetExpectUserLen => match iter.next() {
+                    None => break DecoderState::ReceiveKeySetExpectUserLen,
+                    Some(len) => {
+                        if len == 0 || len > truncate_usize_u8(MAX_KEY_USER_LEN) {
+                            std::panic::panic_any("Key username has an invalid length.");
+                        }
+                        self.byte_acc = Some(ByteAccumulator::new(zero_extend_u8_u32(len)));
+                        state = DecoderState::ReceiveKeySetExpectUser;
+                    }
+                },
+
+                DecoderState::ReceiveKeySetExpectUser => {
+                    let byte_acc = self.byte_acc.as_mut().unwrap();
+                    if byte_acc.has_remaining() {
+                        state = DecoderState::ReceiveKeySetExpectUser;
+                        if !byte_acc.push_from_iter(&mut iter) {
+                            break state;
+                        }
+                    } else {
+                        self.key_acc
+                            .as_mut()
+                            .unwrap()
+                            .set_user(byte_acc.initialized());
+                        state = DecoderState::ReceiveKeySetExpectScopeLen;
+                    }
+                }
+
+                DecoderState::ReceiveKeySetExpectScopeLen => {
+                    match iter.phase_next_32(&mut self.read_phase) {
+                        None => break DecoderState::ReceiveKeySetExpectScopeLen,
+                        Some(len) => {
+                            if zero_extend_u32_usize(len) > MAX_KEY_SCOPE_LEN {
+                                std::panic::panic_any("Key scope too long.");
+                            }
+                            self.byte_acc = Some(ByteAccumulator::new(len));
+                            state = DecoderState::ReceiveKeySetExpectScope;
+                        }
+                    }
+                }
+
+                DecoderState::ReceiveKeySetExpectScope => {
+                    let byte_acc = self.byte_acc.as_mut().unwrap();
+                    if byte_acc.has_remaining() {
+                        state = DecoderState::ReceiveKeySetExpectScope;
+                        if !byte_acc.push_from_iter(&mut iter) {
+                            break state;
+                        }
+                    } else {
+                        let key_acc = self.key_acc.as_mut().unwrap();
+                        match self.byte_acc.take().and_then(|scope| scope.finish()) {
+                            Some(scope) => key_acc.set_scope(&scope),
+                            // Don't let the key through unscoped.
+                            None => key_acc.set_allocation_failed(),
+                        }
+                        state = DecoderState::ReceiveKeySetExpectClientCnLen;
+                    }
+                }
+
+                DecoderState::ReceiveKeySetExpectClientCnLen => match iter.next() {
+                    None => break DecoderState::ReceiveKeySetExpectClientCnLen,
+                    Some(len) => {
+                        if len > truncate_usize_u8(MAX_KEY_CLIENT_CN_LEN) {
+                            std::panic::panic_any("Key client common name too long.");
+                        }
+                        self.byte_acc = Some(ByteAccumulator::new(zero_extend_u8_u32(len)));
+                        state = DecoderState::ReceiveKeySetExpectClientCn;
+                    }
+                },
+
+                DecoderState::ReceiveKeySetExpectClientCn => {
                     let byte_acc = self.byte_acc.as_mut().unwrap();
                     if byte_acc.has_remaining() {
-                        state = DecoderState::ReceiveKeySetExpectKey;
+                        state = DecoderState::ReceiveKeySetExpectClientCn;
                         if !byte_acc.push_from_iter(&mut iter) {
                             break state;
                         }
@@ -199,7 +635,7 @@ impl Decoder {
                         self.key_acc
                             .as_mut()
                             .unwrap()
-                            .push_raw(byte_acc.initialized());
+                            .set_client_cn(byte_acc.initialized());
                         state = DecoderState::ReceiveKeySetExpectEntry;
                     }
                 }
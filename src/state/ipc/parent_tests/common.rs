@@ -1,4 +1,5 @@
 pub use crate::state::ipc::parent::*;
+pub use crate::state::ipc::HealthStatus;
 pub use crate::state::ipc::VERSION_BYTES;
 
 pub const fn index_hex(value: usize) -> u8 {
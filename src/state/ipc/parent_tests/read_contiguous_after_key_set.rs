@@ -148,6 +148,11 @@ fn processes_partial_response_metrics() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -170,6 +175,11 @@ fn processes_receive_metrics_zero_data_ingested_bytes() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::Some(Box::new([])),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -195,6 +205,11 @@ fn processes_receive_metrics_1_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::Some(Box::new(*b"0123456789ABCDEF")),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -225,6 +240,11 @@ fn processes_receive_metrics_2_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -257,6 +277,11 @@ fn processes_receive_metrics_3_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -289,6 +314,11 @@ fn processes_receive_metrics_4_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -309,6 +339,11 @@ fn processes_partial_receive_key() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -331,6 +366,11 @@ fn processes_empty_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(&[])),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -357,6 +397,11 @@ fn processes_single_item_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(&[b"0123456789ABCDEF"])),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -489,6 +534,11 @@ fn processes_max_length_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(EXPECTED_KEYS)),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -144,6 +144,11 @@ fn processes_partial_response_metrics() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(initial_message()),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -166,6 +171,11 @@ fn processes_receive_metrics_zero_data_ingested_bytes() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(Box::new([])),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -191,6 +201,11 @@ fn processes_receive_metrics_1_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(Box::from(*b"0123456789ABCDEF")),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -221,6 +236,11 @@ fn processes_receive_metrics_2_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -253,6 +273,11 @@ fn processes_receive_metrics_3_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -285,6 +310,11 @@ fn processes_receive_metrics_4_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -305,6 +335,11 @@ fn processes_partial_receive_key() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(initial_message()),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -327,6 +362,11 @@ fn processes_empty_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(&[])),
             metrics: ResponseItem::Some(initial_message()),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -353,6 +393,11 @@ fn processes_single_item_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(&[b"0123456789ABCDEF"])),
             metrics: ResponseItem::Some(initial_message()),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -485,6 +530,11 @@ fn processes_max_length_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(EXPECTED_KEYS)),
             metrics: ResponseItem::Some(initial_message()),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
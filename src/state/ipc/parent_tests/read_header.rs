@@ -11,6 +11,11 @@ fn empty_buffer_produces_no_messages() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -44,6 +49,11 @@ fn empty_buffer_post_split_version_produces_no_messages() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -163,6 +163,11 @@ fn processes_partial_response_metrics() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -188,6 +193,11 @@ fn processes_receive_metrics_zero_data_ingested_bytes() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::Some(Box::new([])),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -216,6 +226,11 @@ fn processes_receive_metrics_1_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::Some(Box::from(*b"0123456789ABCDEF")),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -246,6 +261,11 @@ fn processes_receive_metrics_2_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -278,6 +298,11 @@ fn processes_receive_metrics_3_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -310,6 +335,11 @@ fn processes_receive_metrics_4_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -333,6 +363,11 @@ fn processes_partial_receive_key() {
         DecoderResponse {
             key_set: ResponseItem::Some(initial_key_set()),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -358,6 +393,11 @@ fn processes_empty_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(&[])),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -387,6 +427,11 @@ fn processes_single_item_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(&[b"0123456789ABCDEF"])),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -522,6 +567,11 @@ fn processes_max_length_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(EXPECTED_KEYS)),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -159,6 +159,11 @@ fn processes_partial_response_metrics() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(initial_message()),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -184,6 +189,11 @@ fn processes_receive_metrics_zero_data_ingested_bytes() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(Box::new([])),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -212,6 +222,11 @@ fn processes_receive_metrics_1_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(Box::from(*b"0123456789ABCDEF")),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -242,6 +257,11 @@ fn processes_receive_metrics_2_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -274,6 +294,11 @@ fn processes_receive_metrics_3_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -306,6 +331,11 @@ fn processes_receive_metrics_4_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -329,6 +359,11 @@ fn processes_partial_receive_key() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(initial_message()),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -354,6 +389,11 @@ fn processes_empty_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(&[])),
             metrics: ResponseItem::Some(initial_message()),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -383,6 +423,11 @@ fn processes_single_item_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(&[b"0123456789ABCDEF"])),
             metrics: ResponseItem::Some(initial_message()),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -518,6 +563,11 @@ fn processes_max_length_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(EXPECTED_KEYS)),
             metrics: ResponseItem::Some(initial_message()),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
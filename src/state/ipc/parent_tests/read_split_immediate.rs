@@ -148,6 +148,11 @@ fn processes_partial_response_metrics() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -173,6 +178,11 @@ fn processes_receive_metrics_zero_data_ingested_bytes() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(Box::new([])),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -201,6 +211,11 @@ fn processes_receive_metrics_1_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(Box::from(*b"0123456789ABCDEF")),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -231,6 +246,11 @@ fn processes_receive_metrics_2_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -263,6 +283,11 @@ fn processes_receive_metrics_3_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -295,6 +320,11 @@ fn processes_receive_metrics_4_byte_data_len_ingested() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::Some(expected_hex),
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -318,6 +348,11 @@ fn processes_partial_receive_key() {
         DecoderResponse {
             key_set: ResponseItem::None,
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -343,6 +378,11 @@ fn processes_empty_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(&[])),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
@@ -372,10 +412,311 @@ fn processes_single_item_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(&[b"0123456789ABCDEF"])),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
         }
     );
 }
 
+#[test]
+fn processes_named_key_set() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x01,
+        // Key set length
+        0x03,
+        // Key 1: all hex digits (length: 16, with identity)
+        0x90,
+        b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7',
+        b'8', b'9', b'A', b'B', b'C', b'D', b'E', b'F',
+        // Key 1 name (length: 5)
+        0x05,
+        b'a', b'g', b'e', b'n', b't',
+        // Key 1 user (length: 7)
+        0x07,
+        b'g', b'r', b'a', b'f', b'a', b'n', b'a',
+        // Key 1 scope (length: 0)
+        0x00, 0x00, 0x00, 0x00,
+        // Key 1 client CN (length: 0)
+        0x00,
+        // Key 2: 4 `A`s (length: 4, with identity)
+        0x84,
+        b'A', b'A', b'A', b'A',
+        // Key 2 name (length: 0)
+        0x00,
+        // Key 2 user (length: 4)
+        0x04,
+        b'p', b'r', b'o', b'm',
+        // Key 2 scope (length: 0)
+        0x00, 0x00, 0x00, 0x00,
+        // Key 2 client CN (length: 0)
+        0x00,
+        // Key 3: 4 `B`s (length: 4)
+        0x04,
+        b'B', b'B', b'B', b'B',
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+    assert_eq!(
+        D.lock().take_response(),
+        DecoderResponse {
+            key_set: ResponseItem::Some(KeySet::build_named(&[
+                (b"0123456789ABCDEF", b"agent", b"grafana"),
+                (b"AAAA", b"", b"prom"),
+                (b"BBBB", b"", b"metrics"),
+            ])),
+            metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
+        }
+    );
+}
+
+#[test]
+fn processes_scoped_key_set() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x01,
+        // Key set length
+        0x02,
+        // Key 1: 4 `A`s (length: 4, with identity)
+        0x84,
+        b'A', b'A', b'A', b'A',
+        // Key 1 name (length: 0)
+        0x00,
+        // Key 1 user (length: 7)
+        0x07,
+        b'm', b'e', b't', b'r', b'i', b'c', b's',
+        // Key 1 scope (length: 10)
+        0x0A, 0x00, 0x00, 0x00,
+        b's', b'e', b'r', b'v', b'i', b'c', b'e', b's', b'=', b'a',
+        // Key 1 client CN (length: 0)
+        0x00,
+        // Key 2: 4 `B`s (length: 4)
+        0x04,
+        b'B', b'B', b'B', b'B',
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+    assert_eq!(
+        D.lock().take_response(),
+        DecoderResponse {
+            key_set: ResponseItem::Some(KeySet::build_scoped(&[
+                (b"AAAA", b"services=a"),
+                (b"BBBB", b""),
+            ])),
+            metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
+        }
+    );
+}
+
+#[test]
+fn processes_hashed_key_set() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x01,
+        // Key set length
+        0x02,
+        // Key 1: hashed
+        0x00,
+        // Key 1 salt
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+        0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F,
+        // Key 1 digest
+        0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB,
+        0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB,
+        0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB,
+        0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB,
+        // Key 2: hashed, with identity
+        0x80,
+        // Key 2 salt
+        0x0F, 0x0E, 0x0D, 0x0C, 0x0B, 0x0A, 0x09, 0x08,
+        0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x00,
+        // Key 2 digest
+        0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD,
+        0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD,
+        0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD,
+        0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD, 0xCD,
+        // Key 2 name (length: 5)
+        0x05,
+        b'a', b'g', b'e', b'n', b't',
+        // Key 2 user (length: 7)
+        0x07,
+        b'm', b'e', b't', b'r', b'i', b'c', b's',
+        // Key 2 scope (length: 0)
+        0x00, 0x00, 0x00, 0x00,
+        // Key 2 client CN (length: 0)
+        0x00,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+
+    let mut builder = KeySetBuilder::new();
+    let ascending: [u8; KEY_SALT_LEN] = std::array::from_fn(truncate_usize_u8);
+    let mut descending = ascending;
+    descending.reverse();
+    for (salt, digest, name) in [
+        (ascending, [0xAB; KEY_DIGEST_LEN], &b""[..]),
+        (descending, [0xCD; KEY_DIGEST_LEN], &b"agent"[..]),
+    ] {
+        assert_eq!(
+            builder.push_hashed(&KeyHash { salt, digest }),
+            KeyPushResult::Success
+        );
+        builder.set_last_name(name);
+    }
+
+    assert_eq!(
+        D.lock().take_response(),
+        DecoderResponse {
+            key_set: ResponseItem::Some(builder.finish()),
+            metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
+        }
+    );
+}
+
+#[test]
+fn processes_client_cn_key_set() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x01,
+        // Key set length
+        0x02,
+        // Key 1: no key (with identity)
+        0xFF,
+        // Key 1 name (length: 5)
+        0x05,
+        b'a', b'g', b'e', b'n', b't',
+        // Key 1 user (length: 7)
+        0x07,
+        b'm', b'e', b't', b'r', b'i', b'c', b's',
+        // Key 1 scope (length: 0)
+        0x00, 0x00, 0x00, 0x00,
+        // Key 1 client CN (length: 7)
+        0x07,
+        b'g', b'r', b'a', b'f', b'a', b'n', b'a',
+        // Key 2: 4 `A`s (length: 4, with identity)
+        0x84,
+        b'A', b'A', b'A', b'A',
+        // Key 2 name (length: 0)
+        0x00,
+        // Key 2 user (length: 7)
+        0x07,
+        b'm', b'e', b't', b'r', b'i', b'c', b's',
+        // Key 2 scope (length: 0)
+        0x00, 0x00, 0x00, 0x00,
+        // Key 2 client CN (length: 2)
+        0x02,
+        b'c', b'i',
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+    assert_eq!(
+        D.lock().take_response(),
+        DecoderResponse {
+            key_set: ResponseItem::Some(KeySet::build_client_cns(&[
+                (None, b"grafana", b"agent"),
+                (Some(b"AAAA"), b"ci", b""),
+            ])),
+            metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
+        }
+    );
+}
+
+#[test]
+#[should_panic = "Key scope too long."]
+fn panics_on_too_long_key_scope() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x01,
+        // Key set length
+        0x01,
+        // Key 1: 4 `A`s (length: 4, with identity)
+        0x84,
+        b'A', b'A', b'A', b'A',
+        // Key 1 name (length: 0)
+        0x00,
+        // Key 1 user (length: 7)
+        0x07,
+        b'm', b'e', b't', b'r', b'i', b'c', b's',
+        // Key 1 scope (length: 1025)
+        0x01, 0x04, 0x00, 0x00,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+}
+
+#[test]
+#[should_panic = "Key username has an invalid length."]
+fn panics_on_empty_key_username() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x01,
+        // Key set length
+        0x01,
+        // Key 1: 4 `A`s (length: 4, with identity)
+        0x84,
+        b'A', b'A', b'A', b'A',
+        // Key 1 name (length: 0)
+        0x00,
+        // Key 1 user (length: 0)
+        0x00,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+}
+
 #[test]
 fn processes_max_length_key_set() {
     static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
@@ -507,6 +848,185 @@ fn processes_max_length_key_set() {
         DecoderResponse {
             key_set: ResponseItem::Some(KeySet::build(EXPECTED_KEYS)),
             metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: None,
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::None,
+        }
+    );
+}
+
+#[test]
+fn processes_scoped_metrics_responses() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x05,
+        // ID (1)
+        0x01, 0x00, 0x00, 0x00,
+        // Operation ID
+        0x00,
+        // Data length (3)
+        0x03, 0x00, 0x00, 0x00,
+        // Data
+        b'o', b'p', b'e',
+        // Operation ID
+        0x03,
+        // Data length (2)
+        0x02, 0x00, 0x00, 0x00,
+        // Data
+        0x0A, 0x00,
+        // Operation ID
+        0x05,
+        // ID (258)
+        0x02, 0x01, 0x00, 0x00,
+        // Operation ID
+        0x03,
+        // Data length (2)
+        0x02, 0x00, 0x00, 0x00,
+        // Data
+        0x0A, 0x01,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+    assert_eq!(
+        D.lock().take_response(),
+        DecoderResponse {
+            key_set: ResponseItem::None,
+            metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::Some(Box::from([0x0A, 0x00])),
+            health: None,
+            scoped_metrics: vec![
+                (1, ResponseItem::Some(Box::from(*b"ope"))),
+                (258, ResponseItem::Some(Box::from([0x0A, 0x01]))),
+            ],
+            tls_certificate: ResponseItem::None,
+        }
+    );
+}
+
+#[test]
+#[should_panic = "Unknown IPC byte '04'"]
+fn panics_on_scoped_health_response() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x05,
+        // ID (1)
+        0x01, 0x00, 0x00, 0x00,
+        // Operation ID
+        0x04,
+        // Status flags
+        0x02,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+}
+
+#[test]
+fn processes_tls_certificate() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x06,
+        // Certificate length (4)
+        0x04, 0x00, 0x00, 0x00,
+        // Certificate
+        b'c', b'e', b'r', b't',
+        // Private key length (3)
+        0x03, 0x00, 0x00, 0x00,
+        // Private key
+        b'k', b'e', b'y',
+        // OCSP response length (0)
+        0x00, 0x00, 0x00, 0x00,
+        // Operation ID
+        0x04,
+        // Status flags
+        0x03,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+    assert_eq!(
+        D.lock().take_response(),
+        DecoderResponse {
+            key_set: ResponseItem::None,
+            metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: Some(HealthStatus {
+                journal_active: true,
+                child_stable: true,
+            }),
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::Some(TlsCertificatePair {
+                certificate: Box::from(*b"cert"),
+                private_key: Box::from(*b"key"),
+                ocsp_response: None,
+            }),
+        }
+    );
+}
+
+#[test]
+fn processes_tls_certificate_with_ocsp_response() {
+    static D: Uncontended<Decoder> = Uncontended::new(Decoder::new());
+
+    #[rustfmt::skip]
+    static REQUEST: &[u8] = declarative_request![
+        // Operation ID
+        0x06,
+        // Certificate length (4)
+        0x04, 0x00, 0x00, 0x00,
+        // Certificate
+        b'c', b'e', b'r', b't',
+        // Private key length (3)
+        0x03, 0x00, 0x00, 0x00,
+        // Private key
+        b'k', b'e', b'y',
+        // OCSP response length (2)
+        0x02, 0x00, 0x00, 0x00,
+        // OCSP response
+        0x30, 0x00,
+        // Operation ID
+        0x04,
+        // Status flags
+        0x03,
+    ];
+
+    for chunk in split_req(REQUEST) {
+        D.lock().read_bytes(chunk);
+    }
+    assert_eq!(
+        D.lock().take_response(),
+        DecoderResponse {
+            key_set: ResponseItem::None,
+            metrics: ResponseItem::None,
+            text_metrics: ResponseItem::None,
+            protobuf_metrics: ResponseItem::None,
+            health: Some(HealthStatus {
+                journal_active: true,
+                child_stable: true,
+            }),
+            scoped_metrics: Vec::new(),
+            tls_certificate: ResponseItem::Some(TlsCertificatePair {
+                certificate: Box::from(*b"cert"),
+                private_key: Box::from(*b"key"),
+                ocsp_response: Some(Box::from(*b"\x30\x00")),
+            }),
         }
     );
 }
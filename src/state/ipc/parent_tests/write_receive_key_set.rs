@@ -27,6 +27,122 @@ fn encodes_single_item_receive_key_set() {
     );
 }
 
+#[test]
+fn encodes_named_receive_key_set() {
+    assert_eq!(
+        &*receive_key_set_bytes(KeySet::build_named(&[
+            (b"0123456789ABCDEF", b"agent", b"grafana"),
+            (b"AAAA", b"", b"prom"),
+            (b"BBBB", b"ci", b"metrics"),
+            (b"CCCC", b"", b"metrics"),
+        ])),
+        &[
+            0x01, // Operation ID
+            0x04, // Key set length
+            0x90, // Key 1: all hex digits (length: 16, with identity)
+            b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8', b'9', b'a', b'b', b'c', b'd',
+            b'e', b'f', 0x05, // Key 1 name (length: 5)
+            b'a', b'g', b'e', b'n', b't', 0x07, // Key 1 user (length: 7)
+            b'g', b'r', b'a', b'f', b'a', b'n', b'a', // Key 1 scope (length: 0)
+            0x00, 0x00, 0x00, 0x00, // Key 1 client CN (length: 0)
+            0x00, 0x84, // Key 2: 4 `a`s (length: 4, with identity)
+            b'a', b'a', b'a', b'a', 0x00, // Key 2 name (length: 0)
+            0x04, // Key 2 user (length: 4)
+            b'p', b'r', b'o', b'm', // Key 2 scope (length: 0)
+            0x00, 0x00, 0x00, 0x00, // Key 2 client CN (length: 0)
+            0x00, 0x84, // Key 3: 4 `b`s (length: 4, with identity)
+            b'b', b'b', b'b', b'b', 0x02, // Key 3 name (length: 2)
+            b'c', b'i', 0x07, // Key 3 user (length: 7)
+            b'm', b'e', b't', b'r', b'i', b'c', b's', // Key 3 scope (length: 0)
+            0x00, 0x00, 0x00, 0x00, // Key 3 client CN (length: 0)
+            0x00, 0x04, // Key 4: 4 `c`s (length: 4)
+            b'c', b'c', b'c', b'c',
+        ]
+    );
+}
+
+#[test]
+fn encodes_scoped_receive_key_set() {
+    assert_eq!(
+        &*receive_key_set_bytes(KeySet::build_scoped(&[
+            (b"AAAA", b"users=ci"),
+            (b"BBBB", b""),
+        ])),
+        &[
+            0x01, // Operation ID
+            0x02, // Key set length
+            0x84, // Key 1: 4 `a`s (length: 4, with identity)
+            b'a', b'a', b'a', b'a', 0x00, // Key 1 name (length: 0)
+            0x07, // Key 1 user (length: 7)
+            b'm', b'e', b't', b'r', b'i', b'c', b's', // Key 1 scope (length: 8)
+            0x08, 0x00, 0x00, 0x00, b'u', b's', b'e', b'r', b's', b'=', b'c', b'i',
+            0x00, // Key 1 client CN (length: 0)
+            0x04, // Key 2: 4 `b`s (length: 4)
+            b'b', b'b', b'b', b'b',
+        ]
+    );
+}
+
+#[test]
+fn encodes_hashed_receive_key_set() {
+    let hash = KeyHash {
+        salt: [0x11; KEY_SALT_LEN],
+        digest: [0x22; KEY_DIGEST_LEN],
+    };
+    let mut builder = KeySetBuilder::new();
+    assert_eq!(builder.push_hashed(&hash), KeyPushResult::Success);
+    assert_eq!(builder.push_hashed(&hash), KeyPushResult::Success);
+    builder.set_last_user(b"ci");
+    assert_eq!(builder.push_hex(b"AAAA"), KeyPushResult::Success);
+
+    let mut expected = vec![
+        0x01, // Operation ID
+        0x03, // Key set length
+        0x00, // Key 1: hashed
+    ];
+    expected.extend_from_slice(&[0x11; KEY_SALT_LEN]);
+    expected.extend_from_slice(&[0x22; KEY_DIGEST_LEN]);
+    expected.push(0x80); // Key 2: hashed, with identity
+    expected.extend_from_slice(&[0x11; KEY_SALT_LEN]);
+    expected.extend_from_slice(&[0x22; KEY_DIGEST_LEN]);
+    expected.extend_from_slice(&[
+        0x00, // Key 2 name (length: 0)
+        0x02, // Key 2 user (length: 2)
+        b'c', b'i', // Key 2 scope (length: 0)
+        0x00, 0x00, 0x00, 0x00, // Key 2 client CN (length: 0)
+        0x00, 0x04, // Key 3: 4 `a`s (length: 4)
+        b'a', b'a', b'a', b'a',
+    ]);
+
+    assert_eq!(&*receive_key_set_bytes(builder.finish()), &*expected);
+}
+
+#[test]
+fn encodes_client_cn_receive_key_set() {
+    assert_eq!(
+        &*receive_key_set_bytes(KeySet::build_client_cns(&[
+            (None, b"grafana", b""),
+            (Some(b"AAAA"), b"ci", b""),
+        ])),
+        &[
+            0x01, // Operation ID
+            0x02, // Key set length
+            0xFF, // Key 1: no key (with identity)
+            0x00, // Key 1 name (length: 0)
+            0x07, // Key 1 user (length: 7)
+            b'm', b'e', b't', b'r', b'i', b'c', b's', // Key 1 scope (length: 0)
+            0x00, 0x00, 0x00, 0x00, // Key 1 client CN (length: 7)
+            0x07, b'g', b'r', b'a', b'f', b'a', b'n', b'a',
+            0x84, // Key 2: 4 `a`s (length: 4, with identity)
+            b'a', b'a', b'a', b'a', 0x00, // Key 2 name (length: 0)
+            0x07, // Key 2 user (length: 7)
+            b'm', b'e', b't', b'r', b'i', b'c', b's', // Key 2 scope (length: 0)
+            0x00, 0x00, 0x00, 0x00, // Key 2 client CN (length: 2)
+            0x02, b'c', b'i',
+        ]
+    );
+}
+
 #[test]
 fn encodes_max_len_receive_key_set() {
     #[rustfmt::skip]
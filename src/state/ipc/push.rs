@@ -0,0 +1,92 @@
+use crate::prelude::*;
+
+use super::common::*;
+
+// Each request the parent hands to the pusher is a target byte, followed by the body's length as
+// a little-endian `u32`, and then the body itself. Bodies are sent exactly as they're to be pushed.
+pub const PUSH_REMOTE_WRITE: u8 = 0x00;
+pub const PUSH_OTLP: u8 = 0x01;
+pub const PUSH_PUSHGATEWAY: u8 = 0x02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushTarget {
+    RemoteWrite,
+    Otlp,
+    Pushgateway,
+}
+
+impl PushTarget {
+    pub const fn to_byte(self) -> u8 {
+        match self {
+            PushTarget::RemoteWrite => PUSH_REMOTE_WRITE,
+            PushTarget::Otlp => PUSH_OTLP,
+            PushTarget::Pushgateway => PUSH_PUSHGATEWAY,
+        }
+    }
+
+    pub const fn from_byte(byte: u8) -> Option<PushTarget> {
+        match byte {
+            PUSH_REMOTE_WRITE => Some(PushTarget::RemoteWrite),
+            PUSH_OTLP => Some(PushTarget::Otlp),
+            PUSH_PUSHGATEWAY => Some(PushTarget::Pushgateway),
+            _ => None,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            PushTarget::RemoteWrite => "Remote write",
+            PushTarget::Otlp => "OTLP",
+            PushTarget::Pushgateway => "Pushgateway",
+        }
+    }
+}
+
+pub fn write_push_request(
+    output: &mut impl Write,
+    target: PushTarget,
+    body: &[u8],
+) -> io::Result<()> {
+    let Ok(len) = u32::try_from(body.len()) else {
+        return Err(Error::from_raw_os_error(libc::EMSGSIZE));
+    };
+
+    let [a, b, c, d] = len.to_le_bytes();
+    output.write_all(&[target.to_byte(), a, b, c, d])?;
+    output.write_all(body)?;
+    output.flush()
+}
+
+/// Read the next push request, returning `None` once the parent closes its end between requests.
+pub fn read_push_request(input: &mut impl Read) -> io::Result<Option<(PushTarget, Vec<u8>)>> {
+    let mut head = [0; 5];
+
+    match input.read_exact(&mut head[..1]) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    input.read_exact(&mut head[1..])?;
+
+    let [byte, a, b, c, d] = head;
+
+    let Some(target) = PushTarget::from_byte(byte) else {
+        unknown_byte(byte);
+    };
+
+    let len = u32::from_le_bytes([a, b, c, d]);
+    let mut body = Vec::new();
+
+    if body.try_reserve_exact(zero_extend_u32_usize(len)).is_err() {
+        return Err(Error::from_raw_os_error(libc::ENOMEM));
+    }
+
+    input.take(u64::from(len)).read_to_end(&mut body)?;
+
+    if body.len() != zero_extend_u32_usize(len) {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(Some((target, body)))
+}
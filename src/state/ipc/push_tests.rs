@@ -0,0 +1,56 @@
+use crate::prelude::*;
+
+use super::push::*;
+
+#[test]
+fn round_trips_requests() {
+    let mut buf = Vec::new();
+    write_push_request(&mut buf, PushTarget::RemoteWrite, b"snappy").unwrap();
+    write_push_request(&mut buf, PushTarget::Otlp, b"").unwrap();
+    write_push_request(&mut buf, PushTarget::Pushgateway, b"text").unwrap();
+
+    assert_eq!(
+        &buf[..11],
+        &[0x00, 6, 0, 0, 0, b's', b'n', b'a', b'p', b'p', b'y']
+    );
+
+    let mut input = &*buf;
+    assert_eq!(
+        read_push_request(&mut input).unwrap(),
+        Some((PushTarget::RemoteWrite, b"snappy".to_vec()))
+    );
+    assert_eq!(
+        read_push_request(&mut input).unwrap(),
+        Some((PushTarget::Otlp, Vec::new()))
+    );
+    assert_eq!(
+        read_push_request(&mut input).unwrap(),
+        Some((PushTarget::Pushgateway, b"text".to_vec()))
+    );
+    assert_eq!(read_push_request(&mut input).unwrap(), None);
+}
+
+#[test]
+fn rejects_truncated_head() {
+    let mut input: &[u8] = &[0x01, 4, 0];
+    assert_eq!(
+        read_push_request(&mut input).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+fn rejects_truncated_body() {
+    let mut input: &[u8] = &[0x02, 4, 0, 0, 0, b'a', b'b'];
+    assert_eq!(
+        read_push_request(&mut input).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+#[should_panic = "Unknown IPC byte '7F'"]
+fn panics_on_unknown_target() {
+    let mut input: &[u8] = &[0x7F, 0, 0, 0, 0];
+    drop(read_push_request(&mut input));
+}
@@ -7,6 +7,15 @@ use crate::prelude::*;
 // Represents a key strength of 256 bits. Should be enough for the foreseeable future.
 pub const MAX_KEY_LEN: usize = 64;
 pub const MAX_KEY_SET_LEN: usize = zero_extend_u8_usize(u8::MAX);
+pub const MAX_KEY_NAME_LEN: usize = 64;
+pub const MAX_KEY_USER_LEN: usize = 64;
+// X.520's upper bound for common names.
+pub const MAX_KEY_CLIENT_CN_LEN: usize = 64;
+// The username keys accept unless their file says otherwise.
+pub const DEFAULT_KEY_USER: &[u8] = b"metrics";
+
+// A key's name, copied out so the key set's lock doesn't need held for it.
+pub type KeyName = heapless::Vec<u8, MAX_KEY_NAME_LEN>;
 
 #[must_use = "Keys should not be ignored, as that could create security holes."]
 #[cfg_attr(test, derive(PartialEq, Eq))]
@@ -17,11 +26,27 @@ pub struct Key {
     //
     // Also has a secondary benefit in allowing it to be sized.
     raw: [u8; MAX_KEY_LEN],
+    // Set for keys only known by their hash, in which case `raw` is left all zeroes. The salt and
+    // digest are all zeroes otherwise.
+    hashed: bool,
+    salt: [u8; KEY_SALT_LEN],
+    digest: [u8; KEY_DIGEST_LEN],
+    // These two aren't secret, but they're zero-padded all the same, so an all-zeroes key is still
+    // valid and the username check can be just as timing-resistant as the key check.
+    name: [u8; MAX_KEY_NAME_LEN],
+    user: [u8; MAX_KEY_USER_LEN],
 }
 
 impl fmt::Debug for Key {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Key({:?})", BinaryToDebug(self.insecure_get_value()))
+        write!(
+            f,
+            "Key({:?}, hash = {:?}, name = {:?}, user = {:?})",
+            BinaryToDebug(self.insecure_get_value()),
+            self.hash(),
+            BinaryToDebug(self.name()),
+            BinaryToDebug(self.user()),
+        )
     }
 }
 
@@ -30,6 +55,46 @@ fn is_valid_hex_string(bytes: &[u8]) -> bool {
         && bytes.iter().all(|b| b.is_ascii_hexdigit())
 }
 
+/// Names end up as label values, so they're kept to characters that never need escaped there.
+pub fn is_valid_key_name(name: &[u8]) -> bool {
+    matches!(name.len(), 1..=MAX_KEY_NAME_LEN)
+        && name
+            .iter()
+            .all(|&b| b.is_ascii_graphic() && b != b'"' && b != b'\\')
+}
+
+/// Usernames can't contain a colon, as Basic authorization splits the username off at the first
+/// one.
+pub fn is_valid_key_user(user: &[u8]) -> bool {
+    matches!(user.len(), 1..=MAX_KEY_USER_LEN)
+        && user.iter().all(|&b| b.is_ascii_graphic() && b != b':')
+}
+
+fn to_padded<const N: usize>(value: &[u8]) -> [u8; N] {
+    let mut result = [0; N];
+    result[..value.len()].copy_from_slice(value);
+    result
+}
+
+fn from_padded(value: &[u8]) -> &[u8] {
+    let len = value.iter().position(|c| *c == 0).unwrap_or(value.len());
+    &value[..len]
+}
+
+// Compares against the whole fixed-size buffer, treating `value` as if it were zero-padded, so
+// neither its length nor where it first differs shows up in the timing. `value` must not contain
+// any zero bytes, and it must not be any longer than `trusted`.
+fn padded_matches(value: &[u8], trusted: &[u8], normalize: impl Fn(u8) -> u8) -> bool {
+    let mut matched = true;
+
+    for (i, &right) in trusted.iter().enumerate() {
+        let left = value.get(i).map_or(0, |&b| normalize(b));
+        matched = std::hint::black_box(matched & (left == right));
+    }
+
+    matched
+}
+
 fn normalize_hex(byte: u8) -> u8 {
     // Normalize for fast case-insensitive matching. This assumes it's already validated as a hex
     // character, so input can only be one of the following:
@@ -42,18 +107,153 @@ fn normalize_hex(byte: u8) -> u8 {
     byte | 0b0010_0000
 }
 
+/// Client certificate common names are compared as-is, so only control characters are ruled out.
+pub fn is_valid_key_client_cn(client_cn: &[u8]) -> bool {
+    matches!(client_cn.len(), 1..=MAX_KEY_CLIENT_CN_LEN)
+        && client_cn.iter().all(|&b| b >= b' ' && b != 0x7F)
+}
+
+/// Hashes a key as it'd be written in a key file's `key` field, for its `key_hash` field instead.
+/// Returns `None` if the key's invalid.
+pub fn hash_hex_key(key: &[u8]) -> Option<KeyHash> {
+    if !is_valid_hex_string(key) {
+        return None;
+    }
+    let normalized: Vec<u8> = key.iter().map(|&b| normalize_hex(b)).collect();
+    Some(KeyHash::generate(&normalized))
+}
+
 impl Key {
     // This function has this name for a reason. Don't use it unless there's a very good reason,
-    // like serializing it over the IPC channel.
+    // like serializing it over the IPC channel. It's empty for hashed keys and for entries only
+    // reachable by client certificate.
     pub fn insecure_get_value(&self) -> &[u8] {
-        let key_len = self.raw.iter().position(|c| *c == 0).unwrap_or(MAX_KEY_LEN);
-        &self.raw[..key_len]
+        from_padded(&self.raw)
+    }
+
+    /// Set if the key was configured by its hash. Its value is empty in that case.
+    pub fn hash(&self) -> Option<KeyHash> {
+        self.hashed.then_some(KeyHash {
+            salt: self.salt,
+            digest: self.digest,
+        })
+    }
+
+    /// Empty if the key wasn't given a name.
+    pub fn name(&self) -> &[u8] {
+        from_padded(&self.name)
+    }
+
+    pub fn user(&self) -> &[u8] {
+        from_padded(&self.user)
+    }
+}
+
+// The fields of a key file, borrowed from its contents.
+struct KeyFile<'a> {
+    // At most one of these is set, and one of them is required unless `client_cn` is set.
+    key: Option<&'a [u8]>,
+    key_hash: Option<&'a [u8]>,
+    client_cn: Option<&'a [u8]>,
+    name: Option<&'a [u8]>,
+    user: Option<&'a [u8]>,
+    scope_services: Option<&'a [u8]>,
+    scope_users: Option<&'a [u8]>,
+    scope_families: Option<&'a [u8]>,
+}
+
+// A key file is either just the key itself, or a list of `field=value` lines. Returns `None` if
+// it's neither.
+fn parse_key_file(contents: &[u8]) -> Option<KeyFile<'_>> {
+    if !contents.contains(&b'=') {
+        return Some(KeyFile {
+            key: Some(trim_ascii(contents)),
+            key_hash: None,
+            client_cn: None,
+            name: None,
+            user: None,
+            scope_services: None,
+            scope_users: None,
+            scope_families: None,
+        });
+    }
+
+    let mut key = None;
+    let mut key_hash = None;
+    let mut client_cn = None;
+    let mut name = None;
+    let mut user = None;
+    let mut scope_services = None;
+    let mut scope_users = None;
+    let mut scope_families = None;
+
+    for line in contents.split(|&b| b == b'\n') {
+        let line = trim_ascii(line);
+
+        if line.is_empty() || line.starts_with(b"#") {
+            continue;
+        }
+
+        let index = line.iter().position(|&b| b == b'=')?;
+        let value = trim_ascii(&line[index.wrapping_add(1)..]);
+        let target = match trim_ascii(&line[..index]) {
+            b"key" => &mut key,
+            b"key_hash" => &mut key_hash,
+            b"client_cn" => &mut client_cn,
+            b"name" => &mut name,
+            b"user" => &mut user,
+            b"scope_services" => &mut scope_services,
+            b"scope_users" => &mut scope_users,
+            b"scope_families" => &mut scope_families,
+            _ => return None,
+        };
+
+        // Duplicates are almost certainly a mistake, so don't just pick one.
+        if target.replace(value).is_some() {
+            return None;
+        }
+    }
+
+    if key.is_some() && key_hash.is_some() {
+        return None;
+    }
+
+    if key.is_none() && key_hash.is_none() && client_cn.is_none() {
+        return None;
+    }
+
+    Some(KeyFile {
+        key,
+        key_hash,
+        client_cn,
+        name,
+        user,
+        scope_services,
+        scope_users,
+        scope_families,
+    })
+}
+
+// Names default to the file name, minus any `.key` extension. File names that don't work as names
+// just leave the key unnamed.
+fn default_key_name(file_name: &[u8]) -> &[u8] {
+    let name = file_name.strip_suffix(b".key").unwrap_or(file_name);
+    if is_valid_key_name(name) {
+        name
+    } else {
+        b""
     }
 }
 
 #[derive(Debug)]
 pub struct KeySetBuilder {
     key_set: Vec<Key>,
+    // Encoded scopes, indexed the same as the keys. They aren't secret, so they're kept out of
+    // the keys themselves.
+    scopes: Vec<Box<[u8]>>,
+    // Client certificate common names mapped to each key, empty if none is. Indexed and kept out
+    // of the keys the same way as the scopes.
+    client_cns: Vec<Box<[u8]>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -67,12 +267,16 @@ impl KeySetBuilder {
     pub fn new() -> Self {
         Self {
             key_set: Vec::new(),
+            scopes: Vec::new(),
+            client_cns: Vec::new(),
         }
     }
 
     pub fn try_reserve(len: usize) -> Option<Self> {
         Some(Self {
             key_set: try_new_dynamic_vec(len)?,
+            scopes: try_new_dynamic_vec(len)?,
+            client_cns: try_new_dynamic_vec(len)?,
         })
     }
 
@@ -89,13 +293,59 @@ impl KeySetBuilder {
         }
     }
 
+    /// Parses a key file, as read from `file_name` in the key directory. See `parse_key_file` for
+    /// the format.
+    #[must_use]
+    pub fn push_file(&mut self, file_name: &[u8], contents: &[u8]) -> KeyPushResult {
+        let Some(file) = parse_key_file(contents) else {
+            return KeyPushResult::Invalid;
+        };
+
+        let name = file.name.unwrap_or_else(|| default_key_name(file_name));
+        let user = file.user.unwrap_or(DEFAULT_KEY_USER);
+
+        if (file.name.is_some() && !is_valid_key_name(name)) || !is_valid_key_user(user) {
+            return KeyPushResult::Invalid;
+        }
+
+        let client_cn = file.client_cn.unwrap_or_default();
+        if file.client_cn.is_some() && !is_valid_key_client_cn(client_cn) {
+            return KeyPushResult::Invalid;
+        }
+
+        let Some(scope) =
+            KeyScope::encode_fields(file.scope_services, file.scope_users, file.scope_families)
+        else {
+            return KeyPushResult::Invalid;
+        };
+
+        let result = match (file.key, file.key_hash) {
+            (Some(key), _) => self.push_hex(key),
+            (None, Some(key_hash)) => match KeyHash::parse(key_hash) {
+                Some(hash) => self.push_hashed(&hash),
+                None => KeyPushResult::Invalid,
+            },
+            // Only reachable by client certificate.
+            (None, None) => self.push_keyless(),
+        };
+        if result == KeyPushResult::Success {
+            self.set_last_name(name);
+            self.set_last_user(user);
+            self.set_last_scope(&scope);
+            self.set_last_client_cn(client_cn);
+        }
+        result
+    }
+
     /// Safety note: `key` must be validated to satisfy the following constraints:
     /// - The slice itself is non-empty.
     /// - The slice contains at most 64 characters.
     /// - The slice is of even length.
     /// - The slice's contents consist of only hexadecimal digits.
+    ///
+    /// The key is left unnamed and unscoped, with the default username.
     pub unsafe fn push_raw(&mut self, key: &[u8]) {
-        debug_assert!(key.len() < MAX_KEY_LEN);
+        debug_assert!(key.len() <= MAX_KEY_LEN);
 
         let tail = self.key_set.len();
         if tail == MAX_KEY_SET_LEN {
@@ -104,8 +354,8 @@ impl KeySetBuilder {
 
         self.key_set.reserve(1);
         // SAFETY: Push the key to the set while ensuring it's never written to the stack.
-        let mut dest = self.key_set.as_mut_ptr().add(tail).cast::<u8>();
-        debug_assert_eq!(dest.align_offset(std::mem::align_of::<Key>()), 0);
+        let target = self.key_set.as_mut_ptr().add(tail);
+        let mut dest = std::ptr::addr_of_mut!((*target).raw).cast::<u8>();
         for byte in key.iter() {
             *dest = normalize_hex(*byte);
             dest = dest.add(1);
@@ -114,17 +364,104 @@ impl KeySetBuilder {
             *dest = 0;
             dest = dest.add(1);
         }
+        std::ptr::addr_of_mut!((*target).hashed).write(false);
+        std::ptr::addr_of_mut!((*target).salt).write([0; KEY_SALT_LEN]);
+        std::ptr::addr_of_mut!((*target).digest).write([0; KEY_DIGEST_LEN]);
+        std::ptr::addr_of_mut!((*target).name).write([0; MAX_KEY_NAME_LEN]);
+        std::ptr::addr_of_mut!((*target).user).write(to_padded(DEFAULT_KEY_USER));
         self.key_set.set_len(tail.wrapping_add(1));
+        self.scopes.push(Box::default());
+        self.client_cns.push(Box::default());
+    }
+
+    /// Pushes a key known only by its hash. Like with `push_raw`, it's left unnamed and unscoped,
+    /// with the default username.
+    #[must_use]
+    pub fn push_hashed(&mut self, hash: &KeyHash) -> KeyPushResult {
+        if self.key_set.len() == MAX_KEY_SET_LEN {
+            return KeyPushResult::TooManyKeys;
+        }
+
+        self.key_set.push(Key {
+            raw: [0; MAX_KEY_LEN],
+            hashed: true,
+            salt: hash.salt,
+            digest: hash.digest,
+            name: [0; MAX_KEY_NAME_LEN],
+            user: to_padded(DEFAULT_KEY_USER),
+        });
+        self.scopes.push(Box::default());
+        self.client_cns.push(Box::default());
+        KeyPushResult::Success
+    }
+
+    /// Pushes an entry with no key at all, for use with `set_last_client_cn`. No password or token
+    /// ever matches it, as valid ones are never empty. Like with `push_raw`, it's left unnamed and
+    /// unscoped, with the default username.
+    #[must_use]
+    pub fn push_keyless(&mut self) -> KeyPushResult {
+        if self.key_set.len() == MAX_KEY_SET_LEN {
+            return KeyPushResult::TooManyKeys;
+        }
+
+        self.key_set.push(Key {
+            raw: [0; MAX_KEY_LEN],
+            hashed: false,
+            salt: [0; KEY_SALT_LEN],
+            digest: [0; KEY_DIGEST_LEN],
+            name: [0; MAX_KEY_NAME_LEN],
+            user: to_padded(DEFAULT_KEY_USER),
+        });
+        self.scopes.push(Box::default());
+        self.client_cns.push(Box::default());
+        KeyPushResult::Success
+    }
+
+    /// Names the most recently pushed key. `name` must be either empty or a valid name.
+    pub fn set_last_name(&mut self, name: &[u8]) {
+        debug_assert!(name.is_empty() || is_valid_key_name(name));
+        let key = self.key_set.last_mut().expect("No key to name.");
+        key.name = to_padded(name);
+    }
+
+    /// Sets the username of the most recently pushed key. `user` must be a valid username.
+    pub fn set_last_user(&mut self, user: &[u8]) {
+        debug_assert!(is_valid_key_user(user));
+        let key = self.key_set.last_mut().expect("No key to set the user of.");
+        key.user = to_padded(user);
+    }
+
+    /// Scopes the most recently pushed key. `scope` must be either empty or an encoded scope, as
+    /// returned from `KeyScope::encode_fields`.
+    pub fn set_last_scope(&mut self, scope: &[u8]) {
+        debug_assert!(KeyScope::decode(scope).is_some());
+        let target = self.scopes.last_mut().expect("No key to scope.");
+        *target = scope.into();
+    }
+
+    /// Maps a client certificate common name to the most recently pushed key. `client_cn` must be
+    /// either empty or a valid common name.
+    pub fn set_last_client_cn(&mut self, client_cn: &[u8]) {
+        debug_assert!(client_cn.is_empty() || is_valid_key_client_cn(client_cn));
+        let target = self.client_cns.last_mut().expect("No key to map.");
+        *target = client_cn.into();
     }
 
     pub fn finish(self) -> KeySet {
-        // SAFETY: Bypassing the drop logic, to avoid clearing the inner vector.
-        let key_set = unsafe {
+        // SAFETY: Bypassing the drop logic, to avoid clearing the inner vector. Every field is
+        // moved out exactly once.
+        let (key_set, scopes, client_cns) = unsafe {
             let this = std::mem::ManuallyDrop::new(self);
-            std::ptr::read(&this.key_set)
+            (
+                std::ptr::read(&this.key_set),
+                std::ptr::read(&this.scopes),
+                std::ptr::read(&this.client_cns),
+            )
         };
         KeySet {
             key_set: key_set.into(),
+            scopes: scopes.into(),
+            client_cns: client_cns.into(),
         }
     }
 }
@@ -138,10 +475,26 @@ impl Drop for KeySetBuilder {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub enum KeyCheck<'a> {
+    // The matched key and its encoded scope.
+    Matched(&'a Key, &'a [u8]),
+    // No key has this username.
+    WrongUser,
+    // Some key has this username, but none of those have this key.
+    UnknownKey,
+}
+
+#[cfg(test)]
+pub type ClientCnEntry<'a> = (Option<&'a [u8]>, &'a [u8], &'a [u8]);
+
 #[derive(Debug)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct KeySet {
     key_set: Box<[Key]>,
+    scopes: Box<[Box<[u8]>]>,
+    client_cns: Box<[Box<[u8]>]>,
 }
 
 impl Drop for KeySet {
@@ -173,33 +526,158 @@ impl KeySet {
         builder.finish()
     }
 
+    #[cfg(test)]
+    pub fn build_named(keys: &[(&[u8], &[u8], &[u8])]) -> Self {
+        let mut builder = KeySetBuilder::new();
+        for &(key, name, user) in keys {
+            assert_eq!(
+                builder.push_hex(key),
+                KeyPushResult::Success,
+                "Key is invalid: {:?}",
+                BinaryToDebug(key)
+            );
+            builder.set_last_name(name);
+            builder.set_last_user(user);
+        }
+        builder.finish()
+    }
+
+    #[cfg(test)]
+    pub fn build_scoped(keys: &[(&[u8], &[u8])]) -> Self {
+        let mut builder = KeySetBuilder::new();
+        for &(key, scope) in keys {
+            assert_eq!(
+                builder.push_hex(key),
+                KeyPushResult::Success,
+                "Key is invalid: {:?}",
+                BinaryToDebug(key)
+            );
+            builder.set_last_scope(scope);
+        }
+        builder.finish()
+    }
+
+    /// Each entry is an optional key, its client common name, and its name.
+    #[cfg(test)]
+    pub fn build_client_cns(entries: &[ClientCnEntry]) -> Self {
+        let mut builder = KeySetBuilder::new();
+        for &(key, client_cn, name) in entries {
+            let result = match key {
+                Some(key) => builder.push_hex(key),
+                None => builder.push_keyless(),
+            };
+            assert_eq!(result, KeyPushResult::Success);
+            builder.set_last_client_cn(client_cn);
+            builder.set_last_name(name);
+        }
+        builder.finish()
+    }
+
+    #[cfg(test)]
+    pub fn build_hashed(hashes: &[KeyHash]) -> Self {
+        let mut builder = KeySetBuilder::new();
+        for hash in hashes {
+            assert_eq!(builder.push_hashed(hash), KeyPushResult::Success);
+        }
+        builder.finish()
+    }
+
     pub fn insecure_view_keys(&self) -> &[Key] {
         &self.key_set
     }
 
-    pub fn check_key(&self, key: &[u8]) -> bool {
-        // Check for correct syntax. This part isn't security-critical.
-        if !is_valid_hex_string(key) {
-            return false;
+    /// The keys' encoded scopes, indexed the same as `insecure_view_keys`. Unscoped keys' scopes
+    /// are empty.
+    pub fn scopes(&self) -> &[Box<[u8]>] {
+        &self.scopes
+    }
+
+    /// The client certificate common names mapped to each key, indexed the same as
+    /// `insecure_view_keys`. Keys without one have it empty.
+    pub fn client_cns(&self) -> &[Box<[u8]>] {
+        &self.client_cns
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.key_set.is_empty()
+    }
+
+    pub fn check(&self, user: &[u8], key: &[u8]) -> KeyCheck<'_> {
+        self.check_credentials(Some(user), key)
+    }
+
+    /// Checks a bearer token, which comes without a username. So any key matches regardless of
+    /// its username, and it's never `KeyCheck::WrongUser`.
+    pub fn check_token(&self, key: &[u8]) -> KeyCheck<'_> {
+        self.check_credentials(None, key)
+    }
+
+    /// Finds the key a verified client certificate's common name is mapped to, along with its
+    /// encoded scope. If several keys map the same name, the first one wins. Unlike keys, common
+    /// names aren't secret, so there's no need to hide which one matched.
+    pub fn check_client_cn(&self, client_cn: &[u8]) -> Option<(&Key, &[u8])> {
+        if client_cn.is_empty() {
+            return None;
         }
 
-        // This is specially designed to avoid detection of both key length and matched key (if
-        // multiple keys are available).
+        let index = self.client_cns.iter().position(|c| **c == *client_cn)?;
+        Some((&self.key_set[index], &self.scopes[index]))
+    }
 
-        let mut match_found = false;
+    fn check_credentials(&self, user: Option<&[u8]>, key: &[u8]) -> KeyCheck<'_> {
+        // Check for correct syntax. This part isn't security-critical, but it does have to be done
+        // up front, as the comparisons below rely on it.
+        let user_valid = match user {
+            Some(user) => is_valid_key_user(user),
+            None => true,
+        };
+        let key_valid = is_valid_hex_string(key);
 
-        for trusted_key in self.key_set.iter() {
-            let mut current_matched = true;
+        // This is specially designed to avoid detection of the username and key lengths, as well
+        // as which key matched (if multiple keys are available).
 
-            for (&left, &right) in key.iter().zip(&trusted_key.raw) {
-                current_matched =
-                    std::hint::black_box(current_matched & (normalize_hex(left) == right));
+        // Hashed keys are hashed from the same normalized form they were generated from.
+        let mut normalized = [0; MAX_KEY_LEN];
+        let normalized = if key_valid {
+            for (target, &b) in normalized.iter_mut().zip(key) {
+                *target = normalize_hex(b);
             }
+            &normalized[..key.len()]
+        } else {
+            &[]
+        };
+
+        let mut user_found = false;
+        // The matched key's index plus one, so zero can mean none matched.
+        let mut match_index = 0_usize;
 
-            match_found = std::hint::black_box(match_found | current_matched);
+        for (i, trusted_key) in self.key_set.iter().enumerate() {
+            let user_matched = user_valid
+                & match user {
+                    Some(user) => padded_matches(user, &trusted_key.user, |b| b),
+                    None => true,
+                };
+            // Branching on this only depends on the configured keys, not the request, so every
+            // request against the same key set costs the same.
+            let key_matched = key_valid
+                & match trusted_key.hash() {
+                    Some(hash) => hash.matches(normalized),
+                    None => padded_matches(key, &trusted_key.raw, normalize_hex),
+                };
+            let current_matched = user_matched & key_matched;
+
+            user_found = std::hint::black_box(user_found | user_matched);
+
+            // Select it without branching on whether it matched.
+            let mask = usize::from(current_matched).wrapping_neg();
+            match_index = std::hint::black_box((i.wrapping_add(1) & mask) | (match_index & !mask));
         }
 
-        std::hint::black_box(match_found)
+        match std::hint::black_box(match_index).checked_sub(1) {
+            Some(index) => KeyCheck::Matched(&self.key_set[index], &self.scopes[index]),
+            None if user_found || user.is_none() => KeyCheck::UnknownKey,
+            None => KeyCheck::WrongUser,
+        }
     }
 }
 
@@ -207,6 +685,19 @@ impl KeySet {
 mod tests {
     use super::*;
 
+    fn check_key(key_set: &KeySet, key: &[u8]) -> bool {
+        matches!(key_set.check(DEFAULT_KEY_USER, key), KeyCheck::Matched(..))
+    }
+
+    fn build_from_file(file_name: &[u8], contents: &[u8]) -> Option<KeySet> {
+        let mut builder = KeySetBuilder::new();
+        match builder.push_file(file_name, contents) {
+            KeyPushResult::Success => Some(builder.finish()),
+            KeyPushResult::Invalid => None,
+            KeyPushResult::TooManyKeys => unreachable!(),
+        }
+    }
+
     #[test]
     fn does_not_create_key_from_invalid_hex_string_of_even_length() {
         let mut builder = KeySetBuilder::new();
@@ -237,78 +728,511 @@ mod tests {
     #[test]
     fn rejects_if_empty_set() {
         let key_set = KeySet::build(&[]);
-        assert!(!key_set.check_key(b"definitely a non-hex string with even length"));
+        assert!(!check_key(
+            &key_set,
+            b"definitely a non-hex string with even length"
+        ));
     }
 
     #[test]
     fn rejects_non_hex_keys_with_even_length() {
         let key_set = KeySet::build(&[b"abcdef0123456789"]);
-        assert!(!key_set.check_key(b"definitely a non-hex string with even length"));
+        assert!(!check_key(
+            &key_set,
+            b"definitely a non-hex string with even length"
+        ));
     }
 
     #[test]
     fn rejects_non_hex_keys_with_odd_length() {
         let key_set = KeySet::build(&[b"abcdef0123456789"]);
-        assert!(!key_set.check_key(b"definitely a non-hex string with odd length"));
+        assert!(!check_key(
+            &key_set,
+            b"definitely a non-hex string with odd length"
+        ));
     }
 
     #[test]
     fn rejects_hex_keys_with_odd_length() {
         let key_set = KeySet::build(&[b"abcdef0123456789"]);
-        assert!(!key_set.check_key(b"0123456789abcdef1"));
+        assert!(!check_key(&key_set, b"0123456789abcdef1"));
     }
 
     #[test]
     fn checks_hex_lower_against_single_hex_lower_match() {
         let key_set = KeySet::build(&[b"0123456789abcdef"]);
-        assert!(key_set.check_key(b"0123456789abcdef"));
+        assert!(check_key(&key_set, b"0123456789abcdef"));
     }
 
     #[test]
     fn checks_hex_lower_against_single_hex_upper_match() {
         let key_set = KeySet::build(&[b"0123456789ABCDEF"]);
-        assert!(key_set.check_key(b"0123456789abcdef"));
+        assert!(check_key(&key_set, b"0123456789abcdef"));
     }
 
     #[test]
     fn checks_hex_upper_against_single_hex_lower_match() {
         let key_set = KeySet::build(&[b"0123456789abcdef"]);
-        assert!(key_set.check_key(b"0123456789ABCDEF"));
+        assert!(check_key(&key_set, b"0123456789ABCDEF"));
     }
 
     #[test]
     fn checks_hex_upper_against_single_hex_upper_match() {
         let key_set = KeySet::build(&[b"0123456789ABCDEF"]);
-        assert!(key_set.check_key(b"0123456789ABCDEF"));
+        assert!(check_key(&key_set, b"0123456789ABCDEF"));
     }
 
     #[test]
     fn rejects_against_single_mismatch() {
         let key_set = KeySet::build(&[b"abcdef0123456789"]);
-        assert!(!key_set.check_key(b"0123456789abcdef"));
+        assert!(!check_key(&key_set, b"0123456789abcdef"));
     }
 
     #[test]
     fn checks_against_multi_match_one() {
         let key_set = KeySet::build(&[b"0123456789abcdef", b"aaaaaaaaaaaaaaaa"]);
-        assert!(key_set.check_key(b"0123456789abcdef"));
+        assert!(check_key(&key_set, b"0123456789abcdef"));
     }
 
     #[test]
     fn checks_against_multi_match_second() {
         let key_set = KeySet::build(&[b"aaaaaaaaaaaaaaaa", b"0123456789abcdef"]);
-        assert!(key_set.check_key(b"0123456789abcdef"));
+        assert!(check_key(&key_set, b"0123456789abcdef"));
     }
 
     #[test]
     fn checks_against_multi_match_all() {
         let key_set = KeySet::build(&[b"0123456789abcdef", b"0123456789abcdef"]);
-        assert!(key_set.check_key(b"0123456789abcdef"));
+        assert!(check_key(&key_set, b"0123456789abcdef"));
     }
 
     #[test]
     fn rejects_against_multi_match_none() {
         let key_set = KeySet::build(&[b"abcdef0123456789", b"aaaaaaaaaaaaaaaa"]);
-        assert!(!key_set.check_key(b"0123456789abcdef"));
+        assert!(!check_key(&key_set, b"0123456789abcdef"));
+    }
+
+    #[test]
+    fn rejects_prefix_of_key() {
+        let key_set = KeySet::build(&[b"0123456789abcdef"]);
+        assert!(!check_key(&key_set, b"01234567"));
+    }
+
+    #[test]
+    fn accepts_max_length_key() {
+        let key = [b'a'; MAX_KEY_LEN];
+        let key_set = KeySet::build(&[&key]);
+        assert!(check_key(&key_set, &key));
+    }
+
+    #[test]
+    fn returns_matched_key() {
+        let key_set = KeySet::build_named(&[
+            (b"aaaaaaaaaaaaaaaa", b"first", b"metrics"),
+            (b"0123456789abcdef", b"second", b"metrics"),
+        ]);
+        assert_eq!(
+            key_set.check(b"metrics", b"0123456789ABCDEF"),
+            KeyCheck::Matched(&key_set.insecure_view_keys()[1], &[])
+        );
+    }
+
+    #[test]
+    fn checks_against_custom_user() {
+        let key_set = KeySet::build_named(&[(b"0123456789abcdef", b"agent", b"grafana")]);
+        assert_eq!(
+            key_set.check(b"grafana", b"0123456789abcdef"),
+            KeyCheck::Matched(&key_set.insecure_view_keys()[0], &[])
+        );
+    }
+
+    #[test]
+    fn rejects_default_user_for_custom_user_key() {
+        let key_set = KeySet::build_named(&[(b"0123456789abcdef", b"agent", b"grafana")]);
+        assert_eq!(
+            key_set.check(b"metrics", b"0123456789abcdef"),
+            KeyCheck::WrongUser
+        );
+    }
+
+    #[test]
+    fn rejects_prefix_of_user() {
+        let key_set = KeySet::build_named(&[(b"0123456789abcdef", b"agent", b"grafana")]);
+        assert_eq!(
+            key_set.check(b"graf", b"0123456789abcdef"),
+            KeyCheck::WrongUser
+        );
+    }
+
+    #[test]
+    fn rejects_user_with_trailing_nul() {
+        let key_set = KeySet::build(&[b"0123456789abcdef"]);
+        assert_eq!(
+            key_set.check(b"metrics\0", b"0123456789abcdef"),
+            KeyCheck::WrongUser
+        );
+    }
+
+    #[test]
+    fn reports_unknown_key_for_known_user() {
+        let key_set = KeySet::build_named(&[
+            (b"0123456789abcdef", b"first", b"grafana"),
+            (b"aaaaaaaaaaaaaaaa", b"second", b"prometheus"),
+        ]);
+        assert_eq!(
+            key_set.check(b"grafana", b"aaaaaaaaaaaaaaaa"),
+            KeyCheck::UnknownKey
+        );
+    }
+
+    #[test]
+    fn reports_unknown_key_for_invalid_key_of_known_user() {
+        let key_set = KeySet::build(&[b"0123456789abcdef"]);
+        assert_eq!(key_set.check(b"metrics", b""), KeyCheck::UnknownKey);
+    }
+
+    #[test]
+    fn reports_wrong_user_for_empty_set() {
+        let key_set = KeySet::build(&[]);
+        assert_eq!(
+            key_set.check(b"metrics", b"0123456789abcdef"),
+            KeyCheck::WrongUser
+        );
+    }
+
+    #[test]
+    fn reads_bare_key_file_with_default_name_and_user() {
+        let key_set = build_from_file(b"grafana-agent.key", b"  0123456789abcdef\n").unwrap();
+        assert_eq!(
+            key_set,
+            KeySet::build_named(&[(b"0123456789abcdef", b"grafana-agent", b"metrics")])
+        );
+    }
+
+    #[test]
+    fn uses_whole_file_name_without_key_extension() {
+        let key_set = build_from_file(b"grafana-agent.txt", b"0123456789abcdef").unwrap();
+        assert_eq!(
+            key_set,
+            KeySet::build_named(&[(b"0123456789abcdef", b"grafana-agent.txt", b"metrics")])
+        );
+    }
+
+    #[test]
+    fn leaves_key_unnamed_if_file_name_is_not_a_valid_name() {
+        let key_set = build_from_file(b"grafana agent.key", b"0123456789abcdef").unwrap();
+        assert_eq!(
+            key_set,
+            KeySet::build_named(&[(b"0123456789abcdef", b"", b"metrics")])
+        );
+    }
+
+    #[test]
+    fn reads_key_file_with_fields() {
+        let key_set = build_from_file(
+            b"2024-01-01.key",
+            b"# Scraper for the shared Grafana instance\n\
+            name = grafana-agent\n\
+            user = grafana\n\
+            \n\
+            key = 0123456789ABCDEF\n",
+        )
+        .unwrap();
+        assert_eq!(
+            key_set,
+            KeySet::build_named(&[(b"0123456789abcdef", b"grafana-agent", b"grafana")])
+        );
+    }
+
+    #[test]
+    fn reads_key_file_with_only_key_field() {
+        let key_set = build_from_file(b"grafana-agent.key", b"key=0123456789abcdef").unwrap();
+        assert_eq!(
+            key_set,
+            KeySet::build_named(&[(b"0123456789abcdef", b"grafana-agent", b"metrics")])
+        );
+    }
+
+    #[test]
+    fn rejects_key_file_without_key_field() {
+        assert_eq!(
+            build_from_file(b"test.key", b"name=grafana-agent\nuser=grafana\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_key_file_with_unknown_field() {
+        assert_eq!(
+            build_from_file(b"test.key", b"key=0123456789abcdef\nusername=grafana\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_key_file_with_duplicate_field() {
+        assert_eq!(
+            build_from_file(b"test.key", b"key=0123456789abcdef\nkey=aaaaaaaaaaaaaaaa\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_key_file_with_line_missing_equals() {
+        assert_eq!(
+            build_from_file(b"test.key", b"key=0123456789abcdef\ngrafana\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_key_file_with_invalid_name() {
+        assert_eq!(
+            build_from_file(b"test.key", b"key=0123456789abcdef\nname=grafana\"agent\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_key_file_with_invalid_user() {
+        assert_eq!(
+            build_from_file(b"test.key", b"key=0123456789abcdef\nuser=gra:fana\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_key_file_with_invalid_key() {
+        assert_eq!(
+            build_from_file(b"test.key", b"key=0123456789abcdefg\nname=grafana-agent\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn reads_key_file_with_scope() {
+        let key_set = build_from_file(
+            b"team-a.key",
+            b"key=0123456789abcdef\n\
+            scope_families=journald_messages_ingested*\n\
+            scope_services=team-a-*, shared.service\n",
+        )
+        .unwrap();
+        assert_eq!(
+            key_set.check(b"metrics", b"0123456789abcdef"),
+            KeyCheck::Matched(
+                &key_set.insecure_view_keys()[0],
+                b"services=team-a-*,shared.service\nfamilies=journald_messages_ingested*"
+            )
+        );
+    }
+
+    #[test]
+    fn leaves_key_unscoped_without_scope_fields() {
+        let key_set =
+            build_from_file(b"test.key", b"key=0123456789abcdef\nuser=grafana\n").unwrap();
+        assert_eq!(&*key_set.scopes()[0], b"");
+    }
+
+    #[test]
+    fn rejects_key_file_with_invalid_scope() {
+        assert_eq!(
+            build_from_file(
+                b"test.key",
+                b"key=0123456789abcdef\nscope_users=alice,,bob\n"
+            ),
+            None
+        );
+    }
+
+    // The hash of `0123456789abcdef` with a salt of `000102...0f`.
+    const KEY_HASH: &[u8] = b"sha256:000102030405060708090a0b0c0d0e0f:\
+        6a3fbf35b1fdd0a0cbd438e6852f755236021790c2855bc664beea0ae1a88eab";
+
+    fn build_hashed(keys: &[&[u8]]) -> KeySet {
+        let hashes: Vec<_> = keys.iter().map(|key| hash_hex_key(key).unwrap()).collect();
+        KeySet::build_hashed(&hashes)
+    }
+
+    #[test]
+    fn checks_against_hashed_key() {
+        let key_set = build_hashed(&[b"0123456789abcdef"]);
+        assert!(check_key(&key_set, b"0123456789abcdef"));
+        assert!(!check_key(&key_set, b"0123456789abcdee"));
+        assert!(!check_key(&key_set, b"01234567"));
+    }
+
+    #[test]
+    fn checks_hashed_keys_case_insensitively() {
+        let key_set = build_hashed(&[b"0123456789ABCDEF"]);
+        assert!(check_key(&key_set, b"0123456789abcdef"));
+        assert!(check_key(&key_set, b"0123456789ABCDEF"));
+    }
+
+    #[test]
+    fn rejects_empty_key_against_hashed_key() {
+        let key_set = build_hashed(&[b"0123456789abcdef"]);
+        assert_eq!(key_set.check(b"metrics", b""), KeyCheck::UnknownKey);
+    }
+
+    #[test]
+    fn checks_against_mixed_plain_and_hashed_keys() {
+        let mut builder = KeySetBuilder::new();
+        assert_eq!(
+            builder.push_hex(b"aaaaaaaaaaaaaaaa"),
+            KeyPushResult::Success
+        );
+        let hash = hash_hex_key(b"0123456789abcdef").unwrap();
+        assert_eq!(builder.push_hashed(&hash), KeyPushResult::Success);
+        let key_set = builder.finish();
+
+        assert_eq!(
+            key_set.check(b"metrics", b"0123456789abcdef"),
+            KeyCheck::Matched(&key_set.insecure_view_keys()[1], &[])
+        );
+        assert_eq!(
+            key_set.check(b"metrics", b"aaaaaaaaaaaaaaaa"),
+            KeyCheck::Matched(&key_set.insecure_view_keys()[0], &[])
+        );
+    }
+
+    #[test]
+    fn does_not_hash_invalid_keys() {
+        assert_eq!(hash_hex_key(b"0123456789abcdefg"), None);
+        assert_eq!(hash_hex_key(b"0123456789abcdef1"), None);
+        assert_eq!(hash_hex_key(b""), None);
+    }
+
+    #[test]
+    fn reads_key_file_with_key_hash() {
+        let mut contents = b"name=grafana-agent\nkey_hash=".to_vec();
+        contents.extend_from_slice(KEY_HASH);
+        let key_set = build_from_file(b"test.key", &contents).unwrap();
+
+        let key = &key_set.insecure_view_keys()[0];
+        assert_eq!(key.insecure_get_value(), b"");
+        assert_eq!(key.name(), b"grafana-agent");
+        assert_eq!(key.hash(), KeyHash::parse(KEY_HASH));
+        assert!(check_key(&key_set, b"0123456789ABCDEF"));
+        assert!(!check_key(&key_set, b"aaaaaaaaaaaaaaaa"));
+    }
+
+    #[test]
+    fn rejects_key_file_with_both_key_and_key_hash() {
+        let mut contents = b"key=0123456789abcdef\nkey_hash=".to_vec();
+        contents.extend_from_slice(KEY_HASH);
+        assert_eq!(build_from_file(b"test.key", &contents), None);
+    }
+
+    #[test]
+    fn rejects_key_file_with_invalid_key_hash() {
+        assert_eq!(
+            build_from_file(b"test.key", b"key_hash=sha256:0011:2233\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn checks_token_against_any_user() {
+        let key_set = KeySet::build_named(&[
+            (b"aaaaaaaaaaaaaaaa", b"first", b"grafana"),
+            (b"0123456789abcdef", b"second", b"prometheus"),
+        ]);
+        assert_eq!(
+            key_set.check_token(b"0123456789ABCDEF"),
+            KeyCheck::Matched(&key_set.insecure_view_keys()[1], &[])
+        );
+    }
+
+    #[test]
+    fn checks_token_against_hashed_key() {
+        let key_set = build_hashed(&[b"0123456789abcdef"]);
+        assert_eq!(
+            key_set.check_token(b"0123456789abcdef"),
+            KeyCheck::Matched(&key_set.insecure_view_keys()[0], &[])
+        );
+    }
+
+    #[test]
+    fn reports_unknown_key_for_mismatched_token() {
+        let key_set = KeySet::build(&[b"0123456789abcdef"]);
+        assert_eq!(
+            key_set.check_token(b"aaaaaaaaaaaaaaaa"),
+            KeyCheck::UnknownKey
+        );
+        assert_eq!(key_set.check_token(b""), KeyCheck::UnknownKey);
+        assert_eq!(
+            KeySet::build(&[]).check_token(b"0123456789abcdef"),
+            KeyCheck::UnknownKey
+        );
+    }
+
+    #[test]
+    fn reads_key_file_with_client_cn_and_no_key() {
+        let key_set = build_from_file(
+            b"grafana-agent.key",
+            b"client_cn = Grafana Agent\nscope_users=root\n",
+        )
+        .unwrap();
+
+        let key = &key_set.insecure_view_keys()[0];
+        assert_eq!(key.insecure_get_value(), b"");
+        assert_eq!(key.hash(), None);
+        assert_eq!(key.name(), b"grafana-agent");
+        assert_eq!(&*key_set.client_cns()[0], b"Grafana Agent");
+        assert_eq!(
+            key_set.check_client_cn(b"Grafana Agent"),
+            Some((key, &*key_set.scopes()[0]))
+        );
+    }
+
+    #[test]
+    fn reads_key_file_with_client_cn_and_key() {
+        let key_set = build_from_file(
+            b"grafana-agent.key",
+            b"key=0123456789abcdef\nclient_cn=grafana.example.com\n",
+        )
+        .unwrap();
+        assert_eq!(
+            key_set,
+            KeySet::build_client_cns(&[(
+                Some(b"0123456789abcdef"),
+                b"grafana.example.com",
+                b"grafana-agent"
+            )])
+        );
+        assert!(check_key(&key_set, b"0123456789abcdef"));
+    }
+
+    #[test]
+    fn rejects_key_file_with_invalid_client_cn() {
+        assert_eq!(build_from_file(b"test.key", b"client_cn=\n"), None);
+        assert_eq!(build_from_file(b"test.key", b"client_cn=a\x01b\n"), None);
+        let mut contents = b"client_cn=".to_vec();
+        contents.extend_from_slice(&[b'a'; MAX_KEY_CLIENT_CN_LEN + 1]);
+        assert_eq!(build_from_file(b"test.key", &contents), None);
+    }
+
+    #[test]
+    fn never_matches_keyless_entry_by_key() {
+        let key_set = KeySet::build_client_cns(&[(None, b"grafana", b"")]);
+        assert_eq!(key_set.check(b"metrics", b""), KeyCheck::UnknownKey);
+        assert_eq!(key_set.check(b"metrics", b"00000000"), KeyCheck::UnknownKey);
+        assert_eq!(key_set.check_token(b""), KeyCheck::UnknownKey);
+    }
+
+    #[test]
+    fn checks_client_cn_against_first_mapped_key() {
+        let key_set = KeySet::build_client_cns(&[
+            (Some(b"0123456789abcdef"), b"", b"unmapped"),
+            (None, b"grafana", b"first"),
+            (None, b"grafana", b"second"),
+        ]);
+        let (key, scope) = key_set.check_client_cn(b"grafana").unwrap();
+        assert_eq!(key.name(), b"first");
+        assert_eq!(scope, b"");
+        assert_eq!(key_set.check_client_cn(b"Grafana"), None);
+        assert_eq!(key_set.check_client_cn(b"grafan"), None);
+        assert_eq!(key_set.check_client_cn(b""), None);
     }
 }
@@ -0,0 +1,202 @@
+//! Hashed keys let key files hold only what's needed to check a key, not the key itself.
+//!
+//! These are salted SHA-256 rather than something deliberately slow like argon2id. Every key in
+//! the set is checked on every request, so a slow hash would let a flood of bad passwords pin the
+//! CPU. Keys are meant to be long random strings anyways, so there's little for a slow hash to
+//! protect against.
+
+use crate::prelude::*;
+
+use ring::digest;
+
+pub const KEY_SALT_LEN: usize = 16;
+pub const KEY_DIGEST_LEN: usize = 32;
+pub const KEY_HASH_LEN: usize = KEY_SALT_LEN + KEY_DIGEST_LEN;
+
+const KEY_HASH_ALGORITHM: &[u8] = b"sha256";
+// `sha256:SALT:DIGEST`, with both in hexadecimal.
+const FORMATTED_KEY_HASH_LEN: usize = KEY_HASH_ALGORITHM.len() + 2 + KEY_HASH_LEN * 2;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct KeyHash {
+    pub salt: [u8; KEY_SALT_LEN],
+    pub digest: [u8; KEY_DIGEST_LEN],
+}
+
+impl fmt::Debug for KeyHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KeyHash(")?;
+        f.write_str(&String::from_utf8_lossy(&self.format()))?;
+        f.write_str(")")
+    }
+}
+
+fn from_hex_digit(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte.wrapping_sub(b'0')),
+        b'a'..=b'f' => Some(byte.wrapping_sub(b'a' - 10)),
+        b'A'..=b'F' => Some(byte.wrapping_sub(b'A' - 10)),
+        _ => None,
+    }
+}
+
+fn parse_hex<const N: usize>(hex: &[u8]) -> Option<[u8; N]> {
+    let mut result = [0; N];
+    if hex.len() != N.wrapping_mul(2) {
+        return None;
+    }
+    for (target, pair) in result.iter_mut().zip(hex.chunks_exact(2)) {
+        *target = from_hex_digit(pair[0])?.wrapping_shl(4) | from_hex_digit(pair[1])?;
+    }
+    Some(result)
+}
+
+fn push_hex(target: &mut Vec<u8>, bytes: &[u8]) {
+    for &byte in bytes {
+        target.extend_from_slice(&to_hex_pair(byte).map(|b| b.to_ascii_lowercase()));
+    }
+}
+
+/// Hashes a key with the given salt. `key` is expected to be already normalized, so that keys
+/// differing only in case hash the same.
+pub fn digest_key(salt: &[u8; KEY_SALT_LEN], key: &[u8]) -> [u8; KEY_DIGEST_LEN] {
+    let mut context = digest::Context::new(&digest::SHA256);
+    context.update(salt);
+    context.update(key);
+    let mut result = [0; KEY_DIGEST_LEN];
+    result.copy_from_slice(context.finish().as_ref());
+    result
+}
+
+impl KeyHash {
+    /// Hashes a normalized key with a freshly generated salt.
+    pub fn generate(key: &[u8]) -> Self {
+        use ring::rand::SecureRandom;
+
+        let mut salt = [0; KEY_SALT_LEN];
+        // This only fails if the OS can't provide randomness at all.
+        if ring::rand::SystemRandom::new().fill(&mut salt).is_err() {
+            panic!("Failed to generate a key salt.");
+        }
+        Self {
+            salt,
+            digest: digest_key(&salt, key),
+        }
+    }
+
+    /// Parses a key file's `key_hash` field, of the form `sha256:SALT:DIGEST` with both parts in
+    /// hexadecimal.
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        let mut parts = value.split(|&b| b == b':');
+        if parts.next()? != KEY_HASH_ALGORITHM {
+            return None;
+        }
+        let salt = parse_hex(parts.next()?)?;
+        let digest = parse_hex(parts.next()?)?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self { salt, digest })
+    }
+
+    /// The inverse of `parse`.
+    pub fn format(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(FORMATTED_KEY_HASH_LEN);
+        result.extend_from_slice(KEY_HASH_ALGORITHM);
+        result.push(b':');
+        push_hex(&mut result, &self.salt);
+        result.push(b':');
+        push_hex(&mut result, &self.digest);
+        result
+    }
+
+    /// Checks a normalized key against this hash, without the comparison itself leaking where
+    /// the digests first differ.
+    pub fn matches(&self, key: &[u8]) -> bool {
+        ring::constant_time::verify_slices_are_equal(&digest_key(&self.salt, key), &self.digest)
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALT_HEX: &str = "000102030405060708090a0b0c0d0e0f";
+    const SALT: [u8; KEY_SALT_LEN] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+
+    #[test]
+    fn digests_salt_then_key() {
+        // Checked against `(printf '000102...0f' | xxd -r -p; printf 0123456789abcdef) | sha256sum`.
+        let hash = KeyHash {
+            salt: SALT,
+            digest: digest_key(&SALT, b"0123456789abcdef"),
+        };
+        assert_eq!(
+            String::from_utf8(hash.format()).unwrap(),
+            format!(
+                "sha256:{SALT_HEX}:{}",
+                "6a3fbf35b1fdd0a0cbd438e6852f755236021790c2855bc664beea0ae1a88eab"
+            )
+        );
+    }
+
+    #[test]
+    fn parses_what_was_formatted() {
+        let hash = KeyHash {
+            salt: SALT,
+            digest: [0xAB; KEY_DIGEST_LEN],
+        };
+        assert_eq!(KeyHash::parse(&hash.format()), Some(hash));
+    }
+
+    #[test]
+    fn parses_uppercase_hex() {
+        let value = format!("sha256:{}:{}", SALT_HEX.to_uppercase(), "AB".repeat(32));
+        assert_eq!(
+            KeyHash::parse(value.as_bytes()),
+            Some(KeyHash {
+                salt: SALT,
+                digest: [0xAB; KEY_DIGEST_LEN],
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_hashes() {
+        let digest = "ab".repeat(32);
+        let short_digest = "ab".repeat(31);
+        for value in [
+            String::new(),
+            "sha256".into(),
+            format!("sha256:{SALT_HEX}"),
+            format!("sha512:{SALT_HEX}:{digest}"),
+            format!("sha256:{SALT_HEX}:{digest}:"),
+            format!("sha256:{SALT_HEX}:{short_digest}"),
+            format!("sha256:{SALT_HEX}00:{digest}"),
+            format!("sha256:{SALT_HEX}:{short_digest}zz"),
+        ] {
+            assert_eq!(KeyHash::parse(value.as_bytes()), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn matches_only_the_hashed_key() {
+        let hash = KeyHash::generate(b"0123456789abcdef");
+        assert!(hash.matches(b"0123456789abcdef"));
+        assert!(!hash.matches(b"0123456789abcde"));
+        assert!(!hash.matches(b"0123456789abcdee"));
+        assert!(!hash.matches(b""));
+    }
+
+    #[test]
+    fn generates_distinct_salts() {
+        let first = KeyHash::generate(b"0123456789abcdef");
+        let second = KeyHash::generate(b"0123456789abcdef");
+        assert_ne!(first.salt, second.salt);
+        assert_ne!(first.digest, second.digest);
+    }
+}
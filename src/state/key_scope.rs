@@ -0,0 +1,322 @@
+//! Scopes restrict which metrics a key's scrapes can see. The child only ever handles them in
+//! their encoded form, as it's the parent that does the filtering.
+//!
+//! The encoded form is the scope's fields in a fixed order, one `name=patterns` line each, with
+//! unrestricted fields left out entirely. So an unscoped key's encoded scope is empty.
+
+use crate::prelude::*;
+
+// Kept well under `PIPE_BUF`, as the child sends it along with each scoped metrics request.
+pub const MAX_KEY_SCOPE_LEN: usize = 1024;
+
+const FIELD_SERVICES: &[u8] = b"services";
+const FIELD_USERS: &[u8] = b"users";
+const FIELD_FAMILIES: &[u8] = b"families";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyScope {
+    // Each is empty if that part isn't restricted.
+    services: Box<[Box<[u8]>]>,
+    users: Box<[Box<[u8]>]>,
+    families: Box<[Box<[u8]>]>,
+}
+
+fn is_valid_pattern(pattern: &[u8]) -> bool {
+    !pattern.is_empty() && pattern.iter().all(|&b| b.is_ascii_graphic() && b != b',')
+}
+
+// Appends a field's patterns to the encoded scope, normalizing away any whitespace around them.
+fn encode_field(target: &mut Vec<u8>, name: &[u8], patterns: &[u8]) -> bool {
+    if !target.is_empty() {
+        target.push(b'\n');
+    }
+    target.extend_from_slice(name);
+    target.push(b'=');
+
+    for (i, pattern) in patterns.split(|&b| b == b',').enumerate() {
+        let pattern = trim_ascii(pattern);
+        if !is_valid_pattern(pattern) {
+            return false;
+        }
+        if i != 0 {
+            target.push(b',');
+        }
+        target.extend_from_slice(pattern);
+    }
+
+    true
+}
+
+fn decode_field(patterns: &[u8]) -> Option<Box<[Box<[u8]>]>> {
+    let mut result = Vec::new();
+    for pattern in patterns.split(|&b| b == b',') {
+        if !is_valid_pattern(pattern) {
+            return None;
+        }
+        result.push(Box::from(pattern));
+    }
+    Some(result.into())
+}
+
+/// Matches `value` against a glob pattern, where `*` matches any run of characters (including
+/// none) and `?` matches any one character.
+pub fn glob_matches(pattern: &[u8], value: &[u8]) -> bool {
+    let mut p = 0_usize;
+    let mut v = 0_usize;
+    // Where to resume from if the current attempt fails: just past the last `*` seen, and the
+    // value position it's currently assumed to have matched up to.
+    let mut backtrack = None::<(usize, usize)>;
+
+    while v < value.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p = p.wrapping_add(1);
+                backtrack = Some((p, v));
+            }
+            Some(&b) if b == b'?' || b == value[v] => {
+                p = p.wrapping_add(1);
+                v = v.wrapping_add(1);
+            }
+            _ => match backtrack {
+                // Let the `*` match one more character and try again.
+                Some((star_p, star_v)) => {
+                    p = star_p;
+                    v = star_v.wrapping_add(1);
+                    backtrack = Some((star_p, v));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&b| b == b'*')
+}
+
+fn any_matches(patterns: &[Box<[u8]>], value: &[u8]) -> bool {
+    patterns.is_empty() || patterns.iter().any(|p| glob_matches(p, value))
+}
+
+impl KeyScope {
+    /// Builds an encoded scope from a key file's scope fields, each a comma-separated list of
+    /// patterns. Returns `None` if any of the patterns are invalid or it's too long to send.
+    pub fn encode_fields(
+        services: Option<&[u8]>,
+        users: Option<&[u8]>,
+        families: Option<&[u8]>,
+    ) -> Option<Vec<u8>> {
+        let mut result = Vec::new();
+
+        for (name, patterns) in [
+            (FIELD_SERVICES, services),
+            (FIELD_USERS, users),
+            (FIELD_FAMILIES, families),
+        ] {
+            if let Some(patterns) = patterns {
+                if !encode_field(&mut result, name, patterns) {
+                    return None;
+                }
+            }
+        }
+
+        (result.len() <= MAX_KEY_SCOPE_LEN).then_some(result)
+    }
+
+    /// Parses an encoded scope. Returns `None` if it's not one `encode_fields` could've returned.
+    pub fn decode(encoded: &[u8]) -> Option<Self> {
+        let mut services = None;
+        let mut users = None;
+        let mut families = None;
+
+        if encoded.len() > MAX_KEY_SCOPE_LEN {
+            return None;
+        }
+
+        if !encoded.is_empty() {
+            for line in encoded.split(|&b| b == b'\n') {
+                let index = line.iter().position(|&b| b == b'=')?;
+                let target = match &line[..index] {
+                    FIELD_SERVICES => &mut services,
+                    FIELD_USERS => &mut users,
+                    FIELD_FAMILIES => &mut families,
+                    _ => return None,
+                };
+                if target
+                    .replace(decode_field(&line[index.wrapping_add(1)..])?)
+                    .is_some()
+                {
+                    return None;
+                }
+            }
+        }
+
+        Some(Self {
+            services: services.unwrap_or_default(),
+            users: users.unwrap_or_default(),
+            families: families.unwrap_or_default(),
+        })
+    }
+
+    /// `service` is the `service` label's value, so it's `?` for messages without one.
+    pub fn allows_service(&self, service: &[u8]) -> bool {
+        any_matches(&self.services, service)
+    }
+
+    /// Same here, with `user` being the `user` label's value.
+    pub fn allows_user(&self, user: &[u8]) -> bool {
+        any_matches(&self.users, user)
+    }
+
+    /// Families are matched by their OpenMetrics name, so counters are matched without their
+    /// `_total` suffix.
+    pub fn allows_family(&self, family: &[u8]) -> bool {
+        any_matches(&self.families, family)
+    }
+
+    pub fn restricts_messages(&self) -> bool {
+        !self.services.is_empty() || !self.users.is_empty()
+    }
+
+    pub fn restricts_families(&self) -> bool {
+        !self.families.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(
+        services: Option<&[u8]>,
+        users: Option<&[u8]>,
+        families: Option<&[u8]>,
+    ) -> Option<String> {
+        KeyScope::encode_fields(services, users, families)
+            .map(|encoded| String::from_utf8(encoded).unwrap())
+    }
+
+    #[test]
+    fn glob_matches_literals() {
+        assert!(glob_matches(b"foo.service", b"foo.service"));
+        assert!(!glob_matches(b"foo.service", b"foo.servic"));
+        assert!(!glob_matches(b"foo.service", b"foo.services"));
+        assert!(!glob_matches(b"foo", b""));
+        assert!(glob_matches(b"", b""));
+    }
+
+    #[test]
+    fn glob_matches_question_marks() {
+        assert!(glob_matches(b"foo?.service", b"foo1.service"));
+        assert!(!glob_matches(b"foo?.service", b"foo.service"));
+        assert!(!glob_matches(b"foo?.service", b"foo12.service"));
+    }
+
+    #[test]
+    fn glob_matches_stars() {
+        assert!(glob_matches(b"*", b""));
+        assert!(glob_matches(b"*", b"anything"));
+        assert!(glob_matches(b"team-a-*", b"team-a-"));
+        assert!(glob_matches(b"team-a-*", b"team-a-web.service"));
+        assert!(!glob_matches(b"team-a-*", b"team-b-web.service"));
+        assert!(glob_matches(b"*.service", b"foo.service"));
+        assert!(!glob_matches(b"*.service", b"foo.socket"));
+        assert!(glob_matches(b"a*b*c", b"aXXbYYbZZc"));
+        assert!(!glob_matches(b"a*b*c", b"aXXbYYbZZ"));
+        assert!(glob_matches(b"**a**", b"bab"));
+    }
+
+    #[test]
+    fn glob_matches_after_backtracking() {
+        assert!(glob_matches(b"*ab", b"aab"));
+        assert!(glob_matches(b"*aab", b"aaab"));
+        assert!(!glob_matches(b"*ab", b"aba"));
+    }
+
+    #[test]
+    fn encodes_empty_scope() {
+        assert_eq!(encode(None, None, None), Some(String::new()));
+    }
+
+    #[test]
+    fn encodes_all_fields_in_order() {
+        assert_eq!(
+            encode(
+                Some(b" team-a-* , shared "),
+                Some(b"alice"),
+                Some(b"journald_*")
+            ),
+            Some("services=team-a-*,shared\nusers=alice\nfamilies=journald_*".into())
+        );
+    }
+
+    #[test]
+    fn encodes_some_fields() {
+        assert_eq!(
+            encode(None, Some(b"alice,bob"), None),
+            Some("users=alice,bob".into())
+        );
+    }
+
+    #[test]
+    fn rejects_empty_patterns() {
+        assert_eq!(encode(Some(b""), None, None), None);
+        assert_eq!(encode(Some(b"a,,b"), None, None), None);
+        assert_eq!(encode(None, Some(b"alice,"), None), None);
+    }
+
+    #[test]
+    fn rejects_patterns_with_spaces() {
+        assert_eq!(encode(Some(b"foo bar"), None, None), None);
+    }
+
+    #[test]
+    fn rejects_scopes_too_long_to_send() {
+        let patterns = vec![b'a'; MAX_KEY_SCOPE_LEN];
+        assert_eq!(encode(Some(&patterns), None, None), None);
+    }
+
+    #[test]
+    fn decodes_empty_scope() {
+        let scope = KeyScope::decode(b"").unwrap();
+        assert!(!scope.restricts_messages());
+        assert!(scope.allows_service(b"anything.service"));
+        assert!(scope.allows_user(b"anyone"));
+        assert!(scope.allows_family(b"journald_faults"));
+    }
+
+    #[test]
+    fn decodes_what_was_encoded() {
+        let encoded = KeyScope::encode_fields(
+            Some(b"team-a-*,shared"),
+            Some(b"alice"),
+            Some(b"journald_*"),
+        )
+        .unwrap();
+        let scope = KeyScope::decode(&encoded).unwrap();
+
+        assert!(scope.restricts_messages());
+        assert!(scope.allows_service(b"team-a-web.service"));
+        assert!(scope.allows_service(b"shared"));
+        assert!(!scope.allows_service(b"team-b-web.service"));
+        assert!(!scope.allows_service(b"?"));
+        assert!(scope.allows_user(b"alice"));
+        assert!(!scope.allows_user(b"bob"));
+        assert!(scope.allows_family(b"journald_faults"));
+        assert!(!scope.allows_family(b"process_cpu_seconds"));
+    }
+
+    #[test]
+    fn decodes_only_families_as_not_restricting_messages() {
+        let scope = KeyScope::decode(b"families=journald_messages_ingested").unwrap();
+        assert!(!scope.restricts_messages());
+    }
+
+    #[test]
+    fn rejects_invalid_encoded_scopes() {
+        assert_eq!(KeyScope::decode(b"services"), None);
+        assert_eq!(KeyScope::decode(b"services="), None);
+        assert_eq!(KeyScope::decode(b"groups=a"), None);
+        assert_eq!(KeyScope::decode(b"users=a\nusers=b"), None);
+        assert_eq!(KeyScope::decode(b"users=a\n"), None);
+        assert_eq!(KeyScope::decode(b"users= a"), None);
+    }
+}
@@ -1,10 +1,24 @@
 mod byte_count_map;
+mod field_stats;
 pub mod ipc;
 mod key;
+mod key_hash;
+mod key_scope;
 mod message_key;
 mod prom;
+mod rate_limit_policy;
+mod scrape_stats;
+mod tls_certificate;
+mod tls_policy;
 
 pub use self::byte_count_map::*;
+pub use self::field_stats::*;
 pub use self::key::*;
+pub use self::key_hash::*;
+pub use self::key_scope::*;
 pub use self::message_key::*;
 pub use self::prom::*;
+pub use self::rate_limit_policy::*;
+pub use self::scrape_stats::*;
+pub use self::tls_certificate::*;
+pub use self::tls_policy::*;
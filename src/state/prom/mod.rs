@@ -1,11 +1,35 @@
+mod prom_otlp;
+#[cfg(test)]
+mod prom_otlp_tests;
+mod prom_protobuf;
+#[cfg(test)]
+mod prom_protobuf_tests;
+mod prom_remote_write;
+#[cfg(test)]
+mod prom_remote_write_tests;
+mod prom_scope;
+#[cfg(test)]
+mod prom_scope_tests;
 mod prom_state;
 #[cfg(test)]
 mod prom_state_tests;
+mod prom_statsd;
+#[cfg(test)]
+mod prom_statsd_tests;
+mod prom_text;
+#[cfg(test)]
+mod prom_text_tests;
 mod prom_write;
 #[cfg(test)]
 mod prom_write_tests;
 #[cfg(test)]
 mod prom_write_value_tests;
 
+pub use self::prom_otlp::*;
+pub use self::prom_protobuf::*;
+pub use self::prom_remote_write::*;
+pub use self::prom_scope::*;
 pub use self::prom_state::*;
+pub use self::prom_statsd::*;
+pub use self::prom_text::*;
 pub use self::prom_write::*;
@@ -0,0 +1,304 @@
+use crate::prelude::*;
+
+use super::prom_write::collect_last_seen;
+use super::prom_write::each_message_row;
+use super::prom_write::FieldCounterKind;
+use super::prom_write::MessageCounterKind;
+use super::prom_write::MessageRowLabels;
+
+// Encodes a snapshot as an OTLP `ExportMetricsServiceRequest`. The relevant parts of the schema
+// are:
+//
+// ```proto
+// message ExportMetricsServiceRequest { repeated ResourceMetrics resource_metrics = 1; }
+// message ResourceMetrics { Resource resource = 1; repeated ScopeMetrics scope_metrics = 2; }
+// message Resource { repeated KeyValue attributes = 1; }
+// message ScopeMetrics { InstrumentationScope scope = 1; repeated Metric metrics = 2; }
+// message InstrumentationScope { string name = 1; string version = 2; }
+// message Metric {
+//   string name = 1;
+//   string unit = 3;
+//   oneof data { Gauge gauge = 5; Sum sum = 7; }
+// }
+// message Gauge { repeated NumberDataPoint data_points = 1; }
+// message Sum {
+//   repeated NumberDataPoint data_points = 1;
+//   AggregationTemporality aggregation_temporality = 2;
+//   bool is_monotonic = 3;
+// }
+// message NumberDataPoint {
+//   repeated KeyValue attributes = 7;
+//   fixed64 start_time_unix_nano = 2;
+//   fixed64 time_unix_nano = 3;
+//   oneof value { double as_double = 4; sfixed64 as_int = 6; }
+// }
+// message KeyValue { string key = 1; AnyValue value = 2; }
+// message AnyValue { oneof value { string string_value = 1; } }
+// ```
+//
+// Each counter is a cumulative monotonic sum starting from when the exporter started, and the
+// names and attributes match the text exposition's metric families and labels.
+
+const AGGREGATION_TEMPORALITY_CUMULATIVE: u64 = 2;
+
+const SCOPE_NAME: &[u8] = b"journald-exporter";
+const SCOPE_VERSION: &[u8] = env!("CARGO_PKG_VERSION").as_bytes();
+
+fn write_key_value(target: &mut ProtobufWriter, field: u32, key: &[u8], value: &[u8]) {
+    let mut any_value = ProtobufWriter::new();
+    any_value.write_bytes(1, value);
+
+    let mut key_value = ProtobufWriter::new();
+    key_value.write_bytes(1, key);
+    key_value.write_message(2, any_value);
+
+    target.write_message(field, key_value);
+}
+
+enum PointValue {
+    Int(u64),
+    Double(f64),
+}
+
+struct PointsWriter<'a> {
+    // Either a `Sum` or a `Gauge`, as both put their data points in the same field.
+    data: ProtobufWriter,
+    environment: &'a PromEnvironment,
+    start_time_unix_nanos: Option<u64>,
+    time_unix_nanos: u64,
+}
+
+impl PointsWriter<'_> {
+    fn write(&mut self, attributes: &[(&[u8], &[u8])], value: PointValue) {
+        let mut point = ProtobufWriter::new();
+
+        for (key, value) in attributes {
+            write_key_value(&mut point, 7, key, value);
+        }
+
+        for (key, value) in self.environment.static_labels() {
+            write_key_value(&mut point, 7, key.as_bytes(), value.as_bytes());
+        }
+
+        if let Some(start_time_unix_nanos) = self.start_time_unix_nanos {
+            point.write_fixed64(2, start_time_unix_nanos);
+        }
+
+        point.write_fixed64(3, self.time_unix_nanos);
+
+        match value {
+            // Counters won't realistically ever exceed this.
+            PointValue::Int(value) => {
+                point.write_sfixed64(6, i64::try_from(value).unwrap_or(i64::MAX))
+            }
+            PointValue::Double(value) => point.write_double(4, value),
+        }
+
+        self.data.write_message(1, point);
+    }
+
+    fn write_message_rows(
+        &mut self,
+        kind: MessageCounterKind,
+        snapshot: &ByteCountSnapshot,
+        table: &UidGidTable,
+    ) -> bool {
+        if snapshot.is_empty() {
+            // Don't break sum
+            self.write(&[], PointValue::Int(0));
+            return true;
+        }
+
+        each_message_row(
+            self.environment.top_series,
+            snapshot,
+            table,
+            &kind,
+            |labels: MessageRowLabels, value| {
+                self.write(
+                    &[
+                        (b"service", labels.service),
+                        (b"priority", labels.priority.as_name_bytes()),
+                        (b"severity", &[labels.priority.as_severity_byte()]),
+                        (b"user", labels.user),
+                        (b"group", labels.group),
+                    ],
+                    PointValue::Int(value),
+                );
+                true
+            },
+        )
+    }
+}
+
+struct MetricsWriter<'a> {
+    scope_metrics: ProtobufWriter,
+    environment: &'a PromEnvironment,
+    time_unix_nanos: u64,
+}
+
+impl<'a> MetricsWriter<'a> {
+    fn points(&self, cumulative: bool) -> PointsWriter<'a> {
+        PointsWriter {
+            data: ProtobufWriter::new(),
+            environment: self.environment,
+            start_time_unix_nanos: cumulative.then(|| self.environment.created_unix_nanos()),
+            time_unix_nanos: self.time_unix_nanos,
+        }
+    }
+
+    fn write_metric(&mut self, name: &[u8], unit: &[u8], field: u32, data: ProtobufWriter) {
+        let mut metric = ProtobufWriter::new();
+        metric.write_bytes(1, name);
+        if !unit.is_empty() {
+            metric.write_bytes(3, unit);
+        }
+        metric.write_message(field, data);
+        self.scope_metrics.write_message(2, metric);
+    }
+
+    fn write_sum(&mut self, name: &[u8], unit: &[u8], points: PointsWriter) {
+        let mut sum = points.data;
+        sum.write_uint64(2, AGGREGATION_TEMPORALITY_CUMULATIVE);
+        sum.write_bool(3, true);
+        self.write_metric(name, unit, 7, sum);
+    }
+
+    fn write_gauge(&mut self, name: &[u8], unit: &[u8], points: PointsWriter) {
+        self.write_metric(name, unit, 5, points.data);
+    }
+}
+
+pub fn encode_otlp_metrics(
+    environment: &PromEnvironment,
+    snapshot: &PromSnapshot,
+    table: &UidGidTable,
+    time_unix_nanos: u64,
+) -> Option<Vec<u8>> {
+    let mut scope = ProtobufWriter::new();
+    scope.write_bytes(1, SCOPE_NAME);
+    scope.write_bytes(2, SCOPE_VERSION);
+
+    let mut writer = MetricsWriter {
+        scope_metrics: ProtobufWriter::new(),
+        environment,
+        time_unix_nanos,
+    };
+
+    writer.scope_metrics.write_message(1, scope);
+
+    let global_counters: [(&[u8], &[u8], u64); 8] = [
+        (b"journald_entries_ingested", b"", snapshot.entries_ingested),
+        (b"journald_fields_ingested", b"", snapshot.fields_ingested),
+        (
+            b"journald_data_ingested_bytes",
+            b"By",
+            snapshot.data_ingested_bytes,
+        ),
+        (b"journald_faults", b"", snapshot.faults),
+        (
+            b"journald_cursor_double_retries",
+            b"",
+            snapshot.cursor_double_retries,
+        ),
+        (
+            b"journald_unreadable_fields",
+            b"",
+            snapshot.unreadable_fields,
+        ),
+        (b"journald_corrupted_fields", b"", snapshot.corrupted_fields),
+        (b"journald_metrics_requests", b"", snapshot.metrics_requests),
+    ];
+
+    for (name, unit, value) in global_counters {
+        let mut points = writer.points(true);
+        points.write(&[], PointValue::Int(value));
+        writer.write_sum(name, unit, points);
+    }
+
+    if environment.field_stats {
+        let field_counters: [(&[u8], &[u8], FieldCounterKind); 4] = [
+            (b"journald_field_ingested", b"", FieldCounterKind::Ingested),
+            (
+                b"journald_field_ingested_bytes",
+                b"By",
+                FieldCounterKind::IngestedBytes,
+            ),
+            (
+                b"journald_field_unreadable",
+                b"",
+                FieldCounterKind::Unreadable,
+            ),
+            (
+                b"journald_field_corrupted",
+                b"",
+                FieldCounterKind::Corrupted,
+            ),
+        ];
+
+        for (name, unit, kind) in field_counters {
+            let mut points = writer.points(true);
+            for field in JournalField::ALL {
+                points.write(
+                    &[(b"field", field.as_name_bytes())],
+                    PointValue::Int(kind.value(snapshot.fields.get(field))),
+                );
+            }
+            writer.write_sum(name, unit, points);
+        }
+    }
+
+    let message_counters: [(&[u8], &[u8], MessageCounterKind); 2] = [
+        (
+            b"journald_messages_ingested",
+            b"",
+            MessageCounterKind::Lines,
+        ),
+        (
+            b"journald_messages_ingested_bytes",
+            b"By",
+            MessageCounterKind::Bytes,
+        ),
+    ];
+
+    for (name, unit, kind) in message_counters {
+        let mut points = writer.points(true);
+        if !points.write_message_rows(kind, &snapshot.messages_ingested, table) {
+            return None;
+        }
+        writer.write_sum(name, unit, points);
+    }
+
+    let last_seen = collect_last_seen(&snapshot.messages_ingested)?;
+
+    // Empty gauges aren't valid, so just leave it out until there's something to report.
+    if !last_seen.is_empty() {
+        let mut points = writer.points(false);
+        for (priority, service, last_seen) in last_seen {
+            points.write(
+                &[
+                    (b"service", service),
+                    (b"priority", priority.as_name_bytes()),
+                    (b"severity", &[priority.as_severity_byte()]),
+                ],
+                PointValue::Double(round_u64_f64(last_seen) / 1_000_000.0),
+            );
+        }
+        writer.write_gauge(
+            b"journald_service_last_message_timestamp_seconds",
+            b"s",
+            points,
+        );
+    }
+
+    let mut resource = ProtobufWriter::new();
+    write_key_value(&mut resource, 1, b"service.name", SCOPE_NAME);
+
+    let mut resource_metrics = ProtobufWriter::new();
+    resource_metrics.write_message(1, resource);
+    resource_metrics.write_message(2, writer.scope_metrics);
+
+    let mut request = ProtobufWriter::new();
+    request.write_message(1, resource_metrics);
+    Some(request.finish())
+}
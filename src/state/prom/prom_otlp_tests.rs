@@ -0,0 +1,336 @@
+use crate::prelude::*;
+
+use super::*;
+
+const TIME: u64 = 1_700_000_000_123_000_000;
+
+fn decode_key_value(data: &[u8]) -> String {
+    let key_value = read_protobuf_fields(data);
+    assert_eq!(key_value.len(), 2);
+    assert_eq!(key_value[0].0, 1);
+    assert_eq!(key_value[1].0, 2);
+    let any_value = read_protobuf_fields(key_value[1].1.bytes());
+    assert_eq!(any_value.len(), 1);
+    assert_eq!(any_value[0].0, 1);
+    format!("{}=\"{}\"", key_value[0].1.str(), any_value[0].1.str())
+}
+
+// Turns each data point into a line of `TYPE NAME UNIT {ATTRIBUTES} START TIME VALUE`, for easier
+// comparison. The resource and scope are checked separately.
+fn decode_metrics(request: &[u8]) -> Vec<String> {
+    let mut result = Vec::new();
+
+    let request = read_protobuf_fields(request);
+    assert_eq!(request.len(), 1);
+    assert_eq!(request[0].0, 1);
+
+    let resource_metrics = read_protobuf_fields(request[0].1.bytes());
+    assert_eq!(resource_metrics.len(), 2);
+    assert_eq!(resource_metrics[1].0, 2);
+
+    for (field, value) in read_protobuf_fields(resource_metrics[1].1.bytes()) {
+        if field == 1 {
+            continue;
+        }
+
+        assert_eq!(field, 2);
+
+        let mut name = "";
+        let mut unit = "-";
+        let mut kind = "";
+        let mut data = &[][..];
+
+        for (field, value) in read_protobuf_fields(value.bytes()) {
+            match field {
+                1 => name = value.str(),
+                3 => unit = value.str(),
+                5 => {
+                    kind = "gauge";
+                    data = value.bytes();
+                }
+                7 => {
+                    kind = "sum";
+                    data = value.bytes();
+                }
+                field => panic!("Unexpected metric field {field}"),
+            }
+        }
+
+        for (field, value) in read_protobuf_fields(data) {
+            match field {
+                1 => {
+                    let mut attributes = Vec::new();
+                    let mut start = String::from("-");
+                    let mut time = 0;
+                    let mut point_value = String::new();
+
+                    for (field, value) in read_protobuf_fields(value.bytes()) {
+                        match field {
+                            2 => start = value.fixed64().to_string(),
+                            3 => time = value.fixed64(),
+                            4 => point_value = format!("{}", f64::from_bits(value.fixed64())),
+                            6 => point_value = format!("{}i", value.fixed64()),
+                            7 => attributes.push(decode_key_value(value.bytes())),
+                            field => panic!("Unexpected data point field {field}"),
+                        }
+                    }
+
+                    assert_eq!(time, TIME);
+
+                    result.push(format!(
+                        "{kind} {name} {unit} {{{}}} {start} {point_value}",
+                        attributes.join(","),
+                    ));
+                }
+                2 => {
+                    assert_eq!(kind, "sum");
+                    assert_eq!(value.varint(), 2);
+                }
+                3 => {
+                    assert_eq!(kind, "sum");
+                    assert_eq!(value.varint(), 1);
+                }
+                field => panic!("Unexpected data field {field}"),
+            }
+        }
+    }
+
+    result
+}
+
+fn encode(environment: &PromEnvironment, snapshot: PromSnapshot) -> Vec<String> {
+    decode_metrics(
+        &encode_otlp_metrics(environment, &snapshot, &get_user_group_table(), TIME).unwrap(),
+    )
+}
+
+fn empty_snapshot() -> PromSnapshot {
+    PromSnapshot {
+        entries_ingested: 0,
+        fields_ingested: 0,
+        data_ingested_bytes: 0,
+        faults: 0,
+        cursor_double_retries: 0,
+        unreadable_fields: 0,
+        corrupted_fields: 0,
+        metrics_requests: 0,
+        messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
+    }
+}
+
+#[test]
+fn encodes_resource_and_scope() {
+    let request = encode_otlp_metrics(
+        &PromEnvironment::new(mock_system_time(123, 456)),
+        &empty_snapshot(),
+        &get_user_group_table(),
+        TIME,
+    )
+    .unwrap();
+
+    let request = read_protobuf_fields(&request);
+    let resource_metrics = read_protobuf_fields(request[0].1.bytes());
+    assert_eq!(resource_metrics[0].0, 1);
+
+    let resource = read_protobuf_fields(resource_metrics[0].1.bytes());
+    assert_eq!(resource.len(), 1);
+    assert_eq!(resource[0].0, 1);
+    assert_eq!(
+        decode_key_value(resource[0].1.bytes()),
+        "service.name=\"journald-exporter\""
+    );
+
+    let scope_metrics = read_protobuf_fields(resource_metrics[1].1.bytes());
+    assert_eq!(scope_metrics[0].0, 1);
+
+    let scope = read_protobuf_fields(scope_metrics[0].1.bytes());
+    assert_eq!(scope.len(), 2);
+    assert_eq!(scope[0].1.str(), "journald-exporter");
+    assert_eq!(scope[1].1.str(), env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn encodes_empty_snapshot() {
+    assert_eq!(
+        encode(
+            &PromEnvironment::new(mock_system_time(123, 456)),
+            empty_snapshot()
+        ),
+        [
+            "sum journald_entries_ingested - {} 123456000000 0i",
+            "sum journald_fields_ingested - {} 123456000000 0i",
+            "sum journald_data_ingested_bytes By {} 123456000000 0i",
+            "sum journald_faults - {} 123456000000 0i",
+            "sum journald_cursor_double_retries - {} 123456000000 0i",
+            "sum journald_unreadable_fields - {} 123456000000 0i",
+            "sum journald_corrupted_fields - {} 123456000000 0i",
+            "sum journald_metrics_requests - {} 123456000000 0i",
+            "sum journald_messages_ingested - {} 123456000000 0i",
+            "sum journald_messages_ingested_bytes By {} 123456000000 0i",
+        ]
+    );
+}
+
+#[test]
+fn encodes_global_counters_with_static_labels() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("env", "prod");
+
+    let actual = encode(
+        &environment,
+        PromSnapshot {
+            entries_ingested: 1,
+            fields_ingested: 5,
+            data_ingested_bytes: 123,
+            faults: 2,
+            cursor_double_retries: 3,
+            unreadable_fields: 4,
+            corrupted_fields: 6,
+            metrics_requests: 7,
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
+        },
+    );
+
+    assert_eq!(
+        actual,
+        [
+            "sum journald_entries_ingested - {env=\"prod\"} 123456000000 1i",
+            "sum journald_fields_ingested - {env=\"prod\"} 123456000000 5i",
+            "sum journald_data_ingested_bytes By {env=\"prod\"} 123456000000 123i",
+            "sum journald_faults - {env=\"prod\"} 123456000000 2i",
+            "sum journald_cursor_double_retries - {env=\"prod\"} 123456000000 3i",
+            "sum journald_unreadable_fields - {env=\"prod\"} 123456000000 4i",
+            "sum journald_corrupted_fields - {env=\"prod\"} 123456000000 6i",
+            "sum journald_metrics_requests - {env=\"prod\"} 123456000000 7i",
+            "sum journald_messages_ingested - {env=\"prod\"} 123456000000 0i",
+            "sum journald_messages_ingested_bytes By {env=\"prod\"} 123456000000 0i",
+        ]
+    );
+}
+
+#[test]
+fn encodes_message_counters_and_last_seen_gauges() {
+    let actual = encode(
+        &PromEnvironment::new(mock_system_time(123, 456)),
+        PromSnapshot {
+            messages_ingested: ByteCountSnapshot::build([
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(Some(123), Some(456), Some(b"foo"), Priority::Warning),
+                    lines: 2,
+                    bytes: 15,
+                    last_seen: 1_700_000_000_500_000,
+                },
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(None, None, None, Priority::Error),
+                    lines: 1,
+                    bytes: 5,
+                    last_seen: 0,
+                },
+            ]),
+            ..empty_snapshot()
+        },
+    );
+
+    assert_eq!(
+        &actual[8..],
+        [
+            "sum journald_messages_ingested - {service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\"} 123456000000 1i",
+            "sum journald_messages_ingested - {service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_bar\"} 123456000000 2i",
+            "sum journald_messages_ingested_bytes By {service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\"} 123456000000 5i",
+            "sum journald_messages_ingested_bytes By {service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_bar\"} 123456000000 15i",
+            "gauge journald_service_last_message_timestamp_seconds s {service=\"foo\",priority=\"WARNING\",severity=\"4\"} - 1700000000.5",
+        ]
+    );
+}
+
+#[test]
+fn encodes_top_series_with_the_rest_summed_into_other() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.top_series = std::num::NonZeroU32::new(1);
+
+    let actual = encode(
+        &environment,
+        PromSnapshot {
+            messages_ingested: ByteCountSnapshot::build([
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(None, None, Some(b"foo"), Priority::Informational),
+                    lines: 5,
+                    bytes: 50,
+                    last_seen: 0,
+                },
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(None, None, Some(b"bar"), Priority::Informational),
+                    lines: 2,
+                    bytes: 20,
+                    last_seen: 0,
+                },
+            ]),
+            ..empty_snapshot()
+        },
+    );
+
+    assert_eq!(
+        &actual[8..],
+        [
+            "sum journald_messages_ingested - {service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"?\",group=\"?\"} 123456000000 5i",
+            "sum journald_messages_ingested - {service=\"__other__\",priority=\"INFO\",severity=\"6\",user=\"__other__\",group=\"__other__\"} 123456000000 2i",
+            "sum journald_messages_ingested_bytes By {service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"?\",group=\"?\"} 123456000000 50i",
+            "sum journald_messages_ingested_bytes By {service=\"__other__\",priority=\"INFO\",severity=\"6\",user=\"__other__\",group=\"__other__\"} 123456000000 20i",
+        ]
+    );
+}
+
+#[test]
+fn encodes_field_stats_when_enabled() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.field_stats = true;
+
+    let actual = encode(
+        &environment,
+        PromSnapshot {
+            fields: FieldStatsSnapshot::build([(
+                JournalField::Uid,
+                FieldStatsEntry {
+                    ingested: 2,
+                    ingested_bytes: 8,
+                    unreadable: 1,
+                    corrupted: 3,
+                },
+            )]),
+            ..empty_snapshot()
+        },
+    );
+
+    assert_eq!(
+        &actual[8..32],
+        [
+            "sum journald_field_ingested - {field=\"_SYSTEMD_UNIT\"} 123456000000 0i",
+            "sum journald_field_ingested - {field=\"PRIORITY\"} 123456000000 0i",
+            "sum journald_field_ingested - {field=\"_UID\"} 123456000000 2i",
+            "sum journald_field_ingested - {field=\"_GID\"} 123456000000 0i",
+            "sum journald_field_ingested - {field=\"MESSAGE\"} 123456000000 0i",
+            "sum journald_field_ingested - {field=\"_CMDLINE\"} 123456000000 0i",
+            "sum journald_field_ingested_bytes By {field=\"_SYSTEMD_UNIT\"} 123456000000 0i",
+            "sum journald_field_ingested_bytes By {field=\"PRIORITY\"} 123456000000 0i",
+            "sum journald_field_ingested_bytes By {field=\"_UID\"} 123456000000 8i",
+            "sum journald_field_ingested_bytes By {field=\"_GID\"} 123456000000 0i",
+            "sum journald_field_ingested_bytes By {field=\"MESSAGE\"} 123456000000 0i",
+            "sum journald_field_ingested_bytes By {field=\"_CMDLINE\"} 123456000000 0i",
+            "sum journald_field_unreadable - {field=\"_SYSTEMD_UNIT\"} 123456000000 0i",
+            "sum journald_field_unreadable - {field=\"PRIORITY\"} 123456000000 0i",
+            "sum journald_field_unreadable - {field=\"_UID\"} 123456000000 1i",
+            "sum journald_field_unreadable - {field=\"_GID\"} 123456000000 0i",
+            "sum journald_field_unreadable - {field=\"MESSAGE\"} 123456000000 0i",
+            "sum journald_field_unreadable - {field=\"_CMDLINE\"} 123456000000 0i",
+            "sum journald_field_corrupted - {field=\"_SYSTEMD_UNIT\"} 123456000000 0i",
+            "sum journald_field_corrupted - {field=\"PRIORITY\"} 123456000000 0i",
+            "sum journald_field_corrupted - {field=\"_UID\"} 123456000000 3i",
+            "sum journald_field_corrupted - {field=\"_GID\"} 123456000000 0i",
+            "sum journald_field_corrupted - {field=\"MESSAGE\"} 123456000000 0i",
+            "sum journald_field_corrupted - {field=\"_CMDLINE\"} 123456000000 0i",
+        ]
+    );
+    assert_eq!(actual.len(), 34);
+}
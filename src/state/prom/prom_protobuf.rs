@@ -0,0 +1,508 @@
+use crate::prelude::*;
+
+use super::prom_write::collect_last_seen;
+use super::prom_write::each_message_row;
+use super::prom_write::each_process;
+use super::prom_write::FieldCounterKind;
+use super::prom_write::MessageCounterKind;
+use super::prom_write::MessageRowLabels;
+use super::prom_write::ProcessMetricKind;
+use super::prom_write::ProcessValue;
+use super::prom_write::BUILD_VERSION;
+use crate::state::HistogramSnapshot;
+use crate::state::ScrapeHistogram;
+use crate::state::ScrapeStatsSnapshot;
+
+// Encodes a snapshot in the Prometheus protobuf exposition format: a stream of `MetricFamily`
+// messages, each prefixed with its varint-encoded length. The relevant parts of the schema are:
+//
+// ```proto
+// message MetricFamily {
+//   string name = 1;
+//   MetricType type = 3;
+//   repeated Metric metric = 4;
+//   string unit = 5;
+// }
+// enum MetricType { COUNTER = 0; GAUGE = 1; HISTOGRAM = 4; }
+// message Metric {
+//   repeated LabelPair label = 1;
+//   Gauge gauge = 2;
+//   Counter counter = 3;
+//   Histogram histogram = 7;
+// }
+// message LabelPair { string name = 1; string value = 2; }
+// message Gauge { double value = 1; }
+// message Counter { double value = 1; google.protobuf.Timestamp created_timestamp = 3; }
+// message Histogram {
+//   uint64 sample_count = 1;
+//   double sample_sum = 2;
+//   repeated Bucket bucket = 3;
+//   google.protobuf.Timestamp created_timestamp = 15;
+// }
+// message Bucket { uint64 cumulative_count = 1; double upper_bound = 2; }
+// message Timestamp { int64 seconds = 1; int32 nanos = 2; }
+// ```
+//
+// Like the classic text format, counter families are named with their `_total` suffix. Help text
+// is left out, as it's optional and scrapers asking for this format don't surface it anyways. The
+// `+Inf` histogram bucket is also left out, as it's implied by the sample count.
+
+const METRIC_TYPE_COUNTER: u64 = 0;
+const METRIC_TYPE_GAUGE: u64 = 1;
+const METRIC_TYPE_HISTOGRAM: u64 = 4;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+struct FamilyWriter<'a> {
+    family: ProtobufWriter,
+    environment: &'a PromEnvironment,
+    metric_type: u64,
+}
+
+impl<'a> FamilyWriter<'a> {
+    fn new(
+        environment: &'a PromEnvironment,
+        name: &[u8],
+        unit: &[u8],
+        metric_type: u64,
+    ) -> FamilyWriter<'a> {
+        let mut family = ProtobufWriter::new();
+        family.write_bytes(1, name);
+        family.write_uint64(3, metric_type);
+        if !unit.is_empty() {
+            family.write_bytes(5, unit);
+        }
+
+        FamilyWriter {
+            family,
+            environment,
+            metric_type,
+        }
+    }
+
+    fn write(&mut self, labels: &[(&[u8], &[u8])], value: f64) {
+        self.write_with_created(labels, value, self.environment.created_unix_nanos());
+    }
+
+    // The created timestamp is only used for counters.
+    fn write_with_created(&mut self, labels: &[(&[u8], &[u8])], value: f64, created: u64) {
+        let mut metric = self.start_metric(labels);
+        let mut data = ProtobufWriter::new();
+        data.write_double(1, value);
+
+        if self.metric_type == METRIC_TYPE_COUNTER {
+            write_timestamp(&mut data, 3, created);
+            metric.write_message(3, data);
+        } else {
+            metric.write_message(2, data);
+        }
+
+        self.family.write_message(4, metric);
+    }
+
+    fn write_histogram(&mut self, histogram: ScrapeHistogram, snapshot: &HistogramSnapshot) {
+        // Durations are tracked in microseconds, but rendered in seconds.
+        let scale = |value: u64| {
+            if histogram.is_duration() {
+                round_u64_f64(value) / 1_000_000.0
+            } else {
+                round_u64_f64(value)
+            }
+        };
+
+        let mut metric = self.start_metric(&[]);
+        let mut data = ProtobufWriter::new();
+        data.write_uint64(1, snapshot.count);
+        data.write_double(2, scale(snapshot.sum));
+
+        for (&bound, &count) in histogram.bounds().iter().zip(&snapshot.buckets) {
+            let mut bucket = ProtobufWriter::new();
+            bucket.write_uint64(1, count);
+            bucket.write_double(2, scale(bound));
+            data.write_message(3, bucket);
+        }
+
+        write_timestamp(&mut data, 15, self.environment.created_unix_nanos());
+        metric.write_message(7, data);
+        self.family.write_message(4, metric);
+    }
+
+    fn start_metric(&self, labels: &[(&[u8], &[u8])]) -> ProtobufWriter {
+        fn write_label(target: &mut ProtobufWriter, name: &[u8], value: &[u8]) {
+            let mut label = ProtobufWriter::new();
+            label.write_bytes(1, name);
+            label.write_bytes(2, value);
+            target.write_message(1, label);
+        }
+
+        let mut metric = ProtobufWriter::new();
+
+        for (name, value) in labels {
+            write_label(&mut metric, name, value);
+        }
+
+        for (name, value) in self.environment.static_labels() {
+            write_label(&mut metric, name.as_bytes(), value.as_bytes());
+        }
+
+        metric
+    }
+
+    fn write_message_rows(
+        &mut self,
+        kind: MessageCounterKind,
+        snapshot: &ByteCountSnapshot,
+        table: &UidGidTable,
+    ) -> bool {
+        if snapshot.is_empty() {
+            // Keep the family present, same as the text formats do.
+            self.write(&[], 0.0);
+            return true;
+        }
+
+        each_message_row(
+            self.environment.top_series,
+            snapshot,
+            table,
+            &kind,
+            |labels: MessageRowLabels, value| {
+                self.write(
+                    &[
+                        (b"service", labels.service),
+                        (b"priority", labels.priority.as_name_bytes()),
+                        (b"severity", &[labels.priority.as_severity_byte()]),
+                        (b"user", labels.user),
+                        (b"group", labels.group),
+                    ],
+                    round_u64_f64(value),
+                );
+                true
+            },
+        )
+    }
+
+    fn finish(self, target: &mut ProtobufWriter) {
+        target.write_length_delimited(self.family);
+    }
+}
+
+fn write_timestamp(target: &mut ProtobufWriter, field: u32, unix_nanos: u64) {
+    let mut timestamp = ProtobufWriter::new();
+    // Both are always non-negative, so they encode the same as unsigned varints.
+    timestamp.write_uint64(1, unix_nanos.wrapping_div(NANOS_PER_SEC));
+    timestamp.write_uint64(2, unix_nanos.wrapping_rem(NANOS_PER_SEC));
+    target.write_message(field, timestamp);
+}
+
+pub fn encode_prometheus_protobuf(
+    environment: &PromEnvironment,
+    snapshot: &PromSnapshot,
+    process: &ProcessSnapshot,
+    scrape: Option<&ScrapeStatsSnapshot>,
+    table: &UidGidTable,
+) -> Option<Vec<u8>> {
+    let mut result = ProtobufWriter::new();
+
+    let global_counters: [(&[u8], &[u8], u64); 7] = [
+        (
+            b"journald_entries_ingested_total",
+            b"",
+            snapshot.entries_ingested,
+        ),
+        (
+            b"journald_fields_ingested_total",
+            b"",
+            snapshot.fields_ingested,
+        ),
+        (
+            b"journald_data_ingested_bytes_total",
+            b"bytes",
+            snapshot.data_ingested_bytes,
+        ),
+        (b"journald_faults_total", b"", snapshot.faults),
+        (
+            b"journald_cursor_double_retries_total",
+            b"",
+            snapshot.cursor_double_retries,
+        ),
+        (
+            b"journald_unreadable_fields_total",
+            b"",
+            snapshot.unreadable_fields,
+        ),
+        (
+            b"journald_corrupted_fields_total",
+            b"",
+            snapshot.corrupted_fields,
+        ),
+    ];
+
+    for (name, unit, value) in global_counters {
+        let mut family = FamilyWriter::new(environment, name, unit, METRIC_TYPE_COUNTER);
+        family.write(&[], round_u64_f64(value));
+        family.finish(&mut result);
+    }
+
+    let mut family = FamilyWriter::new(
+        environment,
+        b"journald_metrics_requests_total",
+        b"",
+        METRIC_TYPE_COUNTER,
+    );
+    family.write(&[], round_u64_f64(snapshot.metrics_requests));
+    // Requests per named key, only when serving the metrics endpoint
+    if let Some(scrape) = scrape {
+        for (name, count) in scrape.key_requests() {
+            family.write(&[(b"key", name)], round_u64_f64(count));
+        }
+    }
+    family.finish(&mut result);
+
+    if environment.field_stats {
+        let field_counters: [(&[u8], &[u8], FieldCounterKind); 4] = [
+            (
+                b"journald_field_ingested_total",
+                b"",
+                FieldCounterKind::Ingested,
+            ),
+            (
+                b"journald_field_ingested_bytes_total",
+                b"bytes",
+                FieldCounterKind::IngestedBytes,
+            ),
+            (
+                b"journald_field_unreadable_total",
+                b"",
+                FieldCounterKind::Unreadable,
+            ),
+            (
+                b"journald_field_corrupted_total",
+                b"",
+                FieldCounterKind::Corrupted,
+            ),
+        ];
+
+        for (name, unit, kind) in field_counters {
+            let mut family = FamilyWriter::new(environment, name, unit, METRIC_TYPE_COUNTER);
+            for field in JournalField::ALL {
+                family.write(
+                    &[(b"field", field.as_name_bytes())],
+                    round_u64_f64(kind.value(snapshot.fields.get(field))),
+                );
+            }
+            family.finish(&mut result);
+        }
+    }
+
+    let message_counters: [(&[u8], &[u8], MessageCounterKind); 2] = [
+        (
+            b"journald_messages_ingested_total",
+            b"",
+            MessageCounterKind::Lines,
+        ),
+        (
+            b"journald_messages_ingested_bytes_total",
+            b"bytes",
+            MessageCounterKind::Bytes,
+        ),
+    ];
+
+    for (name, unit, kind) in message_counters {
+        let mut family = FamilyWriter::new(environment, name, unit, METRIC_TYPE_COUNTER);
+        if !family.write_message_rows(kind, &snapshot.messages_ingested, table) {
+            return None;
+        }
+        family.finish(&mut result);
+    }
+
+    let last_seen = collect_last_seen(&snapshot.messages_ingested)?;
+
+    // A family needs at least one metric, so just leave it out until there's something to report.
+    if !last_seen.is_empty() {
+        let mut family = FamilyWriter::new(
+            environment,
+            b"journald_service_last_message_timestamp_seconds",
+            b"seconds",
+            METRIC_TYPE_GAUGE,
+        );
+        for (priority, service, last_seen) in last_seen {
+            family.write(
+                &[
+                    (b"service", service),
+                    (b"priority", priority.as_name_bytes()),
+                    (b"severity", &[priority.as_severity_byte()]),
+                ],
+                round_u64_f64(last_seen) / 1_000_000.0,
+            );
+        }
+        family.finish(&mut result);
+    }
+
+    let mut family = FamilyWriter::new(
+        environment,
+        b"journald_exporter_build_info",
+        b"",
+        METRIC_TYPE_GAUGE,
+    );
+    family.write(&[(b"version", BUILD_VERSION.as_bytes())], 1.0);
+    family.finish(&mut result);
+
+    if let Some(scrape) = scrape {
+        let histograms: [(&[u8], &[u8], ScrapeHistogram); 4] = [
+            (
+                b"journald_scrape_duration_seconds",
+                b"seconds",
+                ScrapeHistogram::ScrapeDuration,
+            ),
+            (
+                b"journald_scrape_ipc_duration_seconds",
+                b"seconds",
+                ScrapeHistogram::IpcDuration,
+            ),
+            (
+                b"journald_render_duration_seconds",
+                b"seconds",
+                ScrapeHistogram::RenderDuration,
+            ),
+            (
+                b"journald_render_size_bytes",
+                b"bytes",
+                ScrapeHistogram::RenderSize,
+            ),
+        ];
+
+        for (name, unit, histogram) in histograms {
+            let mut family = FamilyWriter::new(environment, name, unit, METRIC_TYPE_HISTOGRAM);
+            family.write_histogram(histogram, scrape.get(histogram));
+            family.finish(&mut result);
+        }
+
+        let mut family = FamilyWriter::new(
+            environment,
+            b"journald_http_responses_total",
+            b"",
+            METRIC_TYPE_COUNTER,
+        );
+        for (code, count) in scrape.responses() {
+            family.write(&[(b"code", code.as_bytes())], round_u64_f64(count));
+        }
+        family.finish(&mut result);
+
+        let mut family = FamilyWriter::new(
+            environment,
+            b"journald_auth_failures_total",
+            b"",
+            METRIC_TYPE_COUNTER,
+        );
+        for (reason, count) in scrape.auth_failures() {
+            family.write(
+                &[(b"reason", reason.as_label().as_bytes())],
+                round_u64_f64(count),
+            );
+        }
+        family.finish(&mut result);
+
+        let mut family = FamilyWriter::new(
+            environment,
+            b"journald_throttled_requests_total",
+            b"",
+            METRIC_TYPE_COUNTER,
+        );
+        for (reason, count) in scrape.throttled_requests() {
+            family.write(
+                &[(b"reason", reason.as_label().as_bytes())],
+                round_u64_f64(count),
+            );
+        }
+        family.finish(&mut result);
+
+        // Like above, this is left out until a handshake's completed, which it never is without
+        // TLS.
+        if scrape.tls_handshakes().any(|(_, count)| count != 0) {
+            let mut family = FamilyWriter::new(
+                environment,
+                b"journald_tls_handshakes_total",
+                b"",
+                METRIC_TYPE_COUNTER,
+            );
+            for (suite, count) in scrape.tls_handshakes() {
+                if count != 0 {
+                    family.write(
+                        &[
+                            (b"version", suite.version().as_label().as_bytes()),
+                            (b"cipher_suite", suite.as_name().as_bytes()),
+                        ],
+                        round_u64_f64(count),
+                    );
+                }
+            }
+            family.finish(&mut result);
+        }
+    }
+
+    if process.parent.is_some() || process.child.is_some() {
+        let process_metrics: [(&[u8], &[u8], u64, ProcessMetricKind); 7] = [
+            (
+                b"process_cpu_seconds_total",
+                b"seconds",
+                METRIC_TYPE_COUNTER,
+                ProcessMetricKind::CpuSeconds,
+            ),
+            (
+                b"process_resident_memory_bytes",
+                b"bytes",
+                METRIC_TYPE_GAUGE,
+                ProcessMetricKind::ResidentMemoryBytes,
+            ),
+            (
+                b"process_virtual_memory_bytes",
+                b"bytes",
+                METRIC_TYPE_GAUGE,
+                ProcessMetricKind::VirtualMemoryBytes,
+            ),
+            (
+                b"process_open_fds",
+                b"",
+                METRIC_TYPE_GAUGE,
+                ProcessMetricKind::OpenFds,
+            ),
+            (
+                b"process_max_fds",
+                b"",
+                METRIC_TYPE_GAUGE,
+                ProcessMetricKind::MaxFds,
+            ),
+            (
+                b"process_threads",
+                b"",
+                METRIC_TYPE_GAUGE,
+                ProcessMetricKind::Threads,
+            ),
+            (
+                b"process_start_time_seconds",
+                b"seconds",
+                METRIC_TYPE_GAUGE,
+                ProcessMetricKind::StartTimeSeconds,
+            ),
+        ];
+
+        for (name, unit, metric_type, kind) in process_metrics {
+            let mut family = FamilyWriter::new(environment, name, unit, metric_type);
+            for (role, stats) in each_process(process) {
+                let value = match kind.value(stats) {
+                    ProcessValue::Count(value) => round_u64_f64(value),
+                    ProcessValue::Seconds(value) => value.as_secs_f64(),
+                };
+                // The CPU time counts up from when the process started. Truncate it to millisecond
+                // resolution, to match the text formats.
+                let created = u64::try_from(stats.start_time.as_millis())
+                    .unwrap_or(u64::MAX)
+                    .saturating_mul(1_000_000);
+                family.write_with_created(&[(b"role", role)], value, created);
+            }
+            family.finish(&mut result);
+        }
+    }
+
+    Some(result.finish())
+}
@@ -0,0 +1,491 @@
+use crate::prelude::*;
+
+use super::*;
+
+fn decode_label(data: &[u8]) -> String {
+    let label = read_protobuf_fields(data);
+    assert_eq!(label.len(), 2);
+    assert_eq!(label[0].0, 1);
+    assert_eq!(label[1].0, 2);
+    format!("{}=\"{}\"", label[0].1.str(), label[1].1.str())
+}
+
+fn decode_timestamp(data: &[u8]) -> String {
+    let timestamp = read_protobuf_fields(data);
+    assert_eq!(timestamp.len(), 2);
+    assert_eq!(timestamp[0].0, 1);
+    assert_eq!(timestamp[1].0, 2);
+    format!("{}.{:09}", timestamp[0].1.varint(), timestamp[1].1.varint())
+}
+
+// Rendered as `COUNT/SUM[BOUND:COUNT,...]`.
+fn decode_histogram(data: &[u8]) -> String {
+    let mut count = 0;
+    let mut sum = 0.0;
+    let mut buckets = Vec::new();
+
+    for (field, value) in read_protobuf_fields(data) {
+        match field {
+            1 => count = value.varint(),
+            2 => sum = f64::from_bits(value.fixed64()),
+            3 => {
+                let bucket = read_protobuf_fields(value.bytes());
+                assert_eq!(bucket.len(), 2);
+                assert_eq!(bucket[0].0, 1);
+                assert_eq!(bucket[1].0, 2);
+                buckets.push(format!(
+                    "{}:{}",
+                    f64::from_bits(bucket[1].1.fixed64()),
+                    bucket[0].1.varint()
+                ));
+            }
+            // Decoded along with the other metric types' created timestamps.
+            15 => {}
+            field => panic!("Unexpected histogram field {field}"),
+        }
+    }
+
+    format!("{count}/{sum}[{}]", buckets.join(","))
+}
+
+// Turns each metric into a line of `TYPE NAME UNIT {LABELS} VALUE CREATED`, for easier comparison.
+fn decode_families(data: &[u8]) -> Vec<String> {
+    let mut result = Vec::new();
+
+    for family in read_length_delimited_messages(data) {
+        let mut name = "";
+        let mut unit = "-";
+        let mut kind = "counter";
+        let mut metrics = Vec::new();
+
+        for (field, value) in read_protobuf_fields(family) {
+            match field {
+                1 => name = value.str(),
+                3 => {
+                    kind = match value.varint() {
+                        0 => "counter",
+                        1 => "gauge",
+                        4 => "histogram",
+                        kind => panic!("Unexpected metric type {kind}"),
+                    }
+                }
+                4 => metrics.push(value.bytes()),
+                5 => unit = value.str(),
+                field => panic!("Unexpected family field {field}"),
+            }
+        }
+
+        assert!(!metrics.is_empty(), "Family {name} has no metrics");
+
+        for metric in metrics {
+            let mut labels = Vec::new();
+            let mut data = None;
+
+            for (field, value) in read_protobuf_fields(metric) {
+                match field {
+                    1 => labels.push(decode_label(value.bytes())),
+                    2 => {
+                        assert_eq!(kind, "gauge");
+                        data = Some(value.bytes());
+                    }
+                    3 => {
+                        assert_eq!(kind, "counter");
+                        data = Some(value.bytes());
+                    }
+                    7 => {
+                        assert_eq!(kind, "histogram");
+                        data = Some(value.bytes());
+                    }
+                    field => panic!("Unexpected metric field {field}"),
+                }
+            }
+
+            let mut metric_value = String::new();
+            let mut created = String::from("-");
+
+            for (field, value) in read_protobuf_fields(data.unwrap()) {
+                match (kind, field) {
+                    ("histogram", 15) => created = decode_timestamp(value.bytes()),
+                    ("histogram", _) => {}
+                    (_, 1) => metric_value = format!("{}", f64::from_bits(value.fixed64())),
+                    (_, 3) => created = decode_timestamp(value.bytes()),
+                    (_, field) => panic!("Unexpected value field {field}"),
+                }
+            }
+
+            if kind == "histogram" {
+                metric_value = decode_histogram(data.unwrap());
+            }
+
+            result.push(format!(
+                "{kind} {name} {unit} {{{}}} {metric_value} {created}",
+                labels.join(","),
+            ));
+        }
+    }
+
+    result
+}
+
+fn encode(environment: &PromEnvironment, snapshot: PromSnapshot) -> Vec<String> {
+    decode_families(
+        &encode_prometheus_protobuf(
+            environment,
+            &snapshot,
+            &ProcessSnapshot::empty(),
+            None,
+            &get_user_group_table(),
+        )
+        .unwrap(),
+    )
+}
+
+fn empty_snapshot() -> PromSnapshot {
+    PromSnapshot {
+        entries_ingested: 0,
+        fields_ingested: 0,
+        data_ingested_bytes: 0,
+        faults: 0,
+        cursor_double_retries: 0,
+        unreadable_fields: 0,
+        corrupted_fields: 0,
+        metrics_requests: 0,
+        messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
+    }
+}
+
+#[test]
+fn encodes_empty_snapshot() {
+    assert_eq!(
+        encode(
+            &PromEnvironment::new(mock_system_time(123, 456)),
+            empty_snapshot()
+        ),
+        [
+            "counter journald_entries_ingested_total - {} 0 123.456000000",
+            "counter journald_fields_ingested_total - {} 0 123.456000000",
+            "counter journald_data_ingested_bytes_total bytes {} 0 123.456000000",
+            "counter journald_faults_total - {} 0 123.456000000",
+            "counter journald_cursor_double_retries_total - {} 0 123.456000000",
+            "counter journald_unreadable_fields_total - {} 0 123.456000000",
+            "counter journald_corrupted_fields_total - {} 0 123.456000000",
+            "counter journald_metrics_requests_total - {} 0 123.456000000",
+            "counter journald_messages_ingested_total - {} 0 123.456000000",
+            "counter journald_messages_ingested_bytes_total bytes {} 0 123.456000000",
+            "gauge journald_exporter_build_info - {version=\"test\"} 1 -",
+        ]
+    );
+}
+
+#[test]
+fn encodes_global_counters_with_static_labels() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("env", "prod");
+
+    let actual = encode(
+        &environment,
+        PromSnapshot {
+            entries_ingested: 1,
+            fields_ingested: 5,
+            data_ingested_bytes: 123,
+            faults: 2,
+            cursor_double_retries: 3,
+            unreadable_fields: 4,
+            corrupted_fields: 6,
+            metrics_requests: 7,
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
+        },
+    );
+
+    assert_eq!(
+        actual,
+        [
+            "counter journald_entries_ingested_total - {env=\"prod\"} 1 123.456000000",
+            "counter journald_fields_ingested_total - {env=\"prod\"} 5 123.456000000",
+            "counter journald_data_ingested_bytes_total bytes {env=\"prod\"} 123 123.456000000",
+            "counter journald_faults_total - {env=\"prod\"} 2 123.456000000",
+            "counter journald_cursor_double_retries_total - {env=\"prod\"} 3 123.456000000",
+            "counter journald_unreadable_fields_total - {env=\"prod\"} 4 123.456000000",
+            "counter journald_corrupted_fields_total - {env=\"prod\"} 6 123.456000000",
+            "counter journald_metrics_requests_total - {env=\"prod\"} 7 123.456000000",
+            "counter journald_messages_ingested_total - {env=\"prod\"} 0 123.456000000",
+            "counter journald_messages_ingested_bytes_total bytes {env=\"prod\"} 0 123.456000000",
+            "gauge journald_exporter_build_info - {version=\"test\",env=\"prod\"} 1 -",
+        ]
+    );
+}
+
+#[test]
+fn encodes_message_counters_and_last_seen_gauges() {
+    let actual = encode(
+        &PromEnvironment::new(mock_system_time(123, 456)),
+        PromSnapshot {
+            messages_ingested: ByteCountSnapshot::build([
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(Some(123), Some(456), Some(b"foo"), Priority::Warning),
+                    lines: 2,
+                    bytes: 15,
+                    last_seen: 1_700_000_000_500_000,
+                },
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(None, None, None, Priority::Error),
+                    lines: 1,
+                    bytes: 5,
+                    last_seen: 0,
+                },
+            ]),
+            ..empty_snapshot()
+        },
+    );
+
+    assert_eq!(
+        &actual[8..],
+        [
+            "counter journald_messages_ingested_total - {service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\"} 1 123.456000000",
+            "counter journald_messages_ingested_total - {service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_bar\"} 2 123.456000000",
+            "counter journald_messages_ingested_bytes_total bytes {service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\"} 5 123.456000000",
+            "counter journald_messages_ingested_bytes_total bytes {service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_bar\"} 15 123.456000000",
+            "gauge journald_service_last_message_timestamp_seconds seconds {service=\"foo\",priority=\"WARNING\",severity=\"4\"} 1700000000.5 -",
+            "gauge journald_exporter_build_info - {version=\"test\"} 1 -",
+        ]
+    );
+}
+
+#[test]
+fn encodes_field_stats_when_enabled() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.field_stats = true;
+
+    let actual = encode(
+        &environment,
+        PromSnapshot {
+            fields: FieldStatsSnapshot::build([(
+                JournalField::Uid,
+                FieldStatsEntry {
+                    ingested: 2,
+                    ingested_bytes: 8,
+                    unreadable: 1,
+                    corrupted: 3,
+                },
+            )]),
+            ..empty_snapshot()
+        },
+    );
+
+    assert_eq!(
+        &actual[8..14],
+        [
+            "counter journald_field_ingested_total - {field=\"_SYSTEMD_UNIT\"} 0 123.456000000",
+            "counter journald_field_ingested_total - {field=\"PRIORITY\"} 0 123.456000000",
+            "counter journald_field_ingested_total - {field=\"_UID\"} 2 123.456000000",
+            "counter journald_field_ingested_total - {field=\"_GID\"} 0 123.456000000",
+            "counter journald_field_ingested_total - {field=\"MESSAGE\"} 0 123.456000000",
+            "counter journald_field_ingested_total - {field=\"_CMDLINE\"} 0 123.456000000",
+        ]
+    );
+    assert_eq!(
+        actual[16],
+        "counter journald_field_ingested_bytes_total bytes {field=\"_UID\"} 8 123.456000000"
+    );
+    assert_eq!(
+        actual[22],
+        "counter journald_field_unreadable_total - {field=\"_UID\"} 1 123.456000000"
+    );
+    assert_eq!(
+        actual[28],
+        "counter journald_field_corrupted_total - {field=\"_UID\"} 3 123.456000000"
+    );
+    assert_eq!(actual.len(), 35);
+}
+
+#[test]
+fn encodes_process_metrics_for_each_role() {
+    let process = ProcessSnapshot {
+        parent: Some(ProcessStats {
+            cpu_time: Duration::from_millis(4250),
+            start_time: Duration::from_millis(1_700_000_000_500),
+            resident_memory_bytes: 1048576,
+            virtual_memory_bytes: 4194304,
+            open_fds: 12,
+            max_fds: 1024,
+            threads: 3,
+        }),
+        child: Some(ProcessStats {
+            cpu_time: Duration::from_millis(10),
+            start_time: Duration::from_millis(1_700_000_001_250),
+            resident_memory_bytes: 2048,
+            virtual_memory_bytes: 8192,
+            open_fds: 7,
+            max_fds: 524288,
+            threads: 2,
+        }),
+    };
+
+    let actual = decode_families(
+        &encode_prometheus_protobuf(
+            &PromEnvironment::new(mock_system_time(123, 456)),
+            &empty_snapshot(),
+            &process,
+            None,
+            &get_user_group_table(),
+        )
+        .unwrap(),
+    );
+
+    assert_eq!(
+        &actual[11..],
+        [
+            "counter process_cpu_seconds_total seconds {role=\"parent\"} 4.25 1700000000.500000000",
+            "counter process_cpu_seconds_total seconds {role=\"child\"} 0.01 1700000001.250000000",
+            "gauge process_resident_memory_bytes bytes {role=\"parent\"} 1048576 -",
+            "gauge process_resident_memory_bytes bytes {role=\"child\"} 2048 -",
+            "gauge process_virtual_memory_bytes bytes {role=\"parent\"} 4194304 -",
+            "gauge process_virtual_memory_bytes bytes {role=\"child\"} 8192 -",
+            "gauge process_open_fds - {role=\"parent\"} 12 -",
+            "gauge process_open_fds - {role=\"child\"} 7 -",
+            "gauge process_max_fds - {role=\"parent\"} 1024 -",
+            "gauge process_max_fds - {role=\"child\"} 524288 -",
+            "gauge process_threads - {role=\"parent\"} 3 -",
+            "gauge process_threads - {role=\"child\"} 2 -",
+            "gauge process_start_time_seconds seconds {role=\"parent\"} 1700000000.5 -",
+            "gauge process_start_time_seconds seconds {role=\"child\"} 1700000001.25 -",
+        ]
+    );
+}
+
+#[test]
+fn encodes_scrape_stats_with_static_labels() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("host", "a");
+
+    let scrape = ScrapeStatsSnapshot::build([
+        (
+            ScrapeHistogram::ScrapeDuration,
+            HistogramSnapshot {
+                buckets: [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2],
+                count: 3,
+                sum: 12_003_000,
+            },
+        ),
+        (
+            ScrapeHistogram::RenderSize,
+            HistogramSnapshot {
+                buckets: [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+                count: 1,
+                sum: 12345,
+            },
+        ),
+    ])
+    .with_responses(200, 5)
+    .with_responses(401, 2)
+    .with_auth_failures(AuthFailureReason::MissingHeader, 1)
+    .with_throttled_requests(ThrottleReason::Address, 3)
+    .with_tls_handshakes(TlsCipherSuite::Tls13Aes128GcmSha256, 2);
+
+    let actual = decode_families(
+        &encode_prometheus_protobuf(
+            &environment,
+            &empty_snapshot(),
+            &ProcessSnapshot::empty(),
+            Some(&scrape),
+            &get_user_group_table(),
+        )
+        .unwrap(),
+    );
+
+    assert_eq!(
+        &actual[11..],
+        [
+            "histogram journald_scrape_duration_seconds seconds {host=\"a\"} 3/12.003[0.001:0,0.0025:0,0.005:1,0.01:1,0.025:1,0.05:1,0.1:2,0.25:2,0.5:2,1:2,2.5:2,5:2,10:2] 123.456000000",
+            "histogram journald_scrape_ipc_duration_seconds seconds {host=\"a\"} 0/0[0.001:0,0.0025:0,0.005:0,0.01:0,0.025:0,0.05:0,0.1:0,0.25:0,0.5:0,1:0,2.5:0,5:0,10:0] 123.456000000",
+            "histogram journald_render_duration_seconds seconds {host=\"a\"} 0/0[0.001:0,0.0025:0,0.005:0,0.01:0,0.025:0,0.05:0,0.1:0,0.25:0,0.5:0,1:0,2.5:0,5:0,10:0] 123.456000000",
+            "histogram journald_render_size_bytes bytes {host=\"a\"} 1/12345[1024:0,2048:0,4096:0,8192:0,16384:1,32768:1,65536:1,131072:1,262144:1,524288:1,1048576:1,2097152:1,4194304:1] 123.456000000",
+            "counter journald_http_responses_total - {code=\"200\",host=\"a\"} 5 123.456000000",
+            "counter journald_http_responses_total - {code=\"401\",host=\"a\"} 2 123.456000000",
+            "counter journald_http_responses_total - {code=\"403\",host=\"a\"} 0 123.456000000",
+            "counter journald_http_responses_total - {code=\"404\",host=\"a\"} 0 123.456000000",
+            "counter journald_http_responses_total - {code=\"405\",host=\"a\"} 0 123.456000000",
+            "counter journald_http_responses_total - {code=\"429\",host=\"a\"} 0 123.456000000",
+            "counter journald_http_responses_total - {code=\"500\",host=\"a\"} 0 123.456000000",
+            "counter journald_http_responses_total - {code=\"503\",host=\"a\"} 0 123.456000000",
+            "counter journald_auth_failures_total - {reason=\"missing_header\",host=\"a\"} 1 123.456000000",
+            "counter journald_auth_failures_total - {reason=\"bad_syntax\",host=\"a\"} 0 123.456000000",
+            "counter journald_auth_failures_total - {reason=\"wrong_user\",host=\"a\"} 0 123.456000000",
+            "counter journald_auth_failures_total - {reason=\"unknown_key\",host=\"a\"} 0 123.456000000",
+            "counter journald_throttled_requests_total - {reason=\"address\",host=\"a\"} 3 123.456000000",
+            "counter journald_throttled_requests_total - {reason=\"key\",host=\"a\"} 0 123.456000000",
+            "counter journald_throttled_requests_total - {reason=\"lockout\",host=\"a\"} 0 123.456000000",
+            "counter journald_tls_handshakes_total - {version=\"1.3\",cipher_suite=\"TLS_AES_128_GCM_SHA256\",host=\"a\"} 2 123.456000000",
+        ]
+    );
+}
+
+#[test]
+fn encodes_key_requests_with_metrics_requests() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("host", "a");
+
+    let scrape = ScrapeStatsSnapshot::empty()
+        .with_key_requests(b"grafana-agent", 4)
+        .with_key_requests(b"prometheus", 1);
+
+    let snapshot = PromSnapshot {
+        metrics_requests: 6,
+        ..empty_snapshot()
+    };
+
+    let actual = decode_families(
+        &encode_prometheus_protobuf(
+            &environment,
+            &snapshot,
+            &ProcessSnapshot::empty(),
+            Some(&scrape),
+            &get_user_group_table(),
+        )
+        .unwrap(),
+    );
+
+    assert_eq!(
+        Vec::from_iter(
+            actual
+                .iter()
+                .filter(|line| line.starts_with("counter journald_metrics_requests_total "))
+        ),
+        [
+            "counter journald_metrics_requests_total - {host=\"a\"} 6 123.456000000",
+            "counter journald_metrics_requests_total - {key=\"grafana-agent\",host=\"a\"} 4 123.456000000",
+            "counter journald_metrics_requests_total - {key=\"prometheus\",host=\"a\"} 1 123.456000000",
+        ]
+    );
+}
+
+#[test]
+fn renders_protobuf_metrics_response() {
+    let snapshot = PromSnapshot {
+        entries_ingested: 1,
+        ..empty_snapshot()
+    };
+    let environment = PromEnvironment::new(mock_system_time(123, 456));
+
+    let rendered = render_metrics(
+        &environment,
+        &snapshot,
+        &ProcessSnapshot::empty(),
+        None,
+        &get_user_group_table(),
+        ipc::MetricsFormat::Protobuf,
+    )
+    .unwrap();
+
+    let header = ipc::parent::PROTOBUF_METRICS_RESPONSE_HEADER;
+    assert_eq!(rendered[0], header[0]);
+
+    let body = &rendered[header.len()..];
+    assert_eq!(
+        rendered[1..header.len()],
+        u32::try_from(body.len()).unwrap().to_le_bytes()
+    );
+    assert_eq!(decode_families(body), encode(&environment, snapshot));
+}
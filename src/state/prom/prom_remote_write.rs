@@ -0,0 +1,203 @@
+use crate::prelude::*;
+
+use super::prom_write::collect_last_seen;
+use super::prom_write::each_message_row;
+use super::prom_write::FieldCounterKind;
+use super::prom_write::MessageCounterKind;
+use super::prom_write::MessageRowLabels;
+
+// Encodes a snapshot as an (uncompressed) Prometheus remote write `WriteRequest`. The relevant
+// parts of the schema are:
+//
+// ```proto
+// message WriteRequest { repeated TimeSeries timeseries = 1; }
+// message TimeSeries { repeated Label labels = 1; repeated Sample samples = 2; }
+// message Label { string name = 1; string value = 2; }
+// message Sample { double value = 1; int64 timestamp = 2; }
+// ```
+//
+// The series and their labels match the text exposition, minus the `_created` series. Counters
+// get the usual `_total` suffix.
+
+struct SeriesWriter<'a> {
+    request: ProtobufWriter,
+    environment: &'a PromEnvironment,
+    timestamp_millis: i64,
+}
+
+impl SeriesWriter<'_> {
+    fn write(&mut self, name: &[u8], labels: &[(&[u8], &[u8])], value: f64) {
+        let mut all_labels = Vec::with_capacity(
+            labels
+                .len()
+                .wrapping_add(self.environment.static_labels().len())
+                .wrapping_add(1),
+        );
+
+        all_labels.push((&b"__name__"[..], name));
+        all_labels.extend_from_slice(labels);
+        for (name, value) in self.environment.static_labels() {
+            all_labels.push((name.as_bytes(), value.as_bytes()));
+        }
+
+        // The remote write spec requires labels to be sorted by name.
+        all_labels.sort_unstable_by_key(|(name, _)| *name);
+
+        let mut series = ProtobufWriter::new();
+
+        for (name, value) in all_labels {
+            let mut label = ProtobufWriter::new();
+            label.write_bytes(1, name);
+            label.write_bytes(2, value);
+            series.write_message(1, label);
+        }
+
+        let mut sample = ProtobufWriter::new();
+        sample.write_double(1, value);
+        sample.write_int64(2, self.timestamp_millis);
+        series.write_message(2, sample);
+
+        self.request.write_message(1, series);
+    }
+
+    fn write_message_rows(
+        &mut self,
+        name: &[u8],
+        kind: MessageCounterKind,
+        snapshot: &ByteCountSnapshot,
+        table: &UidGidTable,
+    ) -> bool {
+        if snapshot.is_empty() {
+            // Don't break sum
+            self.write(name, &[], 0.0);
+            return true;
+        }
+
+        each_message_row(
+            self.environment.top_series,
+            snapshot,
+            table,
+            &kind,
+            |labels: MessageRowLabels, value| {
+                self.write(
+                    name,
+                    &[
+                        (b"service", labels.service),
+                        (b"priority", labels.priority.as_name_bytes()),
+                        (b"severity", &[labels.priority.as_severity_byte()]),
+                        (b"user", labels.user),
+                        (b"group", labels.group),
+                    ],
+                    round_u64_f64(value),
+                );
+                true
+            },
+        )
+    }
+}
+
+pub fn encode_remote_write(
+    environment: &PromEnvironment,
+    snapshot: &PromSnapshot,
+    table: &UidGidTable,
+    timestamp_millis: i64,
+) -> Option<Vec<u8>> {
+    let mut writer = SeriesWriter {
+        request: ProtobufWriter::new(),
+        environment,
+        timestamp_millis,
+    };
+
+    let global_counters: [(&[u8], u64); 8] = [
+        (
+            b"journald_entries_ingested_total",
+            snapshot.entries_ingested,
+        ),
+        (b"journald_fields_ingested_total", snapshot.fields_ingested),
+        (
+            b"journald_data_ingested_bytes_total",
+            snapshot.data_ingested_bytes,
+        ),
+        (b"journald_faults_total", snapshot.faults),
+        (
+            b"journald_cursor_double_retries_total",
+            snapshot.cursor_double_retries,
+        ),
+        (
+            b"journald_unreadable_fields_total",
+            snapshot.unreadable_fields,
+        ),
+        (
+            b"journald_corrupted_fields_total",
+            snapshot.corrupted_fields,
+        ),
+        (
+            b"journald_metrics_requests_total",
+            snapshot.metrics_requests,
+        ),
+    ];
+
+    for (name, value) in global_counters {
+        writer.write(name, &[], round_u64_f64(value));
+    }
+
+    if environment.field_stats {
+        let field_counters: [(&[u8], FieldCounterKind); 4] = [
+            (b"journald_field_ingested_total", FieldCounterKind::Ingested),
+            (
+                b"journald_field_ingested_bytes_total",
+                FieldCounterKind::IngestedBytes,
+            ),
+            (
+                b"journald_field_unreadable_total",
+                FieldCounterKind::Unreadable,
+            ),
+            (
+                b"journald_field_corrupted_total",
+                FieldCounterKind::Corrupted,
+            ),
+        ];
+
+        for (name, kind) in field_counters {
+            for field in JournalField::ALL {
+                writer.write(
+                    name,
+                    &[(b"field", field.as_name_bytes())],
+                    round_u64_f64(kind.value(snapshot.fields.get(field))),
+                );
+            }
+        }
+    }
+
+    if !writer.write_message_rows(
+        b"journald_messages_ingested_total",
+        MessageCounterKind::Lines,
+        &snapshot.messages_ingested,
+        table,
+    ) {
+        return None;
+    }
+
+    if !writer.write_message_rows(
+        b"journald_messages_ingested_bytes_total",
+        MessageCounterKind::Bytes,
+        &snapshot.messages_ingested,
+        table,
+    ) {
+        return None;
+    }
+
+    for (priority, service, last_seen) in collect_last_seen(&snapshot.messages_ingested)? {
+        writer.write(
+            b"journald_service_last_message_timestamp_seconds",
+            &[
+                (b"service", service),
+                (b"priority", priority.as_name_bytes()),
+                (b"severity", &[priority.as_severity_byte()]),
+            ],
+            round_u64_f64(last_seen) / 1_000_000.0,
+        );
+    }
+
+    Some(writer.request.finish())
+}
@@ -0,0 +1,267 @@
+use crate::prelude::*;
+
+use super::*;
+
+// Turns the request into one line per series, with the labels in their encoded order, for easier
+// comparison.
+fn decode(request: &[u8]) -> Vec<String> {
+    let mut result = Vec::new();
+
+    for (field, series) in read_protobuf_fields(request) {
+        assert_eq!(field, 1);
+        let mut line = String::from("{");
+        let mut samples = Vec::new();
+
+        for (field, value) in read_protobuf_fields(series.bytes()) {
+            match field {
+                1 => {
+                    let label = read_protobuf_fields(value.bytes());
+                    assert_eq!(label.len(), 2);
+                    if line.len() > 1 {
+                        line.push(',');
+                    }
+                    line.push_str(label[0].1.str());
+                    line.push_str("=\"");
+                    line.push_str(label[1].1.str());
+                    line.push('"');
+                }
+                2 => samples.push(read_protobuf_fields(value.bytes())),
+                field => panic!("Unexpected series field {field}"),
+            }
+        }
+
+        line.push('}');
+
+        assert_eq!(samples.len(), 1);
+        let sample = &samples[0];
+        assert_eq!(sample.len(), 2);
+        assert_eq!(sample[0].0, 1);
+        assert_eq!(sample[1].0, 2);
+        let value = f64::from_bits(sample[0].1.fixed64());
+        let timestamp = i64::try_from(sample[1].1.varint()).unwrap();
+        line.push_str(&format!(" {value} @{timestamp}"));
+
+        result.push(line);
+    }
+
+    result
+}
+
+fn encode(environment: &PromEnvironment, snapshot: PromSnapshot) -> Vec<String> {
+    decode(
+        &encode_remote_write(
+            environment,
+            &snapshot,
+            &get_user_group_table(),
+            1_700_000_000_123,
+        )
+        .unwrap(),
+    )
+}
+
+fn empty_snapshot() -> PromSnapshot {
+    PromSnapshot {
+        entries_ingested: 0,
+        fields_ingested: 0,
+        data_ingested_bytes: 0,
+        faults: 0,
+        cursor_double_retries: 0,
+        unreadable_fields: 0,
+        corrupted_fields: 0,
+        metrics_requests: 0,
+        messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
+    }
+}
+
+#[test]
+fn encodes_empty_snapshot() {
+    assert_eq!(
+        encode(
+            &PromEnvironment::new(mock_system_time(123, 456)),
+            empty_snapshot()
+        ),
+        [
+            "{__name__=\"journald_entries_ingested_total\"} 0 @1700000000123",
+            "{__name__=\"journald_fields_ingested_total\"} 0 @1700000000123",
+            "{__name__=\"journald_data_ingested_bytes_total\"} 0 @1700000000123",
+            "{__name__=\"journald_faults_total\"} 0 @1700000000123",
+            "{__name__=\"journald_cursor_double_retries_total\"} 0 @1700000000123",
+            "{__name__=\"journald_unreadable_fields_total\"} 0 @1700000000123",
+            "{__name__=\"journald_corrupted_fields_total\"} 0 @1700000000123",
+            "{__name__=\"journald_metrics_requests_total\"} 0 @1700000000123",
+            "{__name__=\"journald_messages_ingested_total\"} 0 @1700000000123",
+            "{__name__=\"journald_messages_ingested_bytes_total\"} 0 @1700000000123",
+        ]
+    );
+}
+
+#[test]
+fn encodes_global_counters_with_static_labels_sorted() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("zone", "eu1");
+    environment.add_static_label("Cluster", "a \"b\"");
+
+    let actual = encode(
+        &environment,
+        PromSnapshot {
+            entries_ingested: 1,
+            fields_ingested: 5,
+            data_ingested_bytes: 123,
+            faults: 2,
+            cursor_double_retries: 3,
+            unreadable_fields: 4,
+            corrupted_fields: 6,
+            metrics_requests: 7,
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
+        },
+    );
+
+    assert_eq!(
+        actual,
+        [
+            "{Cluster=\"a \"b\"\",__name__=\"journald_entries_ingested_total\",zone=\"eu1\"} 1 @1700000000123",
+            "{Cluster=\"a \"b\"\",__name__=\"journald_fields_ingested_total\",zone=\"eu1\"} 5 @1700000000123",
+            "{Cluster=\"a \"b\"\",__name__=\"journald_data_ingested_bytes_total\",zone=\"eu1\"} 123 @1700000000123",
+            "{Cluster=\"a \"b\"\",__name__=\"journald_faults_total\",zone=\"eu1\"} 2 @1700000000123",
+            "{Cluster=\"a \"b\"\",__name__=\"journald_cursor_double_retries_total\",zone=\"eu1\"} 3 @1700000000123",
+            "{Cluster=\"a \"b\"\",__name__=\"journald_unreadable_fields_total\",zone=\"eu1\"} 4 @1700000000123",
+            "{Cluster=\"a \"b\"\",__name__=\"journald_corrupted_fields_total\",zone=\"eu1\"} 6 @1700000000123",
+            "{Cluster=\"a \"b\"\",__name__=\"journald_metrics_requests_total\",zone=\"eu1\"} 7 @1700000000123",
+            "{Cluster=\"a \"b\"\",__name__=\"journald_messages_ingested_total\",zone=\"eu1\"} 0 @1700000000123",
+            "{Cluster=\"a \"b\"\",__name__=\"journald_messages_ingested_bytes_total\",zone=\"eu1\"} 0 @1700000000123",
+        ]
+    );
+}
+
+#[test]
+fn encodes_message_counters_and_last_seen_gauges() {
+    let actual = encode(
+        &PromEnvironment::new(mock_system_time(123, 456)),
+        PromSnapshot {
+            messages_ingested: ByteCountSnapshot::build([
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(Some(123), Some(456), Some(b"foo"), Priority::Warning),
+                    lines: 2,
+                    bytes: 15,
+                    last_seen: 1_700_000_000_500_000,
+                },
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(None, None, None, Priority::Error),
+                    lines: 1,
+                    bytes: 5,
+                    last_seen: 0,
+                },
+            ]),
+            ..empty_snapshot()
+        },
+    );
+
+    assert_eq!(
+        &actual[8..],
+        [
+            "{__name__=\"journald_messages_ingested_total\",group=\"?\",priority=\"ERR\",service=\"?\",severity=\"3\",user=\"?\"} 1 @1700000000123",
+            "{__name__=\"journald_messages_ingested_total\",group=\"group_bar\",priority=\"WARNING\",service=\"foo\",severity=\"4\",user=\"user_foo\"} 2 @1700000000123",
+            "{__name__=\"journald_messages_ingested_bytes_total\",group=\"?\",priority=\"ERR\",service=\"?\",severity=\"3\",user=\"?\"} 5 @1700000000123",
+            "{__name__=\"journald_messages_ingested_bytes_total\",group=\"group_bar\",priority=\"WARNING\",service=\"foo\",severity=\"4\",user=\"user_foo\"} 15 @1700000000123",
+            "{__name__=\"journald_service_last_message_timestamp_seconds\",priority=\"WARNING\",service=\"foo\",severity=\"4\"} 1700000000.5 @1700000000123",
+        ]
+    );
+}
+
+#[test]
+fn encodes_top_series_with_the_rest_summed_into_other() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.top_series = std::num::NonZeroU32::new(1);
+
+    let actual = encode(
+        &environment,
+        PromSnapshot {
+            messages_ingested: ByteCountSnapshot::build([
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(None, None, Some(b"foo"), Priority::Informational),
+                    lines: 5,
+                    bytes: 50,
+                    last_seen: 0,
+                },
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(None, None, Some(b"bar"), Priority::Informational),
+                    lines: 2,
+                    bytes: 20,
+                    last_seen: 0,
+                },
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(None, None, Some(b"baz"), Priority::Informational),
+                    lines: 1,
+                    bytes: 10,
+                    last_seen: 0,
+                },
+            ]),
+            ..empty_snapshot()
+        },
+    );
+
+    assert_eq!(
+        &actual[8..],
+        [
+            "{__name__=\"journald_messages_ingested_total\",group=\"?\",priority=\"INFO\",service=\"foo\",severity=\"6\",user=\"?\"} 5 @1700000000123",
+            "{__name__=\"journald_messages_ingested_total\",group=\"__other__\",priority=\"INFO\",service=\"__other__\",severity=\"6\",user=\"__other__\"} 3 @1700000000123",
+            "{__name__=\"journald_messages_ingested_bytes_total\",group=\"?\",priority=\"INFO\",service=\"foo\",severity=\"6\",user=\"?\"} 50 @1700000000123",
+            "{__name__=\"journald_messages_ingested_bytes_total\",group=\"__other__\",priority=\"INFO\",service=\"__other__\",severity=\"6\",user=\"__other__\"} 30 @1700000000123",
+        ]
+    );
+}
+
+#[test]
+fn encodes_field_stats_when_enabled() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.field_stats = true;
+
+    let actual = encode(
+        &environment,
+        PromSnapshot {
+            fields: FieldStatsSnapshot::build([(
+                JournalField::Message,
+                FieldStatsEntry {
+                    ingested: 2,
+                    ingested_bytes: 30,
+                    unreadable: 1,
+                    corrupted: 3,
+                },
+            )]),
+            ..empty_snapshot()
+        },
+    );
+
+    assert_eq!(
+        &actual[8..32],
+        [
+            "{__name__=\"journald_field_ingested_total\",field=\"_SYSTEMD_UNIT\"} 0 @1700000000123",
+            "{__name__=\"journald_field_ingested_total\",field=\"PRIORITY\"} 0 @1700000000123",
+            "{__name__=\"journald_field_ingested_total\",field=\"_UID\"} 0 @1700000000123",
+            "{__name__=\"journald_field_ingested_total\",field=\"_GID\"} 0 @1700000000123",
+            "{__name__=\"journald_field_ingested_total\",field=\"MESSAGE\"} 2 @1700000000123",
+            "{__name__=\"journald_field_ingested_total\",field=\"_CMDLINE\"} 0 @1700000000123",
+            "{__name__=\"journald_field_ingested_bytes_total\",field=\"_SYSTEMD_UNIT\"} 0 @1700000000123",
+            "{__name__=\"journald_field_ingested_bytes_total\",field=\"PRIORITY\"} 0 @1700000000123",
+            "{__name__=\"journald_field_ingested_bytes_total\",field=\"_UID\"} 0 @1700000000123",
+            "{__name__=\"journald_field_ingested_bytes_total\",field=\"_GID\"} 0 @1700000000123",
+            "{__name__=\"journald_field_ingested_bytes_total\",field=\"MESSAGE\"} 30 @1700000000123",
+            "{__name__=\"journald_field_ingested_bytes_total\",field=\"_CMDLINE\"} 0 @1700000000123",
+            "{__name__=\"journald_field_unreadable_total\",field=\"_SYSTEMD_UNIT\"} 0 @1700000000123",
+            "{__name__=\"journald_field_unreadable_total\",field=\"PRIORITY\"} 0 @1700000000123",
+            "{__name__=\"journald_field_unreadable_total\",field=\"_UID\"} 0 @1700000000123",
+            "{__name__=\"journald_field_unreadable_total\",field=\"_GID\"} 0 @1700000000123",
+            "{__name__=\"journald_field_unreadable_total\",field=\"MESSAGE\"} 1 @1700000000123",
+            "{__name__=\"journald_field_unreadable_total\",field=\"_CMDLINE\"} 0 @1700000000123",
+            "{__name__=\"journald_field_corrupted_total\",field=\"_SYSTEMD_UNIT\"} 0 @1700000000123",
+            "{__name__=\"journald_field_corrupted_total\",field=\"PRIORITY\"} 0 @1700000000123",
+            "{__name__=\"journald_field_corrupted_total\",field=\"_UID\"} 0 @1700000000123",
+            "{__name__=\"journald_field_corrupted_total\",field=\"_GID\"} 0 @1700000000123",
+            "{__name__=\"journald_field_corrupted_total\",field=\"MESSAGE\"} 3 @1700000000123",
+            "{__name__=\"journald_field_corrupted_total\",field=\"_CMDLINE\"} 0 @1700000000123",
+        ]
+    );
+    assert_eq!(actual.len(), 34);
+}
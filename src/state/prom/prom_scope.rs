@@ -0,0 +1,143 @@
+use crate::prelude::*;
+
+use super::prom_write::data_bytes_from_id;
+use super::prom_write::metrics_response;
+use crate::state::ipc::MetricsFormat;
+
+// Scoped keys see the same metrics as everyone else, just with the message counters narrowed down
+// to the services and users in scope, and with the families out of scope left out entirely. The
+// global counters aren't broken down by service or user, so those can only be hidden as a whole.
+// The exception is the per-key request counts, which list every other key, so they're left out for
+// keys with their messages narrowed down.
+
+fn family_name_from_metadata(line: &[u8]) -> Option<&[u8]> {
+    let rest = line
+        .strip_prefix(b"# TYPE ")
+        .or_else(|| line.strip_prefix(b"# HELP "))
+        .or_else(|| line.strip_prefix(b"# UNIT "))?;
+
+    Some(match rest.iter().position(|&b| b == b' ') {
+        Some(end) => &rest[..end],
+        None => rest,
+    })
+}
+
+// Metadata always precedes its family's samples, so it's enough to track the last family seen.
+// Returns `None` if allocation failed.
+fn retain_openmetrics_families(openmetrics: &[u8], scope: &KeyScope) -> Option<Vec<u8>> {
+    let mut result = try_new_dynamic_vec(openmetrics.len())?;
+    let mut allowed = true;
+
+    for line in openmetrics.split_inclusive(|&b| b == b'\n') {
+        if let Some(family) = family_name_from_metadata(line) {
+            allowed = scope.allows_family(family);
+        } else if line.starts_with(b"# EOF") {
+            allowed = true;
+        }
+
+        if allowed {
+            result.extend_from_slice(line);
+        }
+    }
+
+    Some(result)
+}
+
+fn read_varint(data: &mut &[u8]) -> Option<usize> {
+    let mut result = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first()?;
+        *data = rest;
+        result |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return usize::try_from(result).ok();
+        }
+    }
+    None
+}
+
+fn split_len_prefixed<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = read_varint(data)?;
+    if len > data.len() {
+        return None;
+    }
+    let (head, rest) = data.split_at(len);
+    *data = rest;
+    Some(head)
+}
+
+// Families are written with their name first, so there's no need to decode anything else. Returns
+// `None` if allocation failed or if the stream isn't one `encode_prometheus_protobuf` returned.
+fn retain_protobuf_families(protobuf: &[u8], scope: &KeyScope) -> Option<Vec<u8>> {
+    let mut result = try_new_dynamic_vec(protobuf.len())?;
+    let mut remaining = protobuf;
+
+    while !remaining.is_empty() {
+        let start = remaining;
+        let mut family = split_len_prefixed(&mut remaining)?;
+        let whole = &start[..start.len().wrapping_sub(remaining.len())];
+
+        // Field 1, length-delimited.
+        family = family.strip_prefix(&[0x0A])?;
+        let name = split_len_prefixed(&mut family)?;
+        // Counters are matched without their `_total` suffix, like in OpenMetrics.
+        let name = name.strip_suffix(b"_total").unwrap_or(name);
+
+        if scope.allows_family(name) {
+            result.extend_from_slice(whole);
+        }
+    }
+
+    Some(result)
+}
+
+/// Render the metrics visible to the given scope, as an IPC metrics response for the requested
+/// format. The snapshots are taken by value, as they're filtered in place.
+pub fn render_scoped_metrics(
+    environment: &PromEnvironment,
+    mut snapshot: PromSnapshot,
+    process: &ProcessSnapshot,
+    mut scrape: Option<ScrapeStatsSnapshot>,
+    table: &UidGidTable,
+    format: MetricsFormat,
+    scope: &KeyScope,
+) -> Option<Vec<u8>> {
+    if scope.restricts_messages() {
+        snapshot.messages_ingested.retain(|data| {
+            let service = data.key.service().map(|s| s.as_bytes()).unwrap_or(b"?");
+            scope.allows_service(service)
+                && scope.allows_user(data_bytes_from_id(&table.uids, &data.key.uid))
+        });
+
+        // The per-key request counts name every key and how often it scrapes, which is exactly
+        // what narrowing down the messages is meant to keep from other teams.
+        if let Some(scrape) = &mut scrape {
+            scrape.clear_key_requests();
+        }
+    }
+
+    let scrape = scrape.as_ref();
+
+    if !scope.restricts_families() {
+        return render_metrics(environment, &snapshot, process, scrape, table, format);
+    }
+
+    let body = match format {
+        MetricsFormat::OpenMetrics | MetricsFormat::PrometheusText => {
+            let rendered = render_openapi_metrics(environment, &snapshot, process, scrape, table)?;
+            let openmetrics = retain_openmetrics_families(openmetrics_body(&rendered), scope)?;
+            if format == MetricsFormat::PrometheusText {
+                openmetrics_to_prometheus_text(&openmetrics)
+            } else {
+                openmetrics
+            }
+        }
+        MetricsFormat::Protobuf => {
+            let encoded =
+                encode_prometheus_protobuf(environment, &snapshot, process, scrape, table)?;
+            retain_protobuf_families(&encoded, scope)?
+        }
+    };
+
+    metrics_response(format, &body)
+}
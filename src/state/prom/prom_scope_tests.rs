@@ -0,0 +1,269 @@
+use crate::prelude::*;
+
+use super::*;
+use crate::state::ipc::MetricsFormat;
+
+fn snapshot() -> PromSnapshot {
+    PromSnapshot {
+        entries_ingested: 1,
+        fields_ingested: 0,
+        data_ingested_bytes: 0,
+        faults: 2,
+        cursor_double_retries: 0,
+        unreadable_fields: 0,
+        corrupted_fields: 0,
+        metrics_requests: 0,
+        messages_ingested: ByteCountSnapshot::build([
+            ByteCountSnapshotEntry {
+                key: MessageKey::build(
+                    Some(123),
+                    Some(123),
+                    Some(b"team-a-web"),
+                    Priority::Informational,
+                ),
+                lines: 1,
+                bytes: 10,
+                last_seen: 0,
+            },
+            ByteCountSnapshotEntry {
+                key: MessageKey::build(
+                    Some(456),
+                    Some(456),
+                    Some(b"team-b-web"),
+                    Priority::Informational,
+                ),
+                lines: 2,
+                bytes: 20,
+                last_seen: 0,
+            },
+            ByteCountSnapshotEntry {
+                key: MessageKey::build(None, None, None, Priority::Error),
+                lines: 3,
+                bytes: 30,
+                last_seen: 0,
+            },
+        ]),
+        fields: FieldStatsSnapshot::empty(),
+    }
+}
+
+fn render_scoped_with_scrape(
+    format: MetricsFormat,
+    scrape: Option<ScrapeStatsSnapshot>,
+    scope: &[u8],
+) -> Vec<u8> {
+    render_scoped_metrics(
+        &PromEnvironment::new(mock_system_time(123, 456)),
+        snapshot(),
+        &ProcessSnapshot::empty(),
+        scrape,
+        &get_user_group_table(),
+        format,
+        &KeyScope::decode(scope).unwrap(),
+    )
+    .unwrap()
+}
+
+fn render_scoped(format: MetricsFormat, scope: &[u8]) -> Vec<u8> {
+    render_scoped_with_scrape(format, None, scope)
+}
+
+fn key_requests_scrape() -> ScrapeStatsSnapshot {
+    ScrapeStatsSnapshot::empty()
+        .with_key_requests(b"team-a-agent", 4)
+        .with_key_requests(b"team-b-agent", 2)
+}
+
+fn render_scoped_text(format: MetricsFormat, scope: &[u8]) -> String {
+    let rendered = render_scoped(format, scope);
+    let header = ipc::parent::metrics_response_header(format);
+    assert_eq!(rendered[0], header[0]);
+    assert_eq!(
+        &rendered[1..header.len()],
+        &truncate_usize_u32(rendered.len() - header.len()).to_le_bytes()
+    );
+    String::from_utf8(rendered[header.len()..].to_vec()).unwrap()
+}
+
+fn render_scoped_family_names(scope: &[u8]) -> Vec<String> {
+    let rendered = render_scoped(MetricsFormat::Protobuf, scope);
+    let header_len = ipc::parent::PROTOBUF_METRICS_RESPONSE_HEADER.len();
+    read_length_delimited_messages(&rendered[header_len..])
+        .into_iter()
+        .map(|family| read_protobuf_fields(family)[0].1.str().to_owned())
+        .collect()
+}
+
+fn message_rows(text: &str) -> Vec<&str> {
+    text.lines()
+        .filter(|line| line.starts_with("journald_messages_ingested"))
+        .collect()
+}
+
+#[test]
+fn renders_unscoped_same_as_unscoped_keys() {
+    for format in MetricsFormat::ALL {
+        assert_eq!(
+            BinaryToDebug(&render_scoped(format, b"")),
+            BinaryToDebug(
+                &render_metrics(
+                    &PromEnvironment::new(mock_system_time(123, 456)),
+                    &snapshot(),
+                    &ProcessSnapshot::empty(),
+                    None,
+                    &get_user_group_table(),
+                    format,
+                )
+                .unwrap()
+            ),
+        );
+    }
+}
+
+#[test]
+fn renders_only_services_in_scope() {
+    let actual = render_scoped_text(MetricsFormat::OpenMetrics, b"services=team-a-*");
+
+    assert_eq!(
+        message_rows(&actual),
+        [
+            "journald_messages_ingested_created{service=\"team-a-web\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 123.456",
+            "journald_messages_ingested_total{service=\"team-a-web\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 1",
+            "journald_messages_ingested_bytes_created{service=\"team-a-web\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 123.456",
+            "journald_messages_ingested_bytes_total{service=\"team-a-web\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 10",
+        ]
+    );
+    // The global counters aren't broken down by service, so they're left as-is.
+    assert!(actual.contains("\njournald_faults_total 2\n"));
+}
+
+#[test]
+fn renders_messages_without_service_when_in_scope() {
+    let actual = render_scoped_text(MetricsFormat::OpenMetrics, b"services=?");
+    assert_eq!(
+        message_rows(&actual),
+        [
+            "journald_messages_ingested_created{service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\"} 123.456",
+            "journald_messages_ingested_total{service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\"} 3",
+            "journald_messages_ingested_bytes_created{service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\"} 123.456",
+            "journald_messages_ingested_bytes_total{service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\"} 30",
+        ]
+    );
+}
+
+#[test]
+fn renders_only_users_in_scope() {
+    let actual = render_scoped_text(MetricsFormat::OpenMetrics, b"users=user_bar");
+
+    assert_eq!(
+        message_rows(&actual),
+        [
+            "journald_messages_ingested_created{service=\"team-b-web\",priority=\"INFO\",severity=\"6\",user=\"user_bar\",group=\"group_bar\"} 123.456",
+            "journald_messages_ingested_total{service=\"team-b-web\",priority=\"INFO\",severity=\"6\",user=\"user_bar\",group=\"group_bar\"} 2",
+            "journald_messages_ingested_bytes_created{service=\"team-b-web\",priority=\"INFO\",severity=\"6\",user=\"user_bar\",group=\"group_bar\"} 123.456",
+            "journald_messages_ingested_bytes_total{service=\"team-b-web\",priority=\"INFO\",severity=\"6\",user=\"user_bar\",group=\"group_bar\"} 20",
+        ]
+    );
+}
+
+#[test]
+fn renders_only_messages_matching_both_services_and_users() {
+    let actual = render_scoped_text(
+        MetricsFormat::OpenMetrics,
+        b"services=team-*\nusers=user_foo",
+    );
+
+    assert_eq!(
+        message_rows(&actual),
+        [
+            "journald_messages_ingested_created{service=\"team-a-web\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 123.456",
+            "journald_messages_ingested_total{service=\"team-a-web\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 1",
+            "journald_messages_ingested_bytes_created{service=\"team-a-web\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 123.456",
+            "journald_messages_ingested_bytes_total{service=\"team-a-web\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 10",
+        ]
+    );
+}
+
+#[test]
+fn renders_no_key_requests_when_messages_are_scoped() {
+    for scope in [&b"services=team-a-*"[..], b"users=user_foo"] {
+        for format in MetricsFormat::ALL {
+            let rendered = render_scoped_with_scrape(format, Some(key_requests_scrape()), scope);
+            assert!(
+                !rendered
+                    .windows(b"team-b-agent".len())
+                    .any(|w| w == b"team-b-agent"),
+                "{format:?} {}",
+                String::from_utf8_lossy(scope),
+            );
+        }
+    }
+}
+
+#[test]
+fn renders_key_requests_when_only_families_are_scoped() {
+    let rendered = render_scoped_with_scrape(
+        MetricsFormat::OpenMetrics,
+        Some(key_requests_scrape()),
+        b"families=journald_metrics_requests",
+    );
+    let text = String::from_utf8_lossy(&rendered);
+    assert!(text.contains("journald_metrics_requests_total{key=\"team-a-agent\"} 4"));
+    assert!(text.contains("journald_metrics_requests_total{key=\"team-b-agent\"} 2"));
+}
+
+#[test]
+fn renders_only_families_in_scope_as_openmetrics() {
+    let actual = render_scoped_text(
+        MetricsFormat::OpenMetrics,
+        b"services=team-a-*\nfamilies=journald_faults,journald_messages_ingested",
+    );
+
+    assert_eq!(
+        actual,
+        "# TYPE journald_faults counter
+journald_faults_created 123.456
+journald_faults_total 2
+# TYPE journald_messages_ingested counter
+journald_messages_ingested_created{service=\"team-a-web\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 123.456
+journald_messages_ingested_total{service=\"team-a-web\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 1
+# EOF
+"
+    );
+}
+
+#[test]
+fn renders_only_families_in_scope_as_prometheus_text() {
+    let actual = render_scoped_text(MetricsFormat::PrometheusText, b"families=journald_faults");
+
+    assert_eq!(
+        actual,
+        "# TYPE journald_faults_total counter
+journald_faults_total 2
+"
+    );
+}
+
+#[test]
+fn renders_only_families_in_scope_as_protobuf() {
+    assert_eq!(
+        render_scoped_family_names(b"families=journald_*_ingested"),
+        [
+            "journald_entries_ingested_total",
+            "journald_fields_ingested_total",
+            "journald_messages_ingested_total",
+        ]
+    );
+}
+
+#[test]
+fn renders_no_families_if_none_are_in_scope() {
+    assert_eq!(
+        render_scoped_text(MetricsFormat::OpenMetrics, b"families=nope"),
+        "# EOF\n"
+    );
+    assert_eq!(
+        render_scoped_family_names(b"families=nope"),
+        Vec::<String>::new()
+    );
+}
@@ -1,7 +1,13 @@
 use crate::prelude::*;
 
+use crate::state::AuthFailureReason;
 use crate::state::ByteCountMap;
+use crate::state::FieldStats;
+use crate::state::JournalField;
 use crate::state::MessageKey;
+use crate::state::ScrapeHistogram;
+use crate::state::ScrapeStats;
+use crate::state::ScrapeStatsSnapshot;
 
 pub struct PromState {
     entries_ingested: Counter,
@@ -13,6 +19,8 @@ pub struct PromState {
     corrupted_fields: Counter,
     metrics_requests: Counter,
     messages_ingested: ByteCountMap,
+    fields: FieldStats,
+    scrape_stats: ScrapeStats,
 }
 
 impl PromState {
@@ -27,6 +35,8 @@ impl PromState {
             corrupted_fields: Counter::new(0),
             metrics_requests: Counter::new(0),
             messages_ingested: ByteCountMap::new(),
+            fields: FieldStats::new(),
+            scrape_stats: ScrapeStats::new(),
         }
     }
 
@@ -46,18 +56,26 @@ impl PromState {
     }
 
     #[cold]
-    pub fn add_corrupted_field(&self) {
+    pub fn add_unreadable_field_read(&self, field: JournalField) {
+        self.add_unreadable_field();
+        self.fields.add_unreadable(field);
+    }
+
+    #[cold]
+    pub fn add_corrupted_field(&self, field: JournalField) {
         self.corrupted_fields.increment();
+        self.fields.add_corrupted(field);
     }
 
     pub fn add_entry_ingested(&self) {
         self.entries_ingested.increment();
     }
 
-    pub fn add_field_ingested(&self, bytes: usize) {
+    pub fn add_field_ingested(&self, field: JournalField, bytes: usize) {
         self.fields_ingested.increment();
         self.data_ingested_bytes
             .increment_by(zero_extend_usize_u64(bytes));
+        self.fields.add_ingested(field, bytes);
     }
 
     pub fn add_metrics_requests(&self, requests: usize) {
@@ -65,8 +83,49 @@ impl PromState {
             .increment_by(zero_extend_usize_u64(requests));
     }
 
-    pub fn add_message_line_ingested(&self, key: &MessageKey, msg_len: usize) {
-        if !self.messages_ingested.push_line(key, msg_len) {
+    pub fn observe_scrape(&self, histogram: ScrapeHistogram, value: u64) {
+        self.scrape_stats.observe(histogram, value);
+    }
+
+    pub fn observe_scrape_duration(&self, histogram: ScrapeHistogram, duration: Duration) {
+        self.scrape_stats.observe_duration(histogram, duration);
+    }
+
+    pub fn add_http_responses(&self, code: u16, responses: usize) {
+        self.scrape_stats
+            .add_responses(code, zero_extend_usize_u64(responses));
+    }
+
+    pub fn add_auth_failures(&self, reason: AuthFailureReason, failures: usize) {
+        self.scrape_stats
+            .add_auth_failures(reason, zero_extend_usize_u64(failures));
+    }
+
+    pub fn add_throttled_requests(&self, reason: ThrottleReason, requests: usize) {
+        self.scrape_stats
+            .add_throttled_requests(reason, zero_extend_usize_u64(requests));
+    }
+
+    pub fn add_tls_handshakes(&self, suite: TlsCipherSuite, handshakes: usize) {
+        self.scrape_stats
+            .add_tls_handshakes(suite, zero_extend_usize_u64(handshakes));
+    }
+
+    pub fn add_key_requests(&self, name: &[u8], requests: usize) {
+        self.scrape_stats
+            .add_key_requests(name, zero_extend_usize_u64(requests));
+    }
+
+    // Kept apart from the main snapshot, as only the metrics endpoint itself renders these.
+    pub fn scrape_stats_snapshot(&self) -> ScrapeStatsSnapshot {
+        self.scrape_stats.snapshot()
+    }
+
+    pub fn add_message_line_ingested(&self, key: &MessageKey, msg_len: usize, realtime_usec: u64) {
+        if !self
+            .messages_ingested
+            .push_line(key, msg_len, realtime_usec)
+        {
             self.add_fault();
         }
     }
@@ -82,6 +141,7 @@ impl PromState {
             corrupted_fields: self.corrupted_fields.current(),
             metrics_requests: self.metrics_requests.current(),
             messages_ingested: self.messages_ingested.snapshot()?,
+            fields: self.fields.snapshot(),
         })
     }
 }
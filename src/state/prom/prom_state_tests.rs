@@ -43,6 +43,7 @@ fn returns_correct_initial_metrics() {
             corrupted_fields: 0,
             metrics_requests: 0,
             messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -65,6 +66,7 @@ fn correctly_tracks_a_single_fault() {
             corrupted_fields: 0,
             metrics_requests: 0,
             messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -87,6 +89,7 @@ fn correctly_tracks_a_single_cursor_double_retry() {
             corrupted_fields: 0,
             metrics_requests: 0,
             messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -109,6 +112,7 @@ fn correctly_tracks_a_single_unreadable_entry() {
             corrupted_fields: 0,
             metrics_requests: 0,
             messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -117,7 +121,7 @@ fn correctly_tracks_a_single_unreadable_entry() {
 fn correctly_tracks_a_single_corrupted_entry() {
     static STATE: PromState = PromState::new();
 
-    STATE.add_corrupted_field();
+    STATE.add_corrupted_field(JournalField::Message);
 
     assert_eq!(
         STATE.snapshot().unwrap(),
@@ -131,6 +135,90 @@ fn correctly_tracks_a_single_corrupted_entry() {
             corrupted_fields: 1,
             metrics_requests: 0,
             messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::build([(
+                JournalField::Message,
+                FieldStatsEntry {
+                    ingested: 0,
+                    ingested_bytes: 0,
+                    unreadable: 0,
+                    corrupted: 1,
+                },
+            )]),
+        }
+    );
+}
+
+#[test]
+fn correctly_tracks_a_single_unreadable_field_read() {
+    static STATE: PromState = PromState::new();
+
+    STATE.add_unreadable_field_read(JournalField::SystemdUnit);
+
+    assert_eq!(
+        STATE.snapshot().unwrap(),
+        PromSnapshot {
+            entries_ingested: 0,
+            fields_ingested: 0,
+            data_ingested_bytes: 0,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 1,
+            corrupted_fields: 0,
+            metrics_requests: 0,
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::build([(
+                JournalField::SystemdUnit,
+                FieldStatsEntry {
+                    ingested: 0,
+                    ingested_bytes: 0,
+                    unreadable: 1,
+                    corrupted: 0,
+                },
+            )]),
+        }
+    );
+}
+
+#[test]
+fn correctly_tracks_fields_ingested_per_field() {
+    static STATE: PromState = PromState::new();
+
+    STATE.add_field_ingested(JournalField::Priority, 1);
+    STATE.add_field_ingested(JournalField::Message, 123);
+    STATE.add_field_ingested(JournalField::Message, 45);
+
+    assert_eq!(
+        STATE.snapshot().unwrap(),
+        PromSnapshot {
+            entries_ingested: 0,
+            fields_ingested: 3,
+            data_ingested_bytes: 169,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 0,
+            corrupted_fields: 0,
+            metrics_requests: 0,
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 1,
+                        unreadable: 0,
+                        corrupted: 0,
+                    },
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 168,
+                        unreadable: 0,
+                        corrupted: 0,
+                    },
+                ),
+            ]),
         }
     );
 }
@@ -153,6 +241,7 @@ fn correctly_tracks_a_single_set_of_requests() {
             corrupted_fields: 0,
             metrics_requests: 123,
             messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -169,6 +258,7 @@ fn correctly_tracks_a_single_zero_byte_value() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         0,
+        0,
     );
 
     assert_eq!(
@@ -186,7 +276,9 @@ fn correctly_tracks_a_single_zero_byte_value() {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Informational),
                 lines: 1,
                 bytes: 0,
+                last_seen: 0,
             }]),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -198,6 +290,7 @@ fn correctly_tracks_a_single_message_without_a_service() {
     STATE.add_message_line_ingested(
         &message_key(Some(123), Some(123), Priority::Informational, None),
         5,
+        0,
     );
 
     assert_eq!(
@@ -215,7 +308,9 @@ fn correctly_tracks_a_single_message_without_a_service() {
                 key: MessageKey::build(Some(123), Some(123), None, Priority::Informational),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             }]),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -232,6 +327,7 @@ fn correctly_tracks_a_single_message_without_a_user() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
 
     assert_eq!(
@@ -249,7 +345,9 @@ fn correctly_tracks_a_single_message_without_a_user() {
                 key: MessageKey::build(None, Some(123), Some(b"foo"), Priority::Informational),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             }]),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -266,6 +364,7 @@ fn correctly_tracks_a_single_message_without_a_group() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
 
     assert_eq!(
@@ -283,7 +382,9 @@ fn correctly_tracks_a_single_message_without_a_group() {
                 key: MessageKey::build(Some(123), None, Some(b"foo"), Priority::Informational),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             }]),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -300,6 +401,7 @@ fn correctly_tracks_a_single_message() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
 
     assert_eq!(
@@ -317,7 +419,9 @@ fn correctly_tracks_a_single_message() {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Informational),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             }]),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -334,6 +438,7 @@ fn correctly_tracks_two_messages_across_two_services() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -343,6 +448,7 @@ fn correctly_tracks_two_messages_across_two_services() {
             Some(Service::from_slice(b"bar").unwrap()),
         ),
         7,
+        0,
     );
 
     assert_eq!(
@@ -361,6 +467,7 @@ fn correctly_tracks_two_messages_across_two_services() {
                     key: MessageKey::build(Some(456), Some(123), Some(b"bar"), Priority::Warning),
                     lines: 1,
                     bytes: 7,
+                    last_seen: 0,
                 },
                 ByteCountSnapshotEntry {
                     key: MessageKey::build(
@@ -371,8 +478,10 @@ fn correctly_tracks_two_messages_across_two_services() {
                     ),
                     lines: 1,
                     bytes: 5,
+                    last_seen: 0,
                 },
             ]),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -390,6 +499,7 @@ fn correctly_tracks_1_fault_and_1_message() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
 
     assert_eq!(
@@ -407,7 +517,9 @@ fn correctly_tracks_1_fault_and_1_message() {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Informational),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             }]),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -424,6 +536,7 @@ fn correctly_tracks_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -433,6 +546,7 @@ fn correctly_tracks_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -442,6 +556,7 @@ fn correctly_tracks_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -451,6 +566,7 @@ fn correctly_tracks_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -460,6 +576,7 @@ fn correctly_tracks_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -469,6 +586,7 @@ fn correctly_tracks_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -478,6 +596,7 @@ fn correctly_tracks_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -487,6 +606,7 @@ fn correctly_tracks_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -496,6 +616,7 @@ fn correctly_tracks_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -505,6 +626,7 @@ fn correctly_tracks_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
 
     let expected_ingested_message_data_params = [
@@ -523,6 +645,7 @@ fn correctly_tracks_10_same_service_messages() {
             key: MessageKey::build(Some(123), Some(123), Some(b"foo"), priority),
             lines,
             bytes,
+            last_seen: 0,
         },
     ));
 
@@ -538,6 +661,7 @@ fn correctly_tracks_10_same_service_messages() {
             corrupted_fields: 0,
             metrics_requests: 0,
             messages_ingested: ByteCountSnapshot::build(expected_messages_ingested),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -554,6 +678,7 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_fault();
     STATE.add_message_line_ingested(
@@ -564,6 +689,7 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -573,6 +699,7 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_fault();
     STATE.add_message_line_ingested(
@@ -583,6 +710,7 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_fault();
     STATE.add_message_line_ingested(
@@ -593,6 +721,7 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -602,6 +731,7 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_fault();
     STATE.add_fault();
@@ -613,6 +743,7 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -622,6 +753,7 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -631,6 +763,7 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
     STATE.add_message_line_ingested(
         &message_key(
@@ -640,6 +773,7 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
             Some(Service::from_slice(b"foo").unwrap()),
         ),
         5,
+        0,
     );
 
     let expected_ingested_message_data_params = [
@@ -659,6 +793,8 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
 
             lines,
             bytes,
+
+            last_seen: 0,
         },
     ));
 
@@ -674,6 +810,7 @@ fn correctly_tracks_5_faults_and_10_same_service_messages() {
             corrupted_fields: 0,
             metrics_requests: 0,
             messages_ingested: ByteCountSnapshot::build(expected_messages_ingested),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -725,6 +862,8 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
 
                 lines,
                 bytes,
+
+                last_seen: 0,
             })
         }
     }
@@ -738,6 +877,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_fault();
@@ -749,6 +889,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_message_line_ingested(
             &message_key(
@@ -758,6 +899,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_fault();
@@ -769,6 +911,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_message_line_ingested(
@@ -779,6 +922,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_message_line_ingested(
             &message_key(
@@ -788,6 +932,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_fault();
@@ -799,6 +944,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_message_line_ingested(
             &message_key(
@@ -808,6 +954,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_message_line_ingested(
             &message_key(
@@ -817,6 +964,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_message_line_ingested(
@@ -827,6 +975,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_fault();
@@ -843,6 +992,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_fault();
@@ -854,6 +1004,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_message_line_ingested(
             &message_key(
@@ -863,6 +1014,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_fault();
@@ -874,6 +1026,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_message_line_ingested(
@@ -884,6 +1037,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_message_line_ingested(
             &message_key(
@@ -893,6 +1047,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_fault();
@@ -904,6 +1059,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_message_line_ingested(
             &message_key(
@@ -913,6 +1069,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_message_line_ingested(
             &message_key(
@@ -922,6 +1079,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_message_line_ingested(
@@ -932,6 +1090,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
                 Some(Service::from_slice(name).unwrap()),
             ),
             5,
+            0,
         );
         STATE.add_fault();
         STATE.add_fault();
@@ -952,6 +1111,7 @@ fn correctly_tracks_500_faults_and_400_different_service_messages() {
             corrupted_fields: 0,
             metrics_requests: 0,
             messages_ingested: ByteCountSnapshot::build(expected_messages_ingested),
+            fields: FieldStatsSnapshot::empty(),
         }
     );
 }
@@ -0,0 +1,292 @@
+use crate::prelude::*;
+
+use super::prom_write::each_message_row;
+use super::prom_write::FieldCounterKind;
+use super::prom_write::MessageCounterKind;
+use std::collections::HashMap;
+
+// Encodes the change in each counter since the last snapshot as StatsD counters, with the labels
+// as DogStatsD tags, like so:
+//
+// ```
+// journald_entries_ingested:5|c|#env:prod
+// journald_messages_ingested:2|c|#service:foo.service,priority:INFO,user:root,group:root,env:prod
+// ```
+//
+// Counters that haven't changed are left out entirely. `--top-series` isn't applied here, as
+// series moving in and out of the top would otherwise show up as spurious jumps in the remainder.
+
+// The usual safe UDP payload size for DogStatsD. Lines are never split across packets.
+pub const STATSD_MAX_PACKET_BYTES: usize = 1432;
+
+struct PacketWriter {
+    packets: Vec<Vec<u8>>,
+    current: Vec<u8>,
+}
+
+impl PacketWriter {
+    // Returns `false` if allocation failed.
+    fn push_line(&mut self, line: &[u8]) -> bool {
+        if !self.current.is_empty()
+            && self.current.len().wrapping_add(1).wrapping_add(line.len()) > STATSD_MAX_PACKET_BYTES
+        {
+            if self.packets.try_reserve(1).is_err() {
+                return false;
+            }
+            self.packets.push(take(&mut self.current));
+        }
+
+        if self
+            .current
+            .try_reserve(line.len().wrapping_add(1))
+            .is_err()
+        {
+            return false;
+        }
+
+        if !self.current.is_empty() {
+            self.current.push(b'\n');
+        }
+
+        self.current.extend_from_slice(line);
+        true
+    }
+
+    fn finish(mut self) -> Option<Vec<Vec<u8>>> {
+        if !self.current.is_empty() {
+            self.packets.try_reserve(1).ok()?;
+            self.packets.push(self.current);
+        }
+        Some(self.packets)
+    }
+}
+
+// Commas, pipes, and `#` delimit tags, and newlines delimit lines, so replace them all to keep the
+// line parseable. This never changes the value's length.
+fn push_tag_value(target: &mut Vec<u8>, value: &[u8]) {
+    for &byte in value {
+        target.push(match byte {
+            b',' | b'|' | b'\n' | b'#' => b'_',
+            byte => byte,
+        });
+    }
+}
+
+// Series are tracked by their numeric user and group IDs rather than their rendered tags, so a
+// user or group being renamed between flushes doesn't look like a new series starting from zero.
+// Nearly every series is a message series, so boxing that variant wouldn't save anything.
+#[derive(PartialEq, Eq, Hash)]
+#[allow(clippy::large_enum_variant)]
+enum SeriesKey {
+    Global(&'static [u8]),
+    Field(&'static [u8], JournalField),
+    Message(&'static [u8], Priority, ByteCountTableKey),
+}
+
+struct SeriesWriter<'a> {
+    environment: &'a PromEnvironment,
+    previous: &'a HashMap<SeriesKey, u64>,
+    current: HashMap<SeriesKey, u64>,
+    packets: PacketWriter,
+}
+
+impl SeriesWriter<'_> {
+    // Returns `false` if allocation failed.
+    fn write(&mut self, key: SeriesKey, tags: &[(&[u8], &[u8])], value: u64) -> bool {
+        let name = match &key {
+            SeriesKey::Global(name) | SeriesKey::Field(name, _) | SeriesKey::Message(name, ..) => {
+                *name
+            }
+        };
+
+        let previous = self.previous.get(&key).copied().unwrap_or(0);
+
+        // Counters only go backwards if the series were reset, so treat that as a fresh start.
+        let delta = if value >= previous {
+            value.wrapping_sub(previous)
+        } else {
+            value
+        };
+
+        if delta != 0 && !self.write_line(name, tags, delta) {
+            return false;
+        }
+
+        if self.current.try_reserve(1).is_err() {
+            return false;
+        }
+
+        self.current.insert(key, value);
+        true
+    }
+
+    fn write_line(&mut self, name: &[u8], tags: &[(&[u8], &[u8])], delta: u64) -> bool {
+        let mut value_buffer = [0; MAX_USIZE_ASCII_BYTES];
+        let head = write_u64(&mut value_buffer, delta);
+        let value = &value_buffer[head..];
+
+        let static_tags = self
+            .environment
+            .static_labels()
+            .iter()
+            .map(|(name, value)| (name.as_bytes(), value.as_bytes()));
+        let all_tags = || tags.iter().copied().chain(static_tags.clone());
+
+        // Everything's reserved up front, so nothing below has to reallocate.
+        let line_len = all_tags().fold(
+            name.len().wrapping_add(value.len()).wrapping_add(3),
+            |len, (tag_name, tag_value)| {
+                len.wrapping_add(tag_name.len())
+                    .wrapping_add(tag_value.len())
+                    .wrapping_add(3)
+            },
+        );
+
+        let Some(mut line) = try_new_dynamic_vec(line_len) else {
+            return false;
+        };
+
+        line.extend_from_slice(name);
+        line.push(b':');
+        line.extend_from_slice(value);
+        line.extend_from_slice(b"|c");
+
+        let mut first = true;
+        for (tag_name, tag_value) in all_tags() {
+            line.extend_from_slice(if first { b"|#" } else { b"," });
+            first = false;
+            line.extend_from_slice(tag_name);
+            line.push(b':');
+            push_tag_value(&mut line, tag_value);
+        }
+
+        self.packets.push_line(&line)
+    }
+}
+
+pub struct StatsdEncoder {
+    previous: HashMap<SeriesKey, u64>,
+}
+
+impl StatsdEncoder {
+    pub fn new() -> Self {
+        Self {
+            previous: HashMap::new(),
+        }
+    }
+
+    /// Returns the packets to send, or `None` if allocation failed. The first call reports the
+    /// totals since startup.
+    pub fn encode(
+        &mut self,
+        environment: &PromEnvironment,
+        snapshot: &PromSnapshot,
+        table: &UidGidTable,
+    ) -> Option<Vec<Vec<u8>>> {
+        let mut current = HashMap::new();
+        current.try_reserve(self.previous.len()).ok()?;
+
+        let mut writer = SeriesWriter {
+            environment,
+            previous: &self.previous,
+            current,
+            packets: PacketWriter {
+                packets: Vec::new(),
+                current: Vec::new(),
+            },
+        };
+
+        let global_counters: [(&'static [u8], u64); 8] = [
+            (b"journald_entries_ingested", snapshot.entries_ingested),
+            (b"journald_fields_ingested", snapshot.fields_ingested),
+            (
+                b"journald_data_ingested_bytes",
+                snapshot.data_ingested_bytes,
+            ),
+            (b"journald_faults", snapshot.faults),
+            (
+                b"journald_cursor_double_retries",
+                snapshot.cursor_double_retries,
+            ),
+            (b"journald_unreadable_fields", snapshot.unreadable_fields),
+            (b"journald_corrupted_fields", snapshot.corrupted_fields),
+            (b"journald_metrics_requests", snapshot.metrics_requests),
+        ];
+
+        for (name, value) in global_counters {
+            if !writer.write(SeriesKey::Global(name), &[], value) {
+                return None;
+            }
+        }
+
+        if environment.field_stats {
+            let field_counters: [(&'static [u8], FieldCounterKind); 4] = [
+                (b"journald_field_ingested", FieldCounterKind::Ingested),
+                (
+                    b"journald_field_ingested_bytes",
+                    FieldCounterKind::IngestedBytes,
+                ),
+                (b"journald_field_unreadable", FieldCounterKind::Unreadable),
+                (b"journald_field_corrupted", FieldCounterKind::Corrupted),
+            ];
+
+            for (name, kind) in field_counters {
+                for field in JournalField::ALL {
+                    if !writer.write(
+                        SeriesKey::Field(name, field),
+                        &[(b"field", field.as_name_bytes())],
+                        kind.value(snapshot.fields.get(field)),
+                    ) {
+                        return None;
+                    }
+                }
+            }
+        }
+
+        let message_counters: [(&'static [u8], MessageCounterKind); 2] = [
+            (b"journald_messages_ingested", MessageCounterKind::Lines),
+            (
+                b"journald_messages_ingested_bytes",
+                MessageCounterKind::Bytes,
+            ),
+        ];
+
+        for (name, kind) in message_counters {
+            let completed = each_message_row(
+                None,
+                &snapshot.messages_ingested,
+                table,
+                &kind,
+                |labels, value| {
+                    // Top series aren't selected here, so every row has a key.
+                    let Some(key) = labels.key else {
+                        return true;
+                    };
+
+                    // It's only copyable in tests.
+                    #[allow(clippy::clone_on_copy)]
+                    let key = key.clone();
+
+                    writer.write(
+                        SeriesKey::Message(name, labels.priority, key),
+                        &[
+                            (b"service", labels.service),
+                            (b"priority", labels.priority.as_name_bytes()),
+                            (b"user", labels.user),
+                            (b"group", labels.group),
+                        ],
+                        value,
+                    )
+                },
+            );
+
+            if !completed {
+                return None;
+            }
+        }
+
+        let packets = writer.packets.finish()?;
+        self.previous = writer.current;
+        Some(packets)
+    }
+}
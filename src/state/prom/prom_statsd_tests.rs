@@ -0,0 +1,335 @@
+use crate::prelude::*;
+
+use super::*;
+
+fn encode(
+    encoder: &mut StatsdEncoder,
+    environment: &PromEnvironment,
+    snapshot: PromSnapshot,
+) -> Vec<String> {
+    let packets = encoder
+        .encode(environment, &snapshot, &get_user_group_table())
+        .unwrap();
+
+    for packet in &packets {
+        assert!(packet.len() <= STATSD_MAX_PACKET_BYTES);
+    }
+
+    packets
+        .iter()
+        .map(|packet| String::from_utf8(packet.clone()).unwrap())
+        .collect()
+}
+
+fn empty_snapshot() -> PromSnapshot {
+    PromSnapshot {
+        entries_ingested: 0,
+        fields_ingested: 0,
+        data_ingested_bytes: 0,
+        faults: 0,
+        cursor_double_retries: 0,
+        unreadable_fields: 0,
+        corrupted_fields: 0,
+        metrics_requests: 0,
+        messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
+    }
+}
+
+#[test]
+fn encodes_nothing_for_empty_snapshot() {
+    let mut encoder = StatsdEncoder::new();
+    let environment = PromEnvironment::new(mock_system_time(123, 456));
+
+    assert_eq!(
+        encode(&mut encoder, &environment, empty_snapshot()),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn encodes_global_counter_deltas_with_static_labels() {
+    let mut encoder = StatsdEncoder::new();
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("env", "prod");
+
+    assert_eq!(
+        encode(
+            &mut encoder,
+            &environment,
+            PromSnapshot {
+                entries_ingested: 1,
+                fields_ingested: 5,
+                data_ingested_bytes: 123,
+                faults: 2,
+                cursor_double_retries: 3,
+                unreadable_fields: 4,
+                corrupted_fields: 6,
+                metrics_requests: 7,
+                ..empty_snapshot()
+            },
+        ),
+        [concat!(
+            "journald_entries_ingested:1|c|#env:prod\n",
+            "journald_fields_ingested:5|c|#env:prod\n",
+            "journald_data_ingested_bytes:123|c|#env:prod\n",
+            "journald_faults:2|c|#env:prod\n",
+            "journald_cursor_double_retries:3|c|#env:prod\n",
+            "journald_unreadable_fields:4|c|#env:prod\n",
+            "journald_corrupted_fields:6|c|#env:prod\n",
+            "journald_metrics_requests:7|c|#env:prod",
+        )]
+    );
+
+    assert_eq!(
+        encode(
+            &mut encoder,
+            &environment,
+            PromSnapshot {
+                entries_ingested: 4,
+                fields_ingested: 5,
+                data_ingested_bytes: 200,
+                faults: 2,
+                cursor_double_retries: 3,
+                unreadable_fields: 4,
+                corrupted_fields: 6,
+                metrics_requests: 7,
+                ..empty_snapshot()
+            },
+        ),
+        [concat!(
+            "journald_entries_ingested:3|c|#env:prod\n",
+            "journald_data_ingested_bytes:77|c|#env:prod",
+        )]
+    );
+
+    assert_eq!(
+        encode(
+            &mut encoder,
+            &environment,
+            PromSnapshot {
+                entries_ingested: 4,
+                fields_ingested: 5,
+                data_ingested_bytes: 200,
+                faults: 2,
+                cursor_double_retries: 3,
+                unreadable_fields: 4,
+                corrupted_fields: 6,
+                metrics_requests: 7,
+                ..empty_snapshot()
+            },
+        ),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn encodes_message_counter_deltas_as_tags() {
+    let mut encoder = StatsdEncoder::new();
+    let environment = PromEnvironment::new(mock_system_time(123, 456));
+
+    let snapshot = |foo_lines, foo_bytes| PromSnapshot {
+        messages_ingested: ByteCountSnapshot::build([
+            ByteCountSnapshotEntry {
+                key: MessageKey::build(Some(123), Some(456), Some(b"foo"), Priority::Warning),
+                lines: foo_lines,
+                bytes: foo_bytes,
+                last_seen: 1_700_000_000_500_000,
+            },
+            ByteCountSnapshotEntry {
+                key: MessageKey::build(None, None, None, Priority::Error),
+                lines: 1,
+                bytes: 5,
+                last_seen: 0,
+            },
+        ]),
+        ..empty_snapshot()
+    };
+
+    assert_eq!(
+        encode(&mut encoder, &environment, snapshot(2, 15)),
+        [concat!(
+            "journald_messages_ingested:1|c|#service:?,priority:ERR,user:?,group:?\n",
+            "journald_messages_ingested:2|c|#service:foo,priority:WARNING,user:user_foo,group:group_bar\n",
+            "journald_messages_ingested_bytes:5|c|#service:?,priority:ERR,user:?,group:?\n",
+            "journald_messages_ingested_bytes:15|c|#service:foo,priority:WARNING,user:user_foo,group:group_bar",
+        )]
+    );
+
+    assert_eq!(
+        encode(&mut encoder, &environment, snapshot(5, 40)),
+        [concat!(
+            "journald_messages_ingested:3|c|#service:foo,priority:WARNING,user:user_foo,group:group_bar\n",
+            "journald_messages_ingested_bytes:25|c|#service:foo,priority:WARNING,user:user_foo,group:group_bar",
+        )]
+    );
+}
+
+#[test]
+fn tracks_renamed_users_and_groups_by_id() {
+    let mut encoder = StatsdEncoder::new();
+    let environment = PromEnvironment::new(mock_system_time(123, 456));
+
+    let snapshot = |lines, bytes| PromSnapshot {
+        messages_ingested: ByteCountSnapshot::build([ByteCountSnapshotEntry {
+            key: MessageKey::build(Some(123), Some(456), Some(b"foo"), Priority::Warning),
+            lines,
+            bytes,
+            last_seen: 0,
+        }]),
+        ..empty_snapshot()
+    };
+
+    let renamed = UidGidTable::new(
+        IdTable::from_entries(&[(123_u32, IdName::new(b"user_renamed"))]),
+        IdTable::from_entries(&[(456_u32, IdName::new(b"group_renamed"))]),
+    );
+
+    assert_eq!(
+        encode(&mut encoder, &environment, snapshot(2, 15)),
+        [concat!(
+            "journald_messages_ingested:2|c|#service:foo,priority:WARNING,user:user_foo,group:group_bar\n",
+            "journald_messages_ingested_bytes:15|c|#service:foo,priority:WARNING,user:user_foo,group:group_bar",
+        )]
+    );
+
+    let packets = encoder
+        .encode(&environment, &snapshot(3, 20), &renamed)
+        .unwrap();
+
+    assert_eq!(
+        packets,
+        [concat!(
+            "journald_messages_ingested:1|c|#service:foo,priority:WARNING,user:user_renamed,group:group_renamed\n",
+            "journald_messages_ingested_bytes:5|c|#service:foo,priority:WARNING,user:user_renamed,group:group_renamed",
+        )
+        .as_bytes()]
+    );
+}
+
+#[test]
+fn ignores_top_series() {
+    let mut encoder = StatsdEncoder::new();
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.top_series = std::num::NonZeroU32::new(1);
+
+    assert_eq!(
+        encode(
+            &mut encoder,
+            &environment,
+            PromSnapshot {
+                messages_ingested: ByteCountSnapshot::build([
+                    ByteCountSnapshotEntry {
+                        key: MessageKey::build(None, None, Some(b"foo"), Priority::Informational),
+                        lines: 5,
+                        bytes: 50,
+                        last_seen: 0,
+                    },
+                    ByteCountSnapshotEntry {
+                        key: MessageKey::build(None, None, Some(b"bar"), Priority::Informational),
+                        lines: 2,
+                        bytes: 20,
+                        last_seen: 0,
+                    },
+                ]),
+                ..empty_snapshot()
+            },
+        ),
+        [concat!(
+            "journald_messages_ingested:5|c|#service:foo,priority:INFO,user:?,group:?\n",
+            "journald_messages_ingested:2|c|#service:bar,priority:INFO,user:?,group:?\n",
+            "journald_messages_ingested_bytes:50|c|#service:foo,priority:INFO,user:?,group:?\n",
+            "journald_messages_ingested_bytes:20|c|#service:bar,priority:INFO,user:?,group:?",
+        )]
+    );
+}
+
+#[test]
+fn replaces_tag_delimiters_in_values() {
+    let mut encoder = StatsdEncoder::new();
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("env", "a,b|c#d\ne");
+
+    assert_eq!(
+        encode(
+            &mut encoder,
+            &environment,
+            PromSnapshot {
+                faults: 1,
+                ..empty_snapshot()
+            },
+        ),
+        ["journald_faults:1|c|#env:a_b_c_d_e"]
+    );
+}
+
+#[test]
+fn encodes_field_stats_deltas_when_enabled() {
+    let mut encoder = StatsdEncoder::new();
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.field_stats = true;
+
+    assert_eq!(
+        encode(
+            &mut encoder,
+            &environment,
+            PromSnapshot {
+                fields: FieldStatsSnapshot::build([(
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 8,
+                        unreadable: 1,
+                        corrupted: 0,
+                    },
+                )]),
+                ..empty_snapshot()
+            },
+        ),
+        [concat!(
+            "journald_field_ingested:2|c|#field:_UID\n",
+            "journald_field_ingested_bytes:8|c|#field:_UID\n",
+            "journald_field_unreadable:1|c|#field:_UID",
+        )]
+    );
+}
+
+#[test]
+fn splits_packets_between_lines() {
+    let mut encoder = StatsdEncoder::new();
+    let environment = PromEnvironment::new(mock_system_time(123, 456));
+
+    let services: Vec<String> = (0..100).map(|i| format!("service-{i:03}")).collect();
+
+    let packets = encode(
+        &mut encoder,
+        &environment,
+        PromSnapshot {
+            messages_ingested: ByteCountSnapshot::build(services.iter().map(|service| {
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(None, None, Some(service.as_bytes()), Priority::Debug),
+                    lines: 1,
+                    bytes: 1,
+                    last_seen: 0,
+                }
+            })),
+            ..empty_snapshot()
+        },
+    );
+
+    assert!(packets.len() > 1);
+
+    let lines: Vec<&str> = packets
+        .iter()
+        .flat_map(|packet| packet.split('\n'))
+        .collect();
+    assert_eq!(lines.len(), 200);
+    assert_eq!(
+        lines[0],
+        "journald_messages_ingested:1|c|#service:service-000,priority:DEBUG,user:?,group:?"
+    );
+    assert_eq!(
+        lines[199],
+        "journald_messages_ingested_bytes:1|c|#service:service-099,priority:DEBUG,user:?,group:?"
+    );
+}
@@ -0,0 +1,71 @@
+// Converts the OpenMetrics exposition from `render_openapi_metrics` (minus the IPC header) into
+// the classic Prometheus text format (version 0.0.4), for consumers that only understand that. The
+// differences that matter here are:
+//
+// - Counter families are named with their `_total` suffix.
+// - There's no `_created` series (for counters and histograms alike), `# UNIT` metadata, or `# EOF`
+//   terminator.
+//
+// This is line-based, as label values are always escaped and so never contain raw newlines.
+
+fn split_metadata_name(rest: &[u8]) -> (&[u8], &[u8]) {
+    match rest.iter().position(|&b| b == b' ') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, b""),
+    }
+}
+
+fn is_created_sample(line: &[u8], family_name: &[u8]) -> bool {
+    matches!(
+        line.strip_prefix(family_name)
+            .and_then(|rest| rest.strip_prefix(b"_created")),
+        Some([b' ' | b'{', ..])
+    )
+}
+
+pub fn openmetrics_to_prometheus_text(openmetrics: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(openmetrics.len());
+    // Empty if the current family isn't a counter.
+    let mut counter_name: &[u8] = b"";
+    // Empty if the current family doesn't have a `_created` series.
+    let mut created_name: &[u8] = b"";
+
+    for line in openmetrics.split(|&b| b == b'\n') {
+        if let Some(rest) = line.strip_prefix(b"# TYPE ") {
+            let (name, kind) = split_metadata_name(rest);
+            result.extend_from_slice(b"# TYPE ");
+            result.extend_from_slice(name);
+            if kind == b" counter" {
+                counter_name = name;
+                created_name = name;
+                result.extend_from_slice(b"_total");
+            } else if kind == b" histogram" {
+                counter_name = b"";
+                created_name = name;
+            } else {
+                counter_name = b"";
+                created_name = b"";
+            }
+            result.extend_from_slice(kind);
+        } else if let Some(rest) = line.strip_prefix(b"# HELP ") {
+            let (name, help) = split_metadata_name(rest);
+            result.extend_from_slice(b"# HELP ");
+            result.extend_from_slice(name);
+            if !counter_name.is_empty() && name == counter_name {
+                result.extend_from_slice(b"_total");
+            }
+            result.extend_from_slice(help);
+        } else if line.is_empty()
+            || line.starts_with(b"#")
+            || (!created_name.is_empty() && is_created_sample(line, created_name))
+        {
+            continue;
+        } else {
+            result.extend_from_slice(line);
+        }
+
+        result.push(b'\n');
+    }
+
+    result
+}
@@ -0,0 +1,185 @@
+use crate::prelude::*;
+
+use super::*;
+
+fn convert(openmetrics: &[u8]) -> String {
+    String::from_utf8(openmetrics_to_prometheus_text(openmetrics)).unwrap()
+}
+
+#[test]
+fn converts_rendered_snapshot() {
+    let rendered = render_openapi_metrics(
+        &PromEnvironment::new(mock_system_time(123, 456)),
+        &PromSnapshot {
+            entries_ingested: 1,
+            fields_ingested: 2,
+            data_ingested_bytes: 3,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 0,
+            corrupted_fields: 0,
+            metrics_requests: 4,
+            messages_ingested: ByteCountSnapshot::build([ByteCountSnapshotEntry {
+                key: MessageKey::build(Some(123), Some(456), Some(b"foo"), Priority::Warning),
+                lines: 2,
+                bytes: 15,
+                last_seen: 1_700_000_000_500_000,
+            }]),
+            fields: FieldStatsSnapshot::empty(),
+        },
+        &ProcessSnapshot::empty(),
+        None,
+        &get_user_group_table(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        convert(&rendered[ipc::parent::METRICS_RESPONSE_HEADER.len()..]),
+        "# TYPE journald_entries_ingested_total counter
+journald_entries_ingested_total 1
+# TYPE journald_fields_ingested_total counter
+journald_fields_ingested_total 2
+# TYPE journald_data_ingested_bytes_total counter
+journald_data_ingested_bytes_total 3
+# TYPE journald_faults_total counter
+journald_faults_total 0
+# TYPE journald_cursor_double_retries_total counter
+journald_cursor_double_retries_total 0
+# TYPE journald_unreadable_fields_total counter
+journald_unreadable_fields_total 0
+# TYPE journald_corrupted_fields_total counter
+journald_corrupted_fields_total 0
+# TYPE journald_metrics_requests_total counter
+journald_metrics_requests_total 4
+# TYPE journald_messages_ingested_total counter
+journald_messages_ingested_total{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_bar\"} 2
+# TYPE journald_messages_ingested_bytes_total counter
+journald_messages_ingested_bytes_total{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_bar\"} 15
+# TYPE journald_service_last_message_timestamp_seconds gauge
+journald_service_last_message_timestamp_seconds{service=\"foo\",priority=\"WARNING\",severity=\"4\"} 1700000000.500
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+"
+    );
+}
+
+#[test]
+fn renames_counter_help() {
+    assert_eq!(
+        convert(
+            b"# TYPE journald_faults counter
+# HELP journald_faults The number of faults.
+journald_faults_created 123.456
+journald_faults_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# HELP journald_service_last_message_timestamp_seconds The last message timestamp.
+# EOF
+"
+        ),
+        "# TYPE journald_faults_total counter
+# HELP journald_faults_total The number of faults.
+journald_faults_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# HELP journald_service_last_message_timestamp_seconds The last message timestamp.
+"
+    );
+}
+
+#[test]
+fn drops_histogram_created() {
+    assert_eq!(
+        convert(
+            b"# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+# HELP journald_render_size_bytes The render size.
+journald_render_size_bytes_bucket{le=\"1024\"} 1
+journald_render_size_bytes_bucket{le=\"+Inf\"} 2
+journald_render_size_bytes_count 2
+journald_render_size_bytes_sum 3000
+journald_render_size_bytes_created 123.456
+# EOF
+"
+        ),
+        "# TYPE journald_render_size_bytes histogram
+# HELP journald_render_size_bytes The render size.
+journald_render_size_bytes_bucket{le=\"1024\"} 1
+journald_render_size_bytes_bucket{le=\"+Inf\"} 2
+journald_render_size_bytes_count 2
+journald_render_size_bytes_sum 3000
+"
+    );
+}
+
+#[test]
+fn keeps_non_created_samples_sharing_the_prefix() {
+    assert_eq!(
+        convert(
+            b"# TYPE journald_faults counter
+journald_faults_created{a=\"b\"} 123.456
+journald_faults_createdx 1
+journald_faults_total{a=\"b\"} 0
+"
+        ),
+        "# TYPE journald_faults_total counter
+journald_faults_createdx 1
+journald_faults_total{a=\"b\"} 0
+"
+    );
+}
+
+#[test]
+fn renders_each_format_with_its_response_header() {
+    let environment = PromEnvironment::new(mock_system_time(123, 456));
+    let snapshot = PromSnapshot {
+        entries_ingested: 1,
+        fields_ingested: 2,
+        data_ingested_bytes: 3,
+        faults: 0,
+        cursor_double_retries: 0,
+        unreadable_fields: 0,
+        corrupted_fields: 0,
+        metrics_requests: 4,
+        messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
+    };
+    let process = ProcessSnapshot::empty();
+    let table = get_user_group_table();
+
+    let openmetrics =
+        render_openapi_metrics(&environment, &snapshot, &process, None, &table).unwrap();
+
+    assert_eq!(
+        render_metrics(
+            &environment,
+            &snapshot,
+            &process,
+            None,
+            &table,
+            ipc::MetricsFormat::OpenMetrics
+        ),
+        Some(openmetrics.clone())
+    );
+
+    let text = render_metrics(
+        &environment,
+        &snapshot,
+        &process,
+        None,
+        &table,
+        ipc::MetricsFormat::PrometheusText,
+    )
+    .unwrap();
+
+    let header_len = ipc::parent::TEXT_METRICS_RESPONSE_HEADER.len();
+    let expected = openmetrics_to_prometheus_text(&openmetrics[header_len..]);
+
+    assert_eq!(text[0], ipc::parent::TEXT_METRICS_RESPONSE_HEADER[0]);
+    assert_eq!(
+        text[1..header_len],
+        u32::try_from(expected.len()).unwrap().to_le_bytes()
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&text[header_len..]),
+        String::from_utf8_lossy(&expected)
+    );
+}
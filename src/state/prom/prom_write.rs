@@ -1,6 +1,8 @@
 use crate::prelude::*;
 
+use crate::state::ipc::MetricsFormat;
 use const_str::concat_bytes;
+use std::num::NonZeroU32;
 use std::time::SystemTime;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -14,6 +16,7 @@ pub struct PromSnapshot {
     pub corrupted_fields: u64,
     pub metrics_requests: u64,
     pub messages_ingested: ByteCountSnapshot,
+    pub fields: FieldStatsSnapshot,
 }
 
 // Max integer: 18446744073709551616
@@ -24,10 +27,41 @@ const CREATED_BUFFER_SIZE: usize = match MAX_USIZE_ASCII_BYTES.checked_add(CREAT
     None => unreachable!(),
 };
 
+// Every label name the exporter emits itself, across all metric families and formats. Static
+// labels are appended to these rows, so they can't reuse any of these names.
+pub const RESERVED_LABEL_NAMES: &[&str] = &[
+    "service",
+    "priority",
+    "severity",
+    "user",
+    "group",
+    "field",
+    "le",
+    "role",
+    "key",
+    "code",
+    "reason",
+    "version",
+    "cipher_suite",
+];
+
 pub struct PromEnvironment {
     // Inline `CREATED_BUFFER_SIZE` so sizes can auto-complete.
     created_buffer: [u8; 24],
     created_len: usize,
+    // The same timestamp, for the push formats that need it as a number.
+    created_unix_nanos: u64,
+    // If set, only this many series are emitted per message counter family, and the rest are
+    // summed into `__other__` series.
+    pub top_series: Option<NonZeroU32>,
+    // If set, the per-field-name breakdowns of the global field counters are emitted as well.
+    pub field_stats: bool,
+    // Pre-rendered static labels, as `{name="value",...}` for global counters and as
+    // `,name="value",...` for message counters. Both are empty if there's no static labels.
+    global_labels: Vec<u8>,
+    message_labels: Vec<u8>,
+    // The same static labels, unescaped, for the push formats.
+    static_labels: Vec<(Box<str>, Box<str>)>,
 }
 
 fn split_created_buffer(
@@ -74,48 +108,91 @@ pub fn write_u64(target: &mut [u8; MAX_USIZE_ASCII_BYTES], mut value: u64) -> us
     head
 }
 
+/// Writes the timestamp as a fixed-point decimal. Less work than writing out a floating point
+/// number, and it's easier to render subsecond precision correctly. Returns the start offset. The
+/// end offset is always implicitly the end.
+fn write_timestamp(target: &mut [u8; CREATED_BUFFER_SIZE], time: Duration) -> usize {
+    let (target_u64, target_millis) = split_created_buffer(target);
+    let start = write_u64(target_u64, time.as_secs());
+
+    // Don't expose a high-resolution timer over the network. Write it out at millisecond
+    // resolution only. Also shaves a few bytes off the output, as this is in the part that
+    // generates the per-message entry rows, and it lets me simplify the generation a bit, but the
+    // security part is the most important.
+    let millis = time.subsec_millis();
+    target_millis[0] = b'.';
+    target_millis[1] = truncate_u32_u8(millis / 100).wrapping_add(b'0');
+    target_millis[2] = truncate_u32_u8(millis / 10 % 10).wrapping_add(b'0');
+    target_millis[3] = truncate_u32_u8(millis % 10).wrapping_add(b'0');
+
+    start
+}
+
 impl PromEnvironment {
     pub fn new(created: SystemTime) -> Self {
         let created = created
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap_or(Duration::ZERO);
 
-        // Write it as a fixed-point decimal. Less work than writing out a floating point number, and
-        // it's easier to render subsecond precision correctly.
         let mut created_buffer = [0; CREATED_BUFFER_SIZE];
+        let created_start = write_timestamp(&mut created_buffer, created);
+        created_buffer.copy_within(created_start.., 0);
 
-        let created_start = {
-            let (target_u64, target_millis) = split_created_buffer(&mut created_buffer);
-            let created_start = write_u64(target_u64, created.as_secs());
-
-            // Don't expose a high-resolution timer over the network. Write it out at millisecond
-            // resolution only. Also shaves a few bytes off the output, as this is in the part that
-            // generates the per-message entry rows, and it lets me simplify the generation a bit,
-            // but the security part is the most important.
-            let millis = created.subsec_millis();
-            target_millis[0] = b'.';
-            target_millis[1] = truncate_u32_u8(millis / 100).wrapping_add(b'0');
-            target_millis[2] = truncate_u32_u8(millis / 10 % 10).wrapping_add(b'0');
-            target_millis[3] = truncate_u32_u8(millis % 10).wrapping_add(b'0');
-
-            created_buffer.copy_within(created_start.., 0);
-
-            created_start
-        };
+        // Truncate it to millisecond resolution, to match the rendered timestamp.
+        let created_millis = u64::try_from(created.as_millis()).unwrap_or(u64::MAX);
 
         Self {
             created_buffer,
             created_len: created_buffer.len().wrapping_sub(created_start),
+            created_unix_nanos: created_millis.saturating_mul(1_000_000),
+            top_series: None,
+            field_stats: false,
+            global_labels: Vec::new(),
+            message_labels: Vec::new(),
+            static_labels: Vec::new(),
         }
     }
 
+    // The name is expected to already be validated. The value is escaped here.
+    pub fn add_static_label(&mut self, name: &str, value: &str) {
+        self.static_labels.push((name.into(), value.into()));
+        self.message_labels.push(b',');
+        self.message_labels.extend_from_slice(name.as_bytes());
+        self.message_labels.extend_from_slice(b"=\"");
+
+        for &byte in value.as_bytes() {
+            match byte {
+                b'\\' => self.message_labels.extend_from_slice(b"\\\\"),
+                b'"' => self.message_labels.extend_from_slice(b"\\\""),
+                b'\n' => self.message_labels.extend_from_slice(b"\\n"),
+                byte => self.message_labels.push(byte),
+            }
+        }
+
+        self.message_labels.push(b'"');
+
+        self.global_labels.clear();
+        self.global_labels.push(b'{');
+        self.global_labels
+            .extend_from_slice(&self.message_labels[1..]);
+        self.global_labels.push(b'}');
+    }
+
+    pub fn static_labels(&self) -> &[(Box<str>, Box<str>)] {
+        &self.static_labels
+    }
+
+    pub fn created_unix_nanos(&self) -> u64 {
+        self.created_unix_nanos
+    }
+
     fn created_bytes(&self) -> &[u8] {
         // SAFETY: `self.created_len < self.created_buffer.len()` per the constructor.
         unsafe { std::slice::from_raw_parts(self.created_buffer.as_ptr(), self.created_len) }
     }
 }
 
-fn data_bytes_from_id<'a>(table: &'a IdTable, id: &Option<u32>) -> &'a [u8] {
+pub(super) fn data_bytes_from_id<'a>(table: &'a IdTable, id: &Option<u32>) -> &'a [u8] {
     id.and_then(|id| table.lookup_id(id))
         .map(|name| &**name)
         .unwrap_or(b"?")
@@ -124,6 +201,8 @@ fn data_bytes_from_id<'a>(table: &'a IdTable, id: &Option<u32>) -> &'a [u8] {
 struct Writer {
     result: Vec<u8>,
     value_buffer: [u8; MAX_USIZE_ASCII_BYTES],
+    sum_buffer: [u8; MAX_USIZE_ASCII_BYTES],
+    timestamp_buffer: [u8; CREATED_BUFFER_SIZE],
 }
 
 impl Writer {
@@ -155,6 +234,8 @@ impl Writer {
         Some(Self {
             result: try_new_dynamic_vec(80 * 1024)?,
             value_buffer: [0; MAX_USIZE_ASCII_BYTES],
+            sum_buffer: [0; MAX_USIZE_ASCII_BYTES],
+            timestamp_buffer: [0; CREATED_BUFFER_SIZE],
         })
     }
 
@@ -170,8 +251,12 @@ impl Writer {
             &mut self.result,
             &[
                 constants.header,
+                &environment.global_labels,
+                b" ",
                 environment.created_bytes(),
                 constants.total_label,
+                &environment.global_labels,
+                b" ",
                 &self.value_buffer[head..],
             ],
         )
@@ -190,8 +275,12 @@ impl Writer {
                 &mut self.result,
                 &[
                     constants.empty_fallback_header,
+                    &environment.global_labels,
+                    b" ",
                     environment.created_bytes(),
                     constants.empty_fallback_total,
+                    &environment.global_labels,
+                    b" 0",
                 ],
             )
         } else {
@@ -199,67 +288,611 @@ impl Writer {
                 return false;
             }
 
-            snapshot.each_while(|priority, data| {
-                let head = write_u64(
-                    &mut self.value_buffer,
-                    match constants.kind {
-                        MessageCounterKind::Lines => data.lines,
-                        MessageCounterKind::Bytes => data.bytes,
-                    },
-                );
+            each_message_row(
+                environment.top_series,
+                snapshot,
+                table,
+                &constants.kind,
+                |labels, value| self.write_message_row(constants, environment, labels, value),
+            )
+        }
+    }
+
+    fn write_message_row(
+        &mut self,
+        constants: &'static MessageCounterConstants,
+        environment: &PromEnvironment,
+        labels: MessageRowLabels,
+        value: u64,
+    ) -> bool {
+        let head = write_u64(&mut self.value_buffer, value);
+        let priority_name = labels.priority.as_name_bytes();
+        let priority_severity = [labels.priority.as_severity_byte()];
+
+        write_slices(
+            &mut self.result,
+            &[
+                // *_created key
+                constants.created_prefix,
+                labels.service,
+                b"\",priority=\"",
+                priority_name,
+                b"\",severity=\"",
+                &priority_severity,
+                b"\",user=\"",
+                labels.user,
+                b"\",group=\"",
+                labels.group,
+                b"\"",
+                &environment.message_labels,
+                b"} ",
+                environment.created_bytes(),
+                // *_total key
+                constants.total_prefix,
+                labels.service,
+                b"\",priority=\"",
+                priority_name,
+                b"\",severity=\"",
+                &priority_severity,
+                b"\",user=\"",
+                labels.user,
+                b"\",group=\"",
+                labels.group,
+                b"\"",
+                &environment.message_labels,
+                b"} ",
+                &self.value_buffer[head..],
+            ],
+        )
+    }
+}
+
+// Visits each row of a message counter, only visiting the top series (and the remainder) if
+// `top_series` is set. Returns `false` if the receiver returned `false` or if allocation failed.
+pub(super) fn each_message_row(
+    top_series: Option<NonZeroU32>,
+    snapshot: &ByteCountSnapshot,
+    table: &UidGidTable,
+    kind: &MessageCounterKind,
+    mut receiver: impl FnMut(MessageRowLabels, u64) -> bool,
+) -> bool {
+    // Only select the top series if asked to. Everything is retained otherwise.
+    let selection = match top_series {
+        None => None,
+        Some(limit) => {
+            match snapshot.select_top(zero_extend_u32_usize(limit.get()), |data| kind.value(data)) {
+                None => return false,
+                Some(selection) => Some(selection),
+            }
+        }
+    };
+
+    let mut index = 0_usize;
+
+    let completed = snapshot.each_while(|priority, data| {
+        let current = index;
+        index = index.wrapping_add(1);
+
+        if let Some(selection) = &selection {
+            if !selection.is_retained(current) {
+                return true;
+            }
+        }
+
+        receiver(
+            MessageRowLabels {
+                key: Some(&data.key),
+                service: data.key.service().map(|s| s.as_bytes()).unwrap_or(b"?"),
+                priority,
+                user: data_bytes_from_id(&table.uids, &data.key.uid),
+                group: data_bytes_from_id(&table.gids, &data.key.gid),
+            },
+            kind.value(data),
+        )
+    });
+
+    if !completed {
+        return false;
+    }
+
+    match &selection {
+        None => true,
+        Some(selection) => selection.each_remainder(|priority, sum| {
+            receiver(
+                MessageRowLabels {
+                    key: None,
+                    service: OTHER_SERIES_LABEL,
+                    priority,
+                    user: OTHER_SERIES_LABEL,
+                    group: OTHER_SERIES_LABEL,
+                },
+                sum,
+            )
+        }),
+    }
+}
+
+// Entries are also keyed by user and group, so this merges those to get the latest per service
+// and priority, sorted by priority and then service. Entries without a known timestamp are skipped
+// entirely. Returns `None` if allocation failed.
+pub(super) fn collect_last_seen(
+    snapshot: &ByteCountSnapshot,
+) -> Option<Vec<(Priority, &[u8], u64)>> {
+    let mut latest = try_new_dynamic_vec::<(Priority, &[u8], u64)>(snapshot.len())?;
+
+    snapshot.each_while(|priority, data| {
+        if data.last_seen != 0 {
+            let service = data.key.service().map(|s| s.as_bytes()).unwrap_or(b"?");
+            latest.push((priority, service, data.last_seen));
+        }
+        true
+    });
+
+    latest.sort_unstable();
+    latest.dedup_by(|next, prev| {
+        if next.0 == prev.0 && next.1 == prev.1 {
+            prev.2 = prev.2.max(next.2);
+            true
+        } else {
+            false
+        }
+    });
+
+    Some(latest)
+}
+
+impl Writer {
+    fn write_last_seen_gauges(
+        &mut self,
+        constants: &'static LastSeenGaugeConstants,
+        environment: &PromEnvironment,
+        snapshot: &ByteCountSnapshot,
+    ) -> bool {
+        if !write_slices(&mut self.result, &[constants.header]) {
+            return false;
+        }
+
+        let Some(latest) = collect_last_seen(snapshot) else {
+            return false;
+        };
+
+        for &(priority, service, last_seen) in &latest {
+            let head =
+                write_timestamp(&mut self.timestamp_buffer, Duration::from_micros(last_seen));
+
+            if !write_slices(
+                &mut self.result,
+                &[
+                    constants.row_prefix,
+                    service,
+                    b"\",priority=\"",
+                    priority.as_name_bytes(),
+                    b"\",severity=\"",
+                    &[priority.as_severity_byte()],
+                    b"\"",
+                    &environment.message_labels,
+                    b"} ",
+                    &self.timestamp_buffer[head..],
+                ],
+            ) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl Writer {
+    fn write_field_counters(
+        &mut self,
+        constants: &'static FieldCounterConstants,
+        environment: &PromEnvironment,
+        snapshot: &FieldStatsSnapshot,
+    ) -> bool {
+        if !write_slices(&mut self.result, &[constants.header]) {
+            return false;
+        }
+
+        for field in JournalField::ALL {
+            let head = write_u64(
+                &mut self.value_buffer,
+                constants.kind.value(snapshot.get(field)),
+            );
+
+            if !write_slices(
+                &mut self.result,
+                &[
+                    // *_created key
+                    constants.created_prefix,
+                    field.as_name_bytes(),
+                    b"\"",
+                    &environment.message_labels,
+                    b"} ",
+                    environment.created_bytes(),
+                    // *_total key
+                    constants.total_prefix,
+                    field.as_name_bytes(),
+                    b"\"",
+                    &environment.message_labels,
+                    b"} ",
+                    &self.value_buffer[head..],
+                ],
+            ) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl Writer {
+    fn write_labeled_counters<'a>(
+        &mut self,
+        constants: &'static LabeledCounterConstants,
+        environment: &PromEnvironment,
+        rows: impl IntoIterator<Item = (&'a [u8], u64)>,
+    ) -> bool {
+        if !write_slices(&mut self.result, &[constants.header]) {
+            return false;
+        }
+
+        for (label, value) in rows {
+            let head = write_u64(&mut self.value_buffer, value);
+
+            if !write_slices(
+                &mut self.result,
+                &[
+                    // *_created key
+                    constants.created_prefix,
+                    label,
+                    b"\"",
+                    &environment.message_labels,
+                    b"} ",
+                    environment.created_bytes(),
+                    // *_total key
+                    constants.total_prefix,
+                    label,
+                    b"\"",
+                    &environment.message_labels,
+                    b"} ",
+                    &self.value_buffer[head..],
+                ],
+            ) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Suites never negotiated are left out, and so is the whole family if none were, so plain HTTP
+    // servers don't render it at all.
+    fn write_tls_handshakes(
+        &mut self,
+        header: &'static [u8],
+        environment: &PromEnvironment,
+        rows: impl IntoIterator<Item = (TlsCipherSuite, u64)>,
+    ) -> bool {
+        let mut header = Some(header);
+
+        for (suite, value) in rows {
+            if value == 0 {
+                continue;
+            }
+
+            if let Some(header) = header.take() {
+                if !write_slices(&mut self.result, &[header]) {
+                    return false;
+                }
+            }
+
+            let head = write_u64(&mut self.value_buffer, value);
+            let version = suite.version().as_label().as_bytes();
+            let name = suite.as_name().as_bytes();
+
+            if !write_slices(
+                &mut self.result,
+                &[
+                    // *_created key
+                    b"\njournald_tls_handshakes_created{version=\"",
+                    version,
+                    b"\",cipher_suite=\"",
+                    name,
+                    b"\"",
+                    &environment.message_labels,
+                    b"} ",
+                    environment.created_bytes(),
+                    // *_total key
+                    b"\njournald_tls_handshakes_total{version=\"",
+                    version,
+                    b"\",cipher_suite=\"",
+                    name,
+                    b"\"",
+                    &environment.message_labels,
+                    b"} ",
+                    &self.value_buffer[head..],
+                ],
+            ) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl Writer {
+    fn write_build_info(&mut self, header: &'static [u8], environment: &PromEnvironment) -> bool {
+        write_slices(
+            &mut self.result,
+            &[
+                header,
+                b"\njournald_exporter_build_info{version=\"",
+                BUILD_VERSION.as_bytes(),
+                b"\"",
+                &environment.message_labels,
+                b"} 1",
+            ],
+        )
+    }
+
+    fn write_histogram(
+        &mut self,
+        constants: &'static HistogramConstants,
+        environment: &PromEnvironment,
+        snapshot: &HistogramSnapshot,
+    ) -> bool {
+        if !write_slices(&mut self.result, &[constants.header]) {
+            return false;
+        }
+
+        let bounds = constants.kind.bound_labels().iter().map(|l| l.as_bytes());
+        let buckets = bounds.zip(&snapshot.buckets);
+
+        for (bound, &value) in buckets.chain([(&b"+Inf"[..], &snapshot.count)]) {
+            let head = write_u64(&mut self.value_buffer, value);
+
+            if !write_slices(
+                &mut self.result,
+                &[
+                    constants.bucket_prefix,
+                    bound,
+                    b"\"",
+                    &environment.message_labels,
+                    b"} ",
+                    &self.value_buffer[head..],
+                ],
+            ) {
+                return false;
+            }
+        }
 
-                let service_bytes = data.key.service().map(|s| s.as_bytes()).unwrap_or(b"?");
-                let priority_name = priority.as_name_bytes();
-                let priority_severity = [priority.as_severity_byte()];
-                let user_name = data_bytes_from_id(&table.uids, &data.key.uid);
-                let group_name = data_bytes_from_id(&table.gids, &data.key.gid);
+        let count_head = write_u64(&mut self.value_buffer, snapshot.count);
+        let sum = if constants.kind.is_duration() {
+            let head = write_timestamp(
+                &mut self.timestamp_buffer,
+                Duration::from_micros(snapshot.sum),
+            );
+            &self.timestamp_buffer[head..]
+        } else {
+            let head = write_u64(&mut self.sum_buffer, snapshot.sum);
+            &self.sum_buffer[head..]
+        };
 
-                write_slices(
+        write_slices(
+            &mut self.result,
+            &[
+                constants.count_label,
+                &environment.global_labels,
+                b" ",
+                &self.value_buffer[count_head..],
+                constants.sum_label,
+                &environment.global_labels,
+                b" ",
+                sum,
+                constants.created_label,
+                &environment.global_labels,
+                b" ",
+                environment.created_bytes(),
+            ],
+        )
+    }
+
+    fn write_process_metrics(
+        &mut self,
+        constants: &'static ProcessMetricConstants,
+        environment: &PromEnvironment,
+        process: &ProcessSnapshot,
+    ) -> bool {
+        if !write_slices(&mut self.result, &[constants.header]) {
+            return false;
+        }
+
+        for (role, stats) in each_process(process) {
+            if let Some(created_prefix) = constants.created_prefix {
+                let head = write_timestamp(&mut self.timestamp_buffer, stats.start_time);
+
+                if !write_slices(
                     &mut self.result,
                     &[
-                        // *_created key
-                        constants.created_prefix,
-                        service_bytes,
-                        b"\",priority=\"",
-                        priority_name,
-                        b"\",severity=\"",
-                        &priority_severity,
-                        b"\",user=\"",
-                        user_name,
-                        b"\",group=\"",
-                        group_name,
-                        b"\"} ",
-                        environment.created_bytes(),
-                        // *_total key
-                        constants.total_prefix,
-                        service_bytes,
-                        b"\",priority=\"",
-                        priority_name,
-                        b"\",severity=\"",
-                        &priority_severity,
-                        b"\",user=\"",
-                        user_name,
-                        b"\",group=\"",
-                        group_name,
-                        b"\"} ",
-                        &self.value_buffer[head..],
+                        created_prefix,
+                        role,
+                        b"\"",
+                        &environment.message_labels,
+                        b"} ",
+                        &self.timestamp_buffer[head..],
                     ],
-                )
-            })
+                ) {
+                    return false;
+                }
+            }
+
+            let value = match constants.kind.value(stats) {
+                ProcessValue::Count(value) => {
+                    let head = write_u64(&mut self.value_buffer, value);
+                    &self.value_buffer[head..]
+                }
+                ProcessValue::Seconds(value) => {
+                    let head = write_timestamp(&mut self.timestamp_buffer, value);
+                    &self.timestamp_buffer[head..]
+                }
+            };
+
+            if !write_slices(
+                &mut self.result,
+                &[
+                    constants.row_prefix,
+                    role,
+                    b"\"",
+                    &environment.message_labels,
+                    b"} ",
+                    value,
+                ],
+            ) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+// Pinned in tests, so version bumps don't churn every rendered snapshot.
+#[cfg(test)]
+pub(super) const BUILD_VERSION: &str = "test";
+#[cfg(not(test))]
+pub(super) const BUILD_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Visits each process with stats present, along with its `role` label.
+pub(super) fn each_process(
+    process: &ProcessSnapshot,
+) -> impl Iterator<Item = (&'static [u8], &ProcessStats)> {
+    let parent = process.parent.as_ref().map(|stats| (&b"parent"[..], stats));
+    let child = process.child.as_ref().map(|stats| (&b"child"[..], stats));
+    parent.into_iter().chain(child)
+}
+
+pub(super) enum ProcessValue {
+    Count(u64),
+    Seconds(Duration),
+}
+
+pub(super) enum ProcessMetricKind {
+    CpuSeconds,
+    ResidentMemoryBytes,
+    VirtualMemoryBytes,
+    OpenFds,
+    MaxFds,
+    Threads,
+    StartTimeSeconds,
+}
+
+impl ProcessMetricKind {
+    pub fn value(&self, stats: &ProcessStats) -> ProcessValue {
+        match self {
+            ProcessMetricKind::CpuSeconds => ProcessValue::Seconds(stats.cpu_time),
+            ProcessMetricKind::ResidentMemoryBytes => {
+                ProcessValue::Count(stats.resident_memory_bytes)
+            }
+            ProcessMetricKind::VirtualMemoryBytes => {
+                ProcessValue::Count(stats.virtual_memory_bytes)
+            }
+            ProcessMetricKind::OpenFds => ProcessValue::Count(stats.open_fds),
+            ProcessMetricKind::MaxFds => ProcessValue::Count(stats.max_fds),
+            ProcessMetricKind::Threads => ProcessValue::Count(stats.threads),
+            ProcessMetricKind::StartTimeSeconds => ProcessValue::Seconds(stats.start_time),
         }
     }
 }
 
+struct HistogramConstants {
+    kind: ScrapeHistogram,
+    header: &'static [u8],
+    bucket_prefix: &'static [u8],
+    count_label: &'static [u8],
+    sum_label: &'static [u8],
+    created_label: &'static [u8],
+}
+
+struct LabeledCounterConstants {
+    // Empty if the rows are added to a family already written out.
+    header: &'static [u8],
+    created_prefix: &'static [u8],
+    total_prefix: &'static [u8],
+}
+
+struct ProcessMetricConstants {
+    kind: ProcessMetricKind,
+    header: &'static [u8],
+    // Only set for counters, which use the process start time as their created timestamp.
+    created_prefix: Option<&'static [u8]>,
+    row_prefix: &'static [u8],
+}
+
+pub(super) enum FieldCounterKind {
+    Ingested,
+    IngestedBytes,
+    Unreadable,
+    Corrupted,
+}
+
+impl FieldCounterKind {
+    pub fn value(&self, data: &FieldStatsEntry) -> u64 {
+        match self {
+            FieldCounterKind::Ingested => data.ingested,
+            FieldCounterKind::IngestedBytes => data.ingested_bytes,
+            FieldCounterKind::Unreadable => data.unreadable,
+            FieldCounterKind::Corrupted => data.corrupted,
+        }
+    }
+}
+
+struct FieldCounterConstants {
+    kind: FieldCounterKind,
+    header: &'static [u8],
+    created_prefix: &'static [u8],
+    total_prefix: &'static [u8],
+}
+
+struct LastSeenGaugeConstants {
+    header: &'static [u8],
+    row_prefix: &'static [u8],
+}
+
+// Used in place of all of `service`, `user`, and `group` for the series summing up everything not
+// in the top series.
+pub(super) const OTHER_SERIES_LABEL: &[u8] = b"__other__";
+
+pub(super) struct MessageRowLabels<'a> {
+    // The row's table key, or `None` for the remainder of the top series.
+    pub key: Option<&'a ByteCountTableKey>,
+    pub service: &'a [u8],
+    pub priority: Priority,
+    pub user: &'a [u8],
+    pub group: &'a [u8],
+}
+
 struct GlobalCounterConstants {
     header: &'static [u8],
     total_label: &'static [u8],
 }
 
-enum MessageCounterKind {
+pub(super) enum MessageCounterKind {
     Lines,
     Bytes,
 }
 
+impl MessageCounterKind {
+    pub fn value(&self, data: &ByteCountTableEntrySnapshot) -> u64 {
+        match self {
+            MessageCounterKind::Lines => data.lines,
+            MessageCounterKind::Bytes => data.bytes,
+        }
+    }
+}
+
 struct MessageCounterConstants {
     kind: MessageCounterKind,
     header: &'static [u8],
@@ -272,13 +905,15 @@ struct MessageCounterConstants {
 pub fn render_openapi_metrics(
     environment: &PromEnvironment,
     snapshot: &PromSnapshot,
+    process: &ProcessSnapshot,
+    scrape: Option<&ScrapeStatsSnapshot>,
     table: &UidGidTable,
 ) -> Option<Vec<u8>> {
     let mut writer = Writer::new()?;
 
     // This macro hackery literally makes this reasonable.
-    macro_rules! counter_header {
-        ($(is_first:$is_first:expr,)? key:$key:ident, $(unit:$unit:expr,)? help:$help:expr $(,)?) => {{
+    macro_rules! metric_header {
+        ($(is_first:$is_first:expr,)? $(prefix:$prefix:expr,)? type:$type:ident, key:$key:ident, $(unit:$unit:expr,)? help:$help:expr $(,)?) => {{
             const IS_FIRST: bool = {
                 #[allow(unused)]
                 let is_first = false;
@@ -286,11 +921,18 @@ pub fn render_openapi_metrics(
                 is_first
             };
 
-            const NAME: &[u8] = concat_bytes!("journald_", stringify!($key));
+            const PREFIX: &str = {
+                #[allow(unused)]
+                let prefix = "journald_";
+                $(let prefix = $prefix;)?
+                prefix
+            };
+
+            const NAME: &[u8] = concat_bytes!(PREFIX, stringify!($key));
 
             const HEADER_NO_HELP: &[u8] = concat_bytes!(
                 if IS_FIRST { ipc::parent::METRICS_RESPONSE_HEADER } else { b"\n" },
-                b"# TYPE ", NAME, " counter",
+                b"# TYPE ", NAME, " ", stringify!($type),
                 $("\n# UNIT ", NAME, " ", stringify!($unit),)?
             );
 
@@ -310,15 +952,16 @@ pub fn render_openapi_metrics(
 
             static CONSTANTS: GlobalCounterConstants = GlobalCounterConstants {
                 header: concat_bytes!(
-                    counter_header! {
+                    metric_header! {
                         $(is_first: $is_first,)?
+                        type: counter,
                         key: $key,
                         $(unit: $unit,)?
                         help: $help,
                     },
-                    "\n", NAME, "_created "
+                    "\n", NAME, "_created"
                 ),
-                total_label: concat_bytes!(b"\n", NAME, "_total "),
+                total_label: concat_bytes!(b"\n", NAME, "_total"),
             };
             if !writer.write_global_counter(&CONSTANTS, environment, snapshot.$key) {
                 return None;
@@ -328,7 +971,8 @@ pub fn render_openapi_metrics(
 
     macro_rules! write_message_counter {
         (kind:$kind:ident, key:$key:ident, $(unit:$unit:expr,)? help:$help:expr $(,)?) => {{
-            const HEADER: &[u8] = counter_header! {
+            const HEADER: &[u8] = metric_header! {
+                type:counter,
                 key:$key,
                 $(unit:$unit,)?
                 help:$help,
@@ -339,8 +983,8 @@ pub fn render_openapi_metrics(
             static CONSTANTS: MessageCounterConstants = MessageCounterConstants {
                 kind: MessageCounterKind::$kind,
                 header: HEADER,
-                empty_fallback_header: concat_bytes!(HEADER, "\n", NAME, "_created "),
-                empty_fallback_total: concat_bytes!("\n", NAME, "_total 0"),
+                empty_fallback_header: concat_bytes!(HEADER, "\n", NAME, "_created"),
+                empty_fallback_total: concat_bytes!("\n", NAME, "_total"),
                 created_prefix: concat_bytes!("\n", NAME, "_created{service=\""),
                 total_prefix: concat_bytes!("\n", NAME, "_total{service=\""),
             };
@@ -361,9 +1005,9 @@ pub fn render_openapi_metrics(
     }
     write_global_counter! {
         key: fields_ingested,
-        help: b"The total number of data fields read. Normally it's 5 fields (`_SYSTEMD_UNIT`, \
-        `PRIORITY`, `_UID`, `_GID`, and `MESSAGE`) for message entries, but may be fewer in the \
-        case of incomplete fields and such.",
+        help: b"The total number of data fields read. Each message entry has up to 5 fields read \
+        (`_SYSTEMD_UNIT`, `PRIORITY`, `_UID`, `_GID`, and `MESSAGE`), plus `_CMDLINE` when field \
+        stats are enabled.",
     }
     write_global_counter! {
         key: data_ingested_bytes,
@@ -397,6 +1041,65 @@ pub fn render_openapi_metrics(
         standard `GET /metrics` route.",
     }
 
+    // Requests per named key, only when serving the metrics endpoint
+    if let Some(scrape) = scrape {
+        static CONSTANTS: LabeledCounterConstants = LabeledCounterConstants {
+            header: b"",
+            created_prefix: b"\njournald_metrics_requests_created{key=\"",
+            total_prefix: b"\njournald_metrics_requests_total{key=\"",
+        };
+        if !writer.write_labeled_counters(&CONSTANTS, environment, scrape.key_requests()) {
+            return None;
+        }
+    }
+
+    macro_rules! write_field_counter {
+        (kind:$kind:ident, key:$key:ident, $(unit:$unit:expr,)? help:$help:expr $(,)?) => {{
+            const NAME: &[u8] = concat_bytes!("journald_", stringify!($key));
+
+            static CONSTANTS: FieldCounterConstants = FieldCounterConstants {
+                kind: FieldCounterKind::$kind,
+                header: metric_header! {
+                    type: counter,
+                    key: $key,
+                    $(unit: $unit,)?
+                    help: $help,
+                },
+                created_prefix: concat_bytes!("\n", NAME, "_created{field=\""),
+                total_prefix: concat_bytes!("\n", NAME, "_total{field=\""),
+            };
+            if !writer.write_field_counters(&CONSTANTS, environment, &snapshot.fields) {
+                return None;
+            }
+        }};
+    }
+
+    // Per-field counters, only if requested
+    if environment.field_stats {
+        write_field_counter! {
+            kind: Ingested,
+            key: field_ingested,
+            help: b"The number of times each data field was read.",
+        }
+        write_field_counter! {
+            kind: IngestedBytes,
+            key: field_ingested_bytes,
+            unit: bytes,
+            help: b"The number of data field bytes ingested for each field, including both the key \
+            and its value.",
+        }
+        write_field_counter! {
+            kind: Unreadable,
+            key: field_unreadable,
+            help: b"The number of times each field was too large to be read.",
+        }
+        write_field_counter! {
+            kind: Corrupted,
+            key: field_corrupted,
+            help: b"The number of times each field was detected as corrupted.",
+        }
+    }
+
     // Per-service counters
     write_message_counter! {
         kind: Lines,
@@ -410,6 +1113,233 @@ pub fn render_openapi_metrics(
         help: b"Total number of `MESSAGE` field bytes ingested.",
     }
 
+    // Per-service gauges
+    {
+        static CONSTANTS: LastSeenGaugeConstants = LastSeenGaugeConstants {
+            header: metric_header! {
+                type: gauge,
+                key: service_last_message_timestamp_seconds,
+                unit: seconds,
+                help: b"The realtime timestamp of the most recent message entry, as recorded in \
+                the journal.",
+            },
+            row_prefix: b"\njournald_service_last_message_timestamp_seconds{service=\"",
+        };
+        if !writer.write_last_seen_gauges(&CONSTANTS, environment, &snapshot.messages_ingested) {
+            return None;
+        }
+    }
+
+    // Build info
+    {
+        const HEADER: &[u8] = metric_header! {
+            type: gauge,
+            key: exporter_build_info,
+            help: b"A metric with a constant '1' value labeled by the version of the exporter.",
+        };
+        if !writer.write_build_info(HEADER, environment) {
+            return None;
+        }
+    }
+
+    // Scrape histograms and counters, only when serving the metrics endpoint
+    if let Some(scrape) = scrape {
+        macro_rules! write_histogram {
+            (kind:$kind:ident, key:$key:ident, unit:$unit:expr, help:$help:expr $(,)?) => {{
+                const NAME: &[u8] = concat_bytes!("journald_", stringify!($key));
+
+                static CONSTANTS: HistogramConstants = HistogramConstants {
+                    kind: ScrapeHistogram::$kind,
+                    header: metric_header! {
+                        type: histogram,
+                        key: $key,
+                        unit: $unit,
+                        help: $help,
+                    },
+                    bucket_prefix: concat_bytes!("\n", NAME, "_bucket{le=\""),
+                    count_label: concat_bytes!("\n", NAME, "_count"),
+                    sum_label: concat_bytes!("\n", NAME, "_sum"),
+                    created_label: concat_bytes!("\n", NAME, "_created"),
+                };
+                if !writer.write_histogram(
+                    &CONSTANTS,
+                    environment,
+                    scrape.get(ScrapeHistogram::$kind),
+                ) {
+                    return None;
+                }
+            }};
+        }
+
+        write_histogram! {
+            kind: ScrapeDuration,
+            key: scrape_duration_seconds,
+            unit: seconds,
+            help: b"Time from receiving a metrics request to sending its response.",
+        }
+        write_histogram! {
+            kind: IpcDuration,
+            key: scrape_ipc_duration_seconds,
+            unit: seconds,
+            help: b"Time from asking the collector process for metrics to receiving them.",
+        }
+        write_histogram! {
+            kind: RenderDuration,
+            key: render_duration_seconds,
+            unit: seconds,
+            help: b"Time spent rendering metrics for a request.",
+        }
+        write_histogram! {
+            kind: RenderSize,
+            key: render_size_bytes,
+            unit: bytes,
+            help: b"Size of the metrics rendered for a request, before any compression.",
+        }
+
+        macro_rules! write_labeled_counter {
+            (key:$key:ident, label:$label:ident, rows:$rows:expr, help:$help:expr $(,)?) => {{
+                const NAME: &[u8] = concat_bytes!("journald_", stringify!($key));
+
+                static CONSTANTS: LabeledCounterConstants = LabeledCounterConstants {
+                    header: metric_header! {
+                        type: counter,
+                        key: $key,
+                        help: $help,
+                    },
+                    created_prefix: concat_bytes!(
+                        "\n",
+                        NAME,
+                        "_created{",
+                        stringify!($label),
+                        "=\""
+                    ),
+                    total_prefix: concat_bytes!("\n", NAME, "_total{", stringify!($label), "=\""),
+                };
+                if !writer.write_labeled_counters(&CONSTANTS, environment, $rows) {
+                    return None;
+                }
+            }};
+        }
+
+        write_labeled_counter! {
+            key: http_responses,
+            label: code,
+            rows: scrape.responses().map(|(code, count)| (code.as_bytes(), count)),
+            help: b"The total number of HTTP responses sent, by status code.",
+        }
+        write_labeled_counter! {
+            key: auth_failures,
+            label: reason,
+            rows: scrape
+                .auth_failures()
+                .map(|(reason, count)| (reason.as_label().as_bytes(), count)),
+            help: b"The total number of metrics requests rejected for failing authorization.",
+        }
+
+        write_labeled_counter! {
+            key: throttled_requests,
+            label: reason,
+            rows: scrape
+                .throttled_requests()
+                .map(|(reason, count)| (reason.as_label().as_bytes(), count)),
+            help: b"The total number of authorized metrics requests rejected for exceeding a rate \
+            limit, by the limit exceeded.",
+        }
+
+        {
+            const HEADER: &[u8] = metric_header! {
+                type: counter,
+                key: tls_handshakes,
+                help: b"The total number of completed TLS handshakes, by negotiated version and \
+                cipher suite.",
+            };
+            if !writer.write_tls_handshakes(HEADER, environment, scrape.tls_handshakes()) {
+                return None;
+            }
+        }
+    }
+
+    macro_rules! write_process_metric {
+        (kind:$kind:ident, type:$type:ident, key:$key:ident, $(unit:$unit:expr,)? help:$help:expr $(,)?) => {{
+            const NAME: &[u8] = concat_bytes!("process_", stringify!($key));
+            const IS_COUNTER: bool = matches!(stringify!($type).as_bytes(), b"counter");
+
+            static CONSTANTS: ProcessMetricConstants = ProcessMetricConstants {
+                kind: ProcessMetricKind::$kind,
+                header: metric_header! {
+                    prefix: "process_",
+                    type: $type,
+                    key: $key,
+                    $(unit: $unit,)?
+                    help: $help,
+                },
+                created_prefix: if IS_COUNTER {
+                    Some(concat_bytes!("\n", NAME, "_created{role=\""))
+                } else {
+                    None
+                },
+                row_prefix: if IS_COUNTER {
+                    concat_bytes!("\n", NAME, "_total{role=\"")
+                } else {
+                    concat_bytes!("\n", NAME, "{role=\"")
+                },
+            };
+            if !writer.write_process_metrics(&CONSTANTS, environment, process) {
+                return None;
+            }
+        }};
+    }
+
+    // Process metrics, only if there's any to report
+    if process.parent.is_some() || process.child.is_some() {
+        write_process_metric! {
+            kind: CpuSeconds,
+            type: counter,
+            key: cpu_seconds,
+            unit: seconds,
+            help: b"Total user and system CPU time spent in seconds.",
+        }
+        write_process_metric! {
+            kind: ResidentMemoryBytes,
+            type: gauge,
+            key: resident_memory_bytes,
+            unit: bytes,
+            help: b"Resident memory size in bytes.",
+        }
+        write_process_metric! {
+            kind: VirtualMemoryBytes,
+            type: gauge,
+            key: virtual_memory_bytes,
+            unit: bytes,
+            help: b"Virtual memory size in bytes.",
+        }
+        write_process_metric! {
+            kind: OpenFds,
+            type: gauge,
+            key: open_fds,
+            help: b"Number of open file descriptors.",
+        }
+        write_process_metric! {
+            kind: MaxFds,
+            type: gauge,
+            key: max_fds,
+            help: b"Maximum number of open file descriptors.",
+        }
+        write_process_metric! {
+            kind: Threads,
+            type: gauge,
+            key: threads,
+            help: b"Number of OS threads in the process.",
+        }
+        write_process_metric! {
+            kind: StartTimeSeconds,
+            type: gauge,
+            key: start_time_seconds,
+            unit: seconds,
+            help: b"Start time of the process since unix epoch in seconds.",
+        }
+    }
+
     if !write_slices(&mut writer.result, &[b"\n# EOF\n"]) {
         return None;
     }
@@ -418,3 +1348,44 @@ pub fn render_openapi_metrics(
 
     Some(writer.result)
 }
+
+/// Strip the IPC response header from `render_openapi_metrics`'s output, leaving the bare
+/// OpenMetrics body.
+pub fn openmetrics_body(rendered: &[u8]) -> &[u8] {
+    &rendered[ipc::parent::METRICS_RESPONSE_HEADER.len()..]
+}
+
+/// Render the metrics in the requested format, as an IPC metrics response for that format.
+pub fn render_metrics(
+    environment: &PromEnvironment,
+    snapshot: &PromSnapshot,
+    process: &ProcessSnapshot,
+    scrape: Option<&ScrapeStatsSnapshot>,
+    table: &UidGidTable,
+    format: MetricsFormat,
+) -> Option<Vec<u8>> {
+    let body = match format {
+        MetricsFormat::OpenMetrics => {
+            return render_openapi_metrics(environment, snapshot, process, scrape, table);
+        }
+        MetricsFormat::PrometheusText => {
+            let rendered = render_openapi_metrics(environment, snapshot, process, scrape, table)?;
+            openmetrics_to_prometheus_text(openmetrics_body(&rendered))
+        }
+        MetricsFormat::Protobuf => {
+            encode_prometheus_protobuf(environment, snapshot, process, scrape, table)?
+        }
+    };
+
+    metrics_response(format, &body)
+}
+
+// Wraps an already-rendered body as an IPC metrics response for that format.
+pub(super) fn metrics_response(format: MetricsFormat, body: &[u8]) -> Option<Vec<u8>> {
+    let header = ipc::parent::metrics_response_header(format);
+    let mut result = try_new_dynamic_vec(header.len().saturating_add(body.len()))?;
+    result.extend_from_slice(header);
+    result.extend_from_slice(body);
+    ipc::parent::finish_response_metrics(&mut result);
+    Some(result)
+}
@@ -3,9 +3,15 @@ use crate::prelude::*;
 use super::*;
 
 fn render(snapshot: PromSnapshot) -> Vec<u8> {
+    render_with_environment(&PromEnvironment::new(mock_system_time(123, 456)), snapshot)
+}
+
+fn render_with_environment(environment: &PromEnvironment, snapshot: PromSnapshot) -> Vec<u8> {
     render_openapi_metrics(
-        &PromEnvironment::new(mock_system_time(123, 456)),
+        environment,
         &snapshot,
+        &ProcessSnapshot::empty(),
+        None,
         &get_user_group_table(),
     )
     .unwrap()
@@ -38,6 +44,7 @@ fn renders_1_digit_entries_ingested() {
         corrupted_fields: 0,
         metrics_requests: 0,
         messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -74,6 +81,10 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 ",
     );
@@ -91,6 +102,7 @@ fn renders_max_entries_ingested() {
         corrupted_fields: 0,
         metrics_requests: 0,
         messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -127,6 +139,10 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 ",
     );
@@ -144,6 +160,7 @@ fn renders_max_fields_ingested() {
         corrupted_fields: 0,
         metrics_requests: 0,
         messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -180,6 +197,10 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 ",
     );
@@ -197,6 +218,7 @@ fn renders_max_data_ingested_bytes() {
         corrupted_fields: 0,
         metrics_requests: 0,
         messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -233,6 +255,10 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 ",
     );
@@ -250,6 +276,7 @@ fn renders_max_faults() {
         corrupted_fields: 0,
         metrics_requests: 0,
         messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -286,6 +313,10 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 ",
     );
@@ -303,6 +334,7 @@ fn renders_max_cursor_double_retries() {
         corrupted_fields: 0,
         metrics_requests: 0,
         messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -339,6 +371,10 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 ",
     );
@@ -356,6 +392,7 @@ fn renders_max_unreadable_entries() {
         corrupted_fields: 0,
         metrics_requests: 0,
         messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -392,6 +429,10 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 ",
     );
@@ -409,6 +450,7 @@ fn renders_max_corrupted_entries() {
         corrupted_fields: u64::MAX,
         metrics_requests: 0,
         messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -445,6 +487,10 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 ",
     );
@@ -462,6 +508,7 @@ fn renders_max_requests() {
         corrupted_fields: 0,
         metrics_requests: u64::MAX,
         messages_ingested: ByteCountSnapshot::empty(),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -498,6 +545,10 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 ",
     );
@@ -518,7 +569,9 @@ fn renders_a_single_empty_message_key_ingested() {
             key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Informational),
             lines: 1,
             bytes: 0,
+            last_seen: 0,
         }]),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -555,6 +608,10 @@ journald_messages_ingested_total{service=\"foo\",priority=\"INFO\",severity=\"6\
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 123.456
 journald_messages_ingested_bytes_total{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 "
     );
@@ -575,7 +632,9 @@ fn renders_a_single_small_message_key_ingested() {
             key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Informational),
             lines: 1,
             bytes: 5,
+            last_seen: 0,
         }]),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -612,6 +671,10 @@ journald_messages_ingested_total{service=\"foo\",priority=\"INFO\",severity=\"6\
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 123.456
 journald_messages_ingested_bytes_total{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 5
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 "
     );
@@ -632,7 +695,9 @@ fn renders_a_single_max_len_message_key_ingested() {
             key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Informational),
             lines: 1,
             bytes: u64::MAX,
+            last_seen: 0,
         }]),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -669,6 +734,10 @@ journald_messages_ingested_total{service=\"foo\",priority=\"INFO\",severity=\"6\
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 123.456
 journald_messages_ingested_bytes_total{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 18446744073709551615
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 "
     );
@@ -689,7 +758,9 @@ fn renders_a_single_max_lines_message_key_ingested() {
             key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Informational),
             lines: u64::MAX,
             bytes: 5,
+            last_seen: 0,
         }]),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -726,6 +797,10 @@ journald_messages_ingested_total{service=\"foo\",priority=\"INFO\",severity=\"6\
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 123.456
 journald_messages_ingested_bytes_total{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 5
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 "
     );
@@ -747,13 +822,16 @@ fn renders_two_messages_across_two_services() {
                 key: MessageKey::build(Some(123), Some(456), Some(b"foo"), Priority::Informational),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(456), Some(123), Some(b"bar"), Priority::Warning),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
         ]),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -794,6 +872,10 @@ journald_messages_ingested_bytes_created{service=\"bar\",priority=\"WARNING\",se
 journald_messages_ingested_bytes_total{service=\"bar\",priority=\"WARNING\",severity=\"4\",user=\"user_bar\",group=\"group_foo\"} 5
 journald_messages_ingested_bytes_created{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_bar\"} 123.456
 journald_messages_ingested_bytes_total{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_bar\"} 5
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 "
     );
@@ -814,7 +896,9 @@ fn renders_1_fault_and_1_message() {
             key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Informational),
             lines: 1,
             bytes: 5,
+            last_seen: 0,
         }]),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -851,6 +935,10 @@ journald_messages_ingested_total{service=\"foo\",priority=\"INFO\",severity=\"6\
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 123.456
 journald_messages_ingested_bytes_total{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 5
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 "
     );
@@ -872,43 +960,52 @@ fn renders_multiple_priority_levels_within_same_service() {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Emergency),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Alert),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Critical),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Error),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Warning),
                 lines: 2,
                 bytes: 10,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Notice),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Informational),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Debug),
                 lines: 2,
                 bytes: 10,
+                last_seen: 0,
             },
         ]),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -973,6 +1070,10 @@ journald_messages_ingested_bytes_created{service=\"foo\",priority=\"INFO\",sever
 journald_messages_ingested_bytes_total{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 5
 journald_messages_ingested_bytes_created{service=\"foo\",priority=\"DEBUG\",severity=\"7\",user=\"user_foo\",group=\"group_foo\"} 123.456
 journald_messages_ingested_bytes_total{service=\"foo\",priority=\"DEBUG\",severity=\"7\",user=\"user_foo\",group=\"group_foo\"} 10
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 "
     );
@@ -994,43 +1095,52 @@ fn renders_5_faults_and_multiple_priority_levels_within_same_service() {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Emergency),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Alert),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Critical),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Error),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Warning),
                 lines: 2,
                 bytes: 10,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Notice),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Informational),
                 lines: 1,
                 bytes: 5,
+                last_seen: 0,
             },
             ByteCountSnapshotEntry {
                 key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Debug),
                 lines: 2,
                 bytes: 10,
+                last_seen: 0,
             },
         ]),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -1095,6 +1205,10 @@ journald_messages_ingested_bytes_created{service=\"foo\",priority=\"INFO\",sever
 journald_messages_ingested_bytes_total{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_foo\"} 5
 journald_messages_ingested_bytes_created{service=\"foo\",priority=\"DEBUG\",severity=\"7\",user=\"user_foo\",group=\"group_foo\"} 123.456
 journald_messages_ingested_bytes_total{service=\"foo\",priority=\"DEBUG\",severity=\"7\",user=\"user_foo\",group=\"group_foo\"} 10
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 "
     );
@@ -1108,6 +1222,7 @@ fn renders_500_faults_and_400_different_service_messages() {
             key: MessageKey::build(None, None, None, Priority::Emergency),
             lines: 0,
             bytes: 0,
+            last_seen: 0,
         }; 160];
 
         let service_names: [&[u8]; 20] = [
@@ -1156,6 +1271,7 @@ fn renders_500_faults_and_400_different_service_messages() {
                     key: MessageKey::build(Some(123), Some(123), Some(service), priority),
                     lines,
                     bytes,
+                    last_seen: 0,
                 };
 
                 j += 1;
@@ -1177,6 +1293,7 @@ fn renders_500_faults_and_400_different_service_messages() {
         corrupted_fields: 0,
         metrics_requests: 0,
         messages_ingested: ByteCountSnapshot::build(MESSAGES_INGESTED),
+        fields: FieldStatsSnapshot::empty(),
     });
 
     assert_snapshot_eq(
@@ -1849,7 +1966,1016 @@ journald_messages_ingested_bytes_created{service=\"service19\",priority=\"DEBUG\
 journald_messages_ingested_bytes_total{service=\"service19\",priority=\"DEBUG\",severity=\"7\",user=\"user_foo\",group=\"group_foo\"} 20
 journald_messages_ingested_bytes_created{service=\"service20\",priority=\"DEBUG\",severity=\"7\",user=\"user_foo\",group=\"group_foo\"} 123.456
 journald_messages_ingested_bytes_total{service=\"service20\",priority=\"DEBUG\",severity=\"7\",user=\"user_foo\",group=\"group_foo\"} 20
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 "
     );
 }
+
+#[test]
+fn renders_top_series_with_the_rest_summed_into_other() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.top_series = std::num::NonZeroU32::new(1);
+
+    let actual = render_with_environment(
+        &environment,
+        PromSnapshot {
+            entries_ingested: 0,
+            fields_ingested: 0,
+            data_ingested_bytes: 0,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 0,
+            corrupted_fields: 0,
+            metrics_requests: 0,
+            messages_ingested: ByteCountSnapshot::build([
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(
+                        Some(123),
+                        Some(456),
+                        Some(b"foo"),
+                        Priority::Informational,
+                    ),
+                    lines: 3,
+                    bytes: 5,
+                    last_seen: 0,
+                },
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(Some(456), Some(123), Some(b"bar"), Priority::Warning),
+                    lines: 1,
+                    bytes: 20,
+                    last_seen: 0,
+                },
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(
+                        Some(123),
+                        Some(456),
+                        Some(b"baz"),
+                        Priority::Informational,
+                    ),
+                    lines: 1,
+                    bytes: 2,
+                    last_seen: 0,
+                },
+            ]),
+            fields: FieldStatsSnapshot::empty(),
+        },
+    );
+
+    assert_snapshot_eq(
+        actual,
+        b"# TYPE journald_entries_ingested counter
+journald_entries_ingested_created 123.456
+journald_entries_ingested_total 0
+# TYPE journald_fields_ingested counter
+journald_fields_ingested_created 123.456
+journald_fields_ingested_total 0
+# TYPE journald_data_ingested_bytes counter
+# UNIT journald_data_ingested_bytes bytes
+journald_data_ingested_bytes_created 123.456
+journald_data_ingested_bytes_total 0
+# TYPE journald_faults counter
+journald_faults_created 123.456
+journald_faults_total 0
+# TYPE journald_cursor_double_retries counter
+journald_cursor_double_retries_created 123.456
+journald_cursor_double_retries_total 0
+# TYPE journald_unreadable_fields counter
+journald_unreadable_fields_created 123.456
+journald_unreadable_fields_total 0
+# TYPE journald_corrupted_fields counter
+journald_corrupted_fields_created 123.456
+journald_corrupted_fields_total 0
+# TYPE journald_metrics_requests counter
+journald_metrics_requests_created 123.456
+journald_metrics_requests_total 0
+# TYPE journald_messages_ingested counter
+journald_messages_ingested_created{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_bar\"} 123.456
+journald_messages_ingested_total{service=\"foo\",priority=\"INFO\",severity=\"6\",user=\"user_foo\",group=\"group_bar\"} 3
+journald_messages_ingested_created{service=\"__other__\",priority=\"WARNING\",severity=\"4\",user=\"__other__\",group=\"__other__\"} 123.456
+journald_messages_ingested_total{service=\"__other__\",priority=\"WARNING\",severity=\"4\",user=\"__other__\",group=\"__other__\"} 1
+journald_messages_ingested_created{service=\"__other__\",priority=\"INFO\",severity=\"6\",user=\"__other__\",group=\"__other__\"} 123.456
+journald_messages_ingested_total{service=\"__other__\",priority=\"INFO\",severity=\"6\",user=\"__other__\",group=\"__other__\"} 1
+# TYPE journald_messages_ingested_bytes counter
+# UNIT journald_messages_ingested_bytes bytes
+journald_messages_ingested_bytes_created{service=\"bar\",priority=\"WARNING\",severity=\"4\",user=\"user_bar\",group=\"group_foo\"} 123.456
+journald_messages_ingested_bytes_total{service=\"bar\",priority=\"WARNING\",severity=\"4\",user=\"user_bar\",group=\"group_foo\"} 20
+journald_messages_ingested_bytes_created{service=\"__other__\",priority=\"INFO\",severity=\"6\",user=\"__other__\",group=\"__other__\"} 123.456
+journald_messages_ingested_bytes_total{service=\"__other__\",priority=\"INFO\",severity=\"6\",user=\"__other__\",group=\"__other__\"} 7
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# EOF
+",
+    );
+}
+
+#[test]
+fn renders_static_labels_with_no_messages() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("env", "prod");
+    environment.add_static_label("cluster", "eu1");
+
+    let actual = render_with_environment(
+        &environment,
+        PromSnapshot {
+            entries_ingested: 1,
+            fields_ingested: 0,
+            data_ingested_bytes: 0,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 0,
+            corrupted_fields: 0,
+            metrics_requests: 0,
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
+        },
+    );
+
+    assert_snapshot_eq(
+        actual,
+        b"# TYPE journald_entries_ingested counter
+journald_entries_ingested_created{env=\"prod\",cluster=\"eu1\"} 123.456
+journald_entries_ingested_total{env=\"prod\",cluster=\"eu1\"} 1
+# TYPE journald_fields_ingested counter
+journald_fields_ingested_created{env=\"prod\",cluster=\"eu1\"} 123.456
+journald_fields_ingested_total{env=\"prod\",cluster=\"eu1\"} 0
+# TYPE journald_data_ingested_bytes counter
+# UNIT journald_data_ingested_bytes bytes
+journald_data_ingested_bytes_created{env=\"prod\",cluster=\"eu1\"} 123.456
+journald_data_ingested_bytes_total{env=\"prod\",cluster=\"eu1\"} 0
+# TYPE journald_faults counter
+journald_faults_created{env=\"prod\",cluster=\"eu1\"} 123.456
+journald_faults_total{env=\"prod\",cluster=\"eu1\"} 0
+# TYPE journald_cursor_double_retries counter
+journald_cursor_double_retries_created{env=\"prod\",cluster=\"eu1\"} 123.456
+journald_cursor_double_retries_total{env=\"prod\",cluster=\"eu1\"} 0
+# TYPE journald_unreadable_fields counter
+journald_unreadable_fields_created{env=\"prod\",cluster=\"eu1\"} 123.456
+journald_unreadable_fields_total{env=\"prod\",cluster=\"eu1\"} 0
+# TYPE journald_corrupted_fields counter
+journald_corrupted_fields_created{env=\"prod\",cluster=\"eu1\"} 123.456
+journald_corrupted_fields_total{env=\"prod\",cluster=\"eu1\"} 0
+# TYPE journald_metrics_requests counter
+journald_metrics_requests_created{env=\"prod\",cluster=\"eu1\"} 123.456
+journald_metrics_requests_total{env=\"prod\",cluster=\"eu1\"} 0
+# TYPE journald_messages_ingested counter
+journald_messages_ingested_created{env=\"prod\",cluster=\"eu1\"} 123.456
+journald_messages_ingested_total{env=\"prod\",cluster=\"eu1\"} 0
+# TYPE journald_messages_ingested_bytes counter
+# UNIT journald_messages_ingested_bytes bytes
+journald_messages_ingested_bytes_created{env=\"prod\",cluster=\"eu1\"} 123.456
+journald_messages_ingested_bytes_total{env=\"prod\",cluster=\"eu1\"} 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\",env=\"prod\",cluster=\"eu1\"} 1
+# EOF
+",
+    );
+}
+
+#[test]
+fn renders_static_labels_with_messages_and_escapes_values() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("note", "a \"quoted\\path\"\nline");
+
+    let actual = render_with_environment(
+        &environment,
+        PromSnapshot {
+            entries_ingested: 0,
+            fields_ingested: 0,
+            data_ingested_bytes: 0,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 0,
+            corrupted_fields: 0,
+            metrics_requests: 0,
+            messages_ingested: ByteCountSnapshot::build([ByteCountSnapshotEntry {
+                key: MessageKey::build(Some(123), Some(456), Some(b"foo"), Priority::Warning),
+                lines: 1,
+                bytes: 5,
+                last_seen: 0,
+            }]),
+            fields: FieldStatsSnapshot::empty(),
+        },
+    );
+
+    assert_snapshot_eq(
+        actual,
+        b"# TYPE journald_entries_ingested counter
+journald_entries_ingested_created{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 123.456
+journald_entries_ingested_total{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 0
+# TYPE journald_fields_ingested counter
+journald_fields_ingested_created{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 123.456
+journald_fields_ingested_total{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 0
+# TYPE journald_data_ingested_bytes counter
+# UNIT journald_data_ingested_bytes bytes
+journald_data_ingested_bytes_created{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 123.456
+journald_data_ingested_bytes_total{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 0
+# TYPE journald_faults counter
+journald_faults_created{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 123.456
+journald_faults_total{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 0
+# TYPE journald_cursor_double_retries counter
+journald_cursor_double_retries_created{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 123.456
+journald_cursor_double_retries_total{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 0
+# TYPE journald_unreadable_fields counter
+journald_unreadable_fields_created{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 123.456
+journald_unreadable_fields_total{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 0
+# TYPE journald_corrupted_fields counter
+journald_corrupted_fields_created{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 123.456
+journald_corrupted_fields_total{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 0
+# TYPE journald_metrics_requests counter
+journald_metrics_requests_created{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 123.456
+journald_metrics_requests_total{note=\"a \\\"quoted\\\\path\\\"\\nline\"} 0
+# TYPE journald_messages_ingested counter
+journald_messages_ingested_created{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_bar\",note=\"a \\\"quoted\\\\path\\\"\\nline\"} 123.456
+journald_messages_ingested_total{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_bar\",note=\"a \\\"quoted\\\\path\\\"\\nline\"} 1
+# TYPE journald_messages_ingested_bytes counter
+# UNIT journald_messages_ingested_bytes bytes
+journald_messages_ingested_bytes_created{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_bar\",note=\"a \\\"quoted\\\\path\\\"\\nline\"} 123.456
+journald_messages_ingested_bytes_total{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_bar\",note=\"a \\\"quoted\\\\path\\\"\\nline\"} 5
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\",note=\"a \\\"quoted\\\\path\\\"\\nline\"} 1
+# EOF
+",
+    );
+}
+
+#[test]
+fn renders_last_seen_gauges_merged_across_users_and_groups() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("host", "a");
+
+    let actual = render_with_environment(
+        &environment,
+        PromSnapshot {
+            entries_ingested: 0,
+            fields_ingested: 0,
+            data_ingested_bytes: 0,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 0,
+            corrupted_fields: 0,
+            metrics_requests: 0,
+            messages_ingested: ByteCountSnapshot::build([
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Warning),
+                    lines: 1,
+                    bytes: 5,
+                    last_seen: 1_700_000_000_123_456,
+                },
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(Some(456), Some(456), Some(b"foo"), Priority::Warning),
+                    lines: 1,
+                    bytes: 5,
+                    last_seen: 1_700_000_000_223_456,
+                },
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(None, None, None, Priority::Error),
+                    lines: 1,
+                    bytes: 5,
+                    last_seen: 1_700_000_001_000_000,
+                },
+                ByteCountSnapshotEntry {
+                    key: MessageKey::build(None, None, Some(b"bar"), Priority::Error),
+                    lines: 1,
+                    bytes: 5,
+                    last_seen: 0,
+                },
+            ]),
+            fields: FieldStatsSnapshot::empty(),
+        },
+    );
+
+    assert_snapshot_eq(
+        actual,
+        b"# TYPE journald_entries_ingested counter
+journald_entries_ingested_created{host=\"a\"} 123.456
+journald_entries_ingested_total{host=\"a\"} 0
+# TYPE journald_fields_ingested counter
+journald_fields_ingested_created{host=\"a\"} 123.456
+journald_fields_ingested_total{host=\"a\"} 0
+# TYPE journald_data_ingested_bytes counter
+# UNIT journald_data_ingested_bytes bytes
+journald_data_ingested_bytes_created{host=\"a\"} 123.456
+journald_data_ingested_bytes_total{host=\"a\"} 0
+# TYPE journald_faults counter
+journald_faults_created{host=\"a\"} 123.456
+journald_faults_total{host=\"a\"} 0
+# TYPE journald_cursor_double_retries counter
+journald_cursor_double_retries_created{host=\"a\"} 123.456
+journald_cursor_double_retries_total{host=\"a\"} 0
+# TYPE journald_unreadable_fields counter
+journald_unreadable_fields_created{host=\"a\"} 123.456
+journald_unreadable_fields_total{host=\"a\"} 0
+# TYPE journald_corrupted_fields counter
+journald_corrupted_fields_created{host=\"a\"} 123.456
+journald_corrupted_fields_total{host=\"a\"} 0
+# TYPE journald_metrics_requests counter
+journald_metrics_requests_created{host=\"a\"} 123.456
+journald_metrics_requests_total{host=\"a\"} 0
+# TYPE journald_messages_ingested counter
+journald_messages_ingested_created{service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\",host=\"a\"} 123.456
+journald_messages_ingested_total{service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\",host=\"a\"} 1
+journald_messages_ingested_created{service=\"bar\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\",host=\"a\"} 123.456
+journald_messages_ingested_total{service=\"bar\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\",host=\"a\"} 1
+journald_messages_ingested_created{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_foo\",host=\"a\"} 123.456
+journald_messages_ingested_total{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_foo\",host=\"a\"} 1
+journald_messages_ingested_created{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_bar\",group=\"group_bar\",host=\"a\"} 123.456
+journald_messages_ingested_total{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_bar\",group=\"group_bar\",host=\"a\"} 1
+# TYPE journald_messages_ingested_bytes counter
+# UNIT journald_messages_ingested_bytes bytes
+journald_messages_ingested_bytes_created{service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\",host=\"a\"} 123.456
+journald_messages_ingested_bytes_total{service=\"?\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\",host=\"a\"} 5
+journald_messages_ingested_bytes_created{service=\"bar\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\",host=\"a\"} 123.456
+journald_messages_ingested_bytes_total{service=\"bar\",priority=\"ERR\",severity=\"3\",user=\"?\",group=\"?\",host=\"a\"} 5
+journald_messages_ingested_bytes_created{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_foo\",host=\"a\"} 123.456
+journald_messages_ingested_bytes_total{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_foo\",group=\"group_foo\",host=\"a\"} 5
+journald_messages_ingested_bytes_created{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_bar\",group=\"group_bar\",host=\"a\"} 123.456
+journald_messages_ingested_bytes_total{service=\"foo\",priority=\"WARNING\",severity=\"4\",user=\"user_bar\",group=\"group_bar\",host=\"a\"} 5
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+journald_service_last_message_timestamp_seconds{service=\"?\",priority=\"ERR\",severity=\"3\",host=\"a\"} 1700000001.000
+journald_service_last_message_timestamp_seconds{service=\"foo\",priority=\"WARNING\",severity=\"4\",host=\"a\"} 1700000000.223
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\",host=\"a\"} 1
+# EOF
+",
+    );
+}
+
+#[test]
+fn renders_field_stats_when_enabled() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.field_stats = true;
+    environment.add_static_label("host", "a");
+
+    let actual = render_with_environment(
+        &environment,
+        PromSnapshot {
+            entries_ingested: 2,
+            fields_ingested: 9,
+            data_ingested_bytes: 150,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 1,
+            corrupted_fields: 1,
+            metrics_requests: 0,
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::build([
+                (
+                    JournalField::SystemdUnit,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 36,
+                        unreadable: 0,
+                        corrupted: 0,
+                    },
+                ),
+                (
+                    JournalField::Priority,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 2,
+                        unreadable: 0,
+                        corrupted: 0,
+                    },
+                ),
+                (
+                    JournalField::Uid,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 6,
+                        unreadable: 0,
+                        corrupted: 0,
+                    },
+                ),
+                (
+                    JournalField::Gid,
+                    FieldStatsEntry {
+                        ingested: 1,
+                        ingested_bytes: 3,
+                        unreadable: 0,
+                        corrupted: 1,
+                    },
+                ),
+                (
+                    JournalField::Message,
+                    FieldStatsEntry {
+                        ingested: 2,
+                        ingested_bytes: 103,
+                        unreadable: 1,
+                        corrupted: 0,
+                    },
+                ),
+            ]),
+        },
+    );
+
+    assert_snapshot_eq(
+        actual,
+        b"# TYPE journald_entries_ingested counter
+journald_entries_ingested_created{host=\"a\"} 123.456
+journald_entries_ingested_total{host=\"a\"} 2
+# TYPE journald_fields_ingested counter
+journald_fields_ingested_created{host=\"a\"} 123.456
+journald_fields_ingested_total{host=\"a\"} 9
+# TYPE journald_data_ingested_bytes counter
+# UNIT journald_data_ingested_bytes bytes
+journald_data_ingested_bytes_created{host=\"a\"} 123.456
+journald_data_ingested_bytes_total{host=\"a\"} 150
+# TYPE journald_faults counter
+journald_faults_created{host=\"a\"} 123.456
+journald_faults_total{host=\"a\"} 0
+# TYPE journald_cursor_double_retries counter
+journald_cursor_double_retries_created{host=\"a\"} 123.456
+journald_cursor_double_retries_total{host=\"a\"} 0
+# TYPE journald_unreadable_fields counter
+journald_unreadable_fields_created{host=\"a\"} 123.456
+journald_unreadable_fields_total{host=\"a\"} 1
+# TYPE journald_corrupted_fields counter
+journald_corrupted_fields_created{host=\"a\"} 123.456
+journald_corrupted_fields_total{host=\"a\"} 1
+# TYPE journald_metrics_requests counter
+journald_metrics_requests_created{host=\"a\"} 123.456
+journald_metrics_requests_total{host=\"a\"} 0
+# TYPE journald_field_ingested counter
+journald_field_ingested_created{field=\"_SYSTEMD_UNIT\",host=\"a\"} 123.456
+journald_field_ingested_total{field=\"_SYSTEMD_UNIT\",host=\"a\"} 2
+journald_field_ingested_created{field=\"PRIORITY\",host=\"a\"} 123.456
+journald_field_ingested_total{field=\"PRIORITY\",host=\"a\"} 2
+journald_field_ingested_created{field=\"_UID\",host=\"a\"} 123.456
+journald_field_ingested_total{field=\"_UID\",host=\"a\"} 2
+journald_field_ingested_created{field=\"_GID\",host=\"a\"} 123.456
+journald_field_ingested_total{field=\"_GID\",host=\"a\"} 1
+journald_field_ingested_created{field=\"MESSAGE\",host=\"a\"} 123.456
+journald_field_ingested_total{field=\"MESSAGE\",host=\"a\"} 2
+journald_field_ingested_created{field=\"_CMDLINE\",host=\"a\"} 123.456
+journald_field_ingested_total{field=\"_CMDLINE\",host=\"a\"} 0
+# TYPE journald_field_ingested_bytes counter
+# UNIT journald_field_ingested_bytes bytes
+journald_field_ingested_bytes_created{field=\"_SYSTEMD_UNIT\",host=\"a\"} 123.456
+journald_field_ingested_bytes_total{field=\"_SYSTEMD_UNIT\",host=\"a\"} 36
+journald_field_ingested_bytes_created{field=\"PRIORITY\",host=\"a\"} 123.456
+journald_field_ingested_bytes_total{field=\"PRIORITY\",host=\"a\"} 2
+journald_field_ingested_bytes_created{field=\"_UID\",host=\"a\"} 123.456
+journald_field_ingested_bytes_total{field=\"_UID\",host=\"a\"} 6
+journald_field_ingested_bytes_created{field=\"_GID\",host=\"a\"} 123.456
+journald_field_ingested_bytes_total{field=\"_GID\",host=\"a\"} 3
+journald_field_ingested_bytes_created{field=\"MESSAGE\",host=\"a\"} 123.456
+journald_field_ingested_bytes_total{field=\"MESSAGE\",host=\"a\"} 103
+journald_field_ingested_bytes_created{field=\"_CMDLINE\",host=\"a\"} 123.456
+journald_field_ingested_bytes_total{field=\"_CMDLINE\",host=\"a\"} 0
+# TYPE journald_field_unreadable counter
+journald_field_unreadable_created{field=\"_SYSTEMD_UNIT\",host=\"a\"} 123.456
+journald_field_unreadable_total{field=\"_SYSTEMD_UNIT\",host=\"a\"} 0
+journald_field_unreadable_created{field=\"PRIORITY\",host=\"a\"} 123.456
+journald_field_unreadable_total{field=\"PRIORITY\",host=\"a\"} 0
+journald_field_unreadable_created{field=\"_UID\",host=\"a\"} 123.456
+journald_field_unreadable_total{field=\"_UID\",host=\"a\"} 0
+journald_field_unreadable_created{field=\"_GID\",host=\"a\"} 123.456
+journald_field_unreadable_total{field=\"_GID\",host=\"a\"} 0
+journald_field_unreadable_created{field=\"MESSAGE\",host=\"a\"} 123.456
+journald_field_unreadable_total{field=\"MESSAGE\",host=\"a\"} 1
+journald_field_unreadable_created{field=\"_CMDLINE\",host=\"a\"} 123.456
+journald_field_unreadable_total{field=\"_CMDLINE\",host=\"a\"} 0
+# TYPE journald_field_corrupted counter
+journald_field_corrupted_created{field=\"_SYSTEMD_UNIT\",host=\"a\"} 123.456
+journald_field_corrupted_total{field=\"_SYSTEMD_UNIT\",host=\"a\"} 0
+journald_field_corrupted_created{field=\"PRIORITY\",host=\"a\"} 123.456
+journald_field_corrupted_total{field=\"PRIORITY\",host=\"a\"} 0
+journald_field_corrupted_created{field=\"_UID\",host=\"a\"} 123.456
+journald_field_corrupted_total{field=\"_UID\",host=\"a\"} 0
+journald_field_corrupted_created{field=\"_GID\",host=\"a\"} 123.456
+journald_field_corrupted_total{field=\"_GID\",host=\"a\"} 1
+journald_field_corrupted_created{field=\"MESSAGE\",host=\"a\"} 123.456
+journald_field_corrupted_total{field=\"MESSAGE\",host=\"a\"} 0
+journald_field_corrupted_created{field=\"_CMDLINE\",host=\"a\"} 123.456
+journald_field_corrupted_total{field=\"_CMDLINE\",host=\"a\"} 0
+# TYPE journald_messages_ingested counter
+journald_messages_ingested_created{host=\"a\"} 123.456
+journald_messages_ingested_total{host=\"a\"} 0
+# TYPE journald_messages_ingested_bytes counter
+# UNIT journald_messages_ingested_bytes bytes
+journald_messages_ingested_bytes_created{host=\"a\"} 123.456
+journald_messages_ingested_bytes_total{host=\"a\"} 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\",host=\"a\"} 1
+# EOF
+",
+    );
+}
+
+fn parent_stats() -> ProcessStats {
+    ProcessStats {
+        cpu_time: Duration::from_millis(4250),
+        start_time: Duration::from_millis(1_700_000_000_500),
+        resident_memory_bytes: 1048576,
+        virtual_memory_bytes: 4194304,
+        open_fds: 12,
+        max_fds: 1024,
+        threads: 3,
+    }
+}
+
+fn child_stats() -> ProcessStats {
+    ProcessStats {
+        cpu_time: Duration::from_millis(10),
+        start_time: Duration::from_millis(1_700_000_001_250),
+        resident_memory_bytes: 2048,
+        virtual_memory_bytes: 8192,
+        open_fds: 7,
+        max_fds: 524288,
+        threads: 2,
+    }
+}
+
+#[test]
+fn renders_process_metrics_for_each_role() {
+    let actual = render_openapi_metrics(
+        &PromEnvironment::new(mock_system_time(123, 456)),
+        &PromSnapshot {
+            entries_ingested: 0,
+            fields_ingested: 0,
+            data_ingested_bytes: 0,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 0,
+            corrupted_fields: 0,
+            metrics_requests: 0,
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
+        },
+        &ProcessSnapshot {
+            parent: Some(parent_stats()),
+            child: Some(child_stats()),
+        },
+        None,
+        &get_user_group_table(),
+    )
+    .unwrap();
+
+    assert_snapshot_eq(
+        actual,
+        b"# TYPE journald_entries_ingested counter
+journald_entries_ingested_created 123.456
+journald_entries_ingested_total 0
+# TYPE journald_fields_ingested counter
+journald_fields_ingested_created 123.456
+journald_fields_ingested_total 0
+# TYPE journald_data_ingested_bytes counter
+# UNIT journald_data_ingested_bytes bytes
+journald_data_ingested_bytes_created 123.456
+journald_data_ingested_bytes_total 0
+# TYPE journald_faults counter
+journald_faults_created 123.456
+journald_faults_total 0
+# TYPE journald_cursor_double_retries counter
+journald_cursor_double_retries_created 123.456
+journald_cursor_double_retries_total 0
+# TYPE journald_unreadable_fields counter
+journald_unreadable_fields_created 123.456
+journald_unreadable_fields_total 0
+# TYPE journald_corrupted_fields counter
+journald_corrupted_fields_created 123.456
+journald_corrupted_fields_total 0
+# TYPE journald_metrics_requests counter
+journald_metrics_requests_created 123.456
+journald_metrics_requests_total 0
+# TYPE journald_messages_ingested counter
+journald_messages_ingested_created 123.456
+journald_messages_ingested_total 0
+# TYPE journald_messages_ingested_bytes counter
+# UNIT journald_messages_ingested_bytes bytes
+journald_messages_ingested_bytes_created 123.456
+journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
+# TYPE process_cpu_seconds counter
+# UNIT process_cpu_seconds seconds
+process_cpu_seconds_created{role=\"parent\"} 1700000000.500
+process_cpu_seconds_total{role=\"parent\"} 4.250
+process_cpu_seconds_created{role=\"child\"} 1700000001.250
+process_cpu_seconds_total{role=\"child\"} 0.010
+# TYPE process_resident_memory_bytes gauge
+# UNIT process_resident_memory_bytes bytes
+process_resident_memory_bytes{role=\"parent\"} 1048576
+process_resident_memory_bytes{role=\"child\"} 2048
+# TYPE process_virtual_memory_bytes gauge
+# UNIT process_virtual_memory_bytes bytes
+process_virtual_memory_bytes{role=\"parent\"} 4194304
+process_virtual_memory_bytes{role=\"child\"} 8192
+# TYPE process_open_fds gauge
+process_open_fds{role=\"parent\"} 12
+process_open_fds{role=\"child\"} 7
+# TYPE process_max_fds gauge
+process_max_fds{role=\"parent\"} 1024
+process_max_fds{role=\"child\"} 524288
+# TYPE process_threads gauge
+process_threads{role=\"parent\"} 3
+process_threads{role=\"child\"} 2
+# TYPE process_start_time_seconds gauge
+# UNIT process_start_time_seconds seconds
+process_start_time_seconds{role=\"parent\"} 1700000000.500
+process_start_time_seconds{role=\"child\"} 1700000001.250
+# EOF
+",
+    );
+}
+
+#[test]
+fn renders_process_metrics_without_child_and_with_static_labels() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("host", "a");
+
+    let actual = render_openapi_metrics(
+        &environment,
+        &PromSnapshot {
+            entries_ingested: 0,
+            fields_ingested: 0,
+            data_ingested_bytes: 0,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 0,
+            corrupted_fields: 0,
+            metrics_requests: 0,
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
+        },
+        &ProcessSnapshot {
+            parent: Some(parent_stats()),
+            child: None,
+        },
+        None,
+        &get_user_group_table(),
+    )
+    .unwrap();
+
+    assert_snapshot_eq(
+        actual,
+        b"# TYPE journald_entries_ingested counter
+journald_entries_ingested_created{host=\"a\"} 123.456
+journald_entries_ingested_total{host=\"a\"} 0
+# TYPE journald_fields_ingested counter
+journald_fields_ingested_created{host=\"a\"} 123.456
+journald_fields_ingested_total{host=\"a\"} 0
+# TYPE journald_data_ingested_bytes counter
+# UNIT journald_data_ingested_bytes bytes
+journald_data_ingested_bytes_created{host=\"a\"} 123.456
+journald_data_ingested_bytes_total{host=\"a\"} 0
+# TYPE journald_faults counter
+journald_faults_created{host=\"a\"} 123.456
+journald_faults_total{host=\"a\"} 0
+# TYPE journald_cursor_double_retries counter
+journald_cursor_double_retries_created{host=\"a\"} 123.456
+journald_cursor_double_retries_total{host=\"a\"} 0
+# TYPE journald_unreadable_fields counter
+journald_unreadable_fields_created{host=\"a\"} 123.456
+journald_unreadable_fields_total{host=\"a\"} 0
+# TYPE journald_corrupted_fields counter
+journald_corrupted_fields_created{host=\"a\"} 123.456
+journald_corrupted_fields_total{host=\"a\"} 0
+# TYPE journald_metrics_requests counter
+journald_metrics_requests_created{host=\"a\"} 123.456
+journald_metrics_requests_total{host=\"a\"} 0
+# TYPE journald_messages_ingested counter
+journald_messages_ingested_created{host=\"a\"} 123.456
+journald_messages_ingested_total{host=\"a\"} 0
+# TYPE journald_messages_ingested_bytes counter
+# UNIT journald_messages_ingested_bytes bytes
+journald_messages_ingested_bytes_created{host=\"a\"} 123.456
+journald_messages_ingested_bytes_total{host=\"a\"} 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\",host=\"a\"} 1
+# TYPE process_cpu_seconds counter
+# UNIT process_cpu_seconds seconds
+process_cpu_seconds_created{role=\"parent\",host=\"a\"} 1700000000.500
+process_cpu_seconds_total{role=\"parent\",host=\"a\"} 4.250
+# TYPE process_resident_memory_bytes gauge
+# UNIT process_resident_memory_bytes bytes
+process_resident_memory_bytes{role=\"parent\",host=\"a\"} 1048576
+# TYPE process_virtual_memory_bytes gauge
+# UNIT process_virtual_memory_bytes bytes
+process_virtual_memory_bytes{role=\"parent\",host=\"a\"} 4194304
+# TYPE process_open_fds gauge
+process_open_fds{role=\"parent\",host=\"a\"} 12
+# TYPE process_max_fds gauge
+process_max_fds{role=\"parent\",host=\"a\"} 1024
+# TYPE process_threads gauge
+process_threads{role=\"parent\",host=\"a\"} 3
+# TYPE process_start_time_seconds gauge
+# UNIT process_start_time_seconds seconds
+process_start_time_seconds{role=\"parent\",host=\"a\"} 1700000000.500
+# EOF
+",
+    );
+}
+
+#[test]
+fn renders_scrape_stats_with_static_labels() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.add_static_label("host", "a");
+
+    let scrape = ScrapeStatsSnapshot::build([
+        (
+            ScrapeHistogram::ScrapeDuration,
+            HistogramSnapshot {
+                buckets: [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2],
+                count: 3,
+                sum: 12_003_000,
+            },
+        ),
+        (
+            ScrapeHistogram::RenderSize,
+            HistogramSnapshot {
+                buckets: [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+                count: 1,
+                sum: 12345,
+            },
+        ),
+    ])
+    .with_responses(200, 5)
+    .with_responses(401, 2)
+    .with_auth_failures(AuthFailureReason::MissingHeader, 1)
+    .with_auth_failures(AuthFailureReason::UnknownKey, 1)
+    .with_throttled_requests(ThrottleReason::Address, 3)
+    .with_throttled_requests(ThrottleReason::Key, 2)
+    .with_throttled_requests(ThrottleReason::Lockout, 4)
+    .with_tls_handshakes(TlsCipherSuite::Tls13Aes128GcmSha256, 2)
+    .with_tls_handshakes(TlsCipherSuite::EcdheRsaAes128GcmSha256, 1)
+    .with_key_requests(b"grafana-agent", 4)
+    .with_key_requests(b"prometheus", 1);
+
+    let actual = render_openapi_metrics(
+        &environment,
+        &PromSnapshot {
+            entries_ingested: 0,
+            fields_ingested: 0,
+            data_ingested_bytes: 0,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 0,
+            corrupted_fields: 0,
+            metrics_requests: 6,
+            messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
+        },
+        &ProcessSnapshot::empty(),
+        Some(&scrape),
+        &get_user_group_table(),
+    )
+    .unwrap();
+
+    assert_snapshot_eq(
+        actual,
+        b"# TYPE journald_entries_ingested counter
+journald_entries_ingested_created{host=\"a\"} 123.456
+journald_entries_ingested_total{host=\"a\"} 0
+# TYPE journald_fields_ingested counter
+journald_fields_ingested_created{host=\"a\"} 123.456
+journald_fields_ingested_total{host=\"a\"} 0
+# TYPE journald_data_ingested_bytes counter
+# UNIT journald_data_ingested_bytes bytes
+journald_data_ingested_bytes_created{host=\"a\"} 123.456
+journald_data_ingested_bytes_total{host=\"a\"} 0
+# TYPE journald_faults counter
+journald_faults_created{host=\"a\"} 123.456
+journald_faults_total{host=\"a\"} 0
+# TYPE journald_cursor_double_retries counter
+journald_cursor_double_retries_created{host=\"a\"} 123.456
+journald_cursor_double_retries_total{host=\"a\"} 0
+# TYPE journald_unreadable_fields counter
+journald_unreadable_fields_created{host=\"a\"} 123.456
+journald_unreadable_fields_total{host=\"a\"} 0
+# TYPE journald_corrupted_fields counter
+journald_corrupted_fields_created{host=\"a\"} 123.456
+journald_corrupted_fields_total{host=\"a\"} 0
+# TYPE journald_metrics_requests counter
+journald_metrics_requests_created{host=\"a\"} 123.456
+journald_metrics_requests_total{host=\"a\"} 6
+journald_metrics_requests_created{key=\"grafana-agent\",host=\"a\"} 123.456
+journald_metrics_requests_total{key=\"grafana-agent\",host=\"a\"} 4
+journald_metrics_requests_created{key=\"prometheus\",host=\"a\"} 123.456
+journald_metrics_requests_total{key=\"prometheus\",host=\"a\"} 1
+# TYPE journald_messages_ingested counter
+journald_messages_ingested_created{host=\"a\"} 123.456
+journald_messages_ingested_total{host=\"a\"} 0
+# TYPE journald_messages_ingested_bytes counter
+# UNIT journald_messages_ingested_bytes bytes
+journald_messages_ingested_bytes_created{host=\"a\"} 123.456
+journald_messages_ingested_bytes_total{host=\"a\"} 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\",host=\"a\"} 1
+# TYPE journald_scrape_duration_seconds histogram
+# UNIT journald_scrape_duration_seconds seconds
+journald_scrape_duration_seconds_bucket{le=\"0.001\",host=\"a\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.0025\",host=\"a\"} 0
+journald_scrape_duration_seconds_bucket{le=\"0.005\",host=\"a\"} 1
+journald_scrape_duration_seconds_bucket{le=\"0.01\",host=\"a\"} 1
+journald_scrape_duration_seconds_bucket{le=\"0.025\",host=\"a\"} 1
+journald_scrape_duration_seconds_bucket{le=\"0.05\",host=\"a\"} 1
+journald_scrape_duration_seconds_bucket{le=\"0.1\",host=\"a\"} 2
+journald_scrape_duration_seconds_bucket{le=\"0.25\",host=\"a\"} 2
+journald_scrape_duration_seconds_bucket{le=\"0.5\",host=\"a\"} 2
+journald_scrape_duration_seconds_bucket{le=\"1\",host=\"a\"} 2
+journald_scrape_duration_seconds_bucket{le=\"2.5\",host=\"a\"} 2
+journald_scrape_duration_seconds_bucket{le=\"5\",host=\"a\"} 2
+journald_scrape_duration_seconds_bucket{le=\"10\",host=\"a\"} 2
+journald_scrape_duration_seconds_bucket{le=\"+Inf\",host=\"a\"} 3
+journald_scrape_duration_seconds_count{host=\"a\"} 3
+journald_scrape_duration_seconds_sum{host=\"a\"} 12.003
+journald_scrape_duration_seconds_created{host=\"a\"} 123.456
+# TYPE journald_scrape_ipc_duration_seconds histogram
+# UNIT journald_scrape_ipc_duration_seconds seconds
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.001\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.0025\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.005\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.01\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.025\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.05\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.1\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.25\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"0.5\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"1\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"2.5\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"5\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"10\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_bucket{le=\"+Inf\",host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_count{host=\"a\"} 0
+journald_scrape_ipc_duration_seconds_sum{host=\"a\"} 0.000
+journald_scrape_ipc_duration_seconds_created{host=\"a\"} 123.456
+# TYPE journald_render_duration_seconds histogram
+# UNIT journald_render_duration_seconds seconds
+journald_render_duration_seconds_bucket{le=\"0.001\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"0.0025\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"0.005\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"0.01\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"0.025\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"0.05\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"0.1\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"0.25\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"0.5\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"1\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"2.5\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"5\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"10\",host=\"a\"} 0
+journald_render_duration_seconds_bucket{le=\"+Inf\",host=\"a\"} 0
+journald_render_duration_seconds_count{host=\"a\"} 0
+journald_render_duration_seconds_sum{host=\"a\"} 0.000
+journald_render_duration_seconds_created{host=\"a\"} 123.456
+# TYPE journald_render_size_bytes histogram
+# UNIT journald_render_size_bytes bytes
+journald_render_size_bytes_bucket{le=\"1024\",host=\"a\"} 0
+journald_render_size_bytes_bucket{le=\"2048\",host=\"a\"} 0
+journald_render_size_bytes_bucket{le=\"4096\",host=\"a\"} 0
+journald_render_size_bytes_bucket{le=\"8192\",host=\"a\"} 0
+journald_render_size_bytes_bucket{le=\"16384\",host=\"a\"} 1
+journald_render_size_bytes_bucket{le=\"32768\",host=\"a\"} 1
+journald_render_size_bytes_bucket{le=\"65536\",host=\"a\"} 1
+journald_render_size_bytes_bucket{le=\"131072\",host=\"a\"} 1
+journald_render_size_bytes_bucket{le=\"262144\",host=\"a\"} 1
+journald_render_size_bytes_bucket{le=\"524288\",host=\"a\"} 1
+journald_render_size_bytes_bucket{le=\"1048576\",host=\"a\"} 1
+journald_render_size_bytes_bucket{le=\"2097152\",host=\"a\"} 1
+journald_render_size_bytes_bucket{le=\"4194304\",host=\"a\"} 1
+journald_render_size_bytes_bucket{le=\"+Inf\",host=\"a\"} 1
+journald_render_size_bytes_count{host=\"a\"} 1
+journald_render_size_bytes_sum{host=\"a\"} 12345
+journald_render_size_bytes_created{host=\"a\"} 123.456
+# TYPE journald_http_responses counter
+journald_http_responses_created{code=\"200\",host=\"a\"} 123.456
+journald_http_responses_total{code=\"200\",host=\"a\"} 5
+journald_http_responses_created{code=\"401\",host=\"a\"} 123.456
+journald_http_responses_total{code=\"401\",host=\"a\"} 2
+journald_http_responses_created{code=\"403\",host=\"a\"} 123.456
+journald_http_responses_total{code=\"403\",host=\"a\"} 0
+journald_http_responses_created{code=\"404\",host=\"a\"} 123.456
+journald_http_responses_total{code=\"404\",host=\"a\"} 0
+journald_http_responses_created{code=\"405\",host=\"a\"} 123.456
+journald_http_responses_total{code=\"405\",host=\"a\"} 0
+journald_http_responses_created{code=\"429\",host=\"a\"} 123.456
+journald_http_responses_total{code=\"429\",host=\"a\"} 0
+journald_http_responses_created{code=\"500\",host=\"a\"} 123.456
+journald_http_responses_total{code=\"500\",host=\"a\"} 0
+journald_http_responses_created{code=\"503\",host=\"a\"} 123.456
+journald_http_responses_total{code=\"503\",host=\"a\"} 0
+# TYPE journald_auth_failures counter
+journald_auth_failures_created{reason=\"missing_header\",host=\"a\"} 123.456
+journald_auth_failures_total{reason=\"missing_header\",host=\"a\"} 1
+journald_auth_failures_created{reason=\"bad_syntax\",host=\"a\"} 123.456
+journald_auth_failures_total{reason=\"bad_syntax\",host=\"a\"} 0
+journald_auth_failures_created{reason=\"wrong_user\",host=\"a\"} 123.456
+journald_auth_failures_total{reason=\"wrong_user\",host=\"a\"} 0
+journald_auth_failures_created{reason=\"unknown_key\",host=\"a\"} 123.456
+journald_auth_failures_total{reason=\"unknown_key\",host=\"a\"} 1
+# TYPE journald_throttled_requests counter
+journald_throttled_requests_created{reason=\"address\",host=\"a\"} 123.456
+journald_throttled_requests_total{reason=\"address\",host=\"a\"} 3
+journald_throttled_requests_created{reason=\"key\",host=\"a\"} 123.456
+journald_throttled_requests_total{reason=\"key\",host=\"a\"} 2
+journald_throttled_requests_created{reason=\"lockout\",host=\"a\"} 123.456
+journald_throttled_requests_total{reason=\"lockout\",host=\"a\"} 4
+# TYPE journald_tls_handshakes counter
+journald_tls_handshakes_created{version=\"1.3\",cipher_suite=\"TLS_AES_128_GCM_SHA256\",host=\"a\"} 123.456
+journald_tls_handshakes_total{version=\"1.3\",cipher_suite=\"TLS_AES_128_GCM_SHA256\",host=\"a\"} 2
+journald_tls_handshakes_created{version=\"1.2\",cipher_suite=\"TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256\",host=\"a\"} 123.456
+journald_tls_handshakes_total{version=\"1.2\",cipher_suite=\"TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256\",host=\"a\"} 1
+# EOF
+",
+    );
+}
+
+#[test]
+fn reserves_every_label_name_rendered() {
+    let mut environment = PromEnvironment::new(mock_system_time(123, 456));
+    environment.field_stats = true;
+
+    let scrape = ScrapeStatsSnapshot::build([])
+        .with_responses(200, 1)
+        .with_auth_failures(AuthFailureReason::UnknownKey, 1)
+        .with_throttled_requests(ThrottleReason::Key, 1)
+        .with_tls_handshakes(TlsCipherSuite::Tls13Aes128GcmSha256, 1)
+        .with_key_requests(b"prometheus", 1);
+
+    let actual = render_openapi_metrics(
+        &environment,
+        &PromSnapshot {
+            entries_ingested: 1,
+            fields_ingested: 1,
+            data_ingested_bytes: 1,
+            faults: 0,
+            cursor_double_retries: 0,
+            unreadable_fields: 0,
+            corrupted_fields: 0,
+            metrics_requests: 1,
+            messages_ingested: ByteCountSnapshot::build([ByteCountSnapshotEntry {
+                key: MessageKey::build(Some(123), Some(123), Some(b"foo"), Priority::Informational),
+                lines: 1,
+                bytes: 5,
+                last_seen: 1,
+            }]),
+            fields: FieldStatsSnapshot::build([(
+                JournalField::Message,
+                FieldStatsEntry {
+                    ingested: 1,
+                    ingested_bytes: 5,
+                    unreadable: 0,
+                    corrupted: 0,
+                },
+            )]),
+        },
+        &ProcessSnapshot {
+            parent: Some(parent_stats()),
+            child: Some(child_stats()),
+        },
+        Some(&scrape),
+        &get_user_group_table(),
+    )
+    .unwrap();
+
+    // Skip the response header, then pull the names out of every `{name="value",...}` label set.
+    let text = std::str::from_utf8(&actual[5..]).unwrap();
+    let mut rendered = std::collections::BTreeSet::new();
+    for line in text.lines().filter(|line| !line.starts_with('#')) {
+        let Some((_, mut rest)) = line.split_once('{') else {
+            continue;
+        };
+        while let Some((name, value)) = rest.split_once("=\"") {
+            rendered.insert(name.trim_start_matches(','));
+            let mut chars = value.char_indices();
+            rest = loop {
+                match chars.next() {
+                    Some((_, '\\')) => drop(chars.next()),
+                    Some((i, '"')) => break value.split_at(i + 1).1,
+                    Some(_) => {}
+                    None => panic!("Unterminated label value in {line:?}"),
+                }
+            };
+        }
+    }
+
+    for name in &rendered {
+        assert!(
+            RESERVED_LABEL_NAMES.contains(name),
+            "{name} is not reserved"
+        );
+    }
+    for name in RESERVED_LABEL_NAMES {
+        assert!(rendered.contains(name), "{name} is never rendered");
+    }
+}
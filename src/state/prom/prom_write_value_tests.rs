@@ -6,6 +6,8 @@ fn render_with_created(snapshot: &PromSnapshot, seconds: u64, millis: u32) -> Ve
     render_openapi_metrics(
         &PromEnvironment::new(mock_system_time(seconds, millis)),
         snapshot,
+        &ProcessSnapshot::empty(),
+        None,
         &get_user_group_table(),
     )
     .unwrap()
@@ -97,6 +99,10 @@ journald_messages_ingested_total 0
 # UNIT journald_messages_ingested_bytes bytes
 journald_messages_ingested_bytes_created 123.456
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 ",
     ]);
@@ -112,6 +118,7 @@ journald_messages_ingested_bytes_total 0
             corrupted_fields: 0,
             metrics_requests: 0,
             messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         },
         123,
         456,
@@ -285,6 +292,10 @@ journald_messages_ingested_total 0
 journald_messages_ingested_bytes_created ",
         b"
 journald_messages_ingested_bytes_total 0
+# TYPE journald_service_last_message_timestamp_seconds gauge
+# UNIT journald_service_last_message_timestamp_seconds seconds
+# TYPE journald_exporter_build_info gauge
+journald_exporter_build_info{version=\"test\"} 1
 # EOF
 ",
     ]);
@@ -300,6 +311,7 @@ journald_messages_ingested_bytes_total 0
             corrupted_fields: 0,
             metrics_requests: 0,
             messages_ingested: ByteCountSnapshot::empty(),
+            fields: FieldStatsSnapshot::empty(),
         },
         secs,
         millis,
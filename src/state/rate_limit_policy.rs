@@ -0,0 +1,333 @@
+//! How authorized metrics requests are rate limited. Each limit is a token bucket, refilling at
+//! `count` requests per `seconds` seconds and holding up to `burst` of them at once. Address
+//! prefixes that keep failing authorization are also locked out entirely for a while.
+
+use crate::prelude::*;
+
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::num::NonZeroU32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub count: NonZeroU32,
+    pub seconds: NonZeroU32,
+    pub burst: NonZeroU32,
+}
+
+impl RateLimit {
+    /// One request per second, with no room for bursts.
+    pub const ONE_PER_SECOND: RateLimit = RateLimit {
+        count: NonZeroU32::MIN,
+        seconds: NonZeroU32::MIN,
+        burst: NonZeroU32::MIN,
+    };
+
+    /// Parses a `COUNT/SECONDS` rate.
+    pub fn parse_rate(rate: &[u8]) -> Option<(NonZeroU32, NonZeroU32)> {
+        let split = rate.iter().position(|&b| b == b'/')?;
+        let count = NonZeroU32::new(parse_u32(&rate[..split])?)?;
+        let seconds = NonZeroU32::new(parse_u32(&rate[split.wrapping_add(1)..])?)?;
+        Some((count, seconds))
+    }
+
+    /// The inverse of `parse_rate`.
+    pub fn rate(&self) -> String {
+        format!("{}/{}", self.count, self.seconds)
+    }
+
+    /// How long it takes to refill a single request.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.seconds.get().into())
+            .checked_div(self.count.get())
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// How far ahead of the refill rate requests are allowed to get.
+    pub fn tolerance(&self) -> Duration {
+        self.interval()
+            .saturating_mul(self.burst.get().wrapping_sub(1))
+    }
+}
+
+/// All durations are in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthLockout {
+    // How many failures within `window` seconds it takes to lock a prefix out.
+    pub failures: NonZeroU32,
+    pub window: NonZeroU32,
+    // The first lockout lasts this long, and each one after doubles, up to `max_duration`.
+    pub duration: NonZeroU32,
+    pub max_duration: NonZeroU32,
+}
+
+impl AuthLockout {
+    pub const DEFAULT: AuthLockout = AuthLockout {
+        failures: NonZeroU32::new(10).unwrap(),
+        window: NonZeroU32::new(60).unwrap(),
+        duration: NonZeroU32::new(60).unwrap(),
+        max_duration: NonZeroU32::new(3600).unwrap(),
+    };
+
+    /// How long a prefix is locked out for, given how many times it's been locked out before.
+    pub fn lockout_duration(&self, previous_lockouts: u32) -> Duration {
+        let factor = 1_u32.checked_shl(previous_lockouts).unwrap_or(u32::MAX);
+        let duration = self.duration.get().saturating_mul(factor);
+        Duration::from_secs(duration.min(self.max_duration.get()).into())
+    }
+
+    /// How long a prefix has to go without failing before its past lockouts are forgotten.
+    pub fn forget_after(&self) -> Duration {
+        Duration::from_secs(self.window.max(self.max_duration).get().into())
+    }
+}
+
+/// A range of addresses, with IPv4 addresses mapped into IPv6 like peer addresses are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpPrefix {
+    addr: Ipv6Addr,
+    len: u8,
+}
+
+const IPV4_MAPPED_PREFIX_LEN: u8 = 96;
+
+/// Clears all but the first `len` bits of `addr`.
+pub fn mask_ipv6(addr: Ipv6Addr, len: u8) -> Ipv6Addr {
+    let mask = u128::MAX
+        .checked_shl(128_u32.saturating_sub(len.into()))
+        .unwrap_or(0);
+    Ipv6Addr::from(u128::from(addr) & mask)
+}
+
+impl IpPrefix {
+    /// Parses either a bare address or a CIDR range, like `10.0.0.0/8` or `2001:db8::/32`. Any
+    /// bits past the prefix length are ignored.
+    pub fn parse(prefix: &[u8]) -> Option<Self> {
+        let prefix = std::str::from_utf8(prefix).ok()?;
+        let (addr, len) = match prefix.split_once('/') {
+            None => (prefix, None),
+            Some((addr, len)) => (addr, Some(parse_u32(len.as_bytes())?)),
+        };
+
+        let (addr, len) = if let Ok(addr) = addr.parse::<Ipv4Addr>() {
+            let len = u8::try_from(len.unwrap_or(32))
+                .ok()
+                .filter(|&len| len <= 32)?;
+            (
+                addr.to_ipv6_mapped(),
+                len.wrapping_add(IPV4_MAPPED_PREFIX_LEN),
+            )
+        } else {
+            let addr = addr.parse::<Ipv6Addr>().ok()?;
+            let len = u8::try_from(len.unwrap_or(128))
+                .ok()
+                .filter(|&len| len <= 128)?;
+            (addr, len)
+        };
+
+        Some(Self {
+            addr: mask_ipv6(addr, len),
+            len,
+        })
+    }
+
+    pub fn contains(&self, addr: Ipv6Addr) -> bool {
+        mask_ipv6(addr, self.len) == self.addr
+    }
+}
+
+impl fmt::Display for IpPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.addr.to_ipv4_mapped(), self.len) {
+            (Some(addr), len @ IPV4_MAPPED_PREFIX_LEN..) => {
+                write!(f, "{addr}/{}", len.wrapping_sub(IPV4_MAPPED_PREFIX_LEN))
+            }
+            (_, len) => write!(f, "{}/{len}", self.addr),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    // Shared by every address in the same prefix, so clients can't dodge it by switching between
+    // the addresses they own.
+    pub address: RateLimit,
+    pub ipv4_prefix_len: u8,
+    pub ipv6_prefix_len: u8,
+    // Shared by every address using the same named key, if set.
+    pub key: Option<RateLimit>,
+    // Addresses in these are never rate limited.
+    pub exempt: Vec<IpPrefix>,
+    // Applies to exempt addresses too, as it's about guessing keys rather than load.
+    pub lockout: AuthLockout,
+}
+
+impl RateLimitPolicy {
+    pub const fn new() -> Self {
+        Self {
+            address: RateLimit::ONE_PER_SECOND,
+            ipv4_prefix_len: 32,
+            ipv6_prefix_len: 64,
+            key: None,
+            exempt: Vec::new(),
+            lockout: AuthLockout::DEFAULT,
+        }
+    }
+
+    /// Parses a comma-separated list of prefixes.
+    pub fn parse_exempt(list: &[u8]) -> Option<Vec<IpPrefix>> {
+        list.split(|&b| b == b',').map(IpPrefix::parse).collect()
+    }
+
+    /// The inverse of `parse_exempt`.
+    pub fn exempt_list(&self) -> String {
+        let mut result = String::new();
+        for prefix in &self.exempt {
+            if !result.is_empty() {
+                result.push(',');
+            }
+            write!(result, "{prefix}").unwrap();
+        }
+        result
+    }
+
+    pub fn is_exempt(&self, addr: Ipv6Addr) -> bool {
+        self.exempt.iter().any(|prefix| prefix.contains(addr))
+    }
+
+    /// The prefix `addr` is rate limited by.
+    pub fn address_prefix(&self, addr: Ipv6Addr) -> IpPrefix {
+        let len = match addr.to_ipv4_mapped() {
+            Some(_) => self.ipv4_prefix_len.wrapping_add(IPV4_MAPPED_PREFIX_LEN),
+            None => self.ipv6_prefix_len,
+        };
+        IpPrefix {
+            addr: mask_ipv6(addr, len),
+            len,
+        }
+    }
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip_addr(s: &str) -> Ipv6Addr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_rates() {
+        let rate = |count, seconds| {
+            Some((
+                NonZeroU32::new(count).unwrap(),
+                NonZeroU32::new(seconds).unwrap(),
+            ))
+        };
+        assert_eq!(RateLimit::parse_rate(b"1/1"), rate(1, 1));
+        assert_eq!(RateLimit::parse_rate(b"30/60"), rate(30, 60));
+        assert_eq!(RateLimit::parse_rate(b"1"), None);
+        assert_eq!(RateLimit::parse_rate(b"0/1"), None);
+        assert_eq!(RateLimit::parse_rate(b"1/0"), None);
+        assert_eq!(RateLimit::parse_rate(b"1/1/1"), None);
+        assert_eq!(RateLimit::parse_rate(b"/1"), None);
+    }
+
+    #[test]
+    fn computes_interval_and_tolerance() {
+        let limit = RateLimit {
+            count: NonZeroU32::new(4).unwrap(),
+            seconds: NonZeroU32::new(2).unwrap(),
+            burst: NonZeroU32::new(3).unwrap(),
+        };
+        assert_eq!(limit.interval(), Duration::from_millis(500));
+        assert_eq!(limit.tolerance(), Duration::from_secs(1));
+        assert_eq!(RateLimit::ONE_PER_SECOND.tolerance(), Duration::ZERO);
+    }
+
+    #[test]
+    fn doubles_lockout_duration_up_to_max() {
+        let lockout = AuthLockout::DEFAULT;
+        assert_eq!(lockout.lockout_duration(0), Duration::from_secs(60));
+        assert_eq!(lockout.lockout_duration(1), Duration::from_secs(120));
+        assert_eq!(lockout.lockout_duration(5), Duration::from_secs(1920));
+        assert_eq!(lockout.lockout_duration(6), Duration::from_secs(3600));
+        assert_eq!(lockout.lockout_duration(40), Duration::from_secs(3600));
+        assert_eq!(lockout.forget_after(), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn parses_and_displays_prefixes() {
+        let round_trip = |s: &str| IpPrefix::parse(s.as_bytes()).map(|p| p.to_string());
+        assert_eq!(round_trip("10.1.2.3/8"), Some("10.0.0.0/8".into()));
+        assert_eq!(round_trip("10.1.2.3"), Some("10.1.2.3/32".into()));
+        assert_eq!(round_trip("0.0.0.0/0"), Some("0.0.0.0/0".into()));
+        assert_eq!(round_trip("2001:db8::1/32"), Some("2001:db8::/32".into()));
+        assert_eq!(round_trip("2001:db8::1"), Some("2001:db8::1/128".into()));
+        assert_eq!(round_trip("::/0"), Some("::/0".into()));
+        assert_eq!(round_trip("10.0.0.0/33"), None);
+        assert_eq!(round_trip("2001:db8::/129"), None);
+        assert_eq!(round_trip("10.0.0.0/"), None);
+        assert_eq!(round_trip("example.com/8"), None);
+        assert_eq!(round_trip(""), None);
+    }
+
+    #[test]
+    fn matches_addresses_in_prefix() {
+        let prefix = IpPrefix::parse(b"192.168.0.0/16").unwrap();
+        assert!(prefix.contains(ip_addr("::ffff:192.168.10.20")));
+        assert!(!prefix.contains(ip_addr("::ffff:192.169.0.1")));
+        assert!(!prefix.contains(ip_addr("2001:db8::1")));
+
+        let prefix = IpPrefix::parse(b"::/0").unwrap();
+        assert!(prefix.contains(ip_addr("::ffff:192.169.0.1")));
+        assert!(prefix.contains(ip_addr("2001:db8::1")));
+    }
+
+    #[test]
+    fn exempt_list_round_trips() {
+        let policy = RateLimitPolicy {
+            exempt: RateLimitPolicy::parse_exempt(b"10.0.0.0/8,2001:db8::/32").unwrap(),
+            ..RateLimitPolicy::new()
+        };
+        assert_eq!(policy.exempt_list(), "10.0.0.0/8,2001:db8::/32");
+        assert!(policy.is_exempt(ip_addr("::ffff:10.1.2.3")));
+        assert!(!policy.is_exempt(ip_addr("::ffff:11.1.2.3")));
+        assert_eq!(RateLimitPolicy::parse_exempt(b"10.0.0.0/8,"), None);
+    }
+
+    #[test]
+    fn groups_addresses_by_prefix() {
+        let prefix = |s: &str| IpPrefix::parse(s.as_bytes()).unwrap();
+
+        let policy = RateLimitPolicy::new();
+        assert_eq!(
+            policy.address_prefix(ip_addr("2001:db8:1:2:3:4:5:6")),
+            prefix("2001:db8:1:2::/64")
+        );
+        assert_eq!(
+            policy.address_prefix(ip_addr("::ffff:10.1.2.3")),
+            prefix("10.1.2.3/32")
+        );
+
+        let policy = RateLimitPolicy {
+            ipv4_prefix_len: 24,
+            ipv6_prefix_len: 48,
+            ..RateLimitPolicy::new()
+        };
+        assert_eq!(
+            policy.address_prefix(ip_addr("2001:db8:1:2:3:4:5:6")),
+            prefix("2001:db8:1::/48")
+        );
+        assert_eq!(
+            policy.address_prefix(ip_addr("::ffff:10.1.2.3")),
+            prefix("10.1.2.0/24")
+        );
+    }
+}
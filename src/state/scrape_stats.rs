@@ -0,0 +1,581 @@
+use crate::prelude::*;
+
+/// The histograms tracked for serving scrapes. Each has fixed buckets in its own base unit, which
+/// is microseconds for durations and bytes for sizes. The order here is also the order they're
+/// rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrapeHistogram {
+    // Measured by the child, from receiving the request to sending the response.
+    ScrapeDuration,
+    // Measured by the child, from asking the parent for metrics to receiving them.
+    IpcDuration,
+    // Measured by the parent, the time it took to render the metrics.
+    RenderDuration,
+    // Measured by the parent, the size of the rendered metrics before any compression.
+    RenderSize,
+}
+
+const HISTOGRAM_COUNT: usize = 4;
+pub const HISTOGRAM_BUCKET_COUNT: usize = 13;
+
+// 1ms to 10s, in microseconds.
+const DURATION_BOUNDS: [u64; HISTOGRAM_BUCKET_COUNT] = [
+    1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000, 1_000_000, 2_500_000,
+    5_000_000, 10_000_000,
+];
+
+const DURATION_BOUND_LABELS: [&str; HISTOGRAM_BUCKET_COUNT] = [
+    "0.001", "0.0025", "0.005", "0.01", "0.025", "0.05", "0.1", "0.25", "0.5", "1", "2.5", "5",
+    "10",
+];
+
+// 1 KiB to 4 MiB, doubling each time.
+const SIZE_BOUNDS: [u64; HISTOGRAM_BUCKET_COUNT] = [
+    1 << 10,
+    1 << 11,
+    1 << 12,
+    1 << 13,
+    1 << 14,
+    1 << 15,
+    1 << 16,
+    1 << 17,
+    1 << 18,
+    1 << 19,
+    1 << 20,
+    1 << 21,
+    1 << 22,
+];
+
+const SIZE_BOUND_LABELS: [&str; HISTOGRAM_BUCKET_COUNT] = [
+    "1024", "2048", "4096", "8192", "16384", "32768", "65536", "131072", "262144", "524288",
+    "1048576", "2097152", "4194304",
+];
+
+/// The status codes the server responds with. The order here is also the order they're rendered in.
+pub const HTTP_RESPONSE_CODES: [u16; 8] = [200, 401, 403, 404, 405, 429, 500, 503];
+
+const HTTP_RESPONSE_CODE_LABELS: [&str; HTTP_RESPONSE_CODES.len()] =
+    ["200", "401", "403", "404", "405", "429", "500", "503"];
+
+pub fn http_response_code_index(code: u16) -> Option<usize> {
+    HTTP_RESPONSE_CODES.iter().position(|&c| c == code)
+}
+
+/// Why a metrics request failed authorization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthFailureReason {
+    // No `Authorization` header at all.
+    MissingHeader,
+    // Not valid Basic or Bearer authentication.
+    BadSyntax,
+    // Valid syntax, but a username other than the key's configured user (default `metrics`).
+    WrongUser,
+    // The right username, but the password doesn't match any key. This includes there being no
+    // keys loaded yet.
+    UnknownKey,
+}
+
+impl AuthFailureReason {
+    pub const ALL: [AuthFailureReason; 4] = [
+        AuthFailureReason::MissingHeader,
+        AuthFailureReason::BadSyntax,
+        AuthFailureReason::WrongUser,
+        AuthFailureReason::UnknownKey,
+    ];
+
+    pub fn index(self) -> usize {
+        match self {
+            AuthFailureReason::MissingHeader => 0,
+            AuthFailureReason::BadSyntax => 1,
+            AuthFailureReason::WrongUser => 2,
+            AuthFailureReason::UnknownKey => 3,
+        }
+    }
+
+    pub fn as_label(self) -> &'static str {
+        match self {
+            AuthFailureReason::MissingHeader => "missing_header",
+            AuthFailureReason::BadSyntax => "bad_syntax",
+            AuthFailureReason::WrongUser => "wrong_user",
+            AuthFailureReason::UnknownKey => "unknown_key",
+        }
+    }
+}
+
+/// Which rate limit an authorized metrics request was throttled by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleReason {
+    // The limit shared by the request's address prefix.
+    Address,
+    // The limit shared by the request's named key.
+    Key,
+    // The request's address prefix is locked out after failing authorization too often. Unlike
+    // the others, these requests aren't authorized at all.
+    Lockout,
+}
+
+impl ThrottleReason {
+    pub const ALL: [ThrottleReason; 3] = [
+        ThrottleReason::Address,
+        ThrottleReason::Key,
+        ThrottleReason::Lockout,
+    ];
+
+    pub fn index(self) -> usize {
+        match self {
+            ThrottleReason::Address => 0,
+            ThrottleReason::Key => 1,
+            ThrottleReason::Lockout => 2,
+        }
+    }
+
+    pub fn as_label(self) -> &'static str {
+        match self {
+            ThrottleReason::Address => "address",
+            ThrottleReason::Key => "key",
+            ThrottleReason::Lockout => "lockout",
+        }
+    }
+}
+
+impl ScrapeHistogram {
+    pub const ALL: [ScrapeHistogram; HISTOGRAM_COUNT] = [
+        ScrapeHistogram::ScrapeDuration,
+        ScrapeHistogram::IpcDuration,
+        ScrapeHistogram::RenderDuration,
+        ScrapeHistogram::RenderSize,
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            ScrapeHistogram::ScrapeDuration => 0,
+            ScrapeHistogram::IpcDuration => 1,
+            ScrapeHistogram::RenderDuration => 2,
+            ScrapeHistogram::RenderSize => 3,
+        }
+    }
+
+    pub fn is_duration(self) -> bool {
+        !matches!(self, ScrapeHistogram::RenderSize)
+    }
+
+    /// The inclusive upper bound of each bucket, in the histogram's base unit.
+    pub fn bounds(self) -> &'static [u64; HISTOGRAM_BUCKET_COUNT] {
+        if self.is_duration() {
+            &DURATION_BOUNDS
+        } else {
+            &SIZE_BOUNDS
+        }
+    }
+
+    /// The `le` label of each bucket, with durations rendered in seconds.
+    pub fn bound_labels(self) -> &'static [&'static str; HISTOGRAM_BUCKET_COUNT] {
+        if self.is_duration() {
+            &DURATION_BOUND_LABELS
+        } else {
+            &SIZE_BOUND_LABELS
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    // Cumulative, one per bound. The implicit `+Inf` bucket is just `count`.
+    pub buckets: [u64; HISTOGRAM_BUCKET_COUNT],
+    pub count: u64,
+    pub sum: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScrapeStatsSnapshot {
+    entries: [HistogramSnapshot; HISTOGRAM_COUNT],
+    responses: [u64; HTTP_RESPONSE_CODES.len()],
+    auth_failures: [u64; AuthFailureReason::ALL.len()],
+    throttled_requests: [u64; ThrottleReason::ALL.len()],
+    tls_handshakes: [u64; TlsCipherSuite::ALL.len()],
+    // Sorted by name.
+    key_requests: Vec<(Box<[u8]>, u64)>,
+}
+
+impl ScrapeStatsSnapshot {
+    #[cfg(test)]
+    pub fn empty() -> Self {
+        Self {
+            entries: [HistogramSnapshot::default(); HISTOGRAM_COUNT],
+            responses: [0; HTTP_RESPONSE_CODES.len()],
+            auth_failures: [0; AuthFailureReason::ALL.len()],
+            throttled_requests: [0; ThrottleReason::ALL.len()],
+            tls_handshakes: [0; TlsCipherSuite::ALL.len()],
+            key_requests: Vec::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn build(data: impl IntoIterator<Item = (ScrapeHistogram, HistogramSnapshot)>) -> Self {
+        let mut result = Self::empty();
+        for (histogram, entry) in data {
+            result.entries[histogram.index()] = entry;
+        }
+        result
+    }
+
+    #[cfg(test)]
+    pub fn with_responses(mut self, code: u16, count: u64) -> Self {
+        self.responses[http_response_code_index(code).expect("unknown status code")] = count;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_auth_failures(mut self, reason: AuthFailureReason, count: u64) -> Self {
+        self.auth_failures[reason.index()] = count;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_throttled_requests(mut self, reason: ThrottleReason, count: u64) -> Self {
+        self.throttled_requests[reason.index()] = count;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_tls_handshakes(mut self, suite: TlsCipherSuite, count: u64) -> Self {
+        self.tls_handshakes[suite.index()] = count;
+        self
+    }
+
+    // Must be called in order of name.
+    #[cfg(test)]
+    pub fn with_key_requests(mut self, name: &[u8], count: u64) -> Self {
+        self.key_requests.push((name.into(), count));
+        self
+    }
+
+    pub fn get(&self, histogram: ScrapeHistogram) -> &HistogramSnapshot {
+        &self.entries[histogram.index()]
+    }
+
+    /// The number of responses sent for each of `HTTP_RESPONSE_CODES`, in the same order, along
+    /// with their `code` label.
+    pub fn responses(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        HTTP_RESPONSE_CODE_LABELS.into_iter().zip(self.responses)
+    }
+
+    pub fn auth_failures(&self) -> impl Iterator<Item = (AuthFailureReason, u64)> + '_ {
+        AuthFailureReason::ALL.into_iter().zip(self.auth_failures)
+    }
+
+    pub fn throttled_requests(&self) -> impl Iterator<Item = (ThrottleReason, u64)> + '_ {
+        ThrottleReason::ALL.into_iter().zip(self.throttled_requests)
+    }
+
+    /// The number of completed TLS handshakes by negotiated cipher suite. The suite implies the
+    /// version.
+    pub fn tls_handshakes(&self) -> impl Iterator<Item = (TlsCipherSuite, u64)> + '_ {
+        TlsCipherSuite::ALL.into_iter().zip(self.tls_handshakes)
+    }
+
+    /// The number of requests authorized by each named key, along with its `key` label.
+    pub fn key_requests(&self) -> impl Iterator<Item = (&[u8], u64)> + '_ {
+        self.key_requests
+            .iter()
+            .map(|(name, count)| (&**name, *count))
+    }
+
+    pub fn clear_key_requests(&mut self) {
+        self.key_requests.clear();
+    }
+}
+
+struct Histogram {
+    // Not cumulative, so each observation only touches one bucket. The last one is for everything
+    // past the largest bound.
+    buckets: [Counter; HISTOGRAM_BUCKET_COUNT.wrapping_add(1)],
+    sum: Counter,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [const { Counter::new(0) }; HISTOGRAM_BUCKET_COUNT.wrapping_add(1)],
+            sum: Counter::new(0),
+        }
+    }
+
+    fn observe(&self, bounds: &[u64; HISTOGRAM_BUCKET_COUNT], value: u64) {
+        let index = bounds
+            .iter()
+            .position(|&bound| value <= bound)
+            .unwrap_or(HISTOGRAM_BUCKET_COUNT);
+
+        self.buckets[index].increment();
+        self.sum.increment_by(value);
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        // The count is derived from the buckets rather than tracked separately, so the `+Inf`
+        // bucket can never be seen as less than the others even with concurrent observations.
+        let mut result = HistogramSnapshot::default();
+        let mut total = 0_u64;
+
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            total = total.wrapping_add(bucket.current());
+            if let Some(target) = result.buckets.get_mut(i) {
+                *target = total;
+            }
+        }
+
+        result.count = total;
+        result.sum = self.sum.current();
+        result
+    }
+}
+
+/// Everything tracked about serving the metrics endpoint itself.
+pub struct ScrapeStats {
+    histograms: [Histogram; HISTOGRAM_COUNT],
+    responses: [Counter; HTTP_RESPONSE_CODES.len()],
+    auth_failures: [Counter; AuthFailureReason::ALL.len()],
+    throttled_requests: [Counter; ThrottleReason::ALL.len()],
+    tls_handshakes: [Counter; TlsCipherSuite::ALL.len()],
+    // Sorted by name, so it can be searched and rendered in a stable order.
+    key_requests: Mutex<Vec<(Box<[u8]>, u64)>>,
+}
+
+// Names come from the child, so this caps how many series it could possibly create.
+const MAX_KEY_REQUEST_SERIES: usize = MAX_KEY_SET_LEN;
+
+impl ScrapeStats {
+    pub const fn new() -> Self {
+        Self {
+            histograms: [
+                Histogram::new(),
+                Histogram::new(),
+                Histogram::new(),
+                Histogram::new(),
+            ],
+            responses: [const { Counter::new(0) }; HTTP_RESPONSE_CODES.len()],
+            auth_failures: [const { Counter::new(0) }; AuthFailureReason::ALL.len()],
+            throttled_requests: [const { Counter::new(0) }; ThrottleReason::ALL.len()],
+            tls_handshakes: [const { Counter::new(0) }; TlsCipherSuite::ALL.len()],
+            key_requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn observe(&self, histogram: ScrapeHistogram, value: u64) {
+        self.histograms[histogram.index()].observe(histogram.bounds(), value);
+    }
+
+    pub fn observe_duration(&self, histogram: ScrapeHistogram, duration: Duration) {
+        self.observe(
+            histogram,
+            u64::try_from(duration.as_micros()).unwrap_or(u64::MAX),
+        );
+    }
+
+    // Unknown status codes are just dropped. The child only ever responds with known ones.
+    pub fn add_responses(&self, code: u16, count: u64) {
+        if let Some(index) = http_response_code_index(code) {
+            self.responses[index].increment_by(count);
+        }
+    }
+
+    pub fn add_auth_failures(&self, reason: AuthFailureReason, count: u64) {
+        self.auth_failures[reason.index()].increment_by(count);
+    }
+
+    pub fn add_throttled_requests(&self, reason: ThrottleReason, count: u64) {
+        self.throttled_requests[reason.index()].increment_by(count);
+    }
+
+    pub fn add_tls_handshakes(&self, suite: TlsCipherSuite, count: u64) {
+        self.tls_handshakes[suite.index()].increment_by(count);
+    }
+
+    // Names that aren't valid key names are just dropped. The child only ever sends valid ones.
+    pub fn add_key_requests(&self, name: &[u8], count: u64) {
+        if !is_valid_key_name(name) {
+            return;
+        }
+
+        let mut key_requests = self.key_requests.lock().unwrap_or_else(|e| e.into_inner());
+
+        match key_requests.binary_search_by(|(existing, _)| (**existing).cmp(name)) {
+            Ok(index) => {
+                let current = &mut key_requests[index].1;
+                *current = current.wrapping_add(count);
+            }
+            Err(index) => {
+                if key_requests.len() < MAX_KEY_REQUEST_SERIES {
+                    key_requests.insert(index, (name.into(), count));
+                }
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> ScrapeStatsSnapshot {
+        ScrapeStatsSnapshot {
+            entries: ScrapeHistogram::ALL.map(|h| self.histograms[h.index()].snapshot()),
+            responses: self.responses.each_ref().map(|c| c.current()),
+            auth_failures: self.auth_failures.each_ref().map(|c| c.current()),
+            throttled_requests: self.throttled_requests.each_ref().map(|c| c.current()),
+            tls_handshakes: self.tls_handshakes.each_ref().map(|c| c.current()),
+            key_requests: self
+                .key_requests
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_indices_match_all_order() {
+        for (i, histogram) in ScrapeHistogram::ALL.iter().enumerate() {
+            assert_eq!(histogram.index(), i);
+        }
+    }
+
+    #[test]
+    fn bound_labels_match_bounds() {
+        for histogram in ScrapeHistogram::ALL {
+            for (bound, label) in histogram.bounds().iter().zip(histogram.bound_labels()) {
+                let expected = if histogram.is_duration() {
+                    Duration::from_micros(*bound).as_secs_f64().to_string()
+                } else {
+                    bound.to_string()
+                };
+                assert_eq!(*label, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn response_code_labels_match_codes() {
+        for (code, label) in HTTP_RESPONSE_CODES.iter().zip(HTTP_RESPONSE_CODE_LABELS) {
+            assert_eq!(label, code.to_string());
+        }
+    }
+
+    #[test]
+    fn auth_failure_indices_match_all_order() {
+        for (i, reason) in AuthFailureReason::ALL.iter().enumerate() {
+            assert_eq!(reason.index(), i);
+        }
+    }
+
+    #[test]
+    fn throttle_reason_indices_match_all_order() {
+        for (i, reason) in ThrottleReason::ALL.iter().enumerate() {
+            assert_eq!(reason.index(), i);
+        }
+    }
+
+    #[test]
+    fn starts_empty() {
+        let stats = ScrapeStats::new();
+        assert_eq!(stats.snapshot(), ScrapeStatsSnapshot::empty());
+    }
+
+    #[test]
+    fn tracks_cumulative_buckets_per_histogram() {
+        let stats = ScrapeStats::new();
+        stats.observe_duration(
+            ScrapeHistogram::ScrapeDuration,
+            Duration::from_micros(1_000),
+        );
+        stats.observe_duration(
+            ScrapeHistogram::ScrapeDuration,
+            Duration::from_micros(1_001),
+        );
+        stats.observe_duration(ScrapeHistogram::ScrapeDuration, Duration::from_millis(30));
+        stats.observe_duration(ScrapeHistogram::ScrapeDuration, Duration::from_secs(11));
+        stats.observe(ScrapeHistogram::RenderSize, 0);
+        stats.observe(ScrapeHistogram::RenderSize, 5000);
+
+        assert_eq!(
+            stats.snapshot(),
+            ScrapeStatsSnapshot::build([
+                (
+                    ScrapeHistogram::ScrapeDuration,
+                    HistogramSnapshot {
+                        buckets: [1, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 3],
+                        count: 4,
+                        sum: 11_032_001,
+                    },
+                ),
+                (
+                    ScrapeHistogram::RenderSize,
+                    HistogramSnapshot {
+                        buckets: [1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+                        count: 2,
+                        sum: 5000,
+                    },
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn tracks_response_and_auth_counters() {
+        let stats = ScrapeStats::new();
+        stats.add_responses(200, 3);
+        stats.add_responses(403, 1);
+        stats.add_responses(403, 2);
+        stats.add_responses(418, 5);
+        stats.add_auth_failures(AuthFailureReason::WrongUser, 2);
+        stats.add_auth_failures(AuthFailureReason::UnknownKey, 1);
+        stats.add_throttled_requests(ThrottleReason::Address, 4);
+        stats.add_throttled_requests(ThrottleReason::Key, 1);
+        stats.add_throttled_requests(ThrottleReason::Lockout, 3);
+        stats.add_tls_handshakes(TlsCipherSuite::Tls13Aes128GcmSha256, 5);
+        stats.add_tls_handshakes(TlsCipherSuite::Tls13Aes128GcmSha256, 1);
+
+        assert_eq!(
+            stats.snapshot(),
+            ScrapeStatsSnapshot::empty()
+                .with_responses(200, 3)
+                .with_responses(403, 3)
+                .with_auth_failures(AuthFailureReason::WrongUser, 2)
+                .with_auth_failures(AuthFailureReason::UnknownKey, 1)
+                .with_throttled_requests(ThrottleReason::Address, 4)
+                .with_throttled_requests(ThrottleReason::Key, 1)
+                .with_throttled_requests(ThrottleReason::Lockout, 3)
+                .with_tls_handshakes(TlsCipherSuite::Tls13Aes128GcmSha256, 6)
+        );
+    }
+
+    #[test]
+    fn tracks_key_requests_sorted_by_name() {
+        let stats = ScrapeStats::new();
+        stats.add_key_requests(b"prometheus", 1);
+        stats.add_key_requests(b"grafana-agent", 2);
+        stats.add_key_requests(b"prometheus", 3);
+        stats.add_key_requests(b"not a name", 5);
+        stats.add_key_requests(b"", 5);
+
+        assert_eq!(
+            stats.snapshot(),
+            ScrapeStatsSnapshot::empty()
+                .with_key_requests(b"grafana-agent", 2)
+                .with_key_requests(b"prometheus", 4)
+        );
+    }
+
+    #[test]
+    fn caps_key_request_series() {
+        let stats = ScrapeStats::new();
+        for i in 0..=MAX_KEY_REQUEST_SERIES {
+            stats.add_key_requests(format!("key-{:03}", i).as_bytes(), 1);
+        }
+        stats.add_key_requests(b"key-000", 1);
+
+        let snapshot = stats.snapshot();
+        let key_requests = Vec::from_iter(snapshot.key_requests());
+        assert_eq!(key_requests.len(), MAX_KEY_REQUEST_SERIES);
+        assert_eq!(key_requests[0], (&b"key-000"[..], 2));
+    }
+}
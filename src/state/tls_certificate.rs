@@ -0,0 +1,375 @@
+//! PEM and DER parsing for the HTTPS certificate, private key, and OCSP response. The parent uses
+//! this to check a reloaded pair before handing it off, and the child uses it to actually load it.
+
+use crate::prelude::*;
+
+use rustls::sign::CertifiedKey;
+use rustls::SignatureScheme;
+
+pub fn parse_pem_certificates(pem: &[u8], name: &str) -> io::Result<Vec<rustls::Certificate>> {
+    let certificates = match rustls_pemfile::certs(&mut &*pem) {
+        Ok(certificates) => certificates,
+        Err(e) => return Err(error!("Failed to read {name}: {e}")),
+    };
+    if certificates.is_empty() {
+        return Err(error!("No certificates found in {name}."));
+    }
+    Ok(certificates.into_iter().map(rustls::Certificate).collect())
+}
+
+fn parse_pem_private_key(pem: &[u8]) -> io::Result<rustls::PrivateKey> {
+    let mut reader = pem;
+    loop {
+        match rustls_pemfile::read_one(&mut reader) {
+            Ok(Some(
+                rustls_pemfile::Item::RSAKey(key)
+                | rustls_pemfile::Item::PKCS8Key(key)
+                | rustls_pemfile::Item::ECKey(key),
+            )) => return Ok(rustls::PrivateKey(key)),
+            Ok(Some(_)) => {}
+            Ok(None) => return Err(error!("No private key found in private key file.")),
+            Err(e) => return Err(error!("Failed to read private key file: {e}")),
+        }
+    }
+}
+
+// Splits off the leading DER element if it has the given tag, returning its contents and whatever
+// follows it. Only definite, minimally encoded lengths and single-byte tags are accepted, as DER
+// requires, so there's only ever one way to read a given element.
+pub fn split_der(data: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&actual_tag, rest) = data.split_first()?;
+    // Tag numbers of 31 and up take more bytes, and nothing read here uses them.
+    if actual_tag & 0x1F == 0x1F {
+        return None;
+    }
+
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first < 0x80 {
+        (usize::from(first), rest)
+    } else {
+        let count = usize::from(first & 0x7F);
+        if count == 0 || count > std::mem::size_of::<u32>() || count > rest.len() {
+            return None;
+        }
+        let (len_bytes, rest) = rest.split_at(count);
+        // Leading zeroes and lengths that fit in the short form are both non-minimal.
+        if len_bytes.first() == Some(&0) {
+            return None;
+        }
+        let len = len_bytes
+            .iter()
+            .fold(0_usize, |len, &b| len.wrapping_shl(8) | usize::from(b));
+        if len < 0x80 {
+            return None;
+        }
+        (len, rest)
+    };
+
+    if actual_tag != tag || len > rest.len() {
+        return None;
+    }
+
+    Some(rest.split_at(len))
+}
+
+pub fn split_any_der(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let (contents, rest) = split_der(data, tag)?;
+    Some((tag, contents, rest))
+}
+
+pub const DER_SEQUENCE: u8 = 0x30;
+pub const DER_SET: u8 = 0x31;
+pub const DER_OID: u8 = 0x06;
+pub const DER_BIT_STRING: u8 = 0x03;
+pub const DER_ENUMERATED: u8 = 0x0A;
+pub const DER_EXPLICIT_0: u8 = 0xA0;
+
+/// Returns the fields of a DER-encoded X.509 certificate's `tbsCertificate` starting with the
+/// subject, skipping the version, serial number, signature algorithm, issuer, and validity.
+pub fn x509_fields_from_subject(der: &[u8]) -> Option<&[u8]> {
+    let (certificate, _) = split_der(der, DER_SEQUENCE)?;
+    let (tbs_certificate, _) = split_der(certificate, DER_SEQUENCE)?;
+
+    // The version is optional, and only present if it's not v1.
+    let (tag, _, mut rest) = split_any_der(tbs_certificate)?;
+    if tag == DER_EXPLICIT_0 {
+        (_, _, rest) = split_any_der(rest)?;
+    }
+
+    // Then the signature algorithm, the issuer, and the validity.
+    for _ in 0..3 {
+        (_, _, rest) = split_any_der(rest)?;
+    }
+
+    Some(rest)
+}
+
+// For every supported key type, this is exactly what `ring` expects as the public key.
+fn subject_public_key_from_der(der: &[u8]) -> Option<&[u8]> {
+    let (_, _, rest) = split_any_der(x509_fields_from_subject(der)?)?;
+    let (public_key_info, _) = split_der(rest, DER_SEQUENCE)?;
+    let (_, _, rest) = split_any_der(public_key_info)?;
+    let (public_key, _) = split_der(rest, DER_BIT_STRING)?;
+    // Keys are always a whole number of bytes.
+    match public_key.split_first()? {
+        (0, public_key) => Some(public_key),
+        _ => None,
+    }
+}
+
+// Only what `rustls` can sign with, and in order of preference.
+static CHECK_SCHEMES: &[SignatureScheme] = &[
+    SignatureScheme::ED25519,
+    SignatureScheme::ECDSA_NISTP384_SHA384,
+    SignatureScheme::ECDSA_NISTP256_SHA256,
+    SignatureScheme::RSA_PSS_SHA256,
+    SignatureScheme::RSA_PKCS1_SHA256,
+];
+
+fn verification_algorithm(
+    scheme: SignatureScheme,
+) -> Option<&'static dyn ring::signature::VerificationAlgorithm> {
+    match scheme {
+        SignatureScheme::ED25519 => Some(&ring::signature::ED25519),
+        SignatureScheme::ECDSA_NISTP384_SHA384 => Some(&ring::signature::ECDSA_P384_SHA384_ASN1),
+        SignatureScheme::ECDSA_NISTP256_SHA256 => Some(&ring::signature::ECDSA_P256_SHA256_ASN1),
+        SignatureScheme::RSA_PSS_SHA256 => Some(&ring::signature::RSA_PSS_2048_8192_SHA256),
+        SignatureScheme::RSA_PKCS1_SHA256 => Some(&ring::signature::RSA_PKCS1_2048_8192_SHA256),
+        _ => None,
+    }
+}
+
+// `rustls` happily loads a private key that doesn't go with the certificate, and it only shows up
+// once clients start failing their handshakes. So instead, sign something with the key and check
+// it against the certificate's public key.
+fn key_matches_certificate(key: &CertifiedKey) -> bool {
+    const MESSAGE: &[u8] = b"journald-exporter certificate check";
+
+    let Ok(certificate) = key.end_entity_cert() else {
+        return false;
+    };
+    let Some(public_key) = subject_public_key_from_der(&certificate.0) else {
+        return false;
+    };
+    let Some(signer) = key.key.choose_scheme(CHECK_SCHEMES) else {
+        return false;
+    };
+    let Some(algorithm) = verification_algorithm(signer.scheme()) else {
+        return false;
+    };
+    let Ok(signature) = signer.sign(MESSAGE) else {
+        return false;
+    };
+
+    ring::signature::UnparsedPublicKey::new(algorithm, public_key)
+        .verify(MESSAGE, &signature)
+        .is_ok()
+}
+
+// Only checks that it's a successful response. Checking that it's for this certificate would need
+// far more of OCSP than is worth parsing here, so it's on whatever renews them to keep them in sync.
+fn check_ocsp_response(der: &[u8]) -> io::Result<()> {
+    let status = split_der(der, DER_SEQUENCE)
+        .filter(|(_, rest)| rest.is_empty())
+        .and_then(|(response, _)| split_der(response, DER_ENUMERATED));
+
+    match status {
+        None => Err(error!(ErrorKind::InvalidData, "OCSP response is invalid.")),
+        // `successful`
+        Some(([0], _)) => Ok(()),
+        Some(_) => Err(error!(
+            ErrorKind::InvalidData,
+            "OCSP response is not a successful response."
+        )),
+    }
+}
+
+/// Loads a PEM-encoded certificate chain and private key, checking that the key actually belongs
+/// to the chain's first certificate. The OCSP response, if any, is stapled to it as-is.
+pub fn parse_certified_key(
+    certificate: &[u8],
+    private_key: &[u8],
+    ocsp_response: Option<&[u8]>,
+) -> io::Result<CertifiedKey> {
+    let certificates = parse_pem_certificates(certificate, "certificate file")?;
+    let private_key = parse_pem_private_key(private_key)?;
+
+    if let Some(ocsp_response) = ocsp_response {
+        check_ocsp_response(ocsp_response)?;
+    }
+
+    let signing_key = match rustls::sign::any_supported_type(&private_key) {
+        Ok(signing_key) => signing_key,
+        Err(_) => return Err(error!("Private key is invalid or of an unsupported type.")),
+    };
+
+    let mut key = CertifiedKey::new(certificates, signing_key);
+    key.ocsp = ocsp_response.map(Vec::from);
+
+    if !key_matches_certificate(&key) {
+        return Err(error!(
+            ErrorKind::InvalidData,
+            "Private key does not match the certificate."
+        ));
+    }
+
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_matching_certificate_and_key() {
+        let key = parse_certified_key(TEST_CERTIFICATE, TEST_PRIVATE_KEY, None).unwrap();
+        assert_eq!(key.cert.len(), 1);
+    }
+
+    #[test]
+    fn rejects_mismatched_certificate_and_key() {
+        let error = parse_certified_key(TEST_CERTIFICATE, OTHER_PRIVATE_KEY, None)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Private key does not match the certificate."
+        );
+    }
+
+    #[test]
+    fn rejects_missing_certificate() {
+        let error = parse_certified_key(b"nope", TEST_PRIVATE_KEY, None)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "No certificates found in certificate file."
+        );
+    }
+
+    #[test]
+    fn rejects_missing_private_key() {
+        let error = parse_certified_key(TEST_CERTIFICATE, TEST_CERTIFICATE, None)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "No private key found in private key file."
+        );
+    }
+
+    #[test]
+    fn staples_successful_ocsp_response() {
+        // A successful response, with its response bytes cut down to an empty sequence.
+        let ocsp_response = b"\x30\x07\x0A\x01\x00\xA0\x02\x30\x00";
+        let key =
+            parse_certified_key(TEST_CERTIFICATE, TEST_PRIVATE_KEY, Some(ocsp_response)).unwrap();
+        assert_eq!(key.ocsp.as_deref(), Some(&ocsp_response[..]));
+    }
+
+    #[test]
+    fn rejects_unsuccessful_ocsp_response() {
+        // `unauthorized`
+        let error = parse_certified_key(
+            TEST_CERTIFICATE,
+            TEST_PRIVATE_KEY,
+            Some(b"\x30\x03\x0A\x01\x06"),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "OCSP response is not a successful response."
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_ocsp_response() {
+        for ocsp_response in [&b""[..], b"\x30\x03\x0A\x01", b"\x30\x03\x0A\x01\x00\x00"] {
+            let error =
+                parse_certified_key(TEST_CERTIFICATE, TEST_PRIVATE_KEY, Some(ocsp_response))
+                    .err()
+                    .unwrap();
+            assert_eq!(error.to_string(), "OCSP response is invalid.");
+        }
+    }
+
+    #[test]
+    fn splits_short_and_long_form_lengths() {
+        assert_eq!(
+            split_der(b"\x30\x02ab\x05\x00", DER_SEQUENCE),
+            Some((&b"ab"[..], &b"\x05\x00"[..]))
+        );
+        assert_eq!(
+            split_der(b"\x30\x00", DER_SEQUENCE),
+            Some((&b""[..], &b""[..]))
+        );
+
+        let mut data = vec![DER_SEQUENCE, 0x81, 0x80];
+        data.extend_from_slice(&[b'a'; 0x81]);
+        assert_eq!(
+            split_der(&data, DER_SEQUENCE),
+            Some((&[b'a'; 0x80][..], &b"a"[..]))
+        );
+
+        let mut data = vec![DER_SEQUENCE, 0x82, 0x01, 0x00];
+        data.extend_from_slice(&[b'a'; 0x100]);
+        assert_eq!(
+            split_der(&data, DER_SEQUENCE),
+            Some((&[b'a'; 0x100][..], &b""[..]))
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_tag() {
+        assert_eq!(split_der(b"\x31\x00", DER_SEQUENCE), None);
+        assert_eq!(split_der(b"\x30\x00", DER_SET), None);
+    }
+
+    #[test]
+    fn rejects_truncated_lengths() {
+        assert_eq!(split_der(b"", DER_SEQUENCE), None);
+        assert_eq!(split_der(b"\x30", DER_SEQUENCE), None);
+        // Long form, but missing some or all of its length bytes.
+        assert_eq!(split_der(b"\x30\x81", DER_SEQUENCE), None);
+        assert_eq!(split_der(b"\x30\x82\x01", DER_SEQUENCE), None);
+        // Lengths running past the end of the data.
+        assert_eq!(split_der(b"\x30\x03ab", DER_SEQUENCE), None);
+        assert_eq!(split_der(b"\x30\x81\x80ab", DER_SEQUENCE), None);
+        assert_eq!(split_der(b"\x30\x84\xFF\xFF\xFF\xFFab", DER_SEQUENCE), None);
+    }
+
+    #[test]
+    fn rejects_non_der_lengths() {
+        // Indefinite length, which is BER-only.
+        assert_eq!(split_der(b"\x30\x80ab\x00\x00", DER_SEQUENCE), None);
+        // Long form where the short form would do.
+        assert_eq!(split_der(b"\x30\x81\x02ab", DER_SEQUENCE), None);
+        // Leading zero length bytes.
+        let mut data = vec![DER_SEQUENCE, 0x82, 0x00, 0x80];
+        data.extend_from_slice(&[b'a'; 0x80]);
+        assert_eq!(split_der(&data, DER_SEQUENCE), None);
+        // More length bytes than could ever fit in a certificate.
+        assert_eq!(
+            split_der(b"\x30\x85\x01\x00\x00\x00\x00", DER_SEQUENCE),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_multi_byte_tags() {
+        assert_eq!(split_der(b"\x1F\x1F\x00", 0x1F), None);
+        assert_eq!(split_any_der(b"\xBF\x81\x00\x00"), None);
+    }
+
+    #[test]
+    fn finds_subject_public_key() {
+        let certificates = parse_pem_certificates(TEST_CERTIFICATE, "test").unwrap();
+        let public_key = subject_public_key_from_der(&certificates[0].0).unwrap();
+        // An uncompressed P-256 point.
+        assert_eq!(public_key.len(), 65);
+        assert_eq!(public_key[0], 0x04);
+    }
+}
@@ -0,0 +1,288 @@
+//! The TLS versions and cipher suites HTTPS can be limited to. These are exactly what `rustls`
+//! supports, as nothing else could be negotiated anyways.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TlsVersion {
+    Tls12,
+    Tls13,
+}
+
+impl TlsVersion {
+    pub const ALL: [TlsVersion; 2] = [TlsVersion::Tls12, TlsVersion::Tls13];
+
+    pub fn from_label(label: &[u8]) -> Option<Self> {
+        match label {
+            b"1.2" => Some(TlsVersion::Tls12),
+            b"1.3" => Some(TlsVersion::Tls13),
+            _ => None,
+        }
+    }
+
+    pub fn as_label(self) -> &'static str {
+        match self {
+            TlsVersion::Tls12 => "1.2",
+            TlsVersion::Tls13 => "1.3",
+        }
+    }
+}
+
+/// The order here is `rustls`'s order of preference, and is also the order they're rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsCipherSuite {
+    Tls13Aes256GcmSha384,
+    Tls13Aes128GcmSha256,
+    Tls13Chacha20Poly1305Sha256,
+    EcdheEcdsaAes256GcmSha384,
+    EcdheEcdsaAes128GcmSha256,
+    EcdheEcdsaChacha20Poly1305Sha256,
+    EcdheRsaAes256GcmSha384,
+    EcdheRsaAes128GcmSha256,
+    EcdheRsaChacha20Poly1305Sha256,
+}
+
+impl TlsCipherSuite {
+    pub const ALL: [TlsCipherSuite; 9] = [
+        TlsCipherSuite::Tls13Aes256GcmSha384,
+        TlsCipherSuite::Tls13Aes128GcmSha256,
+        TlsCipherSuite::Tls13Chacha20Poly1305Sha256,
+        TlsCipherSuite::EcdheEcdsaAes256GcmSha384,
+        TlsCipherSuite::EcdheEcdsaAes128GcmSha256,
+        TlsCipherSuite::EcdheEcdsaChacha20Poly1305Sha256,
+        TlsCipherSuite::EcdheRsaAes256GcmSha384,
+        TlsCipherSuite::EcdheRsaAes128GcmSha256,
+        TlsCipherSuite::EcdheRsaChacha20Poly1305Sha256,
+    ];
+
+    pub fn index(self) -> usize {
+        match self {
+            TlsCipherSuite::Tls13Aes256GcmSha384 => 0,
+            TlsCipherSuite::Tls13Aes128GcmSha256 => 1,
+            TlsCipherSuite::Tls13Chacha20Poly1305Sha256 => 2,
+            TlsCipherSuite::EcdheEcdsaAes256GcmSha384 => 3,
+            TlsCipherSuite::EcdheEcdsaAes128GcmSha256 => 4,
+            TlsCipherSuite::EcdheEcdsaChacha20Poly1305Sha256 => 5,
+            TlsCipherSuite::EcdheRsaAes256GcmSha384 => 6,
+            TlsCipherSuite::EcdheRsaAes128GcmSha256 => 7,
+            TlsCipherSuite::EcdheRsaChacha20Poly1305Sha256 => 8,
+        }
+    }
+
+    /// The IANA name, which is also what OpenSSL calls the TLS 1.3 suites.
+    pub fn as_name(self) -> &'static str {
+        match self {
+            TlsCipherSuite::Tls13Aes256GcmSha384 => "TLS_AES_256_GCM_SHA384",
+            TlsCipherSuite::Tls13Aes128GcmSha256 => "TLS_AES_128_GCM_SHA256",
+            TlsCipherSuite::Tls13Chacha20Poly1305Sha256 => "TLS_CHACHA20_POLY1305_SHA256",
+            TlsCipherSuite::EcdheEcdsaAes256GcmSha384 => "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384",
+            TlsCipherSuite::EcdheEcdsaAes128GcmSha256 => "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256",
+            TlsCipherSuite::EcdheEcdsaChacha20Poly1305Sha256 => {
+                "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256"
+            }
+            TlsCipherSuite::EcdheRsaAes256GcmSha384 => "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384",
+            TlsCipherSuite::EcdheRsaAes128GcmSha256 => "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+            TlsCipherSuite::EcdheRsaChacha20Poly1305Sha256 => {
+                "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256"
+            }
+        }
+    }
+
+    /// The IANA code point, as sent on the wire.
+    pub fn code(self) -> u16 {
+        match self {
+            TlsCipherSuite::Tls13Aes256GcmSha384 => 0x1302,
+            TlsCipherSuite::Tls13Aes128GcmSha256 => 0x1301,
+            TlsCipherSuite::Tls13Chacha20Poly1305Sha256 => 0x1303,
+            TlsCipherSuite::EcdheEcdsaAes256GcmSha384 => 0xC02C,
+            TlsCipherSuite::EcdheEcdsaAes128GcmSha256 => 0xC02B,
+            TlsCipherSuite::EcdheEcdsaChacha20Poly1305Sha256 => 0xCCA9,
+            TlsCipherSuite::EcdheRsaAes256GcmSha384 => 0xC030,
+            TlsCipherSuite::EcdheRsaAes128GcmSha256 => 0xC02F,
+            TlsCipherSuite::EcdheRsaChacha20Poly1305Sha256 => 0xCCA8,
+        }
+    }
+
+    pub fn from_code(code: u16) -> Option<Self> {
+        TlsCipherSuite::ALL
+            .into_iter()
+            .find(|suite| suite.code() == code)
+    }
+
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        TlsCipherSuite::ALL
+            .into_iter()
+            .find(|suite| suite.as_name().as_bytes() == name)
+    }
+
+    /// Each suite is only ever negotiated with one version.
+    pub fn version(self) -> TlsVersion {
+        match self {
+            TlsCipherSuite::Tls13Aes256GcmSha384
+            | TlsCipherSuite::Tls13Aes128GcmSha256
+            | TlsCipherSuite::Tls13Chacha20Poly1305Sha256 => TlsVersion::Tls13,
+            _ => TlsVersion::Tls12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsPolicy {
+    pub min_version: TlsVersion,
+    pub max_version: TlsVersion,
+    // Always in the order of `TlsCipherSuite::ALL`, without duplicates.
+    pub cipher_suites: Vec<TlsCipherSuite>,
+    // Stateless session tickets. Sessions can still be resumed from the server's own cache
+    // without them.
+    pub session_tickets: bool,
+}
+
+impl Default for TlsPolicy {
+    fn default() -> Self {
+        Self {
+            min_version: TlsVersion::Tls12,
+            max_version: TlsVersion::Tls13,
+            cipher_suites: TlsCipherSuite::ALL.to_vec(),
+            session_tickets: false,
+        }
+    }
+}
+
+impl TlsPolicy {
+    /// Parses a comma-separated list of cipher suite names. Names can be in any order and
+    /// repeated, but there has to be at least one, and all of them must be known.
+    pub fn parse_cipher_suites(list: &[u8]) -> Option<Vec<TlsCipherSuite>> {
+        let mut enabled = [false; TlsCipherSuite::ALL.len()];
+
+        for name in list.split(|&b| b == b',') {
+            enabled[TlsCipherSuite::from_name(name)?.index()] = true;
+        }
+
+        Some(
+            TlsCipherSuite::ALL
+                .into_iter()
+                .filter(|suite| enabled[suite.index()])
+                .collect(),
+        )
+    }
+
+    /// The inverse of `parse_cipher_suites`.
+    pub fn cipher_suite_list(&self) -> String {
+        let mut result = String::new();
+        for suite in &self.cipher_suites {
+            if !result.is_empty() {
+                result.push(',');
+            }
+            result.push_str(suite.as_name());
+        }
+        result
+    }
+
+    pub fn versions(&self) -> impl Iterator<Item = TlsVersion> + '_ {
+        TlsVersion::ALL
+            .into_iter()
+            .filter(|version| (self.min_version..=self.max_version).contains(version))
+    }
+
+    /// Only the suites that can be negotiated with one of the allowed versions.
+    pub fn usable_cipher_suites(&self) -> impl Iterator<Item = TlsCipherSuite> + '_ {
+        self.cipher_suites
+            .iter()
+            .copied()
+            .filter(|suite| (self.min_version..=self.max_version).contains(&suite.version()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cipher_suite_indices_match_all_order() {
+        for (i, suite) in TlsCipherSuite::ALL.iter().enumerate() {
+            assert_eq!(suite.index(), i);
+        }
+    }
+
+    #[test]
+    fn cipher_suites_match_rustls() {
+        let expected = Vec::from_iter(
+            rustls::ALL_CIPHER_SUITES
+                .iter()
+                .map(|suite| suite.suite().get_u16()),
+        );
+        let actual = Vec::from_iter(TlsCipherSuite::ALL.map(TlsCipherSuite::code));
+        assert_eq!(actual, expected);
+
+        for suite in rustls::ALL_CIPHER_SUITES {
+            let actual = TlsCipherSuite::from_code(suite.suite().get_u16()).unwrap();
+            let expected = match suite.version().version {
+                rustls::ProtocolVersion::TLSv1_2 => TlsVersion::Tls12,
+                _ => TlsVersion::Tls13,
+            };
+            assert_eq!(actual.version(), expected);
+        }
+    }
+
+    #[test]
+    fn cipher_suite_names_round_trip() {
+        for suite in TlsCipherSuite::ALL {
+            assert_eq!(
+                TlsCipherSuite::from_name(suite.as_name().as_bytes()),
+                Some(suite)
+            );
+        }
+        assert_eq!(TlsCipherSuite::from_name(b"TLS13_AES_128_GCM_SHA256"), None);
+    }
+
+    #[test]
+    fn parses_cipher_suite_lists_in_preference_order() {
+        assert_eq!(
+            TlsPolicy::parse_cipher_suites(
+                b"TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,TLS_AES_128_GCM_SHA256,TLS_AES_128_GCM_SHA256"
+            ),
+            Some(vec![
+                TlsCipherSuite::Tls13Aes128GcmSha256,
+                TlsCipherSuite::EcdheRsaAes128GcmSha256,
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_cipher_suite_lists() {
+        assert_eq!(TlsPolicy::parse_cipher_suites(b""), None);
+        assert_eq!(
+            TlsPolicy::parse_cipher_suites(b"TLS_AES_128_GCM_SHA256,"),
+            None
+        );
+        assert_eq!(
+            TlsPolicy::parse_cipher_suites(b"TLS_RSA_WITH_AES_128_CBC_SHA"),
+            None
+        );
+    }
+
+    #[test]
+    fn cipher_suite_list_round_trips() {
+        let policy = TlsPolicy::default();
+        assert_eq!(
+            TlsPolicy::parse_cipher_suites(policy.cipher_suite_list().as_bytes()),
+            Some(policy.cipher_suites)
+        );
+    }
+
+    #[test]
+    fn filters_usable_cipher_suites_by_version() {
+        let policy = TlsPolicy {
+            min_version: TlsVersion::Tls13,
+            max_version: TlsVersion::Tls13,
+            cipher_suites: vec![
+                TlsCipherSuite::Tls13Aes128GcmSha256,
+                TlsCipherSuite::EcdheRsaAes128GcmSha256,
+            ],
+            session_tickets: false,
+        };
+        assert_eq!(Vec::from_iter(policy.versions()), [TlsVersion::Tls13]);
+        assert_eq!(
+            Vec::from_iter(policy.usable_cipher_suites()),
+            [TlsCipherSuite::Tls13Aes128GcmSha256]
+        );
+    }
+}
@@ -4,16 +4,20 @@ mod assert;
 mod errors;
 mod get_user_group_table;
 mod misc;
+mod protobuf;
 mod spy;
 mod thread_checkpoint;
 mod time;
+mod tls;
 mod with_attempts;
 
 pub use assert::*;
 pub use errors::*;
 pub use get_user_group_table::*;
 pub use misc::*;
+pub use protobuf::*;
 pub use spy::*;
 pub use thread_checkpoint::*;
 pub use time::*;
+pub use tls::*;
 pub use with_attempts::*;
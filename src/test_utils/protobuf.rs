@@ -0,0 +1,84 @@
+// Just enough of a protobuf decoder to check the push payloads in tests.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProtobufValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+}
+
+impl<'a> ProtobufValue<'a> {
+    pub fn varint(self) -> u64 {
+        match self {
+            ProtobufValue::Varint(value) => value,
+            value => panic!("Expected varint, found {value:?}"),
+        }
+    }
+
+    pub fn fixed64(self) -> u64 {
+        match self {
+            ProtobufValue::Fixed64(value) => value,
+            value => panic!("Expected fixed64, found {value:?}"),
+        }
+    }
+
+    pub fn bytes(self) -> &'a [u8] {
+        match self {
+            ProtobufValue::Bytes(value) => value,
+            value => panic!("Expected bytes, found {value:?}"),
+        }
+    }
+
+    pub fn str(self) -> &'a str {
+        std::str::from_utf8(self.bytes()).unwrap()
+    }
+}
+
+fn read_varint(data: &mut &[u8]) -> u64 {
+    let mut result = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[0];
+        *data = &data[1..];
+        result |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return result;
+        }
+        shift += 7;
+    }
+}
+
+pub fn read_protobuf_fields(mut data: &[u8]) -> Vec<(u64, ProtobufValue<'_>)> {
+    let mut result = Vec::new();
+    while !data.is_empty() {
+        let tag = read_varint(&mut data);
+        let value = match tag & 7 {
+            0 => ProtobufValue::Varint(read_varint(&mut data)),
+            1 => {
+                let (value, rest) = data.split_at(8);
+                data = rest;
+                ProtobufValue::Fixed64(u64::from_le_bytes(value.try_into().unwrap()))
+            }
+            2 => {
+                let len = usize::try_from(read_varint(&mut data)).unwrap();
+                let (value, rest) = data.split_at(len);
+                data = rest;
+                ProtobufValue::Bytes(value)
+            }
+            wire_type => panic!("Unexpected wire type {wire_type}"),
+        };
+        result.push((tag >> 3, value));
+    }
+    result
+}
+
+pub fn read_length_delimited_messages(mut data: &[u8]) -> Vec<&[u8]> {
+    let mut result = Vec::new();
+    while !data.is_empty() {
+        let len = usize::try_from(read_varint(&mut data)).unwrap();
+        let (message, rest) = data.split_at(len);
+        data = rest;
+        result.push(message);
+    }
+    result
+}